@@ -0,0 +1,92 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use nokhwa::{yuyv422_to_rgb888_with, yuyv444_to_rgb888_with, ColorSpace, YuvLut};
+
+// a 1280x720 YUYV frame with every Y, U and V value in it
+fn yuyv_frame() -> Vec<u8> {
+    (0..1280 * 720 * 2)
+        .map(|idx: usize| (idx * 7 % 256) as u8)
+        .collect()
+}
+
+// the per-pixel arithmetic the converters used before the lookup tables
+fn yuyv422_to_rgb888_arithmetic(data: &[u8], color_space: ColorSpace) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(data.len() / 2 * 3);
+    for yuyv in data.chunks_exact(4) {
+        let u = i32::from(yuyv[1]);
+        let v = i32::from(yuyv[3]);
+        rgb.extend_from_slice(&yuyv444_to_rgb888_with(
+            i32::from(yuyv[0]),
+            u,
+            v,
+            color_space,
+        ));
+        rgb.extend_from_slice(&yuyv444_to_rgb888_with(
+            i32::from(yuyv[2]),
+            u,
+            v,
+            color_space,
+        ));
+    }
+    rgb
+}
+
+// the same loop with the lookup tables, on one thread like the arithmetic one (`yuyv422_to_rgb888_with()` splits frames over threads with `rayon`)
+fn yuyv422_to_rgb888_lut(data: &[u8], lut: &YuvLut) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(data.len() / 2 * 3);
+    for yuyv in data.chunks_exact(4) {
+        rgb.extend_from_slice(&lut.yuv_to_rgb888(yuyv[0], yuyv[1], yuyv[3]));
+        rgb.extend_from_slice(&lut.yuv_to_rgb888(yuyv[2], yuyv[1], yuyv[3]));
+    }
+    rgb
+}
+
+fn yuv_pixel(c: &mut Criterion) {
+    let lut = YuvLut::new(ColorSpace::default());
+    let mut group = c.benchmark_group("yuv444 pixel");
+    group.bench_function("arithmetic", |b| {
+        b.iter(|| {
+            yuyv444_to_rgb888_with(
+                black_box(180),
+                black_box(90),
+                black_box(200),
+                ColorSpace::default(),
+            )
+        })
+    });
+    group.bench_function("lut", |b| {
+        b.iter(|| lut.yuv_to_rgb888(black_box(180), black_box(90), black_box(200)))
+    });
+    group.finish();
+}
+
+fn yuyv_frame_720p(c: &mut Criterion) {
+    let frame = yuyv_frame();
+    let lut = YuvLut::new(ColorSpace::default());
+    let mut group = c.benchmark_group("yuyv422 720p");
+    group.throughput(Throughput::Bytes(frame.len() as u64));
+    group.bench_function("arithmetic", |b| {
+        b.iter(|| yuyv422_to_rgb888_arithmetic(black_box(&frame), ColorSpace::default()))
+    });
+    group.bench_function("lut", |b| {
+        b.iter(|| yuyv422_to_rgb888_lut(black_box(&frame), &lut))
+    });
+    group.bench_function("yuyv422_to_rgb888_with", |b| {
+        b.iter(|| yuyv422_to_rgb888_with(black_box(&frame), ColorSpace::default()))
+    });
+    group.finish();
+}
+
+fn lut_new(c: &mut Criterion) {
+    c.bench_function("YuvLut::new", |b| {
+        b.iter(|| YuvLut::new(black_box(ColorSpace::Bt709Full)))
+    });
+}
+
+criterion_group!(benches, yuv_pixel, yuyv_frame_720p, lut_new);
+criterion_main!(benches);
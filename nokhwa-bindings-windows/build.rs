@@ -5,6 +5,8 @@ fn main() {
         Windows::Win32::System::Com::{CoTaskMemFree, CoInitializeEx, COINIT, CoUninitialize},
         Windows::Win32::Foundation::{S_OK},
         Windows::Win32::Graphics::DirectShow::*,
+        Windows::Win32::Media::KernelStreaming::IKsControl,
+        Windows::Devices::Enumeration::{DeviceInformation, EnclosureLocation, Panel},
     )
 }
 
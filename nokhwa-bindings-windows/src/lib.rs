@@ -41,6 +41,14 @@ pub enum BindingError {
     DeviceOpenFailError(usize, String),
     #[error("Failed to read frame: {0}")]
     ReadFrameError(String),
+    #[error("Device {0} was disconnected: {1}")]
+    DeviceLostError(usize, String),
+    #[error("Device {0} is in use: {1}")]
+    DeviceBusyError(usize, String),
+    #[error("Access to device {0} was denied: {1}")]
+    AccessDeniedError(usize, String),
+    #[error("Media type {0} is not supported: {1}")]
+    UnsupportedMediaTypeError(String, String),
     #[error("Not Implemented!")]
     NotImplementedError,
 }
@@ -201,6 +209,40 @@ impl<'a> Ord for MediaFoundationDeviceDescriptor<'a> {
     }
 }
 
+/// The side of the enclosure (e.g. laptop lid, tablet) a device is mounted on, see [`EnclosureLocation.Panel`](https://docs.microsoft.com/en-us/uwp/api/windows.devices.enumeration.panel).
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MFDevicePanel {
+    Unknown,
+    Front,
+    Back,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// The kind of a camera profile, see [`KSCAMERAPROFILE_*`](https://docs.microsoft.com/en-us/windows-hardware/drivers/stream/camera-profiles). `Unknown` is a profile ID this crate does not know.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MFCameraProfileKind {
+    Legacy,
+    VideoRecording,
+    HighQualityPhoto,
+    BalancedVideoAndPhoto,
+    VideoConferencing,
+    PhotoSequence,
+    HighFrameRate,
+    VariablePhotoSequence,
+    VideoHDR8,
+    Unknown,
+}
+
+/// A camera profile (a set of media types the device can stream at the same time) the device advertises. `index` tells apart profiles of the same kind.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct MFCameraProfile {
+    pub kind: MFCameraProfileKind,
+    pub index: u32,
+}
+
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub enum MediaFoundationControls {
     Brightness,
@@ -220,6 +262,7 @@ pub enum MediaFoundationControls {
     Exposure,
     Iris,
     Focus,
+    PowerLineFrequency,
 }
 
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
@@ -328,10 +371,12 @@ windows::include_bindings!();
 #[cfg(all(windows, not(feature = "docs-only")))]
 pub mod wmf {
     use crate::{
-        BindingError, MFCameraFormat, MFControl, MFFrameFormat, MFResolution,
-        MediaFoundationControls, MediaFoundationDeviceDescriptor,
+        BindingError, MFCameraFormat, MFCameraProfile, MFCameraProfileKind, MFControl,
+        MFDevicePanel, MFFrameFormat, MFResolution, MediaFoundationControls,
+        MediaFoundationDeviceDescriptor,
+        Windows::Devices::Enumeration::{DeviceInformation, Panel},
         Windows::Win32::{
-            Foundation::PWSTR,
+            Foundation::{BOOL, PWSTR},
             Graphics::DirectShow::{
                 CameraControl_Exposure, CameraControl_Focus, CameraControl_Iris, CameraControl_Pan,
                 CameraControl_Roll, CameraControl_Tilt, CameraControl_Zoom, IAMCameraControl,
@@ -340,22 +385,27 @@ pub mod wmf {
                 VideoProcAmp_Gamma, VideoProcAmp_Hue, VideoProcAmp_Saturation,
                 VideoProcAmp_Sharpness, VideoProcAmp_WhiteBalance,
             },
+            Media::KernelStreaming::IKsControl,
             Media::MediaFoundation::{
-                IMFActivate, IMFAttributes, IMFMediaSource, IMFSample, IMFSourceReader,
-                MFCreateAttributes, MFCreateDeviceSource, MFCreateMediaType,
+                IMFActivate, IMFAttributes, IMFMediaSource, IMFMediaSourceEx, IMFMediaType,
+                IMFSample, IMFSensorProfile, IMFSensorProfileCollection, IMFSourceReader,
+                IMFStreamDescriptor, MFCreateAttributes, MFCreateDeviceSource, MFCreateMediaType,
                 MFCreateSourceReaderFromMediaSource, MFEnumDeviceSources, MFMediaType_Video,
                 MFShutdown, MFStartup, MFSTARTUP_NOSOCKET, MF_API_VERSION,
-                MF_DEVSOURCE_ATTRIBUTE_FRIENDLY_NAME, MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
+                MF_DEVICESTREAM_STREAM_CATEGORY, MF_DEVSOURCE_ATTRIBUTE_FRIENDLY_NAME,
+                MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
+                MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_CATEGORY,
                 MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
                 MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK, MF_MT_FRAME_RATE,
                 MF_MT_FRAME_RATE_RANGE_MAX, MF_MT_FRAME_RATE_RANGE_MIN, MF_MT_FRAME_SIZE,
                 MF_MT_MAJOR_TYPE, MF_MT_SUBTYPE, MF_SOURCE_READER_FIRST_VIDEO_STREAM,
+                SENSORPROFILEID,
             },
             System::Com::{CoInitializeEx, CoUninitialize, COINIT},
         },
     };
     use std::{borrow::Cow, cell::Cell, ffi::c_void, mem::MaybeUninit, slice::from_raw_parts};
-    use windows::{Guid, Interface};
+    use windows::{Abi, Guid, Interface};
 
     static mut INITIALIZED: bool = false;
 
@@ -377,6 +427,190 @@ pub mod wmf {
         [0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71],
     );
 
+    // See: https://docs.microsoft.com/en-us/windows/win32/medfound/image-subtype-guids
+    const MF_IMAGE_FORMAT_JPEG: Guid = Guid::from_values(
+        0x19E4_A5AA,
+        0x5662,
+        0x4FC5,
+        [0xA0, 0xC0, 0x17, 0x58, 0x02, 0x8E, 0x10, 0x57],
+    );
+    // See: ksmedia.h
+    const PINNAME_IMAGE: Guid = Guid::from_values(
+        0x38A0_CD98,
+        0xD49B,
+        0x4CE8,
+        [0xB4, 0x8A, 0x34, 0x46, 0x67, 0xA1, 0x78, 0x30],
+    );
+    const PINNAME_VIDEO_STILL: Guid = Guid::from_values(
+        0xFB6C_428A,
+        0x0353,
+        0x11D1,
+        [0x90, 0x5F, 0x00, 0x00, 0xC0, 0xCC, 0x16, 0xBA],
+    );
+
+    const PHOTO_READ_ATTEMPTS: usize = 10;
+
+    // See: ksmedia.h
+    const KSCATEGORY_SENSOR_CAMERA: Guid = Guid::from_values(
+        0x24E5_52D7,
+        0x6523,
+        0x47F7,
+        [0xA6, 0x47, 0xD3, 0x46, 0x5B, 0xF1, 0xF5, 0xCA],
+    );
+    const KSPROPERTYSETID_EXTENDED_CAMERA_CONTROL: Guid = Guid::from_values(
+        0x1CB7_9112,
+        0xC0D2,
+        0x4213,
+        [0x9C, 0xA6, 0xCD, 0x4F, 0xDB, 0x92, 0x79, 0x72],
+    );
+    const KSPROPERTY_CAMERACONTROL_EXTENDED_PROFILE: u32 = 34;
+    const KSPROPERTY_TYPE_SET: u32 = 0x0000_0002;
+    const KSCAMERA_EXTENDEDPROP_VERSION: u32 = 1;
+    const KSCAMERA_EXTENDEDPROP_FILTERSCOPE: u32 = 0xFFFF_FFFF;
+    // See: mfidl.h
+    const MF_DEVICEMFT_SENSORPROFILE_COLLECTION: Guid = Guid::from_values(
+        0x36EB_DC44,
+        0xB12C,
+        0x441B,
+        [0x89, 0xF4, 0x08, 0xB2, 0xEE, 0x41, 0xA9, 0xC7],
+    );
+    // KSCAMERAPROFILE_*, See: ksmedia.h
+    const CAMERA_PROFILES: [(MFCameraProfileKind, Guid); 9] = [
+        (
+            MFCameraProfileKind::Legacy,
+            Guid::from_values(
+                0xB489_4D81,
+                0x62B7,
+                0x4EEC,
+                [0x87, 0x40, 0x80, 0x65, 0x8C, 0x4A, 0x9D, 0x3E],
+            ),
+        ),
+        (
+            MFCameraProfileKind::VideoRecording,
+            Guid::from_values(
+                0xA0E5_17E8,
+                0x8F8C,
+                0x4F6F,
+                [0x9A, 0x57, 0x46, 0xFC, 0x2F, 0x64, 0x7E, 0xC0],
+            ),
+        ),
+        (
+            MFCameraProfileKind::HighQualityPhoto,
+            Guid::from_values(
+                0x3244_0725,
+                0x961B,
+                0x4CA3,
+                [0xB5, 0xB2, 0x85, 0x4E, 0x71, 0x9D, 0x9E, 0x1B],
+            ),
+        ),
+        (
+            MFCameraProfileKind::BalancedVideoAndPhoto,
+            Guid::from_values(
+                0x6B52_B017,
+                0x42C7,
+                0x4A21,
+                [0xBF, 0xE3, 0x23, 0xF0, 0x09, 0x14, 0x98, 0x87],
+            ),
+        ),
+        (
+            MFCameraProfileKind::VideoConferencing,
+            Guid::from_values(
+                0xC544_4A88,
+                0xE1BF,
+                0x4597,
+                [0xB2, 0xDD, 0x9E, 0x1E, 0xAD, 0x86, 0x4B, 0xB8],
+            ),
+        ),
+        (
+            MFCameraProfileKind::PhotoSequence,
+            Guid::from_values(
+                0x0239_9D9D,
+                0x4EE8,
+                0x49BA,
+                [0xBC, 0x07, 0x5F, 0xF1, 0x56, 0x53, 0x14, 0x13],
+            ),
+        ),
+        (
+            MFCameraProfileKind::HighFrameRate,
+            Guid::from_values(
+                0x566E_6113,
+                0x8C35,
+                0x48E7,
+                [0xB8, 0x9F, 0xD2, 0x3F, 0xDC, 0x12, 0x19, 0xDC],
+            ),
+        ),
+        (
+            MFCameraProfileKind::VariablePhotoSequence,
+            Guid::from_values(
+                0x9FF2_CB56,
+                0xE75A,
+                0x49B1,
+                [0xA9, 0x28, 0x99, 0x85, 0xD5, 0x94, 0x6F, 0x87],
+            ),
+        ),
+        (
+            MFCameraProfileKind::VideoHDR8,
+            Guid::from_values(
+                0xD4F3_F4EC,
+                0xBDFF,
+                0x4314,
+                [0xB1, 0xD4, 0x00, 0x8E, 0x28, 0x1F, 0x74, 0xE7],
+            ),
+        ),
+    ];
+
+    // KSPROPERTY, KSCAMERA_EXTENDEDPROP_HEADER and KSCAMERA_EXTENDEDPROP_PROFILE, See: ks.h and ksmedia.h
+    #[repr(C)]
+    struct KsProperty {
+        set: Guid,
+        id: u32,
+        flags: u32,
+    }
+
+    #[repr(C)]
+    struct KsCameraProfileProperty {
+        version: u32,
+        pin_id: u32,
+        size: u32,
+        result: u32,
+        flags: u64,
+        capability: u64,
+        profile_id: Guid,
+        index: u32,
+        reserved: u32,
+    }
+
+    // KSPROPERTY_VIDEOPROCAMP_POWERLINE_FREQUENCY, which `VideoProcAmpProperty` does not list, but `IAMVideoProcAmp` passes through
+    const VIDEO_PROC_AMP_POWER_LINE_FREQUENCY: i32 = 13;
+
+    // See: mferror.h and winerror.h
+    // MF_E_VIDEO_RECORDING_DEVICE_LOCKED, MF_E_VIDEO_RECORDING_DEVICE_PREEMPTED (both codes in use), HRESULT_FROM_WIN32(ERROR_SHARING_VIOLATION)
+    const DEVICE_BUSY_CODES: [u32; 4] = [0xC00D_3704, 0xC00D_3705, 0xC00D_3E9F, 0x8007_0020];
+    // MF_E_VIDEO_RECORDING_DEVICE_INVALIDATED, HRESULT_FROM_WIN32(ERROR_DEVICE_NOT_CONNECTED), HRESULT_FROM_WIN32(ERROR_GEN_FAILURE)
+    const DEVICE_LOST_CODES: [u32; 3] = [0xC00D_3E9E, 0x8007_048F, 0x8007_001F];
+    // E_ACCESSDENIED, which is also what the camera privacy setting gives
+    const ACCESS_DENIED: u32 = 0x8007_0005;
+    // MF_E_INVALIDMEDIATYPE, MF_E_INVALIDTYPE
+    const INVALID_MEDIA_TYPE_CODES: [u32; 2] = [0xC00D_36B4, 0xC00D_36BD];
+
+    // device loss, a busy device and denied access get their own errors, everything else is `otherwise`
+    fn classify_error(
+        index: usize,
+        why: &windows::Error,
+        otherwise: impl FnOnce(String) -> BindingError,
+    ) -> BindingError {
+        let code = why.code().0;
+        if DEVICE_LOST_CODES.contains(&code) {
+            BindingError::DeviceLostError(index, why.to_string())
+        } else if DEVICE_BUSY_CODES.contains(&code) {
+            BindingError::DeviceBusyError(index, why.to_string())
+        } else if code == ACCESS_DENIED {
+            BindingError::AccessDeniedError(index, why.to_string())
+        } else {
+            otherwise(why.to_string())
+        }
+    }
+
     const CAM_CTRL_AUTO: i32 = 0x0001;
     const CAM_CTRL_MANUAL: i32 = 0x0002;
 
@@ -420,11 +654,260 @@ pub mod wmf {
         Ok(())
     }
 
+    /// Gets the panel the device with the symbolic link `symlink` is mounted on. This is `None` if the device has no enclosure location, which is usually the case for external (e.g. USB) cameras.
+    pub fn device_panel(symlink: &str) -> Result<Option<MFDevicePanel>, BindingError> {
+        let information =
+            match DeviceInformation::CreateFromIdAsync(symlink).and_then(|op| op.get()) {
+                Ok(info) => info,
+                Err(why) => return Err(BindingError::AttributeError(why.to_string())),
+            };
+        // a device without ACPI location info has no enclosure location
+        let panel = match information
+            .EnclosureLocation()
+            .and_then(|location| location.Panel())
+        {
+            Ok(panel) => panel,
+            Err(_) => return Ok(None),
+        };
+        let panel = match panel {
+            Panel::Front => MFDevicePanel::Front,
+            Panel::Back => MFDevicePanel::Back,
+            Panel::Top => MFDevicePanel::Top,
+            Panel::Bottom => MFDevicePanel::Bottom,
+            Panel::Left => MFDevicePanel::Left,
+            Panel::Right => MFDevicePanel::Right,
+            _ => MFDevicePanel::Unknown,
+        };
+        Ok(Some(panel))
+    }
+
+    /// Gets how many degrees clockwise the device with the symbolic link `symlink` is mounted, relative to its panel. This is `None` if the device has no enclosure location.
+    pub fn device_rotation(symlink: &str) -> Result<Option<u32>, BindingError> {
+        let information =
+            match DeviceInformation::CreateFromIdAsync(symlink).and_then(|op| op.get()) {
+                Ok(info) => info,
+                Err(why) => return Err(BindingError::AttributeError(why.to_string())),
+            };
+        match information
+            .EnclosureLocation()
+            .and_then(|location| location.RotationAngleInDegreesClockwise())
+        {
+            Ok(degrees) => Ok(Some(degrees)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Gets the camera profiles the device with the symbolic link `symlink` advertises. This is empty for devices that do not support camera profiles, which is most USB cameras.
+    pub fn device_profiles(symlink: &str) -> Result<Vec<MFCameraProfile>, BindingError> {
+        initialize_mf()?;
+
+        let symlink: Vec<u16> = symlink.encode_utf16().collect();
+        let media_source = create_media_source(usize::MAX, &symlink)?;
+        let profiles = sensor_profiles(&media_source)
+            .map(|profiles| profiles.into_iter().map(|(profile, _)| profile).collect());
+        // swallow errors
+        if let Err(_) = unsafe { media_source.Shutdown() } {}
+        profiles
+    }
+
+    fn create_media_source(index: usize, symlink: &[u16]) -> Result<IMFMediaSource, BindingError> {
+        // the symbolic link has to outlive the attributes, and be null terminated
+        let mut symlink_vec = symlink.to_vec();
+        symlink_vec.push(0);
+        let symlink_pwstr = PWSTR(symlink_vec.as_mut_ptr());
+
+        let mut attr: Option<IMFAttributes> = None;
+
+        if let Err(why) = unsafe { MFCreateAttributes(&mut attr, 2) } {
+            return Err(BindingError::AttributeError(why.to_string()));
+        }
+
+        let attributes = match attr {
+            Some(at) => {
+                if let Err(why) = unsafe {
+                    at.SetGUID(
+                        &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
+                        &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
+                    )
+                } {
+                    return Err(BindingError::AttributeError(why.to_string()));
+                }
+
+                if let Err(why) = unsafe {
+                    at.SetString(
+                        &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
+                        &symlink_pwstr,
+                    )
+                } {
+                    return Err(BindingError::AttributeError(why.to_string()));
+                }
+                at
+            }
+            None => {
+                return Err(BindingError::DeviceOpenFailError(
+                    index,
+                    "Attribute Error".to_string(),
+                ))
+            }
+        };
+
+        match unsafe { MFCreateDeviceSource(&attributes) } {
+            Ok(src) => Ok(src),
+            Err(why) => Err(classify_error(index, &why, |error| {
+                BindingError::DeviceOpenFailError(index, error)
+            })),
+        }
+    }
+
+    // the profiles live in the `MF_DEVICEMFT_SENSORPROFILE_COLLECTION` attribute of the source, which only exists if the driver (or the sensor group) declares any
+    fn sensor_profiles(
+        media_source: &IMFMediaSource,
+    ) -> Result<Vec<(MFCameraProfile, IMFSensorProfile)>, BindingError> {
+        let source_attributes = match media_source
+            .cast::<IMFMediaSourceEx>()
+            .and_then(|source| unsafe { source.GetSourceAttributes() })
+        {
+            Ok(attributes) => attributes,
+            Err(why) => return Err(BindingError::AttributeError(why.to_string())),
+        };
+
+        let mut collection: Option<IMFSensorProfileCollection> = None;
+        if unsafe {
+            source_attributes.GetUnknown(
+                &MF_DEVICEMFT_SENSORPROFILE_COLLECTION,
+                &IMFSensorProfileCollection::IID,
+                (&mut collection as *mut Option<IMFSensorProfileCollection>).cast(),
+            )
+        }
+        .is_err()
+        {
+            return Ok(vec![]);
+        }
+        let collection = match collection {
+            Some(collection) => collection,
+            None => return Ok(vec![]),
+        };
+
+        let mut profiles = vec![];
+        for profile_index in 0..unsafe { collection.GetProfileCount() } {
+            let sensor_profile = match unsafe { collection.GetProfile(profile_index) } {
+                Ok(profile) => profile,
+                Err(why) => {
+                    return Err(BindingError::GUIDReadError(
+                        "MF_DEVICEMFT_SENSORPROFILE_COLLECTION".to_string(),
+                        why.to_string(),
+                    ))
+                }
+            };
+            let mut id = SENSORPROFILEID::default();
+            if let Err(why) = unsafe { sensor_profile.GetProfileId(&mut id) } {
+                return Err(BindingError::GUIDReadError(
+                    "SENSORPROFILEID".to_string(),
+                    why.to_string(),
+                ));
+            }
+            let kind = CAMERA_PROFILES
+                .iter()
+                .find(|(_, guid)| *guid == id.Type)
+                .map_or(MFCameraProfileKind::Unknown, |(kind, _)| *kind);
+            profiles.push((
+                MFCameraProfile {
+                    kind,
+                    index: id.Index,
+                },
+                sensor_profile,
+            ));
+        }
+        Ok(profiles)
+    }
+
+    // tells the driver which profile is in use with `KSPROPERTY_CAMERACONTROL_EXTENDED_PROFILE`, which has to happen before any stream is started
+    #[allow(clippy::cast_possible_truncation)]
+    fn select_profile(
+        index: usize,
+        media_source: &IMFMediaSource,
+        profile: MFCameraProfile,
+    ) -> Result<(), BindingError> {
+        let profile_id = match CAMERA_PROFILES
+            .iter()
+            .find(|(kind, _)| *kind == profile.kind)
+        {
+            Some((_, guid)) => *guid,
+            None => {
+                return Err(BindingError::DeviceOpenFailError(
+                    index,
+                    format!("Cannot select camera profile {:?}", profile),
+                ))
+            }
+        };
+
+        let ks_control = match media_source.cast::<IKsControl>() {
+            Ok(ks) => ks,
+            Err(why) => {
+                return Err(BindingError::GUIDReadError(
+                    "IKsControl".to_string(),
+                    why.to_string(),
+                ))
+            }
+        };
+
+        let property = KsProperty {
+            set: KSPROPERTYSETID_EXTENDED_CAMERA_CONTROL,
+            id: KSPROPERTY_CAMERACONTROL_EXTENDED_PROFILE,
+            flags: KSPROPERTY_TYPE_SET,
+        };
+        let mut payload = KsCameraProfileProperty {
+            version: KSCAMERA_EXTENDEDPROP_VERSION,
+            pin_id: KSCAMERA_EXTENDEDPROP_FILTERSCOPE,
+            size: std::mem::size_of::<KsCameraProfileProperty>() as u32,
+            result: 0,
+            flags: 0,
+            capability: 0,
+            profile_id,
+            index: profile.index,
+            reserved: 0,
+        };
+        let mut returned = 0_u32;
+
+        if let Err(why) = unsafe {
+            ks_control.KsProperty(
+                (&property as *const KsProperty).cast(),
+                std::mem::size_of::<KsProperty>() as u32,
+                (&mut payload as *mut KsCameraProfileProperty).cast(),
+                payload.size,
+                &mut returned,
+            )
+        } {
+            return Err(classify_error(index, &why, |error| {
+                BindingError::GUIDSetError(
+                    "KSPROPERTY_CAMERACONTROL_EXTENDED_PROFILE".to_string(),
+                    format!("{:?}", profile),
+                    error,
+                )
+            }));
+        }
+        Ok(())
+    }
+
     pub fn query_msmf() -> Result<Vec<MediaFoundationDeviceDescriptor<'static>>, BindingError> {
+        enumerate_devices(None)
+    }
+
+    /// Lists the sensor groups (`KSCATEGORY_SENSOR_CAMERA`) of the system, which combine several cameras (e.g. the color and IR cameras of a Windows Hello camera) into one source.
+    /// Their indexes are separate from those of [`query_msmf()`], see [`MediaFoundationDevice::new_sensor_group()`].
+    pub fn query_msmf_sensor_groups(
+    ) -> Result<Vec<MediaFoundationDeviceDescriptor<'static>>, BindingError> {
+        enumerate_devices(Some(&KSCATEGORY_SENSOR_CAMERA))
+    }
+
+    // please refer to https://docs.microsoft.com/en-us/windows/win32/medfound/enumerating-video-capture-devices
+    fn enumerate_devices(
+        category: Option<&Guid>,
+    ) -> Result<Vec<MediaFoundationDeviceDescriptor<'static>>, BindingError> {
         initialize_mf()?;
 
         let mut attributes: Option<IMFAttributes> = None;
-        if let Err(why) = unsafe { MFCreateAttributes(&mut attributes, 1) } {
+        if let Err(why) = unsafe { MFCreateAttributes(&mut attributes, 2) } {
             return Err(BindingError::AttributeError(why.to_string()));
         }
 
@@ -438,6 +921,20 @@ pub mod wmf {
                 } {
                     return Err(BindingError::AttributeError(why.to_string()));
                 }
+                if let Some(category) = category {
+                    if let Err(why) = unsafe {
+                        attr.SetGUID(
+                            &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_CATEGORY,
+                            category,
+                        )
+                    } {
+                        return Err(BindingError::GUIDSetError(
+                            "MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_CATEGORY".to_string(),
+                            format!("{:?}", category),
+                            why.to_string(),
+                        ));
+                    }
+                }
                 attr
             }
             None => {
@@ -516,88 +1013,129 @@ pub mod wmf {
         Ok(device_list)
     }
 
+    fn sample_bytes(sample: &IMFSample) -> Result<Vec<u8>, BindingError> {
+        let imf_buffer = match unsafe { sample.ConvertToContiguousBuffer() } {
+            Ok(buf) => buf,
+            Err(why) => return Err(BindingError::ReadFrameError(why.to_string())),
+        };
+
+        let mut buffer_length = match unsafe { imf_buffer.GetCurrentLength() } {
+            Ok(len) => len,
+            Err(why) => return Err(BindingError::ReadFrameError(why.to_string())),
+        };
+
+        let mut raw_data_vec: Vec<u8> = vec![];
+        raw_data_vec.resize(buffer_length as usize, 0_u8);
+
+        let mut max_buffer_len = usize::MAX as u32;
+
+        if let Err(why) = unsafe {
+            imf_buffer.Lock(
+                &mut raw_data_vec.as_mut_ptr(),
+                &mut max_buffer_len,
+                &mut buffer_length,
+            )
+        } {
+            return Err(BindingError::ReadFrameError(why.to_string()));
+        }
+
+        if let Err(why) = unsafe { imf_buffer.Unlock() } {
+            return Err(BindingError::ReadFrameError(why.to_string()));
+        }
+
+        Ok(raw_data_vec)
+    }
+
     pub struct MediaFoundationDevice {
         is_open: Cell<bool>,
         device_specifier: MediaFoundationDeviceDescriptor<'static>,
         device_format: MFCameraFormat,
         media_source: IMFMediaSource,
         source_reader: IMFSourceReader,
+        profile: Option<(MFCameraProfile, IMFSensorProfile)>,
     }
 
     impl MediaFoundationDevice {
         pub fn new(index: usize) -> Result<Self, BindingError> {
-            let device_descriptor = {
-                match query_msmf()?.into_iter().nth(index) {
-                    Some(dd) => dd,
-                    None => {
-                        return Err(BindingError::DeviceOpenFailError(
-                            index,
-                            "No Device".to_string(),
-                        ))
-                    }
-                }
-            };
-
-            let attributes = {
-                let cow_vec = device_descriptor.symlink.to_vec().as_mut_ptr();
-                let symlink_pwstr = PWSTR(cow_vec);
+            Self::new_with_profile(index, None)
+        }
 
-                let mut attr: Option<IMFAttributes> = None;
+        /// Opens the device at `index` of [`query_msmf()`] and selects the camera profile `profile`, which restricts the device to the media types of that profile.
+        /// # Errors
+        /// This errors if the device does not advertise `profile`, or refuses to switch to it.
+        pub fn new_with_profile(
+            index: usize,
+            profile: Option<MFCameraProfile>,
+        ) -> Result<Self, BindingError> {
+            match query_msmf()?.into_iter().nth(index) {
+                Some(descriptor) => Self::open(descriptor, profile),
+                None => Err(BindingError::DeviceOpenFailError(
+                    index,
+                    "No Device".to_string(),
+                )),
+            }
+        }
 
-                if let Err(why) = unsafe { MFCreateAttributes(&mut attr, 2) } {
-                    return Err(BindingError::AttributeError(why.to_string()));
-                }
+        /// Opens the sensor group at `index` of [`query_msmf_sensor_groups()`], optionally selecting the camera profile `profile`.
+        /// # Errors
+        /// This errors if the sensor group does not exist, does not advertise `profile`, or refuses to switch to it.
+        pub fn new_sensor_group(
+            index: usize,
+            profile: Option<MFCameraProfile>,
+        ) -> Result<Self, BindingError> {
+            match query_msmf_sensor_groups()?.into_iter().nth(index) {
+                Some(descriptor) => Self::open(descriptor, profile),
+                None => Err(BindingError::DeviceOpenFailError(
+                    index,
+                    "No Sensor Group".to_string(),
+                )),
+            }
+        }
 
-                let attr = match attr {
-                    Some(at) => {
-                        if let Err(why) = unsafe {
-                            at.SetGUID(
-                                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE,
-                                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
-                            )
-                        } {
-                            return Err(BindingError::AttributeError(why.to_string()));
+        fn open(
+            device_descriptor: MediaFoundationDeviceDescriptor<'static>,
+            profile: Option<MFCameraProfile>,
+        ) -> Result<Self, BindingError> {
+            let index = device_descriptor.index;
+            let media_source = create_media_source(index, &device_descriptor.symlink)?;
+
+            let profile = match profile {
+                Some(wanted) => {
+                    let sensor_profile = match sensor_profiles(&media_source)?
+                        .into_iter()
+                        .find(|(profile, _)| *profile == wanted)
+                    {
+                        Some((_, sensor_profile)) => sensor_profile,
+                        None => {
+                            return Err(BindingError::DeviceOpenFailError(
+                                index,
+                                format!("No camera profile {:?}", wanted),
+                            ))
                         }
+                    };
+                    select_profile(index, &media_source, wanted)?;
+                    Some((wanted, sensor_profile))
+                }
+                None => None,
+            };
 
-                        if let Err(why) = unsafe {
-                            at.SetString(
-                                &MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
-                                &symlink_pwstr,
-                            )
-                        } {
-                            return Err(BindingError::AttributeError(why.to_string()));
-                        }
-                        at
-                    }
-                    None => {
-                        return Err(BindingError::DeviceOpenFailError(
-                            index,
-                            "Attribute Error".to_string(),
-                        ))
+            let source_reader =
+                match unsafe { MFCreateSourceReaderFromMediaSource(&media_source, None) } {
+                    Ok(sr) => sr,
+                    Err(why) => {
+                        return Err(classify_error(index, &why, |error| {
+                            BindingError::DeviceOpenFailError(index, error)
+                        }))
                     }
                 };
 
-                attr
-            };
-
-            let media_source = match unsafe { MFCreateDeviceSource(&attributes) } {
-                Ok(src) => src,
-                Err(why) => return Err(BindingError::DeviceOpenFailError(index, why.to_string())),
-            };
-
-            let source_reader = match unsafe {
-                MFCreateSourceReaderFromMediaSource(&media_source, None)
-            } {
-                Ok(sr) => sr,
-                Err(why) => return Err(BindingError::DeviceOpenFailError(index, why.to_string())),
-            };
-
             Ok(MediaFoundationDevice {
                 is_open: Cell::new(false),
                 device_specifier: device_descriptor,
                 device_format: MFCameraFormat::default(),
                 media_source,
                 source_reader,
+                profile,
             })
         }
 
@@ -613,6 +1151,36 @@ pub mod wmf {
             self.device_specifier.link_as_string()
         }
 
+        /// The `IMFSourceReader` this device reads from, as a raw COM pointer.
+        /// # Safety
+        /// No reference is added, the pointer is only valid for as long as this device is alive. Do not `Release()` it.
+        pub unsafe fn raw_source_reader(&self) -> *mut c_void {
+            self.source_reader.abi()
+        }
+
+        /// The camera profile selected when opening the device, if any.
+        pub fn profile(&self) -> Option<MFCameraProfile> {
+            self.profile.as_ref().map(|(profile, _)| *profile)
+        }
+
+        /// The camera profiles the device advertises.
+        pub fn profiles(&self) -> Result<Vec<MFCameraProfile>, BindingError> {
+            sensor_profiles(&self.media_source)
+                .map(|profiles| profiles.into_iter().map(|(profile, _)| profile).collect())
+        }
+
+        // media types outside of the selected profile are refused by the driver. Without a profile everything goes.
+        fn profile_supports(&self, media_type: &IMFMediaType) -> bool {
+            match &self.profile {
+                // profiles refer to streams by their identifier, the video stream `nokhwa` reads is stream 0
+                Some((_, sensor_profile)) => {
+                    unsafe { sensor_profile.IsMediaTypeSupported(0, media_type) }
+                        .map_or(false, |supported| supported.as_bool())
+                }
+                None => true,
+            }
+        }
+
         pub fn compatible_format_list(&mut self) -> Result<Vec<MFCameraFormat>, BindingError> {
             let mut camera_format_list = vec![];
             let mut index = 0;
@@ -634,6 +1202,11 @@ pub mod wmf {
                     }
                 };
 
+                if !self.profile_supports(&media_type) {
+                    index += 1;
+                    continue;
+                }
+
                 let fourcc = match unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) } {
                     Ok(fcc) => fcc,
                     Err(why) => {
@@ -1261,6 +1834,36 @@ pub mod wmf {
                         ));
                     }
                 }
+                MediaFoundationControls::PowerLineFrequency => {
+                    if let Err(why) = unsafe {
+                        video_proc_amp.GetRange(
+                            VIDEO_PROC_AMP_POWER_LINE_FREQUENCY,
+                            &mut min,
+                            &mut max,
+                            &mut step,
+                            &mut default,
+                            &mut flag,
+                        )
+                    } {
+                        return Err(BindingError::GUIDReadError(
+                            "VideoProcAmp_PowerLineFrequency-Range".to_string(),
+                            why.to_string(),
+                        ));
+                    }
+
+                    if let Err(why) = unsafe {
+                        video_proc_amp.Get(
+                            VIDEO_PROC_AMP_POWER_LINE_FREQUENCY,
+                            &mut value,
+                            &mut flag,
+                        )
+                    } {
+                        return Err(BindingError::GUIDReadError(
+                            "VideoProcAmp_PowerLineFrequency-Value".to_string(),
+                            why.to_string(),
+                        ));
+                    }
+                }
             }
 
             let is_manual = match flag {
@@ -1492,6 +2095,17 @@ pub mod wmf {
                         ));
                     }
                 }
+                MediaFoundationControls::PowerLineFrequency => {
+                    if let Err(why) = unsafe {
+                        video_proc_amp.Set(VIDEO_PROC_AMP_POWER_LINE_FREQUENCY, value, flags)
+                    } {
+                        return Err(BindingError::GUIDSetError(
+                            "VideoProcAmp_PowerLineFrequency".to_string(),
+                            format!("{} {}", value, flag_str),
+                            why.to_string(),
+                        ));
+                    }
+                }
             }
 
             Ok(())
@@ -1501,6 +2115,77 @@ pub mod wmf {
             self.device_format
         }
 
+        /// Reads the media type the video stream currently has (e.g. one another application set on a shared device) and makes it the device's format, without changing it.
+        pub fn adopt_current_format(&mut self) -> Result<MFCameraFormat, BindingError> {
+            let media_type = match unsafe {
+                self.source_reader
+                    .GetCurrentMediaType(MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32)
+            } {
+                Ok(mt) => mt,
+                Err(why) => {
+                    return Err(classify_error(self.index(), &why, |error| {
+                        BindingError::GUIDReadError(
+                            "MF_SOURCE_READER_FIRST_VIDEO_STREAM".to_string(),
+                            error,
+                        )
+                    }))
+                }
+            };
+
+            let format = match unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) } {
+                Ok(fourcc) if fourcc == MF_VIDEO_FORMAT_MJPEG => MFFrameFormat::MJPEG,
+                Ok(fourcc) if fourcc == MF_VIDEO_FORMAT_YUY2 => MFFrameFormat::YUYV,
+                Ok(fourcc) => {
+                    return Err(BindingError::UnsupportedMediaTypeError(
+                        format!("{:?}", fourcc),
+                        "Current media type is not MJPEG or YUY2".to_string(),
+                    ))
+                }
+                Err(why) => {
+                    return Err(BindingError::GUIDReadError(
+                        "MF_MT_SUBTYPE".to_string(),
+                        why.to_string(),
+                    ))
+                }
+            };
+            let resolution = match unsafe { media_type.GetUINT64(&MF_MT_FRAME_SIZE) } {
+                Ok(res_u64) => MFResolution {
+                    width_x: (res_u64 >> 32) as u32,
+                    height_y: res_u64 as u32, // the cast will truncate the upper bits
+                },
+                Err(why) => {
+                    return Err(BindingError::GUIDReadError(
+                        "MF_MT_FRAME_SIZE".to_string(),
+                        why.to_string(),
+                    ))
+                }
+            };
+            let frame_rate = match unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE) } {
+                Ok(fraction_u64) => {
+                    let numerator = (fraction_u64 >> 32) as u32;
+                    let denominator = fraction_u64 as u32;
+                    if denominator == 0 {
+                        0
+                    } else {
+                        numerator / denominator
+                    }
+                }
+                Err(why) => {
+                    return Err(BindingError::GUIDReadError(
+                        "MF_MT_FRAME_RATE".to_string(),
+                        why.to_string(),
+                    ))
+                }
+            };
+
+            self.device_format = MFCameraFormat {
+                resolution,
+                format,
+                frame_rate,
+            };
+            Ok(self.device_format)
+        }
+
         pub fn set_format(&mut self, format: MFCameraFormat) -> Result<(), BindingError> {
             // convert to media_type
             let media_type = match unsafe { MFCreateMediaType() } {
@@ -1562,6 +2247,13 @@ pub mod wmf {
                 ));
             }
 
+            if !self.profile_supports(&media_type) {
+                return Err(BindingError::UnsupportedMediaTypeError(
+                    format!("{:?}", format),
+                    format!("Not part of camera profile {:?}", self.profile()),
+                ));
+            }
+
             let mut reserved = 0_u32;
 
             if let Err(why) = unsafe {
@@ -1571,11 +2263,19 @@ pub mod wmf {
                     media_type.clone(),
                 )
             } {
-                return Err(BindingError::GUIDSetError(
-                    "MF_SOURCE_READER_FIRST_VIDEO_STREAM".to_string(),
-                    format!("{:?}", media_type),
-                    why.to_string(),
-                ));
+                if INVALID_MEDIA_TYPE_CODES.contains(&why.code().0) {
+                    return Err(BindingError::UnsupportedMediaTypeError(
+                        format!("{:?}", format),
+                        why.to_string(),
+                    ));
+                }
+                return Err(classify_error(self.index(), &why, |error| {
+                    BindingError::GUIDSetError(
+                        "MF_SOURCE_READER_FIRST_VIDEO_STREAM".to_string(),
+                        format!("{:?}", media_type),
+                        error,
+                    )
+                }));
             }
 
             self.device_format = format;
@@ -1608,7 +2308,11 @@ pub mod wmf {
                     &mut imf_sample,
                 )
             } {
-                return Err(BindingError::ReadFrameError(why.to_string()));
+                return Err(classify_error(
+                    self.index(),
+                    &why,
+                    BindingError::ReadFrameError,
+                ));
             }
 
             let imf_sample = match imf_sample {
@@ -1616,36 +2320,177 @@ pub mod wmf {
                 None => return Err(BindingError::ReadFrameError("Sample Not Init".to_string())),
             };
 
-            let imf_buffer = match unsafe { imf_sample.ConvertToContiguousBuffer() } {
-                Ok(buf) => buf,
-                Err(why) => return Err(BindingError::ReadFrameError(why.to_string())),
-            };
+            Ok(Cow::from(sample_bytes(&imf_sample)?))
+        }
+
+        /// Reads (and discards) a single sample to find out if another process holds the camera exclusively.
+        pub fn is_locked(&mut self) -> Result<bool, BindingError> {
+            let mut imf_sample: Option<IMFSample> = None;
+            let mut stream_index = 0_u32;
+            let mut stream_flags = 0_u32;
+            let mut time_stamp = 0_i64;
+
+            match unsafe {
+                self.source_reader.ReadSample(
+                    MF_SOURCE_READER_FIRST_VIDEO_STREAM.0 as u32,
+                    0,
+                    &mut stream_index,
+                    &mut stream_flags,
+                    &mut time_stamp,
+                    &mut imf_sample,
+                )
+            } {
+                Ok(_) => Ok(false),
+                Err(why) => {
+                    if DEVICE_BUSY_CODES.contains(&why.code().0) {
+                        Ok(true)
+                    } else {
+                        Err(BindingError::ReadFrameError(why.to_string()))
+                    }
+                }
+            }
+        }
 
-            let mut buffer_length = match unsafe { imf_buffer.GetCurrentLength() } {
-                Ok(len) => len,
-                Err(why) => return Err(BindingError::ReadFrameError(why.to_string())),
+        // finds the photo stream by its category, `PINNAME_IMAGE` for photo streams and `PINNAME_VIDEO_STILL` for still pins
+        fn photo_stream_index(&self) -> Result<Option<u32>, BindingError> {
+            let presentation = match unsafe { self.media_source.CreatePresentationDescriptor() } {
+                Ok(pd) => pd,
+                Err(why) => return Err(BindingError::AttributeError(why.to_string())),
+            };
+            let count = match unsafe { presentation.GetStreamDescriptorCount() } {
+                Ok(count) => count,
+                Err(why) => return Err(BindingError::AttributeError(why.to_string())),
             };
 
-            let mut raw_data_vec: Vec<u8> = vec![];
-            raw_data_vec.resize(buffer_length as usize, 0_u8);
+            for index in 0..count {
+                let mut selected = BOOL::from(false);
+                let mut descriptor: Option<IMFStreamDescriptor> = None;
+                if let Err(why) = unsafe {
+                    presentation.GetStreamDescriptorByIndex(index, &mut selected, &mut descriptor)
+                } {
+                    return Err(BindingError::AttributeError(why.to_string()));
+                }
+                let category = match descriptor
+                    .map(|desc| unsafe { desc.GetGUID(&MF_DEVICESTREAM_STREAM_CATEGORY) })
+                {
+                    Some(Ok(category)) => category,
+                    // streams without a category are not photo streams
+                    _ => continue,
+                };
+                if category == PINNAME_IMAGE || category == PINNAME_VIDEO_STILL {
+                    return Ok(Some(index));
+                }
+            }
+            Ok(None)
+        }
 
-            let mut max_buffer_len = usize::MAX as u32;
+        /// Captures a photo from the device's photo stream (or still pin), at the largest MJPEG, JPEG or YUY2 media type it offers. JPEG photos are reported as MJPEG.
+        /// Returns `None` if the device has no photo stream.
+        pub fn photo(
+            &mut self,
+        ) -> Result<Option<(MFResolution, MFFrameFormat, Vec<u8>)>, BindingError> {
+            let stream = match self.photo_stream_index()? {
+                Some(stream) => stream,
+                None => return Ok(None),
+            };
 
+            let mut largest: Option<(u64, MFResolution, MFFrameFormat, IMFMediaType)> = None;
+            let mut type_index = 0;
+            // MF_E_NO_MORE_TYPES ends the list
+            while let Ok(media_type) =
+                unsafe { self.source_reader.GetNativeMediaType(stream, type_index) }
+            {
+                type_index += 1;
+                let format = match unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) } {
+                    Ok(fourcc)
+                        if fourcc == MF_VIDEO_FORMAT_MJPEG || fourcc == MF_IMAGE_FORMAT_JPEG =>
+                    {
+                        MFFrameFormat::MJPEG
+                    }
+                    Ok(fourcc) if fourcc == MF_VIDEO_FORMAT_YUY2 => MFFrameFormat::YUYV,
+                    _ => continue,
+                };
+                let resolution = match unsafe { media_type.GetUINT64(&MF_MT_FRAME_SIZE) } {
+                    Ok(res_u64) => MFResolution {
+                        width_x: (res_u64 >> 32) as u32,
+                        height_y: res_u64 as u32, // the cast will truncate the upper bits
+                    },
+                    Err(_) => continue,
+                };
+                let pixels = u64::from(resolution.width_x) * u64::from(resolution.height_y);
+                if largest
+                    .as_ref()
+                    .map_or(true, |(most, _, _, _)| pixels > *most)
+                {
+                    largest = Some((pixels, resolution, format, media_type));
+                }
+            }
+            let (_, resolution, format, media_type) = match largest {
+                Some(largest) => largest,
+                None => return Ok(None),
+            };
+
+            let mut reserved = 0_u32;
             if let Err(why) = unsafe {
-                imf_buffer.Lock(
-                    &mut raw_data_vec.as_mut_ptr(),
-                    &mut max_buffer_len,
-                    &mut buffer_length,
-                )
+                self.source_reader
+                    .SetCurrentMediaType(stream, &mut reserved, media_type.clone())
             } {
-                return Err(BindingError::ReadFrameError(why.to_string()));
+                return Err(BindingError::GUIDSetError(
+                    "Photo stream media type".to_string(),
+                    format!("{:?}", media_type),
+                    why.to_string(),
+                ));
             }
-
-            if let Err(why) = unsafe { imf_buffer.Unlock() } {
+            if let Err(why) = unsafe {
+                self.source_reader
+                    .SetStreamSelection(stream, BOOL::from(true))
+            } {
                 return Err(BindingError::ReadFrameError(why.to_string()));
             }
 
-            Ok(Cow::from(raw_data_vec))
+            let photo = self.read_stream_sample(stream);
+
+            // swallow errors, the video stream works either way
+            if let Err(_) = unsafe {
+                self.source_reader
+                    .SetStreamSelection(stream, BOOL::from(false))
+            } {}
+
+            Ok(Some((resolution, format, photo?)))
+        }
+
+        fn read_stream_sample(&mut self, stream: u32) -> Result<Vec<u8>, BindingError> {
+            // the first reads may only deliver stream ticks while the photo is taken
+            for _ in 0..PHOTO_READ_ATTEMPTS {
+                let mut imf_sample: Option<IMFSample> = None;
+                let mut stream_index = 0_u32;
+                let mut stream_flags = 0_u32;
+                let mut time_stamp = 0_i64;
+
+                if let Err(why) = unsafe {
+                    self.source_reader.ReadSample(
+                        stream,
+                        0,
+                        &mut stream_index,
+                        &mut stream_flags,
+                        &mut time_stamp,
+                        &mut imf_sample,
+                    )
+                } {
+                    return Err(classify_error(
+                        self.index(),
+                        &why,
+                        BindingError::ReadFrameError,
+                    ));
+                }
+
+                if let Some(sample) = imf_sample {
+                    return sample_bytes(&sample);
+                }
+            }
+            Err(BindingError::ReadFrameError(
+                "The photo stream did not deliver a photo".to_string(),
+            ))
         }
 
         pub fn stop_stream(&mut self) {
@@ -1672,10 +2517,10 @@ pub mod wmf {
 #[allow(clippy::unused_self)]
 pub mod wmf {
     use crate::{
-        BindingError, MFCameraFormat, MFControl, MediaFoundationControls,
-        MediaFoundationDeviceDescriptor,
+        BindingError, MFCameraFormat, MFCameraProfile, MFControl, MFDevicePanel, MFFrameFormat,
+        MFResolution, MediaFoundationControls, MediaFoundationDeviceDescriptor,
     };
-    use std::{borrow::Cow, cell::Cell};
+    use std::{borrow::Cow, cell::Cell, ffi::c_void};
 
     pub fn initialize_mf() -> Result<(), BindingError> {
         Err(BindingError::NotImplementedError)
@@ -1689,6 +2534,23 @@ pub mod wmf {
         Err(BindingError::NotImplementedError)
     }
 
+    pub fn device_panel(_symlink: &str) -> Result<Option<MFDevicePanel>, BindingError> {
+        Err(BindingError::NotImplementedError)
+    }
+
+    pub fn device_rotation(_symlink: &str) -> Result<Option<u32>, BindingError> {
+        Err(BindingError::NotImplementedError)
+    }
+
+    pub fn device_profiles(_symlink: &str) -> Result<Vec<MFCameraProfile>, BindingError> {
+        Err(BindingError::NotImplementedError)
+    }
+
+    pub fn query_msmf_sensor_groups(
+    ) -> Result<Vec<MediaFoundationDeviceDescriptor<'static>>, BindingError> {
+        Err(BindingError::NotImplementedError)
+    }
+
     pub struct MediaFoundationDevice {
         op: Cell<bool>,
     }
@@ -1700,6 +2562,24 @@ pub mod wmf {
             })
         }
 
+        pub fn new_with_profile(
+            _index: usize,
+            _profile: Option<MFCameraProfile>,
+        ) -> Result<Self, BindingError> {
+            Ok(MediaFoundationDevice {
+                op: Cell::new(false),
+            })
+        }
+
+        pub fn new_sensor_group(
+            _index: usize,
+            _profile: Option<MFCameraProfile>,
+        ) -> Result<Self, BindingError> {
+            Ok(MediaFoundationDevice {
+                op: Cell::new(false),
+            })
+        }
+
         pub fn index(&self) -> usize {
             usize::MAX
         }
@@ -1712,6 +2592,20 @@ pub mod wmf {
             "".to_string()
         }
 
+        /// # Safety
+        /// This is always null.
+        pub unsafe fn raw_source_reader(&self) -> *mut c_void {
+            std::ptr::null_mut()
+        }
+
+        pub fn profile(&self) -> Option<MFCameraProfile> {
+            None
+        }
+
+        pub fn profiles(&self) -> Result<Vec<MFCameraProfile>, BindingError> {
+            Err(BindingError::NotImplementedError)
+        }
+
         pub fn compatible_format_list(&mut self) -> Result<Vec<MFCameraFormat>, BindingError> {
             Err(BindingError::NotImplementedError)
         }
@@ -1731,6 +2625,10 @@ pub mod wmf {
             MFCameraFormat::default()
         }
 
+        pub fn adopt_current_format(&mut self) -> Result<MFCameraFormat, BindingError> {
+            Err(BindingError::NotImplementedError)
+        }
+
         pub fn set_format(&mut self, _format: MFCameraFormat) -> Result<(), BindingError> {
             Err(BindingError::NotImplementedError)
         }
@@ -1747,6 +2645,16 @@ pub mod wmf {
             Err(BindingError::NotImplementedError)
         }
 
+        pub fn is_locked(&mut self) -> Result<bool, BindingError> {
+            Err(BindingError::NotImplementedError)
+        }
+
+        pub fn photo(
+            &mut self,
+        ) -> Result<Option<(MFResolution, MFFrameFormat, Vec<u8>)>, BindingError> {
+            Err(BindingError::NotImplementedError)
+        }
+
         pub fn stop_stream(&mut self) {
             self.op.set(true);
         }
@@ -0,0 +1,24 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{env, path::PathBuf};
+
+// regenerates `include/nokhwa.h`, which is checked in so C users do not need cbindgen
+fn main() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("No manifest dir"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("Invalid cbindgen.toml");
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(crate_dir.join("include").join("nokhwa.h"));
+        }
+        // keep the checked in header rather than failing the build (e.g. on docs.rs)
+        Err(why) => println!("cargo:warning=Could not generate nokhwa.h: {}", why),
+    }
+}
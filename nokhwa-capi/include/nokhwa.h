@@ -0,0 +1,217 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#ifndef NOKHWA_H
+#define NOKHWA_H
+
+/* Generated by cbindgen from nokhwa-capi/src/lib.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The capture backends, see `nokhwa::CaptureAPIBackend`.
+typedef enum NokhwaBackend {
+  NOKHWA_BACKEND_AUTO = 0,
+  NOKHWA_BACKEND_AV_FOUNDATION,
+  NOKHWA_BACKEND_VIDEO4_LINUX,
+  NOKHWA_BACKEND_UNIVERSAL_VIDEO_CLASS,
+  NOKHWA_BACKEND_MEDIA_FOUNDATION,
+  NOKHWA_BACKEND_OPEN_CV,
+  NOKHWA_BACKEND_G_STREAMER,
+} NokhwaBackend;
+
+// The camera controls, see `nokhwa::KnownCameraControls`.
+typedef enum NokhwaControl {
+  NOKHWA_CONTROL_BRIGHTNESS = 0,
+  NOKHWA_CONTROL_CONTRAST,
+  NOKHWA_CONTROL_HUE,
+  NOKHWA_CONTROL_SATURATION,
+  NOKHWA_CONTROL_SHARPNESS,
+  NOKHWA_CONTROL_GAMMA,
+  NOKHWA_CONTROL_COLOR_ENABLE,
+  NOKHWA_CONTROL_WHITE_BALANCE,
+  NOKHWA_CONTROL_BACKLIGHT_COMP,
+  NOKHWA_CONTROL_GAIN,
+  NOKHWA_CONTROL_PAN,
+  NOKHWA_CONTROL_TILT,
+  NOKHWA_CONTROL_ROLL,
+  NOKHWA_CONTROL_ZOOM,
+  NOKHWA_CONTROL_EXPOSURE,
+  NOKHWA_CONTROL_IRIS,
+  NOKHWA_CONTROL_FOCUS,
+  NOKHWA_CONTROL_POWER_LINE_FREQUENCY,
+} NokhwaControl;
+
+// The frame formats, see `nokhwa::FrameFormat`.
+typedef enum NokhwaFrameFormat {
+  NOKHWA_FRAME_FORMAT_MJPEG = 0,
+  NOKHWA_FRAME_FORMAT_YUYV,
+  NOKHWA_FRAME_FORMAT_BAYER_RGGB8,
+  NOKHWA_FRAME_FORMAT_BAYER_GRBG8,
+  NOKHWA_FRAME_FORMAT_BAYER_GBRG8,
+  NOKHWA_FRAME_FORMAT_BAYER_BGGR8,
+  NOKHWA_FRAME_FORMAT_BAYER_RGGB10,
+  NOKHWA_FRAME_FORMAT_BAYER_GRBG10,
+  NOKHWA_FRAME_FORMAT_BAYER_GBRG10,
+  NOKHWA_FRAME_FORMAT_BAYER_BGGR10,
+  NOKHWA_FRAME_FORMAT_BAYER_RGGB12,
+  NOKHWA_FRAME_FORMAT_BAYER_GRBG12,
+  NOKHWA_FRAME_FORMAT_BAYER_GBRG12,
+  NOKHWA_FRAME_FORMAT_BAYER_BGGR12,
+  NOKHWA_FRAME_FORMAT_Y16,
+  NOKHWA_FRAME_FORMAT_P010,
+  NOKHWA_FRAME_FORMAT_H264,
+  NOKHWA_FRAME_FORMAT_H265,
+  NOKHWA_FRAME_FORMAT_Z16,
+} NokhwaFrameFormat;
+
+// The result of every `nokhwa_` function.
+typedef enum NokhwaStatus {
+  // The function succeeded.
+  NOKHWA_STATUS_OK = 0,
+  // A pointer that must not be null was null.
+  NOKHWA_STATUS_NULL_POINTER,
+  // An argument was invalid, e.g. a control value out of range.
+  NOKHWA_STATUS_INVALID_ARGUMENT,
+  // The buffer is too small for the frame. The needed size is written to `written`.
+  NOKHWA_STATUS_BUFFER_TOO_SMALL,
+  // The device was unplugged or went away.
+  NOKHWA_STATUS_DEVICE_DISCONNECTED,
+  // Another process is using the device.
+  NOKHWA_STATUS_DEVICE_BUSY,
+  // This program may not use the device.
+  NOKHWA_STATUS_PERMISSION_DENIED,
+  // The device does not support the requested format.
+  NOKHWA_STATUS_UNSUPPORTED_FORMAT,
+  // No frame arrived in time.
+  NOKHWA_STATUS_TIMEOUT,
+  // The backend does not support this operation.
+  NOKHWA_STATUS_UNSUPPORTED_OPERATION,
+  // Any other error, see [`nokhwa_last_error()`].
+  NOKHWA_STATUS_ERROR,
+  // `nokhwa` panicked. This is a bug, please report it.
+  NOKHWA_STATUS_PANIC,
+} NokhwaStatus;
+
+// An open camera. Made by [`nokhwa_camera_open()`], freed by [`nokhwa_camera_free()`].
+typedef struct NokhwaCamera NokhwaCamera;
+
+// A list of devices. Made by [`nokhwa_query_devices()`], freed by [`nokhwa_device_list_free()`].
+typedef struct NokhwaDeviceList NokhwaDeviceList;
+
+// A camera format: resolution, frame rate and frame format.
+typedef struct NokhwaCameraFormat {
+  uint32_t width;
+  uint32_t height;
+  uint32_t frame_rate;
+  // A [`NokhwaFrameFormat`].
+  uint32_t format;
+} NokhwaCameraFormat;
+
+// The state of a camera control.
+typedef struct NokhwaControlInfo {
+  NokhwaControl control;
+  int32_t minimum;
+  int32_t maximum;
+  int32_t value;
+  int32_t step;
+  int32_t default_value;
+  // Whether the OS/driver manages the control instead of the set value.
+  bool automatic;
+  bool active;
+} NokhwaControlInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The version of `nokhwa-capi`, as a static NUL terminated string.
+const char *nokhwa_version(void);
+
+// The message of the last error on this thread, or null if there was none. It stays valid until the next `nokhwa_` call on this thread fails.
+const char *nokhwa_last_error(void);
+
+// Lists the devices `backend` (a [`NokhwaBackend`]) can find into a new list written to `list`. Free it with [`nokhwa_device_list_free()`].
+NokhwaStatus nokhwa_query_devices(uint32_t backend, NokhwaDeviceList **list);
+
+// How many devices are in `list`. A null `list` has none.
+size_t nokhwa_device_list_len(const NokhwaDeviceList *list);
+
+// The index to pass to [`nokhwa_camera_open()`] of the device at `position` in `list`, or `SIZE_MAX` if there is none.
+size_t nokhwa_device_list_index(const NokhwaDeviceList *list, size_t position);
+
+// The name of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+const char *nokhwa_device_list_name(const NokhwaDeviceList *list, size_t position);
+
+// The description of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+const char *nokhwa_device_list_description(const NokhwaDeviceList *list, size_t position);
+
+// The backend specific extra information (e.g. the symbolic link on Media Foundation) of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+const char *nokhwa_device_list_misc(const NokhwaDeviceList *list, size_t position);
+
+// Frees a list made by [`nokhwa_query_devices()`]. A null `list` is ignored.
+void nokhwa_device_list_free(NokhwaDeviceList *list);
+
+// Opens the camera at `index` with `backend` (a [`NokhwaBackend`]) into a new camera written to `camera`. `format` may be null for the default format. Free it with [`nokhwa_camera_free()`].
+NokhwaStatus nokhwa_camera_open(size_t index,
+                                uint32_t backend,
+                                const NokhwaCameraFormat *format,
+                                NokhwaCamera **camera);
+
+// Stops the stream and frees a camera made by [`nokhwa_camera_open()`]. A null `camera` is ignored.
+void nokhwa_camera_free(NokhwaCamera *camera);
+
+// Writes the current format of `camera` to `format`.
+NokhwaStatus nokhwa_camera_format(NokhwaCamera *camera, NokhwaCameraFormat *format);
+
+// Sets the format of `camera` to `format`. If the stream is open, it is reopened.
+NokhwaStatus nokhwa_camera_set_format(NokhwaCamera *camera, const NokhwaCameraFormat *format);
+
+// Writes up to `capacity` formats `camera` supports to `formats`, and how many it supports to `count`. Pass a null `formats` to only get the count.
+NokhwaStatus nokhwa_camera_compatible_formats(NokhwaCamera *camera,
+                                              NokhwaCameraFormat *formats,
+                                              size_t capacity,
+                                              size_t *count);
+
+// Opens the stream of `camera`.
+NokhwaStatus nokhwa_camera_open_stream(NokhwaCamera *camera);
+
+// Stops the stream of `camera`.
+NokhwaStatus nokhwa_camera_stop_stream(NokhwaCamera *camera);
+
+// Captures a RGB888 frame (3 bytes per pixel, rows without padding) from `camera` into `buffer`, which holds `length` bytes.
+// The size of the frame is written to `written`, and its resolution to `width` and `height` if they are not null.
+//
+// If `buffer` is too small, this returns [`NokhwaStatus::BufferTooSmall`] with the needed size in `written` and the frame is lost.
+// The size is `width * height * 3` of the current format, unless an output resolution or crop is set on the camera.
+NokhwaStatus nokhwa_camera_frame_rgb(NokhwaCamera *camera,
+                                     uint8_t *buffer,
+                                     size_t length,
+                                     size_t *written,
+                                     uint32_t *width,
+                                     uint32_t *height);
+
+// Writes up to `capacity` controls `camera` supports to `controls`, and how many it supports to `count`. Pass a null `controls` to only get the count.
+NokhwaStatus nokhwa_camera_supported_controls(NokhwaCamera *camera,
+                                              NokhwaControl *controls,
+                                              size_t capacity,
+                                              size_t *count);
+
+// Reads `control` (a [`NokhwaControl`]) of `camera` into `info`.
+NokhwaStatus nokhwa_camera_control(NokhwaCamera *camera,
+                                   uint32_t control,
+                                   NokhwaControlInfo *info);
+
+// Sets `control` (a [`NokhwaControl`]) of `camera` to `value`. If `value` is out of range or not aligned with the step, this returns [`NokhwaStatus::InvalidArgument`].
+NokhwaStatus nokhwa_camera_set_control(NokhwaCamera *camera, uint32_t control, int32_t value);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* NOKHWA_H */
@@ -0,0 +1,784 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(clippy::pedantic)]
+#![warn(clippy::all)]
+#![allow(clippy::missing_safety_doc)]
+
+//! The C API of [`nokhwa`], for embedding it in C, C++, C#, Python and others. The header is `include/nokhwa.h`.
+//!
+//! Every function returns a [`NokhwaStatus`], and writes its result through an out pointer. If a function fails, [`nokhwa_last_error()`] has the message.
+//! A panic never crosses the boundary, it is returned as [`NokhwaStatus::Panic`].
+//!
+//! A [`NokhwaCamera`] must only be used by one thread at a time.
+//!
+//! Enums are passed in as `uint32_t`, as C lets any value into an enum while Rust does not. A value that is not one of the variants returns [`NokhwaStatus::InvalidArgument`].
+
+use nokhwa::{
+    query_devices, BayerPattern, Camera, CameraControl, CameraFormat, CameraInfo,
+    CaptureAPIBackend, FrameFormat, KnownCameraControlFlag, KnownCameraControls, NokhwaError,
+};
+use std::{
+    cell::RefCell,
+    convert::TryFrom,
+    ffi::CString,
+    os::raw::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+/// The result of every `nokhwa_` function.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum NokhwaStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// A pointer that must not be null was null.
+    NullPointer,
+    /// An argument was invalid, e.g. a control value out of range.
+    InvalidArgument,
+    /// The buffer is too small for the frame. The needed size is written to `written`.
+    BufferTooSmall,
+    /// The device was unplugged or went away.
+    DeviceDisconnected,
+    /// Another process is using the device.
+    DeviceBusy,
+    /// This program may not use the device.
+    PermissionDenied,
+    /// The device does not support the requested format.
+    UnsupportedFormat,
+    /// No frame arrived in time.
+    Timeout,
+    /// The backend does not support this operation.
+    UnsupportedOperation,
+    /// Any other error, see [`nokhwa_last_error()`].
+    Error,
+    /// `nokhwa` panicked. This is a bug, please report it.
+    Panic,
+}
+
+/// The capture backends, see `nokhwa::CaptureAPIBackend`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum NokhwaBackend {
+    Auto = 0,
+    AvFoundation,
+    Video4Linux,
+    UniversalVideoClass,
+    MediaFoundation,
+    OpenCv,
+    GStreamer,
+}
+
+/// The frame formats, see `nokhwa::FrameFormat`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum NokhwaFrameFormat {
+    Mjpeg = 0,
+    Yuyv,
+    BayerRggb8,
+    BayerGrbg8,
+    BayerGbrg8,
+    BayerBggr8,
+    BayerRggb10,
+    BayerGrbg10,
+    BayerGbrg10,
+    BayerBggr10,
+    BayerRggb12,
+    BayerGrbg12,
+    BayerGbrg12,
+    BayerBggr12,
+    Y16,
+    P010,
+    H264,
+    H265,
+    Z16,
+}
+
+/// The camera controls, see `nokhwa::KnownCameraControls`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum NokhwaControl {
+    Brightness = 0,
+    Contrast,
+    Hue,
+    Saturation,
+    Sharpness,
+    Gamma,
+    ColorEnable,
+    WhiteBalance,
+    BacklightComp,
+    Gain,
+    Pan,
+    Tilt,
+    Roll,
+    Zoom,
+    Exposure,
+    Iris,
+    Focus,
+    PowerLineFrequency,
+}
+
+/// A camera format: resolution, frame rate and frame format.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct NokhwaCameraFormat {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: u32,
+    /// A [`NokhwaFrameFormat`].
+    pub format: u32,
+}
+
+/// The state of a camera control.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct NokhwaControlInfo {
+    pub control: NokhwaControl,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub value: i32,
+    pub step: i32,
+    pub default_value: i32,
+    /// Whether the OS/driver manages the control instead of the set value.
+    pub automatic: bool,
+    pub active: bool,
+}
+
+/// An open camera. Made by [`nokhwa_camera_open()`], freed by [`nokhwa_camera_free()`].
+pub struct NokhwaCamera {
+    camera: Camera,
+}
+
+/// A list of devices. Made by [`nokhwa_query_devices()`], freed by [`nokhwa_device_list_free()`].
+pub struct NokhwaDeviceList {
+    devices: Vec<Device>,
+}
+
+struct Device {
+    index: usize,
+    name: CString,
+    description: CString,
+    misc: CString,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+// interior NULs would cut the string short in C, so they go
+fn c_string(string: &str) -> CString {
+    CString::new(string.replace('\0', "")).unwrap_or_default()
+}
+
+fn set_last_error(message: &str) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(c_string(message)));
+}
+
+fn status_of(error: &NokhwaError) -> NokhwaStatus {
+    match error {
+        NokhwaError::DeviceDisconnected { .. } => NokhwaStatus::DeviceDisconnected,
+        NokhwaError::DeviceBusy { .. } => NokhwaStatus::DeviceBusy,
+        NokhwaError::PermissionDenied { .. } => NokhwaStatus::PermissionDenied,
+        NokhwaError::UnsupportedFormat { .. } => NokhwaStatus::UnsupportedFormat,
+        NokhwaError::Timeout(_) => NokhwaStatus::Timeout,
+        NokhwaError::UnsupportedOperationError(_) | NokhwaError::NotImplementedError(_) => {
+            NokhwaStatus::UnsupportedOperation
+        }
+        NokhwaError::StructureError { .. } => NokhwaStatus::InvalidArgument,
+        _ => NokhwaStatus::Error,
+    }
+}
+
+// runs `function`, turning errors and panics into a status and the last error
+fn guard(function: impl FnOnce() -> Result<(), NokhwaStatus>) -> NokhwaStatus {
+    match catch_unwind(AssertUnwindSafe(function)) {
+        Ok(Ok(())) => NokhwaStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(panic) => {
+            let message = match panic.downcast_ref::<&str>() {
+                Some(message) => (*message).to_string(),
+                None => match panic.downcast_ref::<String>() {
+                    Some(message) => message.clone(),
+                    None => "Unknown panic".to_string(),
+                },
+            };
+            set_last_error(&format!("nokhwa panicked: {}", message));
+            NokhwaStatus::Panic
+        }
+    }
+}
+
+fn fail(error: &NokhwaError) -> NokhwaStatus {
+    set_last_error(&error.to_string());
+    status_of(error)
+}
+
+fn null_pointer(name: &str) -> NokhwaStatus {
+    set_last_error(&format!("{} is null", name));
+    NokhwaStatus::NullPointer
+}
+
+fn invalid_argument(name: &str, value: u32) -> NokhwaStatus {
+    set_last_error(&format!("{} is not a valid {}", value, name));
+    NokhwaStatus::InvalidArgument
+}
+
+// the variants in discriminant order, so a `u32` from C can be looked up instead of transmuted
+fn variant<T: Copy>(variants: &[T], value: u32, name: &str) -> Result<T, NokhwaStatus> {
+    usize::try_from(value)
+        .ok()
+        .and_then(|position| variants.get(position).copied())
+        .ok_or_else(|| invalid_argument(name, value))
+}
+
+impl TryFrom<u32> for NokhwaBackend {
+    type Error = NokhwaStatus;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        variant(
+            &[
+                NokhwaBackend::Auto,
+                NokhwaBackend::AvFoundation,
+                NokhwaBackend::Video4Linux,
+                NokhwaBackend::UniversalVideoClass,
+                NokhwaBackend::MediaFoundation,
+                NokhwaBackend::OpenCv,
+                NokhwaBackend::GStreamer,
+            ],
+            value,
+            "NokhwaBackend",
+        )
+    }
+}
+
+impl TryFrom<u32> for NokhwaFrameFormat {
+    type Error = NokhwaStatus;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        variant(
+            &[
+                NokhwaFrameFormat::Mjpeg,
+                NokhwaFrameFormat::Yuyv,
+                NokhwaFrameFormat::BayerRggb8,
+                NokhwaFrameFormat::BayerGrbg8,
+                NokhwaFrameFormat::BayerGbrg8,
+                NokhwaFrameFormat::BayerBggr8,
+                NokhwaFrameFormat::BayerRggb10,
+                NokhwaFrameFormat::BayerGrbg10,
+                NokhwaFrameFormat::BayerGbrg10,
+                NokhwaFrameFormat::BayerBggr10,
+                NokhwaFrameFormat::BayerRggb12,
+                NokhwaFrameFormat::BayerGrbg12,
+                NokhwaFrameFormat::BayerGbrg12,
+                NokhwaFrameFormat::BayerBggr12,
+                NokhwaFrameFormat::Y16,
+                NokhwaFrameFormat::P010,
+                NokhwaFrameFormat::H264,
+                NokhwaFrameFormat::H265,
+                NokhwaFrameFormat::Z16,
+            ],
+            value,
+            "NokhwaFrameFormat",
+        )
+    }
+}
+
+impl TryFrom<u32> for NokhwaControl {
+    type Error = NokhwaStatus;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        variant(
+            &[
+                NokhwaControl::Brightness,
+                NokhwaControl::Contrast,
+                NokhwaControl::Hue,
+                NokhwaControl::Saturation,
+                NokhwaControl::Sharpness,
+                NokhwaControl::Gamma,
+                NokhwaControl::ColorEnable,
+                NokhwaControl::WhiteBalance,
+                NokhwaControl::BacklightComp,
+                NokhwaControl::Gain,
+                NokhwaControl::Pan,
+                NokhwaControl::Tilt,
+                NokhwaControl::Roll,
+                NokhwaControl::Zoom,
+                NokhwaControl::Exposure,
+                NokhwaControl::Iris,
+                NokhwaControl::Focus,
+                NokhwaControl::PowerLineFrequency,
+            ],
+            value,
+            "NokhwaControl",
+        )
+    }
+}
+
+fn to_backend(backend: NokhwaBackend) -> CaptureAPIBackend {
+    match backend {
+        NokhwaBackend::Auto => CaptureAPIBackend::Auto,
+        NokhwaBackend::AvFoundation => CaptureAPIBackend::AVFoundation,
+        NokhwaBackend::Video4Linux => CaptureAPIBackend::Video4Linux,
+        NokhwaBackend::UniversalVideoClass => CaptureAPIBackend::UniversalVideoClass,
+        NokhwaBackend::MediaFoundation => CaptureAPIBackend::MediaFoundation,
+        NokhwaBackend::OpenCv => CaptureAPIBackend::OpenCv,
+        NokhwaBackend::GStreamer => CaptureAPIBackend::GStreamer,
+    }
+}
+
+fn to_camera_format(format: NokhwaCameraFormat) -> Result<CameraFormat, NokhwaStatus> {
+    let frame_format = match NokhwaFrameFormat::try_from(format.format)? {
+        NokhwaFrameFormat::Mjpeg => FrameFormat::MJPEG,
+        NokhwaFrameFormat::Yuyv => FrameFormat::YUYV,
+        NokhwaFrameFormat::BayerRggb8 => FrameFormat::Bayer8(BayerPattern::RGGB),
+        NokhwaFrameFormat::BayerGrbg8 => FrameFormat::Bayer8(BayerPattern::GRBG),
+        NokhwaFrameFormat::BayerGbrg8 => FrameFormat::Bayer8(BayerPattern::GBRG),
+        NokhwaFrameFormat::BayerBggr8 => FrameFormat::Bayer8(BayerPattern::BGGR),
+        NokhwaFrameFormat::BayerRggb10 => FrameFormat::Bayer10(BayerPattern::RGGB),
+        NokhwaFrameFormat::BayerGrbg10 => FrameFormat::Bayer10(BayerPattern::GRBG),
+        NokhwaFrameFormat::BayerGbrg10 => FrameFormat::Bayer10(BayerPattern::GBRG),
+        NokhwaFrameFormat::BayerBggr10 => FrameFormat::Bayer10(BayerPattern::BGGR),
+        NokhwaFrameFormat::BayerRggb12 => FrameFormat::Bayer12(BayerPattern::RGGB),
+        NokhwaFrameFormat::BayerGrbg12 => FrameFormat::Bayer12(BayerPattern::GRBG),
+        NokhwaFrameFormat::BayerGbrg12 => FrameFormat::Bayer12(BayerPattern::GBRG),
+        NokhwaFrameFormat::BayerBggr12 => FrameFormat::Bayer12(BayerPattern::BGGR),
+        NokhwaFrameFormat::Y16 => FrameFormat::Y16,
+        NokhwaFrameFormat::P010 => FrameFormat::P010,
+        NokhwaFrameFormat::H264 => FrameFormat::H264,
+        NokhwaFrameFormat::H265 => FrameFormat::H265,
+        NokhwaFrameFormat::Z16 => FrameFormat::Z16,
+    };
+    Ok(CameraFormat::new_from(
+        format.width,
+        format.height,
+        frame_format,
+        format.frame_rate,
+    ))
+}
+
+fn from_camera_format(format: CameraFormat) -> NokhwaCameraFormat {
+    NokhwaCameraFormat {
+        width: format.width(),
+        height: format.height(),
+        frame_rate: format.frame_rate(),
+        format: (match format.format() {
+            FrameFormat::MJPEG => NokhwaFrameFormat::Mjpeg,
+            FrameFormat::YUYV => NokhwaFrameFormat::Yuyv,
+            FrameFormat::Bayer8(BayerPattern::RGGB) => NokhwaFrameFormat::BayerRggb8,
+            FrameFormat::Bayer8(BayerPattern::GRBG) => NokhwaFrameFormat::BayerGrbg8,
+            FrameFormat::Bayer8(BayerPattern::GBRG) => NokhwaFrameFormat::BayerGbrg8,
+            FrameFormat::Bayer8(BayerPattern::BGGR) => NokhwaFrameFormat::BayerBggr8,
+            FrameFormat::Bayer10(BayerPattern::RGGB) => NokhwaFrameFormat::BayerRggb10,
+            FrameFormat::Bayer10(BayerPattern::GRBG) => NokhwaFrameFormat::BayerGrbg10,
+            FrameFormat::Bayer10(BayerPattern::GBRG) => NokhwaFrameFormat::BayerGbrg10,
+            FrameFormat::Bayer10(BayerPattern::BGGR) => NokhwaFrameFormat::BayerBggr10,
+            FrameFormat::Bayer12(BayerPattern::RGGB) => NokhwaFrameFormat::BayerRggb12,
+            FrameFormat::Bayer12(BayerPattern::GRBG) => NokhwaFrameFormat::BayerGrbg12,
+            FrameFormat::Bayer12(BayerPattern::GBRG) => NokhwaFrameFormat::BayerGbrg12,
+            FrameFormat::Bayer12(BayerPattern::BGGR) => NokhwaFrameFormat::BayerBggr12,
+            FrameFormat::Y16 => NokhwaFrameFormat::Y16,
+            FrameFormat::P010 => NokhwaFrameFormat::P010,
+            FrameFormat::H264 => NokhwaFrameFormat::H264,
+            FrameFormat::H265 => NokhwaFrameFormat::H265,
+            FrameFormat::Z16 => NokhwaFrameFormat::Z16,
+        }) as u32,
+    }
+}
+
+fn to_control(control: NokhwaControl) -> KnownCameraControls {
+    match control {
+        NokhwaControl::Brightness => KnownCameraControls::Brightness,
+        NokhwaControl::Contrast => KnownCameraControls::Contrast,
+        NokhwaControl::Hue => KnownCameraControls::Hue,
+        NokhwaControl::Saturation => KnownCameraControls::Saturation,
+        NokhwaControl::Sharpness => KnownCameraControls::Sharpness,
+        NokhwaControl::Gamma => KnownCameraControls::Gamma,
+        NokhwaControl::ColorEnable => KnownCameraControls::ColorEnable,
+        NokhwaControl::WhiteBalance => KnownCameraControls::WhiteBalance,
+        NokhwaControl::BacklightComp => KnownCameraControls::BacklightComp,
+        NokhwaControl::Gain => KnownCameraControls::Gain,
+        NokhwaControl::Pan => KnownCameraControls::Pan,
+        NokhwaControl::Tilt => KnownCameraControls::Tilt,
+        NokhwaControl::Roll => KnownCameraControls::Roll,
+        NokhwaControl::Zoom => KnownCameraControls::Zoom,
+        NokhwaControl::Exposure => KnownCameraControls::Exposure,
+        NokhwaControl::Iris => KnownCameraControls::Iris,
+        NokhwaControl::Focus => KnownCameraControls::Focus,
+        NokhwaControl::PowerLineFrequency => KnownCameraControls::PowerLineFrequency,
+    }
+}
+
+fn from_control(control: KnownCameraControls) -> NokhwaControl {
+    match control {
+        KnownCameraControls::Brightness => NokhwaControl::Brightness,
+        KnownCameraControls::Contrast => NokhwaControl::Contrast,
+        KnownCameraControls::Hue => NokhwaControl::Hue,
+        KnownCameraControls::Saturation => NokhwaControl::Saturation,
+        KnownCameraControls::Sharpness => NokhwaControl::Sharpness,
+        KnownCameraControls::Gamma => NokhwaControl::Gamma,
+        KnownCameraControls::ColorEnable => NokhwaControl::ColorEnable,
+        KnownCameraControls::WhiteBalance => NokhwaControl::WhiteBalance,
+        KnownCameraControls::BacklightComp => NokhwaControl::BacklightComp,
+        KnownCameraControls::Gain => NokhwaControl::Gain,
+        KnownCameraControls::Pan => NokhwaControl::Pan,
+        KnownCameraControls::Tilt => NokhwaControl::Tilt,
+        KnownCameraControls::Roll => NokhwaControl::Roll,
+        KnownCameraControls::Zoom => NokhwaControl::Zoom,
+        KnownCameraControls::Exposure => NokhwaControl::Exposure,
+        KnownCameraControls::Iris => NokhwaControl::Iris,
+        KnownCameraControls::Focus => NokhwaControl::Focus,
+        KnownCameraControls::PowerLineFrequency => NokhwaControl::PowerLineFrequency,
+    }
+}
+
+fn from_control_info(control: &CameraControl) -> NokhwaControlInfo {
+    NokhwaControlInfo {
+        control: from_control(control.control()),
+        minimum: control.minimum_value(),
+        maximum: control.maximum_value(),
+        value: control.value(),
+        step: control.step(),
+        default_value: control.default(),
+        automatic: control.flag() == KnownCameraControlFlag::Automatic,
+        active: control.active(),
+    }
+}
+
+unsafe fn camera_mut<'a>(camera: *mut NokhwaCamera) -> Result<&'a mut Camera, NokhwaStatus> {
+    match camera.as_mut() {
+        Some(camera) => Ok(&mut camera.camera),
+        None => Err(null_pointer("camera")),
+    }
+}
+
+/// The version of `nokhwa-capi`, as a static NUL terminated string.
+#[no_mangle]
+pub extern "C" fn nokhwa_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// The message of the last error on this thread, or null if there was none. It stays valid until the next `nokhwa_` call on this thread fails.
+#[no_mangle]
+pub extern "C" fn nokhwa_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Lists the devices `backend` (a [`NokhwaBackend`]) can find into a new list written to `list`. Free it with [`nokhwa_device_list_free()`].
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_query_devices(
+    backend: u32,
+    list: *mut *mut NokhwaDeviceList,
+) -> NokhwaStatus {
+    guard(|| {
+        if list.is_null() {
+            return Err(null_pointer("list"));
+        }
+        let backend = NokhwaBackend::try_from(backend)?;
+        let devices = query_devices(to_backend(backend))
+            .map_err(|why| fail(&why))?
+            .iter()
+            .map(|info: &CameraInfo| Device {
+                index: *info.index(),
+                name: c_string(info.human_name()),
+                description: c_string(info.description()),
+                misc: c_string(info.misc()),
+            })
+            .collect();
+        *list = Box::into_raw(Box::new(NokhwaDeviceList { devices }));
+        Ok(())
+    })
+}
+
+/// How many devices are in `list`. A null `list` has none.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_len(list: *const NokhwaDeviceList) -> usize {
+    match list.as_ref() {
+        Some(list) => list.devices.len(),
+        None => 0,
+    }
+}
+
+unsafe fn device<'a>(list: *const NokhwaDeviceList, position: usize) -> Option<&'a Device> {
+    list.as_ref().and_then(|list| list.devices.get(position))
+}
+
+/// The index to pass to [`nokhwa_camera_open()`] of the device at `position` in `list`, or `SIZE_MAX` if there is none.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_index(
+    list: *const NokhwaDeviceList,
+    position: usize,
+) -> usize {
+    match device(list, position) {
+        Some(device) => device.index,
+        None => usize::MAX,
+    }
+}
+
+/// The name of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_name(
+    list: *const NokhwaDeviceList,
+    position: usize,
+) -> *const c_char {
+    match device(list, position) {
+        Some(device) => device.name.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// The description of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_description(
+    list: *const NokhwaDeviceList,
+    position: usize,
+) -> *const c_char {
+    match device(list, position) {
+        Some(device) => device.description.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// The backend specific extra information (e.g. the symbolic link on Media Foundation) of the device at `position` in `list`, or null if there is none. It is valid until the list is freed.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_misc(
+    list: *const NokhwaDeviceList,
+    position: usize,
+) -> *const c_char {
+    match device(list, position) {
+        Some(device) => device.misc.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Frees a list made by [`nokhwa_query_devices()`]. A null `list` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_device_list_free(list: *mut NokhwaDeviceList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+/// Opens the camera at `index` with `backend` (a [`NokhwaBackend`]) into a new camera written to `camera`. `format` may be null for the default format. Free it with [`nokhwa_camera_free()`].
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_open(
+    index: usize,
+    backend: u32,
+    format: *const NokhwaCameraFormat,
+    camera: *mut *mut NokhwaCamera,
+) -> NokhwaStatus {
+    guard(|| {
+        if camera.is_null() {
+            return Err(null_pointer("camera"));
+        }
+        let backend = NokhwaBackend::try_from(backend)?;
+        let format = match format.as_ref() {
+            Some(format) => Some(to_camera_format(*format)?),
+            None => None,
+        };
+        let opened = Camera::new(index, format, to_backend(backend)).map_err(|why| fail(&why))?;
+        *camera = Box::into_raw(Box::new(NokhwaCamera { camera: opened }));
+        Ok(())
+    })
+}
+
+/// Stops the stream and frees a camera made by [`nokhwa_camera_open()`]. A null `camera` is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_free(camera: *mut NokhwaCamera) {
+    if !camera.is_null() {
+        // a panic in a backend's drop must not unwind into C
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(camera))));
+    }
+}
+
+/// Writes the current format of `camera` to `format`.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_format(
+    camera: *mut NokhwaCamera,
+    format: *mut NokhwaCameraFormat,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        match format.as_mut() {
+            Some(format) => {
+                *format = from_camera_format(camera.camera_format());
+                Ok(())
+            }
+            None => Err(null_pointer("format")),
+        }
+    })
+}
+
+/// Sets the format of `camera` to `format`. If the stream is open, it is reopened.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_set_format(
+    camera: *mut NokhwaCamera,
+    format: *const NokhwaCameraFormat,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        let format = match format.as_ref() {
+            Some(format) => to_camera_format(*format)?,
+            None => return Err(null_pointer("format")),
+        };
+        camera.set_camera_format(format).map_err(|why| fail(&why))
+    })
+}
+
+/// Writes up to `capacity` formats `camera` supports to `formats`, and how many it supports to `count`. Pass a null `formats` to only get the count.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_compatible_formats(
+    camera: *mut NokhwaCamera,
+    formats: *mut NokhwaCameraFormat,
+    capacity: usize,
+    count: *mut usize,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        if count.is_null() {
+            return Err(null_pointer("count"));
+        }
+        let compatible = camera
+            .compatible_camera_formats()
+            .map_err(|why| fail(&why))?;
+        if !formats.is_null() {
+            for (position, format) in compatible.iter().take(capacity).enumerate() {
+                *formats.add(position) = from_camera_format(*format);
+            }
+        }
+        *count = compatible.len();
+        Ok(())
+    })
+}
+
+/// Opens the stream of `camera`.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_open_stream(camera: *mut NokhwaCamera) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        camera.open_stream().map_err(|why| fail(&why))
+    })
+}
+
+/// Stops the stream of `camera`.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_stop_stream(camera: *mut NokhwaCamera) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        camera.stop_stream().map_err(|why| fail(&why))
+    })
+}
+
+/// Captures a RGB888 frame (3 bytes per pixel, rows without padding) from `camera` into `buffer`, which holds `length` bytes.
+/// The size of the frame is written to `written`, and its resolution to `width` and `height` if they are not null.
+///
+/// If `buffer` is too small, this returns [`NokhwaStatus::BufferTooSmall`] with the needed size in `written` and the frame is lost.
+/// The size is `width * height * 3` of the current format, unless an output resolution or crop is set on the camera.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_frame_rgb(
+    camera: *mut NokhwaCamera,
+    buffer: *mut u8,
+    length: usize,
+    written: *mut usize,
+    width: *mut u32,
+    height: *mut u32,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        if buffer.is_null() {
+            return Err(null_pointer("buffer"));
+        }
+        if written.is_null() {
+            return Err(null_pointer("written"));
+        }
+        let frame = camera.frame().map_err(|why| fail(&why))?;
+        *written = frame.len();
+        if let Some(width) = width.as_mut() {
+            *width = frame.width();
+        }
+        if let Some(height) = height.as_mut() {
+            *height = frame.height();
+        }
+        if frame.len() > length {
+            set_last_error(&format!(
+                "Buffer of {} bytes is too small for a frame of {} bytes",
+                length,
+                frame.len()
+            ));
+            return Err(NokhwaStatus::BufferTooSmall);
+        }
+        ptr::copy_nonoverlapping(frame.as_ptr(), buffer, frame.len());
+        Ok(())
+    })
+}
+
+/// Writes up to `capacity` controls `camera` supports to `controls`, and how many it supports to `count`. Pass a null `controls` to only get the count.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_supported_controls(
+    camera: *mut NokhwaCamera,
+    controls: *mut NokhwaControl,
+    capacity: usize,
+    count: *mut usize,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        if count.is_null() {
+            return Err(null_pointer("count"));
+        }
+        let supported = camera
+            .supported_camera_controls()
+            .map_err(|why| fail(&why))?;
+        if !controls.is_null() {
+            for (position, control) in supported.iter().take(capacity).enumerate() {
+                *controls.add(position) = from_control(*control);
+            }
+        }
+        *count = supported.len();
+        Ok(())
+    })
+}
+
+/// Reads `control` (a [`NokhwaControl`]) of `camera` into `info`.
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_control(
+    camera: *mut NokhwaCamera,
+    control: u32,
+    info: *mut NokhwaControlInfo,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        if info.is_null() {
+            return Err(null_pointer("info"));
+        }
+        let control = NokhwaControl::try_from(control)?;
+        let current = camera
+            .camera_control(to_control(control))
+            .map_err(|why| fail(&why))?;
+        *info = from_control_info(&current);
+        Ok(())
+    })
+}
+
+/// Sets `control` (a [`NokhwaControl`]) of `camera` to `value`. If `value` is out of range or not aligned with the step, this returns [`NokhwaStatus::InvalidArgument`].
+#[no_mangle]
+pub unsafe extern "C" fn nokhwa_camera_set_control(
+    camera: *mut NokhwaCamera,
+    control: u32,
+    value: i32,
+) -> NokhwaStatus {
+    guard(|| {
+        let camera = camera_mut(camera)?;
+        let control = NokhwaControl::try_from(control)?;
+        let current = camera
+            .camera_control(to_control(control))
+            .map_err(|why| fail(&why))?;
+        let updated = current.with_value(value).map_err(|why| fail(&why))?;
+        camera.set_camera_control(updated).map_err(|why| fail(&why))
+    })
+}
@@ -0,0 +1,254 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{backend_priority, CaptureAPIBackend, NokhwaError};
+use std::fmt::{Display, Formatter};
+
+// every backend `nokhwa` can open itself, in the order of `CaptureAPIBackend`
+const BACKENDS: [CaptureAPIBackend; 6] = [
+    CaptureAPIBackend::AVFoundation,
+    CaptureAPIBackend::Video4Linux,
+    CaptureAPIBackend::UniversalVideoClass,
+    CaptureAPIBackend::MediaFoundation,
+    CaptureAPIBackend::OpenCv,
+    CaptureAPIBackend::GStreamer,
+];
+
+/// Whether a [`CaptureAPIBackend`] can be used on this machine, see [`supported_backends()`].
+///
+/// `compiled_in` says whether `nokhwa` was built with the backend's feature for this platform. `error` says why a backend cannot be used, if it cannot.
+#[derive(Clone, Debug)]
+pub struct BackendSupport {
+    backend: CaptureAPIBackend,
+    compiled_in: bool,
+    error: Option<NokhwaError>,
+}
+
+impl BackendSupport {
+    /// The backend this is about.
+    #[must_use]
+    pub fn backend(&self) -> CaptureAPIBackend {
+        self.backend
+    }
+
+    /// Whether `nokhwa` was built with this backend for the current platform.
+    #[must_use]
+    pub fn compiled_in(&self) -> bool {
+        self.compiled_in
+    }
+
+    /// Whether this backend is compiled in and passed its checks, so opening cameras with it can work.
+    #[must_use]
+    pub fn is_usable(&self) -> bool {
+        self.compiled_in && self.error.is_none()
+    }
+
+    /// Why this backend cannot be used, if it cannot. Show this to the user.
+    #[must_use]
+    pub fn error(&self) -> Option<&NokhwaError> {
+        self.error.as_ref()
+    }
+}
+
+impl Display for BackendSupport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            None => write!(f, "{}: usable", self.backend),
+            Some(why) => write!(f, "{}: not usable ({})", self.backend, why),
+        }
+    }
+}
+
+/// Checks every backend `nokhwa` knows, for whether it is compiled in and usable on this machine. Use this to validate a configuration before opening a [`Camera`](crate::Camera), and to tell the user why a backend is missing.
+///
+/// The checks are cheap, and do not open any device:
+/// - `Video4Linux`: Linux only, and there has to be at least one `/dev/video*` node.
+/// - Media Foundation: Windows only, and Media Foundation has to start up.
+/// - UVC: `libuvc` has to initialize (it needs access to USB).
+/// - `GStreamer`: `GStreamer` has to initialize.
+/// - `OpenCV`: Is usable if it is compiled in.
+/// - `AVFoundation`: Is not implemented yet, and never usable.
+/// # Quirks
+/// - A usable backend does not mean there is a camera, or that this program may use it. See [`query_devices()`](crate::query_devices) and [`camera_permission_status()`](crate::camera_permission_status).
+/// - [`CaptureAPIBackend::Auto`] and [`CaptureAPIBackend::Custom`] are not listed, see [`CaptureAPIBackend::available()`].
+#[must_use]
+pub fn supported_backends() -> Vec<BackendSupport> {
+    BACKENDS.iter().map(|backend| backend.support()).collect()
+}
+
+impl CaptureAPIBackend {
+    /// Checks whether this backend is compiled in and usable on this machine, see [`supported_backends()`].
+    ///
+    /// [`CaptureAPIBackend::Auto`] is usable if any backend in the [`backend_priority()`](crate::backend_priority) is. [`CaptureAPIBackend::Custom`] backends are not something `nokhwa` can open, so they are never usable.
+    #[must_use]
+    pub fn support(self) -> BackendSupport {
+        let (compiled, feature, platform) = match self {
+            CaptureAPIBackend::Auto => return auto_support(),
+            CaptureAPIBackend::Custom => {
+                return BackendSupport {
+                    backend: self,
+                    compiled_in: false,
+                    error: Some(NokhwaError::UnsupportedOperationError(self)),
+                }
+            }
+            CaptureAPIBackend::AVFoundation => {
+                return BackendSupport {
+                    backend: self,
+                    compiled_in: false,
+                    error: Some(NokhwaError::NotImplementedError(
+                        "AVFoundation is not implemented yet".to_string(),
+                    )),
+                }
+            }
+            CaptureAPIBackend::Video4Linux => {
+                (cfg!(feature = "input-v4l"), "input-v4l", Some("linux"))
+            }
+            CaptureAPIBackend::UniversalVideoClass => {
+                (cfg!(feature = "input-uvc"), "input-uvc", None)
+            }
+            CaptureAPIBackend::MediaFoundation => {
+                (cfg!(feature = "input-msmf"), "input-msmf", Some("windows"))
+            }
+            CaptureAPIBackend::OpenCv => (cfg!(feature = "input-opencv"), "input-opencv", None),
+            CaptureAPIBackend::GStreamer => (cfg!(feature = "input-gst"), "input-gst", None),
+        };
+
+        let error = if !compiled {
+            Some(NokhwaError::NotImplementedError(format!(
+                "nokhwa was built without the `{}` feature",
+                feature
+            )))
+        } else if platform.map_or(false, |platform| platform != std::env::consts::OS) {
+            Some(NokhwaError::NotImplementedError(format!(
+                "{} is not available on {}",
+                self,
+                std::env::consts::OS
+            )))
+        } else {
+            None
+        };
+        match error {
+            Some(why) => BackendSupport {
+                backend: self,
+                compiled_in: false,
+                error: Some(why),
+            },
+            None => BackendSupport {
+                backend: self,
+                compiled_in: true,
+                error: check_backend(self).err(),
+            },
+        }
+    }
+
+    /// Whether this backend is compiled in and usable on this machine. See [`support()`](CaptureAPIBackend::support) to know why it is not.
+    #[must_use]
+    pub fn available(self) -> bool {
+        self.support().is_usable()
+    }
+}
+
+// the first usable backend of the priority list is what `Auto` would use
+fn auto_support() -> BackendSupport {
+    let mut error = None;
+    for backend in backend_priority() {
+        let support = backend.support();
+        if support.is_usable() {
+            return BackendSupport {
+                backend: CaptureAPIBackend::Auto,
+                compiled_in: true,
+                error: None,
+            };
+        }
+        if error.is_none() {
+            error = support.error;
+        }
+    }
+    BackendSupport {
+        backend: CaptureAPIBackend::Auto,
+        compiled_in: false,
+        error: Some(error.unwrap_or_else(|| {
+            NokhwaError::NotImplementedError("nokhwa was built without any backend".to_string())
+        })),
+    }
+}
+
+fn check_backend(backend: CaptureAPIBackend) -> Result<(), NokhwaError> {
+    match backend {
+        CaptureAPIBackend::Video4Linux => check_v4l(),
+        CaptureAPIBackend::UniversalVideoClass => check_uvc(),
+        CaptureAPIBackend::MediaFoundation => check_msmf(),
+        CaptureAPIBackend::GStreamer => check_gstreamer(),
+        _ => Ok(()),
+    }
+}
+
+fn check_v4l() -> Result<(), NokhwaError> {
+    let has_nodes = std::fs::read_dir("/dev").map_or(false, |entries| {
+        entries
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("video"))
+    });
+    if has_nodes {
+        Ok(())
+    } else {
+        Err(NokhwaError::InitializeError {
+            backend: CaptureAPIBackend::Video4Linux,
+            error: "There are no /dev/video* nodes".to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "input-uvc")]
+fn check_uvc() -> Result<(), NokhwaError> {
+    match uvc::Context::new() {
+        Ok(_) => Ok(()),
+        Err(why) => Err(NokhwaError::InitializeError {
+            backend: CaptureAPIBackend::UniversalVideoClass,
+            error: why.to_string(),
+        }),
+    }
+}
+
+#[cfg(not(feature = "input-uvc"))]
+#[allow(clippy::unnecessary_wraps)]
+fn check_uvc() -> Result<(), NokhwaError> {
+    Ok(())
+}
+
+#[cfg(feature = "input-msmf")]
+fn check_msmf() -> Result<(), NokhwaError> {
+    match nokhwa_bindings_windows::wmf::initialize_mf() {
+        Ok(()) => Ok(()),
+        Err(why) => Err(NokhwaError::InitializeError {
+            backend: CaptureAPIBackend::MediaFoundation,
+            error: why.to_string(),
+        }),
+    }
+}
+
+#[cfg(not(feature = "input-msmf"))]
+#[allow(clippy::unnecessary_wraps)]
+fn check_msmf() -> Result<(), NokhwaError> {
+    Ok(())
+}
+
+#[cfg(feature = "input-gst")]
+fn check_gstreamer() -> Result<(), NokhwaError> {
+    match gstreamer::init() {
+        Ok(()) => Ok(()),
+        Err(why) => Err(NokhwaError::InitializeError {
+            backend: CaptureAPIBackend::GStreamer,
+            error: why.to_string(),
+        }),
+    }
+}
+
+#[cfg(not(feature = "input-gst"))]
+#[allow(clippy::unnecessary_wraps)]
+fn check_gstreamer() -> Result<(), NokhwaError> {
+    Ok(())
+}
@@ -5,24 +5,28 @@
  */
 
 use crate::{
-    mjpeg_to_rgb888, yuyv422_to_rgb888, CameraControl, CameraFormat, CameraInfo, CaptureAPIBackend,
-    CaptureBackendTrait, FrameFormat, KnownCameraControls, NokhwaError, Resolution,
+    mjpeg_to_rgb888, yuyv422_to_rgb888_strided, CameraControl, CameraFormat, CameraInfo,
+    CaptureAPIBackend, CaptureBackendTrait, ColorSpace, FrameFormat, KnownCameraControls,
+    NokhwaError, RawFrame, RawHandle, Resolution,
 };
-use flume::Receiver;
-use glib::Quark;
+use flume::{Receiver, RecvTimeoutError};
+use glib::{ObjectType, Quark};
 use gstreamer::{
     element_error,
     glib::Cast,
-    prelude::{DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual, ElementExt, GstBinExt},
-    Bin, Caps, ClockTime, DeviceMonitor, Element, FlowError, FlowSuccess, MessageView,
-    ResourceError, State,
+    prelude::{
+        DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual, ElementExt, GstBinExt, ObjectExt,
+        PadExt,
+    },
+    Bin, Caps, ClockTime, CoreError, DeviceMonitor, Element, FlowError, FlowSuccess, MessageView,
+    ResourceError, State, StreamError,
 };
 use gstreamer_app::{AppSink, AppSinkCallbacks};
-use gstreamer_video::{VideoFormat, VideoInfo};
+use gstreamer_video::{VideoColorMatrix, VideoColorRange, VideoFormat, VideoInfo};
 use image::{ImageBuffer, Rgb};
 use regex::Regex;
 use std::any::Any;
-use std::{borrow::Cow, collections::HashMap, str::FromStr};
+use std::{borrow::Cow, collections::HashMap, convert::TryFrom, str::FromStr, time::Duration};
 
 type PipelineGenRet = (Element, AppSink, Receiver<ImageBuffer<Rgb<u8>, Vec<u8>>>);
 
@@ -31,6 +35,10 @@ type PipelineGenRet = (Element, AppSink, Receiver<ImageBuffer<Rgb<u8>, Vec<u8>>>
 /// # Quirks
 /// - `Drop`-ing this may cause a `panic`.
 /// - Setting controls is not supported.
+/// - The [`ColorSpace`] is read from the colorimetry of every frame's caps as it is decoded. [`color_space()`](CaptureBackendTrait::color_space) does not report it, and [`set_color_space()`](CaptureBackendTrait::set_color_space) is not supported.
+/// - [`set_camera_format()`](CaptureBackendTrait::set_camera_format) on an open stream renegotiates the caps of the running pipeline if the source advertises the new format, and only rebuilds the pipeline otherwise.
+///   If the device rejects the new caps anyway, the error shows up on the next frame call.
+/// - Frames are decoded in the pipeline, so there are no undecoded frames: [`undecoded_frame()`](CaptureBackendTrait::undecoded_frame) is not supported, and a [`FrameDecoder`](crate::FrameDecoder) set on a [`Camera`](crate::Camera) is not used.
 pub struct GStreamerCaptureDevice {
     pipeline: Element,
     app_sink: AppSink,
@@ -99,15 +107,14 @@ impl GStreamerCaptureDevice {
             };
             device_monitor.stop();
             let caps = device.caps();
-            (
-                CameraInfo::new(
-                    DeviceExt::display_name(&device).to_string(),
-                    DeviceExt::device_class(&device).to_string(),
-                    "".to_string(),
-                    index,
-                ),
-                caps,
-            )
+            let mut camera_info = CameraInfo::new(
+                DeviceExt::display_name(&device).to_string(),
+                DeviceExt::device_class(&device).to_string(),
+                "".to_string(),
+                index,
+            );
+            camera_info.guess_stream_kind(&[]);
+            (camera_info, caps)
         };
 
         let (pipeline, app_sink, receiver) = generate_pipeline(camera_format, index)?;
@@ -131,6 +138,96 @@ impl GStreamerCaptureDevice {
         let cam_fmt = CameraFormat::new(Resolution::new(width, height), FrameFormat::MJPEG, fps);
         GStreamerCaptureDevice::new(index, Some(cam_fmt))
     }
+
+    /// Returns the underlying pipeline as a raw `GstElement` pointer, for `GStreamer` operations `nokhwa` does not cover. <br>
+    /// For safe access, the pipeline can also be used through `glib`'s `ObjectType` with [`from_glib_none()`](glib::translate::from_glib_none), which adds a reference.
+    /// # Safety
+    /// No reference is added to the pointer, it is only valid for as long as this device is alive. Do not unref it.
+    /// Changing the pipeline state or caps behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports.
+    #[must_use]
+    pub unsafe fn raw_handle(&self) -> *mut gstreamer::ffi::GstElement {
+        self.pipeline.as_ptr()
+    }
+
+    // Swaps the caps of the running pipeline to `new_fmt`, without tearing it down. Returns `false` if the pipeline
+    // has to be rebuilt instead (the source does not advertise the format, or the elements could not be found).
+    fn renegotiate(&mut self, new_fmt: CameraFormat) -> bool {
+        let caps = match format_caps(new_fmt).map(|caps| Caps::from_str(&caps)) {
+            Ok(Ok(caps)) => caps,
+            _ => return false,
+        };
+        let bin = match self.pipeline.clone().dynamic_cast::<Bin>() {
+            Ok(bin) => bin,
+            Err(_) => return false,
+        };
+        let (source, capsfilter) = match (bin.by_name("source"), bin.by_name("capsfilter")) {
+            (Some(source), Some(capsfilter)) => (source, capsfilter),
+            _ => return false,
+        };
+        let supported = match source.static_pad("src") {
+            Some(pad) => pad.query_caps(None).can_intersect(&caps),
+            None => false,
+        };
+        if !supported || capsfilter.set_property("caps", &caps).is_err() {
+            return false;
+        }
+        // anything still in the channel was captured with the old format
+        for _ in self.receiver.try_iter() {}
+        true
+    }
+
+    // Surfaces any end-of-stream or error message the pipeline has posted since the last frame.
+    fn check_bus(&self) -> Result<(), NokhwaError> {
+        let bus = match self.pipeline.bus() {
+            Some(bus) => bus,
+            None => {
+                return Err(NokhwaError::ReadFrameError(
+                    "The pipeline has no bus!".to_string(),
+                ))
+            }
+        };
+
+        if let Some(message) = bus.timed_pop(ClockTime::from_seconds(0)) {
+            match message.view() {
+                MessageView::Eos(..) => {
+                    return Err(NokhwaError::ReadFrameError("Stream is ended!".to_string()))
+                }
+                MessageView::Error(err) => return Err(self.bus_error(&err.error())),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // an unplugged camera fails to read, negotiation failing means the format does not work
+    fn bus_error(&self, error: &glib::Error) -> NokhwaError {
+        let device = self.camera_info.index().to_string();
+        let message = error.to_string();
+        match error.kind::<ResourceError>() {
+            Some(ResourceError::NotFound | ResourceError::Read) => {
+                return NokhwaError::DeviceDisconnected {
+                    device,
+                    error: message,
+                }
+            }
+            Some(ResourceError::Busy) => {
+                return NokhwaError::DeviceBusy {
+                    device,
+                    error: message,
+                }
+            }
+            _ => {}
+        }
+        if matches!(error.kind::<StreamError>(), Some(StreamError::Format))
+            || matches!(error.kind::<CoreError>(), Some(CoreError::Negotiation))
+        {
+            return NokhwaError::UnsupportedFormat {
+                format: self.camera_format.to_string(),
+                error: message,
+            };
+        }
+        NokhwaError::ReadFrameError(format!("Bus error: {}", message))
+    }
 }
 
 impl CaptureBackendTrait for GStreamerCaptureDevice {
@@ -147,6 +244,11 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if self.is_stream_open() && self.renegotiate(new_fmt) {
+            self.camera_format = new_fmt;
+            return Ok(());
+        }
+
         let mut reopen = false;
         if self.is_stream_open() {
             self.stop_stream()?;
@@ -351,6 +453,15 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
                                     .insert(Resolution::new(width as u32, height as u32), fps_vec);
                             }
                         }
+                        // not supported by this backend, so there are none
+                        FrameFormat::Bayer8(_)
+                        | FrameFormat::Bayer10(_)
+                        | FrameFormat::Bayer12(_)
+                        | FrameFormat::Y16
+                        | FrameFormat::Z16
+                        | FrameFormat::P010
+                        | FrameFormat::H264
+                        | FrameFormat::H265 => {}
                     }
                 }
             }
@@ -463,6 +574,8 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
 
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
         if let Err(why) = self.pipeline.set_state(State::Playing) {
+            // the pipeline posts why it failed on the bus
+            self.check_bus()?;
             return Err(NokhwaError::OpenStreamError(format!(
                 "Failed to set appsink to playing: {}",
                 why.to_string()
@@ -491,7 +604,11 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
         let cam_fmt = self.camera_format;
         let image_data = self.frame_raw()?;
         let imagebuf =
-            match ImageBuffer::from_vec(cam_fmt.width(), cam_fmt.height(), image_data.to_vec()) {
+            match ImageBuffer::from_vec(
+                cam_fmt.width(),
+                cam_fmt.height(),
+                crate::buffer_pool::pooled_copy(&image_data),
+            ) {
                 Some(buf) => {
                     let rgbbuf: ImageBuffer<Rgb<u8>, Vec<u8>> = buf;
                     rgbbuf
@@ -505,29 +622,7 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        let bus = match self.pipeline.bus() {
-            Some(bus) => bus,
-            None => {
-                return Err(NokhwaError::ReadFrameError(
-                    "The pipeline has no bus!".to_string(),
-                ))
-            }
-        };
-
-        if let Some(message) = bus.timed_pop(ClockTime::from_seconds(0)) {
-            match message.view() {
-                MessageView::Eos(..) => {
-                    return Err(NokhwaError::ReadFrameError("Stream is ended!".to_string()))
-                }
-                MessageView::Error(err) => {
-                    return Err(NokhwaError::ReadFrameError(format!(
-                        "Bus error: {}",
-                        err.error().to_string()
-                    )));
-                }
-                _ => {}
-            }
-        }
+        self.check_bus()?;
 
         match self.receiver.recv() {
             Ok(msg) => Ok(Cow::from(msg.to_vec())),
@@ -540,6 +635,44 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
         }
     }
 
+    fn frame_with_deadline(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        self.check_bus()?;
+
+        // skip anything stale that piled up in the channel, we only want the freshest frame
+        if let Some(frame) = self.receiver.try_iter().last() {
+            return Ok(frame);
+        }
+
+        match self.receiver.recv_timeout(deadline) {
+            Ok(frame) => Ok(frame),
+            Err(RecvTimeoutError::Timeout) => Err(NokhwaError::Timeout(deadline)),
+            Err(why) => Err(NokhwaError::ReadFrameError(format!(
+                "Receiver Error: {}",
+                why.to_string()
+            ))),
+        }
+    }
+
+    fn frame_raw_with_deadline(&mut self, deadline: Duration) -> Result<Cow<[u8]>, NokhwaError> {
+        self.check_bus()?;
+
+        match self.receiver.recv_timeout(deadline) {
+            Ok(msg) => Ok(Cow::from(msg.to_vec())),
+            Err(RecvTimeoutError::Timeout) => Err(NokhwaError::Timeout(deadline)),
+            Err(why) => Err(NokhwaError::ReadFrameError(format!(
+                "Receiver Error: {}",
+                why.to_string()
+            ))),
+        }
+    }
+
+    fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
         if let Err(why) = self.pipeline.set_state(State::Null) {
             return Err(NokhwaError::StreamShutdownError(format!(
@@ -549,6 +682,12 @@ impl CaptureBackendTrait for GStreamerCaptureDevice {
         }
         Ok(())
     }
+
+    unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        Some(RawHandle::GStreamer(
+            GStreamerCaptureDevice::raw_handle(self).cast(),
+        ))
+    }
 }
 
 impl Drop for GStreamerCaptureDevice {
@@ -557,60 +696,80 @@ impl Drop for GStreamerCaptureDevice {
     }
 }
 
-#[cfg(target_os = "macos")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
-    match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("autovideosrc location=/dev/video{} ! image/jpeg,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("autovideosrc location=/dev/video{} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
+// the color space of the negotiated colorimetry, BT.601 limited range where it is unknown
+fn colorimetry_color_space(video_info: &VideoInfo) -> ColorSpace {
+    let colorimetry = video_info.colorimetry();
+    let bt709 = colorimetry.matrix() == VideoColorMatrix::Bt709;
+    let full_range = colorimetry.range() == VideoColorRange::Range0_255;
+    match (bt709, full_range) {
+        (false, false) => ColorSpace::Bt601Limited,
+        (false, true) => ColorSpace::Bt601Full,
+        (true, false) => ColorSpace::Bt709Limited,
+        (true, true) => ColorSpace::Bt709Full,
     }
 }
 
-#[cfg(target_os = "linux")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
+fn format_caps(camera_format: CameraFormat) -> Result<String, NokhwaError> {
     match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("v4l2src device=/dev/video{} ! image/jpeg, width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("v4l2src device=/dev/video{} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
+        FrameFormat::MJPEG => Ok(format!(
+            "image/jpeg,width={},height={},framerate={}/1",
+            camera_format.width(),
+            camera_format.height(),
+            camera_format.frame_rate()
+        )),
+        FrameFormat::YUYV => Ok(format!(
+            "video/x-raw,format=YUY2,width={},height={},framerate={}/1",
+            camera_format.width(),
+            camera_format.height(),
+            camera_format.frame_rate()
+        )),
+        FrameFormat::Bayer8(_)
+        | FrameFormat::Bayer10(_)
+        | FrameFormat::Bayer12(_)
+        | FrameFormat::Y16
+        | FrameFormat::Z16
+        | FrameFormat::P010
+        | FrameFormat::H264
+        | FrameFormat::H265 => Err(NokhwaError::UnsupportedFormat {
+            format: camera_format.to_string(),
+            error: "The GStreamer backend only supports MJPEG and YUYV".to_string(),
+        }),
     }
 }
 
+// The source and capsfilter are named, so that `renegotiate()` can find them.
+#[cfg(target_os = "macos")]
+fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> Result<String, NokhwaError> {
+    Ok(format!("autovideosrc name=source location=/dev/video{} ! capsfilter name=capsfilter caps=\"{}\" ! appsink name=appsink async=false sync=false", device, format_caps(camera_format)?))
+}
+
+#[cfg(target_os = "linux")]
+fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> Result<String, NokhwaError> {
+    Ok(format!("v4l2src name=source device=/dev/video{} ! capsfilter name=capsfilter caps=\"{}\" ! appsink name=appsink async=false sync=false", device, format_caps(camera_format)?))
+}
+
 #[cfg(target_os = "windows")]
-fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> String {
-    match camera_format.format() {
-        FrameFormat::MJPEG => {
-            format!("ksvideosrc device_index={} ! image/jpeg, width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-        FrameFormat::YUYV => {
-            format!("ksvideosrc device_index={} ! video/x-raw,format=YUY2,width={},height={},framerate={}/1 ! appsink name=appsink async=false sync=false", device, camera_format.width(), camera_format.height(), camera_format.frame_rate())
-        }
-    }
+fn webcam_pipeline(device: &str, camera_format: CameraFormat) -> Result<String, NokhwaError> {
+    Ok(format!("ksvideosrc name=source device_index={} ! capsfilter name=capsfilter caps=\"{}\" ! appsink name=appsink async=false sync=false", device, format_caps(camera_format)?))
 }
 
 #[allow(clippy::too_many_lines)]
 #[allow(clippy::let_and_return)]
 fn generate_pipeline(fmt: CameraFormat, index: usize) -> Result<PipelineGenRet, NokhwaError> {
-    let pipeline =
-        match gstreamer::parse_launch(webcam_pipeline(format!("{}", index).as_str(), fmt).as_str())
-        {
-            Ok(p) => p,
-            Err(why) => {
-                return Err(NokhwaError::OpenDeviceError(
-                    index.to_string(),
-                    format!(
-                        "Failed to open pipeline with args {}: {}",
-                        webcam_pipeline(format!("{}", index).as_str(), fmt),
-                        why.to_string()
-                    ),
-                ))
-            }
-        };
+    let pipeline_description = webcam_pipeline(format!("{}", index).as_str(), fmt)?;
+    let pipeline = match gstreamer::parse_launch(pipeline_description.as_str()) {
+        Ok(p) => p,
+        Err(why) => {
+            return Err(NokhwaError::OpenDeviceError(
+                index.to_string(),
+                format!(
+                    "Failed to open pipeline with args {}: {}",
+                    pipeline_description,
+                    why.to_string()
+                ),
+            ))
+        }
+    };
 
     let sink = match pipeline
         .clone()
@@ -699,7 +858,18 @@ fn generate_pipeline(fmt: CameraFormat, index: usize) -> Result<PipelineGenRet,
 
                 let image_buffer = match video_info.format() {
                     VideoFormat::Yuy2 => {
-                        let mut decoded_buffer = match yuyv422_to_rgb888(&buffer_map) {
+                        // rows may be padded, e.g. to 4 bytes
+                        let stride = video_info
+                            .stride()
+                            .first()
+                            .and_then(|stride| usize::try_from(*stride).ok())
+                            .unwrap_or(video_info.width() as usize * 2);
+                        let mut decoded_buffer = match yuyv422_to_rgb888_strided(
+                            &buffer_map,
+                            Resolution::new(video_info.width(), video_info.height()),
+                            stride,
+                            colorimetry_color_space(&video_info),
+                        ) {
                             Ok(buf) => buf,
                             Err(why) => {
                                 element_error!(
@@ -0,0 +1,349 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    CameraControl, CameraFormat, CameraInfo, CaptureAPIBackend, CaptureBackendTrait, ColorSpace,
+    DefaultDecoder, FrameDecoder, FrameFormat, KnownCameraControls, NokhwaError, Resolution,
+};
+use image::{ImageBuffer, Rgb};
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+};
+
+/// A fake capture device, for testing code that consumes cameras without a camera. <br>
+/// Use it with [`Camera::from_backend()`](crate::Camera::from_backend), or directly as a [`CaptureBackendTrait`].
+///
+/// Frames queued with [`push_frame()`](MockCaptureDevice::push_frame) are returned first, in order. Once the queue is empty, a YUYV test pattern
+/// (a gradient that shifts by one pixel every frame) is generated instead.
+/// # Quirks
+/// - [`backend()`](CaptureBackendTrait::backend) returns [`CaptureAPIBackend::Custom`].
+/// - Frames are only returned while the stream is open, like a real device.
+/// - Queued frames are not checked against the current [`CameraFormat`]. If you push a MJPEG frame, set the format to MJPEG.
+/// - Without queued frames, a MJPEG format will error on every frame.
+/// - The raw camera control functions are not supported.
+#[derive(Clone, Debug)]
+pub struct MockCaptureDevice {
+    camera_info: CameraInfo,
+    camera_format: CameraFormat,
+    compatible_formats: Vec<CameraFormat>,
+    controls: HashMap<KnownCameraControls, CameraControl>,
+    frames: VecDeque<Vec<u8>>,
+    failures: VecDeque<NokhwaError>,
+    stream_open: bool,
+    frames_served: u64,
+    color_space: Option<ColorSpace>,
+}
+
+impl MockCaptureDevice {
+    /// Creates a new mock device at `index`. If `camera_format` is `None`, it will use a 640x480@15 FPS, YUYV [`CameraFormat`]. <br>
+    /// The device will only claim to support its initial format, see [`set_compatible_formats()`](MockCaptureDevice::set_compatible_formats).
+    #[must_use]
+    pub fn new(index: usize, camera_format: Option<CameraFormat>) -> Self {
+        let camera_format = camera_format
+            .unwrap_or_else(|| CameraFormat::new(Resolution::new(640, 480), FrameFormat::YUYV, 15));
+        MockCaptureDevice {
+            camera_info: CameraInfo::new(
+                "Mock Camera".to_string(),
+                "A fake camera for testing".to_string(),
+                "".to_string(),
+                index,
+            ),
+            camera_format,
+            compatible_formats: vec![camera_format],
+            controls: HashMap::new(),
+            frames: VecDeque::new(),
+            failures: VecDeque::new(),
+            stream_open: false,
+            frames_served: 0,
+            color_space: None,
+        }
+    }
+
+    /// Sets the [`CameraInfo`] this device reports.
+    pub fn set_camera_info(&mut self, camera_info: CameraInfo) {
+        self.camera_info = camera_info;
+    }
+
+    /// Sets the formats this device claims to support. [`set_camera_format()`](CaptureBackendTrait::set_camera_format) will reject anything else.
+    pub fn set_compatible_formats(&mut self, formats: Vec<CameraFormat>) {
+        self.compatible_formats = formats;
+    }
+
+    /// Adds (or replaces) a control this device supports.
+    pub fn add_control(&mut self, control: CameraControl) {
+        self.controls.insert(control.control(), control);
+    }
+
+    /// Queues a raw frame (in the current [`FrameFormat`]) to be returned by the next frame call.
+    pub fn push_frame(&mut self, frame: Vec<u8>) {
+        self.frames.push_back(frame);
+    }
+
+    /// Queues an error to be returned by the next frame call instead of a frame.
+    pub fn push_failure(&mut self, error: NokhwaError) {
+        self.failures.push_back(error);
+    }
+
+    /// The amount of frames that have been returned so far.
+    #[must_use]
+    pub fn frames_served(&self) -> u64 {
+        self.frames_served
+    }
+
+    fn format_supported(&self, format: CameraFormat) -> bool {
+        self.compatible_formats.contains(&format)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_pattern(&self) -> Vec<u8> {
+        let width = self.camera_format.width() as usize;
+        let height = self.camera_format.height() as usize;
+        let shift = (self.frames_served % 256) as usize;
+        let luma = |x: usize| ((x + shift) % 256) as u8;
+        let mut frame = Vec::with_capacity(width * height * 2);
+        for y in 0..height {
+            let chroma = (y % 256) as u8;
+            for x in (0..width).step_by(2) {
+                frame.extend_from_slice(&[luma(x), chroma, luma(x + 1), 255 - chroma]);
+            }
+        }
+        frame
+    }
+}
+
+impl CaptureBackendTrait for MockCaptureDevice {
+    fn backend(&self) -> CaptureAPIBackend {
+        CaptureAPIBackend::Custom
+    }
+
+    fn camera_info(&self) -> CameraInfo {
+        self.camera_info.clone()
+    }
+
+    fn camera_format(&self) -> CameraFormat {
+        self.camera_format
+    }
+
+    fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        if !self.format_supported(new_fmt) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "CameraFormat".to_string(),
+                value: new_fmt.to_string(),
+                error: "Not a compatible format".to_string(),
+            });
+        }
+        self.camera_format = new_fmt;
+        Ok(())
+    }
+
+    fn compatible_list_by_resolution(
+        &mut self,
+        fourcc: FrameFormat,
+    ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
+        let mut resolution_map: HashMap<Resolution, Vec<u32>> = HashMap::new();
+        for format in &self.compatible_formats {
+            if format.format() == fourcc {
+                resolution_map
+                    .entry(format.resolution())
+                    .or_default()
+                    .push(format.frame_rate());
+            }
+        }
+        Ok(resolution_map)
+    }
+
+    fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
+        let mut fourccs: Vec<FrameFormat> = self
+            .compatible_formats
+            .iter()
+            .map(CameraFormat::format)
+            .collect();
+        fourccs.sort();
+        fourccs.dedup();
+        Ok(fourccs)
+    }
+
+    fn resolution(&self) -> Resolution {
+        self.camera_format.resolution()
+    }
+
+    fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format;
+        new_fmt.set_resolution(new_res);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_rate(&self) -> u32 {
+        self.camera_format.frame_rate()
+    }
+
+    fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format;
+        new_fmt.set_frame_rate(new_fps);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn frame_format(&self) -> FrameFormat {
+        self.camera_format.format()
+    }
+
+    fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
+        let mut new_fmt = self.camera_format;
+        new_fmt.set_format(fourcc);
+        self.set_camera_format(new_fmt)
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space.unwrap_or_default()
+    }
+
+    fn set_color_space(&mut self, color_space: Option<ColorSpace>) -> Result<(), NokhwaError> {
+        self.color_space = color_space;
+        Ok(())
+    }
+
+    fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControls>, NokhwaError> {
+        let mut controls: Vec<KnownCameraControls> = self.controls.keys().copied().collect();
+        controls.sort();
+        Ok(controls)
+    }
+
+    fn camera_control(&self, control: KnownCameraControls) -> Result<CameraControl, NokhwaError> {
+        match self.controls.get(&control) {
+            Some(ctrl) => Ok(*ctrl),
+            None => Err(NokhwaError::GetPropertyError {
+                property: control.to_string(),
+                error: "Not supported".to_string(),
+            }),
+        }
+    }
+
+    fn set_camera_control(&mut self, control: CameraControl) -> Result<(), NokhwaError> {
+        match self.controls.get_mut(&control.control()) {
+            Some(ctrl) => {
+                *ctrl = control;
+                Ok(())
+            }
+            None => Err(NokhwaError::SetPropertyError {
+                property: control.control().to_string(),
+                value: control.value().to_string(),
+                error: "Not supported".to_string(),
+            }),
+        }
+    }
+
+    fn raw_supported_camera_controls(&self) -> Result<Vec<Box<dyn Any>>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(
+            CaptureAPIBackend::Custom,
+        ))
+    }
+
+    fn raw_camera_control(&self, _control: &dyn Any) -> Result<Box<dyn Any>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(
+            CaptureAPIBackend::Custom,
+        ))
+    }
+
+    fn set_raw_camera_control(
+        &mut self,
+        _control: &dyn Any,
+        _value: &dyn Any,
+    ) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(
+            CaptureAPIBackend::Custom,
+        ))
+    }
+
+    fn open_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stream_open = true;
+        Ok(())
+    }
+
+    fn is_stream_open(&self) -> bool {
+        self.stream_open
+    }
+
+    fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let frame = self.undecoded_frame()?;
+        DefaultDecoder.decode_rgb(&frame)
+    }
+
+    fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        if !self.stream_open {
+            return Err(NokhwaError::ReadFrameError(
+                "Stream not initialized! Please call \"open_stream()\" first!".to_string(),
+            ));
+        }
+        if let Some(why) = self.failures.pop_front() {
+            return Err(why);
+        }
+
+        let frame = match self.frames.pop_front() {
+            Some(frame) => frame,
+            None => match self.camera_format.format() {
+                FrameFormat::YUYV => self.test_pattern(),
+                format => {
+                    return Err(NokhwaError::ReadFrameError(format!(
+                        "No {} frames queued, use \"push_frame()\"!",
+                        format
+                    )))
+                }
+            },
+        };
+        self.frames_served += 1;
+        Ok(Cow::from(frame))
+    }
+
+    fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.stream_open = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockCaptureDevice;
+    use crate::{Camera, CameraFormat, FrameFormat, Resolution, Rotation, RotationHandling};
+
+    #[test]
+    fn camera_decodes_queued_frame() {
+        let format = CameraFormat::new(Resolution::new(4, 2), FrameFormat::YUYV, 30);
+        let mut device = MockCaptureDevice::new(0, Some(format));
+        // black, white, black, white on both rows (BT.601 limited range, neutral chroma)
+        device.push_frame([16, 128, 235, 128].repeat(4));
+        let mut camera = Camera::from_backend(Box::new(device));
+
+        camera.open_stream().unwrap();
+        let frame = camera.frame().unwrap();
+
+        assert_eq!((frame.width(), frame.height()), (4, 2));
+        let row = [0, 0, 0, 255, 255, 255].repeat(2);
+        assert_eq!(frame.into_raw(), row.repeat(2));
+    }
+
+    #[test]
+    fn metadata_rotation_reaches_sinks() {
+        let format = CameraFormat::new(Resolution::new(4, 2), FrameFormat::YUYV, 30);
+        let mut device = MockCaptureDevice::new(0, Some(format));
+        for _ in 0..2 {
+            device.push_frame([16, 128, 235, 128].repeat(4));
+        }
+        let mut camera = Camera::from_backend(Box::new(device));
+        camera.set_rotation(Rotation::Rotate90);
+        camera.set_rotation_handling(RotationHandling::Metadata);
+        camera.open_stream().unwrap();
+
+        let (payload, rotation) = camera.frame_for_sink_with_rotation().unwrap();
+        assert_eq!(payload.resolution(), Resolution::new(4, 2));
+        assert_eq!(rotation, Rotation::Rotate90);
+
+        // sinks without a rotation flag get upright pixels
+        let payload = camera.frame_for_sink().unwrap();
+        assert_eq!(payload.resolution(), Resolution::new(2, 4));
+    }
+}
@@ -24,3 +24,7 @@ pub use gst_backend::GStreamerCaptureDevice;
 mod opencv_backend;
 #[cfg(feature = "input-opencv")]
 pub use opencv_backend::OpenCvCaptureDevice;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::MockCaptureDevice;
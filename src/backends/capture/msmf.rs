@@ -5,13 +5,15 @@
  */
 
 use crate::{
-    all_known_camera_controls, mjpeg_to_rgb888, yuyv422_to_rgb888, CameraControl, CameraFormat,
-    CameraInfo, CaptureAPIBackend, CaptureBackendTrait, FrameFormat, KnownCameraControlFlag,
-    KnownCameraControls, NokhwaError, Resolution,
+    all_known_camera_controls, AccessMode, CameraControl, CameraFormat, CameraInfo, CameraProfile,
+    CaptureAPIBackend, CaptureBackendTrait, ColorSpace, DefaultDecoder, FrameDecoder, FrameFormat,
+    KnownCameraControlFlag, KnownCameraControls, NokhwaError, RawFrame, RawHandle, Resolution,
 };
 use image::{ImageBuffer, Rgb};
-use nokhwa_bindings_windows::{wmf::MediaFoundationDevice, MFControl, MediaFoundationControls};
-use std::{any::Any, borrow::Cow, collections::HashMap};
+use nokhwa_bindings_windows::{
+    wmf::MediaFoundationDevice, MFCameraFormat, MFControl, MFFrameFormat, MediaFoundationControls,
+};
+use std::{any::Any, borrow::Cow, collections::HashMap, convert::TryFrom, ffi::c_void};
 
 /// The backend that deals with Media Foundation on Windows.
 /// To see what this does, please see [`CaptureBackendTrait`].
@@ -23,8 +25,18 @@ use std::{any::Any, borrow::Cow, collections::HashMap};
 /// - [`raw_supported_camera_controls()`](CaptureBackendTrait::raw_supported_camera_controls), [`raw_camera_control()`](CaptureBackendTrait::raw_camera_control), [`set_raw_camera_control()`](CaptureBackendTrait::set_raw_camera_control) is **not** supported.
 /// - The symbolic link for the device is listed in the `misc` attribute of the [`CameraInfo`].
 /// - The names may contain invalid characters since they were converted from UTF16.
+/// - [`take_photo()`](CaptureBackendTrait::take_photo) reads from the device's photo stream (or still pin) at its largest MJPEG, JPEG or YUY2 size. Photo streams that need a hardware trigger (most Windows 10 "independent photo" streams) will time out after a few reads.
+/// - [`set_camera_format()`](CaptureBackendTrait::set_camera_format) swaps the format of an open stream in place (with `SetCurrentMediaType`), without stopping it.
+/// - The [`ColorSpace`] is not read from the media type, it is BT.601 limited range unless set with [`set_color_space()`](CaptureBackendTrait::set_color_space).
+/// - Camera profiles (See: [`new_with_profile()`](MediaFoundationCaptureDevice::new_with_profile)) need Windows 10 1607 or newer, and a driver that declares them. Selecting one restricts [`compatible_list_by_resolution()`](CaptureBackendTrait::compatible_list_by_resolution) and [`set_camera_format()`](CaptureBackendTrait::set_camera_format) to the media types of that profile.
+/// - Sensor groups (See: [`new_sensor_group()`](MediaFoundationCaptureDevice::new_sensor_group)) are opened by their index in [`query_sensor_groups()`](crate::query_sensor_groups), which is separate from the index of regular devices. Only the first video stream of a sensor group is read.
+/// - Devices are opened in [`AccessMode::Exclusive`], where `nokhwa` sets the media type and controls. Windows (10 and later) lets other applications read the same stream as long as they do not ask for another media type; asking for one while another application streams fails with [`DeviceBusy`](NokhwaError::DeviceBusy) rather than taking the device from it.
+///   In [`AccessMode::Shared`] the stream keeps the media type it has (see [`camera_format()`](CaptureBackendTrait::camera_format)), and changing the format or a control is refused with [`SetPropertyError`](NokhwaError::SetPropertyError).
+/// - Media Foundation has no generic sensor crop (ROI) control, so [`set_hardware_crop()`](CaptureBackendTrait::set_hardware_crop) is **not** supported. Use [`Camera::set_crop()`](crate::Camera::set_crop) to crop in software.
 pub struct MediaFoundationCaptureDevice {
     inner: MediaFoundationDevice,
+    color_space: Option<ColorSpace>,
+    access_mode: AccessMode,
 }
 
 impl MediaFoundationCaptureDevice {
@@ -34,11 +46,54 @@ impl MediaFoundationCaptureDevice {
     /// # Errors
     /// This function will error if Media Foundation fails to get the device.
     pub fn new(index: usize, camera_fmt: Option<CameraFormat>) -> Result<Self, NokhwaError> {
-        let mut mf_device = MediaFoundationDevice::new(index)?;
+        let mf_device = MediaFoundationDevice::new(index)?;
+        Self::from_device(mf_device, camera_fmt)
+    }
+
+    /// Creates a new capture device using the Media Foundation backend, with the camera profile `profile` selected. The device will only offer the [`CameraFormat`]s of that profile.
+    ///
+    /// The profiles a device advertises are listed in its [`CameraInfo`] (See: [`CameraInfo::profiles()`]).
+    /// # Errors
+    /// This function will error if Media Foundation fails to get the device, or the device does not advertise `profile`.
+    pub fn new_with_profile(
+        index: usize,
+        camera_fmt: Option<CameraFormat>,
+        profile: CameraProfile,
+    ) -> Result<Self, NokhwaError> {
+        let mf_device = MediaFoundationDevice::new_with_profile(index, Some(profile.into()))?;
+        Self::from_device(mf_device, camera_fmt)
+    }
+
+    /// Creates a new capture device from the sensor group at `index` of [`query_sensor_groups()`](crate::query_sensor_groups), optionally with the camera profile `profile` selected.
+    /// # Errors
+    /// This function will error if Media Foundation fails to get the sensor group, or the sensor group does not advertise `profile`.
+    pub fn new_sensor_group(
+        index: usize,
+        camera_fmt: Option<CameraFormat>,
+        profile: Option<CameraProfile>,
+    ) -> Result<Self, NokhwaError> {
+        let mf_device = MediaFoundationDevice::new_sensor_group(index, profile.map(Into::into))?;
+        Self::from_device(mf_device, camera_fmt)
+    }
+
+    fn from_device(
+        mut mf_device: MediaFoundationDevice,
+        camera_fmt: Option<CameraFormat>,
+    ) -> Result<Self, NokhwaError> {
         if let Some(fmt) = camera_fmt {
-            mf_device.set_format(fmt.into())?;
+            mf_device.set_format(MFCameraFormat::try_from(fmt)?)?;
         }
-        Ok(MediaFoundationCaptureDevice { inner: mf_device })
+        Ok(MediaFoundationCaptureDevice {
+            inner: mf_device,
+            color_space: None,
+            access_mode: AccessMode::Exclusive,
+        })
+    }
+
+    /// The camera profile selected when the device was opened, if any.
+    #[must_use]
+    pub fn camera_profile(&self) -> Option<CameraProfile> {
+        self.inner.profile().map(CameraProfile::from)
     }
 
     /// Create a new Media Foundation Device with desired settings.
@@ -54,6 +109,16 @@ impl MediaFoundationCaptureDevice {
         let camera_format = Some(CameraFormat::new_from(width, height, fourcc, fps));
         MediaFoundationCaptureDevice::new(index, camera_format)
     }
+
+    /// Returns the underlying `IMFSourceReader` as a raw COM pointer, for Media Foundation operations `nokhwa` does not cover.
+    /// # Safety
+    /// No reference is added to the pointer, it is only valid for as long as this device is alive. Do not `Release()` it.
+    /// Changing the media type or reading samples behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports.
+    /// It is null when built on a non-windows platform.
+    #[must_use]
+    pub unsafe fn raw_handle(&self) -> *mut c_void {
+        self.inner.raw_source_reader()
+    }
 }
 
 impl CaptureBackendTrait for MediaFoundationCaptureDevice {
@@ -62,12 +127,19 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
     }
 
     fn camera_info(&self) -> CameraInfo {
-        CameraInfo::new(
+        let symlink = self.inner.symlink();
+        let mut camera_info = CameraInfo::new(
             self.inner.name(),
             "".to_string(),
-            self.inner.symlink(),
+            symlink.clone(),
             self.inner.index(),
-        )
+        );
+        camera_info.fill_from_symbolic_link(&symlink);
+        camera_info.guess_stream_kind(&[]);
+        if let Ok(profiles) = self.inner.profiles() {
+            camera_info.set_profiles(profiles.into_iter().map(CameraProfile::from).collect());
+        }
+        camera_info
     }
 
     fn camera_format(&self) -> CameraFormat {
@@ -75,7 +147,11 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        if let Err(why) = self.inner.set_format(new_fmt.into()) {
+        if self.access_mode == AccessMode::Shared {
+            return Err(shared_error("CameraFormat", new_fmt.to_string()));
+        }
+        // `SetCurrentMediaType` swaps the format of a running source reader, no need to stop the stream
+        if let Err(why) = self.inner.set_format(MFCameraFormat::try_from(new_fmt)?) {
             return Err(why.into());
         }
         Ok(())
@@ -162,6 +238,15 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
         self.set_camera_format(new_format)
     }
 
+    fn color_space(&self) -> ColorSpace {
+        self.color_space.unwrap_or_default()
+    }
+
+    fn set_color_space(&mut self, color_space: Option<ColorSpace>) -> Result<(), NokhwaError> {
+        self.color_space = color_space;
+        Ok(())
+    }
+
     fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControls>, NokhwaError> {
         let mut supported_camera_controls: Vec<KnownCameraControls> = vec![];
 
@@ -184,6 +269,9 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
                 KnownCameraControls::Exposure => MediaFoundationControls::Exposure,
                 KnownCameraControls::Iris => MediaFoundationControls::Iris,
                 KnownCameraControls::Focus => MediaFoundationControls::Focus,
+                KnownCameraControls::PowerLineFrequency => {
+                    MediaFoundationControls::PowerLineFrequency
+                }
             };
 
             if let Ok(supported) = self.inner.control(msmf_camera_control) {
@@ -213,6 +301,7 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
             KnownCameraControls::Exposure => MediaFoundationControls::Exposure,
             KnownCameraControls::Iris => MediaFoundationControls::Iris,
             KnownCameraControls::Focus => MediaFoundationControls::Focus,
+            KnownCameraControls::PowerLineFrequency => MediaFoundationControls::PowerLineFrequency,
         };
 
         let ctrl = match self.inner.control(msmf_camera_control) {
@@ -242,6 +331,12 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
     }
 
     fn set_camera_control(&mut self, control: CameraControl) -> Result<(), NokhwaError> {
+        if self.access_mode == AccessMode::Shared {
+            return Err(shared_error(
+                &control.control().to_string(),
+                control.value().to_string(),
+            ));
+        }
         let ctrl = match control.control() {
             KnownCameraControls::Brightness => MediaFoundationControls::Brightness,
             KnownCameraControls::Contrast => MediaFoundationControls::Contrast,
@@ -260,6 +355,7 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
             KnownCameraControls::Exposure => MediaFoundationControls::Exposure,
             KnownCameraControls::Iris => MediaFoundationControls::Iris,
             KnownCameraControls::Focus => MediaFoundationControls::Focus,
+            KnownCameraControls::PowerLineFrequency => MediaFoundationControls::PowerLineFrequency,
         };
 
         let flag = match control.flag() {
@@ -306,6 +402,19 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
         ))
     }
 
+    fn access_mode(&self) -> Result<AccessMode, NokhwaError> {
+        Ok(self.access_mode)
+    }
+
+    fn set_access_mode(&mut self, mode: AccessMode) -> Result<(), NokhwaError> {
+        // a shared device streams in whatever media type it already has
+        if mode == AccessMode::Shared {
+            self.inner.adopt_current_format()?;
+        }
+        self.access_mode = mode;
+        Ok(())
+    }
+
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
         if let Err(why) = self.inner.start_stream() {
             return Err(why.into());
@@ -319,26 +428,8 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
     }
 
     fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        let camera_format = self.camera_format();
-        let raw_data = self.frame_raw()?;
-        let conv = match camera_format.format() {
-            FrameFormat::MJPEG => mjpeg_to_rgb888(&raw_data)?,
-            FrameFormat::YUYV => yuyv422_to_rgb888(&raw_data)?,
-        };
-
-        let imagebuf =
-            match ImageBuffer::from_vec(camera_format.width(), camera_format.height(), conv) {
-                Some(buf) => {
-                    let rgbbuf: ImageBuffer<Rgb<u8>, Vec<u8>> = buf;
-                    rgbbuf
-                }
-                None => return Err(NokhwaError::ReadFrameError(
-                    "Imagebuffer is not large enough! This is probably a bug, please report it!"
-                        .to_string(),
-                )),
-            };
-
-        Ok(imagebuf)
+        let frame = self.undecoded_frame()?;
+        DefaultDecoder.decode_rgb(&frame)
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
@@ -348,8 +439,42 @@ impl CaptureBackendTrait for MediaFoundationCaptureDevice {
         }
     }
 
+    fn take_photo(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let color_space = self.color_space();
+        let (resolution, format, raw_data) = match self.inner.photo() {
+            Ok(Some(photo)) => photo,
+            Ok(None) => return Err(NokhwaError::UnsupportedOperationError(self.backend())),
+            Err(why) => return Err(why.into()),
+        };
+        DefaultDecoder.decode_rgb(&RawFrame::new(
+            Cow::from(raw_data),
+            format.into(),
+            Resolution::new(resolution.width_x, resolution.height_y),
+            None,
+            color_space,
+        ))
+    }
+
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
         self.inner.stop_stream();
         Ok(())
     }
+
+    unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        let source_reader = MediaFoundationCaptureDevice::raw_handle(self);
+        if source_reader.is_null() {
+            None
+        } else {
+            Some(RawHandle::MediaFoundation(source_reader))
+        }
+    }
+}
+
+// changing the format or controls of a shared device would change them for every application using it
+fn shared_error(property: &str, value: String) -> NokhwaError {
+    NokhwaError::SetPropertyError {
+        property: property.to_string(),
+        value,
+        error: "The device is opened in shared access mode".to_string(),
+    }
 }
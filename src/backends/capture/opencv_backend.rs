@@ -6,17 +6,31 @@
 
 use crate::{
     CameraControl, CameraFormat, CameraIndexType, CameraInfo, CaptureAPIBackend,
-    CaptureBackendTrait, FrameFormat, KnownCameraControls, NokhwaError, Resolution,
+    CaptureBackendTrait, FrameFormat, KnownCameraControls, NokhwaError, RawFrame, RawHandle,
+    Resolution,
 };
 use image::{ImageBuffer, Rgb};
 use opencv::{
-    core::{Mat, MatTrait, MatTraitManual, Vec3b},
+    core::{Mat, MatTrait, MatTraitManual, Vec3b, Vector},
     videoio::{
         VideoCapture, VideoCaptureTrait, CAP_ANY, CAP_AVFOUNDATION, CAP_MSMF, CAP_PROP_FPS,
         CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH, CAP_V4L2,
     },
 };
-use std::{any::Any, borrow::Cow, collections::HashMap};
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    convert::TryFrom,
+    ffi::c_void,
+    time::{Duration, Instant},
+};
+
+// `cv::CAP_PROP_OPEN_TIMEOUT_MSEC` and `cv::CAP_PROP_READ_TIMEOUT_MSEC`, which the `opencv` 0.53 bindings do not export.
+const CAP_PROP_OPEN_TIMEOUT_MSEC: i32 = 53;
+const CAP_PROP_READ_TIMEOUT_MSEC: i32 = 54;
+// The most frames `drop_queued_frames()` will throw away in one go.
+const MAX_QUEUED_FRAMES: usize = 64;
 
 /// Converts $from into $to
 /// Example usage:
@@ -51,6 +65,7 @@ macro_rules! tryinto_num {
 ///  - This backend can also do IP Camera input.
 ///  - The backend's backend will default to system level APIs on Linux(V4L2), Mac(AVFoundation), and Windows(Media Foundation). Otherwise, it will decide for itself.
 ///  - If the [`OpenCvCaptureDevice`] is initialized as a `IPCamera`, the [`CameraFormat`]'s `index` value will be [`u32::MAX`](std::u32::MAX) (4294967295).
+///  - `IPCamera`s opened with [`new_ip_camera_with_timeout()`](OpenCvCaptureDevice::new_ip_camera_with_timeout) are always opened with `CAP_ANY`, as the timeouts are only honoured by `OpenCV`'s network capable backends (e.g. `FFmpeg`).
 ///  - `OpenCV` does not support camera querying. Camera Name and Camera supported resolution/fps/fourcc is a [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
 /// Note: [`resolution()`](crate::CaptureBackendTrait::resolution()), [`frame_format()`](crate::CaptureBackendTrait::frame_format()), and [`frame_rate()`](crate::CaptureBackendTrait::frame_rate()) is not affected.
 ///  - [`CameraInfo`]'s human name will be "`OpenCV` Capture Device {location}"
@@ -58,12 +73,15 @@ macro_rules! tryinto_num {
 ///  - The API Preference order is the native OS API (linux => `v4l2`, mac => `AVFoundation`, windows => `MSMF`) than [`CAP_AUTO`](https://docs.opencv.org/4.5.2/d4/d15/group__videoio__flags__base.html#gga023786be1ee68a9105bf2e48c700294da77ab1fe260fd182f8ec7655fab27a31d)
 /// - The `Any` type for [`raw_camera_control()`](CaptureBackendTrait::raw_camera_control) is [`i32`], and its return `Any` is a [`f64`]. Please check [`OpenCV Documentation Constants`](https://docs.rs/opencv/0.53.1/opencv/videoio/index.html) for more.
 /// - The `Any` type for `control` for [`set_raw_camera_control()`](CaptureBackendTrait::set_raw_camera_control) is [`i32`] and [`f64`]. Please check [`OpenCV Documentation Constants`](https://docs.rs/opencv/0.53.1/opencv/videoio/index.html) for more.
+/// - Frames are decoded by `OpenCV`, so there are no undecoded frames: [`undecoded_frame()`](CaptureBackendTrait::undecoded_frame) is not supported, and a [`FrameDecoder`](crate::FrameDecoder) set on a [`Camera`](crate::Camera) is not used.
 
 pub struct OpenCvCaptureDevice {
     camera_format: CameraFormat,
     camera_location: CameraIndexType,
     camera_info: CameraInfo,
     api_preference: i32,
+    ip_timeout: Option<Duration>,
+    frame_grabbed: bool,
     video_capture: VideoCapture,
 }
 
@@ -88,6 +106,15 @@ impl OpenCvCaptureDevice {
         camera_location: CameraIndexType,
         cfmt: Option<CameraFormat>,
         api_pref: Option<u32>,
+    ) -> Result<Self, NokhwaError> {
+        OpenCvCaptureDevice::new_with_ip_timeout(camera_location, cfmt, api_pref, None)
+    }
+
+    fn new_with_ip_timeout(
+        camera_location: CameraIndexType,
+        cfmt: Option<CameraFormat>,
+        api_pref: Option<u32>,
+        ip_timeout: Option<Duration>,
     ) -> Result<Self, NokhwaError> {
         let api = if let Some(a) = api_pref {
             tryinto_num!(i32, a)
@@ -118,10 +145,7 @@ impl OpenCvCaptureDevice {
                 };
                 vid_cap
             }
-            CameraIndexType::IPCamera(ip) => match VideoCapture::from_file(&*ip, CAP_ANY) {
-                Ok(vc) => vc,
-                Err(why) => return Err(NokhwaError::OpenDeviceError(ip, why.to_string())),
-            },
+            CameraIndexType::IPCamera(ip) => open_ip_capture(&ip, ip_timeout)?,
         };
 
         set_properties(&mut video_capture, camera_format, &camera_location)?;
@@ -138,6 +162,8 @@ impl OpenCvCaptureDevice {
             camera_location,
             camera_info,
             api_preference: api,
+            ip_timeout,
+            frame_grabbed: false,
             video_capture,
         })
     }
@@ -158,6 +184,16 @@ impl OpenCvCaptureDevice {
         OpenCvCaptureDevice::new(camera_location, None, None)
     }
 
+    /// Creates a new capture device for an `IPCamera` using the `OpenCV` backend, like [`new_ip_camera()`](OpenCvCaptureDevice::new_ip_camera).
+    ///
+    /// Connecting, and every read after that, gives up once `timeout` has passed, instead of waiting for `OpenCV`'s default (30 seconds for `FFmpeg`).
+    /// # Errors
+    /// If the backend fails to open the camera (e.g. Device does not exist at specified ip, or it did not answer within `timeout`) and/or other `OpenCV` Error, this will error.
+    pub fn new_ip_camera_with_timeout(ip: String, timeout: Duration) -> Result<Self, NokhwaError> {
+        let camera_location = CameraIndexType::IPCamera(ip);
+        OpenCvCaptureDevice::new_with_ip_timeout(camera_location, None, None, Some(timeout))
+    }
+
     /// Creates a new capture device using the `OpenCV` backend.
     /// Indexes are gives to devices by the OS, and usually numbered by order of discovery.
     ///
@@ -205,6 +241,11 @@ impl OpenCvCaptureDevice {
         self.camera_location.clone()
     }
 
+    /// Gets the connect and read timeout of an `IPCamera`, if one was set with [`new_ip_camera_with_timeout()`](OpenCvCaptureDevice::new_ip_camera_with_timeout).
+    pub fn ip_timeout(&self) -> Option<Duration> {
+        self.ip_timeout
+    }
+
     /// Gets the `OpenCV` API Preference number. Please refer to [`OpenCV VideoCapture Flag Docs`](https://docs.opencv.org/4.5.2/d4/d15/group__videoio__flags__base.html).
     pub fn opencv_preference(&self) -> i32 {
         self.api_preference
@@ -222,7 +263,12 @@ impl OpenCvCaptureDevice {
         }
 
         let mut frame = Mat::default();
-        match self.video_capture.read(&mut frame) {
+        let read = if std::mem::take(&mut self.frame_grabbed) {
+            self.video_capture.retrieve(&mut frame, 0)
+        } else {
+            self.video_capture.read(&mut frame)
+        };
+        match read {
             Ok(a) => {
                 if !a {
                     return Err(NokhwaError::ReadFrameError(
@@ -299,6 +345,45 @@ impl OpenCvCaptureDevice {
         }
     }
 
+    /// Grabs and throws away the frames `OpenCV` has already buffered, so that the next frame read is a live one instead of a stale one. Returns how many frames were dropped.
+    ///
+    /// A grab that returns within half a frame interval is assumed to have come out of the buffer. The last frame grabbed is kept for the next read.
+    /// # Errors
+    /// If the stream is not open or a frame fails to be grabbed (e.g. the camera disconnected), this will error.
+    pub fn drop_queued_frames(&mut self) -> Result<usize, NokhwaError> {
+        if !self.is_stream_open() {
+            return Err(NokhwaError::ReadFrameError(
+                "Stream is not open!".to_string(),
+            ));
+        }
+
+        let live_after = Duration::from_secs(1) / self.frame_rate().max(1) / 2;
+        let mut grabbed = 0;
+        while grabbed < MAX_QUEUED_FRAMES {
+            let started = Instant::now();
+            match self.video_capture.grab() {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Err(NokhwaError::ReadFrameError(
+                        "Failed to grab frame from videocapture: OpenCV return false, camera disconnected?".to_string(),
+                    ))
+                }
+                Err(why) => {
+                    return Err(NokhwaError::ReadFrameError(format!(
+                        "Failed to grab frame from videocapture: {}",
+                        why.to_string()
+                    )))
+                }
+            }
+            self.frame_grabbed = true;
+            grabbed += 1;
+            if started.elapsed() >= live_after {
+                break;
+            }
+        }
+        Ok(grabbed - 1)
+    }
+
     /// Gets the resolution raw as read by `OpenCV`.
     /// # Errors
     /// If the resolution is failed to be read (e.g. invalid or not supported), this will error.
@@ -342,6 +427,14 @@ impl OpenCvCaptureDevice {
             }),
         }
     }
+
+    /// Returns the underlying `cv::VideoCapture` as a raw pointer, for `OpenCV` operations `nokhwa` does not cover.
+    /// # Safety
+    /// The pointer is owned by this device and is only valid for as long as it is alive. Do not free it.
+    /// Changing capture properties behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports.
+    pub unsafe fn raw_handle(&mut self) -> *mut c_void {
+        self.video_capture.as_raw_mut_VideoCapture()
+    }
 }
 
 impl CaptureBackendTrait for OpenCvCaptureDevice {
@@ -540,8 +633,9 @@ impl CaptureBackendTrait for OpenCvCaptureDevice {
                     }
                 }
             }
-            CameraIndexType::IPCamera(ip) => {
-                match self
+            CameraIndexType::IPCamera(ip) => match self.ip_timeout {
+                Some(_) => self.video_capture = open_ip_capture(&ip, self.ip_timeout)?,
+                None => match self
                     .video_capture
                     .open_file(&*ip, get_api_pref_int() as i32)
                 {
@@ -552,9 +646,10 @@ impl CaptureBackendTrait for OpenCvCaptureDevice {
                             format!("Failed to open device: {}", why.to_string()),
                         ))
                     }
-                }
-            }
+                },
+            },
         };
+        self.frame_grabbed = false;
 
         match self.video_capture.is_opened() {
             Ok(open) => {
@@ -579,7 +674,7 @@ impl CaptureBackendTrait for OpenCvCaptureDevice {
     fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
         let camera_resolution = self.camera_format.resolution();
         let image_data = {
-            let mut data = self.frame_raw()?.to_vec();
+            let mut data = crate::buffer_pool::pooled_copy(&self.frame_raw()?);
             data.resize(
                 (camera_resolution.width() * camera_resolution.height() * 3) as usize,
                 0_u8,
@@ -609,12 +704,21 @@ impl CaptureBackendTrait for OpenCvCaptureDevice {
         Ok(cow)
     }
 
+    fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
+        self.frame_grabbed = false;
         match self.video_capture.release() {
             Ok(_) => Ok(()),
             Err(why) => Err(NokhwaError::StreamShutdownError(why.to_string())),
         }
     }
+
+    unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        Some(RawHandle::OpenCv(OpenCvCaptureDevice::raw_handle(self)))
+    }
 }
 
 fn get_api_pref_int() -> u32 {
@@ -626,6 +730,25 @@ fn get_api_pref_int() -> u32 {
     }
 }
 
+fn open_ip_capture(ip: &str, timeout: Option<Duration>) -> Result<VideoCapture, NokhwaError> {
+    let video_capture = match timeout {
+        Some(timeout) => {
+            let millis = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+            let params = vec![
+                CAP_PROP_OPEN_TIMEOUT_MSEC,
+                millis,
+                CAP_PROP_READ_TIMEOUT_MSEC,
+                millis,
+            ]
+            .into_iter()
+            .collect::<Vector<i32>>();
+            VideoCapture::from_file_with_params(ip, CAP_ANY, &params)
+        }
+        None => VideoCapture::from_file(ip, CAP_ANY),
+    };
+    video_capture.map_err(|why| NokhwaError::OpenDeviceError(ip.to_string(), why.to_string()))
+}
+
 #[allow(clippy::cast_possible_wrap)]
 #[allow(clippy::unnecessary_wraps)]
 // I'm done. This stupid POS refuses to actually do anything useful with camera settings
@@ -6,7 +6,8 @@
 
 use crate::{
     CameraControl, CameraFormat, CameraInfo, CaptureAPIBackend, CaptureBackendTrait, FrameFormat,
-    KnownCameraControlFlag, KnownCameraControls, NokhwaError, Resolution,
+    KnownCameraControlFlag, KnownCameraControls, NokhwaError, PowerLineFrequency, RawFrame,
+    Resolution,
 };
 use flume::{Receiver, Sender};
 use image::{ImageBuffer, Rgb};
@@ -16,6 +17,7 @@ use std::borrow::Cow;
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    convert::TryFrom,
     mem::MaybeUninit,
     sync::{atomic::AtomicUsize, Arc},
 };
@@ -24,6 +26,24 @@ use uvc::{
     StreamFormat, StreamHandle,
 };
 
+const UVC_PHOTO_WARMUP_FRAMES: usize = 2;
+
+// losing the device, a busy device and denied access get their own variants, everything else is `otherwise`
+fn uvc_error(
+    index: usize,
+    why: uvc::Error,
+    otherwise: impl FnOnce(String) -> NokhwaError,
+) -> NokhwaError {
+    let device = index.to_string();
+    let error = why.to_string();
+    match why {
+        uvc::Error::NoDevice => NokhwaError::DeviceDisconnected { device, error },
+        uvc::Error::Busy => NokhwaError::DeviceBusy { device, error },
+        uvc::Error::Access => NokhwaError::PermissionDenied { device, error },
+        _ => otherwise(error),
+    }
+}
+
 // ignore the IDE, this compiles
 /// The backend struct that interfaces with `libuvc`.
 /// To see what this does, please see [`CaptureBackendTrait`]
@@ -35,7 +55,10 @@ use uvc::{
 /// - Calling [`set_resolution()`](CaptureBackendTrait::set_resolution()), [`set_frame_rate()`](crate::CaptureBackendTrait::set_frame_rate()), or [`set_frame_format()`](crate::CaptureBackendTrait::set_frame_format()) each internally calls [`set_camera_format()`](crate::CaptureBackendTrait::set_camera_format()).
 /// - [`frame_raw()`](crate::CaptureBackendTrait::frame_raw()) returns the same raw data as [`get_frame()`](crate::CaptureBackendTrait::frame()), a.k.a. no custom decoding required, all data is automatically RGB
 /// - The [`frame_raw()`](crate::CaptureBackendTrait::frame_raw()) and by extension [`frame()`](crate::CaptureBackendTrait::frame()) functions block.
+/// - Frames are decoded by `libuvc`, so there are no undecoded frames: [`undecoded_frame()`](crate::CaptureBackendTrait::undecoded_frame()) is not supported, and a [`FrameDecoder`](crate::FrameDecoder) set on a [`Camera`](crate::Camera) is not used.
 /// - Setting controls is not supported.
+/// - There is no [`raw_handle()`](crate::CaptureBackendTrait::raw_handle()), as the `libuvc` bindings keep the `uvc_device_handle_t` private.
+/// - [`take_photo()`](crate::CaptureBackendTrait::take_photo()) cannot trigger the still image pin, as the `libuvc` bindings do not expose it. It instead switches the stream to the largest resolution of the current [`FrameFormat`] for a moment (the "method 1" still capture of the UVC spec), and switches back.
 /// - This backend, once stream is open, will constantly collect frames. When you call [`frame()`](crate::CaptureBackendTrait::frame()) or one of its variants, it will only give you the latest frame.
 /// # Safety
 /// This backend requires use of `unsafe` due to the self-referencing structs involved.
@@ -86,10 +109,9 @@ impl<'a> UVCCaptureDevice<'a> {
             let device_list = match context.devices() {
                 Ok(device_list) => device_list,
                 Err(why) => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        why.to_string(),
-                    ))
+                    return Err(uvc_error(index, why, |error| {
+                        NokhwaError::OpenDeviceError(index.to_string(), error)
+                    }))
                 }
             };
 
@@ -106,10 +128,9 @@ impl<'a> UVCCaptureDevice<'a> {
             let device_desc = match device.description() {
                 Ok(desc) => desc,
                 Err(why) => {
-                    return Err(NokhwaError::OpenDeviceError(
-                        index.to_string(),
-                        why.to_string(),
-                    ))
+                    return Err(uvc_error(index, why, |error| {
+                        NokhwaError::OpenDeviceError(index.to_string(), error)
+                    }))
                 }
             };
 
@@ -220,11 +241,17 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
                     self.with_camera_format_mut(|cfmt| {
                         *cfmt = prev_fmt;
                     });
-                    Err(NokhwaError::SetPropertyError {
-                        property: "CameraFormat".to_string(),
-                        value: new_fmt.to_string(),
-                        error: why.to_string(),
-                    })
+                    match why {
+                        NokhwaError::UnsupportedFormat { .. }
+                        | NokhwaError::DeviceDisconnected { .. }
+                        | NokhwaError::DeviceBusy { .. }
+                        | NokhwaError::PermissionDenied { .. } => Err(why),
+                        why => Err(NokhwaError::SetPropertyError {
+                            property: "CameraFormat".to_string(),
+                            value: new_fmt.to_string(),
+                            error: why.to_string(),
+                        }),
+                    }
                 }
             };
         }
@@ -321,6 +348,7 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
         Ok(vec![
             KnownCameraControls::Exposure,
             KnownCameraControls::Focus,
+            KnownCameraControls::PowerLineFrequency,
         ])
     }
 
@@ -347,6 +375,27 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
                     }
                 }
             },
+            KnownCameraControls::PowerLineFrequency => {
+                match self.borrow_device_handle().power_line_frequency() {
+                    Ok(v) => {
+                        let v: u8 = v;
+                        CameraControl::new(
+                            control,
+                            PowerLineFrequency::Disabled.into(),
+                            PowerLineFrequency::Auto.into(),
+                            i32::from(v),
+                            1_i32,
+                            i32::from(v),
+                            KnownCameraControlFlag::Manual,
+                            true,
+                        )
+                    }
+                    Err(why) => Err(NokhwaError::GetPropertyError {
+                        property: control.to_string(),
+                        error: why.to_string(),
+                    }),
+                }
+            }
             _ => Err(NokhwaError::GetPropertyError {
                 property: control.to_string(),
                 error: "Not Supported".to_string(),
@@ -384,7 +433,7 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
 
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
         let ret: Result<(), NokhwaError> = self.with_mut(|fields| {
-            let stream_format: StreamFormat = CameraFormat::into(*fields.camera_format);
+            let stream_format = StreamFormat::try_from(*fields.camera_format)?;
 
             // first, drop the existing stream by setting it to None
             {
@@ -412,7 +461,16 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
                     }
                     Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
                 },
-                Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
+                Err(uvc::Error::InvalidMode) => {
+                    return Err(NokhwaError::UnsupportedFormat {
+                        format: fields.camera_format.to_string(),
+                        error: uvc::Error::InvalidMode.to_string(),
+                    })
+                }
+                Err(why) => {
+                    let index = *fields.camera_info.index();
+                    return Err(uvc_error(index, why, NokhwaError::OpenStreamError));
+                }
             }
             Ok(())
         });
@@ -453,7 +511,10 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
                 counter,
             ) {
                 Ok(active) => active,
-                Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
+                Err(why) => {
+                    let index = *fields.camera_info.index();
+                    return Err(uvc_error(index, why, NokhwaError::OpenStreamError));
+                }
             };
             *fields.active_stream.borrow_mut() = MaybeUninit::new(active_stream);
             Ok(())
@@ -479,16 +540,18 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
 
         let resolution: Resolution = self.borrow_camera_format().resolution();
 
-        let imagebuf: ImageBuffer<Rgb<u8>, Vec<u8>> =
-            match ImageBuffer::from_vec(resolution.width(), resolution.height(), data.to_vec()) {
-                Some(img) => img,
-                None => {
-                    return Err(NokhwaError::ReadFrameError(
-                        "ImageBuffer too small! This is probably a bug, please report it!"
-                            .to_string(),
-                    ))
-                }
-            };
+        let imagebuf: ImageBuffer<Rgb<u8>, Vec<u8>> = match ImageBuffer::from_vec(
+            resolution.width(),
+            resolution.height(),
+            crate::buffer_pool::pooled_copy(&data),
+        ) {
+            Some(img) => img,
+            None => {
+                return Err(NokhwaError::ReadFrameError(
+                    "ImageBuffer too small! This is probably a bug, please report it!".to_string(),
+                ))
+            }
+        };
 
         Ok(imagebuf)
     }
@@ -517,6 +580,50 @@ impl<'a> CaptureBackendTrait for UVCCaptureDevice<'a> {
         }
     }
 
+    fn take_photo(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let video_format = *self.borrow_camera_format();
+        let (resolution, framerates) = match self
+            .compatible_list_by_resolution(video_format.format())?
+            .into_iter()
+            .max_by_key(|(resolution, _)| resolution.width() * resolution.height())
+        {
+            Some(largest) => largest,
+            None => return Err(NokhwaError::UnsupportedOperationError(self.backend())),
+        };
+        if resolution == video_format.resolution() {
+            return self.frame();
+        }
+        let framerate = match framerates.into_iter().max() {
+            Some(fps) => fps,
+            None => return Err(NokhwaError::UnsupportedOperationError(self.backend())),
+        };
+
+        let was_open = self.is_stream_open();
+        self.set_camera_format(CameraFormat::new(
+            resolution,
+            video_format.format(),
+            framerate,
+        ))?;
+        if !was_open {
+            self.open_stream()?;
+        }
+        // the first frames after switching are often still adjusting
+        for _ in 0..UVC_PHOTO_WARMUP_FRAMES {
+            self.frame_raw()?;
+        }
+        let photo = self.frame();
+
+        self.set_camera_format(video_format)?;
+        if !was_open {
+            self.stop_stream()?;
+        }
+        photo
+    }
+
+    fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
         self.with(|fields| {
             if fields.active_stream_init.get() {
@@ -6,18 +6,24 @@
 
 use crate::{
     error::NokhwaError,
-    mjpeg_to_rgb888,
-    utils::{CameraFormat, CameraInfo},
-    yuyv422_to_rgb888, CameraControl, CaptureAPIBackend, CaptureBackendTrait, FrameFormat,
-    KnownCameraControlFlag, KnownCameraControls, Resolution,
+    utils::{AccessMode, CameraFormat, CameraInfo},
+    CameraControl, CaptureAPIBackend, CaptureBackendTrait, ColorSpace, DefaultDecoder,
+    FrameDecoder, FrameFormat, FrameMetadata, KnownCameraControlFlag, KnownCameraControls,
+    RawFrame, RawHandle, Rect, Resolution, StreamKind,
 };
 use image::{ImageBuffer, Rgb};
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    convert::TryFrom,
+    io,
+    time::{Duration, Instant},
+};
 use v4l::{
     buffer::Type,
     frameinterval::FrameIntervalEnum,
     framesize::FrameSizeEnum,
-    io::traits::CaptureStream,
+    io::traits::{CaptureStream, Stream},
     prelude::*,
     video::{capture::Parameters, Capture},
     Format, FourCC,
@@ -25,6 +31,10 @@ use v4l::{
 
 use std::any::Any;
 pub use v4l::control::{Control, Description, Flags};
+use v4l2_sys_mit::{
+    v4l2_buf_type_V4L2_BUF_TYPE_VIDEO_CAPTURE, v4l2_capability, v4l2_rect, v4l2_selection,
+    V4L2_CAP_DEVICE_CAPS, V4L2_CAP_META_CAPTURE, V4L2_SEL_TGT_CROP, V4L2_SEL_TGT_CROP_BOUNDS,
+};
 
 /// Generates a camera control from a device and a description of control
 /// # Error
@@ -116,6 +126,7 @@ pub fn try_known_camera_control_to_id(ctrl: KnownCameraControls) -> Option<u32>
         KnownCameraControls::Exposure => Some(99_637_930),
         KnownCameraControls::Iris => Some(10_094_866),
         KnownCameraControls::Focus => Some(10_094_859),
+        KnownCameraControls::PowerLineFrequency => Some(9_963_800),
         _ => None,
     }
 }
@@ -139,10 +150,259 @@ pub fn try_id_to_known_camera_control(id: u32) -> Option<KnownCameraControls> {
         99_637_930 => Some(KnownCameraControls::Exposure),
         10_094_866 => Some(KnownCameraControls::Iris),
         10_094_859 => Some(KnownCameraControls::Focus),
+        9_963_800 => Some(KnownCameraControls::PowerLineFrequency),
         _ => None,
     }
 }
 
+// Waits until `fd` has a frame ready to be dequeued. Returns `false` if `timeout` passed first.
+fn poll_readable(fd: std::os::raw::c_int, timeout: Duration) -> io::Result<bool> {
+    let started = Instant::now();
+    loop {
+        let remaining = timeout
+            .checked_sub(started.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = i32::try_from(remaining.as_millis()).unwrap_or(i32::MAX);
+        match unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } {
+            -1 => {
+                let why = io::Error::last_os_error();
+                if why.kind() != io::ErrorKind::Interrupted {
+                    return Err(why);
+                }
+            }
+            0 => return Ok(false),
+            _ => return Ok(true),
+        }
+    }
+}
+
+// `EINVAL` from setting a format means the driver does not do it at all
+fn set_format_error(index: usize, format: String, why: &io::Error) -> NokhwaError {
+    const INVALID: i32 = 22;
+    if why.raw_os_error() == Some(INVALID) {
+        return NokhwaError::UnsupportedFormat {
+            format,
+            error: why.to_string(),
+        };
+    }
+    NokhwaError::from_io_error(&index.to_string(), why, |error| {
+        NokhwaError::SetPropertyError {
+            property: "Resolution, FrameFormat".to_string(),
+            value: format,
+            error,
+        }
+    })
+}
+
+// `V4L2_COLORSPACE_REC709`, `V4L2_COLORSPACE_JPEG`, `V4L2_QUANTIZATION_FULL_RANGE` and `V4L2_QUANTIZATION_LIM_RANGE`
+const COLORSPACE_REC709: u32 = 3;
+const COLORSPACE_JPEG: u32 = 7;
+const QUANTIZATION_FULL_RANGE: u32 = 1;
+const QUANTIZATION_LIM_RANGE: u32 = 2;
+
+// the color space the driver reports for `format`
+fn format_color_space(format: &Format) -> ColorSpace {
+    let colorspace = format.colorspace as u32;
+    let full_range = match format.quantization as u32 {
+        QUANTIZATION_FULL_RANGE => true,
+        QUANTIZATION_LIM_RANGE => false,
+        // the default quantization is limited range, except for the JPEG colorspace
+        _ => colorspace == COLORSPACE_JPEG,
+    };
+    match (colorspace == COLORSPACE_REC709, full_range) {
+        (false, false) => ColorSpace::Bt601Limited,
+        (false, true) => ColorSpace::Bt601Full,
+        (true, false) => ColorSpace::Bt709Limited,
+        (true, true) => ColorSpace::Bt709Full,
+    }
+}
+
+// `VIDIOC_G_SELECTION` and `VIDIOC_S_SELECTION`
+const VIDIOC_G_SELECTION: u32 = 0xC040_565E;
+const VIDIOC_S_SELECTION: u32 = 0xC040_565F;
+
+// Gets (or with `rect`, sets) the `target` selection rectangle of the capture stream
+#[allow(clippy::cast_possible_wrap)]
+#[allow(clippy::cast_sign_loss)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_lossless)]
+fn selection(fd: std::os::raw::c_int, target: u32, rect: Option<Rect>) -> io::Result<Rect> {
+    let mut selection: v4l2_selection = unsafe { std::mem::zeroed() };
+    selection.type_ = v4l2_buf_type_V4L2_BUF_TYPE_VIDEO_CAPTURE as u32;
+    selection.target = target;
+    let request = match rect {
+        Some(rect) => {
+            selection.r = v4l2_rect {
+                left: i32::try_from(rect.x()).unwrap_or(i32::MAX),
+                top: i32::try_from(rect.y()).unwrap_or(i32::MAX),
+                width: rect.width(),
+                height: rect.height(),
+            };
+            VIDIOC_S_SELECTION
+        }
+        None => VIDIOC_G_SELECTION,
+    };
+    if unsafe { libc::ioctl(fd, request as _, &mut selection) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Rect::new(
+        selection.r.left.max(0) as u32,
+        selection.r.top.max(0) as u32,
+        selection.r.width,
+        selection.r.height,
+    ))
+}
+
+// `ENOTTY` (or `EINVAL` for the capture buffer type) means the driver has no selection API
+fn selection_error(
+    backend: CaptureAPIBackend,
+    index: usize,
+    why: &io::Error,
+    otherwise: impl FnOnce(String) -> NokhwaError,
+) -> NokhwaError {
+    const INVALID: i32 = 22;
+    const NOT_TTY: i32 = 25;
+    match why.raw_os_error() {
+        Some(INVALID) | Some(NOT_TTY) => NokhwaError::UnsupportedOperationError(backend),
+        _ => NokhwaError::from_io_error(&index.to_string(), why, otherwise),
+    }
+}
+
+// `VIDIOC_S_PRIORITY`, and the `V4L2_PRIORITY_INTERACTIVE` (the default of every file handle) and `V4L2_PRIORITY_RECORD` priorities
+const VIDIOC_S_PRIORITY: u32 = 0x4004_5644;
+const PRIORITY_INTERACTIVE: u32 = 2;
+const PRIORITY_RECORD: u32 = 3;
+
+// `EBUSY` means another file handle holds `V4L2_PRIORITY_RECORD`
+fn set_priority(fd: std::os::raw::c_int, priority: u32) -> io::Result<()> {
+    let mut priority = priority;
+    if unsafe { libc::ioctl(fd, VIDIOC_S_PRIORITY as _, &mut priority) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// `VIDIOC_QUERYCAP`
+const VIDIOC_QUERYCAP: u32 = 0x8068_5600;
+// buffers for the metadata node, which are given back as soon as they are read
+const METADATA_BUFFER_COUNT: u32 = 4;
+
+// The capabilities of this node alone, rather than of the whole device
+#[allow(clippy::cast_possible_wrap)]
+fn node_capabilities(fd: std::os::raw::c_int) -> io::Result<(u32, [u8; 32])> {
+    let mut caps: v4l2_capability = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(fd, VIDIOC_QUERYCAP as _, &mut caps) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let capabilities = if caps.capabilities & V4L2_CAP_DEVICE_CAPS == 0 {
+        caps.capabilities
+    } else {
+        caps.device_caps
+    };
+    Ok((capabilities, caps.bus_info))
+}
+
+// The metadata node of the same device as `device` (which `uvcvideo` creates since Linux 4.16), if there is one
+fn find_metadata_device(device: &Device) -> Option<Device> {
+    let (_, bus_info) = node_capabilities(device.handle().fd()).ok()?;
+    v4l::context::enum_devices()
+        .into_iter()
+        .filter_map(|node| Device::new(node.index()).ok())
+        .find(|candidate| {
+            node_capabilities(candidate.handle().fd()).map_or(false, |(capabilities, bus)| {
+                bus == bus_info && capabilities & V4L2_CAP_META_CAPTURE != 0
+            })
+        })
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from(le_u32(bytes)) | u64::from(le_u32(&bytes[4..])) << 32
+}
+
+// A `V4L2_META_FMT_UVC` buffer is a list of `struct uvc_meta_buf`: the host time in nanoseconds (u64), the USB frame number (u16),
+// then the UVC payload header of a packet: its length (which counts itself), `bmHeaderInfo`, then the PTS and SCR if the flags say so
+fn parse_uvc_metadata(data: &[u8], mut metadata: FrameMetadata) -> FrameMetadata {
+    const HEADER_START: usize = 10;
+    const PTS_PRESENT: u8 = 0x04;
+    const SCR_PRESENT: u8 = 0x08;
+
+    let mut first = true;
+    let mut blocks = data;
+    while blocks.len() >= HEADER_START + 2 {
+        let length = usize::from(blocks[HEADER_START]);
+        if length < 2 || blocks.len() < HEADER_START + length {
+            break;
+        }
+        let flags = blocks[HEADER_START + 1];
+        let mut fields = &blocks[HEADER_START + 2..HEADER_START + length];
+        if flags & PTS_PRESENT != 0 && fields.len() >= 4 {
+            if first {
+                metadata = metadata.with_presentation_time(le_u32(fields));
+            }
+            fields = &fields[4..];
+        }
+        if flags & SCR_PRESENT != 0 && fields.len() >= 6 {
+            if first {
+                metadata = metadata.with_source_clock(le_u32(fields), le_u16(&fields[4..]) & 0x7FF);
+            }
+            fields = &fields[6..];
+        }
+        metadata = parse_capture_stats(fields, metadata);
+        first = false;
+        blocks = &blocks[HEADER_START + length..];
+    }
+    metadata
+}
+
+// Cameras that implement Microsoft's UVC metadata extension put a list of `KSCAMERA_METADATA_ITEMHEADER` items after the standard header fields.
+// `MetadataId_CaptureStats` has the exposure time (in 100ns units) and ISO speed, each valid only when its flag is set
+fn parse_capture_stats(mut items: &[u8], mut metadata: FrameMetadata) -> FrameMetadata {
+    const CAPTURE_STATS: u32 = 3;
+    const CAPTURE_STATS_SIZE: usize = 40;
+    const EXPOSURE_TIME: u32 = 0x1;
+    const ISO_SPEED: u32 = 0x4;
+
+    while items.len() >= 8 {
+        let id = le_u32(items);
+        let size = usize::try_from(le_u32(&items[4..])).unwrap_or(usize::MAX);
+        if size < 8 || size > items.len() {
+            break;
+        }
+        let item = &items[..size];
+        if id == CAPTURE_STATS && size >= CAPTURE_STATS_SIZE {
+            let flags = le_u32(&item[8..]);
+            if flags & EXPOSURE_TIME != 0 {
+                let exposure = le_u64(&item[16..]).saturating_mul(100);
+                metadata = metadata.with_exposure_time(Duration::from_nanos(exposure));
+            }
+            if flags & ISO_SPEED != 0 {
+                metadata = metadata.with_iso_speed(le_u32(&item[36..]));
+            }
+        }
+        items = &items[size..];
+    }
+    metadata
+}
+
+// The metadata node of a UVC camera, streaming alongside the video node
+struct MetadataStream<'a> {
+    device: Device,
+    stream: MmapStream<'a>,
+}
+
 fn clone_control(ctrl: &Control) -> Control {
     match ctrl {
         Control::Value(v) => Control::Value(*v),
@@ -158,11 +418,31 @@ fn clone_control(ctrl: &Control) -> Control {
 /// - The `Any` return type for [`raw_supported_camera_controls()`](CaptureBackendTrait::raw_supported_camera_controls) is [`Description`]
 /// - The `Any` type for [`raw_camera_control()`](CaptureBackendTrait::raw_camera_control) is [`u32`], and its return `Any` is a [`Control`]
 /// - The `Any` type for `control` for [`set_raw_camera_control()`](CaptureBackendTrait::set_raw_camera_control) is [`u32`] and [`Control`]
+/// - [`frame_with_deadline()`](CaptureBackendTrait::frame_with_deadline) has millisecond granularity. If it times out, the buffer stays queued, and the next frame call picks it up.
+/// - Changing only the frame rate with [`set_camera_format()`](CaptureBackendTrait::set_camera_format) while the stream is open is done in place where the driver allows it. Anything else re-opens the stream.
+/// - The stream uses 4 buffers by default, see [`set_buffer_count()`](CaptureBackendTrait::set_buffer_count). The driver may allocate more or fewer.
+/// - The [`ColorSpace`] is read from the colorspace and quantization the driver reports for the format. A colorspace other than Rec. 709 is decoded as BT.601.
+/// - Hardware cropping uses the V4L2 selection API (`VIDIOC_S_SELECTION`). Drivers without it return [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError). Some drivers shrink the resolution to the crop, others scale it back up; [`camera_format()`](CaptureBackendTrait::camera_format) is re-read after the crop is set.
+/// - With [`set_frame_metadata()`](CaptureBackendTrait::set_frame_metadata) on, every frame gets the sequence number and timestamp of its buffer. UVC cameras also stream their payload headers (PTS and SCR, and on cameras that implement Microsoft's metadata extension the exposure time and ISO) from the device's metadata node, where the kernel has one (4.16 and later).
+/// - [`AccessMode`]s map to V4L2 priorities: [`AccessMode::Exclusive`] takes `V4L2_PRIORITY_RECORD`, which stops other applications from changing the format or controls, and fails with [`DeviceBusy`](NokhwaError::DeviceBusy) if another application already holds it.
+///   Devices are opened in [`AccessMode::Shared`] (the default priority). Either way, only one application can stream from a video node at a time.
+/// - Drivers may pad rows of uncompressed formats (`bytesperline`). [`frame_raw()`](CaptureBackendTrait::frame_raw) keeps the padding, see [`frame_stride()`](CaptureBackendTrait::frame_stride).
 pub struct V4LCaptureDevice<'a> {
     camera_format: CameraFormat,
     camera_info: CameraInfo,
     device: Device,
     stream_handle: Option<MmapStream<'a>>,
+    buffer_count: u32,
+    buffer_index: usize,
+    buffer_queued: bool,
+    color_space: ColorSpace,
+    color_space_override: Option<ColorSpace>,
+    // `bytesperline` of the format
+    stride: usize,
+    capture_metadata: bool,
+    metadata_stream: Option<MetadataStream<'a>>,
+    metadata: Option<FrameMetadata>,
+    access_mode: AccessMode,
 }
 
 impl<'a> V4LCaptureDevice<'a> {
@@ -175,15 +455,28 @@ impl<'a> V4LCaptureDevice<'a> {
         let device = match Device::new(index) {
             Ok(dev) => dev,
             Err(why) => {
-                return Err(NokhwaError::OpenDeviceError(
-                    index.to_string(),
-                    format!("V4L2 Error: {}", why.to_string()),
+                return Err(NokhwaError::from_io_error(
+                    &index.to_string(),
+                    &why,
+                    |error| {
+                        NokhwaError::OpenDeviceError(
+                            index.to_string(),
+                            format!("V4L2 Error: {}", error),
+                        )
+                    },
                 ))
             }
         };
 
         let camera_info = match device.query_caps() {
-            Ok(caps) => CameraInfo::new(caps.card, "".to_string(), caps.driver, index),
+            Ok(caps) => {
+                let mut camera_info =
+                    CameraInfo::new(caps.card, "".to_string(), caps.driver, index);
+                camera_info.fill_from_sysfs(index);
+                camera_info.guess_stream_kind_from_v4l(&device);
+                camera_info.read_sensor_orientation_from_v4l(&device);
+                camera_info
+            }
             Err(why) => {
                 return Err(NokhwaError::GetPropertyError {
                     property: "Capabilities".to_string(),
@@ -197,35 +490,26 @@ impl<'a> V4LCaptureDevice<'a> {
             None => CameraFormat::default(),
         };
 
-        let fourcc = match camera_format.format() {
-            FrameFormat::MJPEG => FourCC::new(b"MJPG"),
-            FrameFormat::YUYV => FourCC::new(b"YUYV"),
-        };
+        let fourcc = FourCC::from(camera_format.format());
 
         let new_param = Parameters::with_fps(camera_format.frame_rate());
         let new_v4l_fmt = Format::new(camera_format.width(), camera_format.height(), fourcc);
 
-        match Capture::set_format(&device, &new_v4l_fmt) {
+        let (color_space, stride) = match Capture::set_format(&device, &new_v4l_fmt) {
             Ok(v4l_fmt) => {
                 if v4l_fmt.height != new_v4l_fmt.height
                     && v4l_fmt.width != new_v4l_fmt.width
                     && v4l_fmt.fourcc != new_v4l_fmt.fourcc
                 {
-                    return Err(NokhwaError::SetPropertyError {
-                        property: "Format(V4L Resolution, FourCC)".to_string(),
-                        value: camera_format.to_string(),
+                    return Err(NokhwaError::UnsupportedFormat {
+                        format: camera_format.to_string(),
                         error: "Rejected".to_string(),
                     });
                 }
+                (format_color_space(&v4l_fmt), v4l_fmt.stride as usize)
             }
-            Err(why) => {
-                return Err(NokhwaError::SetPropertyError {
-                    property: "Format(V4L Resolution, FourCC)".to_string(),
-                    value: camera_format.to_string(),
-                    error: why.to_string(),
-                })
-            }
-        }
+            Err(why) => return Err(set_format_error(index, camera_format.to_string(), &why)),
+        };
 
         match Capture::set_params(&device, &new_param) {
             Ok(param) => {
@@ -251,6 +535,16 @@ impl<'a> V4LCaptureDevice<'a> {
             camera_info,
             device,
             stream_handle: None,
+            buffer_count: 4,
+            buffer_index: 0,
+            buffer_queued: false,
+            color_space,
+            color_space_override: None,
+            stride,
+            capture_metadata: false,
+            metadata_stream: None,
+            metadata: None,
+            access_mode: AccessMode::Shared,
         })
     }
 
@@ -269,10 +563,7 @@ impl<'a> V4LCaptureDevice<'a> {
     }
 
     fn get_resolution_list(&self, fourcc: FrameFormat) -> Result<Vec<Resolution>, NokhwaError> {
-        let format = match fourcc {
-            FrameFormat::MJPEG => FourCC::new(b"MJPG"),
-            FrameFormat::YUYV => FourCC::new(b"YUYV"),
-        };
+        let format = FourCC::from(fourcc);
 
         match v4l::video::Capture::enum_framesizes(&self.device, format) {
             Ok(frame_sizes) => {
@@ -298,6 +589,162 @@ impl<'a> V4LCaptureDevice<'a> {
         }
     }
 
+    // Gives the buffer we hold back to the driver (if we hold one) and dequeues the next filled buffer.
+    // With a `timeout`, this will not wait on the device for longer than it.
+    fn next_frame(&mut self, timeout: Option<Duration>) -> Result<Cow<[u8]>, NokhwaError> {
+        self.dequeue_frame(timeout)?;
+        self.current_frame()
+    }
+
+    fn dequeue_frame(&mut self, timeout: Option<Duration>) -> Result<(), NokhwaError> {
+        let stream = match &mut self.stream_handle {
+            Some(stream) => stream,
+            None => {
+                return Err(NokhwaError::ReadFrameError(
+                    "Stream not initialized! Please call \"open_stream()\" first!".to_string(),
+                ))
+            }
+        };
+
+        let device = self.camera_info.index().to_string();
+        // an unplugged camera shows up here first, as `ENODEV`
+        let read_error =
+            |why: &io::Error| NokhwaError::from_io_error(&device, why, NokhwaError::ReadFrameError);
+
+        if !self.buffer_queued {
+            if let Err(why) = CaptureStream::queue(stream, self.buffer_index) {
+                return Err(read_error(&why));
+            }
+            self.buffer_queued = true;
+        }
+
+        if let Some(timeout) = timeout {
+            match poll_readable(self.device.handle().fd(), timeout) {
+                Ok(true) => {}
+                Ok(false) => return Err(NokhwaError::Timeout(timeout)),
+                Err(why) => return Err(read_error(&why)),
+            }
+        }
+
+        match CaptureStream::dequeue(stream) {
+            Ok(index) => {
+                self.buffer_index = index;
+                self.buffer_queued = false;
+            }
+            Err(why) => return Err(read_error(&why)),
+        }
+
+        if self.capture_metadata {
+            self.metadata = Some(self.read_metadata()?);
+        }
+        Ok(())
+    }
+
+    // Whether the driver has a filled buffer for us, without waiting
+    fn frame_ready(&self) -> Result<bool, NokhwaError> {
+        poll_readable(self.device.handle().fd(), Duration::from_secs(0)).map_err(|why| {
+            NokhwaError::from_io_error(
+                &self.camera_info.index().to_string(),
+                &why,
+                NokhwaError::ReadFrameError,
+            )
+        })
+    }
+
+    // The buffer we hold
+    fn current_frame(&self) -> Result<Cow<[u8]>, NokhwaError> {
+        let data = self
+            .stream_handle
+            .as_ref()
+            .and_then(|stream| CaptureStream::get(stream, self.buffer_index));
+        match data {
+            Some(data) => Ok(Cow::from(data)),
+            None => Err(NokhwaError::ReadFrameError(
+                "Buffer index out of range! This is probably a bug, please report it!".to_string(),
+            )),
+        }
+    }
+
+    // The metadata of the buffer we hold. The metadata node finishes its buffer before the video node, so it is either ready or not coming.
+    #[allow(clippy::cast_sign_loss)]
+    fn read_metadata(&mut self) -> Result<FrameMetadata, NokhwaError> {
+        let mut metadata = FrameMetadata::new();
+        let mut sequence = None;
+        if let Some(buffer) = self
+            .stream_handle
+            .as_ref()
+            .and_then(|stream| CaptureStream::get_meta(stream, self.buffer_index))
+        {
+            let timestamp = Duration::from_secs(buffer.timestamp.sec.max(0) as u64)
+                + Duration::from_micros(buffer.timestamp.usec.max(0) as u64);
+            metadata = metadata
+                .with_sequence(buffer.sequence)
+                .with_timestamp(timestamp);
+            sequence = Some(buffer.sequence);
+        }
+
+        let metadata_stream = match &mut self.metadata_stream {
+            Some(metadata_stream) => metadata_stream,
+            None => return Ok(metadata),
+        };
+        let device = self.camera_info.index().to_string();
+        let read_error =
+            |why: &io::Error| NokhwaError::from_io_error(&device, why, NokhwaError::ReadFrameError);
+        let mut uvc_metadata = None;
+        // drain everything that is ready, as frames we dropped leave their metadata behind
+        while poll_readable(metadata_stream.device.handle().fd(), Duration::from_secs(0))
+            .map_err(|why| read_error(&why))?
+        {
+            let index = CaptureStream::dequeue(&mut metadata_stream.stream)
+                .map_err(|why| read_error(&why))?;
+            let buffer_sequence = CaptureStream::get_meta(&metadata_stream.stream, index)
+                .map(|buffer| buffer.sequence);
+            // the metadata node uses the sequence numbers of the video node
+            let (belongs, caught_up) = match (buffer_sequence, sequence) {
+                (Some(buffer_sequence), Some(sequence)) => {
+                    (buffer_sequence <= sequence, buffer_sequence >= sequence)
+                }
+                _ => (true, false),
+            };
+            if let Some(data) = CaptureStream::get(&metadata_stream.stream, index) {
+                if belongs {
+                    uvc_metadata = Some(parse_uvc_metadata(data, metadata));
+                }
+            }
+            CaptureStream::queue(&mut metadata_stream.stream, index)
+                .map_err(|why| read_error(&why))?;
+            if caught_up {
+                break;
+            }
+        }
+        Ok(uvc_metadata.unwrap_or(metadata))
+    }
+
+    // Opens the metadata node next to the video node, if there is one
+    fn open_metadata_stream(&mut self) -> Result<(), NokhwaError> {
+        self.metadata_stream = None;
+        let metadata_device = match find_metadata_device(&self.device) {
+            Some(metadata_device) => metadata_device,
+            None => return Ok(()),
+        };
+        let device = self.camera_info.index().to_string();
+        let open_error = |why: &io::Error| {
+            NokhwaError::from_io_error(&device, why, NokhwaError::OpenStreamError)
+        };
+        let mut stream =
+            MmapStream::with_buffers(&metadata_device, Type::MetaCapture, METADATA_BUFFER_COUNT)
+                .map_err(|why| open_error(&why))?;
+        for index in 0..METADATA_BUFFER_COUNT as usize {
+            CaptureStream::queue(&mut stream, index).map_err(|why| open_error(&why))?;
+        }
+        Stream::start(&mut stream).map_err(|why| open_error(&why))?;
+        self.metadata_stream = Some(MetadataStream {
+            device: metadata_device,
+            stream,
+        });
+        Ok(())
+    }
+
     /// Get the inner device (immutable) for e.g. Controls
     #[allow(clippy::must_use_candidate)]
     pub fn inner_device(&self) -> &Device {
@@ -308,6 +755,15 @@ impl<'a> V4LCaptureDevice<'a> {
     pub fn inner_device_mut(&mut self) -> &mut Device {
         &mut self.device
     }
+
+    /// Returns the file descriptor of the underlying V4L2 device, for `ioctl`s `nokhwa` does not cover.
+    /// # Safety
+    /// The file descriptor is owned by this device and is only valid for as long as it is alive. Do not close it.
+    /// Changing the format or buffers behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports and may break the stream.
+    #[must_use]
+    pub unsafe fn raw_handle(&self) -> std::os::raw::c_int {
+        self.device.handle().fd()
+    }
 }
 
 impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
@@ -324,6 +780,17 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
     }
 
     fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
+        // only the frame rate changed, most drivers can do that without reallocating the buffers
+        if self.stream_handle.is_some()
+            && new_fmt.resolution() == self.camera_format.resolution()
+            && new_fmt.format() == self.camera_format.format()
+            && Capture::set_params(&self.device, &Parameters::with_fps(new_fmt.frame_rate()))
+                .is_ok()
+        {
+            self.camera_format = new_fmt;
+            return Ok(());
+        }
+
         let prev_format = match Capture::format(&self.device) {
             Ok(fmt) => fmt,
             Err(why) => {
@@ -346,14 +813,31 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
         let format: Format = new_fmt.into();
         let frame_rate = Parameters::with_fps(new_fmt.frame_rate());
 
-        if let Err(why) = Capture::set_format(&self.device, &format) {
-            return Err(NokhwaError::SetPropertyError {
-                property: "Resolution, FrameFormat".to_string(),
-                value: format.to_string(),
-                error: why.to_string(),
-            });
+        // the format cannot be changed while buffers are allocated, so the stream has to go first
+        let reopen = self.stream_handle.take().is_some();
+
+        match Capture::set_format(&self.device, &format) {
+            Ok(v4l_fmt) => {
+                self.color_space = format_color_space(&v4l_fmt);
+                self.stride = v4l_fmt.stride as usize;
+            }
+            Err(why) => {
+                if reopen {
+                    // try to get the old stream back, the error below is what matters
+                    let _ = self.open_stream();
+                }
+                return Err(set_format_error(
+                    *self.camera_info.index(),
+                    format.to_string(),
+                    &why,
+                ));
+            }
         }
         if let Err(why) = Capture::set_params(&self.device, &frame_rate) {
+            if reopen {
+                let _ = Capture::set_format(&self.device, &prev_format);
+                let _ = self.open_stream();
+            }
             return Err(NokhwaError::SetPropertyError {
                 property: "Frame rate".to_string(),
                 value: frame_rate.to_string(),
@@ -361,7 +845,7 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
             });
         }
 
-        if self.stream_handle.is_some() {
+        if reopen {
             return match self.open_stream() {
                 Ok(_) => Ok(()),
                 Err(why) => {
@@ -394,10 +878,7 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
         fourcc: FrameFormat,
     ) -> Result<HashMap<Resolution, Vec<u32>>, NokhwaError> {
         let resolutions = self.get_resolution_list(fourcc)?;
-        let format = match fourcc {
-            FrameFormat::MJPEG => FourCC::new(b"MJPG"),
-            FrameFormat::YUYV => FourCC::new(b"YUYV"),
-        };
+        let format = FourCC::from(fourcc);
         let mut res_map = HashMap::new();
         for res in resolutions {
             let mut compatible_fps = vec![];
@@ -432,19 +913,9 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
             Ok(formats) => {
                 let mut frame_format_vec = vec![];
                 for format in formats {
-                    let format_as_string = match format.fourcc.str() {
-                        Ok(s) => s,
-                        Err(why) => {
-                            return Err(NokhwaError::GetPropertyError {
-                                property: "FrameFormat".to_string(),
-                                error: why.to_string(),
-                            })
-                        }
-                    };
-                    match format_as_string {
-                        "YUYV" => frame_format_vec.push(FrameFormat::YUYV),
-                        "MJPG" => frame_format_vec.push(FrameFormat::MJPEG),
-                        _ => {}
+                    // formats nokhwa cannot decode are left out
+                    if let Ok(frame_format) = FrameFormat::try_from(format.fourcc) {
+                        frame_format_vec.push(frame_format);
                     }
                 }
                 frame_format_vec.sort();
@@ -491,6 +962,15 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
         self.set_camera_format(new_fmt)
     }
 
+    fn color_space(&self) -> ColorSpace {
+        self.color_space_override.unwrap_or(self.color_space)
+    }
+
+    fn set_color_space(&mut self, color_space: Option<ColorSpace>) -> Result<(), NokhwaError> {
+        self.color_space_override = color_space;
+        Ok(())
+    }
+
     fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControls>, NokhwaError> {
         let v4l2_controls = match self.device.query_controls() {
             Ok(controls) => controls,
@@ -657,55 +1137,207 @@ impl<'a> CaptureBackendTrait for V4LCaptureDevice<'a> {
     }
 
     fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        let stream = match MmapStream::new(&self.device, Type::VideoCapture) {
-            Ok(s) => s,
-            Err(why) => return Err(NokhwaError::OpenStreamError(why.to_string())),
+        // drop the old stream first, the device will refuse to allocate buffers twice
+        self.stream_handle = None;
+        self.metadata_stream = None;
+        let device = self.camera_info.index().to_string();
+        // another process streaming from the device shows up here, as `EBUSY`
+        let open_error = |why: &io::Error| {
+            NokhwaError::from_io_error(&device, why, NokhwaError::OpenStreamError)
         };
+        let mut stream =
+            match MmapStream::with_buffers(&self.device, Type::VideoCapture, self.buffer_count) {
+                Ok(s) => s,
+                Err(why) => return Err(open_error(&why)),
+            };
+        // hand every buffer to the driver so it can keep capturing while we hold one
+        for index in 0..self.buffer_count as usize {
+            if let Err(why) = CaptureStream::queue(&mut stream, index) {
+                // the driver may have given us fewer buffers than we asked for
+                if index == 0 {
+                    return Err(open_error(&why));
+                }
+                break;
+            }
+        }
+        if let Err(why) = Stream::start(&mut stream) {
+            return Err(open_error(&why));
+        }
         self.stream_handle = Some(stream);
+        self.buffer_index = 0;
+        self.buffer_queued = true;
+        self.metadata = None;
+        if self.capture_metadata {
+            self.open_metadata_stream()?;
+        }
         Ok(())
     }
 
+    fn set_buffer_count(&mut self, count: u32) -> Result<(), NokhwaError> {
+        if count == 0 {
+            return Err(NokhwaError::SetPropertyError {
+                property: "Buffer Count".to_string(),
+                value: count.to_string(),
+                error: "Need at least 1 buffer".to_string(),
+            });
+        }
+        self.buffer_count = count;
+        Ok(())
+    }
+
+    fn set_frame_metadata(&mut self, enabled: bool) -> Result<(), NokhwaError> {
+        self.capture_metadata = enabled;
+        if !enabled {
+            self.metadata = None;
+        }
+        Ok(())
+    }
+
+    fn frame_metadata(&self) -> Option<FrameMetadata> {
+        self.metadata
+    }
+
+    fn access_mode(&self) -> Result<AccessMode, NokhwaError> {
+        Ok(self.access_mode)
+    }
+
+    fn set_access_mode(&mut self, mode: AccessMode) -> Result<(), NokhwaError> {
+        let priority = match mode {
+            AccessMode::Exclusive => PRIORITY_RECORD,
+            AccessMode::Shared => PRIORITY_INTERACTIVE,
+        };
+        set_priority(self.device.handle().fd(), priority).map_err(|why| {
+            NokhwaError::from_io_error(&self.camera_info.index().to_string(), &why, |error| {
+                NokhwaError::SetPropertyError {
+                    property: "Priority".to_string(),
+                    value: mode.to_string(),
+                    error,
+                }
+            })
+        })?;
+        self.access_mode = mode;
+        Ok(())
+    }
+
+    fn hardware_crop_bounds(&self) -> Result<Rect, NokhwaError> {
+        selection(self.device.handle().fd(), V4L2_SEL_TGT_CROP_BOUNDS, None).map_err(|why| {
+            selection_error(self.backend(), *self.camera_info.index(), &why, |error| {
+                NokhwaError::GetPropertyError {
+                    property: "Crop Bounds".to_string(),
+                    error,
+                }
+            })
+        })
+    }
+
+    fn set_hardware_crop(&mut self, rect: Rect) -> Result<Rect, NokhwaError> {
+        let applied =
+            selection(self.device.handle().fd(), V4L2_SEL_TGT_CROP, Some(rect)).map_err(|why| {
+                selection_error(self.backend(), *self.camera_info.index(), &why, |error| {
+                    NokhwaError::SetPropertyError {
+                        property: "Crop".to_string(),
+                        value: format!("{:?}", rect),
+                        error,
+                    }
+                })
+            })?;
+
+        let format = self.device.format().map_err(|why| {
+            NokhwaError::from_io_error(&self.camera_info.index().to_string(), &why, |error| {
+                NokhwaError::GetPropertyError {
+                    property: "Resolution, FrameFormat".to_string(),
+                    error,
+                }
+            })
+        })?;
+        self.camera_format
+            .set_resolution(Resolution::new(format.width, format.height));
+        self.stride = format.stride as usize;
+        Ok(applied)
+    }
+
     fn is_stream_open(&self) -> bool {
         self.stream_handle.is_some()
     }
 
     fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        let cam_fmt = self.camera_format;
-        let raw_frame = self.frame_raw()?;
-        let conv = match cam_fmt.format() {
-            FrameFormat::MJPEG => mjpeg_to_rgb888(&raw_frame)?,
-            FrameFormat::YUYV => yuyv422_to_rgb888(&raw_frame)?,
-        };
-        let image_buf =
-            match ImageBuffer::from_vec(cam_fmt.width(), cam_fmt.height(), conv) {
-                Some(buf) => {
-                    let rgb_buf: ImageBuffer<Rgb<u8>, Vec<u8>> = buf;
-                    rgb_buf
-                }
-                None => return Err(NokhwaError::ReadFrameError(
-                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
-                        .to_string(),
-                )),
-            };
-        Ok(image_buf)
+        let frame = self.undecoded_frame()?;
+        DefaultDecoder.decode_rgb(&frame)
     }
 
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        match &mut self.stream_handle {
-            Some(stream_handler) => match stream_handler.next() {
-                Ok((data, _)) => Ok(Cow::from(data)),
-                Err(why) => Err(NokhwaError::ReadFrameError(why.to_string())),
-            },
-            None => Err(NokhwaError::ReadFrameError(
-                "Stream not initialized! Please call \"open_stream()\" first!".to_string(),
-            )),
+        self.next_frame(None)
+    }
+
+    fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        let camera_format = self.camera_format;
+        let stride = self.frame_stride();
+        let color_space = self.color_space();
+        let stream_kind = match camera_format.format() {
+            FrameFormat::Z16 => StreamKind::Depth,
+            _ => self.camera_info.stream_kind(),
+        };
+        self.dequeue_frame(None)?;
+        let metadata = self.metadata;
+        let frame = RawFrame::new(
+            self.current_frame()?,
+            camera_format.format(),
+            camera_format.resolution(),
+            stride,
+            color_space,
+        )
+        .with_stream_kind(stream_kind);
+        Ok(match metadata {
+            Some(metadata) => frame.with_metadata(metadata),
+            None => frame,
+        })
+    }
+
+    fn frame_stride(&self) -> Option<usize> {
+        match self
+            .camera_format
+            .format()
+            .row_bytes(self.camera_format.width())
+        {
+            Some(row_bytes) if row_bytes != self.stride => Some(self.stride),
+            _ => None,
         }
     }
 
+    fn frame_with_deadline(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let cam_fmt = self.camera_format;
+        let color_space = self.color_space();
+        let stride = self.frame_stride();
+        self.dequeue_frame(Some(deadline))?;
+        // more frames may have piled up in the driver, skip to the newest
+        while self.frame_ready()? {
+            self.dequeue_frame(Some(Duration::from_secs(0)))?;
+        }
+        DefaultDecoder.decode_rgb(&RawFrame::new(
+            self.current_frame()?,
+            cam_fmt.format(),
+            cam_fmt.resolution(),
+            stride,
+            color_space,
+        ))
+    }
+
+    fn frame_raw_with_deadline(&mut self, deadline: Duration) -> Result<Cow<[u8]>, NokhwaError> {
+        self.next_frame(Some(deadline))
+    }
+
     fn stop_stream(&mut self) -> Result<(), NokhwaError> {
         if self.stream_handle.is_some() {
             self.stream_handle = None;
         }
+        self.metadata_stream = None;
         Ok(())
     }
+
+    unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        Some(RawHandle::Video4Linux(V4LCaptureDevice::raw_handle(self)))
+    }
 }
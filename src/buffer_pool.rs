@@ -0,0 +1,218 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{FrameMetadata, Resolution};
+use image::{ImageBuffer, Rgb};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+// a 60 FPS stream with a frame in the consumer, one being decoded and a little slack
+const DEFAULT_POOL_CAPACITY: usize = 4;
+
+static POOL: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+static POOL_CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_POOL_CAPACITY);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static REUSES: AtomicU64 = AtomicU64::new(0);
+
+fn pool() -> MutexGuard<'static, Vec<Vec<u8>>> {
+    match POOL.lock() {
+        Ok(pool) => pool,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Takes an empty buffer that can hold at least `capacity` bytes out of the pool, or allocates one if none fits.
+pub(crate) fn take_buffer(capacity: usize) -> Vec<u8> {
+    let reused = {
+        let mut pool = pool();
+        // the smallest one that fits, so large buffers stay around for large frames
+        let fitting = pool
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(index, _)| index);
+        fitting.map(|index| pool.swap_remove(index))
+    };
+    if let Some(mut buffer) = reused {
+        REUSES.fetch_add(1, Ordering::Relaxed);
+        buffer.clear();
+        return buffer;
+    }
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    Vec::with_capacity(capacity)
+}
+
+/// Copies `data` into a buffer from the pool.
+pub(crate) fn pooled_copy(data: &[u8]) -> Vec<u8> {
+    let mut buffer = take_buffer(data.len());
+    buffer.extend_from_slice(data);
+    buffer
+}
+
+/// Puts `buffer` back into the pool, or frees it if the pool is full.
+pub(crate) fn recycle_buffer(buffer: Vec<u8>) {
+    if buffer.capacity() == 0 {
+        return;
+    }
+    let mut pool = pool();
+    if pool.len() < POOL_CAPACITY.load(Ordering::Relaxed) {
+        pool.push(buffer);
+    }
+}
+
+/// Sets how many idle frame buffers the pool keeps around for reuse. Defaults to 4. Use 0 to turn pooling off.
+///
+/// The pool is shared by the decoders (e.g. [`mjpeg_to_rgb888()`](crate::mjpeg_to_rgb888)) and backends. Buffers come back to it when a [`Frame`] is dropped.
+/// If there are more frames in flight than this (e.g. queued for an encoder), raise it, or the extra buffers are freed and allocated again.
+pub fn set_buffer_pool_capacity(capacity: usize) {
+    POOL_CAPACITY.store(capacity, Ordering::Relaxed);
+    pool().truncate(capacity);
+}
+
+/// How many idle frame buffers the pool keeps around for reuse.
+#[must_use]
+pub fn buffer_pool_capacity() -> usize {
+    POOL_CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Frees all idle buffers in the pool.
+pub fn clear_buffer_pool() {
+    pool().clear();
+}
+
+/// How well the frame buffer pool is doing, see [`buffer_pool_stats()`].
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub struct BufferPoolStats {
+    allocations: u64,
+    reuses: u64,
+    idle: usize,
+}
+
+impl BufferPoolStats {
+    /// How many buffers had to be allocated because none in the pool fit.
+    #[must_use]
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// How many buffers were reused from the pool.
+    #[must_use]
+    pub fn reuses(&self) -> u64 {
+        self.reuses
+    }
+
+    /// How many buffers are idle in the pool right now.
+    #[must_use]
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+}
+
+/// Gets the [`BufferPoolStats`] since the program started. If allocations keep growing while capturing, raise the [capacity](set_buffer_pool_capacity) or drop frames sooner.
+#[must_use]
+pub fn buffer_pool_stats() -> BufferPoolStats {
+    BufferPoolStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        reuses: REUSES.load(Ordering::Relaxed),
+        idle: pool().len(),
+    }
+}
+
+/// A RGB888 frame in a pooled buffer, which goes back to the buffer pool when the [`Frame`] is dropped, so the next frame does not have to allocate.
+/// It dereferences to the raw RGB data.
+///
+/// Get one with [`Camera::frame_pooled()`](crate::Camera::frame_pooled), or make one from any [`ImageBuffer`] with [`From`].
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    resolution: Resolution,
+    // only `None` while being turned into an image
+    data: Option<Vec<u8>>,
+    metadata: Option<FrameMetadata>,
+}
+
+impl Frame {
+    /// Attaches the [`FrameMetadata`] the camera reported for the frame.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: FrameMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// The resolution of the frame.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The [`FrameMetadata`] of the frame, if capturing it is on (see [`Camera::set_frame_metadata()`](crate::Camera::set_frame_metadata)).
+    #[must_use]
+    pub fn metadata(&self) -> Option<FrameMetadata> {
+        self.metadata
+    }
+
+    /// The frame as an [`ImageBuffer`] borrowing the pooled buffer.
+    /// # Panics
+    /// Only if the buffer does not match the resolution, which it always does.
+    #[must_use]
+    pub fn as_image(&self) -> ImageBuffer<Rgb<u8>, &[u8]> {
+        ImageBuffer::from_raw(self.resolution.width(), self.resolution.height(), &**self)
+            .expect("Frame buffer does not match its resolution")
+    }
+
+    /// Takes the buffer out of the pool's hands as an [`ImageBuffer`]. It will not be reused (unless it is turned back into a [`Frame`]).
+    /// # Panics
+    /// Only if the buffer does not match the resolution, which it always does.
+    #[must_use]
+    pub fn into_image(mut self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let data = self.data.take().unwrap_or_default();
+        ImageBuffer::from_raw(self.resolution.width(), self.resolution.height(), data)
+            .expect("Frame buffer does not match its resolution")
+    }
+}
+
+impl From<ImageBuffer<Rgb<u8>, Vec<u8>>> for Frame {
+    fn from(image: ImageBuffer<Rgb<u8>, Vec<u8>>) -> Self {
+        Frame {
+            resolution: Resolution::new(image.width(), image.height()),
+            data: Some(image.into_raw()),
+            metadata: None,
+        }
+    }
+}
+
+impl Deref for Frame {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.data {
+            Some(data) => data,
+            None => &[],
+        }
+    }
+}
+
+impl DerefMut for Frame {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.data {
+            Some(data) => data,
+            None => &mut [],
+        }
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            recycle_buffer(data);
+        }
+    }
+}
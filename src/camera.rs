@@ -4,23 +4,74 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+#[cfg(feature = "input-opencv")]
+use crate::rgb888_buffer_to_bgr_mat;
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::wgpu_api::{Device as WgpuDevice, Queue as WgpuQueue, Texture as WgpuTexture};
+#[cfg(feature = "output-ash")]
+use crate::VulkanFrameUploader;
 use crate::{
-    CameraFormat, CameraInfo, CaptureAPIBackend, CaptureBackendTrait, FrameFormat, NokhwaError,
-    Resolution,
+    camera_traits::write_rgb_to_buffer,
+    journal::{JournalEvent, SessionJournal},
+    p010_to_luma16, p010_to_rgb48_with, pack_frame_rows,
+    quirks::{self, Quirk},
+    sink::{SinkPayload, SinkTransform},
+    snapshot::encode_snapshot,
+    stats::StatsTracker,
+    telemetry,
+    transform::{self, Mirror, Rect, ResizeFilter, ResizeMode, Rotation, RotationHandling},
+    y16_to_luma16, z16_to_depth, AccessMode, CameraControl, CameraFormat, CameraInfo,
+    CaptureAPIBackend, CaptureBackendTrait, CaptureStats, ColorSpace, CorruptFrameHandling, Frame,
+    FrameAccess, FrameDecoder, FrameFormat, FrameMetadata, KnownCameraControls, NokhwaError,
+    RawFrame, RawHandle, Resolution, MAX_SKIPPED_CORRUPT_FRAMES,
 };
-use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbaImage};
-use std::{borrow::Cow, collections::HashMap};
-#[cfg(feature = "output-wgpu")]
-use wgpu::{
-    Device as WgpuDevice, Extent3d, ImageCopyTexture, ImageDataLayout, Queue as WgpuQueue,
-    Texture as WgpuTexture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
+#[cfg(feature = "output-rgb565")]
+use crate::{
+    debayer, mjpeg_to_rgb565, p010_to_rgb888_with, rgb888_to_rgb565, utils::passthrough_error,
+    y16_to_rgb888, yuyv422_to_rgb565_into_with, z16_to_rgb888,
+};
+#[cfg(feature = "output-ndarray")]
+use crate::{rgb_to_ndarray_chw, rgb_to_ndarray_hwc, Normalization};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::{texture::frame_to_texture, TextureOptions};
+use image::{buffer::ConvertBuffer, ImageBuffer, ImageFormat, Luma, Rgb, RgbaImage};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::RwLock,
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
+// how early a frame may arrive and still count as due under an output frame rate limit, as frames jitter
+const FPS_LIMIT_SLACK: Duration = Duration::from_millis(5);
+
 /// The main `Camera` struct. This is the struct that abstracts over all the backends, providing a simplified interface for use.
 pub struct Camera {
     idx: usize,
     backend: Box<dyn CaptureBackendTrait>,
     backend_api: CaptureAPIBackend,
+    last_frame: Option<Instant>,
+    timeout: Option<Duration>,
+    rotation: Rotation,
+    rotation_handling: RotationHandling,
+    auto_rotate: bool,
+    sensor_orientation: Rotation,
+    mirror: Mirror,
+    crop: Option<Rect>,
+    output_resolution: Option<Resolution>,
+    output_fps_limit: Option<f32>,
+    next_output: Option<Instant>,
+    resize_filter: ResizeFilter,
+    resize_mode: ResizeMode,
+    journal: Option<SessionJournal>,
+    sink_transform: Option<Box<dyn SinkTransform>>,
+    decoder: Option<Box<dyn FrameDecoder>>,
+    corrupt_frame_handling: CorruptFrameHandling,
+    last_good_frame: Option<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    stats: StatsTracker,
 }
 
 #[allow(clippy::nonminimal_bool)]
@@ -34,11 +85,31 @@ impl Camera {
         backend: CaptureAPIBackend,
     ) -> Result<Self, NokhwaError> {
         let camera_backend = init_camera(index, format, backend)?;
+        let sensor_orientation = camera_backend.camera_info().sensor_orientation();
 
         Ok(Camera {
             idx: index,
             backend: camera_backend,
             backend_api: backend,
+            last_frame: None,
+            timeout: None,
+            rotation: Rotation::None,
+            rotation_handling: RotationHandling::Pixels,
+            auto_rotate: false,
+            sensor_orientation,
+            mirror: Mirror::None,
+            crop: None,
+            output_resolution: None,
+            output_fps_limit: None,
+            next_output: None,
+            resize_filter: ResizeFilter::Bilinear,
+            resize_mode: ResizeMode::CropAndScale,
+            journal: None,
+            sink_transform: None,
+            decoder: None,
+            corrupt_frame_handling: CorruptFrameHandling::Error,
+            last_good_frame: None,
+            stats: StatsTracker::new(),
         })
     }
 
@@ -57,6 +128,41 @@ impl Camera {
         Camera::new(index, Some(camera_format), backend)
     }
 
+    /// Create a new `Camera` from an already created backend, such as one of the structs in [`backends::capture`](crate::backends::capture), your own [`CaptureBackendTrait`] implementation, or a mock for testing.
+    ///
+    /// The index and backend are taken from the backend's [`CameraInfo`] and [`backend()`](CaptureBackendTrait::backend).
+    /// # Quirks
+    /// - If the backend is [`CaptureAPIBackend::Custom`], [`set_index()`](Camera::set_index) will error since `nokhwa` does not know how to re-create it.
+    #[must_use]
+    pub fn from_backend(backend: Box<dyn CaptureBackendTrait>) -> Self {
+        let backend_api = backend.backend();
+        let sensor_orientation = backend.camera_info().sensor_orientation();
+        Camera {
+            idx: *backend.camera_info().index(),
+            backend,
+            backend_api,
+            last_frame: None,
+            timeout: None,
+            rotation: Rotation::None,
+            rotation_handling: RotationHandling::Pixels,
+            auto_rotate: false,
+            sensor_orientation,
+            mirror: Mirror::None,
+            crop: None,
+            output_resolution: None,
+            output_fps_limit: None,
+            next_output: None,
+            resize_filter: ResizeFilter::Bilinear,
+            resize_mode: ResizeMode::CropAndScale,
+            journal: None,
+            sink_transform: None,
+            decoder: None,
+            corrupt_frame_handling: CorruptFrameHandling::Error,
+            last_good_frame: None,
+            stats: StatsTracker::new(),
+        }
+    }
+
     /// Gets the current Camera's index.
     #[must_use]
     pub fn index(&self) -> usize {
@@ -73,15 +179,294 @@ impl Camera {
         let new_camera_format = self.backend.camera_format();
         let new_camera = init_camera(new_idx, Some(new_camera_format), self.backend_api)?;
         self.backend = new_camera;
+        self.idx = new_idx;
+        self.sensor_orientation = self.backend.camera_info().sensor_orientation();
+        self.last_frame = None;
+        self.stats.restart_window();
+        telemetry::record_reconnect(self.active_backend(), self.idx);
         Ok(())
     }
 
-    /// Gets the current Camera's backend
+    /// Gets the timeout used by [`frame()`](Camera::frame), if any.
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the timeout used by [`frame()`](Camera::frame). `None` (the default) blocks until a frame arrives.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Gets the [`Rotation`] applied to frames.
+    #[must_use]
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Sets the clockwise [`Rotation`] applied to frames from [`frame()`](Camera::frame) and [`frame_with_deadline()`](Camera::frame_with_deadline). Raw frames are never rotated.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    /// Gets how the [`Rotation`] is handled.
+    #[must_use]
+    pub fn rotation_handling(&self) -> RotationHandling {
+        self.rotation_handling
+    }
+
+    /// Sets how the [`Rotation`] is handled. With [`RotationHandling::Metadata`], frames are not rotated and the rotation is reported through [`pending_rotation()`](Camera::pending_rotation) instead.
+    ///
+    /// The rotation travels with frames from `CameraThread` and [`FrameBus`](crate::FrameBus) (see [`BusFrame::rotation()`](crate::BusFrame::rotation)), and `Recorder` writes it into MP4 files.
+    /// Sinks that cannot carry it (see [`frame_for_sink()`](Camera::frame_for_sink)) still get rotated pixels.
+    pub fn set_rotation_handling(&mut self, handling: RotationHandling) {
+        self.rotation_handling = handling;
+    }
+
+    /// The rotation that the consumer still has to apply to frames from [`frame()`](Camera::frame), e.g. as a WebRTC or MP4 rotation flag. This is [`Rotation::None`] unless the [`rotation_handling()`](Camera::rotation_handling) is [`RotationHandling::Metadata`].
+    #[must_use]
+    pub fn pending_rotation(&self) -> Rotation {
+        match self.rotation_handling {
+            RotationHandling::Pixels => Rotation::None,
+            RotationHandling::Metadata => self.output_rotation(),
+        }
+    }
+
+    /// The clockwise rotation that turns frames upright, as the OS reports it for the camera. See [`CameraInfo::sensor_orientation()`].
+    #[must_use]
+    pub fn sensor_orientation(&self) -> Rotation {
+        self.sensor_orientation
+    }
+
+    /// Gets whether the [`sensor_orientation()`](Camera::sensor_orientation) is applied to frames.
+    #[must_use]
+    pub fn auto_rotate(&self) -> bool {
+        self.auto_rotate
+    }
+
+    /// Sets whether the [`sensor_orientation()`](Camera::sensor_orientation) is applied to frames, on top of the [`rotation()`](Camera::rotation). It is handled the same way as the rotation, see [`set_rotation_handling()`](Camera::set_rotation_handling). Off by default.
+    pub fn set_auto_rotate(&mut self, auto_rotate: bool) {
+        self.auto_rotate = auto_rotate;
+    }
+
+    // the rotation set, plus the sensor orientation if it is applied automatically
+    fn output_rotation(&self) -> Rotation {
+        if self.auto_rotate {
+            self.sensor_orientation.then(self.rotation)
+        } else {
+            self.rotation
+        }
+    }
+
+    /// Gets the [`Mirror`] applied to frames.
+    #[must_use]
+    pub fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    /// Sets the [`Mirror`] applied to frames from [`frame()`](Camera::frame) and [`frame_with_deadline()`](Camera::frame_with_deadline). Raw frames are never mirrored.
+    pub fn set_mirror(&mut self, mirror: Mirror) {
+        self.mirror = mirror;
+    }
+
+    /// Gets the [`Rect`] frames are cropped to, if any.
+    #[must_use]
+    pub fn crop(&self) -> Option<Rect> {
+        self.crop
+    }
+
+    /// Sets the [`Rect`] frames from [`frame()`](Camera::frame) and [`frame_with_deadline()`](Camera::frame_with_deadline) are cropped to, or `None` to not crop. Raw frames are never cropped.
+    ///
+    /// The [`Rect`] is in the coordinates of the captured frame, before it is mirrored or rotated. The part of it outside the frame is ignored.
+    /// # Errors
+    /// If the [`Rect`] is empty (0 on any axis), this will error.
+    pub fn set_crop(&mut self, crop: Option<Rect>) -> Result<(), NokhwaError> {
+        if let Some(rect) = crop {
+            if rect.is_empty() {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "Crop".to_string(),
+                    value: rect.to_string(),
+                    error: "Crop rect is empty".to_string(),
+                });
+            }
+        }
+        self.crop = crop;
+        Ok(())
+    }
+
+    /// Gets the [`Resolution`] frames are resized to, if any.
+    #[must_use]
+    pub fn output_resolution(&self) -> Option<Resolution> {
+        self.output_resolution
+    }
+
+    /// Sets the [`Resolution`] frames from [`frame()`](Camera::frame) and [`frame_with_deadline()`](Camera::frame_with_deadline) are resized to in software, or `None` to not resize. Raw frames are never resized.
+    /// Use this when the camera does not offer the size you need, otherwise prefer [`set_resolution()`](Camera::set_resolution).
+    ///
+    /// This is the size of the frame *after* the [`Rotation`], even if the rotation is left to the consumer with [`RotationHandling::Metadata`]. Frames that already have this size are not touched.
+    /// # Errors
+    /// If the [`Resolution`] is 0 on any axis, this will error.
+    pub fn set_output_resolution(
+        &mut self,
+        resolution: Option<Resolution>,
+    ) -> Result<(), NokhwaError> {
+        if let Some(res) = resolution {
+            if res.width() == 0 || res.height() == 0 {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "Output Resolution".to_string(),
+                    value: res.to_string(),
+                    error: "Resolution is 0 on an axis".to_string(),
+                });
+            }
+        }
+        self.output_resolution = resolution;
+        Ok(())
+    }
+
+    /// Gets the frame rate [`frame()`](Camera::frame) is limited to, if any.
+    #[must_use]
+    pub fn output_fps_limit(&self) -> Option<f32> {
+        self.output_fps_limit
+    }
+
+    /// Limits [`frame()`](Camera::frame) (and the functions built on it) to at most `fps` frames per second, whatever the camera's frame rate, or `None` to not limit it. Raw frames are never limited.
+    /// This way e.g. a 30 FPS camera can feed ML inference at 5 FPS, without the application skipping frames itself.
+    ///
+    /// When [`frame()`](Camera::frame) is called before the next frame is due, it reads the frames in between and throws them away, then returns the next one.
+    /// The frames thrown away are read like any other: they count in the [`CaptureStats`] and each one is bound by the [`timeout()`](Camera::timeout).
+    /// A frame that is taken late restarts the schedule, so a slow consumer gets frames as soon as it asks for them.
+    /// # Errors
+    /// If `fps` is below 0.001, or not a number, this will error.
+    /// # Quirks
+    /// - Frames from [`frame_with_deadline()`](Camera::frame_with_deadline) are not limited.
+    /// - The frames thrown away are not decoded, unless a [`timeout()`](Camera::timeout) is set and the backend supports [`frame_with_deadline()`](CaptureBackendTrait::frame_with_deadline), which decodes.
+    /// - Which frames are thrown away is worked out from the frame rate of the [`CameraFormat`]. If the camera runs slower than that (e.g. in low light), the limit may be overshot slightly.
+    pub fn set_output_fps_limit(&mut self, fps: Option<f32>) -> Result<(), NokhwaError> {
+        if let Some(fps) = fps {
+            if !(fps.is_finite() && fps >= 0.001) {
+                return Err(NokhwaError::SetPropertyError {
+                    property: "Output FPS Limit".to_string(),
+                    value: fps.to_string(),
+                    error: "FPS limit must be at least 0.001".to_string(),
+                });
+            }
+        }
+        self.output_fps_limit = fps;
+        self.next_output = None;
+        Ok(())
+    }
+
+    /// Gets the [`ResizeFilter`] used for [`set_output_resolution()`](Camera::set_output_resolution).
+    #[must_use]
+    pub fn resize_filter(&self) -> ResizeFilter {
+        self.resize_filter
+    }
+
+    /// Sets the [`ResizeFilter`] used for [`set_output_resolution()`](Camera::set_output_resolution). The default is [`ResizeFilter::Bilinear`].
+    pub fn set_resize_filter(&mut self, filter: ResizeFilter) {
+        self.resize_filter = filter;
+    }
+
+    /// Gets the [`ResizeMode`] used for [`set_output_resolution()`](Camera::set_output_resolution).
+    #[must_use]
+    pub fn resize_mode(&self) -> ResizeMode {
+        self.resize_mode
+    }
+
+    /// Sets the [`ResizeMode`] used for [`set_output_resolution()`](Camera::set_output_resolution). The default is [`ResizeMode::CropAndScale`].
+    pub fn set_resize_mode(&mut self, mode: ResizeMode) {
+        self.resize_mode = mode;
+    }
+
+    /// Sets the amount of buffers the backend should capture into. More buffers means frames are less likely to be dropped when processing is slow, at the cost of memory and latency.
+    /// This takes effect the next time the stream is opened.
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError). It may also reject the count (e.g. 0).
+    pub fn set_buffer_count(&mut self, count: u32) -> Result<(), NokhwaError> {
+        self.backend.set_buffer_count(count)
+    }
+
+    /// The [`AccessMode`] the device is held in. [`AccessMode::Shared`] means other applications may be using the camera at the same time. See [`CaptureBackendTrait::access_mode()`].
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError).
+    pub fn access_mode(&self) -> Result<AccessMode, NokhwaError> {
+        self.backend.access_mode()
+    }
+
+    /// Sets the [`AccessMode`] the device is held in, see [`CaptureBackendTrait::set_access_mode()`]. Use [`CameraBuilder::access_mode()`](crate::CameraBuilder::access_mode) to set it at open time.
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError). It will also error if another application holds the camera in a way that conflicts with `mode`.
+    pub fn set_access_mode(&mut self, mode: AccessMode) -> Result<(), NokhwaError> {
+        self.backend.set_access_mode(mode)
+    }
+
+    /// The area of the sensor that [`set_hardware_crop()`](Camera::set_hardware_crop) can crop to. See [`CaptureBackendTrait::hardware_crop_bounds()`].
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError).
+    pub fn hardware_crop_bounds(&self) -> Result<Rect, NokhwaError> {
+        self.backend.hardware_crop_bounds()
+    }
+
+    /// Crops on the sensor itself, which unlike [`set_crop()`](Camera::set_crop) can raise the frame rate. Returns the crop the driver actually applied.
+    /// See [`CaptureBackendTrait::set_hardware_crop()`].
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError). The driver may also reject the crop.
+    pub fn set_hardware_crop(&mut self, rect: Rect) -> Result<Rect, NokhwaError> {
+        self.backend.set_hardware_crop(rect)
+    }
+
+    /// Turns capturing [`FrameMetadata`] along with every frame on or off. This takes effect the next time the stream is opened. See [`CaptureBackendTrait::set_frame_metadata()`].
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError).
+    pub fn set_frame_metadata(&mut self, enabled: bool) -> Result<(), NokhwaError> {
+        self.backend.set_frame_metadata(enabled)
+    }
+
+    /// The [`FrameMetadata`] of the last frame captured, if capturing it is on. [`undecoded_frame()`](Camera::undecoded_frame) also carries it.
+    #[must_use]
+    pub fn frame_metadata(&self) -> Option<FrameMetadata> {
+        self.backend.frame_metadata()
+    }
+
+    /// The bytes from the start of one row of [`frame_raw()`](Camera::frame_raw) to the next, if the driver pads rows. See [`CaptureBackendTrait::frame_stride()`].
+    #[must_use]
+    pub fn frame_stride(&self) -> Option<usize> {
+        self.backend.frame_stride()
+    }
+
+    /// The [`ColorSpace`] YUV frames are decoded in. See [`CaptureBackendTrait::color_space()`].
+    #[must_use]
+    pub fn color_space(&self) -> ColorSpace {
+        self.backend.color_space()
+    }
+
+    /// Overrides the [`ColorSpace`] YUV frames are decoded in, for cameras that report the wrong one (or none). `None` goes back to the one the driver reports.
+    /// # Errors
+    /// If the backend does not support this, it will return a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError).
+    pub fn set_color_space(&mut self, color_space: Option<ColorSpace>) -> Result<(), NokhwaError> {
+        self.backend.set_color_space(color_space)
+    }
+
+    /// Gets the current Camera's backend, as requested. This may be [`CaptureAPIBackend::Auto`], see [`active_backend()`](Camera::active_backend) for the backend that is actually in use.
     #[must_use]
     pub fn backend(&self) -> CaptureAPIBackend {
         self.backend_api
     }
 
+    /// Gets the backend that is actually in use. If the camera was created with [`CaptureAPIBackend::Auto`], this is the backend that was picked from the [`backend_priority()`](crate::backend_priority).
+    #[must_use]
+    pub fn active_backend(&self) -> CaptureAPIBackend {
+        self.backend.backend()
+    }
+
+    /// Returns the native handle of the backend in use, see [`RawHandle`]. This is `None` if the backend has no handle to give.
+    /// # Safety
+    /// The handle is owned by the camera and is only valid until the camera is dropped or its backend is re-initialized (e.g. by [`set_backend()`](Camera::set_backend) or [`set_index()`](Camera::set_index)). Do not close or release it.
+    /// Changing the format or stream behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports.
+    pub unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        self.backend.raw_handle()
+    }
+
     /// Sets the current Camera's backend. Note that this re-initializes the camera.
     /// # Errors
     /// The new backend may not exist or may fail to initialize the new camera.
@@ -92,6 +477,11 @@ impl Camera {
         let new_camera_format = self.backend.camera_format();
         let new_camera = init_camera(self.idx, Some(new_camera_format), new_backend)?;
         self.backend = new_camera;
+        self.backend_api = new_backend;
+        self.sensor_orientation = self.backend.camera_info().sensor_orientation();
+        self.last_frame = None;
+        self.stats.restart_window();
+        telemetry::record_reconnect(self.active_backend(), self.idx);
         Ok(())
     }
 
@@ -110,7 +500,15 @@ impl Camera {
     /// # Errors
     /// If you started the stream and the camera rejects the new camera format, this will return an error.
     pub fn set_camera_format(&mut self, new_fmt: CameraFormat) -> Result<(), NokhwaError> {
-        self.backend.set_camera_format(new_fmt)
+        let result = self.backend.set_camera_format(new_fmt);
+        telemetry::record_format(
+            self.active_backend(),
+            self.idx,
+            new_fmt,
+            result.as_ref().copied(),
+        );
+        self.record(JournalEvent::Format(new_fmt), result.is_ok());
+        result
     }
     /// A hashmap of [`Resolution`]s mapped to framerates
     /// # Errors
@@ -127,6 +525,28 @@ impl Camera {
     pub fn compatible_fourcc(&mut self) -> Result<Vec<FrameFormat>, NokhwaError> {
         self.backend.compatible_fourcc()
     }
+    /// A Vector of every compatible [`CameraFormat`], sorted by resolution (highest first), then frame rate (highest first).
+    /// Formats marked as [`Quirk::UnreliableFormat`] for this camera are left out.
+    /// # Errors
+    /// This will error if the camera is not queryable or a query operation has failed. Some backends will error this out as a [`UnsupportedOperationError`](crate::NokhwaError::UnsupportedOperationError).
+    pub fn compatible_camera_formats(&mut self) -> Result<Vec<CameraFormat>, NokhwaError> {
+        let mut formats = vec![];
+        for fourcc in self.compatible_fourcc()? {
+            for (resolution, framerates) in self.compatible_list_by_resolution(fourcc)? {
+                for fps in framerates {
+                    formats.push(CameraFormat::new(resolution, fourcc, fps));
+                }
+            }
+        }
+        let quirks = self.quirks();
+        formats.retain(|format| !quirks.contains(&Quirk::UnreliableFormat(*format)));
+        formats.sort_by(|a, b| {
+            b.resolution()
+                .cmp(&a.resolution())
+                .then(b.frame_rate().cmp(&a.frame_rate()))
+        });
+        Ok(formats)
+    }
     /// Gets the current camera resolution (See: [`Resolution`], [`CameraFormat`]).
     #[must_use]
     pub fn resolution(&self) -> Resolution {
@@ -137,7 +557,11 @@ impl Camera {
     /// # Errors
     /// If you started the stream and the camera rejects the new resolution, this will return an error.
     pub fn set_resolution(&mut self, new_res: Resolution) -> Result<(), NokhwaError> {
-        self.backend.set_resolution(new_res)
+        let mut new_fmt = self.backend.camera_format();
+        new_fmt.set_resolution(new_res);
+        let result = self.backend.set_resolution(new_res);
+        self.record(JournalEvent::Format(new_fmt), result.is_ok());
+        result
     }
     /// Gets the current camera framerate (See: [`CameraFormat`]).
     #[must_use]
@@ -149,7 +573,11 @@ impl Camera {
     /// # Errors
     /// If you started the stream and the camera rejects the new framerate, this will return an error.
     pub fn set_frame_rate(&mut self, new_fps: u32) -> Result<(), NokhwaError> {
-        self.backend.set_frame_rate(new_fps)
+        let mut new_fmt = self.backend.camera_format();
+        new_fmt.set_frame_rate(new_fps);
+        let result = self.backend.set_frame_rate(new_fps);
+        self.record(JournalEvent::Format(new_fmt), result.is_ok());
+        result
     }
     /// Gets the current camera's frame format (See: [`FrameFormat`], [`CameraFormat`]).
     #[must_use]
@@ -161,13 +589,120 @@ impl Camera {
     /// # Errors
     /// If you started the stream and the camera rejects the new frame format, this will return an error.
     pub fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError> {
-        self.backend.set_frame_format(fourcc)
+        let mut new_fmt = self.backend.camera_format();
+        new_fmt.set_format(fourcc);
+        let result = self.backend.set_frame_format(fourcc);
+        self.record(JournalEvent::Format(new_fmt), result.is_ok());
+        result
+    }
+    /// Gets the controls supported by this camera (See: [`KnownCameraControls`]). Controls marked as [`Quirk::BrokenControl`] for this camera are left out.
+    /// # Errors
+    /// If the backend fails to query the controls, this will error.
+    pub fn supported_camera_controls(&self) -> Result<Vec<KnownCameraControls>, NokhwaError> {
+        let quirks = self.quirks();
+        let mut controls = self.backend.supported_camera_controls()?;
+        controls.retain(|control| !quirks.contains(&Quirk::BrokenControl(*control)));
+        Ok(controls)
+    }
+    /// Gets the current value and range of a [`KnownCameraControls`] as a [`CameraControl`].
+    /// # Errors
+    /// If the control is not supported or the backend fails to read it, this will error.
+    pub fn camera_control(
+        &self,
+        control: KnownCameraControls,
+    ) -> Result<CameraControl, NokhwaError> {
+        self.backend.camera_control(control)
+    }
+    /// Sets a [`CameraControl`]. Get one from [`camera_control()`](Camera::camera_control) and use [`CameraControl::set_value()`] on it first.
+    /// # Errors
+    /// If the control is not supported (or marked as [`Quirk::BrokenControl`]), the value is rejected or the backend fails to write it, this will error.
+    pub fn set_camera_control(&mut self, control: CameraControl) -> Result<(), NokhwaError> {
+        let quirks = self.quirks();
+        if quirks.contains(&Quirk::BrokenControl(control.control())) {
+            return Err(NokhwaError::SetPropertyError {
+                property: control.control().to_string(),
+                value: control.value().to_string(),
+                error: "Control is broken on this camera (see Quirk::BrokenControl)".to_string(),
+            });
+        }
+        let event = JournalEvent::Control(control.control(), control.value());
+        if let Err(why) = self.backend.set_camera_control(control) {
+            telemetry::record_backend_error(
+                self.active_backend(),
+                self.idx,
+                "set_camera_control",
+                &why,
+            );
+            self.record(event, false);
+            return Err(why);
+        }
+        self.record(event, true);
+        if quirks.contains(&Quirk::RenegotiateAfterControl) {
+            let camera_format = self.backend.camera_format();
+            let result = self.backend.set_camera_format(camera_format);
+            telemetry::record_format(
+                self.active_backend(),
+                self.idx,
+                camera_format,
+                result.as_ref().copied(),
+            );
+            result?;
+        }
+        Ok(())
+    }
+    /// Starts recording every control change and format negotiation into a new [`SessionJournal`], replacing the current one.
+    pub fn start_journal(&mut self) {
+        self.journal = Some(SessionJournal::new());
+    }
+    /// Stops recording, and returns the [`SessionJournal`] if one was running.
+    pub fn stop_journal(&mut self) -> Option<SessionJournal> {
+        self.journal.take()
+    }
+    /// Gets the running [`SessionJournal`], if any.
+    #[must_use]
+    pub fn journal(&self) -> Option<&SessionJournal> {
+        self.journal.as_ref()
+    }
+    fn record(&mut self, event: JournalEvent, succeeded: bool) {
+        if let Some(journal) = &mut self.journal {
+            journal.record(event, succeeded);
+        }
+    }
+    /// Gets the [`CaptureStats`] (frame rate, frame intervals, decode time and dropped frames) of this camera, measured over the [stats window](Camera::stats_window).
+    #[must_use]
+    pub fn capture_stats(&self) -> CaptureStats {
+        self.stats.stats()
+    }
+    /// Resets the [`CaptureStats`], including the frame counters.
+    pub fn reset_capture_stats(&mut self) {
+        self.stats.reset();
+    }
+    /// Gets the length of the sliding window the [`CaptureStats`] are measured over. Defaults to [`DEFAULT_STATS_WINDOW`](crate::DEFAULT_STATS_WINDOW).
+    #[must_use]
+    pub fn stats_window(&self) -> Duration {
+        self.stats.window()
+    }
+    /// Sets the length of the sliding window the [`CaptureStats`] are measured over. A longer window gives steadier numbers that are slower to follow changes.
+    pub fn set_stats_window(&mut self, window: Duration) {
+        self.stats.set_window(window);
+    }
+    /// Gets the [`Quirk`]s known for this camera's USB vendor and product ID (See: [`add_quirk()`](crate::add_quirk)). Cameras without a known USB ID never have quirks.
+    #[must_use]
+    pub fn quirks(&self) -> Vec<Quirk> {
+        quirks::quirks_for_info(&self.backend.camera_info())
     }
     /// Will open the camera stream with set parameters. This will be called internally if you try and call [`frame()`](CaptureBackendTrait::frame()) before you call [`open_stream()`](CaptureBackendTrait::open_stream()).
     /// # Errors
     /// If the specific backend fails to open the camera (e.g. already taken, busy, doesn't exist anymore) this will error.
     pub fn open_stream(&mut self) -> Result<(), NokhwaError> {
-        self.backend.open_stream()
+        let result = self.backend.open_stream();
+        telemetry::record_stream(
+            self.active_backend(),
+            self.idx,
+            "open_stream",
+            result.as_ref().copied(),
+        );
+        result
     }
     /// Checks if stream if open. If it is, it will return true.
     #[must_use]
@@ -179,60 +714,544 @@ impl Camera {
     /// # Errors
     /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails (e.g. MJPEG -> u8), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
     /// this will error.
+    ///
+    /// If a [`timeout()`](Camera::timeout) is set, this will not block for longer than it where the backend supports [`frame_with_deadline()`](Camera::frame_with_deadline), and error with [`NokhwaError::Timeout`] instead.
+    ///
+    /// Corrupt frames are handled as set with [`set_corrupt_frame_handling()`](Camera::set_corrupt_frame_handling).
     pub fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        self.backend.frame()
+        self.wait_for_output();
+        let frame = self.unlimited_frame();
+        if frame.is_ok() {
+            self.schedule_next_output();
+        }
+        frame
+    }
+    fn unlimited_frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let mut skipped = 0;
+        loop {
+            match self.decoded_frame() {
+                Ok(frame) => {
+                    if self.corrupt_frame_handling == CorruptFrameHandling::RepeatLast {
+                        self.last_good_frame = Some(frame.clone());
+                    }
+                    return Ok(self.apply_transforms(frame));
+                }
+                Err(why) if is_corrupt_frame(&why) => match self.corrupt_frame_handling {
+                    CorruptFrameHandling::Skip if skipped < MAX_SKIPPED_CORRUPT_FRAMES => {
+                        skipped += 1;
+                    }
+                    CorruptFrameHandling::RepeatLast => {
+                        return match self.last_good_frame.clone() {
+                            Some(frame) => Ok(self.apply_transforms(frame)),
+                            None => Err(why),
+                        }
+                    }
+                    _ => return Err(why),
+                },
+                Err(why) => return Err(why),
+            }
+        }
+    }
+    // throws away the frames that arrive before the next one `set_output_fps_limit()` lets through, which keeps the stream's buffers fresh
+    fn wait_for_output(&mut self) {
+        let due = match self.next_output {
+            Some(due) => due,
+            None => return,
+        };
+        let frame_rate = self.backend.camera_format().frame_rate();
+        if frame_rate == 0 {
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+            return;
+        }
+        let camera_interval = Duration::from_secs(1) / frame_rate;
+        // stop once the camera's next frame would be due
+        while Instant::now() + camera_interval + FPS_LIMIT_SLACK < due {
+            if self.skip_frame().is_err() {
+                thread::sleep(due.saturating_duration_since(Instant::now()));
+                return;
+            }
+        }
+    }
+
+    // reads a frame to throw away, without decoding it, with the same timeout and stats as any other frame
+    fn skip_frame(&mut self) -> Result<(), NokhwaError> {
+        if let Some(timeout) = self.timeout {
+            let _span = telemetry::frame_span(self.active_backend(), self.idx);
+            let started = telemetry::now();
+            let error = self.backend.frame_raw_with_deadline(timeout).err();
+            if matches!(error, Some(NokhwaError::UnsupportedOperationError(_))) {
+                return self.frame_raw().map(|_| ());
+            }
+            // running out of time is not a dropped frame, as in `decoded_frame_with_deadline()`
+            if !matches!(error, Some(NokhwaError::Timeout(_))) {
+                // raw frames are not decoded, whatever decoded last does not belong to this one
+                telemetry::take_decode_time();
+                self.record_frame_result(started, error.as_ref());
+            }
+            return error.map_or(Ok(()), Err);
+        }
+        self.frame_raw().map(|_| ())
     }
+
+    fn schedule_next_output(&mut self) {
+        let fps = match self.output_fps_limit {
+            Some(fps) => fps,
+            None => return,
+        };
+        let interval = Duration::from_secs_f32(1.0 / fps);
+        let now = Instant::now();
+        // keep to the schedule, unless the frame was taken late
+        self.next_output = Some(match self.next_output {
+            Some(due) if now < due + interval => due + interval,
+            _ => now + interval,
+        });
+    }
+    // gets and decodes a frame, without any transforms
+    fn decoded_frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        if let Some(timeout) = self.timeout {
+            match self.decoded_frame_with_deadline(timeout) {
+                Err(NokhwaError::UnsupportedOperationError(_)) => {}
+                frame => return frame,
+            }
+        }
+        let _span = telemetry::frame_span(self.active_backend(), self.idx);
+        let started = telemetry::now();
+        let frame = match self.decoder.as_mut() {
+            Some(decoder) => {
+                let decoded = match self.backend.undecoded_frame() {
+                    Ok(raw) => Some(decoder.decode_rgb(&raw)),
+                    // the backend decodes by itself
+                    Err(NokhwaError::UnsupportedOperationError(_)) => None,
+                    Err(why) => Some(Err(why)),
+                };
+                match decoded {
+                    Some(frame) => frame,
+                    None => self.backend.frame(),
+                }
+            }
+            None => self.backend.frame(),
+        };
+        self.record_frame_result(started, frame.as_ref().err());
+        frame
+    }
+    /// Will get a frame like [`frame()`](Camera::frame), as a [`Frame`] whose buffer goes back to the buffer pool when it is dropped.
+    /// Capturing with this in a loop (and dropping each frame when done with it) does not allocate a new buffer for every frame.
+    /// # Errors
+    /// This errors the same way as [`frame()`](Camera::frame).
+    pub fn frame_pooled(&mut self) -> Result<Frame, NokhwaError> {
+        let frame = Frame::from(self.frame()?);
+        Ok(match self.frame_metadata() {
+            Some(metadata) => frame.with_metadata(metadata),
+            None => frame,
+        })
+    }
+    /// Will get the freshest frame the camera can provide within `deadline` as a Raw RGB image buffer, never waiting on the camera past it.
+    /// Decoding and transforming the frame happen after it arrives, and are not counted against `deadline`.
+    /// # Errors
+    /// If no frame becomes available in time this will return [`NokhwaError::Timeout`]. If the backend does not support deadlines, this will return [`NokhwaError::UnsupportedOperationError`].
+    /// Otherwise, this errors the same way as [`frame()`](Camera::frame()).
+    pub fn frame_with_deadline(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        self.decoded_frame_with_deadline(deadline)
+            .map(|frame| self.apply_transforms(frame))
+    }
+    fn decoded_frame_with_deadline(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let _span = telemetry::frame_span(self.active_backend(), self.idx);
+        let started = telemetry::now();
+        let frame = self.backend.frame_with_deadline(deadline);
+        // running out of time is not a dropped frame, the camera just had nothing for us yet
+        if !matches!(frame, Err(NokhwaError::Timeout(_))) {
+            self.record_frame_result(started, frame.as_ref().err());
+        }
+        frame
+    }
+    /// Will capture a still photo at the highest resolution the camera offers for stills, using its dedicated still capture where the backend supports it, and a frame from [`frame()`](Camera::frame) otherwise.
+    /// # Errors
+    /// If the photo fails to capture or decode, this will error. When falling back, this errors the same way as [`frame()`](Camera::frame).
+    /// # Quirks
+    /// - Photos from the still capture are mirrored and rotated like frames, but not cropped or resized, as those are set for the video resolution.
+    /// - The still capture may briefly interrupt the video stream, depending on the backend.
+    pub fn take_photo(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        match self.backend.take_photo() {
+            Ok(photo) => {
+                let photo = transform::mirror_frame(photo, self.mirror);
+                match self.rotation_handling {
+                    RotationHandling::Pixels => {
+                        Ok(transform::rotate_frame(photo, self.output_rotation()))
+                    }
+                    RotationHandling::Metadata => Ok(photo),
+                }
+            }
+            Err(NokhwaError::UnsupportedOperationError(_)) => self.frame(),
+            Err(why) => Err(why),
+        }
+    }
+    /// Sets the [`FrameDecoder`] [`frame()`](Camera::frame) (and the functions built on it) decodes frames with, or `None` to let the backend decode them with the [`DefaultDecoder`](crate::DefaultDecoder).
+    /// Backends that decode frames themselves (see [`undecoded_frame()`](CaptureBackendTrait::undecoded_frame)) do not use it.
+    /// # Quirks
+    /// - [`frame_with_deadline()`](Camera::frame_with_deadline) (and [`frame()`](Camera::frame) with a timeout set) always decodes with the backend.
+    pub fn set_frame_decoder(&mut self, decoder: Option<Box<dyn FrameDecoder>>) {
+        self.decoder = decoder;
+    }
+    /// Whether a [`FrameDecoder`] is set.
+    #[must_use]
+    pub fn has_frame_decoder(&self) -> bool {
+        self.decoder.is_some()
+    }
+    /// Gets what [`frame()`](Camera::frame) does with corrupt frames. Defaults to [`CorruptFrameHandling::Error`].
+    #[must_use]
+    pub fn corrupt_frame_handling(&self) -> CorruptFrameHandling {
+        self.corrupt_frame_handling
+    }
+    /// Sets what [`frame()`](Camera::frame) (and the functions built on it) does with corrupt frames. See [`CorruptFrameHandling`].
+    pub fn set_corrupt_frame_handling(&mut self, handling: CorruptFrameHandling) {
+        self.corrupt_frame_handling = handling;
+        self.last_good_frame = None;
+    }
+    /// Will get a frame from the camera without any decoding, along with its format, resolution, stride and color space. See [`RawFrame`].
+    /// # Errors
+    /// If the backend fails to get the frame, or decodes frames by itself, this will error. See [`CaptureBackendTrait::undecoded_frame()`].
+    pub fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        self.backend.undecoded_frame()
+    }
+    /// Sets the [`SinkTransform`] (e.g. encryption or redaction) that runs on every frame from [`frame_for_sink()`](Camera::frame_for_sink), or `None` to hand sinks plain frames.
+    pub fn set_sink_transform(&mut self, transform: Option<Box<dyn SinkTransform>>) {
+        self.sink_transform = transform;
+    }
+    /// Whether a [`SinkTransform`] is set.
+    #[must_use]
+    pub fn has_sink_transform(&self) -> bool {
+        self.sink_transform.is_some()
+    }
+
+    /// Will get a frame like [`frame()`](Camera::frame), and run it through the [`SinkTransform`] if one is set. Sinks that write or send frames use this, so they never see a frame the transform has not processed.
+    ///
+    /// Sinks that use this cannot carry a rotation flag, so the [`pending_rotation()`](Camera::pending_rotation) is applied to the pixels (before the [`SinkTransform`]), even with [`RotationHandling::Metadata`].
+    /// Sinks that can carry one use [`frame_for_sink_with_rotation()`](Camera::frame_for_sink_with_rotation) instead.
+    /// # Errors
+    /// This errors the same way as [`frame()`](Camera::frame), or if the [`SinkTransform`] fails.
+    pub fn frame_for_sink(&mut self) -> Result<SinkPayload, NokhwaError> {
+        let frame = self.frame()?;
+        let frame = transform::rotate_frame(frame, self.pending_rotation());
+        self.transform_for_sink(frame)
+    }
+
+    /// Will get a frame like [`frame_for_sink()`](Camera::frame_for_sink), but leaves the [`pending_rotation()`](Camera::pending_rotation) to the sink, which gets it alongside the payload
+    /// (e.g. to write it as a display matrix or rotation flag). This is [`Rotation::None`] unless the [`rotation_handling()`](Camera::rotation_handling) is [`RotationHandling::Metadata`].
+    /// # Errors
+    /// This errors the same way as [`frame_for_sink()`](Camera::frame_for_sink).
+    pub fn frame_for_sink_with_rotation(&mut self) -> Result<(SinkPayload, Rotation), NokhwaError> {
+        let frame = self.frame()?;
+        let rotation = self.pending_rotation();
+        Ok((self.transform_for_sink(frame)?, rotation))
+    }
+
+    fn transform_for_sink(
+        &mut self,
+        frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<SinkPayload, NokhwaError> {
+        match &mut self.sink_transform {
+            Some(transform) => transform.transform(frame),
+            None => Ok(SinkPayload::Image(frame)),
+        }
+    }
+
     /// Will get a frame from the camera **without** any processing applied, meaning you will usually get a frame you need to decode yourself.
     /// # Errors
     /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
     pub fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let _span = telemetry::frame_span(self.active_backend(), self.idx);
+        let started = telemetry::now();
+        let backend_api = self.active_backend();
+        let index = self.idx;
+        let last_frame = self.last_frame;
         match self.backend.frame_raw() {
-            Ok(f) => Ok(f),
-            Err(why) => Err(why),
+            Ok(f) => {
+                telemetry::record_frame(backend_api, index, started, last_frame);
+                if started.is_some() {
+                    self.last_frame = started;
+                }
+                // raw frames are not decoded, whatever decoded last does not belong to this one
+                telemetry::take_decode_time();
+                self.stats.record_frame(None);
+                Ok(f)
+            }
+            Err(why) => {
+                telemetry::record_dropped_frame(backend_api, index, &why);
+                self.stats.record_dropped_frame();
+                Err(why)
+            }
+        }
+    }
+
+    /// Will get a raw frame like [`frame_raw()`](Camera::frame_raw), but with an explicit choice between a zero-copy borrow of the stream's buffer and a stable owned copy (See: [`FrameAccess`]).
+    /// [`frame_raw()`](Camera::frame_raw) is the same as [`FrameAccess::Borrowed`].
+    /// # Errors
+    /// This errors the same way as [`frame_raw()`](Camera::frame_raw).
+    pub fn frame_raw_with(&mut self, access: FrameAccess) -> Result<Cow<[u8]>, NokhwaError> {
+        let frame = self.frame_raw()?;
+        match access {
+            FrameAccess::Copied => Ok(Cow::Owned(frame.into_owned())),
+            FrameAccess::Borrowed => Ok(frame),
+        }
+    }
+
+    // crop, then resize, then mirror, then rotate. The rotation goes last so it can be left to the consumer.
+    fn apply_transforms(
+        &self,
+        frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let frame = match self.crop {
+            Some(rect) => transform::crop_frame(frame, rect),
+            None => frame,
+        };
+        let frame = match self.output_resolution {
+            // resize to the size *before* rotating, so the rotated frame has the output resolution
+            Some(resolution) => transform::resize_frame(
+                frame,
+                self.output_rotation().rotated_resolution(resolution),
+                self.resize_filter,
+                self.resize_mode,
+            ),
+            None => frame,
+        };
+        let frame = transform::mirror_frame(frame, self.mirror);
+        match self.rotation_handling {
+            RotationHandling::Pixels => transform::rotate_frame(frame, self.output_rotation()),
+            RotationHandling::Metadata => frame,
+        }
+    }
+
+    fn record_frame_result(&mut self, started: Option<Instant>, error: Option<&NokhwaError>) {
+        match error {
+            None => {
+                telemetry::record_frame(self.active_backend(), self.idx, started, self.last_frame);
+                if started.is_some() {
+                    self.last_frame = started;
+                }
+                self.stats.record_frame(telemetry::take_decode_time());
+            }
+            Some(why) => {
+                telemetry::record_dropped_frame(self.active_backend(), self.idx, why);
+                self.stats.record_dropped_frame();
+            }
         }
     }
 
-    /// The minimum buffer size needed to write the current frame (RGB24). If `rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
+    /// The minimum buffer size needed to write the current frame (RGB24) with [`frame_to_buffer()`](Camera::frame_to_buffer), i.e. after the crop, output resolution and rotation are applied.
+    /// If `rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
     #[must_use]
     pub fn min_buffer_size(&self, rgba: bool) -> usize {
-        let resolution = self.backend.resolution();
-        if rgba {
-            return (resolution.width() * resolution.height() * 4) as usize;
+        let resolution = self.transformed_resolution();
+        let channels = if rgba { 4 } else { 3 };
+        resolution.width() as usize * resolution.height() as usize * channels
+    }
+
+    // the resolution of frames from `frame()`, the same steps as `apply_transforms()`
+    fn transformed_resolution(&self) -> Resolution {
+        let mut resolution = self.backend.resolution();
+        if let Some(rect) = self.crop.and_then(|rect| rect.clamp_to(resolution)) {
+            resolution = rect.resolution();
+        }
+        let rotation = self.output_rotation();
+        if let Some(output) = self.output_resolution {
+            resolution = rotation.rotated_resolution(output);
+        }
+        match self.rotation_handling {
+            RotationHandling::Pixels => rotation.rotated_resolution(resolution),
+            RotationHandling::Metadata => resolution,
         }
-        (resolution.width() * resolution.height() * 3) as usize
     }
+
     /// Directly writes the current frame(RGB24) into said `buffer`. If `convert_rgba` is true, the buffer written will be written as an RGBA frame instead of a RGB frame. Returns the amount of bytes written on successful capture.
+    /// The frame is the same as from [`frame()`](Camera::frame), with its transforms applied and any row padding (see [`frame_stride()`](Camera::frame_stride)) removed.
     /// # Errors
-    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), `buffer` is too small for the frame, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
     pub fn frame_to_buffer(
         &mut self,
         buffer: &mut [u8],
         convert_rgba: bool,
     ) -> Result<usize, NokhwaError> {
-        let resolution = self.resolution();
-        let frame = self.frame_raw()?;
-        if convert_rgba {
-            let image_data =
-                match ImageBuffer::from_raw(resolution.width(), resolution.height(), frame) {
-                    Some(image) => {
-                        let image: ImageBuffer<Rgb<u8>, Cow<[u8]>> = image;
-                        image
-                    }
-                    None => {
-                        return Err(NokhwaError::ReadFrameError(
-                            "Frame Cow Too Small".to_string(),
-                        ))
-                    }
-                };
-            let rgba_image: RgbaImage = image_data.convert();
-            buffer.copy_from_slice(rgba_image.as_raw());
-            return Ok(rgba_image.len());
+        let frame = self.frame()?;
+        write_rgb_to_buffer(&frame, buffer, convert_rgba)
+    }
+
+    /// Captures a frame (see [`frame()`](Camera::frame)) and encodes it as a JPEG or PNG, with EXIF metadata (timestamp, camera name and resolution). See [`encode_snapshot()`](crate::encode_snapshot).
+    /// # Errors
+    /// If the frame cannot be captured, or `format` is not [`ImageFormat::Jpeg`] or [`ImageFormat::Png`], this will error.
+    pub fn snapshot(&mut self, format: ImageFormat) -> Result<Vec<u8>, NokhwaError> {
+        let frame = self.frame()?;
+        encode_snapshot(&frame, format, self.info().human_name(), SystemTime::now())
+    }
+
+    /// Captures a frame and writes it to `path` as a JPEG or PNG, with EXIF metadata. See [`snapshot()`](Camera::snapshot).
+    /// # Errors
+    /// If the frame cannot be captured, `format` is not [`ImageFormat::Jpeg`] or [`ImageFormat::Png`], or the file cannot be written, this will error.
+    pub fn save_frame<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        format: ImageFormat,
+    ) -> Result<(), NokhwaError> {
+        let encoded = self.snapshot(format)?;
+        match fs::write(path.as_ref(), encoded) {
+            Ok(()) => Ok(()),
+            Err(why) => Err(NokhwaError::GeneralError(format!(
+                "Could not write snapshot to {}: {}",
+                path.as_ref().display(),
+                why
+            ))),
         }
-        buffer.copy_from_slice(&frame);
-        Ok(frame.len())
     }
 
-    #[cfg(feature = "output-wgpu")]
+    /// Will get a frame from the camera as 16 bit luma (gray), keeping the full bit depth of [`FrameFormat::Y16`] and the luma of [`FrameFormat::P010`]. No transforms are applied.
+    /// For [`FrameFormat::Z16`] these are the raw depth samples, see [`z16_to_depth()`].
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the frame is not in one of those formats, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_u16(&mut self) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>, NokhwaError> {
+        let camera_format = self.camera_format();
+        let stride = self.frame_stride();
+        let frame = packed_frame(self.frame_raw()?, camera_format, stride)?;
+        let luma = match camera_format.format() {
+            FrameFormat::Y16 => y16_to_luma16(&frame)?,
+            FrameFormat::Z16 => z16_to_depth(&frame)?,
+            FrameFormat::P010 => p010_to_luma16(&frame, camera_format.resolution())?,
+            format => {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: format,
+                    destination: "Luma16".to_string(),
+                    error: "Not a 16 bit format".to_string(),
+                })
+            }
+        };
+        match ImageBuffer::from_vec(camera_format.width(), camera_format.height(), luma) {
+            Some(buf) => Ok(buf),
+            None => Err(NokhwaError::ReadFrameError(
+                "ImageBuffer is not large enough! Is the frame the right size?".to_string(),
+            )),
+        }
+    }
+
+    /// Will get a frame from the camera as 16 bit RGB, keeping the full bit depth of [`FrameFormat::P010`] (and [`FrameFormat::Y16`] and [`FrameFormat::Z16`], as gray). No transforms are applied.
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the frame is not in one of those formats, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_rgb16(&mut self) -> Result<ImageBuffer<Rgb<u16>, Vec<u16>>, NokhwaError> {
+        let camera_format = self.camera_format();
+        let color_space = self.color_space();
+        let stride = self.frame_stride();
+        let frame = packed_frame(self.frame_raw()?, camera_format, stride)?;
+        let rgb = match camera_format.format() {
+            FrameFormat::Y16 => y16_to_luma16(&frame)?
+                .into_iter()
+                .flat_map(|luma| [luma; 3])
+                .collect(),
+            FrameFormat::Z16 => z16_to_depth(&frame)?
+                .into_iter()
+                .flat_map(|luma| [luma; 3])
+                .collect(),
+            FrameFormat::P010 => {
+                p010_to_rgb48_with(&frame, camera_format.resolution(), color_space)?
+            }
+            format => {
+                return Err(NokhwaError::ProcessFrameError {
+                    src: format,
+                    destination: "RGB48".to_string(),
+                    error: "Not a 16 bit format".to_string(),
+                })
+            }
+        };
+        match ImageBuffer::from_vec(camera_format.width(), camera_format.height(), rgb) {
+            Some(buf) => Ok(buf),
+            None => Err(NokhwaError::ReadFrameError(
+                "ImageBuffer is not large enough! Is the frame the right size?".to_string(),
+            )),
+        }
+    }
+
+    #[cfg(feature = "output-rgb565")]
+    /// Will get a frame from the camera as RGB565 pixels (in native endianness). YUYV frames are converted directly, without an intermediate RGB888 buffer.
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_rgb565(&mut self) -> Result<Vec<u16>, NokhwaError> {
+        let camera_format = self.camera_format();
+        let color_space = self.color_space();
+        let stride = self.frame_stride();
+        let frame = packed_frame(self.frame_raw()?, camera_format, stride)?;
+        frame_to_rgb565(&frame, camera_format, color_space)
+    }
+
+    #[cfg(feature = "output-rgb565")]
+    /// Directly writes the current frame as RGB565 pixels into `buffer`, which must be exactly `width * height` pixels. Returns the amount of pixels written on successful capture.
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails, `buffer` is the wrong size, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_rgb565_to_buffer(&mut self, buffer: &mut [u16]) -> Result<usize, NokhwaError> {
+        let camera_format = self.camera_format();
+        let color_space = self.color_space();
+        let stride = self.frame_stride();
+        let frame = packed_frame(self.frame_raw()?, camera_format, stride)?;
+        match camera_format.format() {
+            FrameFormat::YUYV => yuyv422_to_rgb565_into_with(&frame, buffer, color_space)?,
+            format => {
+                let rgb565 = frame_to_rgb565(&frame, camera_format, color_space)?;
+                if rgb565.len() != buffer.len() {
+                    return Err(NokhwaError::ProcessFrameError {
+                        src: format,
+                        destination: "RGB565".to_string(),
+                        error: format!(
+                            "Output buffer is {} pixels, expected {}",
+                            buffer.len(),
+                            rgb565.len()
+                        ),
+                    });
+                }
+                buffer.copy_from_slice(&rgb565);
+            }
+        }
+        Ok(buffer.len())
+    }
+
+    #[cfg(feature = "input-opencv")]
+    /// Will get a frame from the camera as a BGR (`CV_8UC3`) `OpenCV` [`Mat`](opencv::core::Mat), with the same transforms as [`frame()`](Camera::frame) applied.
+    ///
+    /// This works with every backend, not just [`CaptureAPIBackend::OpenCv`].
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails, `OpenCV` fails to allocate the [`Mat`](opencv::core::Mat),
+    /// or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
+    pub fn frame_mat(&mut self) -> Result<opencv::core::Mat, NokhwaError> {
+        let frame = self.frame()?;
+        rgb888_buffer_to_bgr_mat(frame.as_raw(), frame.width())
+    }
+
+    #[cfg(feature = "output-ndarray")]
+    /// Will get a frame from the camera as a `height x width x 3` (HWC) [`Array3`](ndarray::Array3) of RGB bytes, with the same transforms as [`frame()`](Camera::frame) applied.
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_ndarray(&mut self) -> Result<ndarray::Array3<u8>, NokhwaError> {
+        rgb_to_ndarray_hwc(self.frame()?)
+    }
+
+    #[cfg(feature = "output-ndarray")]
+    /// Will get a frame from the camera as a `3 x height x width` (CHW) [`Array3`](ndarray::Array3) of `f32`s, normalized with `normalization` (e.g. [`Normalization::IMAGENET`]), with the same transforms as [`frame()`](Camera::frame) applied.
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), the decoding fails, or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet,
+    /// this will error.
+    pub fn frame_ndarray_chw(
+        &mut self,
+        normalization: Normalization,
+    ) -> Result<ndarray::Array3<f32>, NokhwaError> {
+        rgb_to_ndarray_chw(&self.frame()?, normalization)
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
     /// Directly copies a frame to a Wgpu texture. This will automatically convert the frame into a RGBA frame.
     /// # Errors
     /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
@@ -242,90 +1261,186 @@ impl Camera {
         queue: &WgpuQueue,
         label: Option<&'a str>,
     ) -> Result<WgpuTexture, NokhwaError> {
-        use std::{convert::TryFrom, num::NonZeroU32};
+        self.frame_texture_with(device, queue, label, TextureOptions::default())
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+    /// Directly copies a frame to a Wgpu texture made with `options`, e.g. in a swapchain-compatible `Bgra8Unorm` format. See [`TextureOptions`].
+    /// # Errors
+    /// If the frame cannot be captured, the resolution is 0 on any axis, or the format of `options` is not supported, this will error.
+    pub fn frame_texture_with<'a>(
+        &mut self,
+        device: &WgpuDevice,
+        queue: &WgpuQueue,
+        label: Option<&'a str>,
+        options: TextureOptions,
+    ) -> Result<WgpuTexture, NokhwaError> {
         let frame = self.frame()?;
         let rgba_frame: RgbaImage = frame.convert();
-
-        let texture_size = Extent3d {
-            width: frame.width(),
-            height: frame.height(),
-            depth_or_array_layers: 1,
-        };
-
-        let texture = device.create_texture(&TextureDescriptor {
+        frame_to_texture(
+            device,
+            queue,
             label,
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
-        });
-
-        let width_nonzero = match NonZeroU32::try_from(4 * rgba_frame.width()) {
-            Ok(w) => Some(w),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        let height_nonzero = match NonZeroU32::try_from(rgba_frame.height()) {
-            Ok(h) => Some(h),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        queue.write_texture(
-            ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba_frame.to_vec(),
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: width_nonzero,
-                rows_per_image: height_nonzero,
-            },
-            texture_size,
-        );
+            Resolution::new(rgba_frame.width(), rgba_frame.height()),
+            rgba_frame.as_raw(),
+            options,
+        )
+    }
 
-        Ok(texture)
+    #[cfg(feature = "output-ash")]
+    /// Captures a frame (see [`frame()`](Camera::frame)) and writes it into the staging buffer of `uploader`. Returns the resolution of the frame, for [`VulkanFrameUploader::record_copy()`].
+    /// # Errors
+    /// If the frame cannot be captured, or writing it into the staging buffer fails, this will error.
+    pub fn frame_vulkan(
+        &mut self,
+        uploader: &VulkanFrameUploader,
+    ) -> Result<Resolution, NokhwaError> {
+        let frame = self.frame()?;
+        uploader.write_frame(&frame)
     }
 
     /// Will drop the stream.
     /// # Errors
     /// Please check the `Quirks` section of each backend.
     pub fn stop_stream(&mut self) -> Result<(), NokhwaError> {
-        self.backend.stop_stream()
+        let result = self.backend.stop_stream();
+        telemetry::record_stream(
+            self.active_backend(),
+            self.idx,
+            "stop_stream",
+            result.as_ref().copied(),
+        );
+        result
     }
 }
 
 impl Drop for Camera {
     fn drop(&mut self) {
+        telemetry::record_close(self.active_backend(), self.idx);
         self.stop_stream().unwrap();
     }
 }
 
+// a MJPEG frame that failed to decode, as opposed to failing to capture one
+fn is_corrupt_frame(error: &NokhwaError) -> bool {
+    matches!(
+        error,
+        NokhwaError::ProcessFrameError {
+            src: FrameFormat::MJPEG,
+            ..
+        }
+    )
+}
+
+// removes the row padding from a raw frame, if the backend reports any
+fn packed_frame(
+    frame: Cow<[u8]>,
+    camera_format: CameraFormat,
+    stride: Option<usize>,
+) -> Result<Cow<[u8]>, NokhwaError> {
+    let stride = match stride {
+        Some(stride) => stride,
+        None => return Ok(frame),
+    };
+    let packed = match pack_frame_rows(
+        &frame,
+        camera_format.format(),
+        camera_format.resolution(),
+        stride,
+    )? {
+        Cow::Owned(packed) => Some(packed),
+        Cow::Borrowed(_) => None,
+    };
+    Ok(packed.map_or(frame, Cow::Owned))
+}
+
+#[cfg(feature = "output-rgb565")]
+fn frame_to_rgb565(
+    frame: &[u8],
+    camera_format: CameraFormat,
+    color_space: ColorSpace,
+) -> Result<Vec<u16>, NokhwaError> {
+    let rgb = match camera_format.format() {
+        FrameFormat::MJPEG => return mjpeg_to_rgb565(frame),
+        FrameFormat::YUYV => {
+            let mut rgb565 = vec![0_u16; frame.len() / 2];
+            yuyv422_to_rgb565_into_with(frame, &mut rgb565, color_space)?;
+            return Ok(rgb565);
+        }
+        FrameFormat::Bayer8(_) | FrameFormat::Bayer10(_) | FrameFormat::Bayer12(_) => {
+            debayer(frame, camera_format.resolution(), camera_format.format())?
+        }
+        FrameFormat::Y16 => y16_to_rgb888(frame)?,
+        FrameFormat::Z16 => z16_to_rgb888(frame)?,
+        FrameFormat::P010 => p010_to_rgb888_with(frame, camera_format.resolution(), color_space)?,
+        FrameFormat::H264 | FrameFormat::H265 => {
+            return Err(passthrough_error(camera_format.format(), "RGB565"))
+        }
+    };
+    Ok(rgb
+        .chunks_exact(3)
+        .map(|px| rgb888_to_rgb565(px[0], px[1], px[2]))
+        .collect())
+}
+
+// empty means "use the default order"
+static BACKEND_PRIORITY: RwLock<Vec<CaptureAPIBackend>> = RwLock::new(Vec::new());
+
 // TODO: Update as we go
-#[allow(clippy::ifs_same_cond)]
-fn figure_out_auto() -> Option<CaptureAPIBackend> {
+fn default_backend_priority() -> Vec<CaptureAPIBackend> {
     let platform = std::env::consts::OS;
-    let mut cap = CaptureAPIBackend::Auto;
+    let mut priority = vec![];
     if cfg!(feature = "input-v4l") && platform == "linux" {
-        cap = CaptureAPIBackend::Video4Linux;
-    } else if cfg!(feature = "input-msmf") && platform == "windows" {
-        cap = CaptureAPIBackend::MediaFoundation;
-    } else if cfg!(feature = "input-avfoundationn") && platform == "mac" {
-        cap = CaptureAPIBackend::AVFoundation;
-    } else if cfg!(feature = "input-uvc") {
-        cap = CaptureAPIBackend::UniversalVideoClass;
-    } else if cfg!(feature = "input-gst") {
-        cap = CaptureAPIBackend::GStreamer;
-    } else if cfg!(feature = "input-opencv") {
-        cap = CaptureAPIBackend::OpenCv;
-    }
-    if cap == CaptureAPIBackend::Auto {
-        return None;
-    }
-    Some(cap)
+        priority.push(CaptureAPIBackend::Video4Linux);
+    }
+    if cfg!(feature = "input-msmf") && platform == "windows" {
+        priority.push(CaptureAPIBackend::MediaFoundation);
+    }
+    if cfg!(feature = "input-avfoundation") && platform == "macos" {
+        priority.push(CaptureAPIBackend::AVFoundation);
+    }
+    if cfg!(feature = "input-uvc") {
+        priority.push(CaptureAPIBackend::UniversalVideoClass);
+    }
+    if cfg!(feature = "input-gst") {
+        priority.push(CaptureAPIBackend::GStreamer);
+    }
+    if cfg!(feature = "input-opencv") {
+        priority.push(CaptureAPIBackend::OpenCv);
+    }
+    priority
+}
+
+/// Sets the order in which backends are tried when [`CaptureAPIBackend::Auto`] is used, both for opening a [`Camera`] and for [`query_devices()`](crate::query_devices).
+/// The first backend that succeeds is used. [`Camera::active_backend()`] tells you which one that was.
+///
+/// Backends that are not enabled or not available on this platform are skipped. Passing an empty slice restores the default order.
+/// # Quirks
+/// - This is global, and affects every `Camera` created afterwards. Existing cameras are not affected until they are re-initialized.
+/// - `CaptureAPIBackend::Auto` in `priority` is ignored.
+pub fn set_backend_priority(priority: &[CaptureAPIBackend]) {
+    let priority: Vec<CaptureAPIBackend> = priority
+        .iter()
+        .copied()
+        .filter(|backend| *backend != CaptureAPIBackend::Auto)
+        .collect();
+    match BACKEND_PRIORITY.write() {
+        Ok(mut lock) => *lock = priority,
+        Err(poisoned) => *poisoned.into_inner() = priority,
+    }
+}
+
+/// Gets the order in which backends are tried when [`CaptureAPIBackend::Auto`] is used. See [`set_backend_priority()`].
+#[must_use]
+pub fn backend_priority() -> Vec<CaptureAPIBackend> {
+    let priority = match BACKEND_PRIORITY.read() {
+        Ok(lock) => lock.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    };
+    if priority.is_empty() {
+        return default_backend_priority();
+    }
+    priority
 }
 
 macro_rules! cap_impl_fn {
@@ -360,44 +1475,6 @@ macro_rules! cap_impl_matches {
             let i = $index;
             let s = $setting;
             match $use_backend {
-                CaptureAPIBackend::Auto => match figure_out_auto() {
-                    Some(cap) => match cap {
-                        $(
-                            CaptureAPIBackend::$backend => {
-                                match cfg!(feature = $feature) {
-                                    true => {
-                                        match $fn(i,s) {
-                                            Some(cap) => match cap {
-                                                Ok(c) => c,
-                                                Err(why) => return Err(why),
-                                            }
-                                            None => {
-                                                return Err(NokhwaError::NotImplementedError(
-                                                    "Platform requirements not satisfied.".to_string(),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                    false => {
-                                        return Err(NokhwaError::NotImplementedError(
-                                            "Platform requirements not satisfied.".to_string(),
-                                        ));
-                                    }
-                                }
-                            }
-                        )+
-                        _ => {
-                            return Err(NokhwaError::NotImplementedError(
-                                "Platform requirements not satisfied.".to_string(),
-                            ));
-                        }
-                    }
-                    None => {
-                        return Err(NokhwaError::NotImplementedError(
-                            "Platform requirements not satisfied.".to_string(),
-                        ));
-                    }
-                }
                 $(
                     CaptureAPIBackend::$backend => {
                         match cfg!(feature = $feature) {
@@ -445,6 +1522,28 @@ fn init_camera(
     format: Option<CameraFormat>,
     backend: CaptureAPIBackend,
 ) -> Result<Box<dyn CaptureBackendTrait>, NokhwaError> {
+    let camera = open_backend(index, format, backend);
+    telemetry::record_open(
+        backend,
+        index,
+        format,
+        camera.as_ref().map(|camera| camera.backend()),
+    );
+    camera
+}
+
+fn open_backend(
+    index: usize,
+    format: Option<CameraFormat>,
+    backend: CaptureAPIBackend,
+) -> Result<Box<dyn CaptureBackendTrait>, NokhwaError> {
+    match backend {
+        CaptureAPIBackend::Auto => return init_camera_fallback(index, format, &backend_priority()),
+        // we have no idea how to create these, see `Camera::from_backend()`
+        CaptureAPIBackend::Custom => return Err(NokhwaError::UnsupportedOperationError(backend)),
+        _ => {}
+    }
+
     let camera_backend = cap_impl_matches! {
             backend, index, format,
             ("input-v4l", Video4Linux, init_v4l),
@@ -455,3 +1554,42 @@ fn init_camera(
     };
     Ok(camera_backend)
 }
+
+fn init_camera_fallback(
+    index: usize,
+    format: Option<CameraFormat>,
+    priority: &[CaptureAPIBackend],
+) -> Result<Box<dyn CaptureBackendTrait>, NokhwaError> {
+    let mut error = None;
+    for backend in priority {
+        if *backend == CaptureAPIBackend::Auto {
+            continue;
+        }
+        match open_backend(index, format, *backend) {
+            Ok(camera) => return Ok(camera),
+            // keep the first real failure, a backend that is not compiled in is not interesting
+            Err(why) => {
+                telemetry::record_fallback(*backend, index, &why);
+                if matches!(error, None | Some(NokhwaError::NotImplementedError(_))) {
+                    error = Some(why);
+                }
+            }
+        }
+    }
+    // registered plugins come after every built-in backend
+    #[cfg(feature = "plugins")]
+    for (create, _) in crate::plugin::registered_plugin_fns() {
+        match create(index, format) {
+            Ok(camera) => return Ok(camera),
+            Err(why) => {
+                telemetry::record_fallback(CaptureAPIBackend::Custom, index, &why);
+                if matches!(error, None | Some(NokhwaError::NotImplementedError(_))) {
+                    error = Some(why);
+                }
+            }
+        }
+    }
+    Err(error.unwrap_or_else(|| {
+        NokhwaError::NotImplementedError("Platform requirements not satisfied.".to_string())
+    }))
+}
@@ -0,0 +1,349 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    frame_channel, query_devices, telemetry, AccessMode, BusFrame, Camera, CameraFormat,
+    CameraInfo, CaptureAPIBackend, DropPolicy, KnownCameraControls, Mirror, NokhwaError, Rect,
+    Resolution, Rotation,
+};
+#[cfg(feature = "plugins")]
+use crate::{open_plugin_camera, query_plugin_devices};
+use flume::Receiver;
+use std::time::Duration;
+
+/// How a [`CameraBuilder`] picks the [`CameraFormat`] of the camera it opens.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FormatStrategy {
+    /// Use whatever format the backend picks by default.
+    Default,
+    /// Use exactly this format. Opening the camera fails if the camera rejects it.
+    Exact(CameraFormat),
+    /// Use the compatible format closest to this one. A matching [`FrameFormat`](crate::FrameFormat) is preferred, then the closest resolution, then the closest frame rate.
+    Closest(CameraFormat),
+    /// Use the compatible format with the highest resolution, and the highest frame rate among those.
+    HighestResolution,
+    /// Use the compatible format with the highest frame rate, and the highest resolution among those.
+    HighestFrameRate,
+}
+
+impl Default for FormatStrategy {
+    fn default() -> Self {
+        FormatStrategy::Default
+    }
+}
+
+/// A builder for a [`Camera`], to configure everything about it before opening it in one go.
+///
+/// ```.ignore
+/// let camera = CameraBuilder::new()
+///     .name("Integrated")
+///     .format(FormatStrategy::HighestFrameRate)
+///     .control(KnownCameraControls::Brightness, 20)
+///     .timeout(Duration::from_millis(500))
+///     .open()?;
+/// ```
+/// # Quirks
+/// - [`buffer_count()`](CameraBuilder::buffer_count) is a hint. Backends that do not support it ignore it.
+/// - Controls are applied in the order they are added to the builder.
+/// - An [`access_mode()`](CameraBuilder::access_mode) the backend does not support fails [`open()`](CameraBuilder::open) with [`UnsupportedOperationError`](NokhwaError::UnsupportedOperationError).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraBuilder {
+    index: usize,
+    name: Option<String>,
+    backend: CaptureAPIBackend,
+    #[cfg(feature = "plugins")]
+    plugin: Option<String>,
+    format: FormatStrategy,
+    controls: Vec<(KnownCameraControls, i32)>,
+    buffer_count: Option<u32>,
+    timeout: Option<Duration>,
+    rotation: Rotation,
+    mirror: Mirror,
+    crop: Option<Rect>,
+    output_resolution: Option<Resolution>,
+    output_fps_limit: Option<f32>,
+    access_mode: Option<AccessMode>,
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        CameraBuilder {
+            index: 0,
+            name: None,
+            backend: CaptureAPIBackend::Auto,
+            #[cfg(feature = "plugins")]
+            plugin: None,
+            format: FormatStrategy::Default,
+            controls: vec![],
+            buffer_count: None,
+            timeout: None,
+            rotation: Rotation::None,
+            mirror: Mirror::None,
+            crop: None,
+            output_resolution: None,
+            output_fps_limit: None,
+            access_mode: None,
+        }
+    }
+}
+
+impl CameraBuilder {
+    /// Constructs a default [`CameraBuilder`].
+    /// The constructed default [`CameraBuilder`] has these settings:
+    /// - Index 0
+    /// - [`CaptureAPIBackend::Auto`]
+    /// - [`FormatStrategy::Default`]
+    /// - No controls, buffer count or timeout
+    /// - No rotation, mirroring, cropping or resizing
+    /// - The backend's default [`AccessMode`]
+    #[must_use]
+    pub fn new() -> Self {
+        CameraBuilder::default()
+    }
+
+    /// Sets the index of the camera to open. This is ignored if a [`name()`](CameraBuilder::name) is set.
+    #[must_use]
+    pub fn index(mut self, index: usize) -> CameraBuilder {
+        self.index = index;
+        self
+    }
+
+    /// Opens the camera by its name instead of its index. An exact match of [`CameraInfo::human_name()`](crate::CameraInfo::human_name) is preferred,
+    /// otherwise the first camera whose name contains `name` (ignoring case) is used.
+    #[must_use]
+    pub fn name<S: ToString>(mut self, name: &S) -> CameraBuilder {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the [`CaptureAPIBackend`] to open the camera with.
+    #[must_use]
+    pub fn backend(mut self, backend: CaptureAPIBackend) -> CameraBuilder {
+        self.backend = backend;
+        self
+    }
+
+    /// Opens the camera with the registered plugin `name` instead of the [`backend()`](CameraBuilder::backend), see [`register_plugin()`](crate::register_plugin).
+    /// [`name()`](CameraBuilder::name) then looks the camera up in the plugin's devices.
+    #[cfg(feature = "plugins")]
+    #[must_use]
+    pub fn plugin<S: ToString>(mut self, name: &S) -> CameraBuilder {
+        self.plugin = Some(name.to_string());
+        self
+    }
+
+    /// Sets the [`FormatStrategy`] used to pick the [`CameraFormat`].
+    #[must_use]
+    pub fn format(mut self, format: FormatStrategy) -> CameraBuilder {
+        self.format = format;
+        self
+    }
+
+    /// Sets a control to `value` once the camera is opened.
+    #[must_use]
+    pub fn control(mut self, control: KnownCameraControls, value: i32) -> CameraBuilder {
+        self.controls.push((control, value));
+        self
+    }
+
+    /// Sets the amount of buffers the camera should capture into, see [`Camera::set_buffer_count()`].
+    #[must_use]
+    pub fn buffer_count(mut self, count: u32) -> CameraBuilder {
+        self.buffer_count = Some(count);
+        self
+    }
+
+    /// Sets the frame timeout of the camera, see [`Camera::set_timeout()`].
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> CameraBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the [`Rotation`] of the camera's frames, see [`Camera::set_rotation()`].
+    #[must_use]
+    pub fn rotation(mut self, rotation: Rotation) -> CameraBuilder {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the [`Mirror`] of the camera's frames, see [`Camera::set_mirror()`].
+    #[must_use]
+    pub fn mirror(mut self, mirror: Mirror) -> CameraBuilder {
+        self.mirror = mirror;
+        self
+    }
+
+    /// Sets the [`Rect`] the camera's frames are cropped to, see [`Camera::set_crop()`].
+    #[must_use]
+    pub fn crop(mut self, crop: Rect) -> CameraBuilder {
+        self.crop = Some(crop);
+        self
+    }
+
+    /// Sets the [`Resolution`] the camera's frames are resized to in software, see [`Camera::set_output_resolution()`].
+    #[must_use]
+    pub fn output_resolution(mut self, resolution: Resolution) -> CameraBuilder {
+        self.output_resolution = Some(resolution);
+        self
+    }
+
+    /// Limits the camera's frames to at most `fps` frames per second, or `None` to not limit them, see [`Camera::set_output_fps_limit()`].
+    #[must_use]
+    pub fn output_fps_limit(mut self, fps: Option<f32>) -> CameraBuilder {
+        self.output_fps_limit = fps;
+        self
+    }
+
+    /// Sets the [`AccessMode`] the camera is opened in, see [`Camera::set_access_mode()`]. It is applied before the format, so that a [`AccessMode::Shared`] camera does not change the format of a stream another application is using
+    /// unless a [`FormatStrategy`] other than [`FormatStrategy::Default`] asks for it.
+    #[must_use]
+    pub fn access_mode(mut self, mode: AccessMode) -> CameraBuilder {
+        self.access_mode = Some(mode);
+        self
+    }
+
+    /// Opens the [`Camera`] with the configured settings. The stream is not opened.
+    /// # Errors
+    /// If the plugin is not registered, no camera matches the name, the camera fails to open, the access mode is refused, no compatible format can be found, a control or the buffer count is rejected, the crop or output resolution is empty, or the output FPS limit is invalid, this will error.
+    pub fn open(self) -> Result<Camera, NokhwaError> {
+        let index = match &self.name {
+            Some(name) => find_by_name(name, &self.query_devices()?)?,
+            None => self.index,
+        };
+
+        // the access mode has to be set before the format is
+        let initial_format = match (self.format, self.access_mode) {
+            (FormatStrategy::Exact(format), None) => Some(format),
+            _ => None,
+        };
+        let mut camera = self.open_camera(index, initial_format)?;
+
+        if let Some(mode) = self.access_mode {
+            camera.set_access_mode(mode)?;
+        }
+
+        let picked = match self.format {
+            FormatStrategy::Default => None,
+            FormatStrategy::Exact(format) if initial_format.is_none() => Some(format),
+            FormatStrategy::Exact(_) => None,
+            strategy => {
+                let formats = camera.compatible_camera_formats()?;
+                let candidates = formats.len();
+                let format = pick_format(strategy, formats)?;
+                telemetry::record_format_pick(strategy, candidates, format);
+                Some(format)
+            }
+        };
+        if let Some(format) = picked {
+            camera.set_camera_format(format)?;
+        }
+
+        if let Some(count) = self.buffer_count {
+            match camera.set_buffer_count(count) {
+                Ok(()) | Err(NokhwaError::UnsupportedOperationError(_)) => {}
+                Err(why) => return Err(why),
+            }
+        }
+
+        for (control, value) in self.controls {
+            let mut camera_control = camera.camera_control(control)?;
+            camera_control.set_value(value)?;
+            camera.set_camera_control(camera_control)?;
+        }
+
+        camera.set_timeout(self.timeout);
+        camera.set_rotation(self.rotation);
+        camera.set_mirror(self.mirror);
+        camera.set_crop(self.crop)?;
+        camera.set_output_resolution(self.output_resolution)?;
+        camera.set_output_fps_limit(self.output_fps_limit)?;
+        Ok(camera)
+    }
+
+    /// Opens the [`Camera`] on a capture thread of its own and returns a channel of its frames, see [`frame_channel()`](crate::frame_channel).
+    /// # Errors
+    /// If the camera or its stream fails to open, this will error.
+    pub fn frame_channel(
+        self,
+        depth: usize,
+        drop_policy: DropPolicy,
+    ) -> Result<Receiver<BusFrame>, NokhwaError> {
+        frame_channel(move || self.open(), depth, drop_policy)
+    }
+
+    // the devices of the plugin if one is set, otherwise of the backend
+    fn query_devices(&self) -> Result<Vec<CameraInfo>, NokhwaError> {
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = &self.plugin {
+            return query_plugin_devices(plugin);
+        }
+        query_devices(self.backend)
+    }
+
+    fn open_camera(
+        &self,
+        index: usize,
+        format: Option<CameraFormat>,
+    ) -> Result<Camera, NokhwaError> {
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = &self.plugin {
+            return open_plugin_camera(plugin, index, format);
+        }
+        Camera::new(index, format, self.backend)
+    }
+}
+
+fn find_by_name(name: &str, devices: &[CameraInfo]) -> Result<usize, NokhwaError> {
+    let lowercase = name.to_lowercase();
+    let found = devices
+        .iter()
+        .find(|info| info.human_name() == name)
+        .or_else(|| {
+            devices
+                .iter()
+                .find(|info| info.human_name().to_lowercase().contains(&lowercase))
+        });
+    match found {
+        Some(info) => Ok(*info.index()),
+        None => Err(NokhwaError::OpenDeviceError(
+            name.to_string(),
+            "No camera with this name".to_string(),
+        )),
+    }
+}
+
+fn pick_format(
+    strategy: FormatStrategy,
+    formats: Vec<CameraFormat>,
+) -> Result<CameraFormat, NokhwaError> {
+    // `formats` is sorted by resolution, then frame rate, both highest first
+    let picked = match strategy {
+        FormatStrategy::Default | FormatStrategy::Exact(_) | FormatStrategy::HighestResolution => {
+            formats.into_iter().next()
+        }
+        FormatStrategy::HighestFrameRate => formats.into_iter().max_by(|a, b| {
+            a.frame_rate()
+                .cmp(&b.frame_rate())
+                .then(a.resolution().cmp(&b.resolution()))
+        }),
+        FormatStrategy::Closest(target) => formats.into_iter().min_by_key(|format| {
+            let fps_diff = i64::from(format.frame_rate()) - i64::from(target.frame_rate());
+            (
+                format.format() != target.format(),
+                format.resolution().distance(target.resolution()),
+                fps_diff.abs(),
+            )
+        }),
+    };
+    match picked {
+        Some(format) => Ok(format),
+        None => Err(NokhwaError::GetPropertyError {
+            property: "CameraFormat".to_string(),
+            error: "No compatible formats".to_string(),
+        }),
+    }
+}
@@ -5,18 +5,23 @@
  */
 
 use crate::{
+    decoder::{FrameMetadata, RawFrame},
     error::NokhwaError,
-    utils::{CameraFormat, CameraInfo, FrameFormat, Resolution},
-    CameraControl, CaptureAPIBackend, KnownCameraControls,
+    utils::{
+        AccessMode, CameraFormat, CameraInfo, ColorSpace, FrameFormat, Resolution, StreamKind,
+    },
+    CameraControl, CaptureAPIBackend, KnownCameraControls, RawHandle, Rect,
 };
 use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbaImage};
 
-use std::{any::Any, borrow::Cow, collections::HashMap};
-#[cfg(feature = "output-wgpu")]
-use wgpu::{
-    Device as WgpuDevice, Extent3d, ImageCopyTexture, ImageDataLayout, Queue as WgpuQueue,
-    Texture as WgpuTexture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
-};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::wgpu_api::{Device as WgpuDevice, Queue as WgpuQueue, Texture as WgpuTexture};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::{texture::frame_to_texture, TextureOptions};
+use std::{any::Any, borrow::Cow, collections::HashMap, time::Duration};
+
+// `Camera` (and downstream code) relies on this trait being usable as a trait object. This fails to compile if that ever breaks.
+const _: Option<&dyn CaptureBackendTrait> = None;
 
 /// This trait is for any backend that allows you to grab and take frames from a camera.
 /// Many of the backends are **blocking**, if the camera is occupied the library will block while it waits for it to become available.
@@ -81,6 +86,19 @@ pub trait CaptureBackendTrait {
     /// If you started the stream and the camera rejects the new frame format, this will return an error.
     fn set_frame_format(&mut self, fourcc: FrameFormat) -> Result<(), NokhwaError>;
 
+    /// The [`ColorSpace`] YUV frames are decoded in: the one set with [`set_color_space()`](CaptureBackendTrait::set_color_space), else the one the driver reports, else BT.601 limited range.
+    /// The default implementation always returns BT.601 limited range.
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::default()
+    }
+
+    /// Overrides the [`ColorSpace`] YUV frames are decoded in, for cameras that report the wrong one (or none). `None` goes back to the one the driver reports.
+    /// # Errors
+    /// The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn set_color_space(&mut self, _color_space: Option<ColorSpace>) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     /// Gets the current supported list of [`KnownCameraControls`]
     /// # Errors
     /// If the list cannot be collected, this will error. This can be treated as a "nothing supported".
@@ -132,6 +150,55 @@ pub trait CaptureBackendTrait {
     /// If the specific backend fails to open the camera (e.g. already taken, busy, doesn't exist anymore) this will error.
     fn open_stream(&mut self) -> Result<(), NokhwaError>;
 
+    /// Sets the amount of buffers the backend should capture into. This takes effect the next time the stream is opened.
+    /// # Errors
+    /// If the count is invalid (e.g. 0), this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn set_buffer_count(&mut self, _count: u32) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// The [`AccessMode`] the device is held in, which says whether the stream is shared with other applications.
+    /// # Errors
+    /// If the mode fails to be read, this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn access_mode(&self) -> Result<AccessMode, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Sets the [`AccessMode`] the device is held in. Set this right after opening the device, before changing its format or opening the stream.
+    /// # Errors
+    /// If another application holds the device exclusively (or, for [`AccessMode::Exclusive`], is using it at all), this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn set_access_mode(&mut self, _mode: AccessMode) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// The area of the sensor that [`set_hardware_crop()`](CaptureBackendTrait::set_hardware_crop) can crop to, in sensor pixels.
+    /// # Errors
+    /// If the bounds fail to be read, this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn hardware_crop_bounds(&self) -> Result<Rect, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Crops the sensor to `rect` (in sensor pixels, within [`hardware_crop_bounds()`](CaptureBackendTrait::hardware_crop_bounds)), so it reads out less and can reach higher frame rates.
+    /// Returns the crop that was actually applied, which the driver may have adjusted to what the hardware can do.
+    /// # Errors
+    /// If the crop is rejected or fails to be set, this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn set_hardware_crop(&mut self, _rect: Rect) -> Result<Rect, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Turns capturing [`FrameMetadata`] (e.g. timestamps, exposure time and gain) along with every frame on or off. It is off by default, as it can take another stream.
+    /// This takes effect the next time the stream is opened.
+    /// # Errors
+    /// If the backend cannot capture metadata, this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn set_frame_metadata(&mut self, _enabled: bool) -> Result<(), NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// The [`FrameMetadata`] of the last frame captured. `None` if capturing it is off, or no frame was captured yet. The default implementation returns `None`.
+    fn frame_metadata(&self) -> Option<FrameMetadata> {
+        None
+    }
+
     /// Checks if stream if open. If it is, it will return true.
     fn is_stream_open(&self) -> bool;
 
@@ -147,6 +214,68 @@ pub trait CaptureBackendTrait {
     /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
     fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError>;
 
+    /// The bytes from the start of one row of [`frame_raw()`](CaptureBackendTrait::frame_raw()) to the start of the next, where the driver pads rows to more than [`FrameFormat::row_bytes()`].
+    /// [`frame()`](CaptureBackendTrait::frame()) already skips the padding, use [`pack_frame_rows()`](crate::pack_frame_rows) to remove it from raw frames.
+    ///
+    /// `None` if the rows are tightly packed, the format is compressed, or the backend cannot tell. The default implementation returns `None`.
+    fn frame_stride(&self) -> Option<usize> {
+        None
+    }
+
+    /// Will get a frame from the camera without any decoding, as a [`RawFrame`] that carries its format, resolution, stride, color space, [`StreamKind`] and [`FrameMetadata`], so it can be decoded with any [`FrameDecoder`](crate::FrameDecoder).
+    /// The default implementation wraps [`frame_raw()`](CaptureBackendTrait::frame_raw()).
+    /// # Errors
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
+    /// Backends that decode frames themselves have no undecoded frames, and return [`NokhwaError::UnsupportedOperationError`].
+    fn undecoded_frame(&mut self) -> Result<RawFrame, NokhwaError> {
+        let camera_format = self.camera_format();
+        let stride = self.frame_stride();
+        let color_space = self.color_space();
+        let stream_kind = match camera_format.format() {
+            FrameFormat::Z16 => StreamKind::Depth,
+            _ => self.camera_info().stream_kind(),
+        };
+        let data = self.frame_raw()?;
+        Ok(RawFrame::new(
+            data,
+            camera_format.format(),
+            camera_format.resolution(),
+            stride,
+            color_space,
+        )
+        .with_stream_kind(stream_kind))
+    }
+
+    /// Will get the freshest frame the camera can provide within `deadline` as a Raw RGB image buffer. Unlike [`frame()`](CaptureBackendTrait::frame()), this will never block for longer than `deadline` waiting on the camera.
+    ///
+    /// Frames that were already waiting when this is called are skipped in favour of the newest one, where the backend allows it.
+    /// Decoding the frame happens after it arrives, and is not counted against `deadline`.
+    /// # Errors
+    /// If no frame becomes available in time this will return [`NokhwaError::Timeout`]. Otherwise, this errors the same way as [`frame()`](CaptureBackendTrait::frame()).
+    /// The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn frame_with_deadline(
+        &mut self,
+        _deadline: Duration,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Will get the next frame like [`frame_raw()`](CaptureBackendTrait::frame_raw()), but will never block for longer than `deadline` waiting on the camera.
+    /// # Errors
+    /// If no frame becomes available in time this will return [`NokhwaError::Timeout`]. Otherwise, this errors the same way as [`frame_raw()`](CaptureBackendTrait::frame_raw()).
+    /// The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn frame_raw_with_deadline(&mut self, _deadline: Duration) -> Result<Cow<[u8]>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
+    /// Will capture a still photo as a Raw RGB image buffer, using the camera's dedicated still capture (e.g. a UVC still image pin or a Media Foundation photo stream) where it has one.
+    /// Photos are usually at a higher resolution than the video stream, so their size may differ from [`resolution()`](CaptureBackendTrait::resolution()).
+    /// # Errors
+    /// If the backend fails to capture or decode the photo, this will error. The default implementation returns [`NokhwaError::UnsupportedOperationError`].
+    fn take_photo(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        Err(NokhwaError::UnsupportedOperationError(self.backend()))
+    }
+
     /// The minimum buffer size needed to write the current frame (RGB24). If `rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
     fn min_buffer_size(&self, rgba: bool) -> usize {
         let resolution = self.resolution();
@@ -157,37 +286,19 @@ pub trait CaptureBackendTrait {
     }
 
     /// Directly writes the current frame(RGB24) into said `buffer`. If `convert_rgba` is true, the buffer written will be written as an RGBA frame instead of a RGB frame. Returns the amount of bytes written on successful capture.
+    /// The frame is decoded with [`frame()`](CaptureBackendTrait::frame()), so padded rows (see [`frame_stride()`](CaptureBackendTrait::frame_stride())) are never written.
     /// # Errors
-    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
+    /// If the backend fails to get the frame (e.g. already taken, busy, doesn't exist anymore), `buffer` is smaller than [`min_buffer_size()`](CaptureBackendTrait::min_buffer_size()), or [`open_stream()`](CaptureBackendTrait::open_stream()) has not been called yet, this will error.
     fn write_frame_to_buffer(
         &mut self,
         buffer: &mut [u8],
         convert_rgba: bool,
     ) -> Result<usize, NokhwaError> {
-        let resolution = self.resolution();
-        let frame = self.frame_raw()?;
-        if convert_rgba {
-            let image_data =
-                match ImageBuffer::from_raw(resolution.width(), resolution.height(), frame) {
-                    Some(image) => {
-                        let image: ImageBuffer<Rgb<u8>, Cow<[u8]>> = image;
-                        image
-                    }
-                    None => {
-                        return Err(NokhwaError::ReadFrameError(
-                            "Frame Cow Too Small".to_string(),
-                        ))
-                    }
-                };
-            let rgba_image: RgbaImage = image_data.convert();
-            buffer.copy_from_slice(rgba_image.as_raw());
-            return Ok(rgba_image.len());
-        }
-        buffer.copy_from_slice(&frame);
-        Ok(frame.len())
+        let frame = self.frame()?;
+        write_rgb_to_buffer(&frame, buffer, convert_rgba)
     }
 
-    #[cfg(feature = "output-wgpu")]
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
     /// Directly copies a frame to a Wgpu texture. This will automatically convert the frame into a RGBA frame.
     /// # Errors
     /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
@@ -197,52 +308,38 @@ pub trait CaptureBackendTrait {
         queue: &WgpuQueue,
         label: Option<&'a str>,
     ) -> Result<WgpuTexture, NokhwaError> {
-        use std::{convert::TryFrom, num::NonZeroU32};
+        self.frame_texture_with(device, queue, label, TextureOptions::default())
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+    /// Directly copies a frame to a Wgpu texture made with `options`, e.g. in a swapchain-compatible `Bgra8Unorm` format. See [`TextureOptions`].
+    /// # Errors
+    /// If the frame cannot be captured, the resolution is 0 on any axis, or the format of `options` is not supported, this will error.
+    fn frame_texture_with<'a>(
+        &mut self,
+        device: &WgpuDevice,
+        queue: &WgpuQueue,
+        label: Option<&'a str>,
+        options: TextureOptions,
+    ) -> Result<WgpuTexture, NokhwaError> {
         let frame = self.frame()?;
         let rgba_frame: RgbaImage = frame.convert();
-
-        let texture_size = Extent3d {
-            width: frame.width(),
-            height: frame.height(),
-            depth_or_array_layers: 1,
-        };
-
-        let texture = device.create_texture(&TextureDescriptor {
+        frame_to_texture(
+            device,
+            queue,
             label,
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
-        });
-
-        let width_nonzero = match NonZeroU32::try_from(4 * rgba_frame.width()) {
-            Ok(w) => Some(w),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        let height_nonzero = match NonZeroU32::try_from(rgba_frame.height()) {
-            Ok(h) => Some(h),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        queue.write_texture(
-            ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba_frame.to_vec(),
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: width_nonzero,
-                rows_per_image: height_nonzero,
-            },
-            texture_size,
-        );
+            Resolution::new(rgba_frame.width(), rgba_frame.height()),
+            rgba_frame.as_raw(),
+            options,
+        )
+    }
 
-        Ok(texture)
+    /// Returns the native handle of the backend, see [`RawHandle`]. The default implementation returns `None`.
+    /// # Safety
+    /// The handle is owned by the backend and is only valid for as long as it is alive. Do not close or release it.
+    /// Changing the format or stream behind `nokhwa`'s back will desynchronize the [`CameraFormat`] it reports.
+    unsafe fn raw_handle(&mut self) -> Option<RawHandle> {
+        None
     }
 
     /// Will drop the stream.
@@ -252,3 +349,29 @@ pub trait CaptureBackendTrait {
 }
 
 pub trait VirtualBackendTrait {}
+
+// writes `frame` (as RGBA if `convert_rgba`) into the start of `buffer`, returning the amount of bytes written
+pub(crate) fn write_rgb_to_buffer(
+    frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    buffer: &mut [u8],
+    convert_rgba: bool,
+) -> Result<usize, NokhwaError> {
+    let rgba_image: RgbaImage;
+    let data: &[u8] = if convert_rgba {
+        rgba_image = frame.convert();
+        rgba_image.as_raw()
+    } else {
+        frame.as_raw()
+    };
+    match buffer.get_mut(..data.len()) {
+        Some(out) => {
+            out.copy_from_slice(data);
+            Ok(data.len())
+        }
+        None => Err(NokhwaError::ReadFrameError(format!(
+            "Buffer is {} bytes, the frame needs {}",
+            buffer.len(),
+            data.len()
+        ))),
+    }
+}
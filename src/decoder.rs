@@ -0,0 +1,345 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    debayer, mjpeg_to_rgb888, p010_to_luma16, p010_to_rgb888_with, pack_frame_rows,
+    utils::passthrough_error, y16_to_luma16, y16_to_rgb888, yuyv422_to_rgb888_strided,
+    yuyv422_to_rgb888_with, z16_to_rgb888, ColorSpace, FrameFormat, NokhwaError, Resolution,
+    StreamKind,
+};
+use image::{buffer::ConvertBuffer, GrayImage, ImageBuffer, Rgb, RgbaImage};
+use std::{borrow::Cow, time::Duration};
+
+/// A frame as it came from the camera, before any decoding, along with everything needed to decode it.
+///
+/// Get one with [`Camera::undecoded_frame()`](crate::Camera::undecoded_frame) (or [`CaptureBackendTrait::undecoded_frame()`](crate::CaptureBackendTrait::undecoded_frame)), and decode it with a [`FrameDecoder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawFrame<'a> {
+    data: Cow<'a, [u8]>,
+    format: FrameFormat,
+    resolution: Resolution,
+    stride: Option<usize>,
+    color_space: ColorSpace,
+    stream_kind: StreamKind,
+    metadata: Option<FrameMetadata>,
+}
+
+impl<'a> RawFrame<'a> {
+    /// Creates a new [`RawFrame`]. `stride` is the bytes from the start of one row to the next, or `None` if the rows are tightly packed (see [`frame_stride()`](crate::CaptureBackendTrait::frame_stride)).
+    /// The [`StreamKind`] is the one `format` implies, use [`with_stream_kind()`](RawFrame::with_stream_kind) to set another.
+    #[must_use]
+    pub fn new(
+        data: Cow<'a, [u8]>,
+        format: FrameFormat,
+        resolution: Resolution,
+        stride: Option<usize>,
+        color_space: ColorSpace,
+    ) -> Self {
+        RawFrame {
+            data,
+            format,
+            resolution,
+            stride,
+            color_space,
+            stream_kind: StreamKind::from(format),
+            metadata: None,
+        }
+    }
+
+    /// Sets what the frame is an image of, e.g. for the infrared sensor of a depth camera.
+    #[must_use]
+    pub fn with_stream_kind(mut self, stream_kind: StreamKind) -> Self {
+        self.stream_kind = stream_kind;
+        self
+    }
+
+    /// Attaches the [`FrameMetadata`] the camera reported for the frame.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: FrameMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// The undecoded data, which may have padded rows. See [`stride()`](RawFrame::stride).
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The [`FrameFormat`] of the data.
+    #[must_use]
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    /// The [`Resolution`] of the frame.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The bytes from the start of one row to the next, or `None` if the rows are tightly packed.
+    #[must_use]
+    pub fn stride(&self) -> Option<usize> {
+        self.stride
+    }
+
+    /// The [`ColorSpace`] YUV data is in.
+    #[must_use]
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// What the frame is an image of: color, infrared or depth.
+    #[must_use]
+    pub fn stream_kind(&self) -> StreamKind {
+        self.stream_kind
+    }
+
+    /// The [`FrameMetadata`] of the frame, if capturing it is on (see [`set_frame_metadata()`](crate::CaptureBackendTrait::set_frame_metadata)) and the backend reports it.
+    #[must_use]
+    pub fn metadata(&self) -> Option<FrameMetadata> {
+        self.metadata
+    }
+
+    /// The data with the row padding removed. See [`pack_frame_rows()`](crate::pack_frame_rows).
+    /// # Errors
+    /// If the data is too small for the resolution and stride, this will error.
+    pub fn packed_data(&self) -> Result<Cow<[u8]>, NokhwaError> {
+        match self.stride {
+            Some(stride) => pack_frame_rows(&self.data, self.format, self.resolution, stride),
+            None => Ok(Cow::Borrowed(&self.data)),
+        }
+    }
+
+    /// Copies the data if it is borrowed (e.g. from the driver's buffer), so the frame can outlive the next capture.
+    #[must_use]
+    pub fn into_owned(self) -> RawFrame<'static> {
+        RawFrame {
+            data: Cow::Owned(self.data.into_owned()),
+            format: self.format,
+            resolution: self.resolution,
+            stride: self.stride,
+            color_space: self.color_space,
+            stream_kind: self.stream_kind,
+            metadata: self.metadata,
+        }
+    }
+}
+
+/// What the driver and the camera reported about how a frame was captured, e.g. to sync it with other sensors or to undo motion blur.
+///
+/// Every field is `None` when it is not reported. Get it with [`RawFrame::metadata()`] or [`frame_metadata()`](crate::CaptureBackendTrait::frame_metadata).
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct FrameMetadata {
+    sequence: Option<u32>,
+    timestamp: Option<Duration>,
+    presentation_time: Option<u32>,
+    source_time: Option<u32>,
+    start_of_frame: Option<u16>,
+    exposure_time: Option<Duration>,
+    iso_speed: Option<u32>,
+}
+
+impl FrameMetadata {
+    /// Creates an empty [`FrameMetadata`].
+    #[must_use]
+    pub fn new() -> Self {
+        FrameMetadata::default()
+    }
+
+    /// Sets the sequence number.
+    #[must_use]
+    pub fn with_sequence(mut self, sequence: u32) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Sets the capture timestamp.
+    #[must_use]
+    pub fn with_timestamp(mut self, timestamp: Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the presentation time stamp (PTS), in device clock ticks.
+    #[must_use]
+    pub fn with_presentation_time(mut self, presentation_time: u32) -> Self {
+        self.presentation_time = Some(presentation_time);
+        self
+    }
+
+    /// Sets the source clock reference (SCR): the device clock, and the USB frame number it was sampled at.
+    #[must_use]
+    pub fn with_source_clock(mut self, source_time: u32, start_of_frame: u16) -> Self {
+        self.source_time = Some(source_time);
+        self.start_of_frame = Some(start_of_frame);
+        self
+    }
+
+    /// Sets the exposure time.
+    #[must_use]
+    pub fn with_exposure_time(mut self, exposure_time: Duration) -> Self {
+        self.exposure_time = Some(exposure_time);
+        self
+    }
+
+    /// Sets the sensor gain, as an ISO speed.
+    #[must_use]
+    pub fn with_iso_speed(mut self, iso_speed: u32) -> Self {
+        self.iso_speed = Some(iso_speed);
+        self
+    }
+
+    /// The driver's frame counter. Gaps in it are dropped frames.
+    #[must_use]
+    pub fn sequence(&self) -> Option<u32> {
+        self.sequence
+    }
+
+    /// When the driver captured the frame, on the host's monotonic clock (`CLOCK_MONOTONIC` on Linux).
+    #[must_use]
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// When the camera started exposing the frame, in ticks of the camera's own clock (the UVC PTS).
+    #[must_use]
+    pub fn presentation_time(&self) -> Option<u32> {
+        self.presentation_time
+    }
+
+    /// The camera's clock when the frame was sent (the UVC SCR), which together with [`start_of_frame()`](FrameMetadata::start_of_frame) maps [`presentation_time()`](FrameMetadata::presentation_time) onto the host clock.
+    #[must_use]
+    pub fn source_time(&self) -> Option<u32> {
+        self.source_time
+    }
+
+    /// The USB frame number (11 bits) that [`source_time()`](FrameMetadata::source_time) was sampled at.
+    #[must_use]
+    pub fn start_of_frame(&self) -> Option<u16> {
+        self.start_of_frame
+    }
+
+    /// How long the sensor was exposed for.
+    #[must_use]
+    pub fn exposure_time(&self) -> Option<Duration> {
+        self.exposure_time
+    }
+
+    /// The sensor gain, as an ISO speed.
+    #[must_use]
+    pub fn iso_speed(&self) -> Option<u32> {
+        self.iso_speed
+    }
+}
+
+/// Decodes [`RawFrame`]s into images. [`DefaultDecoder`] is what `nokhwa` uses, implement this to decode with something else, e.g. on the GPU or with `turbojpeg`.
+///
+/// Set one with [`Camera::set_frame_decoder()`](crate::Camera::set_frame_decoder).
+///
+/// This is implemented for any `FnMut(&RawFrame) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> + Send` closure.
+pub trait FrameDecoder: Send {
+    /// Decodes `frame` into RGB.
+    /// # Errors
+    /// If the frame cannot be decoded (e.g. it is corrupt, or its format is not supported), this will error.
+    fn decode_rgb(
+        &mut self,
+        frame: &RawFrame,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError>;
+
+    /// Decodes `frame` into RGBA. The default implementation converts the output of [`decode_rgb()`](FrameDecoder::decode_rgb), with an opaque alpha.
+    /// # Errors
+    /// If the frame cannot be decoded, this will error.
+    fn decode_rgba(&mut self, frame: &RawFrame) -> Result<RgbaImage, NokhwaError> {
+        Ok(self.decode_rgb(frame)?.convert())
+    }
+
+    /// Decodes `frame` into luma (gray). The default implementation converts the output of [`decode_rgb()`](FrameDecoder::decode_rgb).
+    /// # Errors
+    /// If the frame cannot be decoded, this will error.
+    fn decode_luma(&mut self, frame: &RawFrame) -> Result<GrayImage, NokhwaError> {
+        Ok(self.decode_rgb(frame)?.convert())
+    }
+}
+
+impl<F> FrameDecoder for F
+where
+    F: FnMut(&RawFrame) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> + Send,
+{
+    fn decode_rgb(
+        &mut self,
+        frame: &RawFrame,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        self(frame)
+    }
+}
+
+/// The [`FrameDecoder`] `nokhwa` uses, with the converters in this crate: `mozjpeg` for MJPEG, [`yuyv422_to_rgb888_with()`] for YUYV, [`debayer()`] for Bayer formats, and so on.
+/// H264 and H265 are not decoded.
+///
+/// Luma is taken straight from the Y samples of YUYV, Y16 and P010 frames, without going through RGB. Z16 depth is scaled for display, see [`z16_to_rgb888()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DefaultDecoder;
+
+impl FrameDecoder for DefaultDecoder {
+    fn decode_rgb(
+        &mut self,
+        frame: &RawFrame,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let resolution = frame.resolution();
+        let color_space = frame.color_space();
+        let conv = match (frame.format(), frame.stride()) {
+            // YUYV skips over the padding itself, everything else is packed first
+            (FrameFormat::YUYV, Some(stride)) => {
+                yuyv422_to_rgb888_strided(frame.data(), resolution, stride, color_space)?
+            }
+            (FrameFormat::YUYV, None) => yuyv422_to_rgb888_with(frame.data(), color_space)?,
+            (FrameFormat::MJPEG, _) => mjpeg_to_rgb888(frame.data())?,
+            (format @ FrameFormat::Bayer8(_), _)
+            | (format @ FrameFormat::Bayer10(_), _)
+            | (format @ FrameFormat::Bayer12(_), _) => {
+                debayer(&frame.packed_data()?, resolution, format)?
+            }
+            (FrameFormat::Y16, _) => y16_to_rgb888(&frame.packed_data()?)?,
+            (FrameFormat::Z16, _) => z16_to_rgb888(&frame.packed_data()?)?,
+            (FrameFormat::P010, _) => {
+                p010_to_rgb888_with(&frame.packed_data()?, resolution, color_space)?
+            }
+            (format @ FrameFormat::H264, _) | (format @ FrameFormat::H265, _) => {
+                return Err(passthrough_error(format, "RGB888"))
+            }
+        };
+        match ImageBuffer::from_vec(resolution.width(), resolution.height(), conv) {
+            Some(buf) => Ok(buf),
+            None => Err(NokhwaError::ReadFrameError(
+                "ImageBuffer is not large enough! Is the frame the right size?".to_string(),
+            )),
+        }
+    }
+
+    fn decode_luma(&mut self, frame: &RawFrame) -> Result<GrayImage, NokhwaError> {
+        let resolution = frame.resolution();
+        let luma = match frame.format() {
+            FrameFormat::YUYV => frame.packed_data()?.iter().step_by(2).copied().collect(),
+            FrameFormat::Y16 => y16_to_luma16(&frame.packed_data()?)?
+                .into_iter()
+                .map(|luma| luma.to_be_bytes()[0])
+                .collect(),
+            FrameFormat::P010 => p010_to_luma16(&frame.packed_data()?, resolution)?
+                .into_iter()
+                .map(|luma| luma.to_be_bytes()[0])
+                .collect(),
+            _ => return Ok(self.decode_rgb(frame)?.convert()),
+        };
+        match ImageBuffer::from_vec(resolution.width(), resolution.height(), luma) {
+            Some(buf) => Ok(buf),
+            None => Err(NokhwaError::ReadFrameError(
+                "ImageBuffer is not large enough! Is the frame the right size?".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,525 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    backend_priority, camera_permission_status, probe_availability, query_devices, Camera,
+    CameraAvailability, CameraControl, CameraFormat, CameraInfo, CameraPermission,
+    CaptureAPIBackend, NokhwaError, Quirk,
+};
+use std::fmt::{Display, Formatter, Write};
+
+macro_rules! enabled_features {
+    ($( $feature:expr ),+) => {
+        {
+            let mut features = vec![];
+            $(
+                if cfg!(feature = $feature) {
+                    features.push($feature);
+                }
+            )+
+            features
+        }
+    };
+}
+
+/// Generates a [`DiagnosticsReport`] of this machine: the `nokhwa` version and features it was built with, the OS, the [camera permission](crate::camera_permission_status), and every device each backend in the
+/// [`backend_priority()`](crate::backend_priority) can find, with its formats, controls and quirks.
+///
+/// If you are filing a bug report, please include the output of this (`println!("{}", nokhwa::diagnostics())`, or [`to_json()`](DiagnosticsReport::to_json)).
+/// # Quirks
+/// - Every device is opened (but not streamed from) to read its formats and controls, so this is slow, and a device that is in use may show errors.
+/// - Device serial numbers are left out on purpose, as reports are usually posted in public.
+/// - The OS version is only known on Linux.
+#[must_use]
+pub fn diagnostics() -> DiagnosticsReport {
+    let priority = backend_priority();
+    let backends = priority
+        .iter()
+        .filter(|backend| **backend != CaptureAPIBackend::Auto)
+        .map(|backend| diagnose_backend(*backend))
+        .collect();
+
+    DiagnosticsReport {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        os_version: os_version(),
+        arch: std::env::consts::ARCH,
+        features: enabled_features!(
+            "input-uvc",
+            "input-v4l",
+            "input-opencv",
+            "input-ipcam",
+            "input-gst",
+            "input-msmf",
+            "input-onvif",
+            "input-jscam",
+            "output-wgpu",
+            "output-wgpu-0-19",
+            "output-ash",
+            "output-rgb565",
+            "output-ndarray",
+            "output-http-stream",
+            "output-recorder",
+            "output-webrtc",
+            "output-threaded",
+            "output-shm",
+            "output-ndi",
+            "output-virtualcam",
+            "metrics",
+            "tracing",
+            "rayon",
+            "turbojpeg",
+            "mock",
+            "plugins"
+        ),
+        backend_priority: priority,
+        permission: camera_permission_status(),
+        backends,
+    }
+}
+
+fn diagnose_backend(backend: CaptureAPIBackend) -> BackendDiagnostics {
+    match query_devices(backend) {
+        Ok(devices) => BackendDiagnostics {
+            backend,
+            devices: devices
+                .into_iter()
+                .map(|info| diagnose_device(info, backend))
+                .collect(),
+            error: None,
+        },
+        Err(why) => BackendDiagnostics {
+            backend,
+            devices: vec![],
+            error: Some(why),
+        },
+    }
+}
+
+fn diagnose_device(info: CameraInfo, backend: CaptureAPIBackend) -> DeviceDiagnostics {
+    let mut device = DeviceDiagnostics {
+        info,
+        availability: None,
+        format: None,
+        formats: vec![],
+        controls: vec![],
+        quirks: vec![],
+        errors: vec![],
+    };
+    let index = *device.info.index();
+
+    match probe_availability(index, backend) {
+        Ok(availability) => device.availability = Some(availability),
+        Err(why) => device.errors.push(why),
+    }
+
+    let mut camera = match Camera::new(index, None, backend) {
+        Ok(camera) => camera,
+        Err(why) => {
+            device.errors.push(why);
+            return device;
+        }
+    };
+    device.format = Some(camera.camera_format());
+    device.quirks = camera.quirks();
+
+    match camera.compatible_camera_formats() {
+        Ok(formats) => device.formats = formats,
+        Err(NokhwaError::UnsupportedOperationError(_)) => {}
+        Err(why) => device.errors.push(why),
+    }
+
+    match camera.supported_camera_controls() {
+        Ok(controls) => {
+            for control in controls {
+                match camera.camera_control(control) {
+                    Ok(control) => device.controls.push(control),
+                    Err(why) => device.errors.push(why),
+                }
+            }
+        }
+        Err(NokhwaError::UnsupportedOperationError(_)) => {}
+        Err(why) => device.errors.push(why),
+    }
+
+    device
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok();
+    let distribution = std::fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|os_release| {
+            os_release
+                .lines()
+                .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+                .map(|name| name.trim_matches('"').to_string())
+        });
+    match (distribution, kernel) {
+        (Some(distribution), Some(kernel)) => {
+            Some(format!("{} (kernel {})", distribution, kernel.trim()))
+        }
+        (Some(distribution), None) => Some(distribution),
+        (None, Some(kernel)) => Some(format!("kernel {}", kernel.trim())),
+        (None, None) => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_version() -> Option<String> {
+    None
+}
+
+/// A report of the `nokhwa` build, the OS and the devices found, made by [`diagnostics()`]. Print it with [`Display`], or turn it into JSON with [`to_json()`](DiagnosticsReport::to_json).
+#[derive(Clone, Debug)]
+pub struct DiagnosticsReport {
+    version: &'static str,
+    os: &'static str,
+    os_version: Option<String>,
+    arch: &'static str,
+    features: Vec<&'static str>,
+    backend_priority: Vec<CaptureAPIBackend>,
+    permission: CameraPermission,
+    backends: Vec<BackendDiagnostics>,
+}
+
+impl DiagnosticsReport {
+    /// The version of `nokhwa`.
+    #[must_use]
+    pub fn version(&self) -> &'static str {
+        self.version
+    }
+
+    /// The OS, as in [`std::env::consts::OS`].
+    #[must_use]
+    pub fn os(&self) -> &'static str {
+        self.os
+    }
+
+    /// The OS distribution and version, if known.
+    #[must_use]
+    pub fn os_version(&self) -> Option<&String> {
+        self.os_version.as_ref()
+    }
+
+    /// The CPU architecture, as in [`std::env::consts::ARCH`].
+    #[must_use]
+    pub fn arch(&self) -> &'static str {
+        self.arch
+    }
+
+    /// The features `nokhwa` was built with.
+    #[must_use]
+    pub fn features(&self) -> &Vec<&'static str> {
+        &self.features
+    }
+
+    /// The [`backend_priority()`](crate::backend_priority) at the time of the report.
+    #[must_use]
+    pub fn backend_priority(&self) -> &Vec<CaptureAPIBackend> {
+        &self.backend_priority
+    }
+
+    /// Whether this program may use the cameras, see [`camera_permission_status()`](crate::camera_permission_status).
+    #[must_use]
+    pub fn permission(&self) -> CameraPermission {
+        self.permission
+    }
+
+    /// What each backend found.
+    #[must_use]
+    pub fn backends(&self) -> &Vec<BackendDiagnostics> {
+        &self.backends
+    }
+
+    /// The report as a JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let backends: Vec<String> = self
+            .backends
+            .iter()
+            .map(BackendDiagnostics::to_json)
+            .collect();
+        format!(
+            "{{\"version\":{},\"os\":{},\"os_version\":{},\"arch\":{},\"features\":{},\"backend_priority\":{},\"permission\":{},\"backends\":[{}]}}",
+            json_string(self.version),
+            json_string(self.os),
+            json_optional(self.os_version.as_ref()),
+            json_string(self.arch),
+            json_list(&self.features),
+            json_list(&self.backend_priority),
+            json_string(&self.permission.to_string()),
+            backends.join(",")
+        )
+    }
+}
+
+impl Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "nokhwa {} on {} ({})", self.version, self.os, self.arch)?;
+        if let Some(os_version) = &self.os_version {
+            writeln!(f, "OS: {}", os_version)?;
+        }
+        writeln!(f, "Features: {}", display_list(&self.features))?;
+        writeln!(
+            f,
+            "Backend priority: {}",
+            display_list(&self.backend_priority)
+        )?;
+        writeln!(f, "Camera permission: {}", self.permission)?;
+        for backend in &self.backends {
+            write!(f, "{}", backend)?;
+        }
+        Ok(())
+    }
+}
+
+/// The devices a backend found, see [`DiagnosticsReport`].
+#[derive(Clone, Debug)]
+pub struct BackendDiagnostics {
+    backend: CaptureAPIBackend,
+    devices: Vec<DeviceDiagnostics>,
+    error: Option<NokhwaError>,
+}
+
+impl BackendDiagnostics {
+    /// The backend.
+    #[must_use]
+    pub fn backend(&self) -> CaptureAPIBackend {
+        self.backend
+    }
+
+    /// The devices the backend found.
+    #[must_use]
+    pub fn devices(&self) -> &Vec<DeviceDiagnostics> {
+        &self.devices
+    }
+
+    /// Why the backend could not list its devices, if it could not.
+    #[must_use]
+    pub fn error(&self) -> Option<&NokhwaError> {
+        self.error.as_ref()
+    }
+
+    fn to_json(&self) -> String {
+        let devices: Vec<String> = self
+            .devices
+            .iter()
+            .map(DeviceDiagnostics::to_json)
+            .collect();
+        format!(
+            "{{\"backend\":{},\"error\":{},\"devices\":[{}]}}",
+            json_string(&self.backend.to_string()),
+            json_optional(self.error.as_ref()),
+            devices.join(",")
+        )
+    }
+}
+
+impl Display for BackendDiagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(why) = &self.error {
+            return writeln!(f, "{}: failed to query devices: {}", self.backend, why);
+        }
+        writeln!(f, "{}: {} device(s)", self.backend, self.devices.len())?;
+        for device in &self.devices {
+            write!(f, "{}", device)?;
+        }
+        Ok(())
+    }
+}
+
+/// A device, with what could be read from it, see [`DiagnosticsReport`].
+#[derive(Clone, Debug)]
+pub struct DeviceDiagnostics {
+    info: CameraInfo,
+    availability: Option<CameraAvailability>,
+    format: Option<CameraFormat>,
+    formats: Vec<CameraFormat>,
+    controls: Vec<CameraControl>,
+    quirks: Vec<Quirk>,
+    errors: Vec<NokhwaError>,
+}
+
+impl DeviceDiagnostics {
+    /// The device's [`CameraInfo`].
+    #[must_use]
+    pub fn info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    /// Whether the device was in use, if the backend can tell.
+    #[must_use]
+    pub fn availability(&self) -> Option<CameraAvailability> {
+        self.availability
+    }
+
+    /// The format the device opened with, if it could be opened.
+    #[must_use]
+    pub fn format(&self) -> Option<CameraFormat> {
+        self.format
+    }
+
+    /// The formats the device supports. This is empty if the backend cannot list them.
+    #[must_use]
+    pub fn formats(&self) -> &Vec<CameraFormat> {
+        &self.formats
+    }
+
+    /// The controls the device supports, with their current values.
+    #[must_use]
+    pub fn controls(&self) -> &Vec<CameraControl> {
+        &self.controls
+    }
+
+    /// The [`Quirk`]s known for the device.
+    #[must_use]
+    pub fn quirks(&self) -> &Vec<Quirk> {
+        &self.quirks
+    }
+
+    /// Everything that failed while reading the device.
+    #[must_use]
+    pub fn errors(&self) -> &Vec<NokhwaError> {
+        &self.errors
+    }
+
+    fn to_json(&self) -> String {
+        let controls: Vec<String> = self
+            .controls
+            .iter()
+            .map(|control| {
+                format!(
+                    "{{\"control\":{},\"value\":{},\"minimum\":{},\"maximum\":{},\"step\":{},\"default\":{},\"flag\":{},\"active\":{}}}",
+                    json_string(&control.control().to_string()),
+                    control.value(),
+                    control.minimum_value(),
+                    control.maximum_value(),
+                    control.step(),
+                    control.default(),
+                    json_string(&format!("{:?}", control.flag())),
+                    control.active()
+                )
+            })
+            .collect();
+        format!(
+            "{{\"index\":{},\"name\":{},\"description\":{},\"misc\":{},\"vendor_id\":{},\"product_id\":{},\"bus_path\":{},\"position\":{},\"availability\":{},\"format\":{},\"formats\":{},\"controls\":[{}],\"quirks\":{},\"errors\":{}}}",
+            self.info.index(),
+            json_string(self.info.human_name()),
+            json_string(self.info.description()),
+            json_string(self.info.misc()),
+            json_optional(self.info.vendor_id().map(|vid| format!("{:04x}", vid)).as_ref()),
+            json_optional(self.info.product_id().map(|pid| format!("{:04x}", pid)).as_ref()),
+            json_optional(self.info.bus_path()),
+            json_string(&self.info.position().to_string()),
+            json_optional(self.availability.as_ref()),
+            json_optional(self.format.as_ref()),
+            json_list(&self.formats),
+            controls.join(","),
+            json_list(&self.quirks),
+            json_list(&self.errors)
+        )
+    }
+}
+
+impl Display for DeviceDiagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "  [{}] {} ({}, {})",
+            self.info.index(),
+            self.info.human_name(),
+            self.info.description(),
+            self.info.misc()
+        )?;
+        if let (Some(vid), Some(pid)) = (self.info.vendor_id(), self.info.product_id()) {
+            writeln!(f, "    USB ID: {:04x}:{:04x}", vid, pid)?;
+        }
+        if let Some(bus_path) = self.info.bus_path() {
+            writeln!(f, "    Bus path: {}", bus_path)?;
+        }
+        writeln!(f, "    Position: {}", self.info.position())?;
+        if let Some(availability) = self.availability {
+            writeln!(f, "    Availability: {}", availability)?;
+        }
+        if let Some(format) = self.format {
+            writeln!(f, "    Current format: {}", format)?;
+        }
+        writeln!(f, "    Formats ({}):", self.formats.len())?;
+        for format in &self.formats {
+            writeln!(f, "      {}", format)?;
+        }
+        writeln!(f, "    Controls ({}):", self.controls.len())?;
+        for control in &self.controls {
+            writeln!(
+                f,
+                "      {}: {} ({} to {}, step {}, default {}, {:?}{})",
+                control.control(),
+                control.value(),
+                control.minimum_value(),
+                control.maximum_value(),
+                control.step(),
+                control.default(),
+                control.flag(),
+                if control.active() { "" } else { ", inactive" }
+            )?;
+        }
+        if !self.quirks.is_empty() {
+            writeln!(f, "    Quirks: {}", display_list(&self.quirks))?;
+        }
+        for error in &self.errors {
+            writeln!(f, "    Error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+fn display_list<T: Display>(items: &[T]) -> String {
+    if items.is_empty() {
+        return "none".to_string();
+    }
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if control.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", control as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_optional<T: Display>(value: Option<&T>) -> String {
+    match value {
+        Some(value) => json_string(&value.to_string()),
+        None => "null".to_string(),
+    }
+}
+
+fn json_list<T: Display>(items: &[T]) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| json_string(&item.to_string()))
+        .collect();
+    format!("[{}]", items.join(","))
+}
@@ -4,13 +4,28 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+#[cfg(feature = "input-v4l")]
+use std::io;
+use std::time::Duration;
 use thiserror::Error;
 
 use crate::{CaptureAPIBackend, FrameFormat};
 
 /// All errors in `nokhwa`.
+///
+/// Where the backend can tell, losing the device, the device being in use by someone else, being denied access and the device not supporting a format
+/// have their own variants ([`DeviceDisconnected`](NokhwaError::DeviceDisconnected), [`DeviceBusy`](NokhwaError::DeviceBusy), [`PermissionDenied`](NokhwaError::PermissionDenied)
+/// and [`UnsupportedFormat`](NokhwaError::UnsupportedFormat)), so they can be told apart from other failures.
+/// # Quirks
+/// - `Video4Linux`: Mapped from `ENODEV`/`ENXIO`, `EBUSY`, `EACCES`/`EPERM` and (when setting a format) `EINVAL`.
+/// - Media Foundation: Mapped from the `HRESULT`s for an invalidated device, a locked or preempted device, `E_ACCESSDENIED` (including the camera privacy setting) and an invalid media type.
+/// - `GStreamer`: Mapped from `ResourceError::NotFound`/`Read`, `ResourceError::Busy` and `StreamError::Format`/`CoreError::Negotiation` pipeline errors.
+/// - UVC: Mapped from `libuvc`'s `NoDevice`, `Busy`, `Access` and `InvalidMode` errors.
+/// - `JSCamera`: Mapped from the `NotReadableError`, `NotAllowedError`/`SecurityError` and `OverconstrainedError` `getUserMedia` rejections. A device that goes away is not reported.
+/// - `OpenCV` does not tell why it failed, so it never returns these.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Error, Debug, Clone)]
+#[non_exhaustive]
 pub enum NokhwaError {
     #[error("Could not initialize {backend}: {error}")]
     InitializeError {
@@ -52,6 +67,61 @@ pub enum NokhwaError {
     UnsupportedOperationError(CaptureAPIBackend),
     #[error("This operation is not implemented yet: {0}")]
     NotImplementedError(String),
+    #[error("No frame became available within {0:?}")]
+    Timeout(Duration),
+    #[error("Device {device} was disconnected: {error}")]
+    DeviceDisconnected { device: String, error: String },
+    #[error("Device {device} is in use: {error}")]
+    DeviceBusy { device: String, error: String },
+    #[error("Permission to use device {device} was denied: {error}")]
+    PermissionDenied { device: String, error: String },
+    #[error("Format {format} is not supported: {error}")]
+    UnsupportedFormat { format: String, error: String },
+}
+
+impl NokhwaError {
+    /// Maps the OS error `error` from `device` to [`DeviceDisconnected`](NokhwaError::DeviceDisconnected), [`DeviceBusy`](NokhwaError::DeviceBusy) or
+    /// [`PermissionDenied`](NokhwaError::PermissionDenied) where it is one of those, and to `otherwise` with the error message if it is not.
+    #[cfg(feature = "input-v4l")]
+    pub(crate) fn from_io_error(
+        device: &str,
+        error: &io::Error,
+        otherwise: impl FnOnce(String) -> NokhwaError,
+    ) -> NokhwaError {
+        // these are the same on every Unix
+        const EPERM: i32 = 1;
+        const ENXIO: i32 = 6;
+        const EACCES: i32 = 13;
+        const EBUSY: i32 = 16;
+        const ENODEV: i32 = 19;
+
+        let device = device.to_string();
+        let message = error.to_string();
+        if error.kind() == io::ErrorKind::PermissionDenied {
+            return NokhwaError::PermissionDenied {
+                device,
+                error: message,
+            };
+        }
+        if !cfg!(unix) {
+            return otherwise(message);
+        }
+        match error.raw_os_error() {
+            Some(ENODEV | ENXIO) => NokhwaError::DeviceDisconnected {
+                device,
+                error: message,
+            },
+            Some(EBUSY) => NokhwaError::DeviceBusy {
+                device,
+                error: message,
+            },
+            Some(EPERM | EACCES) => NokhwaError::PermissionDenied {
+                device,
+                error: message,
+            },
+            _ => otherwise(message),
+        }
+    }
 }
 #[cfg(feature = "input-msmf")]
 use nokhwa_bindings_windows::BindingError;
@@ -88,6 +158,21 @@ impl From<nokhwa_bindings_windows::BindingError> for NokhwaError {
                 NokhwaError::OpenDeviceError(device.to_string(), error)
             }
             BindingError::ReadFrameError(error) => NokhwaError::ReadFrameError(error),
+            BindingError::DeviceLostError(device, error) => NokhwaError::DeviceDisconnected {
+                device: device.to_string(),
+                error,
+            },
+            BindingError::DeviceBusyError(device, error) => NokhwaError::DeviceBusy {
+                device: device.to_string(),
+                error,
+            },
+            BindingError::AccessDeniedError(device, error) => NokhwaError::PermissionDenied {
+                device: device.to_string(),
+                error,
+            },
+            BindingError::UnsupportedMediaTypeError(format, error) => {
+                NokhwaError::UnsupportedFormat { format, error }
+            }
             BindingError::NotImplementedError => {
                 NokhwaError::NotImplementedError("Docs-Only MediaFoundation".to_string())
             }
@@ -0,0 +1,511 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraBuilder, NokhwaError, Rotation};
+use flume::{Receiver, RecvTimeoutError, SendTimeoutError, Sender, TryRecvError, TrySendError};
+use image::{ImageBuffer, Rgb};
+use std::{
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+// how long the capture thread waits after a failed frame before trying again
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What happens when a frame is published to a subscriber whose queue is full.
+/// - `DropOldest` - The oldest queued frame is dropped to make room. The subscriber always gets the freshest frames, e.g. for a preview or an ML model.
+/// - `DropNewest` - The new frame is dropped. The subscriber gets an unbroken run of frames, then a gap.
+/// - `Block` - The publisher waits until the subscriber makes room. Nothing is lost, but a slow subscriber (e.g. an encoder) holds up everyone else.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum DropPolicy {
+    DropOldest,
+    DropNewest,
+    Block,
+}
+
+impl Default for DropPolicy {
+    fn default() -> Self {
+        DropPolicy::DropOldest
+    }
+}
+
+impl Display for DropPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A frame published on a [`FrameBus`]. The image is shared between all subscribers, so it is not copied for each of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BusFrame {
+    image: Arc<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    captured_at: SystemTime,
+    sequence: u64,
+    rotation: Rotation,
+}
+
+impl BusFrame {
+    // captured now
+    pub(crate) fn new(
+        image: Arc<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+        sequence: u64,
+        rotation: Rotation,
+    ) -> Self {
+        BusFrame {
+            image,
+            captured_at: SystemTime::now(),
+            sequence,
+            rotation,
+        }
+    }
+
+    /// The frame.
+    #[must_use]
+    pub fn image(&self) -> &ImageBuffer<Rgb<u8>, Vec<u8>> {
+        &self.image
+    }
+
+    /// The shared frame, to keep it around without copying.
+    #[must_use]
+    pub fn shared_image(&self) -> Arc<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        self.image.clone()
+    }
+
+    /// When the frame was published.
+    #[must_use]
+    pub fn captured_at(&self) -> SystemTime {
+        self.captured_at
+    }
+
+    /// The number of the frame on its bus, counting from 1. A subscriber that sees a gap has had frames dropped.
+    #[must_use]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// The rotation the subscriber still has to apply to the image. See [`Camera::pending_rotation()`].
+    #[must_use]
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+}
+
+struct Subscriber {
+    sender: Sender<BusFrame>,
+    // kept to drop the oldest frame when the queue is full
+    receiver: Receiver<BusFrame>,
+    policy: DropPolicy,
+    dropped: Arc<AtomicU64>,
+    alive: Arc<AtomicBool>,
+}
+
+impl Subscriber {
+    // the subscription was dropped, or nothing but our own receiver is left to read the queue
+    fn abandoned(&self) -> bool {
+        !self.alive.load(Ordering::SeqCst) || self.sender.receiver_count() <= 1
+    }
+
+    fn deliver(&self, frame: BusFrame, running: &AtomicBool) {
+        match self.policy {
+            DropPolicy::DropOldest => {
+                let mut frame = frame;
+                loop {
+                    match self.sender.try_send(frame) {
+                        Err(TrySendError::Full(rejected)) => {
+                            if self.receiver.try_recv().is_ok() {
+                                self.dropped.fetch_add(1, Ordering::SeqCst);
+                            }
+                            frame = rejected;
+                        }
+                        Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                    }
+                }
+            }
+            DropPolicy::DropNewest => {
+                if let Err(TrySendError::Full(_)) = self.sender.try_send(frame) {
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            DropPolicy::Block => {
+                // only the subscription or the bus going away ends the wait
+                let mut frame = frame;
+                while !self.abandoned() && running.load(Ordering::SeqCst) {
+                    match self.sender.send_timeout(frame, RETRY_INTERVAL) {
+                        Err(SendTimeoutError::Timeout(rejected)) => frame = rejected,
+                        Ok(()) | Err(SendTimeoutError::Disconnected(_)) => return,
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct BusShared {
+    subscribers: Mutex<Vec<Subscriber>>,
+    sequence: AtomicU64,
+    running: AtomicBool,
+    error: Mutex<Option<NokhwaError>>,
+}
+
+impl BusShared {
+    fn subscribers(&self) -> MutexGuard<'_, Vec<Subscriber>> {
+        match self.subscribers.lock() {
+            Ok(subscribers) => subscribers,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn error(&self) -> MutexGuard<'_, Option<NokhwaError>> {
+        match self.error.lock() {
+            Ok(error) => error,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn publish(&self, image: Arc<ImageBuffer<Rgb<u8>, Vec<u8>>>, rotation: Rotation) {
+        let frame = BusFrame::new(
+            image,
+            self.sequence.fetch_add(1, Ordering::SeqCst) + 1,
+            rotation,
+        );
+        let mut subscribers = self.subscribers();
+        subscribers.retain(|subscriber| !subscriber.abandoned());
+        for subscriber in subscribers.iter() {
+            subscriber.deliver(frame.clone(), &self.running);
+        }
+    }
+}
+
+/// One end of a [`FrameBus`]: a bounded queue of frames. Dropping it unsubscribes.
+pub struct FrameSubscriber {
+    receiver: Receiver<BusFrame>,
+    policy: DropPolicy,
+    dropped: Arc<AtomicU64>,
+    alive: Arc<AtomicBool>,
+}
+
+impl FrameSubscriber {
+    /// Waits for the next frame.
+    /// # Errors
+    /// If the bus is dropped, this will error.
+    pub fn recv(&self) -> Result<BusFrame, NokhwaError> {
+        match self.receiver.recv() {
+            Ok(frame) => Ok(frame),
+            Err(_) => Err(bus_dropped_error()),
+        }
+    }
+
+    /// Waits up to `timeout` for the next frame.
+    /// # Errors
+    /// If no frame arrives in time this will return [`NokhwaError::Timeout`]. If the bus is dropped, this will error.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<BusFrame, NokhwaError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(frame) => Ok(frame),
+            Err(RecvTimeoutError::Timeout) => Err(NokhwaError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(bus_dropped_error()),
+        }
+    }
+
+    /// Takes the next frame if one is queued.
+    /// # Errors
+    /// If the bus is dropped, this will error.
+    pub fn try_recv(&self) -> Result<Option<BusFrame>, NokhwaError> {
+        match self.receiver.try_recv() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(bus_dropped_error()),
+        }
+    }
+
+    /// The underlying channel, e.g. to `select` over several subscriptions or to `recv_async()`.
+    #[must_use]
+    pub fn receiver(&self) -> &Receiver<BusFrame> {
+        &self.receiver
+    }
+
+    /// The [`DropPolicy`] of this subscription.
+    #[must_use]
+    pub fn policy(&self) -> DropPolicy {
+        self.policy
+    }
+
+    /// How many frames were dropped because this subscriber's queue was full.
+    #[must_use]
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// How many frames are waiting in the queue.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.receiver.len()
+    }
+}
+
+impl Drop for FrameSubscriber {
+    fn drop(&mut self) {
+        self.alive.store(false, Ordering::SeqCst);
+    }
+}
+
+fn bus_dropped_error() -> NokhwaError {
+    NokhwaError::GeneralError("The frame bus has been dropped".to_string())
+}
+
+/// Broadcasts frames from one camera to any number of [`FrameSubscriber`]s, each with its own bounded queue and [`DropPolicy`].
+/// This way one [`Camera`] can feed e.g. a preview, an encoder and an ML model at once.
+/// ```.ignore
+/// let bus = FrameBus::from_builder(CameraBuilder::new().index(0))?;
+/// let preview = bus.subscribe(1, DropPolicy::DropOldest);
+/// let encoder = bus.subscribe(30, DropPolicy::Block);
+/// thread::spawn(move || while let Ok(frame) = encoder.recv() { /* ... */ });
+/// ```
+/// Use [`new()`](FrameBus::new) and [`publish()`](FrameBus::publish) to feed a bus from a capture loop of your own instead.
+/// # Quirks
+/// - A subscriber with [`DropPolicy::Block`] that stops reading stalls the whole bus, until it is dropped.
+/// - Subscribers only get frames published after they subscribed.
+pub struct FrameBus {
+    shared: Arc<BusShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FrameBus {
+    /// Creates a new [`FrameBus`] without a capture thread. Frames are published with [`publish()`](FrameBus::publish).
+    #[must_use]
+    pub fn new() -> Self {
+        FrameBus {
+            shared: Arc::new(BusShared {
+                subscribers: Mutex::new(vec![]),
+                sequence: AtomicU64::new(0),
+                running: AtomicBool::new(true),
+                error: Mutex::new(None),
+            }),
+            thread: None,
+        }
+    }
+
+    /// Creates a new [`FrameBus`] with a capture thread that opens the camera with `open_camera` (on that thread), opens its stream, and publishes every frame.
+    /// The camera is closed when the bus is stopped or dropped.
+    /// # Errors
+    /// If `open_camera` or opening the stream fails, this will error.
+    pub fn spawn<F>(open_camera: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        let mut bus = FrameBus::new();
+        let shared = bus.shared.clone();
+        let (opened_tx, opened_rx) = flume::bounded(1);
+
+        let thread = thread::spawn(move || {
+            let mut camera = match open_camera().and_then(|mut camera| {
+                if !camera.is_stream_open() {
+                    camera.open_stream()?;
+                }
+                Ok(camera)
+            }) {
+                Ok(camera) => {
+                    let _sent = opened_tx.send(Ok(()));
+                    camera
+                }
+                Err(why) => {
+                    let _sent = opened_tx.send(Err(why));
+                    return;
+                }
+            };
+
+            while shared.running.load(Ordering::SeqCst) {
+                match camera.frame() {
+                    Ok(frame) => {
+                        shared.publish(Arc::new(frame), camera.pending_rotation());
+                        *shared.error() = None;
+                    }
+                    Err(why) => {
+                        *shared.error() = Some(why);
+                        thread::sleep(RETRY_INTERVAL);
+                    }
+                }
+            }
+            let _stopped = camera.stop_stream();
+        });
+
+        match opened_rx.recv() {
+            Ok(Ok(())) => {
+                bus.thread = Some(thread);
+                Ok(bus)
+            }
+            Ok(Err(why)) => Err(why),
+            Err(_) => Err(NokhwaError::GeneralError(
+                "The capture thread panicked while opening the camera".to_string(),
+            )),
+        }
+    }
+
+    /// Creates a new [`FrameBus`] with a capture thread for the camera `builder` opens. See [`spawn()`](FrameBus::spawn).
+    /// # Errors
+    /// If the camera or its stream fails to open, this will error.
+    pub fn from_builder(builder: CameraBuilder) -> Result<Self, NokhwaError> {
+        FrameBus::spawn(move || builder.open())
+    }
+
+    /// Adds a subscriber, with a queue of up to `capacity` frames (at least 1) and `policy` for when it is full.
+    #[must_use]
+    pub fn subscribe(&self, capacity: usize, policy: DropPolicy) -> FrameSubscriber {
+        let (sender, receiver) = flume::bounded(capacity.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let alive = Arc::new(AtomicBool::new(true));
+        self.shared.subscribers().push(Subscriber {
+            sender,
+            receiver: receiver.clone(),
+            policy,
+            dropped: dropped.clone(),
+            alive: alive.clone(),
+        });
+        FrameSubscriber {
+            receiver,
+            policy,
+            dropped,
+            alive,
+        }
+    }
+
+    /// The number of live subscribers.
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self.shared.subscribers();
+        subscribers.retain(|subscriber| !subscriber.abandoned());
+        subscribers.len()
+    }
+
+    /// Publishes `frame` to every subscriber, upright (its [`rotation()`](BusFrame::rotation) is [`Rotation::None`]). With a capture thread running, this mixes your frames in with the camera's.
+    pub fn publish(&self, frame: ImageBuffer<Rgb<u8>, Vec<u8>>) {
+        self.shared.publish(Arc::new(frame), Rotation::None);
+    }
+
+    /// The number of frames published so far.
+    #[must_use]
+    pub fn frames_published(&self) -> u64 {
+        self.shared.sequence.load(Ordering::SeqCst)
+    }
+
+    /// The error of the last failed capture of the capture thread, if it has not succeeded since.
+    #[must_use]
+    pub fn last_error(&self) -> Option<NokhwaError> {
+        self.shared.error().clone()
+    }
+
+    /// Stops the capture thread, if there is one, and closes the camera. This is also done on drop.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _joined = thread.join();
+        }
+    }
+}
+
+impl Default for FrameBus {
+    fn default() -> Self {
+        FrameBus::new()
+    }
+}
+
+impl Drop for FrameBus {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Starts a capture thread with the camera `open_camera` opens (on that thread), opens its stream, and returns a channel of its frames.
+/// Up to `depth` frames (at least 1) are queued, and `drop_policy` says what happens when the queue is full.
+///
+/// Unlike a [`FrameBus`] there is nothing else to keep around: the capture thread stops and closes the camera once every clone of the [`Receiver`] is dropped.
+/// This way an actor can `select` over camera frames and its other channels (or `recv_async()` them).
+/// ```.ignore
+/// let frames = frame_channel(|| CameraBuilder::new().index(0).open(), 2, DropPolicy::DropOldest)?;
+/// loop {
+///     let done = Selector::new()
+///         .recv(&frames, |frame| frame.map(|frame| show(&frame)).is_err())
+///         .recv(&events, |event| event.map(|event| handle(event)).is_err())
+///         .wait();
+///     if done {
+///         break;
+///     }
+/// }
+/// ```
+/// See [`CameraBuilder::frame_channel()`] to open the camera from a [`CameraBuilder`].
+/// # Quirks
+/// - Capture errors are not sent, the capture thread keeps trying until the camera works again.
+/// - The channel disconnects if the capture thread panics.
+/// - With [`DropPolicy::Block`], the camera is not read while the queue is full, so its own buffers fill up and it drops frames instead.
+/// # Errors
+/// If `open_camera` or opening the stream fails, this will error.
+pub fn frame_channel<F>(
+    open_camera: F,
+    depth: usize,
+    drop_policy: DropPolicy,
+) -> Result<Receiver<BusFrame>, NokhwaError>
+where
+    F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+{
+    let (sender, receiver) = flume::bounded(depth.max(1));
+    let subscriber = Subscriber {
+        sender,
+        receiver: receiver.clone(),
+        policy: drop_policy,
+        dropped: Arc::new(AtomicU64::new(0)),
+        alive: Arc::new(AtomicBool::new(true)),
+    };
+    let (opened_tx, opened_rx) = flume::bounded(1);
+
+    // detached, it ends on its own once the receivers are gone
+    let _thread = thread::spawn(move || {
+        let mut camera = match open_camera().and_then(|mut camera| {
+            if !camera.is_stream_open() {
+                camera.open_stream()?;
+            }
+            Ok(camera)
+        }) {
+            Ok(camera) => {
+                let _sent = opened_tx.send(Ok(()));
+                camera
+            }
+            Err(why) => {
+                let _sent = opened_tx.send(Err(why));
+                return;
+            }
+        };
+
+        let running = AtomicBool::new(true);
+        let mut sequence = 0;
+        while !subscriber.abandoned() {
+            match camera.frame() {
+                Ok(frame) => {
+                    sequence += 1;
+                    let frame = BusFrame::new(Arc::new(frame), sequence, camera.pending_rotation());
+                    subscriber.deliver(frame, &running);
+                }
+                Err(_) => thread::sleep(RETRY_INTERVAL),
+            }
+        }
+        let _stopped = camera.stop_stream();
+    });
+
+    match opened_rx.recv() {
+        Ok(Ok(())) => Ok(receiver),
+        Ok(Err(why)) => Err(why),
+        Err(_) => Err(NokhwaError::GeneralError(
+            "The capture thread panicked while opening the camera".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,417 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraBuilder, NokhwaError, SinkPayload};
+use image::{codecs::jpeg::JpegEncoder, ColorType};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// The JPEG quality frames are streamed with.
+pub const HTTP_STREAM_JPEG_QUALITY: u8 = 80;
+
+const BOUNDARY: &str = "nokhwaframe";
+// how often the accept loop checks for shutdown, and the capture loop for clients
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone)]
+struct EncodedFrame {
+    data: Arc<Vec<u8>>,
+    content_type: &'static str,
+}
+
+// the latest encoded frame, with a sequence number so clients can wait for a new one
+#[derive(Default)]
+struct LatestFrame {
+    sequence: u64,
+    frame: Option<EncodedFrame>,
+    // the last capture error, until the next frame
+    error: Option<String>,
+    // the camera is gone for good, clients are let go
+    failed: bool,
+}
+
+struct Shared {
+    latest: Mutex<LatestFrame>,
+    new_frame: Condvar,
+    running: AtomicBool,
+    clients: AtomicUsize,
+}
+
+impl Shared {
+    // waits for a frame newer than `sequence`, `None` once the server stops or the camera fails
+    fn wait_for_frame(&self, sequence: u64) -> Option<(u64, EncodedFrame)> {
+        let mut latest = match self.latest.lock() {
+            Ok(latest) => latest,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        loop {
+            if !self.running.load(Ordering::SeqCst) || latest.failed {
+                return None;
+            }
+            if latest.sequence > sequence {
+                if let Some(frame) = &latest.frame {
+                    return Some((latest.sequence, frame.clone()));
+                }
+            }
+            latest = match self.new_frame.wait_timeout(latest, POLL_INTERVAL) {
+                Ok((latest, _)) => latest,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+
+    fn publish(&self, frame: EncodedFrame) {
+        let mut latest = match self.latest.lock() {
+            Ok(latest) => latest,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        latest.sequence += 1;
+        latest.frame = Some(frame);
+        latest.error = None;
+        self.new_frame.notify_all();
+    }
+
+    fn report(&self, error: &NokhwaError, fatal: bool) {
+        let mut latest = match self.latest.lock() {
+            Ok(latest) => latest,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        latest.error = Some(error.to_string());
+        latest.failed |= fatal;
+        self.new_frame.notify_all();
+    }
+}
+
+// errors that no amount of retrying gets a frame past
+fn is_fatal(error: &NokhwaError) -> bool {
+    matches!(
+        error,
+        NokhwaError::DeviceDisconnected { .. } | NokhwaError::PermissionDenied { .. }
+    )
+}
+
+fn encode_payload(payload: SinkPayload) -> Result<EncodedFrame, NokhwaError> {
+    match payload {
+        SinkPayload::Image(image) => {
+            let mut jpeg = vec![];
+            if let Err(why) = JpegEncoder::new_with_quality(&mut jpeg, HTTP_STREAM_JPEG_QUALITY)
+                .encode(
+                    image.as_raw(),
+                    image.width(),
+                    image.height(),
+                    ColorType::Rgb8,
+                )
+            {
+                return Err(NokhwaError::GeneralError(format!(
+                    "Could not encode frame to JPEG: {}",
+                    why
+                )));
+            }
+            Ok(EncodedFrame {
+                data: Arc::new(jpeg),
+                content_type: "image/jpeg",
+            })
+        }
+        // already transformed by the `SinkTransform`, sent as is
+        SinkPayload::Opaque { data, .. } => Ok(EncodedFrame {
+            data: Arc::new(data),
+            content_type: "application/octet-stream",
+        }),
+    }
+}
+
+fn capture_loop(mut camera: Camera, shared: &Shared) {
+    while shared.running.load(Ordering::SeqCst) {
+        // no one is watching, don't capture (and encode) frames for nothing
+        if shared.clients.load(Ordering::SeqCst) == 0 {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        match camera.frame_for_sink().and_then(encode_payload) {
+            Ok(frame) => shared.publish(frame),
+            // a late frame is not worth reporting, try again
+            Err(NokhwaError::Timeout(_)) => {}
+            Err(why) => {
+                let fatal = is_fatal(&why);
+                shared.report(&why, fatal);
+                if fatal {
+                    break;
+                }
+                // e.g. a corrupt frame, keep the clients and try again
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+    let _stop = camera.stop_stream();
+}
+
+fn write_stream(stream: &mut TcpStream, shared: &Shared) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 200 OK\r\nCache-Control: no-cache\r\nConnection: close\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\n\r\n",
+        BOUNDARY
+    )?;
+    let mut sequence = 0;
+    while let Some((new_sequence, frame)) = shared.wait_for_frame(sequence) {
+        sequence = new_sequence;
+        write!(
+            stream,
+            "--{}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            BOUNDARY,
+            frame.content_type,
+            frame.data.len()
+        )?;
+        stream.write_all(&frame.data)?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+    }
+    Ok(())
+}
+
+fn write_snapshot(stream: &mut TcpStream, shared: &Shared) -> std::io::Result<()> {
+    let sequence = match shared.latest.lock() {
+        Ok(latest) => latest.sequence,
+        Err(poisoned) => poisoned.into_inner().sequence,
+    };
+    match shared.wait_for_frame(sequence) {
+        Some((_, frame)) => {
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nCache-Control: no-cache\r\nConnection: close\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+                frame.content_type,
+                frame.data.len()
+            )?;
+            stream.write_all(&frame.data)
+        }
+        None => write_status(stream, "503 Service Unavailable"),
+    }
+}
+
+fn write_status(stream: &mut TcpStream, status: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        status
+    )
+}
+
+fn handle_client(mut stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    {
+        let mut reader = BufReader::new(&mut stream);
+        reader.read_line(&mut request_line)?;
+        // skip the headers, nothing in them changes the response
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    // ignore query strings, so `?t=123` cache busters work
+    let path = parts
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+    if method != "GET" {
+        return write_status(&mut stream, "405 Method Not Allowed");
+    }
+    match path {
+        "/" | "/stream" | "/stream.mjpg" => write_stream(&mut stream, shared),
+        "/snapshot.jpg" => write_snapshot(&mut stream, shared),
+        _ => write_status(&mut stream, "404 Not Found"),
+    }
+}
+
+fn accept_loop(listener: &TcpListener, shared: &Arc<Shared>) {
+    while shared.running.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // the listener is non-blocking, the clients should not be
+                if stream.set_nonblocking(false).is_err() {
+                    continue;
+                }
+                let shared = shared.clone();
+                shared.clients.fetch_add(1, Ordering::SeqCst);
+                thread::spawn(move || {
+                    // a client going away mid-frame is not an error worth reporting
+                    let _result = handle_client(stream, &shared);
+                    shared.clients.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+/// Serves a camera over HTTP as a multipart MJPEG stream, turning it into an IP camera.
+///
+/// - `/`, `/stream` or `/stream.mjpg` - The MJPEG stream (`multipart/x-mixed-replace`), which browsers, VLC, `ffmpeg` and [`NetworkCamera`](crate::NetworkCamera) can play.
+/// - `/snapshot.jpg` - The next frame, as a single JPEG.
+///
+/// Any number of clients can connect at once, they all get the same frames. Frames are only captured while at least one client is connected.
+/// Frames go through [`Camera::frame_for_sink()`], so a [`SinkTransform`](crate::SinkTransform) set on the camera applies to everything that is served.
+/// [`SinkPayload::Opaque`] frames are sent as is, as `application/octet-stream`.
+/// ```.ignore
+/// let server = MjpegHttpServer::from_builder(CameraBuilder::new().index(0), "0.0.0.0:8080")?;
+/// // open http://<this machine>:8080/stream in a browser
+/// ```
+/// # Quirks
+/// - There is no authentication or TLS. Put it behind a reverse proxy if it is reachable from an untrusted network.
+/// - The camera is opened on its own capture thread, which is why this takes something that opens a [`Camera`] instead of a [`Camera`].
+/// - Frames that fail to capture or encode are skipped, and the server carries on. If the camera is gone for good (unplugged, or access was revoked), the server stops serving frames
+///   (clients are disconnected, `/snapshot.jpg` gives a 503). See [`error()`](MjpegHttpServer::error).
+pub struct MjpegHttpServer {
+    address: SocketAddr,
+    shared: Arc<Shared>,
+    accept_thread: Option<JoinHandle<()>>,
+    capture_thread: Option<JoinHandle<()>>,
+}
+
+impl MjpegHttpServer {
+    /// Starts serving on `address` (e.g. `"0.0.0.0:8080"`, use port 0 to pick a free port) the camera opened by `open_camera`, which runs on the capture thread.
+    /// The stream is opened after `open_camera` returns, if it is not open already.
+    /// # Errors
+    /// If `address` cannot be bound, or `open_camera` or opening the stream fails, this will error.
+    pub fn new<A, F>(address: A, open_camera: F) -> Result<Self, NokhwaError>
+    where
+        A: ToSocketAddrs,
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        let listener = match TcpListener::bind(address) {
+            Ok(listener) => listener,
+            Err(why) => {
+                return Err(NokhwaError::GeneralError(format!(
+                    "Could not bind HTTP stream server: {}",
+                    why
+                )))
+            }
+        };
+        let address = match listener
+            .set_nonblocking(true)
+            .and_then(|()| listener.local_addr())
+        {
+            Ok(address) => address,
+            Err(why) => {
+                return Err(NokhwaError::GeneralError(format!(
+                    "Could not set up HTTP stream server: {}",
+                    why
+                )))
+            }
+        };
+
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(LatestFrame::default()),
+            new_frame: Condvar::new(),
+            running: AtomicBool::new(true),
+            clients: AtomicUsize::new(0),
+        });
+
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let capture_shared = shared.clone();
+        let capture_thread = thread::spawn(move || {
+            let camera = open_camera().and_then(|mut camera| {
+                if !camera.is_stream_open() {
+                    camera.open_stream()?;
+                }
+                Ok(camera)
+            });
+            match camera {
+                Ok(camera) => {
+                    let _sent = opened_tx.send(Ok(()));
+                    capture_loop(camera, &capture_shared);
+                }
+                Err(why) => {
+                    let _sent = opened_tx.send(Err(why));
+                }
+            }
+        });
+
+        match opened_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(why)) => return Err(why),
+            Err(_) => {
+                return Err(NokhwaError::GeneralError(
+                    "HTTP stream capture thread panicked while opening the camera".to_string(),
+                ))
+            }
+        }
+
+        let accept_shared = shared.clone();
+        let accept_thread = thread::spawn(move || accept_loop(&listener, &accept_shared));
+
+        Ok(MjpegHttpServer {
+            address,
+            shared,
+            accept_thread: Some(accept_thread),
+            capture_thread: Some(capture_thread),
+        })
+    }
+
+    /// Starts serving on `address` the camera `builder` opens. See [`new()`](MjpegHttpServer::new).
+    /// # Errors
+    /// If `address` cannot be bound, or the camera or its stream fails to open, this will error.
+    pub fn from_builder<A: ToSocketAddrs>(
+        builder: CameraBuilder,
+        address: A,
+    ) -> Result<Self, NokhwaError> {
+        MjpegHttpServer::new(address, move || builder.open())
+    }
+
+    /// The address the server is listening on. Useful when binding to port 0.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// How many clients are connected right now.
+    #[must_use]
+    pub fn client_count(&self) -> usize {
+        self.shared.clients.load(Ordering::SeqCst)
+    }
+
+    /// The last error capturing or encoding a frame, until the next frame succeeds. If the camera failed for good, this is the error that stopped it.
+    #[must_use]
+    pub fn error(&self) -> Option<String> {
+        match self.shared.latest.lock() {
+            Ok(latest) => latest.error.clone(),
+            Err(poisoned) => poisoned.into_inner().error.clone(),
+        }
+    }
+
+    /// Stops the server, disconnects all clients and closes the camera. This is also done on drop.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        self.shared.new_frame.notify_all();
+        if let Some(thread) = self.accept_thread.take() {
+            let _joined = thread.join();
+        }
+        if let Some(thread) = self.capture_thread.take() {
+            let _joined = thread.join();
+        }
+    }
+}
+
+impl Drop for MjpegHttpServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
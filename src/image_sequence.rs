@@ -0,0 +1,219 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, NokhwaError, SinkPayload};
+use image::{
+    buffer::ConvertBuffer,
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageBuffer, ImageFormat, Rgb, RgbaImage,
+};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+// 1 is the best quality and very slow, 30 is the fastest. 10 is what `gif` considers a good trade off.
+const GIF_SPEED: i32 = 10;
+
+fn sequence_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("Image sequence: {}", error))
+}
+
+enum Target {
+    Gif(GifEncoder<BufWriter<File>>),
+    Files {
+        directory: PathBuf,
+        format: ImageFormat,
+        extension: &'static str,
+    },
+}
+
+/// Records frames into an animated GIF, or a numbered sequence of PNG or JPEG files, at a fixed capture interval.
+/// This is a lot lighter than the `Recorder` of the `output-recorder` feature (it only needs `image`), which makes it a good fit for short clips and timelapses.
+///
+/// Frames are only written once every `capture_interval`, frames in between are dropped. Use an interval of 0 to write every frame.
+/// ```.ignore
+/// // one frame every 10 seconds, played back at 10 FPS
+/// let mut timelapse = ImageSequenceRecorder::gif("timelapse.gif", Duration::from_secs(10))?
+///     .with_playback_interval(Duration::from_millis(100));
+/// loop {
+///     timelapse.record_frame(&mut camera)?;
+/// }
+/// ```
+/// # Quirks
+/// - GIFs have at most 256 colors per frame and a delay resolution of 10 milliseconds. Long GIFs at high resolutions get large quickly.
+/// - The end of a GIF is written when the recorder is dropped or [`finish()`](ImageSequenceRecorder::finish)ed. Errors at that point cannot be reported.
+pub struct ImageSequenceRecorder {
+    target: Target,
+    capture_interval: Duration,
+    playback_interval: Duration,
+    last_written: Option<Instant>,
+    frames_written: u64,
+}
+
+impl ImageSequenceRecorder {
+    /// Creates a new [`ImageSequenceRecorder`] that writes a looping animated GIF to `path`, with a frame every `capture_interval`.
+    /// By default, it plays back at the speed it was recorded at.
+    /// # Errors
+    /// If the file cannot be created, this will error.
+    pub fn gif<P: AsRef<Path>>(path: P, capture_interval: Duration) -> Result<Self, NokhwaError> {
+        let file = match File::create(path.as_ref()) {
+            Ok(file) => file,
+            Err(why) => {
+                return Err(sequence_error(&format!(
+                    "Could not create {}: {}",
+                    path.as_ref().display(),
+                    why
+                )))
+            }
+        };
+        let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), GIF_SPEED);
+        if let Err(why) = encoder.set_repeat(Repeat::Infinite) {
+            return Err(sequence_error(&why.to_string()));
+        }
+        Ok(ImageSequenceRecorder::with_target(
+            Target::Gif(encoder),
+            capture_interval,
+        ))
+    }
+
+    /// Creates a new [`ImageSequenceRecorder`] that writes a frame every `capture_interval` into `directory` (which is created if it does not exist),
+    /// as `000000.png`, `000001.png`, and so on. `format` must be [`ImageFormat::Png`] or [`ImageFormat::Jpeg`].
+    /// # Errors
+    /// If `format` is not supported or the directory cannot be created, this will error.
+    pub fn sequence<P: AsRef<Path>>(
+        directory: P,
+        format: ImageFormat,
+        capture_interval: Duration,
+    ) -> Result<Self, NokhwaError> {
+        let extension = match format {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            _ => {
+                return Err(sequence_error(&format!(
+                    "{:?} is not supported, use PNG or JPEG",
+                    format
+                )))
+            }
+        };
+        if let Err(why) = fs::create_dir_all(directory.as_ref()) {
+            return Err(sequence_error(&format!(
+                "Could not create {}: {}",
+                directory.as_ref().display(),
+                why
+            )));
+        }
+        Ok(ImageSequenceRecorder::with_target(
+            Target::Files {
+                directory: directory.as_ref().to_path_buf(),
+                format,
+                extension,
+            },
+            capture_interval,
+        ))
+    }
+
+    fn with_target(target: Target, capture_interval: Duration) -> Self {
+        ImageSequenceRecorder {
+            target,
+            capture_interval,
+            playback_interval: capture_interval,
+            last_written: None,
+            frames_written: 0,
+        }
+    }
+
+    /// Sets how long each frame of a GIF is shown, e.g. shorter than the capture interval for a timelapse. This does nothing for image sequences.
+    #[must_use]
+    pub fn with_playback_interval(mut self, playback_interval: Duration) -> Self {
+        self.playback_interval = playback_interval;
+        self
+    }
+
+    /// How often frames are written.
+    #[must_use]
+    pub fn capture_interval(&self) -> Duration {
+        self.capture_interval
+    }
+
+    /// How long each frame of a GIF is shown.
+    #[must_use]
+    pub fn playback_interval(&self) -> Duration {
+        self.playback_interval
+    }
+
+    /// How many frames have been written so far.
+    #[must_use]
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Whether the next frame would be written, i.e. the capture interval has passed since the last written frame.
+    #[must_use]
+    pub fn is_due(&self) -> bool {
+        match self.last_written {
+            Some(last) => last.elapsed() >= self.capture_interval,
+            None => true,
+        }
+    }
+
+    /// Writes `frame` if the capture interval has passed since the last written frame. Returns whether it was written.
+    /// # Errors
+    /// If the frame cannot be encoded or written, this will error.
+    pub fn write_frame(
+        &mut self,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<bool, NokhwaError> {
+        if !self.is_due() {
+            return Ok(false);
+        }
+        match &mut self.target {
+            Target::Gif(encoder) => {
+                let rgba: RgbaImage = frame.convert();
+                let delay = Delay::from_saturating_duration(self.playback_interval);
+                if let Err(why) = encoder.encode_frame(Frame::from_parts(rgba, 0, 0, delay)) {
+                    return Err(sequence_error(&why.to_string()));
+                }
+            }
+            Target::Files {
+                directory,
+                format,
+                extension,
+            } => {
+                let path = directory.join(format!("{:06}.{}", self.frames_written, extension));
+                if let Err(why) = frame.save_with_format(&path, *format) {
+                    return Err(sequence_error(&format!(
+                        "Could not write {}: {}",
+                        path.display(),
+                        why
+                    )));
+                }
+            }
+        }
+        self.last_written = Some(Instant::now());
+        self.frames_written += 1;
+        Ok(true)
+    }
+
+    /// Captures a frame from `camera` with [`Camera::frame_for_sink()`] and writes it if it is due. Returns whether it was written.
+    ///
+    /// The frame is captured even when it is not due, so the camera does not fall behind.
+    /// # Errors
+    /// If capturing fails, the frame is [`SinkPayload::Opaque`], or [`write_frame()`](ImageSequenceRecorder::write_frame) fails, this will error.
+    pub fn record_frame(&mut self, camera: &mut Camera) -> Result<bool, NokhwaError> {
+        match camera.frame_for_sink()? {
+            SinkPayload::Image(frame) => self.write_frame(&frame),
+            SinkPayload::Opaque { .. } => Err(sequence_error(
+                "Opaque frames (e.g. encrypted by a SinkTransform) cannot be encoded",
+            )),
+        }
+    }
+
+    /// Finishes the GIF or sequence. This is the same as dropping the recorder.
+    pub fn finish(self) {}
+}
@@ -0,0 +1,242 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraFormat, FrameFormat, KnownCameraControls, NokhwaError};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A change made to a [`Camera`] that is recorded into a [`SessionJournal`].
+/// - `Format` - A format negotiation, with the full [`CameraFormat`] that was requested.
+/// - `Control` - A control was set to a value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum JournalEvent {
+    Format(CameraFormat),
+    Control(KnownCameraControls, i32),
+}
+
+/// One line of a [`SessionJournal`]: what was changed, when, and whether the camera accepted it.
+///
+/// As text, an entry is a tab separated line of the milliseconds since the journal started, `ok` or `failed`, and the event
+/// (shown with spaces instead of tabs):
+/// ```.ignore
+/// 1520    ok      format  1280    720     30      MJPEG
+/// 2034    failed  control Exposure        5000
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    elapsed: Duration,
+    succeeded: bool,
+    event: JournalEvent,
+}
+
+impl JournalEntry {
+    /// Create a new [`JournalEntry`].
+    #[must_use]
+    pub fn new(elapsed: Duration, succeeded: bool, event: JournalEvent) -> Self {
+        JournalEntry {
+            elapsed,
+            succeeded,
+            event,
+        }
+    }
+
+    /// How long after the start of the journal this happened.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether the camera accepted the change.
+    #[must_use]
+    pub fn succeeded(&self) -> bool {
+        self.succeeded
+    }
+
+    /// The change that was made.
+    #[must_use]
+    pub fn event(&self) -> JournalEvent {
+        self.event
+    }
+}
+
+impl Display for JournalEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let outcome = if self.succeeded { "ok" } else { "failed" };
+        match self.event {
+            JournalEvent::Format(format) => write!(
+                f,
+                "{}\t{}\tformat\t{}\t{}\t{}\t{}",
+                self.elapsed.as_millis(),
+                outcome,
+                format.width(),
+                format.height(),
+                format.frame_rate(),
+                format.format()
+            ),
+            JournalEvent::Control(control, value) => write!(
+                f,
+                "{}\t{}\tcontrol\t{}\t{}",
+                self.elapsed.as_millis(),
+                outcome,
+                control,
+                value
+            ),
+        }
+    }
+}
+
+fn journal_error(line: &str, error: &str) -> NokhwaError {
+    NokhwaError::StructureError {
+        structure: "JournalEntry".to_string(),
+        error: format!("{} (line: {:?})", error, line),
+    }
+}
+
+fn parse_field<T: FromStr>(line: &str, field: Option<&str>, name: &str) -> Result<T, NokhwaError> {
+    match field.map(str::parse) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) => Err(journal_error(line, &format!("Invalid {}", name))),
+        None => Err(journal_error(line, &format!("Missing {}", name))),
+    }
+}
+
+impl FromStr for JournalEntry {
+    type Err = NokhwaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.trim_end_matches(&['\r', '\n'][..]).split('\t');
+        let elapsed = Duration::from_millis(parse_field(s, fields.next(), "timestamp")?);
+        let succeeded = match fields.next() {
+            Some("ok") => true,
+            Some("failed") => false,
+            _ => return Err(journal_error(s, "Invalid outcome")),
+        };
+        let event = match fields.next() {
+            Some("format") => {
+                let width = parse_field(s, fields.next(), "width")?;
+                let height = parse_field(s, fields.next(), "height")?;
+                let fps = parse_field(s, fields.next(), "frame rate")?;
+                let fourcc: FrameFormat = parse_field(s, fields.next(), "frame format")?;
+                JournalEvent::Format(CameraFormat::new_from(width, height, fourcc, fps))
+            }
+            Some("control") => {
+                let control = parse_field(s, fields.next(), "control")?;
+                let value = parse_field(s, fields.next(), "value")?;
+                JournalEvent::Control(control, value)
+            }
+            _ => return Err(journal_error(s, "Invalid event")),
+        };
+        if fields.next().is_some() {
+            return Err(journal_error(s, "Too many fields"));
+        }
+        Ok(JournalEntry::new(elapsed, succeeded, event))
+    }
+}
+
+/// A timestamped record of every control change and format negotiation on a [`Camera`], for reproducing bugs that depend on the order of changes.
+///
+/// Start one with [`Camera::start_journal()`], then save it with [`to_text()`](SessionJournal::to_text) and [`replay()`](SessionJournal::replay) it later, on the same or another camera.
+#[derive(Clone, Debug)]
+pub struct SessionJournal {
+    started: Instant,
+    entries: Vec<JournalEntry>,
+}
+
+impl SessionJournal {
+    /// Create a new, empty [`SessionJournal`] that starts now.
+    #[must_use]
+    pub fn new() -> Self {
+        SessionJournal {
+            started: Instant::now(),
+            entries: vec![],
+        }
+    }
+
+    /// Records `event` at the current time.
+    pub fn record(&mut self, event: JournalEvent, succeeded: bool) {
+        self.entries
+            .push(JournalEntry::new(self.started.elapsed(), succeeded, event));
+    }
+
+    /// All entries, oldest first.
+    #[must_use]
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes the journal as text, one [`JournalEntry`] per line.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&entry.to_string());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reads a journal written by [`to_text()`](SessionJournal::to_text). Empty lines and lines starting with `#` are skipped.
+    /// # Errors
+    /// If any line is not a valid [`JournalEntry`], this will error.
+    pub fn from_text(text: &str) -> Result<Self, NokhwaError> {
+        let mut entries = vec![];
+        for line in text.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            entries.push(line.parse()?);
+        }
+        Ok(SessionJournal {
+            started: Instant::now(),
+            entries,
+        })
+    }
+
+    /// Re-applies every entry to `camera`, in order. If `timed` is true, this sleeps between entries to reproduce the original timing.
+    ///
+    /// Entries that failed originally are applied too (the failure may be part of the bug), but their errors are ignored.
+    /// # Errors
+    /// If an entry that originally succeeded fails, this stops and returns its error.
+    pub fn replay(&self, camera: &mut Camera, timed: bool) -> Result<(), NokhwaError> {
+        let replay_started = Instant::now();
+        for entry in &self.entries {
+            if timed {
+                if let Some(wait) = entry.elapsed.checked_sub(replay_started.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+            let result =
+                match entry.event {
+                    JournalEvent::Format(format) => camera.set_camera_format(format),
+                    JournalEvent::Control(control, value) => camera
+                        .camera_control(control)
+                        .and_then(|mut camera_control| {
+                            camera_control.set_value(value)?;
+                            camera.set_camera_control(camera_control)
+                        }),
+                };
+            if entry.succeeded {
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SessionJournal {
+    fn default() -> Self {
+        SessionJournal::new()
+    }
+}
@@ -4,49 +4,47 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-/// Note: for WASM bindings you need to bind them yourself.
-use crate::{CameraInfo, NokhwaError, Resolution};
-use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbImage, Rgba};
-use js_sys::{Array, Function, JsString, Object, Promise};
+use crate::{
+    encode_snapshot, CameraInfo, CameraPermission, CameraPosition, FrameFormat, NokhwaError,
+    Resolution,
+};
+use image::{buffer::ConvertBuffer, ImageBuffer, ImageFormat, Rgb, RgbImage, Rgba};
+use js_sys::{
+    Array, Atomics, Date, Function, Int32Array, Object, Promise, Reflect, SharedArrayBuffer,
+    Uint8Array,
+};
 use std::{
     borrow::Cow,
+    cell::RefCell,
     convert::TryFrom,
     fmt::{Debug, Display, Formatter},
     ops::Deref,
+    rc::Rc,
+    time::{Duration, UNIX_EPOCH},
 };
-use wasm_bindgen::{JsCast, JsValue};
-use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "wasm-bindgen-exports")]
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::{
-    CanvasRenderingContext2d, Document, Element, HtmlCanvasElement, HtmlVideoElement,
-    MediaDeviceInfo, MediaDeviceKind, MediaDevices, MediaStream, MediaStreamConstraints, Navigator,
-    Node, Window,
+    Blob, BlobEvent, BlobPropertyBag, CanvasRenderingContext2d, DisplayMediaStreamConstraints,
+    Document, Element, HtmlCanvasElement, HtmlVideoElement, ImageBitmap, ImageData,
+    MediaDeviceInfo, MediaDeviceKind, MediaDevices, MediaRecorder, MediaRecorderOptions,
+    MediaStream, MediaStreamConstraints, MediaStreamTrack, MediaStreamTrackState,
+    MediaTrackConstraints, MediaTrackSupportedConstraints, Navigator, Node, OffscreenCanvas,
+    PermissionState, PermissionStatus, RecordingState, Window,
 };
 
-#[cfg(feature = "output-wgpu")]
-use wgpu::{Device, Queue, Texture};
-use wgpu::{
-    Extent3d, ImageCopyTexture, ImageDataLayout, TextureDescriptor, TextureDimension,
-    TextureFormat, TextureUsage,
-};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::wgpu_api::{Device, Queue, Texture};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::{texture::frame_to_texture, TextureOptions};
 
 // why no code completion
 // big sadger
 
 // intellij 2021.2 review: i like structure window, 4 pengs / 5 pengs
 
-const GET_CONSTRAINT_LIST_JS_CODE_STR: &str = r#"
-let constraints_list = navigator.mediaDevices.getSupportedConstraints();
-let constraint_string_arr = [];
-
-for (let constraint in supportedConstraints) {
-    if (constraints_list.hasOwnProperty(constraint)) {
-        constraint_string_arr.push(constraint.to_string());
-    }
-}
-
-return constraint_string_arr;
-"#;
-
 fn window() -> Result<Window, NokhwaError> {
     match web_sys::window() {
         Some(win) => Ok(win),
@@ -140,6 +138,14 @@ fn create_element(doc: &Document, element: &str) -> Result<Element, NokhwaError>
     }
 }
 
+// the size of the video a `<video>` element shows, once it has loaded any
+fn video_resolution(video_element: &HtmlVideoElement) -> Option<Resolution> {
+    match (video_element.video_width(), video_element.video_height()) {
+        (0, _) | (_, 0) => None,
+        (width, height) => Some(Resolution::new(width, height)),
+    }
+}
+
 fn set_autoplay_inline(element: &Element) -> Result<(), NokhwaError> {
     if let Err(why) = element.set_attribute("autoplay", "autoplay") {
         return Err(NokhwaError::SetPropertyError {
@@ -149,6 +155,15 @@ fn set_autoplay_inline(element: &Element) -> Result<(), NokhwaError> {
         });
     }
 
+    // with audio, the microphone would play back through the speakers. The `muted` attribute only counts for parsed elements, so this sets the property
+    if let Err(why) = Reflect::set(element, &JsValue::from_str("muted"), &JsValue::TRUE) {
+        return Err(NokhwaError::SetPropertyError {
+            property: "Video-muted".to_string(),
+            value: "true".to_string(),
+            error: format!("{:?}", why),
+        });
+    }
+
     if let Err(why) = element.set_attribute("playsinline", "playsinline") {
         return Err(NokhwaError::SetPropertyError {
             property: "Video-playsinline".to_string(),
@@ -160,10 +175,47 @@ fn set_autoplay_inline(element: &Element) -> Result<(), NokhwaError> {
     Ok(())
 }
 
+async fn apply_track_constraints(
+    track: MediaStreamTrack,
+    constraints: MediaTrackConstraints,
+) -> Result<(), NokhwaError> {
+    match JsFuture::from(track.apply_constraints_with_constraints(&constraints)).await {
+        Ok(_) => Ok(()),
+        Err(why) => Err(user_media_error(&why, "MediaStreamTrackApplyConstraints")),
+    }
+}
+
+// `getUserMedia()` rejects with a `DOMException`, whose name says why
+fn user_media_error(why: &JsValue, structure: &str) -> NokhwaError {
+    let name = Reflect::get(why, &JsValue::from_str("name"))
+        .ok()
+        .and_then(|name| name.as_string());
+    let device = "getUserMedia".to_string();
+    let error = format!("{:?}", why);
+    match name.as_deref() {
+        Some("NotAllowedError" | "SecurityError") => {
+            NokhwaError::PermissionDenied { device, error }
+        }
+        Some("NotReadableError") => NokhwaError::DeviceBusy { device, error },
+        Some("OverconstrainedError") => NokhwaError::UnsupportedFormat {
+            format: "JSCameraConstraints".to_string(),
+            error,
+        },
+        _ => NokhwaError::StructureError {
+            structure: structure.to_string(),
+            error,
+        },
+    }
+}
+
 /// Requests Webcam permissions from the browser using [`MediaDevices::get_user_media()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.get_user_media) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getUserMedia)
 /// # Errors
 /// This will error if there is no valid web context or the web API is not supported
 pub fn request_permission() -> Result<JsFuture, NokhwaError> {
+    Ok(JsFuture::from(permission_promise()?))
+}
+
+fn permission_promise() -> Result<Promise, NokhwaError> {
     let window: Window = window()?;
     let navigator = window.navigator();
     let media_devices = media_devices(&navigator)?;
@@ -171,7 +223,7 @@ pub fn request_permission() -> Result<JsFuture, NokhwaError> {
     match media_devices.get_user_media() {
         Ok(promise) => {
             let promise: Promise = promise;
-            Ok(JsFuture::from(promise))
+            Ok(promise)
         }
         Err(why) => {
             return Err(NokhwaError::StructureError {
@@ -182,6 +234,153 @@ pub fn request_permission() -> Result<JsFuture, NokhwaError> {
     }
 }
 
+/// Gets whether the page may use the cameras, without asking the user, using [`navigator.permissions.query({ name: "camera" })`](https://developer.mozilla.org/en-US/docs/Web/API/Permissions/query).
+/// - `granted` is [`Granted`](CameraPermission::Granted): [`JSCamera::new()`] will not show a prompt.
+/// - `denied` is [`Denied`](CameraPermission::Denied): [`JSCamera::new()`] will fail with [`NokhwaError::PermissionDenied`].
+/// - `prompt` is [`NotDetermined`](CameraPermission::NotDetermined): [`JSCamera::new()`] (or [`request_permission()`]) will ask.
+///
+/// Browsers that cannot be asked (no Permissions API, or no `"camera"` permission in it, like older Firefox and Safari) get [`Unknown`](CameraPermission::Unknown).
+/// # Errors
+/// This will error if there is no valid web context.
+pub async fn permission_status() -> Result<CameraPermission, NokhwaError> {
+    Ok(match camera_permission_query().await? {
+        Some(status) => camera_permission(status.state()),
+        None => CameraPermission::Unknown,
+    })
+}
+
+/// Calls `callback` with the new [`CameraPermission`] every time it changes (e.g. the user revokes it in the site settings), listening for the [`change`](https://developer.mozilla.org/en-US/docs/Web/API/PermissionStatus/change_event) event. See [`permission_status()`].
+///
+/// Keep the returned [`JSCameraPermissionWatcher`] around for as long as `callback` should be called.
+/// # Errors
+/// This will error if there is no valid web context, or the browser cannot be asked for the camera permission (see [`permission_status()`]).
+pub async fn watch_permission_status<F>(
+    callback: F,
+) -> Result<JSCameraPermissionWatcher, NokhwaError>
+where
+    F: FnMut(CameraPermission) + 'static,
+{
+    let status = match camera_permission_query().await? {
+        Some(status) => status,
+        None => {
+            return Err(NokhwaError::StructureError {
+                structure: "PermissionStatus".to_string(),
+                error: "The camera permission cannot be queried".to_string(),
+            })
+        }
+    };
+
+    let mut callback = callback;
+    let listener_status = status.clone();
+    let listener = Closure::wrap(Box::new(move || {
+        callback(camera_permission(listener_status.state()));
+    }) as Box<dyn FnMut()>);
+
+    if let Err(why) =
+        status.add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+    {
+        return Err(NokhwaError::StructureError {
+            structure: "PermissionStatusChangeListener".to_string(),
+            error: format!("{:?}", why),
+        });
+    }
+
+    Ok(JSCameraPermissionWatcher { status, listener })
+}
+
+/// Watches the camera permission for changes, see [`watch_permission_status()`].
+///
+/// Dropping the watcher stops watching.
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraPermissionWatcher {
+    status: PermissionStatus,
+    listener: Closure<dyn FnMut()>,
+}
+
+impl JSCameraPermissionWatcher {
+    /// The current [`CameraPermission`].
+    #[must_use]
+    pub fn permission(&self) -> CameraPermission {
+        camera_permission(self.status.state())
+    }
+
+    /// Stops watching. The callback is not called again.
+    pub fn stop(&self) {
+        let _ = self
+            .status
+            .remove_event_listener_with_callback("change", self.listener.as_ref().unchecked_ref());
+    }
+}
+
+impl Drop for JSCameraPermissionWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// `None` if the browser has no Permissions API, or it does not know the `"camera"` permission
+async fn camera_permission_query() -> Result<Option<PermissionStatus>, NokhwaError> {
+    let window: Window = window()?;
+    let navigator = window.navigator();
+    let permissions = match navigator.permissions() {
+        Ok(permissions) => permissions,
+        Err(_) => return Ok(None),
+    };
+
+    // `web_sys::PermissionName` has no `Camera`, so the descriptor is built by hand
+    let descriptor = Object::new();
+    if let Err(why) = Reflect::set(
+        &descriptor,
+        &JsValue::from_str("name"),
+        &JsValue::from_str("camera"),
+    ) {
+        return Err(NokhwaError::StructureError {
+            structure: "PermissionDescriptor".to_string(),
+            error: format!("{:?}", why),
+        });
+    }
+
+    let promise = match permissions.query(&descriptor) {
+        Ok(promise) => promise,
+        Err(_) => return Ok(None),
+    };
+    match JsFuture::from(promise).await {
+        Ok(status) => Ok(Some(PermissionStatus::unchecked_from_js(status))),
+        // browsers without a `"camera"` permission reject with a `TypeError`
+        Err(_) => Ok(None),
+    }
+}
+
+fn camera_permission(state: PermissionState) -> CameraPermission {
+    match state {
+        PermissionState::Granted => CameraPermission::Granted,
+        PermissionState::Denied => CameraPermission::Denied,
+        PermissionState::Prompt => CameraPermission::NotDetermined,
+        _ => CameraPermission::Unknown,
+    }
+}
+
+// `InputDeviceInfo.getCapabilities()` is missing in some browsers (and in `web-sys`), so it is looked up at runtime
+fn device_position(device: &MediaDeviceInfo) -> CameraPosition {
+    let get_capabilities = match Reflect::get(device, &JsValue::from_str("getCapabilities")) {
+        Ok(function) if function.is_function() => Function::unchecked_from_js(function),
+        _ => return CameraPosition::Unknown,
+    };
+    let facing_modes = match get_capabilities
+        .call0(device)
+        .and_then(|capabilities| Reflect::get(&capabilities, &JsValue::from_str("facingMode")))
+    {
+        Ok(modes) if Array::is_array(&modes) => Array::from(&modes),
+        _ => return CameraPosition::Unknown,
+    };
+    // desktop browsers report no facing mode for built-in and USB cameras alike, so an empty list stays unknown
+    match facing_modes.get(0).as_string().as_deref() {
+        Some("user" | "left" | "right") => CameraPosition::Front,
+        Some("environment") => CameraPosition::Back,
+        _ => CameraPosition::Unknown,
+    }
+}
+
 /// Queries Cameras using [`MediaDevices::enumerate_devices()`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaDevices.html#method.enumerate_devices) [MDN](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/enumerateDevices)
 /// # Errors
 /// This will error if there is no valid web context or the web API is not supported
@@ -203,7 +402,7 @@ pub async fn query_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
                             let media_device_info =
                                 MediaDeviceInfo::unchecked_from_js(array.get(idx_device));
                             if media_device_info.kind() == MediaDeviceKind::Videoinput {
-                                device_list.push(CameraInfo::new(
+                                let mut camera_info = CameraInfo::new(
                                     media_device_info.label(),
                                     format!("{:?}", media_device_info.kind()),
                                     format!(
@@ -212,7 +411,14 @@ pub async fn query_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
                                         media_device_info.device_id()
                                     ),
                                     idx_device as usize,
-                                ));
+                                );
+                                camera_info.set_position(device_position(&media_device_info));
+                                let group_id = media_device_info.group_id();
+                                if !group_id.is_empty() {
+                                    camera_info.set_group_id(Some(group_id));
+                                }
+                                camera_info.guess_stream_kind(&[]);
+                                device_list.push(camera_info);
                             }
                         }
                     }
@@ -231,53 +437,163 @@ pub async fn query_js_cameras() -> Result<Vec<CameraInfo>, NokhwaError> {
     }
 }
 
-/// Queries the browser's supported constraints using [`navigator.mediaDevices.getSupportedConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getSupportedConstraints)
+/// The cameras that were plugged in or unplugged since the last change, see [`watch_js_cameras()`].
+///
+/// Cameras are told apart by their group and device ID (the `misc` of their [`CameraInfo`]).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JSCameraDeviceChange {
+    added: Vec<CameraInfo>,
+    removed: Vec<CameraInfo>,
+}
+
+impl JSCameraDeviceChange {
+    /// The cameras that were plugged in.
+    #[must_use]
+    pub fn added(&self) -> &[CameraInfo] {
+        &self.added
+    }
+
+    /// The cameras that were unplugged.
+    #[must_use]
+    pub fn removed(&self) -> &[CameraInfo] {
+        &self.removed
+    }
+
+    /// Whether no camera was plugged in or unplugged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    fn between(old: &[CameraInfo], new: &[CameraInfo]) -> Self {
+        let added = new
+            .iter()
+            .filter(|camera| !old.iter().any(|old| old.misc() == camera.misc()))
+            .cloned()
+            .collect();
+        let removed = old
+            .iter()
+            .filter(|camera| !new.iter().any(|new| new.misc() == camera.misc()))
+            .cloned()
+            .collect();
+        JSCameraDeviceChange { added, removed }
+    }
+}
+
+struct WatchState {
+    cameras: Vec<CameraInfo>,
+    callback: Box<dyn FnMut(Result<JSCameraDeviceChange, NokhwaError>)>,
+}
+
+/// Watches for cameras being plugged in or unplugged, listening for the [`devicechange`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/devicechange_event) event. See [`watch_js_cameras()`].
+///
+/// Dropping the watcher stops watching.
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraWatcher {
+    media_devices: MediaDevices,
+    listener: Closure<dyn FnMut()>,
+}
+
+impl JSCameraWatcher {
+    /// Stops watching. The callback is not called again.
+    pub fn stop(&self) {
+        let _ = self.media_devices.remove_event_listener_with_callback(
+            "devicechange",
+            self.listener.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+impl Drop for JSCameraWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Calls `callback` with the cameras that were plugged in or unplugged every time the browser fires [`devicechange`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/devicechange_event), re-running [`query_js_cameras()`].
+/// If that fails, `callback` gets the error instead. Changes that leave the list of cameras the same are not passed on.
+///
+/// The cameras there are when this is called are the starting point, so they are not reported as added. Keep the returned [`JSCameraWatcher`] around for as long as `callback` should be called.
 /// # Errors
 /// This will error if there is no valid web context or the web API is not supported
-pub fn query_supported_constraints() -> Result<Vec<JSCameraSupportedCapabilities>, NokhwaError> {
-    let js_supported_fn = Function::new_no_args(GET_CONSTRAINT_LIST_JS_CODE_STR);
-    match js_supported_fn.call0(&JsValue::NULL) {
-        Ok(value) => {
-            let value: JsValue = value;
-            let supported_cap_array: Array = Array::from(&value);
-
-            let mut capability_list = vec![];
-            for idx_supported in 0_u32..supported_cap_array.length() {
-                let supported = match supported_cap_array.get(idx_supported).dyn_ref::<JsString>() {
-                    Some(v) => {
-                        let v: &JsValue = v.as_ref();
-                        let s: String = match v.as_string() {
-                            Some(str) => str,
-                            None => {
-                                return Err(NokhwaError::StructureError {
-                                    structure: "Query Supported Constraints String None"
-                                        .to_string(),
-                                    error: "None".to_string(),
-                                })
-                            }
-                        };
-                        s
-                    }
-                    None => {
-                        continue;
-                    }
-                };
+/// # Quirks
+/// Until camera permission is granted, browsers hide the device IDs (and may only list one camera), so changes are reported less reliably.
+pub fn watch_js_cameras<F>(callback: F) -> Result<JSCameraWatcher, NokhwaError>
+where
+    F: FnMut(Result<JSCameraDeviceChange, NokhwaError>) + 'static,
+{
+    let window: Window = window()?;
+    let navigator = window.navigator();
+    let media_devices = media_devices(&navigator)?;
 
-                let capability = match JSCameraSupportedCapabilities::try_from(supported) {
-                    Ok(cap) => cap,
-                    Err(_) => {
-                        continue;
-                    }
-                };
-                capability_list.push(capability);
-            }
-            Ok(capability_list)
+    let state = Rc::new(RefCell::new(WatchState {
+        cameras: vec![],
+        callback: Box::new(callback),
+    }));
+
+    let initial_state = state.clone();
+    spawn_local(async move {
+        if let Ok(cameras) = query_js_cameras().await {
+            initial_state.borrow_mut().cameras = cameras;
         }
-        Err(why) => Err(NokhwaError::StructureError {
-            structure: "JSCameraSupportedCapabilities List Dict Function".to_string(),
-            error: why.as_string().unwrap_or_else(|| "".to_string()),
-        }),
+    });
+
+    let listener = Closure::wrap(Box::new(move || {
+        let state = state.clone();
+        spawn_local(async move {
+            let cameras = query_js_cameras().await;
+            let mut state = state.borrow_mut();
+            let change = match cameras {
+                Ok(cameras) => {
+                    let change = JSCameraDeviceChange::between(&state.cameras, &cameras);
+                    state.cameras = cameras;
+                    if change.is_empty() {
+                        return;
+                    }
+                    Ok(change)
+                }
+                Err(why) => Err(why),
+            };
+            (state.callback)(change);
+        });
+    }) as Box<dyn FnMut()>);
+
+    if let Err(why) = media_devices
+        .add_event_listener_with_callback("devicechange", listener.as_ref().unchecked_ref())
+    {
+        return Err(NokhwaError::StructureError {
+            structure: "MediaDevicesDeviceChangeListener".to_string(),
+            error: format!("{:?}", why),
+        });
     }
+
+    Ok(JSCameraWatcher {
+        media_devices,
+        listener,
+    })
+}
+
+/// Queries the browser's supported constraints using [`navigator.mediaDevices.getSupportedConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getSupportedConstraints)
+/// # Errors
+/// This will error if there is no valid web context or the web API is not supported
+pub fn query_supported_constraints() -> Result<Vec<JSCameraSupportedCapabilities>, NokhwaError> {
+    let window: Window = window()?;
+    let navigator = window.navigator();
+    let media_devices = media_devices(&navigator)?;
+    let supported: MediaTrackSupportedConstraints = media_devices.get_supported_constraints();
+
+    // the dictionary only holds the constraints the browser knows, set to `true`
+    let capability_list = JSCameraSupportedCapabilities::ALL
+        .iter()
+        .filter(|capability| {
+            Reflect::get(&supported, &JsValue::from_str(&capability.to_string()))
+                .ok()
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false)
+        })
+        .copied()
+        .collect();
+    Ok(capability_list)
 }
 
 /// The enum describing the possible constraints for video in the browser.
@@ -291,6 +607,7 @@ pub fn query_supported_constraints() -> Result<Vec<JSCameraSupportedCapabilities
 /// - `ResizeMode`: Whether the client can crop and/or scale the stream to match the resolution (width, height). See [`JSCameraResizeMode`]
 /// See More: [`MediaTrackConstraints`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints) [`Capabilities, constraints, and settings`](https://developer.mozilla.org/en-US/docs/Web/API/Media_Streams_API/Constraints)
 #[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
 pub enum JSCameraSupportedCapabilities {
     DeviceID,
     GroupID,
@@ -302,6 +619,20 @@ pub enum JSCameraSupportedCapabilities {
     ResizeMode,
 }
 
+impl JSCameraSupportedCapabilities {
+    /// Every constraint, in the order [`query_supported_constraints()`] lists them.
+    pub const ALL: [JSCameraSupportedCapabilities; 8] = [
+        JSCameraSupportedCapabilities::DeviceID,
+        JSCameraSupportedCapabilities::GroupID,
+        JSCameraSupportedCapabilities::AspectRatio,
+        JSCameraSupportedCapabilities::FacingMode,
+        JSCameraSupportedCapabilities::FrameRate,
+        JSCameraSupportedCapabilities::Height,
+        JSCameraSupportedCapabilities::Width,
+        JSCameraSupportedCapabilities::ResizeMode,
+    ];
+}
+
 impl Display for JSCameraSupportedCapabilities {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let cap = match self {
@@ -358,6 +689,7 @@ impl TryFrom<String> for JSCameraSupportedCapabilities {
 /// - Right: The camera that shows the user but to their right, such as a camera that shows a user but to their right shoulder
 /// See More: [`facingMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/facingMode)
 #[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
 pub enum JSCameraFacingMode {
     Any,
     Environment,
@@ -392,6 +724,7 @@ impl Debug for JSCameraFacingMode {
 /// - `CropAndScale`: Crop and/or scale to match the requested resolution.
 /// See More: [`resizeMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints#resizemode)
 #[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
 pub enum JSCameraResizeMode {
     Any,
     None,
@@ -417,25 +750,250 @@ impl Debug for JSCameraResizeMode {
     }
 }
 
+/// How the camera focuses.
+/// - `None`: The camera has no focus control.
+/// - `Manual`: The camera stays at the set [`focusDistance`](JSCameraControl::FocusDistance).
+/// - `SingleShot`: The camera focuses once, then stays there.
+/// - `Continuous`: The camera keeps refocusing.
+/// See More: [`focusMode`](https://w3c.github.io/mediacapture-image/#dom-mediatrackconstraintset-focusmode)
+#[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub enum JSCameraFocusMode {
+    None,
+    Manual,
+    SingleShot,
+    Continuous,
+}
+
+impl Display for JSCameraFocusMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cap = match self {
+            JSCameraFocusMode::None => "none",
+            JSCameraFocusMode::Manual => "manual",
+            JSCameraFocusMode::SingleShot => "single-shot",
+            JSCameraFocusMode::Continuous => "continuous",
+        };
+        write!(f, "{}", cap)
+    }
+}
+
+impl Debug for JSCameraFocusMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = self.to_string();
+        write!(f, "{}", str)
+    }
+}
+
+/// A camera control of a [`JSCamera`], the browser side of [`KnownCameraControls`](crate::KnownCameraControls). See [`JSCamera::set_control()`].
+/// - `Torch`: Turns the torch (flash light) on or off.
+/// - `Zoom`: The zoom factor.
+/// - `FocusMode`: How the camera focuses, see [`JSCameraFocusMode`].
+/// - `FocusDistance`: The focus distance in meters, for [`JSCameraFocusMode::Manual`].
+/// - `ExposureCompensation`: The exposure compensation in f-stops.
+///
+/// These are mostly supported on mobile devices. Check [`JSCamera::capabilities()`] for what a camera has.
+/// See More: [`MediaStream Image Capture`](https://w3c.github.io/mediacapture-image/#mediatrackconstraintset-section)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum JSCameraControl {
+    Torch(bool),
+    Zoom(f64),
+    FocusMode(JSCameraFocusMode),
+    FocusDistance(f64),
+    ExposureCompensation(f64),
+}
+
+impl JSCameraControl {
+    /// The name of the constraint this control sets.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            JSCameraControl::Torch(_) => "torch",
+            JSCameraControl::Zoom(_) => "zoom",
+            JSCameraControl::FocusMode(_) => "focusMode",
+            JSCameraControl::FocusDistance(_) => "focusDistance",
+            JSCameraControl::ExposureCompensation(_) => "exposureCompensation",
+        }
+    }
+
+    fn value(&self) -> JsValue {
+        match self {
+            JSCameraControl::Torch(torch) => JsValue::from_bool(*torch),
+            JSCameraControl::FocusMode(mode) => JsValue::from_str(&mode.to_string()),
+            JSCameraControl::Zoom(value)
+            | JSCameraControl::FocusDistance(value)
+            | JSCameraControl::ExposureCompensation(value) => JsValue::from_f64(*value),
+        }
+    }
+
+    // checks the control against what the camera reports it can do, as browsers skip advanced sets they cannot satisfy without saying so
+    fn check(&self, capabilities: &JSCameraCapabilities) -> Result<(), NokhwaError> {
+        let supported = match self {
+            JSCameraControl::Torch(_) => capabilities.torch(),
+            JSCameraControl::FocusMode(mode) => capabilities.focus_modes().contains(mode),
+            JSCameraControl::Zoom(value) => in_range(capabilities.zoom(), *value),
+            JSCameraControl::FocusDistance(value) => {
+                in_range(capabilities.focus_distance(), *value)
+            }
+            JSCameraControl::ExposureCompensation(value) => {
+                in_range(capabilities.exposure_compensation(), *value)
+            }
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(NokhwaError::SetPropertyError {
+                property: self.name().to_string(),
+                value: format!("{:?}", self),
+                error: "Not supported by this camera".to_string(),
+            })
+        }
+    }
+}
+
+fn in_range(range: Option<JSCameraCapabilityRange>, value: f64) -> bool {
+    match range {
+        Some(range) => range.min() <= value && value <= range.max(),
+        None => false,
+    }
+}
+
+/// One set of the [`advanced`](https://developer.mozilla.org/en-US/docs/Web/API/Media_Streams_API/Constraints#advanced_constraints) constraints of a [`JSCameraConstraintsBuilder`].
+///
+/// The browser tries the sets in order, and keeps each one it can satisfy *completely* on top of the basic constraints. Unlike those, the values here are neither `ideal` nor ranges: a set either fits or is skipped.
+/// Unset values (`None`) are left out of the set.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraConstraintSet {
+    pub(crate) resolution: Option<Resolution>,
+    pub(crate) aspect_ratio: Option<f64>,
+    pub(crate) facing_mode: Option<JSCameraFacingMode>,
+    pub(crate) frame_rate: Option<u32>,
+    pub(crate) resize_mode: Option<JSCameraResizeMode>,
+    pub(crate) device_id: Option<String>,
+    pub(crate) group_id: Option<String>,
+}
+
+impl JSCameraConstraintSet {
+    /// Constructs an empty [`JSCameraConstraintSet`].
+    #[must_use]
+    pub fn new() -> Self {
+        JSCameraConstraintSet::default()
+    }
+
+    /// Sets [`width`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/width) and [`height`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/height).
+    #[must_use]
+    pub fn resolution(mut self, resolution: Resolution) -> JSCameraConstraintSet {
+        self.resolution = Some(resolution);
+        self
+    }
+
+    /// Sets [`aspectRatio`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/aspectRatio).
+    #[must_use]
+    pub fn aspect_ratio(mut self, ratio: f64) -> JSCameraConstraintSet {
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Sets [`facingMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/facingMode).
+    #[must_use]
+    pub fn facing_mode(mut self, facing_mode: JSCameraFacingMode) -> JSCameraConstraintSet {
+        self.facing_mode = Some(facing_mode);
+        self
+    }
+
+    /// Sets [`frameRate`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/frameRate).
+    #[must_use]
+    pub fn frame_rate(mut self, fps: u32) -> JSCameraConstraintSet {
+        self.frame_rate = Some(fps);
+        self
+    }
+
+    /// Sets [`resizeMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints#resizemode).
+    #[must_use]
+    pub fn resize_mode(mut self, resize_mode: JSCameraResizeMode) -> JSCameraConstraintSet {
+        self.resize_mode = Some(resize_mode);
+        self
+    }
+
+    /// Sets [`deviceId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/deviceId).
+    #[must_use]
+    pub fn device_id<S: ToString>(mut self, id: &S) -> JSCameraConstraintSet {
+        self.device_id = Some(id.to_string());
+        self
+    }
+
+    /// Sets [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId).
+    #[must_use]
+    pub fn group_id<S: ToString>(mut self, id: &S) -> JSCameraConstraintSet {
+        self.group_id = Some(id.to_string());
+        self
+    }
+
+    fn to_object(&self) -> Result<Object, NokhwaError> {
+        let set = Object::new();
+        let values = [
+            (
+                "width",
+                self.resolution.map(|res| JsValue::from(res.width_x)),
+            ),
+            (
+                "height",
+                self.resolution.map(|res| JsValue::from(res.height_y)),
+            ),
+            ("aspectRatio", self.aspect_ratio.map(JsValue::from_f64)),
+            (
+                "facingMode",
+                self.facing_mode
+                    .map(|mode| JsValue::from_str(&mode.to_string())),
+            ),
+            ("frameRate", self.frame_rate.map(JsValue::from)),
+            (
+                "resizeMode",
+                self.resize_mode
+                    .map(|mode| JsValue::from_str(&mode.to_string())),
+            ),
+            ("deviceId", self.device_id.as_deref().map(JsValue::from_str)),
+            ("groupId", self.group_id.as_deref().map(JsValue::from_str)),
+        ];
+        for (name, value) in values.iter() {
+            if let Some(value) = value {
+                if let Err(why) = Reflect::set(&set, &JsValue::from_str(name), value) {
+                    return Err(constraint_error(&why));
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
 /// A builder that builds a [`JSCameraConstraints`] that is used to construct a [`JSCamera`].
 /// See More: [`Constraints MDN`](https://developer.mozilla.org/en-US/docs/Web/API/Media_Streams_API/Constraints), [`Properties of Media Tracks MDN`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints)
 #[derive(Clone, Debug)]
 #[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
 pub struct JSCameraConstraintsBuilder {
     pub(crate) preferred_resolution: Resolution,
     pub(crate) resolution_exact: bool,
+    pub(crate) min_resolution: Option<Resolution>,
+    pub(crate) max_resolution: Option<Resolution>,
     pub(crate) aspect_ratio: f64,
     pub(crate) aspect_ratio_exact: bool,
+    pub(crate) min_aspect_ratio: Option<f64>,
+    pub(crate) max_aspect_ratio: Option<f64>,
     pub(crate) facing_mode: JSCameraFacingMode,
     pub(crate) facing_mode_exact: bool,
     pub(crate) frame_rate: u32,
     pub(crate) frame_rate_exact: bool,
+    pub(crate) min_frame_rate: Option<u32>,
+    pub(crate) max_frame_rate: Option<u32>,
     pub(crate) resize_mode: JSCameraResizeMode,
     pub(crate) resize_mode_exact: bool,
     pub(crate) device_id: String,
     pub(crate) device_id_exact: bool,
     pub(crate) group_id: String,
     pub(crate) group_id_exact: bool,
+    pub(crate) advanced: Vec<JSCameraConstraintSet>,
+    pub(crate) audio: bool,
 }
 
 impl JSCameraConstraintsBuilder {
@@ -467,6 +1025,23 @@ impl JSCameraConstraintsBuilder {
         self
     }
 
+    /// Sets the lowest, preferred and highest resolution, e.g. "at least 720p but prefer 1080p" is `resolution_range(Some(720p), 1080p, None)`.
+    ///
+    /// Sets `min`, `ideal` and `max` of [`width`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/width) and [`height`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/height).
+    /// The browser fails to open the camera if it cannot stay within `min` and `max`. If [`resolution_exact`](crate::js_camera::JSCameraConstraintsBuilder::resolution_exact) is set, only `ideal` is used (as `exact`).
+    #[must_use]
+    pub fn resolution_range(
+        mut self,
+        min: Option<Resolution>,
+        ideal: Resolution,
+        max: Option<Resolution>,
+    ) -> JSCameraConstraintsBuilder {
+        self.min_resolution = min;
+        self.preferred_resolution = ideal;
+        self.max_resolution = max;
+        self
+    }
+
     /// Sets the aspect ratio of the resulting constraint for the [`JSCameraConstraintsBuilder`].
     ///
     /// Sets [`aspectRatio`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/aspectRatio).
@@ -483,6 +1058,23 @@ impl JSCameraConstraintsBuilder {
         self
     }
 
+    /// Sets the lowest, preferred and highest aspect ratio.
+    ///
+    /// Sets `min`, `ideal` and `max` of [`aspectRatio`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/aspectRatio).
+    /// If [`aspect_ratio_exact`](crate::js_camera::JSCameraConstraintsBuilder::aspect_ratio_exact) is set, only `ideal` is used (as `exact`).
+    #[must_use]
+    pub fn aspect_ratio_range(
+        mut self,
+        min: Option<f64>,
+        ideal: f64,
+        max: Option<f64>,
+    ) -> JSCameraConstraintsBuilder {
+        self.min_aspect_ratio = min;
+        self.aspect_ratio = ideal;
+        self.max_aspect_ratio = max;
+        self
+    }
+
     /// Sets the facing mode of the resulting constraint for the [`JSCameraConstraintsBuilder`].
     ///
     /// Sets [`facingMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/facingMode).
@@ -515,6 +1107,23 @@ impl JSCameraConstraintsBuilder {
         self
     }
 
+    /// Sets the lowest, preferred and highest frame rate.
+    ///
+    /// Sets `min`, `ideal` and `max` of [`frameRate`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/frameRate).
+    /// If [`frame_rate_exact`](crate::js_camera::JSCameraConstraintsBuilder::frame_rate_exact) is set, only `ideal` is used (as `exact`).
+    #[must_use]
+    pub fn frame_rate_range(
+        mut self,
+        min: Option<u32>,
+        ideal: u32,
+        max: Option<u32>,
+    ) -> JSCameraConstraintsBuilder {
+        self.min_frame_rate = min;
+        self.frame_rate = ideal;
+        self.max_frame_rate = max;
+        self
+    }
+
     /// Sets the resize mode of the resulting constraint for the [`JSCameraConstraintsBuilder`].
     ///
     /// Sets [`resizeMode`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints#resizemode).
@@ -563,203 +1172,197 @@ impl JSCameraConstraintsBuilder {
         self
     }
 
+    /// Sets the [`advanced`](https://developer.mozilla.org/en-US/docs/Web/API/Media_Streams_API/Constraints#advanced_constraints) constraint sets, tried in order after the ones above. See [`JSCameraConstraintSet`].
+    ///
+    /// E.g. "1080p at 60 FPS, or else 720p at 60 FPS, or else whatever fits" is two sets.
+    #[must_use]
+    pub fn advanced(mut self, sets: Vec<JSCameraConstraintSet>) -> JSCameraConstraintsBuilder {
+        self.advanced = sets;
+        self
+    }
+
+    /// Sets whether to also capture audio from the default microphone, so a [`JSCamera`] gets both with a single permission prompt. See [`JSCamera::audio_tracks()`].
+    #[must_use]
+    pub fn audio(mut self, value: bool) -> JSCameraConstraintsBuilder {
+        self.audio = value;
+        self
+    }
+
     /// Builds the [`JSCameraConstraints`]
     ///
-    /// # Security
-    /// WARNING: This function uses [`Function`](https://docs.rs/js-sys/0.3.52/js_sys/struct.Function.html) and if the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are invalid/contain malicious JS, it will run without restraint. Please take care as to make sure the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) and the [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are not malicious! (This usually boils down to not letting users input data directly)
-    ///
+    /// The constraints are built as plain JS objects, so the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) and [`group_id`](crate::js_camera::JSCameraConstraintsBuilder::group_id) are only ever passed as strings, never run.
     /// # Errors
-    /// This function may return an error on an invalid string in [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId) or if the
-    /// Javascript Function fails to run.
-    #[allow(clippy::too_many_lines)]
+    /// This function may return an error if a constraint cannot be set on the JS object.
     pub fn build(self) -> Result<JSCameraConstraints, NokhwaError> {
-        let null_resolution = Resolution::default();
-        let null_string = String::new();
-
-        let width_string = {
-            if self.resolution_exact {
-                if self.preferred_resolution == null_resolution {
-                    format!("")
-                } else {
-                    format!("width: {{ exact: {} }}", self.preferred_resolution.width_x)
-                }
-            } else if self.preferred_resolution.width_x == 0 {
-                format!("")
-            } else {
-                format!("width: {{ ideal: {} }}", self.preferred_resolution.width_x)
-            }
-        };
-
-        let height_string = {
-            if self.aspect_ratio_exact {
-                if self.preferred_resolution == null_resolution {
-                    format!("")
-                } else {
-                    format!(
-                        "height: {{ exact: {} }}",
-                        self.preferred_resolution.height_y
-                    )
-                }
-            } else if self.preferred_resolution == null_resolution {
-                format!("")
-            } else {
-                format!(
-                    "height: {{ ideal: {} }}",
-                    self.preferred_resolution.height_y
-                )
-            }
-        };
-
-        let aspect_ratio_string = {
-            if self.aspect_ratio_exact {
-                if self.aspect_ratio == 0_f64 {
-                    format!("")
-                } else {
-                    format!("aspectRatio: {{ exact: {} }}", self.aspect_ratio)
-                }
-            } else if self.aspect_ratio == 0_f64 {
-                format!("")
-            } else {
-                format!("aspectRatio: {{ ideal: {} }}", self.aspect_ratio)
-            }
-        };
-
-        let facing_mode_string = {
-            if self.facing_mode_exact {
-                if self.facing_mode == JSCameraFacingMode::Any {
-                    format!("")
-                } else {
-                    format!("facingMode: {{ exact: {} }}", self.facing_mode)
-                }
-            } else if self.facing_mode == JSCameraFacingMode::Any {
-                format!("")
-            } else {
-                format!("facingMode: {{ ideal: {} }}", self.facing_mode)
-            }
-        };
-
-        let frame_rate_string = {
-            if self.frame_rate_exact {
-                if self.frame_rate == 0 {
-                    format!("")
-                } else {
-                    format!("frameRate: {{ exact: {} }}", self.frame_rate)
-                }
-            } else if self.frame_rate == 0 {
-                format!("")
-            } else {
-                format!("frameRate: {{ ideal: {} }}", self.frame_rate)
-            }
-        };
-
-        let resize_mode_string = {
-            if self.resize_mode_exact {
-                if self.resize_mode == JSCameraResizeMode::Any {
-                    format!("")
-                } else {
-                    format!("resizeMode: {{ exact: {} }}", self.resize_mode)
-                }
-            } else if self.resize_mode == JSCameraResizeMode::Any {
-                format!("")
-            } else {
-                format!("resizeMode: {{ ideal: {} }}", self.resize_mode)
+        let video = Object::new();
+        let width = |resolution: Resolution| JsValue::from(resolution.width_x);
+        let height = |resolution: Resolution| JsValue::from(resolution.height_y);
+
+        set_constraint(
+            &video,
+            "width",
+            Some(self.preferred_resolution)
+                .filter(|resolution| resolution.width_x != 0)
+                .map(width),
+            self.resolution_exact,
+            self.min_resolution.map(width),
+            self.max_resolution.map(width),
+        )?;
+        set_constraint(
+            &video,
+            "height",
+            Some(self.preferred_resolution)
+                .filter(|resolution| resolution.height_y != 0)
+                .map(height),
+            self.resolution_exact,
+            self.min_resolution.map(height),
+            self.max_resolution.map(height),
+        )?;
+        set_constraint(
+            &video,
+            "aspectRatio",
+            Some(self.aspect_ratio)
+                .filter(|ratio| *ratio != 0_f64)
+                .map(JsValue::from_f64),
+            self.aspect_ratio_exact,
+            self.min_aspect_ratio.map(JsValue::from_f64),
+            self.max_aspect_ratio.map(JsValue::from_f64),
+        )?;
+        set_constraint(
+            &video,
+            "facingMode",
+            Some(self.facing_mode)
+                .filter(|mode| *mode != JSCameraFacingMode::Any)
+                .map(|mode| JsValue::from_str(&mode.to_string())),
+            self.facing_mode_exact,
+            None,
+            None,
+        )?;
+        set_constraint(
+            &video,
+            "frameRate",
+            Some(self.frame_rate)
+                .filter(|fps| *fps != 0)
+                .map(JsValue::from),
+            self.frame_rate_exact,
+            self.min_frame_rate.map(JsValue::from),
+            self.max_frame_rate.map(JsValue::from),
+        )?;
+        set_constraint(
+            &video,
+            "resizeMode",
+            Some(self.resize_mode)
+                .filter(|mode| *mode != JSCameraResizeMode::Any)
+                .map(|mode| JsValue::from_str(&mode.to_string())),
+            self.resize_mode_exact,
+            None,
+            None,
+        )?;
+        set_constraint(
+            &video,
+            "deviceId",
+            Some(&self.device_id)
+                .filter(|id| !id.is_empty())
+                .map(|id| JsValue::from_str(id)),
+            self.device_id_exact,
+            None,
+            None,
+        )?;
+        set_constraint(
+            &video,
+            "groupId",
+            Some(&self.group_id)
+                .filter(|id| !id.is_empty())
+                .map(|id| JsValue::from_str(id)),
+            self.group_id_exact,
+            None,
+            None,
+        )?;
+
+        if !self.advanced.is_empty() {
+            let advanced = Array::new();
+            for set in &self.advanced {
+                advanced.push(&set.to_object()?);
             }
-        };
-
-        let device_id_string = {
-            if self.device_id_exact {
-                if self.device_id == null_string {
-                    format!("")
-                } else {
-                    format!("deviceId: {{ exact: {} }}", self.device_id)
-                }
-            } else if self.device_id == null_string {
-                format!("")
-            } else {
-                format!("deviceId: {{ ideal: {} }}", self.device_id)
+            if let Err(why) = Reflect::set(&video, &JsValue::from_str("advanced"), &advanced) {
+                return Err(constraint_error(&why));
             }
-        };
+        }
 
-        let group_id_string = {
-            if self.group_id_exact {
-                if self.group_id == null_string {
-                    format!("")
-                } else {
-                    format!("groupId: {{ exact: {} }}", self.group_id)
-                }
-            } else if self.group_id == null_string {
-                format!("")
-            } else {
-                format!("groupId: {{ ideal: {} }}", self.group_id)
-            }
-        };
+        let mut media_stream_constraints = MediaStreamConstraints::new();
+        media_stream_constraints.audio(&JsValue::from_bool(self.audio));
+        if Object::keys(&video).length() == 0 {
+            media_stream_constraints.video(&JsValue::TRUE);
+        } else {
+            media_stream_constraints.video(&video);
+        }
 
-        let mut arguments = vec![
-            width_string,
-            height_string,
-            aspect_ratio_string,
-            facing_mode_string,
-            frame_rate_string,
-            resize_mode_string,
-            device_id_string,
-            group_id_string,
-        ];
-        arguments.sort();
-        arguments.dedup();
+        Ok(JSCameraConstraints {
+            media_constraints: media_stream_constraints,
+            preferred_resolution: self.preferred_resolution,
+            resolution_exact: self.resolution_exact,
+            min_resolution: self.min_resolution,
+            max_resolution: self.max_resolution,
+            aspect_ratio: self.aspect_ratio,
+            aspect_ratio_exact: self.aspect_ratio_exact,
+            min_aspect_ratio: self.min_aspect_ratio,
+            max_aspect_ratio: self.max_aspect_ratio,
+            facing_mode: self.facing_mode,
+            facing_mode_exact: self.facing_mode_exact,
+            frame_rate: self.frame_rate,
+            frame_rate_exact: self.frame_rate_exact,
+            min_frame_rate: self.min_frame_rate,
+            max_frame_rate: self.max_frame_rate,
+            resize_mode: self.resize_mode,
+            resize_mode_exact: self.resize_mode_exact,
+            device_id: self.device_id,
+            device_id_exact: self.device_id_exact,
+            group_id: self.group_id,
+            group_id_exact: self.group_id_exact,
+            advanced: self.advanced,
+            audio: self.audio,
+        })
+    }
+}
 
-        let mut arguments_condensed = String::new();
-        for argument in arguments {
-            if argument != null_string {
-                arguments_condensed = format!("{},{}\n", arguments_condensed, argument);
-            }
-        }
-        if arguments_condensed == null_string {
-            arguments_condensed = "true".to_string();
-        }
-
-        let constraints_fn = Function::new_no_args(&format!(
-            r#"
-let constraints = {{
-    audio: false,
-    video: {{
-        {}
-    }}
-}};
-
-return constraints;
-"#,
-            arguments_condensed
-        ));
-        match constraints_fn.call0(&JsValue::NULL) {
-            Ok(constraints) => {
-                let constraints: JsValue = constraints;
-                let media_stream_constraints = MediaStreamConstraints::from(constraints);
-                Ok(JSCameraConstraints {
-                    media_constraints: media_stream_constraints,
-                    preferred_resolution: self.preferred_resolution,
-                    resolution_exact: self.resolution_exact,
-                    aspect_ratio: self.aspect_ratio,
-                    aspect_ratio_exact: self.aspect_ratio_exact,
-                    facing_mode: self.facing_mode,
-                    facing_mode_exact: self.facing_mode_exact,
-                    frame_rate: self.frame_rate,
-                    frame_rate_exact: self.frame_rate_exact,
-                    resize_mode: self.resize_mode,
-                    resize_mode_exact: self.resize_mode_exact,
-                    device_id: self.device_id,
-                    device_id_exact: self.device_id_exact,
-                    group_id: self.group_id,
-                    group_id_exact: self.device_id_exact,
-                })
+// sets `video[name]` to `{ exact: ideal }`, or to `{ ideal, min, max }` with the ones that are set. Nothing at all leaves it unset
+fn set_constraint(
+    video: &Object,
+    name: &str,
+    ideal: Option<JsValue>,
+    exact: bool,
+    min: Option<JsValue>,
+    max: Option<JsValue>,
+) -> Result<(), NokhwaError> {
+    let values = if exact {
+        vec![("exact", ideal)]
+    } else {
+        vec![("ideal", ideal), ("min", min), ("max", max)]
+    };
+    let constraint = Object::new();
+    let mut is_empty = true;
+    for (kind, value) in values {
+        if let Some(value) = value {
+            if let Err(why) = Reflect::set(&constraint, &JsValue::from_str(kind), &value) {
+                return Err(constraint_error(&why));
             }
-            Err(why) => Err(NokhwaError::StructureError {
-                structure: "MediaStreamConstraintsJSBuild".to_string(),
-                error: format!("{:?}", why),
-            }),
+            is_empty = false;
         }
     }
+    if is_empty {
+        return Ok(());
+    }
+    match Reflect::set(video, &JsValue::from_str(name), &constraint) {
+        Ok(_) => Ok(()),
+        Err(why) => Err(constraint_error(&why)),
+    }
+}
+
+fn constraint_error(why: &JsValue) -> NokhwaError {
+    NokhwaError::StructureError {
+        structure: "MediaStreamConstraintsJSBuild".to_string(),
+        error: format!("{:?}", why),
+    }
 }
 
 impl Default for JSCameraConstraintsBuilder {
@@ -767,18 +1370,26 @@ impl Default for JSCameraConstraintsBuilder {
         JSCameraConstraintsBuilder {
             preferred_resolution: Resolution::new(640, 480),
             resolution_exact: false,
+            min_resolution: None,
+            max_resolution: None,
             aspect_ratio: 1.777_777_777_78_f64,
             aspect_ratio_exact: false,
+            min_aspect_ratio: None,
+            max_aspect_ratio: None,
             facing_mode: JSCameraFacingMode::Any,
             facing_mode_exact: false,
             frame_rate: 15,
             frame_rate_exact: false,
+            min_frame_rate: None,
+            max_frame_rate: None,
             resize_mode: JSCameraResizeMode::Any,
             resize_mode_exact: false,
             device_id: "".to_string(),
             device_id_exact: false,
             group_id: "".to_string(),
             group_id_exact: false,
+            advanced: vec![],
+            audio: false,
         }
     }
 }
@@ -788,22 +1399,31 @@ impl Default for JSCameraConstraintsBuilder {
 /// If you want more options, see [`JSCameraConstraintsBuilder`]
 #[derive(Clone, Debug)]
 #[allow(clippy::struct_excessive_bools)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
 pub struct JSCameraConstraints {
     pub(crate) media_constraints: MediaStreamConstraints,
     pub(crate) preferred_resolution: Resolution,
     pub(crate) resolution_exact: bool,
+    pub(crate) min_resolution: Option<Resolution>,
+    pub(crate) max_resolution: Option<Resolution>,
     pub(crate) aspect_ratio: f64,
     pub(crate) aspect_ratio_exact: bool,
+    pub(crate) min_aspect_ratio: Option<f64>,
+    pub(crate) max_aspect_ratio: Option<f64>,
     pub(crate) facing_mode: JSCameraFacingMode,
     pub(crate) facing_mode_exact: bool,
     pub(crate) frame_rate: u32,
     pub(crate) frame_rate_exact: bool,
+    pub(crate) min_frame_rate: Option<u32>,
+    pub(crate) max_frame_rate: Option<u32>,
     pub(crate) resize_mode: JSCameraResizeMode,
     pub(crate) resize_mode_exact: bool,
     pub(crate) device_id: String,
     pub(crate) device_id_exact: bool,
     pub(crate) group_id: String,
     pub(crate) group_id_exact: bool,
+    pub(crate) advanced: Vec<JSCameraConstraintSet>,
+    pub(crate) audio: bool,
 }
 
 impl JSCameraConstraints {
@@ -840,6 +1460,30 @@ impl JSCameraConstraints {
         self.resolution_exact = resolution_exact;
     }
 
+    /// Gets the internal resolution range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn resolution_range(&self) -> (Option<Resolution>, Resolution, Option<Resolution>) {
+        (
+            self.min_resolution,
+            self.preferred_resolution,
+            self.max_resolution,
+        )
+    }
+
+    /// Sets the internal resolution range. See [`JSCameraConstraintsBuilder::resolution_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_resolution_range(
+        &mut self,
+        min: Option<Resolution>,
+        ideal: Resolution,
+        max: Option<Resolution>,
+    ) {
+        self.min_resolution = min;
+        self.preferred_resolution = ideal;
+        self.max_resolution = max;
+    }
+
     /// Gets the internal aspect ratio.
     #[must_use]
     pub fn aspect_ratio(&self) -> f64 {
@@ -866,6 +1510,25 @@ impl JSCameraConstraints {
         self.aspect_ratio_exact = aspect_ratio_exact;
     }
 
+    /// Gets the internal aspect ratio range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn aspect_ratio_range(&self) -> (Option<f64>, f64, Option<f64>) {
+        (
+            self.min_aspect_ratio,
+            self.aspect_ratio,
+            self.max_aspect_ratio,
+        )
+    }
+
+    /// Sets the internal aspect ratio range. See [`JSCameraConstraintsBuilder::aspect_ratio_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_aspect_ratio_range(&mut self, min: Option<f64>, ideal: f64, max: Option<f64>) {
+        self.min_aspect_ratio = min;
+        self.aspect_ratio = ideal;
+        self.max_aspect_ratio = max;
+    }
+
     /// Gets the internal [`JSCameraFacingMode`].
     #[must_use]
     pub fn facing_mode(&self) -> JSCameraFacingMode {
@@ -918,6 +1581,21 @@ impl JSCameraConstraints {
         self.frame_rate_exact = frame_rate_exact;
     }
 
+    /// Gets the internal frame rate range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn frame_rate_range(&self) -> (Option<u32>, u32, Option<u32>) {
+        (self.min_frame_rate, self.frame_rate, self.max_frame_rate)
+    }
+
+    /// Sets the internal frame rate range. See [`JSCameraConstraintsBuilder::frame_rate_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_frame_rate_range(&mut self, min: Option<u32>, ideal: u32, max: Option<u32>) {
+        self.min_frame_rate = min;
+        self.frame_rate = ideal;
+        self.max_frame_rate = max;
+    }
+
     /// Gets the internal [`JSCameraResizeMode`].
     #[must_use]
     pub fn resize_mode(&self) -> JSCameraResizeMode {
@@ -996,31 +1674,59 @@ impl JSCameraConstraints {
         self.group_id_exact = group_id_exact;
     }
 
+    /// Gets the internal [`advanced`](crate::js_camera::JSCameraConstraintsBuilder::advanced) constraint sets.
+    #[must_use]
+    pub fn advanced(&self) -> &[JSCameraConstraintSet] {
+        &self.advanced
+    }
+
+    /// Sets the internal advanced constraint sets.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_advanced(&mut self, advanced: Vec<JSCameraConstraintSet>) {
+        self.advanced = advanced;
+    }
+
+    /// Gets whether audio is captured as well.
+    #[must_use]
+    pub fn audio(&self) -> bool {
+        self.audio
+    }
+
+    /// Sets whether audio is captured as well. This only affects new [`JSCamera`]s.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_audio(&mut self, audio: bool) {
+        self.audio = audio;
+    }
+
     /// Applies any modified constraints.
-    /// # Security
-    /// WARNING: This function uses [`Function`](https://docs.rs/js-sys/0.3.52/js_sys/struct.Function.html) and if the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are invalid/contain malicious JS, it will run without restraint. Please take care as to make sure the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) and the [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are not malicious! (This usually boils down to not letting users input data directly)
-    ///
     /// # Errors
-    /// This function may return an error on an invalid string in [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId) or if the
-    /// Javascript Function fails to run.
+    /// This function may return an error if a constraint cannot be set. See [`JSCameraConstraintsBuilder::build()`].
     pub fn apply_constraints(&mut self) -> Result<(), NokhwaError> {
         let new_constraints = JSCameraConstraintsBuilder {
             preferred_resolution: self.preferred_resolution(),
             resolution_exact: self.resolution_exact(),
+            min_resolution: self.resolution_range().0,
+            max_resolution: self.resolution_range().2,
             aspect_ratio: self.aspect_ratio(),
             aspect_ratio_exact: self.aspect_ratio_exact(),
+            min_aspect_ratio: self.aspect_ratio_range().0,
+            max_aspect_ratio: self.aspect_ratio_range().2,
             facing_mode: self.facing_mode(),
             facing_mode_exact: self.facing_mode_exact(),
             frame_rate: self.frame_rate(),
             frame_rate_exact: self.frame_rate_exact(),
+            min_frame_rate: self.frame_rate_range().0,
+            max_frame_rate: self.frame_rate_range().2,
             resize_mode: self.resize_mode(),
             resize_mode_exact: self.resize_mode_exact(),
             device_id: self.device_id().to_string(),
             device_id_exact: self.device_id_exact(),
             group_id: self.group_id().to_string(),
             group_id_exact: self.group_id_exact(),
+            advanced: self.advanced().to_vec(),
+            audio: self.audio(),
         }
         .build()?;
 
@@ -1037,606 +1743,3131 @@ impl Deref for JSCameraConstraints {
     }
 }
 
-/// A wrapper around a [`MediaStream`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStream.html)
-pub struct JSCamera {
-    media_stream: MediaStream,
-    constraints: JSCameraConstraints,
-    attached: bool,
-    attached_node: Option<Node>,
+/// A `{ min, max, step }` range of a [`JSCameraCapabilities`] value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JSCameraCapabilityRange {
+    min: f64,
+    max: f64,
+    step: Option<f64>,
 }
 
-impl JSCamera {
-    /// Creates a new [`JSCamera`] using [`JSCameraConstraints`].
-    ///
-    /// # Errors
-    /// This may error if permission is not granted, or the constraints are invalid.
-    pub async fn new(constraints: JSCameraConstraints) -> Result<Self, NokhwaError> {
-        let window: Window = window()?;
-        let navigator = window.navigator();
-        let media_devices = media_devices(&navigator)?;
+impl JSCameraCapabilityRange {
+    /// The lowest value.
+    #[must_use]
+    pub fn min(&self) -> f64 {
+        self.min
+    }
 
-        let stream: MediaStream = match media_devices.get_user_media_with_constraints(&*constraints)
-        {
-            Ok(promise) => {
-                let future = JsFuture::from(promise);
-                match future.await {
-                    Ok(stream) => {
-                        let media_stream: MediaStream = MediaStream::from(stream);
-                        media_stream
-                    }
-                    Err(why) => {
-                        return Err(NokhwaError::StructureError {
-                            structure: "MediaDevicesGetUserMediaJsFuture".to_string(),
-                            error: format!("{:?}", why),
-                        })
-                    }
-                }
-            }
-            Err(why) => {
-                return Err(NokhwaError::StructureError {
-                    structure: "MediaDevicesGetUserMedia".to_string(),
-                    error: format!("{:?}", why),
-                })
-            }
-        };
+    /// The highest value.
+    #[must_use]
+    pub fn max(&self) -> f64 {
+        self.max
+    }
 
-        Ok(JSCamera {
-            media_stream: stream,
-            constraints,
-            attached: false,
-            attached_node: None,
-        })
+    /// The step between values, if the browser reports one.
+    #[must_use]
+    pub fn step(&self) -> Option<f64> {
+        self.step
     }
+}
 
-    /// Gets the internal [`Resolution`]
+/// What the video track of a [`JSCamera`] can do, from [`MediaStreamTrack.getCapabilities()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/getCapabilities). See [`JSCamera::capabilities()`].
+///
+/// Anything the browser does not report is `None` (or empty). Firefox reports no capabilities at all, and `zoom`, `torch` and the exposure and focus ranges are usually only reported on mobile devices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JSCameraCapabilities {
+    width: Option<JSCameraCapabilityRange>,
+    height: Option<JSCameraCapabilityRange>,
+    aspect_ratio: Option<JSCameraCapabilityRange>,
+    frame_rate: Option<JSCameraCapabilityRange>,
+    facing_modes: Vec<JSCameraFacingMode>,
+    resize_modes: Vec<JSCameraResizeMode>,
+    zoom: Option<JSCameraCapabilityRange>,
+    torch: bool,
+    focus_modes: Vec<JSCameraFocusMode>,
+    exposure_time: Option<JSCameraCapabilityRange>,
+    exposure_compensation: Option<JSCameraCapabilityRange>,
+    focus_distance: Option<JSCameraCapabilityRange>,
+    color_temperature: Option<JSCameraCapabilityRange>,
+    device_id: Option<String>,
+    group_id: Option<String>,
+}
+
+impl JSCameraCapabilities {
+    /// The supported widths in pixels.
     #[must_use]
-    pub fn preferred_resolution(&self) -> Resolution {
-        self.constraints.preferred_resolution
+    pub fn width(&self) -> Option<JSCameraCapabilityRange> {
+        self.width
     }
 
-    /// Sets the internal [`Resolution`]
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_preferred_resolution(&mut self, preferred_resolution: Resolution) {
-        self.constraints.preferred_resolution = preferred_resolution;
+    /// The supported heights in pixels.
+    #[must_use]
+    pub fn height(&self) -> Option<JSCameraCapabilityRange> {
+        self.height
     }
 
-    /// Gets the internal resolution exact.
+    /// The supported aspect ratios.
     #[must_use]
-    pub fn resolution_exact(&self) -> bool {
-        self.constraints.resolution_exact
+    pub fn aspect_ratio(&self) -> Option<JSCameraCapabilityRange> {
+        self.aspect_ratio
     }
 
-    /// Sets the internal resolution exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_resolution_exact(&mut self, resolution_exact: bool) {
-        self.constraints.resolution_exact = resolution_exact;
+    /// The supported frame rates.
+    #[must_use]
+    pub fn frame_rate(&self) -> Option<JSCameraCapabilityRange> {
+        self.frame_rate
     }
 
-    /// Gets the internal aspect ratio.
+    /// The facing modes the camera can be in.
     #[must_use]
-    pub fn aspect_ratio(&self) -> f64 {
-        self.constraints.aspect_ratio
+    pub fn facing_modes(&self) -> &[JSCameraFacingMode] {
+        &self.facing_modes
     }
 
-    /// Sets the internal aspect ratio.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) {
-        self.constraints.aspect_ratio = aspect_ratio;
+    /// The resize modes the browser supports for this camera.
+    #[must_use]
+    pub fn resize_modes(&self) -> &[JSCameraResizeMode] {
+        &self.resize_modes
     }
 
-    /// Gets the internal aspect ratio exact.
+    /// The supported zoom factors.
     #[must_use]
-    pub fn aspect_ratio_exact(&self) -> bool {
-        self.constraints.aspect_ratio_exact
+    pub fn zoom(&self) -> Option<JSCameraCapabilityRange> {
+        self.zoom
     }
 
-    /// Sets the internal aspect ratio exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_aspect_ratio_exact(&mut self, aspect_ratio_exact: bool) {
-        self.constraints.aspect_ratio_exact = aspect_ratio_exact;
+    /// Whether the camera has a torch (flash light).
+    #[must_use]
+    pub fn torch(&self) -> bool {
+        self.torch
     }
 
-    /// Gets the internal [`JSCameraFacingMode`].
+    /// The focus modes the camera supports.
     #[must_use]
-    pub fn facing_mode(&self) -> JSCameraFacingMode {
-        self.constraints.facing_mode
+    pub fn focus_modes(&self) -> &[JSCameraFocusMode] {
+        &self.focus_modes
     }
 
-    /// Sets the internal [`JSCameraFacingMode`]
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_facing_mode(&mut self, facing_mode: JSCameraFacingMode) {
-        self.constraints.facing_mode = facing_mode;
+    /// The supported exposure times, in 100 microsecond units.
+    #[must_use]
+    pub fn exposure_time(&self) -> Option<JSCameraCapabilityRange> {
+        self.exposure_time
     }
 
-    /// Gets the internal facing mode exact.
+    /// The supported exposure compensations, in f-stops.
     #[must_use]
-    pub fn facing_mode_exact(&self) -> bool {
-        self.constraints.facing_mode_exact
+    pub fn exposure_compensation(&self) -> Option<JSCameraCapabilityRange> {
+        self.exposure_compensation
     }
 
-    /// Sets the internal facing mode exact
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_facing_mode_exact(&mut self, facing_mode_exact: bool) {
-        self.constraints.facing_mode_exact = facing_mode_exact;
+    /// The supported focus distances, in meters.
+    #[must_use]
+    pub fn focus_distance(&self) -> Option<JSCameraCapabilityRange> {
+        self.focus_distance
     }
 
-    /// Gets the internal frame rate.
+    /// The supported color temperatures, in kelvin.
     #[must_use]
-    pub fn frame_rate(&self) -> u32 {
-        self.constraints.frame_rate
+    pub fn color_temperature(&self) -> Option<JSCameraCapabilityRange> {
+        self.color_temperature
     }
 
-    /// Sets the internal frame rate
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_frame_rate(&mut self, frame_rate: u32) {
-        self.constraints.frame_rate = frame_rate;
+    /// The device ID of the camera.
+    #[must_use]
+    pub fn device_id(&self) -> Option<&String> {
+        self.device_id.as_ref()
     }
 
-    /// Gets the internal frame rate exact.
+    /// The group ID of the camera.
     #[must_use]
-    pub fn frame_rate_exact(&self) -> bool {
-        self.constraints.frame_rate_exact
+    pub fn group_id(&self) -> Option<&String> {
+        self.group_id.as_ref()
     }
 
-    /// Sets the internal frame rate exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_frame_rate_exact(&mut self, frame_rate_exact: bool) {
-        self.constraints.frame_rate_exact = frame_rate_exact;
+    fn from_js(capabilities: &JsValue) -> Self {
+        JSCameraCapabilities {
+            width: js_range(capabilities, "width"),
+            height: js_range(capabilities, "height"),
+            aspect_ratio: js_range(capabilities, "aspectRatio"),
+            frame_rate: js_range(capabilities, "frameRate"),
+            facing_modes: js_strings(capabilities, "facingMode")
+                .iter()
+                .filter_map(|mode| facing_mode_from(mode))
+                .collect(),
+            resize_modes: js_strings(capabilities, "resizeMode")
+                .iter()
+                .filter_map(|mode| resize_mode_from(mode))
+                .collect(),
+            zoom: js_range(capabilities, "zoom"),
+            torch: js_bool(capabilities, "torch").unwrap_or(false),
+            focus_modes: js_strings(capabilities, "focusMode")
+                .iter()
+                .filter_map(|mode| focus_mode_from(mode))
+                .collect(),
+            exposure_time: js_range(capabilities, "exposureTime"),
+            exposure_compensation: js_range(capabilities, "exposureCompensation"),
+            focus_distance: js_range(capabilities, "focusDistance"),
+            color_temperature: js_range(capabilities, "colorTemperature"),
+            device_id: js_string(capabilities, "deviceId"),
+            group_id: js_string(capabilities, "groupId"),
+        }
     }
+}
 
-    /// Gets the internal [`JSCameraResizeMode`].
+/// What the video track of a [`JSCamera`] is actually doing, from [`MediaStreamTrack.getSettings()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/getSettings). See [`JSCamera::settings()`].
+///
+/// These are the values the browser negotiated, which may differ from the requested constraints. Anything the browser does not report is `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JSCameraSettings {
+    resolution: Option<Resolution>,
+    aspect_ratio: Option<f64>,
+    frame_rate: Option<f64>,
+    facing_mode: Option<JSCameraFacingMode>,
+    resize_mode: Option<JSCameraResizeMode>,
+    zoom: Option<f64>,
+    torch: Option<bool>,
+    focus_mode: Option<JSCameraFocusMode>,
+    exposure_time: Option<f64>,
+    exposure_compensation: Option<f64>,
+    focus_distance: Option<f64>,
+    color_temperature: Option<f64>,
+    device_id: Option<String>,
+    group_id: Option<String>,
+}
+
+impl JSCameraSettings {
+    /// The resolution of the stream.
     #[must_use]
-    pub fn resize_mode(&self) -> JSCameraResizeMode {
-        self.constraints.resize_mode
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
     }
 
-    /// Sets the internal [`JSCameraResizeMode`]
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_resize_mode(&mut self, resize_mode: JSCameraResizeMode) {
-        self.constraints.resize_mode = resize_mode;
+    /// The aspect ratio of the stream.
+    #[must_use]
+    pub fn aspect_ratio(&self) -> Option<f64> {
+        self.aspect_ratio
     }
 
-    /// Gets the internal resize mode exact.
+    /// The frame rate of the stream. Browsers may report fractional rates.
     #[must_use]
-    pub fn resize_mode_exact(&self) -> bool {
-        self.constraints.resize_mode_exact
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.frame_rate
     }
 
-    /// Sets the internal resize mode exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_resize_mode_exact(&mut self, resize_mode_exact: bool) {
-        self.constraints.resize_mode_exact = resize_mode_exact;
+    /// The facing mode of the camera.
+    #[must_use]
+    pub fn facing_mode(&self) -> Option<JSCameraFacingMode> {
+        self.facing_mode
     }
 
-    /// Gets the internal device id.
+    /// The resize mode the browser uses.
     #[must_use]
-    pub fn device_id(&self) -> &str {
-        &self.constraints.device_id
+    pub fn resize_mode(&self) -> Option<JSCameraResizeMode> {
+        self.resize_mode
     }
 
-    /// Sets the internal device ID.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_device_id(&mut self, device_id: String) {
-        self.constraints.device_id = device_id;
+    /// The zoom factor.
+    #[must_use]
+    pub fn zoom(&self) -> Option<f64> {
+        self.zoom
     }
 
-    /// Gets the internal device id exact.
+    /// Whether the torch (flash light) is on.
     #[must_use]
-    pub fn device_id_exact(&self) -> bool {
-        self.constraints.device_id_exact
+    pub fn torch(&self) -> Option<bool> {
+        self.torch
     }
 
-    /// Sets the internal device ID exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_device_id_exact(&mut self, device_id_exact: bool) {
-        self.constraints.device_id_exact = device_id_exact;
+    /// The focus mode of the camera.
+    #[must_use]
+    pub fn focus_mode(&self) -> Option<JSCameraFocusMode> {
+        self.focus_mode
     }
 
-    /// Gets the internal group id.
+    /// The exposure time, in 100 microsecond units.
     #[must_use]
-    pub fn group_id(&self) -> &str {
-        &self.constraints.group_id
+    pub fn exposure_time(&self) -> Option<f64> {
+        self.exposure_time
     }
 
-    /// Sets the internal group ID.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_group_id(&mut self, group_id: String) {
-        self.constraints.group_id = group_id;
+    /// The exposure compensation, in f-stops.
+    #[must_use]
+    pub fn exposure_compensation(&self) -> Option<f64> {
+        self.exposure_compensation
     }
 
-    /// Gets the internal group id exact.
+    /// The focus distance, in meters.
     #[must_use]
-    pub fn group_id_exact(&self) -> bool {
-        self.constraints.group_id_exact
+    pub fn focus_distance(&self) -> Option<f64> {
+        self.focus_distance
     }
 
-    /// Sets the internal group ID exact.
-    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
-    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
-    pub fn set_group_id_exact(&mut self, group_id_exact: bool) {
-        self.constraints.group_id_exact = group_id_exact;
+    /// The color temperature, in kelvin.
+    #[must_use]
+    pub fn color_temperature(&self) -> Option<f64> {
+        self.color_temperature
     }
 
+    /// The device ID of the camera.
     #[must_use]
-    pub fn is_attached(&self) -> bool {
-        self.attached
+    pub fn device_id(&self) -> Option<&String> {
+        self.device_id.as_ref()
     }
 
+    /// The group ID of the camera.
     #[must_use]
-    pub fn media_stream(&self) -> &MediaStream {
-        &self.media_stream
+    pub fn group_id(&self) -> Option<&String> {
+        self.group_id.as_ref()
     }
 
-    /// Applies any modified constraints.
-    /// # Security
-    /// WARNING: This function uses [`Function`](https://docs.rs/js-sys/0.3.52/js_sys/struct.Function.html) and if the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are invalid/contain malicious JS, it will run without restraint. Please take care as to make sure the [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) and the [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId)
-    /// fields are not malicious! (This usually boils down to not letting users input data directly)
-    ///
-    /// # Errors
-    /// This function may return an error on an invalid string in [`device_id`](crate::js_camera::JSCameraConstraintsBuilder::device_id) or [`groupId`](https://developer.mozilla.org/en-US/docs/Web/API/MediaTrackConstraints/groupId) or if the
-    /// Javascript Function fails to run.
-    pub fn apply_constraints(&mut self) -> Result<(), NokhwaError> {
-        let new_constraints = JSCameraConstraintsBuilder {
-            preferred_resolution: self.preferred_resolution(),
-            resolution_exact: self.resolution_exact(),
-            aspect_ratio: self.aspect_ratio(),
-            aspect_ratio_exact: self.aspect_ratio_exact(),
-            facing_mode: self.facing_mode(),
-            facing_mode_exact: self.facing_mode_exact(),
-            frame_rate: self.frame_rate(),
-            frame_rate_exact: self.frame_rate_exact(),
-            resize_mode: self.resize_mode(),
-            resize_mode_exact: self.resize_mode_exact(),
-            device_id: self.device_id().to_string(),
-            device_id_exact: self.device_id_exact(),
-            group_id: self.group_id().to_string(),
-            group_id_exact: self.group_id_exact(),
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_js(settings: &JsValue) -> Self {
+        let resolution = match (js_number(settings, "width"), js_number(settings, "height")) {
+            (Some(width), Some(height)) => Some(Resolution::new(width as u32, height as u32)),
+            _ => None,
+        };
+        JSCameraSettings {
+            resolution,
+            aspect_ratio: js_number(settings, "aspectRatio"),
+            frame_rate: js_number(settings, "frameRate"),
+            facing_mode: js_string(settings, "facingMode")
+                .as_deref()
+                .and_then(facing_mode_from),
+            resize_mode: js_string(settings, "resizeMode")
+                .as_deref()
+                .and_then(resize_mode_from),
+            zoom: js_number(settings, "zoom"),
+            torch: js_bool(settings, "torch"),
+            focus_mode: js_string(settings, "focusMode")
+                .as_deref()
+                .and_then(focus_mode_from),
+            exposure_time: js_number(settings, "exposureTime"),
+            exposure_compensation: js_number(settings, "exposureCompensation"),
+            focus_distance: js_number(settings, "focusDistance"),
+            color_temperature: js_number(settings, "colorTemperature"),
+            device_id: js_string(settings, "deviceId"),
+            group_id: js_string(settings, "groupId"),
         }
-        .build()?;
-
-        self.constraints.media_constraints = new_constraints.media_constraints;
-        Ok(())
     }
+}
 
-    /// Attaches camera to a `element`(by-id).
-    /// - `generate_new`: Whether to add a video element to provided element to attach to. Set this to `false` if the `element` ID you are passing is already a `<video>` element.
-    /// # Errors
-    /// If the camera fails to attach, fails to generate the video element, or a cast fails, this will error.
-    pub fn attach(&mut self, element: &str, generate_new: bool) -> Result<(), NokhwaError> {
-        let window: Window = window()?;
-        let document: Document = document(&window)?;
-
-        let selected_element: Element = document_select_elem(&document, element)?;
-
-        if generate_new {
-            let video_element = create_element(&document, "video")?;
+fn js_value(object: &JsValue, name: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+}
 
-            set_autoplay_inline(&video_element)?;
+fn js_number(object: &JsValue, name: &str) -> Option<f64> {
+    js_value(object, name).as_f64()
+}
 
-            let video_element: HtmlVideoElement =
-                element_cast::<Element, HtmlVideoElement>(video_element, "HtmlVideoElement")?;
+fn js_bool(object: &JsValue, name: &str) -> Option<bool> {
+    js_value(object, name).as_bool()
+}
 
-            video_element.set_width(self.preferred_resolution().width());
-            video_element.set_width(self.preferred_resolution().height());
-            video_element.set_src_object(Some(self.media_stream()));
+fn js_string(object: &JsValue, name: &str) -> Option<String> {
+    js_value(object, name).as_string()
+}
 
-            return match selected_element.append_child(&Node::from(video_element)) {
-                Ok(n) => {
-                    self.attached_node = Some(n);
-                    self.attached = true;
-                    Ok(())
-                }
-                Err(why) => Err(NokhwaError::StructureError {
-                    structure: "Attach Error".to_string(),
-                    error: format!("{:?}", why),
-                }),
-            };
-        }
+fn js_strings(object: &JsValue, name: &str) -> Vec<String> {
+    let value = js_value(object, name);
+    if !Array::is_array(&value) {
+        return vec![];
+    }
+    Array::from(&value)
+        .iter()
+        .filter_map(|value| value.as_string())
+        .collect()
+}
 
-        set_autoplay_inline(&selected_element)?;
+fn js_range(object: &JsValue, name: &str) -> Option<JSCameraCapabilityRange> {
+    let range = js_value(object, name);
+    if !range.is_object() {
+        return None;
+    }
+    Some(JSCameraCapabilityRange {
+        min: js_number(&range, "min")?,
+        max: js_number(&range, "max")?,
+        step: js_number(&range, "step"),
+    })
+}
 
-        let selected_element =
-            element_cast::<Element, HtmlVideoElement>(selected_element, "HtmlVideoElement")?;
+fn facing_mode_from(mode: &str) -> Option<JSCameraFacingMode> {
+    match mode {
+        "user" => Some(JSCameraFacingMode::User),
+        "environment" => Some(JSCameraFacingMode::Environment),
+        "left" => Some(JSCameraFacingMode::Left),
+        "right" => Some(JSCameraFacingMode::Right),
+        _ => None,
+    }
+}
 
-        selected_element.set_width(self.preferred_resolution().width());
-        selected_element.set_width(self.preferred_resolution().height());
-        selected_element.set_src_object(Some(self.media_stream()));
+fn resize_mode_from(mode: &str) -> Option<JSCameraResizeMode> {
+    match mode {
+        "none" => Some(JSCameraResizeMode::None),
+        "crop-and-scale" => Some(JSCameraResizeMode::CropAndScale),
+        _ => None,
+    }
+}
 
-        self.attached_node = Some(Node::from(selected_element));
-        self.attached = true;
-        Ok(())
+fn focus_mode_from(mode: &str) -> Option<JSCameraFocusMode> {
+    match mode {
+        "none" => Some(JSCameraFocusMode::None),
+        "manual" => Some(JSCameraFocusMode::Manual),
+        "single-shot" => Some(JSCameraFocusMode::SingleShot),
+        "continuous" => Some(JSCameraFocusMode::Continuous),
+        _ => None,
     }
+}
 
-    /// # Errors
-    pub fn de_attach(&mut self) -> Result<(), NokhwaError> {
-        if !self.attached {
-            return Ok(());
-        }
+/// Whether the flash fires for a photo taken with [`JSCamera::take_photo()`].
+/// - `Auto`: Let the camera decide.
+/// - `Off`: Never fire the flash.
+/// - `Flash`: Always fire the flash.
+/// See More: [`fillLightMode`](https://w3c.github.io/mediacapture-image/#dom-photosettings-filllightmode)
+#[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub enum JSCameraFillLightMode {
+    Auto,
+    Off,
+    Flash,
+}
 
-        let attached: &Node = match &self.attached_node {
-            Some(node) => node,
-            None => return Ok(()),
+impl Display for JSCameraFillLightMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cap = match self {
+            JSCameraFillLightMode::Auto => "auto",
+            JSCameraFillLightMode::Off => "off",
+            JSCameraFillLightMode::Flash => "flash",
         };
+        write!(f, "{}", cap)
+    }
+}
 
-        let attached = element_cast_ref::<Node, HtmlVideoElement>(attached, "HtmlVideoElement")?;
+impl Debug for JSCameraFillLightMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = self.to_string();
+        write!(f, "{}", str)
+    }
+}
 
-        attached.set_src_object(None);
-        self.attached_node = None;
-        self.attached = false;
+/// The [`PhotoSettings`](https://w3c.github.io/mediacapture-image/#photosettings-section) of a photo taken with [`JSCamera::take_photo()`].
+///
+/// Unset values (`None`) are left to the browser. Browsers that do not support a setting reject the photo, so only set what [`JSCamera::capabilities()`] suggests the camera has.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraPhotoSettings {
+    pub(crate) resolution: Option<Resolution>,
+    pub(crate) fill_light_mode: Option<JSCameraFillLightMode>,
+    pub(crate) red_eye_reduction: Option<bool>,
+}
 
-        Ok(())
+impl JSCameraPhotoSettings {
+    /// Constructs an empty [`JSCameraPhotoSettings`].
+    #[must_use]
+    pub fn new() -> Self {
+        JSCameraPhotoSettings::default()
     }
 
-    /// Creates an off-screen canvas and a `<video>` element (if not already attached) and returns a raw `Cow<[u8]>` RGBA frame.
-    /// # Errors
-    /// If a cast fails, the camera fails to attach, the currently attached node is invalid, or writing/reading from the canvas fails, this will error.
-    pub fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
-        let window: Window = window()?;
-        let document: Document = document(&window)?;
-        let canvas = create_element(&document, "canvas")?;
-        let canvas = element_cast::<Element, HtmlCanvasElement>(canvas, "HtmlCanvasElement")?;
+    /// Sets [`imageWidth`](https://w3c.github.io/mediacapture-image/#dom-photosettings-imagewidth) and [`imageHeight`](https://w3c.github.io/mediacapture-image/#dom-photosettings-imageheight). This may be higher than the resolution of the video stream.
+    #[must_use]
+    pub fn resolution(mut self, resolution: Resolution) -> JSCameraPhotoSettings {
+        self.resolution = Some(resolution);
+        self
+    }
 
-        canvas.set_height(self.preferred_resolution().height());
-        canvas.set_width(self.preferred_resolution().width());
+    /// Sets [`fillLightMode`](https://w3c.github.io/mediacapture-image/#dom-photosettings-filllightmode).
+    #[must_use]
+    pub fn fill_light_mode(mut self, mode: JSCameraFillLightMode) -> JSCameraPhotoSettings {
+        self.fill_light_mode = Some(mode);
+        self
+    }
 
-        let context = match canvas.get_context("2d") {
-            Ok(maybe_ctx) => match maybe_ctx {
-                Some(ctx) => element_cast::<Object, CanvasRenderingContext2d>(
-                    ctx,
-                    "CanvasRenderingContext2d",
-                )?,
-                None => {
+    /// Sets [`redEyeReduction`](https://w3c.github.io/mediacapture-image/#dom-photosettings-redeyereduction).
+    #[must_use]
+    pub fn red_eye_reduction(mut self, value: bool) -> JSCameraPhotoSettings {
+        self.red_eye_reduction = Some(value);
+        self
+    }
+
+    fn to_object(&self) -> Result<Object, NokhwaError> {
+        let settings = Object::new();
+        let values = [
+            (
+                "imageWidth",
+                self.resolution.map(|res| JsValue::from(res.width_x)),
+            ),
+            (
+                "imageHeight",
+                self.resolution.map(|res| JsValue::from(res.height_y)),
+            ),
+            (
+                "fillLightMode",
+                self.fill_light_mode
+                    .map(|mode| JsValue::from_str(&mode.to_string())),
+            ),
+            ("redEyeReduction", self.red_eye_reduction.map(JsValue::from)),
+        ];
+        for (name, value) in values.iter() {
+            if let Some(value) = value {
+                if let Err(why) = Reflect::set(&settings, &JsValue::from_str(name), value) {
                     return Err(NokhwaError::StructureError {
-                        structure: "HtmlCanvasElement Context 2D".to_string(),
-                        error: "None".to_string(),
+                        structure: "PhotoSettings".to_string(),
+                        error: format!("{:?}", why),
                     });
                 }
-            },
-            Err(why) => {
-                return Err(NokhwaError::StructureError {
-                    structure: "HtmlCanvasElement Context 2D".to_string(),
-                    error: format!("{:?}", why),
-                });
             }
-        };
+        }
+        Ok(settings)
+    }
+}
 
-        if self.attached && self.attached_node.is_some() {
-            let video_element = match &self.attached_node {
-                Some(n) => element_cast_ref::<Node, HtmlVideoElement>(n, "HtmlVideoElement")?,
-                None => {
-                    // this shouldn't happen
-                    return Err(NokhwaError::StructureError {
-                        structure: "Document Attached Video Element".to_string(),
-                        error: "None".to_string(),
-                    });
-                }
-            };
+// `web_sys::ImageCapture` only has the old event based API, so this calls the promise based one through `Reflect`
+fn image_capture(track: &MediaStreamTrack) -> Result<JsValue, NokhwaError> {
+    construct_global("ImageCapture", &Array::of1(track))
+}
 
-            video_element.set_width(self.preferred_resolution().width());
-            video_element.set_width(self.preferred_resolution().height());
-            video_element.set_src_object(Some(self.media_stream()));
+// `new globalThis[name](...args)`, for the APIs `web_sys` does not have (yet)
+fn construct_global(name: &str, args: &Array) -> Result<JsValue, NokhwaError> {
+    let constructor = match Reflect::get(&js_sys::global(), &JsValue::from_str(name)) {
+        Ok(constructor) if constructor.is_function() => Function::unchecked_from_js(constructor),
+        _ => {
+            return Err(NokhwaError::StructureError {
+                structure: name.to_string(),
+                error: format!("The {} API is not supported", name),
+            })
+        }
+    };
+    match Reflect::construct(&constructor, args) {
+        Ok(object) => Ok(object),
+        Err(why) => Err(NokhwaError::StructureError {
+            structure: name.to_string(),
+            error: format!("{:?}", why),
+        }),
+    }
+}
 
-            if let Err(why) = context.draw_image_with_html_video_element_and_dw_and_dh(
-                video_element,
-                0_f64,
-                0_f64,
-                self.preferred_resolution().width().into(),
-                self.preferred_resolution().height().into(),
-            ) {
-                return Err(NokhwaError::ReadFrameError(format!("{:?}", why)));
-            }
-        } else {
-            let video_element = match document.create_element("video") {
-                Ok(new_element) => new_element,
-                Err(why) => {
-                    return Err(NokhwaError::StructureError {
-                        structure: "Document Video Element".to_string(),
-                        error: format!("{:?}", why.as_string()),
-                    })
-                }
-            };
+// calls `object[method](...args)` and waits for the promise it returns
+async fn promise_call(
+    object: &JsValue,
+    structure: &str,
+    method: &str,
+    args: &Array,
+) -> Result<JsValue, NokhwaError> {
+    let property = format!("{}.{}", structure, method);
+    let function = match Reflect::get(object, &JsValue::from_str(method)) {
+        Ok(function) if function.is_function() => Function::unchecked_from_js(function),
+        _ => {
+            return Err(NokhwaError::GetPropertyError {
+                property,
+                error: "Not supported".to_string(),
+            })
+        }
+    };
+    let promise = match function.apply(object, args) {
+        Ok(promise) => Promise::unchecked_from_js(promise),
+        Err(why) => {
+            return Err(NokhwaError::GetPropertyError {
+                property,
+                error: format!("{:?}", why),
+            })
+        }
+    };
+    match JsFuture::from(promise).await {
+        Ok(value) => Ok(value),
+        Err(why) => Err(NokhwaError::ReadFrameError(format!(
+            "{} failed: {:?}",
+            property, why
+        ))),
+    }
+}
 
-            set_autoplay_inline(&video_element)?;
+async fn take_track_photo(track: MediaStreamTrack, settings: Object) -> Result<Blob, NokhwaError> {
+    let image_capture = image_capture(&track)?;
+    let photo = promise_call(
+        &image_capture,
+        "ImageCapture",
+        "takePhoto",
+        &Array::of1(&settings),
+    )
+    .await?;
+    Ok(Blob::unchecked_from_js(photo))
+}
 
-            let video_element: HtmlVideoElement =
-                element_cast::<Element, HtmlVideoElement>(video_element, "HtmlVideoElement")?;
+async fn blob_bytes(blob: &Blob) -> Result<Vec<u8>, NokhwaError> {
+    match JsFuture::from(blob.array_buffer()).await {
+        Ok(buffer) => Ok(Uint8Array::new(&buffer).to_vec()),
+        Err(why) => Err(NokhwaError::ReadFrameError(format!(
+            "Blob.arrayBuffer failed: {:?}",
+            why
+        ))),
+    }
+}
 
-            video_element.set_width(self.preferred_resolution().width());
-            video_element.set_width(self.preferred_resolution().height());
-            video_element.set_src_object(Some(self.media_stream()));
+/// Grabs a frame from a video `track` with [`ImageCapture.grabFrame()`](https://developer.mozilla.org/en-US/docs/Web/API/ImageCapture/grabFrame), drawing it on an [`OffscreenCanvas`](https://developer.mozilla.org/en-US/docs/Web/API/OffscreenCanvas).
+///
+/// Unlike [`JSCamera::frame_raw()`], this never touches the DOM, so it also works in a Web Worker the track was transferred to. The frame has the resolution of the track.
+/// # Errors
+/// If the browser does not support the `ImageCapture` API or `OffscreenCanvas`, or the frame cannot be grabbed or read, this will error.
+pub async fn grab_track_frame(
+    track: &MediaStreamTrack,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, NokhwaError> {
+    let image_data = grab_image_data(track).await?;
+    match ImageBuffer::from_vec(image_data.width(), image_data.height(), image_data.data().0) {
+        Some(frame) => Ok(frame),
+        None => Err(NokhwaError::ReadFrameError(
+            "ImageBuffer is not large enough! This is probably a bug, please report it!"
+                .to_string(),
+        )),
+    }
+}
 
-            if let Err(why) = context.draw_image_with_html_video_element_and_dw_and_dh(
-                &video_element,
-                0_f64,
-                0_f64,
-                self.preferred_resolution().width().into(),
-                self.preferred_resolution().height().into(),
-            ) {
-                return Err(NokhwaError::ReadFrameError(format!("{:?}", why)));
-            }
+async fn grab_image_data(track: &MediaStreamTrack) -> Result<ImageData, NokhwaError> {
+    let image_capture = image_capture(track)?;
+    let bitmap = ImageBitmap::unchecked_from_js(
+        promise_call(&image_capture, "ImageCapture", "grabFrame", &Array::new()).await?,
+    );
+    let image_data = bitmap_image_data(&bitmap);
+    bitmap.close();
+    image_data
+}
+
+// `web_sys` has no 2D context type for `OffscreenCanvas`, so it is drawn on through `Reflect`
+fn bitmap_image_data(bitmap: &ImageBitmap) -> Result<ImageData, NokhwaError> {
+    let (width, height) = (bitmap.width(), bitmap.height());
+    let canvas = match OffscreenCanvas::new(width, height) {
+        Ok(canvas) => canvas,
+        Err(why) => {
+            return Err(NokhwaError::StructureError {
+                structure: "OffscreenCanvas".to_string(),
+                error: format!("{:?}", why),
+            })
+        }
+    };
+    let context = match canvas.get_context("2d") {
+        Ok(Some(context)) => context,
+        Ok(None) => {
+            return Err(NokhwaError::StructureError {
+                structure: "OffscreenCanvas Context 2D".to_string(),
+                error: "None".to_string(),
+            })
+        }
+        Err(why) => {
+            return Err(NokhwaError::StructureError {
+                structure: "OffscreenCanvas Context 2D".to_string(),
+                error: format!("{:?}", why),
+            })
         }
+    };
+    context_call(
+        &context,
+        "drawImage",
+        &Array::of3(bitmap, &JsValue::from(0), &JsValue::from(0)),
+    )?;
+    let image_data = context_call(
+        &context,
+        "getImageData",
+        &Array::of4(
+            &JsValue::from(0),
+            &JsValue::from(0),
+            &JsValue::from(width),
+            &JsValue::from(height),
+        ),
+    )?;
+    Ok(ImageData::unchecked_from_js(image_data))
+}
 
-        let image_data = match context.get_image_data(
-            0_f64,
-            0_f64,
-            self.preferred_resolution().width().into(),
-            self.preferred_resolution().height().into(),
-        ) {
-            Ok(data) => data.data().0,
-            Err(why) => {
-                return Err(NokhwaError::ReadFrameError(format!("{:?}", why)));
-            }
-        };
+fn context_call(context: &Object, method: &str, args: &Array) -> Result<JsValue, NokhwaError> {
+    let function = match Reflect::get(context, &JsValue::from_str(method)) {
+        Ok(function) if function.is_function() => Function::unchecked_from_js(function),
+        _ => {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "OffscreenCanvasRenderingContext2D.{} is not supported",
+                method
+            )))
+        }
+    };
+    match function.apply(context, args) {
+        Ok(value) => Ok(value),
+        Err(why) => Err(NokhwaError::ReadFrameError(format!("{:?}", why))),
+    }
+}
+
+/// A stream of [WebCodecs `VideoFrame`s](https://developer.mozilla.org/en-US/docs/Web/API/VideoFrame) from the video track of a [`JSCamera`], read with a [`MediaStreamTrackProcessor`](https://w3c.github.io/mediacapture-transform/#track-processor). See [`JSCamera::video_frame_stream()`].
+///
+/// The frames are handed over without copying their pixels, so they can go straight to a `VideoEncoder` or WebGPU's `importExternalTexture()`.
+/// `web_sys` has no `VideoFrame` type yet, so frames are plain [`Object`]s. Every frame must be `close()`d once it is used, or the camera stalls when the browser runs out of frames.
+///
+/// Dropping the stream cancels it.
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraVideoFrameStream {
+    reader: JsValue,
+}
 
-        Ok(Cow::from(image_data))
+impl JSCameraVideoFrameStream {
+    /// Creates a [`JSCameraVideoFrameStream`] reading from a video `track`.
+    /// # Errors
+    /// If the browser does not support `MediaStreamTrackProcessor` (anything but Chromium), this will error.
+    pub fn new(track: &MediaStreamTrack) -> Result<Self, NokhwaError> {
+        let init = Object::new();
+        if let Err(why) = Reflect::set(&init, &JsValue::from_str("track"), track) {
+            return Err(NokhwaError::StructureError {
+                structure: "MediaStreamTrackProcessorInit".to_string(),
+                error: format!("{:?}", why),
+            });
+        }
+        let processor = construct_global("MediaStreamTrackProcessor", &Array::of1(&init))?;
+        let readable = Reflect::get(&processor, &JsValue::from_str("readable"))
+            .ok()
+            .filter(JsValue::is_object);
+        let reader = readable.and_then(|readable| {
+            let get_reader = Reflect::get(&readable, &JsValue::from_str("getReader")).ok()?;
+            Function::unchecked_from_js(get_reader)
+                .call0(&readable)
+                .ok()
+        });
+        match reader {
+            Some(reader) => Ok(JSCameraVideoFrameStream { reader }),
+            None => Err(NokhwaError::StructureError {
+                structure: "MediaStreamTrackProcessor".to_string(),
+                error: "Could not get a reader for the readable stream".to_string(),
+            }),
+        }
     }
 
-    /// This takes the output from [`frame_raw()`](crate::JSCamera::frame_raw) and turns it into an `ImageBuffer<Rgb<u8>, Vec<u8>>`.
+    /// Waits for the next `VideoFrame`. This is `None` once the stream ends (e.g. the [`JSCamera`] was dropped).
     /// # Errors
-    /// This will error if the frame vec is too small(this is probably a bug, please report it!) or if the frame fails to capture. See [`frame_raw()`](crate::JSCamera::frame_raw).
-    pub fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        let raw_data = self.frame_raw()?.to_vec();
-        let resolution = self.preferred_resolution();
-        let image_buf =
-            match ImageBuffer::from_vec(resolution.width(), resolution.height(), raw_data) {
-                Some(buf) => {
-                    let rgba_buf: ImageBuffer<Rgba<u8>, Vec<u8>> = buf;
-                    let rgb_image_converted: ImageBuffer<Rgb<u8>, Vec<u8>> = rgba_buf.convert();
-                    rgb_image_converted
-                }
-                None => return Err(NokhwaError::ReadFrameError(
-                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
-                        .to_string(),
-                )),
-            };
-        Ok(image_buf)
+    /// If reading from the stream fails, this will error.
+    pub async fn next_frame(&self) -> Result<Option<Object>, NokhwaError> {
+        read_video_frame(&self.reader).await
     }
 
-    /// This takes the output from [`frame_raw()`](crate::JSCamera::frame_raw) and turns it into an `ImageBuffer<Rgba<u8>, Vec<u8>>`.
+    /// Cancels the stream, so the browser stops producing frames for it.
+    pub fn close(&self) {
+        if let Ok(cancel) = Reflect::get(&self.reader, &JsValue::from_str("cancel")) {
+            if cancel.is_function() {
+                let _ = Function::unchecked_from_js(cancel).call0(&self.reader);
+            }
+        }
+    }
+}
+
+impl Drop for JSCameraVideoFrameStream {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// reads `{ value, done }` from a `ReadableStreamDefaultReader` of `VideoFrame`s
+async fn read_video_frame(reader: &JsValue) -> Result<Option<Object>, NokhwaError> {
+    let result = promise_call(reader, "ReadableStreamDefaultReader", "read", &Array::new()).await?;
+    if js_bool(&result, "done").unwrap_or(false) {
+        return Ok(None);
+    }
+    let frame = js_value(&result, "value");
+    if frame.is_object() {
+        Ok(Some(Object::unchecked_from_js(frame)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether the browser can record `mime_type` (e.g. `"video/webm;codecs=vp9"` or `"video/mp4"`) with a [`JSCameraRecorder`], using [`MediaRecorder.isTypeSupported()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaRecorder/isTypeSupported).
+#[must_use]
+pub fn recorder_type_supported(mime_type: &str) -> bool {
+    MediaRecorder::is_type_supported(mime_type)
+}
+
+/// The options of a [`JSCameraRecorder`], see [`JSCamera::record()`].
+///
+/// Unset values (`None`) are left to the browser.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraRecorderOptions {
+    pub(crate) mime_type: Option<String>,
+    pub(crate) video_bits_per_second: Option<u32>,
+    pub(crate) audio_bits_per_second: Option<u32>,
+    pub(crate) timeslice: Option<Duration>,
+}
+
+impl JSCameraRecorderOptions {
+    /// Constructs empty [`JSCameraRecorderOptions`].
+    #[must_use]
+    pub fn new() -> Self {
+        JSCameraRecorderOptions::default()
+    }
+
+    /// Sets the container and codecs to record, e.g. `"video/webm;codecs=vp9"`. Check it with [`recorder_type_supported()`] first.
+    #[must_use]
+    pub fn mime_type<S: ToString>(mut self, mime_type: &S) -> JSCameraRecorderOptions {
+        self.mime_type = Some(mime_type.to_string());
+        self
+    }
+
+    /// Sets the video bitrate, in bits per second.
+    #[must_use]
+    pub fn video_bits_per_second(mut self, bits: u32) -> JSCameraRecorderOptions {
+        self.video_bits_per_second = Some(bits);
+        self
+    }
+
+    /// Sets the audio bitrate, in bits per second.
+    #[must_use]
+    pub fn audio_bits_per_second(mut self, bits: u32) -> JSCameraRecorderOptions {
+        self.audio_bits_per_second = Some(bits);
+        self
+    }
+
+    /// Sets how often a chunk is handed over. Without one, the whole recording is a single chunk once it is stopped.
+    #[must_use]
+    pub fn timeslice(mut self, timeslice: Duration) -> JSCameraRecorderOptions {
+        self.timeslice = Some(timeslice);
+        self
+    }
+}
+
+type ChunkCallback = Rc<RefCell<Option<Box<dyn FnMut(Blob)>>>>;
+
+/// Records the [`MediaStream`] of a [`JSCamera`] with the [`MediaRecorder`](https://developer.mozilla.org/en-US/docs/Web/API/MediaRecorder) API, so the browser encodes it (usually to WebM, or MP4 on Safari) instead of the frames going through WASM. See [`JSCamera::record()`].
+///
+/// The recorded chunks are collected until the recorder is [stopped](JSCameraRecorder::stop), or handed to the callback of [`on_chunk()`](JSCameraRecorder::on_chunk) as they come in.
+///
+/// Dropping the recorder stops it and discards what was not handed over yet.
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraRecorder {
+    recorder: MediaRecorder,
+    chunks: Rc<RefCell<Vec<Blob>>>,
+    callback: ChunkCallback,
+    // only kept so the `dataavailable` handler lives as long as the recorder
+    _listener: Closure<dyn FnMut(BlobEvent)>,
+}
+
+impl JSCameraRecorder {
+    /// Starts recording `stream`, see [`JSCamera::record()`].
     /// # Errors
-    /// This will error if the frame vec is too small(this is probably a bug, please report it!) or if the frame fails to capture. See [`frame_raw()`](crate::JSCamera::frame_raw).
-    pub fn rgba_frame(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, NokhwaError> {
-        let raw_data = self.frame_raw()?.to_vec();
-        let resolution = self.preferred_resolution();
-        let image_buf =
-            match ImageBuffer::from_vec(resolution.width(), resolution.height(), raw_data) {
-                Some(buf) => {
-                    let rgba_buf: ImageBuffer<Rgba<u8>, Vec<u8>> = buf;
-                    rgba_buf
+    /// If the browser does not support `MediaRecorder` or the [`mime_type`](JSCameraRecorderOptions::mime_type) ([`NokhwaError::UnsupportedFormat`]), or recording fails to start, this will error.
+    pub fn new(
+        stream: &MediaStream,
+        options: &JSCameraRecorderOptions,
+    ) -> Result<Self, NokhwaError> {
+        let mut recorder_options = MediaRecorderOptions::new();
+        if let Some(mime_type) = &options.mime_type {
+            recorder_options.mime_type(mime_type);
+        }
+        if let Some(bits) = options.video_bits_per_second {
+            recorder_options.video_bits_per_second(bits);
+        }
+        if let Some(bits) = options.audio_bits_per_second {
+            recorder_options.audio_bits_per_second(bits);
+        }
+
+        let recorder = match MediaRecorder::new_with_media_stream_and_media_recorder_options(
+            stream,
+            &recorder_options,
+        ) {
+            Ok(recorder) => recorder,
+            Err(why) => {
+                return Err(NokhwaError::UnsupportedFormat {
+                    format: options
+                        .mime_type
+                        .clone()
+                        .unwrap_or_else(|| "MediaRecorder".to_string()),
+                    error: format!("{:?}", why),
+                })
+            }
+        };
+
+        let chunks = Rc::new(RefCell::new(vec![]));
+        let callback: ChunkCallback = Rc::new(RefCell::new(None));
+        let listener = {
+            let chunks = chunks.clone();
+            let callback = callback.clone();
+            Closure::wrap(Box::new(move |event: BlobEvent| {
+                let chunk = match event.data() {
+                    // the last chunk may be empty
+                    Some(chunk) if chunk.size() > 0_f64 => chunk,
+                    _ => return,
+                };
+                match callback.borrow_mut().as_mut() {
+                    Some(callback) => callback(chunk),
+                    None => chunks.borrow_mut().push(chunk),
                 }
-                None => return Err(NokhwaError::ReadFrameError(
-                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
-                        .to_string(),
-                )),
-            };
-        Ok(image_buf)
+            }) as Box<dyn FnMut(BlobEvent)>)
+        };
+        recorder.set_ondataavailable(Some(listener.as_ref().unchecked_ref()));
+
+        let started = match options.timeslice {
+            Some(timeslice) => recorder
+                .start_with_time_slice(i32::try_from(timeslice.as_millis()).unwrap_or(i32::MAX)),
+            None => recorder.start(),
+        };
+        if let Err(why) = started {
+            return Err(NokhwaError::OpenStreamError(format!(
+                "MediaRecorder.start failed: {:?}",
+                why
+            )));
+        }
+
+        Ok(JSCameraRecorder {
+            recorder,
+            chunks,
+            callback,
+            _listener: listener,
+        })
     }
 
-    /// The minimum buffer size needed to write the current frame (RGB24). If `use_rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
+    /// Hands every chunk to `callback` as it comes in, instead of collecting it for [`stop()`](JSCameraRecorder::stop). Chunks collected before this is set stay collected.
+    pub fn on_chunk<F: FnMut(Blob) + 'static>(&self, callback: F) {
+        *self.callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// The MIME type the browser records, e.g. `"video/webm;codecs=vp8,opus"`.
     #[must_use]
-    pub fn min_buffer_size(&self, use_rgba: bool) -> usize {
-        let resolution = self.preferred_resolution();
-        if use_rgba {
-            (resolution.width() * resolution.height() * 4) as usize
-        } else {
-            (resolution.width() * resolution.height() * 3) as usize
+    pub fn mime_type(&self) -> String {
+        self.recorder.mime_type()
+    }
+
+    /// Whether the recorder is recording (or paused) and not stopped.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.state() != RecordingState::Inactive
+    }
+
+    /// Whether the recorder is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.recorder.state() == RecordingState::Paused
+    }
+
+    /// Pauses recording. Nothing is recorded until it is [resumed](JSCameraRecorder::resume).
+    /// # Errors
+    /// If the recorder was stopped, this will error.
+    pub fn pause(&self) -> Result<(), NokhwaError> {
+        self.recorder.pause().map_err(|why| {
+            NokhwaError::GeneralError(format!("MediaRecorder.pause failed: {:?}", why))
+        })
+    }
+
+    /// Resumes a paused recording.
+    /// # Errors
+    /// If the recorder was stopped, this will error.
+    pub fn resume(&self) -> Result<(), NokhwaError> {
+        self.recorder.resume().map_err(|why| {
+            NokhwaError::GeneralError(format!("MediaRecorder.resume failed: {:?}", why))
+        })
+    }
+
+    /// Stops recording and returns the collected chunks as a single [`Blob`] of the recorded [`mime_type()`](JSCameraRecorder::mime_type).
+    /// If an [`on_chunk()`](JSCameraRecorder::on_chunk) callback is set, the chunks went there instead and this is empty (or has only the ones from before it was set).
+    /// # Errors
+    /// If the recorder fails to stop, or the `Blob` cannot be made, this will error.
+    pub async fn stop(&self) -> Result<Blob, NokhwaError> {
+        stop_recorder(self.recorder.clone(), self.chunks.clone()).await
+    }
+}
+
+impl Drop for JSCameraRecorder {
+    fn drop(&mut self) {
+        if self.recorder.state() != RecordingState::Inactive {
+            let _ = self.recorder.stop();
         }
+        self.recorder.set_ondataavailable(None);
+        self.recorder.set_onstop(None);
     }
+}
 
-    /// Directly writes the current frame(RGB24) into said `buffer`. If `convert_rgba` is true, the buffer written will be written as an RGBA frame instead of a RGB frame. Returns the amount of bytes written on successful capture.
+// waits for the `stop` event, which comes after the last `dataavailable`
+async fn stop_recorder(
+    recorder: MediaRecorder,
+    chunks: Rc<RefCell<Vec<Blob>>>,
+) -> Result<Blob, NokhwaError> {
+    if recorder.state() != RecordingState::Inactive {
+        let stopped = Promise::new(&mut |resolve, _reject| {
+            recorder.set_onstop(Some(&resolve));
+        });
+        if let Err(why) = recorder.stop() {
+            return Err(NokhwaError::StreamShutdownError(format!(
+                "MediaRecorder.stop failed: {:?}",
+                why
+            )));
+        }
+        if let Err(why) = JsFuture::from(stopped).await {
+            return Err(NokhwaError::StreamShutdownError(format!("{:?}", why)));
+        }
+        recorder.set_onstop(None);
+    }
+
+    let parts: Array = chunks.borrow_mut().drain(..).collect();
+    let mut properties = BlobPropertyBag::new();
+    properties.type_(&recorder.mime_type());
+    match Blob::new_with_blob_sequence_and_options(&parts, &properties) {
+        Ok(blob) => Ok(blob),
+        Err(why) => Err(NokhwaError::StructureError {
+            structure: "Blob".to_string(),
+            error: format!("{:?}", why),
+        }),
+    }
+}
+
+// `Int32` words before the slots: the sequence of the last frame, width, height, slot count, slot size, and 3 reserved
+const RING_HEADER_WORDS: u32 = 8;
+const RING_SEQUENCE: u32 = 0;
+const RING_WIDTH: u32 = 1;
+const RING_HEIGHT: u32 = 2;
+const RING_SLOTS: u32 = 3;
+const RING_SLOT_SIZE: u32 = 4;
+// a slot's word while it is being written
+const RING_SLOT_WRITING: i32 = -1;
+
+/// A ring of RGBA frames in a [`SharedArrayBuffer`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/SharedArrayBuffer), so a [`JSCamera`] on the main thread can hand frames to a Web Worker without copying them through `postMessage()`.
+///
+/// Make the ring on the main thread, post its [`buffer()`](JSCameraFrameRing::buffer) to the worker once, and open it there with [`from_buffer()`](JSCameraFrameRing::from_buffer).
+/// The main thread then [writes](JSCamera::write_frame_to_ring) frames, and the worker [waits](JSCameraFrameRing::wait_frame) for and [reads](JSCameraFrameRing::read_frame_into) the latest one.
+///
+/// Each frame gets a sequence number, starting at 1. The writer never waits for the reader: if the reader is too slow, it skips frames (and a read that was overtaken by the writer errors, try again).
+/// # Quirks
+/// Browsers only have `SharedArrayBuffer` on [cross-origin isolated](https://developer.mozilla.org/en-US/docs/Web/API/crossOriginIsolated) pages, which need the `Cross-Origin-Opener-Policy: same-origin` and `Cross-Origin-Embedder-Policy: require-corp` headers.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCameraFrameRing {
+    buffer: SharedArrayBuffer,
+    header: Int32Array,
+    data: Uint8Array,
+    resolution: Resolution,
+    slots: u32,
+    slot_size: u32,
+}
+
+impl JSCameraFrameRing {
+    /// Makes a ring of `slots` RGBA frames of `resolution`. 2 or 3 slots are usually enough.
     /// # Errors
-    /// If reading the frame fails, this will error. See [`frame_raw()`](crate::JSCamera::frame_raw).
-    pub fn write_frame_to_buffer(
-        &mut self,
-        buffer: &mut [u8],
-        convert_rgba: bool,
-    ) -> Result<usize, NokhwaError> {
-        let resolution = self.preferred_resolution();
-        let frame = self.frame_raw()?;
-        if convert_rgba {
-            buffer.copy_from_slice(&frame);
-            return Ok(frame.len());
+    /// If the page is not cross-origin isolated (so there is no `SharedArrayBuffer`), `slots` is 0, or the ring would be too large, this will error.
+    pub fn new(resolution: Resolution, slots: u32) -> Result<Self, NokhwaError> {
+        if !Reflect::has(&js_sys::global(), &JsValue::from_str("SharedArrayBuffer"))
+            .unwrap_or(false)
+        {
+            return Err(NokhwaError::StructureError {
+                structure: "SharedArrayBuffer".to_string(),
+                error: "Not available, the page must be cross-origin isolated".to_string(),
+            });
         }
-        let image = match ImageBuffer::from_raw(resolution.width(), resolution.height(), frame) {
-            Some(image) => {
-                let image: ImageBuffer<Rgba<u8>, Cow<[u8]>> = image;
-                let rgb_image: RgbImage = image.convert();
-                rgb_image
-            }
-            None => {
-                return Err(NokhwaError::ReadFrameError(
-                    "Frame Cow Too Small".to_string(),
-                ))
+        let slot_size = resolution
+            .width()
+            .checked_mul(resolution.height())
+            .and_then(|pixels| pixels.checked_mul(4));
+        let length = slot_size
+            .filter(|_| slots > 0)
+            .and_then(|slot_size| slot_size.checked_mul(slots))
+            .and_then(|data| data.checked_add((RING_HEADER_WORDS + slots) * 4));
+        let (slot_size, length) = match (slot_size, length) {
+            (Some(slot_size), Some(length)) => (slot_size, length),
+            _ => {
+                return Err(NokhwaError::StructureError {
+                    structure: "JSCameraFrameRing".to_string(),
+                    error: format!("Cannot fit {} slots of {}", slots, resolution),
+                })
             }
         };
 
-        buffer.copy_from_slice(image.as_raw());
-        Ok(image.len())
+        let ring = JSCameraFrameRing::from_parts(
+            SharedArrayBuffer::new(length),
+            resolution,
+            slots,
+            slot_size,
+        );
+        ring.header.set_index(RING_WIDTH, resolution.width() as i32);
+        ring.header
+            .set_index(RING_HEIGHT, resolution.height() as i32);
+        ring.header.set_index(RING_SLOTS, slots as i32);
+        ring.header.set_index(RING_SLOT_SIZE, slot_size as i32);
+        Ok(ring)
     }
 
-    #[cfg(feature = "output-wgpu")]
-    /// Directly copies a frame to a Wgpu texture. This will automatically convert the frame into a RGBA frame.
+    /// Opens a ring made with [`new()`](JSCameraFrameRing::new) from its [`buffer()`](JSCameraFrameRing::buffer), e.g. in the Web Worker it was posted to.
     /// # Errors
-    /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
-    pub fn frame_texture<'a>(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
-        label: Option<&'a str>,
-    ) -> Result<Texture, NokhwaError> {
-        use std::num::NonZeroU32;
-        let resolution = self.preferred_resolution();
-        let frame = self.frame_raw()?;
+    /// If `buffer` does not hold a ring, this will error.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn from_buffer(buffer: SharedArrayBuffer) -> Result<Self, NokhwaError> {
+        let header = Int32Array::new_with_byte_offset_and_length(&buffer, 0, RING_HEADER_WORDS);
+        let width = header.get_index(RING_WIDTH) as u32;
+        let height = header.get_index(RING_HEIGHT) as u32;
+        let slots = header.get_index(RING_SLOTS) as u32;
+        let slot_size = header.get_index(RING_SLOT_SIZE) as u32;
+        let expected =
+            u64::from(RING_HEADER_WORDS + slots) * 4 + u64::from(slot_size) * u64::from(slots);
+        if slots == 0
+            || u64::from(width) * u64::from(height) * 4 != u64::from(slot_size)
+            || expected != u64::from(buffer.byte_length())
+        {
+            return Err(NokhwaError::StructureError {
+                structure: "JSCameraFrameRing".to_string(),
+                error: "The buffer does not hold a frame ring".to_string(),
+            });
+        }
+        Ok(JSCameraFrameRing::from_parts(
+            buffer,
+            Resolution::new(width, height),
+            slots,
+            slot_size,
+        ))
+    }
 
-        let texture_size = Extent3d {
-            width: resolution.width(),
-            height: resolution.height(),
-            depth_or_array_layers: 1,
-        };
+    fn from_parts(
+        buffer: SharedArrayBuffer,
+        resolution: Resolution,
+        slots: u32,
+        slot_size: u32,
+    ) -> Self {
+        let header =
+            Int32Array::new_with_byte_offset_and_length(&buffer, 0, RING_HEADER_WORDS + slots);
+        let data_offset = (RING_HEADER_WORDS + slots) * 4;
+        let data =
+            Uint8Array::new_with_byte_offset_and_length(&buffer, data_offset, slot_size * slots);
+        JSCameraFrameRing {
+            buffer,
+            header,
+            data,
+            resolution,
+            slots,
+            slot_size,
+        }
+    }
 
-        let texture = device.create_texture(&TextureDescriptor {
-            label,
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
-        });
+    /// The `SharedArrayBuffer` of the ring, to post to a Web Worker.
+    #[must_use]
+    pub fn buffer(&self) -> &SharedArrayBuffer {
+        &self.buffer
+    }
 
-        let width_nonzero = match NonZeroU32::try_from(4 * resolution.width()) {
-            Ok(w) => Some(w),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
+    /// The resolution of the frames.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The number of frame slots.
+    #[must_use]
+    pub fn slots(&self) -> u32 {
+        self.slots
+    }
 
-        let height_nonzero = match NonZeroU32::try_from(resolution.height()) {
-            Ok(h) => Some(h),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
+    /// The sequence number of the latest frame, or 0 if none was written yet.
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn sequence(&self) -> u32 {
+        Atomics::load(&self.header, RING_SEQUENCE).unwrap_or(0) as u32
+    }
+
+    /// Writes an RGBA `frame` into the next slot and wakes up waiting readers. Returns its sequence number.
+    /// # Errors
+    /// If `frame` is not exactly one frame of the [`resolution()`](JSCameraFrameRing::resolution), this will error.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn write_frame(&self, frame: &[u8]) -> Result<u32, NokhwaError> {
+        if frame.len() != self.slot_size as usize {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "The frame is {} bytes, but the ring holds {} byte frames",
+                frame.len(),
+                self.slot_size
+            )));
+        }
+        // wraps around before the sign bit, since 0 is "no frame yet"
+        let sequence = match self.sequence() {
+            sequence if sequence >= i32::MAX as u32 => 1,
+            sequence => sequence + 1,
         };
+        let slot = self.slot_of(sequence);
+        let _ = Atomics::store(&self.header, RING_HEADER_WORDS + slot, RING_SLOT_WRITING);
+        let start = slot * self.slot_size;
+        self.data
+            .subarray(start, start + self.slot_size)
+            .copy_from(frame);
+        let _ = Atomics::store(&self.header, RING_HEADER_WORDS + slot, sequence as i32);
+        let _ = Atomics::store(&self.header, RING_SEQUENCE, sequence as i32);
+        let _ = Atomics::notify(&self.header, RING_SEQUENCE);
+        Ok(sequence)
+    }
 
-        queue.write_texture(
-            ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &frame,
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: width_nonzero,
-                rows_per_image: height_nonzero,
+    /// Copies the latest frame into `buffer`, returning its sequence number, or `None` if no frame was written yet.
+    /// # Errors
+    /// If `buffer` is not exactly one frame of the [`resolution()`](JSCameraFrameRing::resolution), or the writer overwrote the frame while it was being read, this will error.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn read_frame_into(&self, buffer: &mut [u8]) -> Result<Option<u32>, NokhwaError> {
+        if buffer.len() != self.slot_size as usize {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "The buffer is {} bytes, but the ring holds {} byte frames",
+                buffer.len(),
+                self.slot_size
+            )));
+        }
+        let sequence = self.sequence();
+        if sequence == 0 {
+            return Ok(None);
+        }
+        let slot = self.slot_of(sequence);
+        let slot_sequence =
+            || Atomics::load(&self.header, RING_HEADER_WORDS + slot).unwrap_or(RING_SLOT_WRITING);
+        if slot_sequence() != sequence as i32 {
+            return Err(NokhwaError::ReadFrameError(
+                "The frame was overwritten while reading".to_string(),
+            ));
+        }
+        let start = slot * self.slot_size;
+        self.data
+            .subarray(start, start + self.slot_size)
+            .copy_to(buffer);
+        if slot_sequence() != sequence as i32 {
+            return Err(NokhwaError::ReadFrameError(
+                "The frame was overwritten while reading".to_string(),
+            ));
+        }
+        Ok(Some(sequence))
+    }
+
+    /// Reads the latest frame like [`read_frame_into()`](JSCameraFrameRing::read_frame_into), as an `ImageBuffer<Rgba<u8>, Vec<u8>>`.
+    /// # Errors
+    /// See [`read_frame_into()`](JSCameraFrameRing::read_frame_into).
+    pub fn read_frame(&self) -> Result<Option<(u32, ImageBuffer<Rgba<u8>, Vec<u8>>)>, NokhwaError> {
+        let mut buffer = vec![0_u8; self.slot_size as usize];
+        match self.read_frame_into(&mut buffer)? {
+            Some(sequence) => match ImageBuffer::from_vec(
+                self.resolution.width(),
+                self.resolution.height(),
+                buffer,
+            ) {
+                Some(frame) => Ok(Some((sequence, frame))),
+                None => Err(NokhwaError::ReadFrameError(
+                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
+                        .to_string(),
+                )),
             },
-            texture_size,
-        );
+            None => Ok(None),
+        }
+    }
+
+    /// Blocks until a frame newer than `last_sequence` is written, or `timeout` passes. Returns whether there is a newer frame.
+    /// # Errors
+    /// Browsers do not allow blocking the main thread, so this errors there. Only call this in a Web Worker.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn wait_frame(&self, last_sequence: u32, timeout: Duration) -> Result<bool, NokhwaError> {
+        if self.sequence() != last_sequence {
+            return Ok(true);
+        }
+        if let Err(why) = Atomics::wait_with_timeout(
+            &self.header,
+            RING_SEQUENCE,
+            last_sequence as i32,
+            timeout.as_secs_f64() * 1000_f64,
+        ) {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "Atomics.wait failed: {:?}",
+                why
+            )));
+        }
+        Ok(self.sequence() != last_sequence)
+    }
 
-        Ok(texture)
+    fn slot_of(&self, sequence: u32) -> u32 {
+        (sequence - 1) % self.slots
     }
 }
 
-impl Deref for JSCamera {
-    type Target = MediaStream;
+/// A wrapper around a [`MediaStream`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStream.html)
+#[cfg_attr(feature = "wasm-bindgen-exports", wasm_bindgen)]
+pub struct JSCamera {
+    media_stream: MediaStream,
+    constraints: JSCameraConstraints,
+    controls: Object,
+    attached: bool,
+    attached_node: Option<Node>,
+    canvas: Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+    detached_video: Option<HtmlVideoElement>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.media_stream
+impl JSCamera {
+    /// Creates a new [`JSCamera`] using [`JSCameraConstraints`].
+    ///
+    /// # Errors
+    /// This may error if permission is not granted, or the constraints are invalid.
+    pub async fn new(constraints: JSCameraConstraints) -> Result<Self, NokhwaError> {
+        let window: Window = window()?;
+        let navigator = window.navigator();
+        let media_devices = media_devices(&navigator)?;
+
+        let stream: MediaStream = match media_devices.get_user_media_with_constraints(&*constraints)
+        {
+            Ok(promise) => {
+                let future = JsFuture::from(promise);
+                match future.await {
+                    Ok(stream) => {
+                        let media_stream: MediaStream = MediaStream::from(stream);
+                        media_stream
+                    }
+                    Err(why) => {
+                        return Err(user_media_error(&why, "MediaDevicesGetUserMediaJsFuture"))
+                    }
+                }
+            }
+            Err(why) => return Err(user_media_error(&why, "MediaDevicesGetUserMedia")),
+        };
+
+        Ok(JSCamera::from_stream(stream, constraints))
     }
-}
 
-impl Drop for JSCamera {
-    fn drop(&mut self) {
-        todo!()
+    /// Creates a new [`JSCamera`] that captures the screen, a window or a browser tab using [`MediaDevices.getDisplayMedia()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices/getDisplayMedia), instead of a camera.
+    /// The browser asks the user what to share. Everything else (e.g. [`attach()`](JSCamera::attach), [`frame()`](JSCamera::frame)) works the same as with a camera.
+    ///
+    /// Only the resolution, aspect ratio, frame rate and resize mode of `constraints` apply to screen capture, and [`audio`](JSCameraConstraintsBuilder::audio) captures the shared tab's or system audio where the browser supports it. Browsers reject `exact` and `min` values and [`advanced`](JSCameraConstraintsBuilder::advanced) sets here, so use ideal and max values only.
+    /// # Errors
+    /// This may error if the user cancels or denies sharing, the constraints are invalid, or the browser does not support screen capture (most mobile browsers).
+    /// # Quirks
+    /// Browsers only allow this in response to a user action, such as a click.
+    pub async fn new_display(constraints: JSCameraConstraints) -> Result<Self, NokhwaError> {
+        let window: Window = window()?;
+        let navigator = window.navigator();
+        let media_devices = media_devices(&navigator)?;
+
+        let mut display_constraints = DisplayMediaStreamConstraints::new();
+        display_constraints.audio(&JsValue::from_bool(constraints.audio));
+        display_constraints.video(
+            &Reflect::get(&constraints.media_constraints, &JsValue::from_str("video"))
+                .unwrap_or(JsValue::TRUE),
+        );
+
+        let stream: MediaStream =
+            match media_devices.get_display_media_with_constraints(&display_constraints) {
+                Ok(promise) => match JsFuture::from(promise).await {
+                    Ok(stream) => MediaStream::from(stream),
+                    Err(why) => {
+                        return Err(user_media_error(
+                            &why,
+                            "MediaDevicesGetDisplayMediaJsFuture",
+                        ))
+                    }
+                },
+                Err(why) => return Err(user_media_error(&why, "MediaDevicesGetDisplayMedia")),
+            };
+
+        Ok(JSCamera::from_stream(stream, constraints))
+    }
+
+    fn from_stream(media_stream: MediaStream, constraints: JSCameraConstraints) -> Self {
+        JSCamera {
+            media_stream,
+            constraints,
+            controls: Object::new(),
+            attached: false,
+            attached_node: None,
+            canvas: None,
+            detached_video: None,
+        }
+    }
+
+    /// Gets the internal [`Resolution`]
+    #[must_use]
+    pub fn preferred_resolution(&self) -> Resolution {
+        self.constraints.preferred_resolution
+    }
+
+    /// Sets the internal [`Resolution`]
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_preferred_resolution(&mut self, preferred_resolution: Resolution) {
+        self.constraints.preferred_resolution = preferred_resolution;
+    }
+
+    /// Gets the internal resolution exact.
+    #[must_use]
+    pub fn resolution_exact(&self) -> bool {
+        self.constraints.resolution_exact
+    }
+
+    /// Sets the internal resolution exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_resolution_exact(&mut self, resolution_exact: bool) {
+        self.constraints.resolution_exact = resolution_exact;
+    }
+
+    /// Gets the internal resolution range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn resolution_range(&self) -> (Option<Resolution>, Resolution, Option<Resolution>) {
+        (
+            self.constraints.min_resolution,
+            self.constraints.preferred_resolution,
+            self.constraints.max_resolution,
+        )
+    }
+
+    /// Sets the internal resolution range. See [`JSCameraConstraintsBuilder::resolution_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_resolution_range(
+        &mut self,
+        min: Option<Resolution>,
+        ideal: Resolution,
+        max: Option<Resolution>,
+    ) {
+        self.constraints.min_resolution = min;
+        self.constraints.preferred_resolution = ideal;
+        self.constraints.max_resolution = max;
+    }
+
+    /// Gets the internal aspect ratio.
+    #[must_use]
+    pub fn aspect_ratio(&self) -> f64 {
+        self.constraints.aspect_ratio
+    }
+
+    /// Sets the internal aspect ratio.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) {
+        self.constraints.aspect_ratio = aspect_ratio;
+    }
+
+    /// Gets the internal aspect ratio exact.
+    #[must_use]
+    pub fn aspect_ratio_exact(&self) -> bool {
+        self.constraints.aspect_ratio_exact
+    }
+
+    /// Sets the internal aspect ratio exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_aspect_ratio_exact(&mut self, aspect_ratio_exact: bool) {
+        self.constraints.aspect_ratio_exact = aspect_ratio_exact;
+    }
+
+    /// Gets the internal aspect ratio range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn aspect_ratio_range(&self) -> (Option<f64>, f64, Option<f64>) {
+        (
+            self.constraints.min_aspect_ratio,
+            self.constraints.aspect_ratio,
+            self.constraints.max_aspect_ratio,
+        )
+    }
+
+    /// Sets the internal aspect ratio range. See [`JSCameraConstraintsBuilder::aspect_ratio_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_aspect_ratio_range(&mut self, min: Option<f64>, ideal: f64, max: Option<f64>) {
+        self.constraints.min_aspect_ratio = min;
+        self.constraints.aspect_ratio = ideal;
+        self.constraints.max_aspect_ratio = max;
+    }
+
+    /// Gets the internal [`JSCameraFacingMode`].
+    #[must_use]
+    pub fn facing_mode(&self) -> JSCameraFacingMode {
+        self.constraints.facing_mode
+    }
+
+    /// Sets the internal [`JSCameraFacingMode`]
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_facing_mode(&mut self, facing_mode: JSCameraFacingMode) {
+        self.constraints.facing_mode = facing_mode;
+    }
+
+    /// Gets the internal facing mode exact.
+    #[must_use]
+    pub fn facing_mode_exact(&self) -> bool {
+        self.constraints.facing_mode_exact
+    }
+
+    /// Sets the internal facing mode exact
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_facing_mode_exact(&mut self, facing_mode_exact: bool) {
+        self.constraints.facing_mode_exact = facing_mode_exact;
+    }
+
+    /// Gets the internal frame rate.
+    #[must_use]
+    pub fn frame_rate(&self) -> u32 {
+        self.constraints.frame_rate
+    }
+
+    /// Sets the internal frame rate
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_frame_rate(&mut self, frame_rate: u32) {
+        self.constraints.frame_rate = frame_rate;
+    }
+
+    /// Gets the internal frame rate exact.
+    #[must_use]
+    pub fn frame_rate_exact(&self) -> bool {
+        self.constraints.frame_rate_exact
+    }
+
+    /// Sets the internal frame rate exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_frame_rate_exact(&mut self, frame_rate_exact: bool) {
+        self.constraints.frame_rate_exact = frame_rate_exact;
+    }
+
+    /// Gets the internal frame rate range as `(min, ideal, max)`.
+    #[must_use]
+    pub fn frame_rate_range(&self) -> (Option<u32>, u32, Option<u32>) {
+        (
+            self.constraints.min_frame_rate,
+            self.constraints.frame_rate,
+            self.constraints.max_frame_rate,
+        )
+    }
+
+    /// Sets the internal frame rate range. See [`JSCameraConstraintsBuilder::frame_rate_range()`].
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_frame_rate_range(&mut self, min: Option<u32>, ideal: u32, max: Option<u32>) {
+        self.constraints.min_frame_rate = min;
+        self.constraints.frame_rate = ideal;
+        self.constraints.max_frame_rate = max;
+    }
+
+    /// Gets the internal [`JSCameraResizeMode`].
+    #[must_use]
+    pub fn resize_mode(&self) -> JSCameraResizeMode {
+        self.constraints.resize_mode
+    }
+
+    /// Sets the internal [`JSCameraResizeMode`]
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_resize_mode(&mut self, resize_mode: JSCameraResizeMode) {
+        self.constraints.resize_mode = resize_mode;
+    }
+
+    /// Gets the internal resize mode exact.
+    #[must_use]
+    pub fn resize_mode_exact(&self) -> bool {
+        self.constraints.resize_mode_exact
+    }
+
+    /// Sets the internal resize mode exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_resize_mode_exact(&mut self, resize_mode_exact: bool) {
+        self.constraints.resize_mode_exact = resize_mode_exact;
+    }
+
+    /// Gets the internal device id.
+    #[must_use]
+    pub fn device_id(&self) -> &str {
+        &self.constraints.device_id
+    }
+
+    /// Sets the internal device ID.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_device_id(&mut self, device_id: String) {
+        self.constraints.device_id = device_id;
+    }
+
+    /// Gets the internal device id exact.
+    #[must_use]
+    pub fn device_id_exact(&self) -> bool {
+        self.constraints.device_id_exact
+    }
+
+    /// Sets the internal device ID exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_device_id_exact(&mut self, device_id_exact: bool) {
+        self.constraints.device_id_exact = device_id_exact;
+    }
+
+    /// Gets the internal group id.
+    #[must_use]
+    pub fn group_id(&self) -> &str {
+        &self.constraints.group_id
+    }
+
+    /// Sets the internal group ID.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_group_id(&mut self, group_id: String) {
+        self.constraints.group_id = group_id;
+    }
+
+    /// Gets the internal group id exact.
+    #[must_use]
+    pub fn group_id_exact(&self) -> bool {
+        self.constraints.group_id_exact
+    }
+
+    /// Sets the internal group ID exact.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_group_id_exact(&mut self, group_id_exact: bool) {
+        self.constraints.group_id_exact = group_id_exact;
+    }
+
+    /// Gets the internal [`advanced`](crate::js_camera::JSCameraConstraintsBuilder::advanced) constraint sets.
+    #[must_use]
+    pub fn advanced(&self) -> &[JSCameraConstraintSet] {
+        &self.constraints.advanced
+    }
+
+    /// Sets the internal advanced constraint sets.
+    /// Note that this doesn't affect the internal [`MediaStreamConstraints`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStreamConstraints.html) until you call
+    /// [`apply_constraints()`](crate::JSCameraConstraints::apply_constraints)
+    pub fn set_advanced(&mut self, advanced: Vec<JSCameraConstraintSet>) {
+        self.constraints.advanced = advanced;
+    }
+
+    /// Gets whether audio was requested, see [`JSCameraConstraintsBuilder::audio()`].
+    #[must_use]
+    pub fn audio(&self) -> bool {
+        self.constraints.audio
+    }
+
+    #[must_use]
+    pub fn is_attached(&self) -> bool {
+        self.attached
+    }
+
+    /// The browser's [`MediaStream`](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.MediaStream.html) of the camera, for what `nokhwa` does not cover (e.g. recording it with a `MediaRecorder`).
+    /// This is the native handle of a [`JSCamera`], as [`RawHandle`](crate::RawHandle) is for a [`Camera`](crate::Camera).
+    #[must_use]
+    pub fn media_stream(&self) -> &MediaStream {
+        &self.media_stream
+    }
+
+    /// The video track of the [`media_stream()`](JSCamera::media_stream).
+    /// # Errors
+    /// If the stream has no video track (e.g. it was stopped), this will error.
+    pub fn video_track(&self) -> Result<MediaStreamTrack, NokhwaError> {
+        let track = self.media_stream.get_video_tracks().get(0);
+        if track.is_undefined() {
+            return Err(NokhwaError::StructureError {
+                structure: "MediaStreamTrack".to_string(),
+                error: "No video track".to_string(),
+            });
+        }
+        Ok(MediaStreamTrack::unchecked_from_js(track))
+    }
+
+    /// The audio tracks of the [`media_stream()`](JSCamera::media_stream). This is empty unless [`audio`](JSCameraConstraintsBuilder::audio) was requested, or the browser did not grant it.
+    #[must_use]
+    pub fn audio_tracks(&self) -> Vec<MediaStreamTrack> {
+        self.media_stream
+            .get_audio_tracks()
+            .iter()
+            .map(MediaStreamTrack::unchecked_from_js)
+            .collect()
+    }
+
+    /// Gets what the camera can do (e.g. its resolution, frame rate and zoom ranges, and whether it has a torch). See [`JSCameraCapabilities`].
+    /// # Errors
+    /// If there is no video track, or the browser fails to report them, this will error. Browsers without `getCapabilities()` (Firefox) get empty [`JSCameraCapabilities`].
+    pub fn capabilities(&self) -> Result<JSCameraCapabilities, NokhwaError> {
+        let track = self.video_track()?;
+        let get_capabilities = match Reflect::get(&track, &JsValue::from_str("getCapabilities")) {
+            Ok(function) if function.is_function() => Function::unchecked_from_js(function),
+            _ => return Ok(JSCameraCapabilities::default()),
+        };
+        match get_capabilities.call0(&track) {
+            Ok(capabilities) => Ok(JSCameraCapabilities::from_js(&capabilities)),
+            Err(why) => Err(NokhwaError::GetPropertyError {
+                property: "MediaStreamTrack.getCapabilities".to_string(),
+                error: format!("{:?}", why),
+            }),
+        }
+    }
+
+    /// Gets what the camera is actually doing (e.g. the negotiated resolution and frame rate). See [`JSCameraSettings`].
+    /// # Errors
+    /// If there is no video track, or the browser fails to report them, this will error.
+    pub fn settings(&self) -> Result<JSCameraSettings, NokhwaError> {
+        let track = self.video_track()?;
+        let get_settings = match Reflect::get(&track, &JsValue::from_str("getSettings")) {
+            Ok(function) if function.is_function() => Function::unchecked_from_js(function),
+            _ => return Ok(JSCameraSettings::default()),
+        };
+        match get_settings.call0(&track) {
+            Ok(settings) => Ok(JSCameraSettings::from_js(&settings)),
+            Err(why) => Err(NokhwaError::GetPropertyError {
+                property: "MediaStreamTrack.getSettings".to_string(),
+                error: format!("{:?}", why),
+            }),
+        }
+    }
+
+    /// Turns the torch (flash light) on or off. See [`JSCamera::set_control()`].
+    /// # Errors
+    /// If the camera has no torch, or applying it fails, this will error.
+    pub async fn set_torch(&mut self, torch: bool) -> Result<(), NokhwaError> {
+        self.set_control(JSCameraControl::Torch(torch)).await
+    }
+
+    /// Sets the zoom factor. See [`JSCamera::set_control()`].
+    /// # Errors
+    /// If the camera cannot zoom to `zoom`, or applying it fails, this will error.
+    pub async fn set_zoom(&mut self, zoom: f64) -> Result<(), NokhwaError> {
+        self.set_control(JSCameraControl::Zoom(zoom)).await
+    }
+
+    /// Sets how the camera focuses. See [`JSCamera::set_control()`].
+    /// # Errors
+    /// If the camera does not support `focus_mode`, or applying it fails, this will error.
+    pub async fn set_focus_mode(
+        &mut self,
+        focus_mode: JSCameraFocusMode,
+    ) -> Result<(), NokhwaError> {
+        self.set_control(JSCameraControl::FocusMode(focus_mode))
+            .await
+    }
+
+    /// Sets the focus distance, in meters. This only does anything in [`JSCameraFocusMode::Manual`]. See [`JSCamera::set_control()`].
+    /// # Errors
+    /// If the camera cannot focus at `distance`, or applying it fails, this will error.
+    pub async fn set_focus_distance(&mut self, distance: f64) -> Result<(), NokhwaError> {
+        self.set_control(JSCameraControl::FocusDistance(distance))
+            .await
+    }
+
+    /// Sets the exposure compensation, in f-stops. See [`JSCamera::set_control()`].
+    /// # Errors
+    /// If the camera cannot compensate by `compensation`, or applying it fails, this will error.
+    pub async fn set_exposure_compensation(
+        &mut self,
+        compensation: f64,
+    ) -> Result<(), NokhwaError> {
+        self.set_control(JSCameraControl::ExposureCompensation(compensation))
+            .await
+    }
+
+    /// Sets a [`JSCameraControl`] on the live video track, using [`MediaStreamTrack.applyConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/applyConstraints).
+    ///
+    /// Controls are kept as the last [`advanced`](JSCameraConstraintsBuilder::advanced) constraint set, so they stay set when other constraints are applied later.
+    /// # Errors
+    /// If the camera does not report the control in its [`capabilities()`](JSCamera::capabilities) (or the value is out of range), there is no video track, or the browser fails to apply it, this will error.
+    pub async fn set_control(&mut self, control: JSCameraControl) -> Result<(), NokhwaError> {
+        let (track, constraints) = self.control_constraints(control)?;
+        apply_track_constraints(track, constraints).await
+    }
+
+    // checks and records the control, then takes what applying it needs so the future does not borrow `self`
+    fn control_constraints(
+        &mut self,
+        control: JSCameraControl,
+    ) -> Result<(MediaStreamTrack, MediaTrackConstraints), NokhwaError> {
+        control.check(&self.capabilities()?)?;
+        if let Err(why) = Reflect::set(
+            &self.controls,
+            &JsValue::from_str(control.name()),
+            &control.value(),
+        ) {
+            return Err(constraint_error(&why));
+        }
+        self.track_constraints()
+    }
+
+    /// Takes a photo with the [`ImageCapture`](https://developer.mozilla.org/en-US/docs/Web/API/ImageCapture) API, returning the encoded (usually JPEG) [`Blob`](https://developer.mozilla.org/en-US/docs/Web/API/Blob).
+    ///
+    /// Unlike [`frame()`](JSCamera::frame), this uses the camera's still capture, so photos may be taken at a higher resolution than the stream, with the flash and red eye reduction of `settings`.
+    /// # Errors
+    /// If the browser does not support the `ImageCapture` API (Firefox, Safari), there is no video track, or the photo cannot be taken with `settings`, this will error.
+    pub async fn take_photo(&self, settings: &JSCameraPhotoSettings) -> Result<Blob, NokhwaError> {
+        take_track_photo(self.video_track()?, settings.to_object()?).await
+    }
+
+    /// Takes a photo like [`take_photo()`](JSCamera::take_photo), returning the encoded bytes.
+    /// # Errors
+    /// This errors the same way as [`take_photo()`](JSCamera::take_photo), or if the `Blob` cannot be read.
+    pub async fn take_photo_bytes(
+        &self,
+        settings: &JSCameraPhotoSettings,
+    ) -> Result<Vec<u8>, NokhwaError> {
+        blob_bytes(&self.take_photo(settings).await?).await
+    }
+
+    /// Takes a photo like [`take_photo()`](JSCamera::take_photo), decoding it into an RGBA image.
+    /// # Errors
+    /// This errors the same way as [`take_photo_bytes()`](JSCamera::take_photo_bytes), or if the photo fails to decode.
+    pub async fn take_photo_rgba(
+        &self,
+        settings: &JSCameraPhotoSettings,
+    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, NokhwaError> {
+        let bytes = self.take_photo_bytes(settings).await?;
+        match image::load_from_memory(&bytes) {
+            Ok(photo) => Ok(photo.to_rgba8()),
+            Err(why) => Err(NokhwaError::ProcessFrameError {
+                src: FrameFormat::MJPEG,
+                destination: "RGBA8888".to_string(),
+                error: why.to_string(),
+            }),
+        }
+    }
+
+    /// Applies any modified constraints to the live video track, using [`MediaStreamTrack.applyConstraints()`](https://developer.mozilla.org/en-US/docs/Web/API/MediaStreamTrack/applyConstraints), so they take effect without creating a new [`JSCamera`].
+    /// # Errors
+    /// This function may return an error if a constraint cannot be set (see [`JSCameraConstraintsBuilder::build()`]), there is no video track, or the browser cannot satisfy the constraints
+    /// ([`NokhwaError::UnsupportedFormat`]). If it cannot, the track keeps its previous settings.
+    pub async fn apply_constraints(&mut self) -> Result<(), NokhwaError> {
+        let (track, constraints) = self.track_constraints()?;
+        apply_track_constraints(track, constraints).await
+    }
+
+    // rebuilds the constraints, and takes what applying them needs so the future does not borrow `self`
+    fn track_constraints(
+        &mut self,
+    ) -> Result<(MediaStreamTrack, MediaTrackConstraints), NokhwaError> {
+        self.constraints.apply_constraints()?;
+        let track = self.video_track()?;
+        // `video` is `true` (no constraints) or the constraints object
+        let video = Reflect::get(
+            &self.constraints.media_constraints,
+            &JsValue::from_str("video"),
+        )
+        .ok()
+        .filter(JsValue::is_object)
+        .unwrap_or_else(|| Object::new().into());
+        if Object::keys(&self.controls).length() != 0 {
+            let advanced = match Reflect::get(&video, &JsValue::from_str("advanced")) {
+                Ok(advanced) if Array::is_array(&advanced) => Array::from(&advanced),
+                _ => Array::new(),
+            };
+            advanced.push(&self.controls);
+            if let Err(why) = Reflect::set(&video, &JsValue::from_str("advanced"), &advanced) {
+                return Err(constraint_error(&why));
+            }
+        }
+        Ok((track, MediaTrackConstraints::unchecked_from_js(video)))
+    }
+
+    /// Attaches camera to a `element`(by-id).
+    /// - `generate_new`: Whether to add a video element to provided element to attach to. Set this to `false` if the `element` ID you are passing is already a `<video>` element.
+    /// # Errors
+    /// If the camera fails to attach, fails to generate the video element, or a cast fails, this will error.
+    pub fn attach(&mut self, element: &str, generate_new: bool) -> Result<(), NokhwaError> {
+        let window: Window = window()?;
+        let document: Document = document(&window)?;
+
+        let selected_element: Element = document_select_elem(&document, element)?;
+
+        if generate_new {
+            let video_element = create_element(&document, "video")?;
+
+            set_autoplay_inline(&video_element)?;
+
+            let video_element: HtmlVideoElement =
+                element_cast::<Element, HtmlVideoElement>(video_element, "HtmlVideoElement")?;
+
+            video_element.set_width(self.preferred_resolution().width());
+            video_element.set_height(self.preferred_resolution().height());
+            video_element.set_src_object(Some(self.media_stream()));
+
+            return match selected_element.append_child(&Node::from(video_element)) {
+                Ok(n) => {
+                    self.attached_node = Some(n);
+                    self.attached = true;
+                    Ok(())
+                }
+                Err(why) => Err(NokhwaError::StructureError {
+                    structure: "Attach Error".to_string(),
+                    error: format!("{:?}", why),
+                }),
+            };
+        }
+
+        set_autoplay_inline(&selected_element)?;
+
+        let selected_element =
+            element_cast::<Element, HtmlVideoElement>(selected_element, "HtmlVideoElement")?;
+
+        selected_element.set_width(self.preferred_resolution().width());
+        selected_element.set_height(self.preferred_resolution().height());
+        selected_element.set_src_object(Some(self.media_stream()));
+
+        self.attached_node = Some(Node::from(selected_element));
+        self.attached = true;
+        Ok(())
+    }
+
+    /// # Errors
+    pub fn de_attach(&mut self) -> Result<(), NokhwaError> {
+        if !self.attached {
+            return Ok(());
+        }
+
+        let attached: &Node = match &self.attached_node {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+
+        let attached = element_cast_ref::<Node, HtmlVideoElement>(attached, "HtmlVideoElement")?;
+
+        attached.set_src_object(None);
+        self.attached_node = None;
+        self.attached = false;
+
+        Ok(())
+    }
+
+    /// Stops the camera: detaches it (see [`de_attach()`](JSCamera::de_attach)) and stops every track of the [`media_stream()`](JSCamera::media_stream), which releases the camera and turns its light off.
+    ///
+    /// The [`JSCamera`] cannot capture after this, make a new one instead. Dropping a [`JSCamera`] stops it.
+    /// # Errors
+    /// If detaching fails, this will error. The tracks are stopped anyway.
+    pub fn stop(&mut self) -> Result<(), NokhwaError> {
+        let detached = self.de_attach();
+        if let Some(video_element) = self.detached_video.take() {
+            video_element.set_src_object(None);
+        }
+        self.canvas = None;
+        // the camera light stays on until every track is stopped
+        let tracks = self.media_stream.get_tracks();
+        for idx_track in 0_u32..tracks.length() {
+            MediaStreamTrack::unchecked_from_js(tracks.get(idx_track)).stop();
+        }
+        detached
+    }
+
+    /// Whether the camera was stopped, by [`stop()`](JSCamera::stop) or by the browser (e.g. the camera was unplugged or the permission was revoked).
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        let tracks = self.media_stream.get_tracks();
+        (0_u32..tracks.length()).all(|idx_track| {
+            MediaStreamTrack::unchecked_from_js(tracks.get(idx_track)).ready_state()
+                == MediaStreamTrackState::Ended
+        })
+    }
+
+    /// The resolution frames are captured at by [`frame_raw()`](JSCamera::frame_raw) and friends, which is what the browser actually negotiated for the video track and may differ from the [`preferred_resolution()`](JSCamera::preferred_resolution).
+    ///
+    /// This is the size of the video the `<video>` element is currently showing if there is one, else the resolution from the track [`settings()`](JSCamera::settings). Browsers that report neither fall back to the [`preferred_resolution()`](JSCamera::preferred_resolution).
+    /// It can change while the camera runs, e.g. when a phone is rotated, so check it every frame instead of keeping it.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.frame_video_element()
+            .ok()
+            .flatten()
+            .and_then(|video_element| video_resolution(&video_element))
+            .unwrap_or_else(|| self.track_resolution())
+    }
+
+    /// Draws the current frame on an off-screen canvas, using a `<video>` element (if not already attached), and returns a raw `Cow<[u8]>` RGBA frame.
+    ///
+    /// The frame has the [`resolution()`](JSCamera::resolution) the browser actually negotiated, which may differ from the [`preferred_resolution()`](JSCamera::preferred_resolution).
+    ///
+    /// The canvas and `<video>` element are made on the first call and kept for the next ones. The canvas is resized when the resolution changes.
+    ///
+    /// This needs the DOM, so it does not work in a Web Worker. See [`grab_frame_rgba()`](JSCamera::grab_frame_rgba) for a frame path that does not.
+    /// # Errors
+    /// If a cast fails, the camera fails to attach, the currently attached node is invalid, or writing/reading from the canvas fails, this will error.
+    pub fn frame_raw(&mut self) -> Result<Cow<[u8]>, NokhwaError> {
+        let (_, frame) = self.capture_frame()?;
+        Ok(Cow::from(frame))
+    }
+
+    // captures an RGBA frame along with the resolution it was captured at, which is read again every frame
+    fn capture_frame(&mut self) -> Result<(Resolution, Vec<u8>), NokhwaError> {
+        let video_element = match self.frame_video_element()? {
+            Some(video_element) => video_element,
+            None => self.detached_video_element(self.track_resolution())?,
+        };
+        // before the first frame is decoded the element has no size yet
+        let resolution =
+            video_resolution(&video_element).unwrap_or_else(|| self.track_resolution());
+        let context = self.canvas_context(resolution)?;
+
+        if let Err(why) = context.draw_image_with_html_video_element_and_dw_and_dh(
+            &video_element,
+            0_f64,
+            0_f64,
+            resolution.width().into(),
+            resolution.height().into(),
+        ) {
+            return Err(NokhwaError::ReadFrameError(format!("{:?}", why)));
+        }
+
+        let image_data = match context.get_image_data(
+            0_f64,
+            0_f64,
+            resolution.width().into(),
+            resolution.height().into(),
+        ) {
+            Ok(data) => data.data().0,
+            Err(why) => {
+                return Err(NokhwaError::ReadFrameError(format!("{:?}", why)));
+            }
+        };
+
+        Ok((resolution, image_data))
+    }
+
+    // the `<video>` element frames are drawn from: the attached one, or the detached one if `frame_raw()` made it already
+    fn frame_video_element(&self) -> Result<Option<HtmlVideoElement>, NokhwaError> {
+        if self.attached {
+            if let Some(node) = &self.attached_node {
+                return Ok(Some(
+                    element_cast_ref::<Node, HtmlVideoElement>(node, "HtmlVideoElement")?.clone(),
+                ));
+            }
+        }
+        Ok(self.detached_video.clone())
+    }
+
+    // what the track negotiated, not what was asked for
+    fn track_resolution(&self) -> Resolution {
+        self.settings()
+            .ok()
+            .and_then(|settings| settings.resolution())
+            .filter(|resolution| resolution.width() > 0 && resolution.height() > 0)
+            .unwrap_or_else(|| self.preferred_resolution())
+    }
+
+    // the cached canvas context for `frame_raw()`, with the canvas resized to `resolution` if that changed
+    fn canvas_context(
+        &mut self,
+        resolution: Resolution,
+    ) -> Result<CanvasRenderingContext2d, NokhwaError> {
+        if let Some((canvas, context)) = &self.canvas {
+            // resizing clears the canvas, so only do it when needed
+            if canvas.width() != resolution.width() || canvas.height() != resolution.height() {
+                canvas.set_width(resolution.width());
+                canvas.set_height(resolution.height());
+            }
+            return Ok(context.clone());
+        }
+
+        let window: Window = window()?;
+        let document: Document = document(&window)?;
+        let canvas = create_element(&document, "canvas")?;
+        let canvas = element_cast::<Element, HtmlCanvasElement>(canvas, "HtmlCanvasElement")?;
+
+        canvas.set_height(resolution.height());
+        canvas.set_width(resolution.width());
+
+        let context = match canvas.get_context("2d") {
+            Ok(maybe_ctx) => match maybe_ctx {
+                Some(ctx) => element_cast::<Object, CanvasRenderingContext2d>(
+                    ctx,
+                    "CanvasRenderingContext2d",
+                )?,
+                None => {
+                    return Err(NokhwaError::StructureError {
+                        structure: "HtmlCanvasElement Context 2D".to_string(),
+                        error: "None".to_string(),
+                    });
+                }
+            },
+            Err(why) => {
+                return Err(NokhwaError::StructureError {
+                    structure: "HtmlCanvasElement Context 2D".to_string(),
+                    error: format!("{:?}", why),
+                });
+            }
+        };
+
+        self.canvas = Some((canvas, context.clone()));
+        Ok(context)
+    }
+
+    // the cached `<video>` element `frame_raw()` draws from when the camera is not attached. Keeping it also means it has a frame to draw after the first call
+    fn detached_video_element(
+        &mut self,
+        resolution: Resolution,
+    ) -> Result<HtmlVideoElement, NokhwaError> {
+        if let Some(video_element) = &self.detached_video {
+            if video_element.width() != resolution.width()
+                || video_element.height() != resolution.height()
+            {
+                video_element.set_width(resolution.width());
+                video_element.set_height(resolution.height());
+            }
+            return Ok(video_element.clone());
+        }
+
+        let window: Window = window()?;
+        let document: Document = document(&window)?;
+        let video_element = match document.create_element("video") {
+            Ok(new_element) => new_element,
+            Err(why) => {
+                return Err(NokhwaError::StructureError {
+                    structure: "Document Video Element".to_string(),
+                    error: format!("{:?}", why.as_string()),
+                })
+            }
+        };
+
+        set_autoplay_inline(&video_element)?;
+
+        let video_element: HtmlVideoElement =
+            element_cast::<Element, HtmlVideoElement>(video_element, "HtmlVideoElement")?;
+
+        video_element.set_width(resolution.width());
+        video_element.set_height(resolution.height());
+        video_element.set_src_object(Some(self.media_stream()));
+
+        self.detached_video = Some(video_element.clone());
+        Ok(video_element)
+    }
+
+    /// This takes the output from [`frame_raw()`](crate::JSCamera::frame_raw) and turns it into an `ImageBuffer<Rgb<u8>, Vec<u8>>`.
+    /// # Errors
+    /// This will error if the frame vec is too small(this is probably a bug, please report it!) or if the frame fails to capture. See [`frame_raw()`](crate::JSCamera::frame_raw).
+    pub fn frame(&mut self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let (resolution, raw_data) = self.capture_frame()?;
+        let image_buf =
+            match ImageBuffer::from_vec(resolution.width(), resolution.height(), raw_data) {
+                Some(buf) => {
+                    let rgba_buf: ImageBuffer<Rgba<u8>, Vec<u8>> = buf;
+                    let rgb_image_converted: ImageBuffer<Rgb<u8>, Vec<u8>> = rgba_buf.convert();
+                    rgb_image_converted
+                }
+                None => return Err(NokhwaError::ReadFrameError(
+                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
+                        .to_string(),
+                )),
+            };
+        Ok(image_buf)
+    }
+
+    /// Captures a frame (see [`frame()`](crate::JSCamera::frame)) and encodes it as a JPEG or PNG, with EXIF metadata (timestamp, the label of the video track as the camera name, and resolution).
+    /// See [`encode_snapshot()`](crate::encode_snapshot).
+    ///
+    /// There is no `save_frame()` like on [`Camera`](crate::Camera), as browsers have no file system. Offer the bytes as a download (e.g. through a `Blob`) instead.
+    /// # Errors
+    /// If the frame cannot be captured, or `format` is not [`ImageFormat::Jpeg`] or [`ImageFormat::Png`], this will error.
+    pub fn snapshot(&mut self, format: ImageFormat) -> Result<Vec<u8>, NokhwaError> {
+        let frame = self.frame()?;
+        let camera_name = Reflect::get(
+            &self.media_stream.get_video_tracks().get(0),
+            &JsValue::from_str("label"),
+        )
+        .ok()
+        .and_then(|label| label.as_string())
+        .unwrap_or_default();
+        // `SystemTime::now()` panics on `wasm32-unknown-unknown`
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        let timestamp = UNIX_EPOCH + Duration::from_millis(Date::now() as u64);
+        encode_snapshot(&frame, format, &camera_name, timestamp)
+    }
+
+    /// This takes the output from [`frame_raw()`](crate::JSCamera::frame_raw) and turns it into an `ImageBuffer<Rgba<u8>, Vec<u8>>`.
+    /// # Errors
+    /// This will error if the frame vec is too small(this is probably a bug, please report it!) or if the frame fails to capture. See [`frame_raw()`](crate::JSCamera::frame_raw).
+    pub fn rgba_frame(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, NokhwaError> {
+        let (resolution, raw_data) = self.capture_frame()?;
+        let image_buf =
+            match ImageBuffer::from_vec(resolution.width(), resolution.height(), raw_data) {
+                Some(buf) => {
+                    let rgba_buf: ImageBuffer<Rgba<u8>, Vec<u8>> = buf;
+                    rgba_buf
+                }
+                None => return Err(NokhwaError::ReadFrameError(
+                    "ImageBuffer is not large enough! This is probably a bug, please report it!"
+                        .to_string(),
+                )),
+            };
+        Ok(image_buf)
+    }
+
+    /// Captures a frame with [`frame_raw()`](JSCamera::frame_raw) and writes it into `ring`, waking up the Web Worker waiting for it. Returns the sequence number of the frame.
+    /// # Errors
+    /// If capturing fails, or the [`resolution()`](JSCamera::resolution) differs from the resolution of the ring, this will error.
+    pub fn write_frame_to_ring(&mut self, ring: &JSCameraFrameRing) -> Result<u32, NokhwaError> {
+        let frame = self.frame_raw()?;
+        ring.write_frame(&frame)
+    }
+
+    /// Starts recording the [`media_stream()`](JSCamera::media_stream) with a [`JSCameraRecorder`]. With [`audio`](JSCameraConstraintsBuilder::audio), the recording has sound.
+    /// # Errors
+    /// If the browser does not support `MediaRecorder` or the requested MIME type, or recording fails to start, this will error.
+    pub fn record(
+        &self,
+        options: &JSCameraRecorderOptions,
+    ) -> Result<JSCameraRecorder, NokhwaError> {
+        JSCameraRecorder::new(&self.media_stream, options)
+    }
+
+    /// Reads a single [WebCodecs `VideoFrame`](https://developer.mozilla.org/en-US/docs/Web/API/VideoFrame), without copying it through a canvas. The frame must be `close()`d once it is used.
+    ///
+    /// This sets up a new [`JSCameraVideoFrameStream`] every call, so use [`video_frame_stream()`](JSCamera::video_frame_stream) to read frames continuously.
+    /// # Errors
+    /// If there is no video track, the browser does not support `MediaStreamTrackProcessor`, or reading the frame fails, this will error.
+    pub async fn frame_video_frame(&self) -> Result<Object, NokhwaError> {
+        let stream = self.video_frame_stream()?;
+        match stream.next_frame().await? {
+            Some(frame) => Ok(frame),
+            None => Err(NokhwaError::ReadFrameError(
+                "The video track has ended".to_string(),
+            )),
+        }
+    }
+
+    /// Opens a [`JSCameraVideoFrameStream`] of [WebCodecs `VideoFrame`s](https://developer.mozilla.org/en-US/docs/Web/API/VideoFrame) from the video track.
+    /// # Errors
+    /// If there is no video track, or the browser does not support `MediaStreamTrackProcessor`, this will error.
+    pub fn video_frame_stream(&self) -> Result<JSCameraVideoFrameStream, NokhwaError> {
+        JSCameraVideoFrameStream::new(&self.video_track()?)
+    }
+
+    /// Grabs a frame without touching the DOM, see [`grab_track_frame()`]. The frame has the resolution of the video track, which may differ from [`preferred_resolution()`](JSCamera::preferred_resolution).
+    /// # Errors
+    /// If there is no video track, or grabbing the frame fails, this will error. See [`grab_track_frame()`].
+    pub async fn grab_frame_rgba(&self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, NokhwaError> {
+        grab_track_frame(&self.video_track()?).await
+    }
+
+    /// Grabs a frame like [`grab_frame_rgba()`](JSCamera::grab_frame_rgba), as an `ImageBuffer<Rgb<u8>, Vec<u8>>`.
+    /// # Errors
+    /// See [`grab_frame_rgba()`](JSCamera::grab_frame_rgba).
+    pub async fn grab_frame(&self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        Ok(self.grab_frame_rgba().await?.convert())
+    }
+
+    /// The minimum buffer size needed to write the current frame (RGB24). If `use_rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
+    #[must_use]
+    pub fn min_buffer_size(&self, use_rgba: bool) -> usize {
+        let resolution = self.resolution();
+        if use_rgba {
+            (resolution.width() * resolution.height() * 4) as usize
+        } else {
+            (resolution.width() * resolution.height() * 3) as usize
+        }
+    }
+
+    /// Directly writes the current frame(RGB24) into said `buffer`. If `convert_rgba` is true, the buffer written will be written as an RGBA frame instead of a RGB frame. Returns the amount of bytes written on successful capture.
+    /// # Errors
+    /// If reading the frame fails, this will error. See [`frame_raw()`](crate::JSCamera::frame_raw).
+    pub fn write_frame_to_buffer(
+        &mut self,
+        buffer: &mut [u8],
+        convert_rgba: bool,
+    ) -> Result<usize, NokhwaError> {
+        let (resolution, frame) = self.capture_frame()?;
+        if convert_rgba {
+            buffer.copy_from_slice(&frame);
+            return Ok(frame.len());
+        }
+        let image = match ImageBuffer::from_raw(resolution.width(), resolution.height(), frame) {
+            Some(image) => {
+                let image: ImageBuffer<Rgba<u8>, Vec<u8>> = image;
+                let rgb_image: RgbImage = image.convert();
+                rgb_image
+            }
+            None => {
+                return Err(NokhwaError::ReadFrameError(
+                    "Frame Cow Too Small".to_string(),
+                ))
+            }
+        };
+
+        buffer.copy_from_slice(image.as_raw());
+        Ok(image.len())
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+    /// Directly copies a frame to a Wgpu texture. This will automatically convert the frame into a RGBA frame.
+    /// # Errors
+    /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
+    pub fn frame_texture<'a>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        label: Option<&'a str>,
+    ) -> Result<Texture, NokhwaError> {
+        self.frame_texture_with(device, queue, label, TextureOptions::default())
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+    /// Directly copies a frame to a Wgpu texture made with `options`, e.g. in a swapchain-compatible `Bgra8Unorm` format. See [`TextureOptions`].
+    /// # Errors
+    /// If the frame cannot be captured, the resolution is 0 on any axis, or the format of `options` is not supported, this will error.
+    pub fn frame_texture_with<'a>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        label: Option<&'a str>,
+        options: TextureOptions,
+    ) -> Result<Texture, NokhwaError> {
+        let (resolution, frame) = self.capture_frame()?;
+        frame_to_texture(device, queue, label, resolution, &frame, options)
+    }
+}
+
+impl Deref for JSCamera {
+    type Target = MediaStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.media_stream
+    }
+}
+
+impl Drop for JSCamera {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// `#[wasm_bindgen]` cannot take `Resolution`s, generics, borrowed returns or `NokhwaError`s, so JavaScript gets these instead of the Rust API
+#[cfg(feature = "wasm-bindgen-exports")]
+mod exports {
+    use super::{
+        apply_track_constraints, grab_image_data, permission_promise, permission_status,
+        query_js_cameras, query_supported_constraints, read_video_frame, recorder_type_supported,
+        stop_recorder, take_track_photo, watch_js_cameras, watch_permission_status, JSCamera,
+        JSCameraConstraintSet, JSCameraConstraints, JSCameraConstraintsBuilder, JSCameraControl,
+        JSCameraDeviceChange, JSCameraFacingMode, JSCameraFillLightMode, JSCameraFocusMode,
+        JSCameraFrameRing, JSCameraPermissionWatcher, JSCameraPhotoSettings, JSCameraRecorder,
+        JSCameraRecorderOptions, JSCameraResizeMode, JSCameraVideoFrameStream, JSCameraWatcher,
+    };
+    use crate::{CameraInfo, NokhwaError, Resolution};
+    use image::ImageFormat;
+    use js_sys::{Array, Function, Object, Promise, Reflect, SharedArrayBuffer};
+    use std::{convert::TryFrom, time::Duration};
+    use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+    use wasm_bindgen_futures::future_to_promise;
+    use web_sys::MediaStream;
+
+    impl From<NokhwaError> for JsValue {
+        fn from(error: NokhwaError) -> Self {
+            js_sys::Error::new(&error.to_string()).into()
+        }
+    }
+
+    fn camera_info_object(info: &CameraInfo) -> Result<Object, JsValue> {
+        let object = Object::new();
+        let index = u32::try_from(*info.index()).unwrap_or(u32::MAX);
+        Reflect::set(&object, &"name".into(), &info.human_name().into())?;
+        Reflect::set(&object, &"description".into(), &info.description().into())?;
+        Reflect::set(&object, &"misc".into(), &info.misc().into())?;
+        Reflect::set(&object, &"index".into(), &index.into())?;
+        Reflect::set(
+            &object,
+            &"position".into(),
+            &info.position().to_string().into(),
+        )?;
+        Ok(object)
+    }
+
+    /// Resolves to an array of `{ name, description, misc, index, position }` objects, see [`query_js_cameras()`].
+    /// # Errors
+    /// This will error if there is no valid web context or the web API is not supported
+    #[wasm_bindgen(js_name = queryJsCameras)]
+    pub async fn js_query_js_cameras() -> Result<Array, JsValue> {
+        let cameras = Array::new();
+        for info in query_js_cameras().await? {
+            cameras.push(&camera_info_object(&info)?);
+        }
+        Ok(cameras)
+    }
+
+    fn device_change_object(change: &JSCameraDeviceChange) -> Result<Object, JsValue> {
+        let object = Object::new();
+        let added = Array::new();
+        for info in change.added() {
+            added.push(&camera_info_object(info)?);
+        }
+        let removed = Array::new();
+        for info in change.removed() {
+            removed.push(&camera_info_object(info)?);
+        }
+        Reflect::set(&object, &"added".into(), &added)?;
+        Reflect::set(&object, &"removed".into(), &removed)?;
+        Ok(object)
+    }
+
+    /// Calls `callback` with `(error, { added, removed })`, where `added` and `removed` are arrays like the ones [`js_query_js_cameras()`] resolves to, see [`watch_js_cameras()`].
+    /// # Errors
+    /// This will error if there is no valid web context or the web API is not supported
+    #[wasm_bindgen(js_name = watchJsCameras)]
+    pub fn js_watch_js_cameras(callback: Function) -> Result<JSCameraWatcher, JsValue> {
+        Ok(watch_js_cameras(move |change| {
+            let _ = match change
+                .map_err(JsValue::from)
+                .and_then(|change| device_change_object(&change))
+            {
+                Ok(change) => callback.call2(&JsValue::NULL, &JsValue::NULL, &change),
+                Err(why) => callback.call1(&JsValue::NULL, &why),
+            };
+        })?)
+    }
+
+    /// Resolves to `"Granted"`, `"Denied"`, `"NotDetermined"` or `"Unknown"`, see [`permission_status()`].
+    /// # Errors
+    /// This will error if there is no valid web context.
+    #[wasm_bindgen(js_name = cameraPermissionStatus)]
+    pub async fn js_permission_status() -> Result<JsValue, JsValue> {
+        Ok(permission_status().await?.to_string().into())
+    }
+
+    /// Resolves to a watcher that calls `callback` with the new permission, as a string like [`js_permission_status()`] resolves to, see [`watch_permission_status()`].
+    /// # Errors
+    /// This will error if there is no valid web context, or the browser cannot be asked for the camera permission.
+    #[wasm_bindgen(js_name = watchCameraPermission)]
+    pub async fn js_watch_permission_status(
+        callback: Function,
+    ) -> Result<JSCameraPermissionWatcher, JsValue> {
+        Ok(watch_permission_status(move |permission| {
+            let _ = callback.call1(&JsValue::NULL, &permission.to_string().into());
+        })
+        .await?)
+    }
+
+    /// See [`recorder_type_supported()`].
+    #[wasm_bindgen(js_name = recorderTypeSupported)]
+    #[must_use]
+    pub fn js_recorder_type_supported(mime_type: &str) -> bool {
+        recorder_type_supported(mime_type)
+    }
+
+    /// An array of the constraint names the browser supports (e.g. `"frameRate"`), see [`query_supported_constraints()`].
+    /// # Errors
+    /// This will error if there is no valid web context or the web API is not supported
+    #[wasm_bindgen(js_name = querySupportedConstraints)]
+    pub fn js_query_supported_constraints() -> Result<Array, JsValue> {
+        Ok(query_supported_constraints()?
+            .iter()
+            .map(|capability| JsValue::from_str(&capability.to_string()))
+            .collect())
+    }
+
+    /// The `getUserMedia()` promise that asks for camera permission, see [`request_permission()`](super::request_permission).
+    /// # Errors
+    /// This will error if there is no valid web context or the web API is not supported
+    #[wasm_bindgen(js_name = requestPermission)]
+    pub fn js_request_permission() -> Result<Promise, JsValue> {
+        Ok(permission_promise()?)
+    }
+
+    /// Resolves to a new [`JSCamera`], see [`JSCamera::new()`]. Constructors cannot be `async`, so this is a function.
+    /// # Errors
+    /// This may error if permission is not granted, or the constraints are invalid.
+    #[wasm_bindgen(js_name = openJsCamera)]
+    pub async fn js_open_js_camera(constraints: JSCameraConstraints) -> Result<JSCamera, JsValue> {
+        Ok(JSCamera::new(constraints).await?)
+    }
+
+    /// Resolves to a new [`JSCamera`] capturing the screen, see [`JSCamera::new_display()`].
+    /// # Errors
+    /// This may error if the user cancels or denies sharing, or the constraints are invalid.
+    #[wasm_bindgen(js_name = openJsDisplay)]
+    pub async fn js_open_js_display(constraints: JSCameraConstraints) -> Result<JSCamera, JsValue> {
+        Ok(JSCamera::new_display(constraints).await?)
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraConstraintsBuilder {
+        #[wasm_bindgen(constructor)]
+        #[must_use]
+        pub fn js_new() -> Self {
+            JSCameraConstraintsBuilder::new()
+        }
+
+        #[wasm_bindgen(js_name = resolution)]
+        #[must_use]
+        pub fn js_resolution(self, width: u32, height: u32) -> Self {
+            self.resolution(Resolution::new(width, height))
+        }
+
+        #[wasm_bindgen(js_name = resolutionExact)]
+        #[must_use]
+        pub fn js_resolution_exact(self, value: bool) -> Self {
+            self.resolution_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = resolutionRange)]
+        #[must_use]
+        pub fn js_resolution_range(
+            self,
+            min_width: Option<u32>,
+            min_height: Option<u32>,
+            width: u32,
+            height: u32,
+            max_width: Option<u32>,
+            max_height: Option<u32>,
+        ) -> Self {
+            let resolution = |width: Option<u32>, height: Option<u32>| match (width, height) {
+                (Some(width), Some(height)) => Some(Resolution::new(width, height)),
+                _ => None,
+            };
+            self.resolution_range(
+                resolution(min_width, min_height),
+                Resolution::new(width, height),
+                resolution(max_width, max_height),
+            )
+        }
+
+        #[wasm_bindgen(js_name = aspectRatio)]
+        #[must_use]
+        pub fn js_aspect_ratio(self, ratio: f64) -> Self {
+            self.aspect_ratio(ratio)
+        }
+
+        #[wasm_bindgen(js_name = aspectRatioExact)]
+        #[must_use]
+        pub fn js_aspect_ratio_exact(self, value: bool) -> Self {
+            self.aspect_ratio_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = aspectRatioRange)]
+        #[must_use]
+        pub fn js_aspect_ratio_range(self, min: Option<f64>, ratio: f64, max: Option<f64>) -> Self {
+            self.aspect_ratio_range(min, ratio, max)
+        }
+
+        #[wasm_bindgen(js_name = facingMode)]
+        #[must_use]
+        pub fn js_facing_mode(self, facing_mode: JSCameraFacingMode) -> Self {
+            self.facing_mode(facing_mode)
+        }
+
+        #[wasm_bindgen(js_name = facingModeExact)]
+        #[must_use]
+        pub fn js_facing_mode_exact(self, value: bool) -> Self {
+            self.facing_mode_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = frameRate)]
+        #[must_use]
+        pub fn js_frame_rate(self, fps: u32) -> Self {
+            self.frame_rate(fps)
+        }
+
+        #[wasm_bindgen(js_name = frameRateExact)]
+        #[must_use]
+        pub fn js_frame_rate_exact(self, value: bool) -> Self {
+            self.frame_rate_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = frameRateRange)]
+        #[must_use]
+        pub fn js_frame_rate_range(self, min: Option<u32>, fps: u32, max: Option<u32>) -> Self {
+            self.frame_rate_range(min, fps, max)
+        }
+
+        #[wasm_bindgen(js_name = resizeMode)]
+        #[must_use]
+        pub fn js_resize_mode(self, resize_mode: JSCameraResizeMode) -> Self {
+            self.resize_mode(resize_mode)
+        }
+
+        #[wasm_bindgen(js_name = resizeModeExact)]
+        #[must_use]
+        pub fn js_resize_mode_exact(self, value: bool) -> Self {
+            self.resize_mode_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = deviceId)]
+        #[must_use]
+        pub fn js_device_id(self, id: String) -> Self {
+            self.device_id(&id)
+        }
+
+        #[wasm_bindgen(js_name = deviceIdExact)]
+        #[must_use]
+        pub fn js_device_id_exact(self, value: bool) -> Self {
+            self.device_id_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = groupId)]
+        #[must_use]
+        pub fn js_group_id(self, id: String) -> Self {
+            self.group_id(&id)
+        }
+
+        #[wasm_bindgen(js_name = groupIdExact)]
+        #[must_use]
+        pub fn js_group_id_exact(self, value: bool) -> Self {
+            self.group_id_exact(value)
+        }
+
+        #[wasm_bindgen(js_name = audio)]
+        #[must_use]
+        pub fn js_audio(self, value: bool) -> Self {
+            self.audio(value)
+        }
+
+        /// Adds one [`advanced`](JSCameraConstraintsBuilder::advanced) constraint set, after the ones added before.
+        #[wasm_bindgen(js_name = addAdvanced)]
+        #[must_use]
+        pub fn js_add_advanced(mut self, set: JSCameraConstraintSet) -> Self {
+            self.advanced.push(set);
+            self
+        }
+
+        /// # Errors
+        /// See [`JSCameraConstraintsBuilder::build()`].
+        #[wasm_bindgen(js_name = build)]
+        pub fn js_build(self) -> Result<JSCameraConstraints, JsValue> {
+            Ok(self.build()?)
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraConstraintSet {
+        #[wasm_bindgen(constructor)]
+        #[must_use]
+        pub fn js_new() -> Self {
+            JSCameraConstraintSet::new()
+        }
+
+        #[wasm_bindgen(js_name = resolution)]
+        #[must_use]
+        pub fn js_resolution(self, width: u32, height: u32) -> Self {
+            self.resolution(Resolution::new(width, height))
+        }
+
+        #[wasm_bindgen(js_name = aspectRatio)]
+        #[must_use]
+        pub fn js_aspect_ratio(self, ratio: f64) -> Self {
+            self.aspect_ratio(ratio)
+        }
+
+        #[wasm_bindgen(js_name = facingMode)]
+        #[must_use]
+        pub fn js_facing_mode(self, facing_mode: JSCameraFacingMode) -> Self {
+            self.facing_mode(facing_mode)
+        }
+
+        #[wasm_bindgen(js_name = frameRate)]
+        #[must_use]
+        pub fn js_frame_rate(self, fps: u32) -> Self {
+            self.frame_rate(fps)
+        }
+
+        #[wasm_bindgen(js_name = resizeMode)]
+        #[must_use]
+        pub fn js_resize_mode(self, resize_mode: JSCameraResizeMode) -> Self {
+            self.resize_mode(resize_mode)
+        }
+
+        #[wasm_bindgen(js_name = deviceId)]
+        #[must_use]
+        pub fn js_device_id(self, id: String) -> Self {
+            self.device_id(&id)
+        }
+
+        #[wasm_bindgen(js_name = groupId)]
+        #[must_use]
+        pub fn js_group_id(self, id: String) -> Self {
+            self.group_id(&id)
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraPhotoSettings {
+        #[wasm_bindgen(constructor)]
+        #[must_use]
+        pub fn js_new() -> Self {
+            JSCameraPhotoSettings::new()
+        }
+
+        #[wasm_bindgen(js_name = resolution)]
+        #[must_use]
+        pub fn js_resolution(self, width: u32, height: u32) -> Self {
+            self.resolution(Resolution::new(width, height))
+        }
+
+        #[wasm_bindgen(js_name = fillLightMode)]
+        #[must_use]
+        pub fn js_fill_light_mode(self, mode: JSCameraFillLightMode) -> Self {
+            self.fill_light_mode(mode)
+        }
+
+        #[wasm_bindgen(js_name = redEyeReduction)]
+        #[must_use]
+        pub fn js_red_eye_reduction(self, value: bool) -> Self {
+            self.red_eye_reduction(value)
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraFrameRing {
+        /// # Errors
+        /// See [`JSCameraFrameRing::new()`].
+        #[wasm_bindgen(constructor)]
+        pub fn js_new(width: u32, height: u32, slots: u32) -> Result<JSCameraFrameRing, JsValue> {
+            Ok(JSCameraFrameRing::new(
+                Resolution::new(width, height),
+                slots,
+            )?)
+        }
+
+        /// # Errors
+        /// See [`JSCameraFrameRing::from_buffer()`].
+        #[wasm_bindgen(js_name = fromBuffer)]
+        pub fn js_from_buffer(buffer: SharedArrayBuffer) -> Result<JSCameraFrameRing, JsValue> {
+            Ok(JSCameraFrameRing::from_buffer(buffer)?)
+        }
+
+        #[wasm_bindgen(getter = buffer)]
+        #[must_use]
+        pub fn js_buffer(&self) -> SharedArrayBuffer {
+            self.buffer().clone()
+        }
+
+        #[wasm_bindgen(getter = width)]
+        #[must_use]
+        pub fn js_width(&self) -> u32 {
+            self.resolution().width()
+        }
+
+        #[wasm_bindgen(getter = height)]
+        #[must_use]
+        pub fn js_height(&self) -> u32 {
+            self.resolution().height()
+        }
+
+        #[wasm_bindgen(getter = sequence)]
+        #[must_use]
+        pub fn js_sequence(&self) -> u32 {
+            self.sequence()
+        }
+
+        /// The latest frame as RGBA, or `undefined` if none was written yet.
+        /// # Errors
+        /// See [`JSCameraFrameRing::read_frame_into()`].
+        #[wasm_bindgen(js_name = readFrame)]
+        pub fn js_read_frame(&self) -> Result<Option<Vec<u8>>, JsValue> {
+            let mut buffer = vec![0_u8; self.slot_size as usize];
+            Ok(self.read_frame_into(&mut buffer)?.map(|_| buffer))
+        }
+
+        /// Takes milliseconds.
+        /// # Errors
+        /// See [`JSCameraFrameRing::wait_frame()`].
+        #[wasm_bindgen(js_name = waitFrame)]
+        pub fn js_wait_frame(&self, last_sequence: u32, timeout: u32) -> Result<bool, JsValue> {
+            Ok(self.wait_frame(last_sequence, Duration::from_millis(timeout.into()))?)
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraRecorderOptions {
+        #[wasm_bindgen(constructor)]
+        #[must_use]
+        pub fn js_new() -> Self {
+            JSCameraRecorderOptions::new()
+        }
+
+        #[wasm_bindgen(js_name = mimeType)]
+        #[must_use]
+        pub fn js_mime_type(self, mime_type: String) -> Self {
+            self.mime_type(&mime_type)
+        }
+
+        #[wasm_bindgen(js_name = videoBitsPerSecond)]
+        #[must_use]
+        pub fn js_video_bits_per_second(self, bits: u32) -> Self {
+            self.video_bits_per_second(bits)
+        }
+
+        #[wasm_bindgen(js_name = audioBitsPerSecond)]
+        #[must_use]
+        pub fn js_audio_bits_per_second(self, bits: u32) -> Self {
+            self.audio_bits_per_second(bits)
+        }
+
+        /// Takes milliseconds.
+        #[wasm_bindgen(js_name = timeslice)]
+        #[must_use]
+        pub fn js_timeslice(self, millis: u32) -> Self {
+            self.timeslice(Duration::from_millis(millis.into()))
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraRecorder {
+        /// Calls `callback` with every chunk `Blob` as it comes in, see [`JSCameraRecorder::on_chunk()`].
+        #[wasm_bindgen(js_name = onChunk)]
+        pub fn js_on_chunk(&self, callback: Function) {
+            self.on_chunk(move |chunk| {
+                let _ = callback.call1(&JsValue::NULL, &chunk);
+            });
+        }
+
+        #[wasm_bindgen(getter = mimeType)]
+        #[must_use]
+        pub fn js_mime_type(&self) -> String {
+            self.mime_type()
+        }
+
+        #[wasm_bindgen(getter = isRecording)]
+        #[must_use]
+        pub fn js_is_recording(&self) -> bool {
+            self.is_recording()
+        }
+
+        #[wasm_bindgen(getter = isPaused)]
+        #[must_use]
+        pub fn js_is_paused(&self) -> bool {
+            self.is_paused()
+        }
+
+        /// # Errors
+        /// See [`JSCameraRecorder::pause()`].
+        #[wasm_bindgen(js_name = pause)]
+        pub fn js_pause(&self) -> Result<(), JsValue> {
+            Ok(self.pause()?)
+        }
+
+        /// # Errors
+        /// See [`JSCameraRecorder::resume()`].
+        #[wasm_bindgen(js_name = resume)]
+        pub fn js_resume(&self) -> Result<(), JsValue> {
+            Ok(self.resume()?)
+        }
+
+        /// Resolves to the recording `Blob`, see [`JSCameraRecorder::stop()`].
+        #[wasm_bindgen(js_name = stop)]
+        pub fn js_stop(&self) -> Promise {
+            let recorder = self.recorder.clone();
+            let chunks = self.chunks.clone();
+            future_to_promise(async move { Ok(stop_recorder(recorder, chunks).await?.into()) })
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraPermissionWatcher {
+        #[wasm_bindgen(getter = permission)]
+        #[must_use]
+        pub fn js_permission(&self) -> String {
+            self.permission().to_string()
+        }
+
+        #[wasm_bindgen(js_name = stop)]
+        pub fn js_stop(&self) {
+            self.stop();
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraWatcher {
+        #[wasm_bindgen(js_name = stop)]
+        pub fn js_stop(&self) {
+            self.stop();
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraVideoFrameStream {
+        /// Resolves to the next `VideoFrame`, or `undefined` once the stream ends.
+        /// # Errors
+        /// See [`JSCameraVideoFrameStream::next_frame()`].
+        #[wasm_bindgen(js_name = nextFrame)]
+        pub fn js_next_frame(&self) -> Promise {
+            let reader = self.reader.clone();
+            future_to_promise(async move {
+                match read_video_frame(&reader).await? {
+                    Some(frame) => Ok(frame.into()),
+                    None => Ok(JsValue::UNDEFINED),
+                }
+            })
+        }
+
+        #[wasm_bindgen(js_name = close)]
+        pub fn js_close(&self) {
+            self.close();
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCameraConstraints {
+        #[wasm_bindgen(getter = width)]
+        #[must_use]
+        pub fn js_width(&self) -> u32 {
+            self.preferred_resolution().width()
+        }
+
+        #[wasm_bindgen(getter = height)]
+        #[must_use]
+        pub fn js_height(&self) -> u32 {
+            self.preferred_resolution().height()
+        }
+
+        #[wasm_bindgen(getter = frameRate)]
+        #[must_use]
+        pub fn js_frame_rate(&self) -> u32 {
+            self.frame_rate()
+        }
+    }
+
+    impl JSCamera {
+        fn control_promise(&mut self, control: JSCameraControl) -> Result<Promise, JsValue> {
+            let (track, constraints) = self.control_constraints(control)?;
+            Ok(future_to_promise(async move {
+                apply_track_constraints(track, constraints).await?;
+                Ok(JsValue::UNDEFINED)
+            }))
+        }
+    }
+
+    #[doc(hidden)]
+    #[wasm_bindgen]
+    impl JSCamera {
+        #[wasm_bindgen(getter = width)]
+        #[must_use]
+        pub fn js_width(&self) -> u32 {
+            self.preferred_resolution().width()
+        }
+
+        #[wasm_bindgen(getter = height)]
+        #[must_use]
+        pub fn js_height(&self) -> u32 {
+            self.preferred_resolution().height()
+        }
+
+        /// The width frames are captured at, see [`JSCamera::resolution()`].
+        #[wasm_bindgen(getter = frameWidth)]
+        #[must_use]
+        pub fn js_frame_width(&self) -> u32 {
+            self.resolution().width()
+        }
+
+        /// The height frames are captured at, see [`JSCamera::resolution()`].
+        #[wasm_bindgen(getter = frameHeight)]
+        #[must_use]
+        pub fn js_frame_height(&self) -> u32 {
+            self.resolution().height()
+        }
+
+        #[wasm_bindgen(getter = frameRate)]
+        #[must_use]
+        pub fn js_frame_rate(&self) -> u32 {
+            self.frame_rate()
+        }
+
+        #[wasm_bindgen(getter = mediaStream)]
+        #[must_use]
+        pub fn js_media_stream(&self) -> MediaStream {
+            self.media_stream().clone()
+        }
+
+        #[wasm_bindgen(getter = audioTracks)]
+        #[must_use]
+        pub fn js_audio_tracks(&self) -> Array {
+            self.audio_tracks().into_iter().collect()
+        }
+
+        #[wasm_bindgen(getter = isAttached)]
+        #[must_use]
+        pub fn js_is_attached(&self) -> bool {
+            self.is_attached()
+        }
+
+        #[wasm_bindgen(js_name = setResolution)]
+        pub fn js_set_resolution(&mut self, width: u32, height: u32) {
+            self.set_preferred_resolution(Resolution::new(width, height));
+        }
+
+        #[wasm_bindgen(js_name = setFrameRate)]
+        pub fn js_set_frame_rate(&mut self, fps: u32) {
+            self.set_frame_rate(fps);
+        }
+
+        #[wasm_bindgen(js_name = setFacingMode)]
+        pub fn js_set_facing_mode(&mut self, facing_mode: JSCameraFacingMode) {
+            self.set_facing_mode(facing_mode);
+        }
+
+        #[wasm_bindgen(js_name = setResizeMode)]
+        pub fn js_set_resize_mode(&mut self, resize_mode: JSCameraResizeMode) {
+            self.set_resize_mode(resize_mode);
+        }
+
+        #[wasm_bindgen(js_name = setDeviceId)]
+        pub fn js_set_device_id(&mut self, id: String) {
+            self.set_device_id(id);
+        }
+
+        /// Resolves once the constraints are applied to the live track.
+        /// # Errors
+        /// See [`JSCamera::apply_constraints()`].
+        #[wasm_bindgen(js_name = applyConstraints)]
+        pub fn js_apply_constraints(&mut self) -> Result<Promise, JsValue> {
+            let (track, constraints) = self.track_constraints()?;
+            Ok(future_to_promise(async move {
+                apply_track_constraints(track, constraints).await?;
+                Ok(JsValue::UNDEFINED)
+            }))
+        }
+
+        /// Resolves once the torch is turned on or off.
+        /// # Errors
+        /// See [`JSCamera::set_torch()`].
+        #[wasm_bindgen(js_name = setTorch)]
+        pub fn js_set_torch(&mut self, torch: bool) -> Result<Promise, JsValue> {
+            self.control_promise(JSCameraControl::Torch(torch))
+        }
+
+        /// Resolves once the zoom is set.
+        /// # Errors
+        /// See [`JSCamera::set_zoom()`].
+        #[wasm_bindgen(js_name = setZoom)]
+        pub fn js_set_zoom(&mut self, zoom: f64) -> Result<Promise, JsValue> {
+            self.control_promise(JSCameraControl::Zoom(zoom))
+        }
+
+        /// Resolves once the focus mode is set.
+        /// # Errors
+        /// See [`JSCamera::set_focus_mode()`].
+        #[wasm_bindgen(js_name = setFocusMode)]
+        pub fn js_set_focus_mode(
+            &mut self,
+            focus_mode: JSCameraFocusMode,
+        ) -> Result<Promise, JsValue> {
+            self.control_promise(JSCameraControl::FocusMode(focus_mode))
+        }
+
+        /// Resolves once the focus distance is set.
+        /// # Errors
+        /// See [`JSCamera::set_focus_distance()`].
+        #[wasm_bindgen(js_name = setFocusDistance)]
+        pub fn js_set_focus_distance(&mut self, distance: f64) -> Result<Promise, JsValue> {
+            self.control_promise(JSCameraControl::FocusDistance(distance))
+        }
+
+        /// Resolves once the exposure compensation is set.
+        /// # Errors
+        /// See [`JSCamera::set_exposure_compensation()`].
+        #[wasm_bindgen(js_name = setExposureCompensation)]
+        pub fn js_set_exposure_compensation(
+            &mut self,
+            compensation: f64,
+        ) -> Result<Promise, JsValue> {
+            self.control_promise(JSCameraControl::ExposureCompensation(compensation))
+        }
+
+        /// Resolves to the photo `Blob`.
+        /// # Errors
+        /// See [`JSCamera::take_photo()`].
+        #[wasm_bindgen(js_name = takePhoto)]
+        pub fn js_take_photo(&self, settings: &JSCameraPhotoSettings) -> Result<Promise, JsValue> {
+            let track = self.video_track()?;
+            let settings = settings.to_object()?;
+            Ok(future_to_promise(async move {
+                Ok(take_track_photo(track, settings).await?.into())
+            }))
+        }
+
+        /// Resolves to the frame as an `ImageData`, without touching the DOM.
+        /// # Errors
+        /// See [`JSCamera::grab_frame_rgba()`].
+        #[wasm_bindgen(js_name = grabFrame)]
+        pub fn js_grab_frame(&self) -> Result<Promise, JsValue> {
+            let track = self.video_track()?;
+            Ok(future_to_promise(async move {
+                Ok(grab_image_data(&track).await?.into())
+            }))
+        }
+
+        /// Resolves to the next `VideoFrame`.
+        /// # Errors
+        /// See [`JSCamera::frame_video_frame()`].
+        #[wasm_bindgen(js_name = frameVideoFrame)]
+        pub fn js_frame_video_frame(&self) -> Result<Promise, JsValue> {
+            let stream = self.video_frame_stream()?;
+            Ok(future_to_promise(async move {
+                match stream.next_frame().await? {
+                    Some(frame) => Ok(frame.into()),
+                    None => Err(NokhwaError::ReadFrameError(
+                        "The video track has ended".to_string(),
+                    )
+                    .into()),
+                }
+            }))
+        }
+
+        /// # Errors
+        /// See [`JSCamera::video_frame_stream()`].
+        #[wasm_bindgen(js_name = videoFrameStream)]
+        pub fn js_video_frame_stream(&self) -> Result<JSCameraVideoFrameStream, JsValue> {
+            Ok(self.video_frame_stream()?)
+        }
+
+        /// # Errors
+        /// See [`JSCamera::write_frame_to_ring()`].
+        #[wasm_bindgen(js_name = writeFrameToRing)]
+        pub fn js_write_frame_to_ring(&mut self, ring: &JSCameraFrameRing) -> Result<u32, JsValue> {
+            Ok(self.write_frame_to_ring(ring)?)
+        }
+
+        /// # Errors
+        /// See [`JSCamera::record()`].
+        #[wasm_bindgen(js_name = record)]
+        pub fn js_record(
+            &self,
+            options: &JSCameraRecorderOptions,
+        ) -> Result<JSCameraRecorder, JsValue> {
+            Ok(self.record(options)?)
+        }
+
+        /// # Errors
+        /// See [`JSCamera::attach()`].
+        #[wasm_bindgen(js_name = attach)]
+        pub fn js_attach(&mut self, element: &str, generate_new: bool) -> Result<(), JsValue> {
+            Ok(self.attach(element, generate_new)?)
+        }
+
+        /// # Errors
+        /// See [`JSCamera::de_attach()`].
+        #[wasm_bindgen(js_name = deAttach)]
+        pub fn js_de_attach(&mut self) -> Result<(), JsValue> {
+            Ok(self.de_attach()?)
+        }
+
+        /// # Errors
+        /// See [`JSCamera::stop()`].
+        #[wasm_bindgen(js_name = stop)]
+        pub fn js_stop(&mut self) -> Result<(), JsValue> {
+            Ok(self.stop()?)
+        }
+
+        #[wasm_bindgen(getter = isStopped)]
+        #[must_use]
+        pub fn js_is_stopped(&self) -> bool {
+            self.is_stopped()
+        }
+
+        /// The frame as RGBA, ready for an `ImageData`.
+        /// # Errors
+        /// See [`JSCamera::frame_raw()`].
+        #[wasm_bindgen(js_name = frameRgba)]
+        pub fn js_frame_rgba(&mut self) -> Result<Vec<u8>, JsValue> {
+            Ok(self.frame_raw()?.into_owned())
+        }
+
+        /// # Errors
+        /// See [`JSCamera::frame()`].
+        #[wasm_bindgen(js_name = frameRgb)]
+        pub fn js_frame_rgb(&mut self) -> Result<Vec<u8>, JsValue> {
+            Ok(self.frame()?.into_raw())
+        }
+
+        /// Takes `"jpeg"` or `"png"`.
+        /// # Errors
+        /// See [`JSCamera::snapshot()`].
+        #[wasm_bindgen(js_name = snapshot)]
+        pub fn js_snapshot(&mut self, format: &str) -> Result<Vec<u8>, JsValue> {
+            let image_format = match ImageFormat::from_extension(format) {
+                Some(image_format) => image_format,
+                None => {
+                    return Err(NokhwaError::UnsupportedFormat {
+                        format: format.to_string(),
+                        error: "Snapshots are JPEG or PNG".to_string(),
+                    }
+                    .into())
+                }
+            };
+            Ok(self.snapshot(image_format)?)
+        }
     }
 }
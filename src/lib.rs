@@ -8,22 +8,136 @@
 #![deny(clippy::pedantic)]
 #![warn(clippy::all)]
 
+mod backend_support;
 /// Raw access to each of Nokhwa's backends.
 pub mod backends;
+mod buffer_pool;
 mod camera;
+mod camera_builder;
 mod camera_traits;
+mod decoder;
+mod diagnostics;
 mod error;
+mod frame_bus;
+#[cfg(feature = "output-http-stream")]
+mod http_stream;
+mod image_sequence;
+mod journal;
 #[cfg(feature = "input-jscam")]
 /// A camera that uses native browser APIs meant for WASM applications.
 pub mod js_camera;
+#[cfg(feature = "output-ndarray")]
+mod ndarray_output;
+#[cfg(feature = "output-ndi")]
+mod ndi;
 #[cfg(feature = "input-ipcam")]
 /// A camera that uses `OpenCV` to access IP (rtsp/http) on the local network
 pub mod network_camera;
+#[cfg(feature = "input-onvif")]
+mod onvif;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod permission;
+#[cfg(feature = "plugins")]
+mod plugin;
 mod query;
+mod quirks;
+#[cfg(feature = "output-recorder")]
+mod recorder;
+#[cfg(all(feature = "output-shm", any(unix, windows)))]
+mod shm;
+mod sink;
+mod snapshot;
+mod stats;
+mod telemetry;
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+mod texture;
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+mod texture_streamer;
+#[cfg(feature = "output-threaded")]
+mod threaded;
+#[cfg(feature = "async-tokio")]
+mod tokio_capture;
+mod transform;
+#[cfg(feature = "turbojpeg")]
+mod turbojpeg_decoder;
 mod utils;
+#[cfg(feature = "output-virtualcam")]
+mod virtual_camera;
+#[cfg(feature = "output-ash")]
+mod vulkan;
+#[cfg(feature = "output-webrtc")]
+mod webrtc_publisher;
 
-pub use camera::Camera;
+// the `wgpu` the texture outputs are built against. If several `output-wgpu*` features are enabled, the newest `wgpu` wins
+#[cfg(all(feature = "output-wgpu", not(feature = "output-wgpu-0-19")))]
+use wgpu as wgpu_api;
+#[cfg(feature = "output-wgpu-0-19")]
+use wgpu_0_19 as wgpu_api;
+
+pub use backend_support::{supported_backends, BackendSupport};
+pub use buffer_pool::{
+    buffer_pool_capacity, buffer_pool_stats, clear_buffer_pool, set_buffer_pool_capacity,
+    BufferPoolStats, Frame,
+};
+pub use camera::{backend_priority, set_backend_priority, Camera};
+pub use camera_builder::{CameraBuilder, FormatStrategy};
 pub use camera_traits::*;
+pub use decoder::{DefaultDecoder, FrameDecoder, FrameMetadata, RawFrame};
+pub use diagnostics::{diagnostics, BackendDiagnostics, DeviceDiagnostics, DiagnosticsReport};
 pub use error::NokhwaError;
-pub use query::query_devices;
+pub use frame_bus::{frame_channel, BusFrame, DropPolicy, FrameBus, FrameSubscriber};
+#[cfg(feature = "output-http-stream")]
+pub use http_stream::{MjpegHttpServer, HTTP_STREAM_JPEG_QUALITY};
+pub use image_sequence::ImageSequenceRecorder;
+pub use journal::{JournalEntry, JournalEvent, SessionJournal};
+#[cfg(feature = "output-ndarray")]
+pub use ndarray_output::{rgb_to_ndarray_chw, rgb_to_ndarray_hwc, Normalization};
+#[cfg(feature = "output-ndi")]
+pub use ndi::NdiSender;
+#[cfg(feature = "input-onvif")]
+pub use onvif::{discover_onvif_devices, OnvifDevice, ONVIF_REQUEST_TIMEOUT};
+pub use permission::{camera_permission_status, request_camera_permission, CameraPermission};
+#[cfg(feature = "plugins")]
+pub use plugin::{
+    open_plugin_camera, query_plugin_devices, register_plugin, registered_plugins, PluginCreateFn,
+    PluginQueryFn,
+};
+pub use query::{
+    probe, probe_availability, query_camera_groups, query_capabilities, query_devices,
+    query_names_only, query_sensor_groups, CameraGroup, QueryCache, QueryMode,
+};
+pub use quirks::{add_quirk, quirks_for, reset_quirks, set_quirks, Quirk};
+#[cfg(feature = "output-recorder")]
+pub use recorder::{Recorder, VideoCodec, VideoContainer};
+#[cfg(all(feature = "output-shm", any(unix, windows)))]
+pub use shm::{SharedFrameFormat, SharedFrameInfo, SharedMemoryRing};
+pub use sink::{Redact, SinkPayload, SinkTransform};
+pub use snapshot::{encode_snapshot, SNAPSHOT_JPEG_QUALITY};
+pub use stats::{CaptureStats, DEFAULT_STATS_WINDOW};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+pub use texture::TextureOptions;
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+pub use texture_streamer::TextureStreamer;
+#[cfg(feature = "output-threaded")]
+pub use threaded::{
+    CaptureThreadOptions, ThreadPriority, ThreadedCamera, Timelapse, TimelapseDevice,
+    TimestampedFrame,
+};
+#[cfg(feature = "async-tokio")]
+pub use tokio_capture::{capture_to_broadcast, capture_to_mpsc, TokioCapture};
+pub use transform::{
+    crop_frame, mirror_frame, resize_frame, rotate_frame, Mirror, Rect, ResizeFilter, ResizeMode,
+    ResolutionLadder, Rotation, RotationHandling,
+};
+#[cfg(feature = "turbojpeg")]
+pub use turbojpeg_decoder::TurboJpegDecoder;
 pub use utils::*;
+#[cfg(feature = "output-virtualcam")]
+pub use virtual_camera::VirtualCamera;
+#[cfg(all(feature = "output-ash", target_os = "linux"))]
+pub use vulkan::import_dma_buf;
+#[cfg(feature = "output-ash")]
+pub use vulkan::VulkanFrameUploader;
+#[cfg(feature = "output-webrtc")]
+pub use webrtc_publisher::WebRtcPublisher;
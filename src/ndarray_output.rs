@@ -0,0 +1,96 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::NokhwaError;
+use image::{ImageBuffer, Rgb};
+use ndarray::Array3;
+
+/// Per-channel normalization for [`rgb_to_ndarray_chw()`], applied as `(value / 255 - mean) / std` on each RGB channel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Normalization {
+    mean: [f32; 3],
+    std: [f32; 3],
+}
+
+impl Normalization {
+    /// The mean and standard deviation of the `ImageNet` training set, which most pretrained vision models expect.
+    pub const IMAGENET: Normalization = Normalization {
+        mean: [0.485, 0.456, 0.406],
+        std: [0.229, 0.224, 0.225],
+    };
+
+    /// Only scales the values into `0.0..=1.0`.
+    pub const UNIT: Normalization = Normalization {
+        mean: [0.0, 0.0, 0.0],
+        std: [1.0, 1.0, 1.0],
+    };
+
+    /// Create a new [`Normalization`] with a mean and standard deviation per RGB channel.
+    /// # Errors
+    /// If any standard deviation is 0, this will error.
+    pub fn new(mean: [f32; 3], std: [f32; 3]) -> Result<Self, NokhwaError> {
+        if std.iter().any(|s| *s == 0.0) {
+            return Err(NokhwaError::StructureError {
+                structure: "Normalization".to_string(),
+                error: "Standard deviation must not be 0".to_string(),
+            });
+        }
+        Ok(Normalization { mean, std })
+    }
+
+    /// Get the mean per RGB channel.
+    #[must_use]
+    pub fn mean(&self) -> [f32; 3] {
+        self.mean
+    }
+
+    /// Get the standard deviation per RGB channel.
+    #[must_use]
+    pub fn std(&self) -> [f32; 3] {
+        self.std
+    }
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization::UNIT
+    }
+}
+
+fn shape_error(structure: &str, why: &ndarray::ShapeError) -> NokhwaError {
+    NokhwaError::StructureError {
+        structure: structure.to_string(),
+        error: why.to_string(),
+    }
+}
+
+/// Converts a RGB frame into a `height x width x 3` (HWC) [`Array3`], in standard (row major) layout. This does not copy the pixels.
+/// # Errors
+/// If the frame's buffer does not match its dimensions, this will error.
+pub fn rgb_to_ndarray_hwc(frame: ImageBuffer<Rgb<u8>, Vec<u8>>) -> Result<Array3<u8>, NokhwaError> {
+    let shape = (frame.height() as usize, frame.width() as usize, 3);
+    Array3::from_shape_vec(shape, frame.into_raw()).map_err(|why| shape_error("Array3<u8>", &why))
+}
+
+/// Converts a RGB frame into a `3 x height x width` (CHW) [`Array3`] of `f32`s normalized with `normalization`, in standard (row major) layout, as most ML models take it.
+/// # Errors
+/// If the frame's buffer does not match its dimensions, this will error.
+pub fn rgb_to_ndarray_chw(
+    frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    normalization: Normalization,
+) -> Result<Array3<f32>, NokhwaError> {
+    let (width, height) = (frame.width() as usize, frame.height() as usize);
+    let plane = width * height;
+    let mut data = vec![0_f32; plane * 3];
+    for (index, pixel) in frame.pixels().enumerate() {
+        for channel in 0..3 {
+            let value = f32::from(pixel[channel]) / 255.0;
+            data[channel * plane + index] =
+                (value - normalization.mean[channel]) / normalization.std[channel];
+        }
+    }
+    Array3::from_shape_vec((3, height, width), data).map_err(|why| shape_error("Array3<f32>", &why))
+}
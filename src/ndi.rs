@@ -0,0 +1,394 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraBuilder, NokhwaError};
+use libloading::Library;
+use std::{
+    convert::TryFrom,
+    ffi::CString,
+    os::raw::{c_char, c_float, c_int, c_void},
+    path::PathBuf,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// how often the capture loop checks for receivers and shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// `NDIlib_FourCC_video_type_RGBX`, which is `NDI_LIB_FOURCC('R', 'G', 'B', 'X')`
+const FOURCC_RGBX: u32 = u32::from_le_bytes(*b"RGBX");
+// `NDIlib_frame_format_type_progressive`
+const FRAME_FORMAT_PROGRESSIVE: c_int = 1;
+// `NDIlib_send_timecode_synthesize`
+const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+
+// `NDIlib_send_create_t`
+#[repr(C)]
+struct NdiSendCreate {
+    ndi_name: *const c_char,
+    groups: *const c_char,
+    clock_video: bool,
+    clock_audio: bool,
+}
+
+// `NDIlib_video_frame_v2_t`
+#[repr(C)]
+struct NdiVideoFrame {
+    xres: c_int,
+    yres: c_int,
+    fourcc: u32,
+    frame_rate_n: c_int,
+    frame_rate_d: c_int,
+    picture_aspect_ratio: c_float,
+    frame_format_type: c_int,
+    timecode: i64,
+    data: *const u8,
+    line_stride_in_bytes: c_int,
+    metadata: *const c_char,
+    timestamp: i64,
+}
+
+type InitializeFn = unsafe extern "C" fn() -> bool;
+type SendCreateFn = unsafe extern "C" fn(*const NdiSendCreate) -> *mut c_void;
+type SendDestroyFn = unsafe extern "C" fn(*mut c_void);
+type SendVideoFn = unsafe extern "C" fn(*mut c_void, *const NdiVideoFrame);
+type SendConnectionsFn = unsafe extern "C" fn(*mut c_void, u32) -> c_int;
+
+fn ndi_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("NDI: {}", error))
+}
+
+// the NDI runtime, loaded at runtime like the NDI SDK asks redistributable applications to do
+struct NdiRuntime {
+    send_create: SendCreateFn,
+    send_destroy: SendDestroyFn,
+    send_video: SendVideoFn,
+    send_connections: SendConnectionsFn,
+    // keeps the functions above mapped, must be dropped last
+    _library: Library,
+}
+
+impl NdiRuntime {
+    fn library_paths() -> Vec<PathBuf> {
+        let name = if cfg!(windows) {
+            "Processing.NDI.Lib.x64.dll"
+        } else if cfg!(target_os = "macos") {
+            "libndi.dylib"
+        } else {
+            "libndi.so.5"
+        };
+        // the installers set this to where the runtime is, otherwise let the OS search for it
+        let mut paths: Vec<PathBuf> = std::env::var_os("NDI_RUNTIME_DIR_V5")
+            .map(|dir| PathBuf::from(dir).join(name))
+            .into_iter()
+            .collect();
+        paths.push(PathBuf::from(name));
+        paths
+    }
+
+    fn load() -> Result<Self, NokhwaError> {
+        let paths = NdiRuntime::library_paths();
+        // SAFETY: the NDI runtime has no initialization code beyond setting up its own state
+        let library = match paths
+            .iter()
+            .find_map(|path| unsafe { Library::new(path) }.ok())
+        {
+            Some(library) => library,
+            None => {
+                return Err(ndi_error(&format!(
+                    "The NDI runtime is not installed (tried {})",
+                    paths
+                        .iter()
+                        .map(|path| path.to_string_lossy().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )))
+            }
+        };
+        // SAFETY: the types match the declarations in `Processing.NDI.Lib.h`
+        unsafe {
+            let symbol_error =
+                |why: libloading::Error| ndi_error(&format!("Invalid NDI runtime: {}", why));
+            let initialize = *library
+                .get::<InitializeFn>(b"NDIlib_initialize\0")
+                .map_err(symbol_error)?;
+            let send_create = *library
+                .get::<SendCreateFn>(b"NDIlib_send_create\0")
+                .map_err(symbol_error)?;
+            let send_destroy = *library
+                .get::<SendDestroyFn>(b"NDIlib_send_destroy\0")
+                .map_err(symbol_error)?;
+            let send_video = *library
+                .get::<SendVideoFn>(b"NDIlib_send_send_video_v2\0")
+                .map_err(symbol_error)?;
+            let send_connections = *library
+                .get::<SendConnectionsFn>(b"NDIlib_send_get_no_connections\0")
+                .map_err(symbol_error)?;
+            // this fails on CPUs NDI does not support (it needs SSE4.2 on x86)
+            if !initialize() {
+                return Err(ndi_error("The NDI runtime failed to initialize"));
+            }
+            Ok(NdiRuntime {
+                send_create,
+                send_destroy,
+                send_video,
+                send_connections,
+                _library: library,
+            })
+        }
+    }
+}
+
+// an NDI sender. The NDI SDK allows sending and counting connections from different threads
+struct NdiSend {
+    runtime: NdiRuntime,
+    instance: *mut c_void,
+}
+
+unsafe impl Send for NdiSend {}
+unsafe impl Sync for NdiSend {}
+
+impl NdiSend {
+    fn new(name: &str) -> Result<Self, NokhwaError> {
+        let runtime = NdiRuntime::load()?;
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return Err(ndi_error("The source name contains a NUL byte")),
+        };
+        let create = NdiSendCreate {
+            ndi_name: name.as_ptr(),
+            groups: ptr::null(),
+            // the camera already paces the frames
+            clock_video: false,
+            clock_audio: false,
+        };
+        // SAFETY: `create` and the name it points to outlive the call, NDI copies them
+        let instance = unsafe { (runtime.send_create)(&create) };
+        if instance.is_null() {
+            return Err(ndi_error("Failed to create the NDI sender"));
+        }
+        Ok(NdiSend { runtime, instance })
+    }
+
+    fn connections(&self) -> usize {
+        // SAFETY: the instance is valid until drop
+        let connections = unsafe { (self.runtime.send_connections)(self.instance, 0) };
+        usize::try_from(connections).unwrap_or(0)
+    }
+
+    // sends an RGBX frame. The synchronous send copies the frame, so it can be freed afterwards
+    fn send(
+        &self,
+        rgbx: &[u8],
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> Result<(), NokhwaError> {
+        let (xres, yres, line_stride) = match (
+            c_int::try_from(width),
+            c_int::try_from(height),
+            c_int::try_from(u64::from(width) * 4),
+        ) {
+            (Ok(xres), Ok(yres), Ok(line_stride)) => (xres, yres, line_stride),
+            _ => return Err(ndi_error("The frame is too large")),
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let picture_aspect_ratio = width as f32 / height.max(1) as f32;
+        let frame = NdiVideoFrame {
+            xres,
+            yres,
+            fourcc: FOURCC_RGBX,
+            frame_rate_n: c_int::try_from(frame_rate.max(1)).unwrap_or(30),
+            frame_rate_d: 1,
+            picture_aspect_ratio,
+            frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+            timecode: TIMECODE_SYNTHESIZE,
+            data: rgbx.as_ptr(),
+            line_stride_in_bytes: line_stride,
+            metadata: ptr::null(),
+            timestamp: 0,
+        };
+        // SAFETY: `rgbx` holds `height` rows of `line_stride` bytes, and the instance is valid until drop
+        unsafe { (self.runtime.send_video)(self.instance, &frame) };
+        Ok(())
+    }
+}
+
+impl Drop for NdiSend {
+    fn drop(&mut self) {
+        // SAFETY: the instance is valid, and not used after this
+        unsafe { (self.runtime.send_destroy)(self.instance) };
+    }
+}
+
+struct Shared {
+    sender: NdiSend,
+    running: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+fn capture_loop(mut camera: Camera, shared: &Shared) {
+    let frame_rate = camera.frame_rate();
+    while shared.running.load(Ordering::SeqCst) {
+        // no one is watching, don't capture frames for nothing
+        if shared.sender.connections() == 0 {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        let sent = camera.frame_for_sink().and_then(|payload| {
+            let image = match payload.image() {
+                Some(image) => image,
+                None => {
+                    return Err(ndi_error(
+                        "Opaque frames (e.g. encrypted by a SinkTransform) cannot be sent",
+                    ))
+                }
+            };
+            let mut rgbx = Vec::with_capacity(image.as_raw().len() / 3 * 4);
+            for pixel in image.as_raw().chunks_exact(3) {
+                rgbx.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+            }
+            shared
+                .sender
+                .send(&rgbx, image.width(), image.height(), frame_rate)
+        });
+        match sent {
+            Ok(()) => {}
+            // a late frame is not fatal, try again
+            Err(NokhwaError::Timeout(_)) => {}
+            Err(why) => {
+                match shared.error.lock() {
+                    Ok(mut error) => *error = Some(why.to_string()),
+                    Err(poisoned) => *poisoned.into_inner() = Some(why.to_string()),
+                }
+                break;
+            }
+        }
+    }
+    let _stop = camera.stop_stream();
+}
+
+/// Publishes a camera as an NDI source on the local network, for OBS (with the NDI plugin), vMix, NDI Studio Monitor and other NDI receivers.
+///
+/// Any number of receivers can watch at once. Frames are only captured while at least one receiver is connected.
+/// Frames go through [`Camera::frame_for_sink()`], so a [`SinkTransform`](crate::SinkTransform) set on the camera applies to what is sent.
+/// ```.ignore
+/// let sender = NdiSender::from_builder(CameraBuilder::new().index(0), "Desk Camera")?;
+/// // the source shows up as "<hostname> (Desk Camera)" in NDI receivers
+/// ```
+/// # Quirks
+/// - The NDI runtime (version 5) is loaded when the sender is created, from `NDI_RUNTIME_DIR_V5` or wherever the OS finds libraries. It is not distributed with `nokhwa`,
+///   install it from <https://ndi.video>. Using it means agreeing to the NDI SDK license.
+/// - Frames are sent as uncompressed RGBX, NDI compresses them itself.
+/// - [`SinkPayload::Opaque`](crate::SinkPayload::Opaque) frames cannot be sent, and stop the sender.
+pub struct NdiSender {
+    name: String,
+    shared: Arc<Shared>,
+    capture_thread: Option<JoinHandle<()>>,
+}
+
+impl NdiSender {
+    /// Starts publishing the camera opened by `open_camera`, which runs on the capture thread, as the NDI source `name`. The stream is opened after `open_camera` returns, if it is not open already.
+    /// # Errors
+    /// If the NDI runtime is not installed, the sender cannot be created, or `open_camera` or opening the stream fails, this will error.
+    pub fn new<F>(name: &str, open_camera: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            sender: NdiSend::new(name)?,
+            running: AtomicBool::new(true),
+            error: Mutex::new(None),
+        });
+
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let capture_shared = shared.clone();
+        let capture_thread = thread::spawn(move || {
+            let camera = open_camera().and_then(|mut camera| {
+                if !camera.is_stream_open() {
+                    camera.open_stream()?;
+                }
+                Ok(camera)
+            });
+            match camera {
+                Ok(camera) => {
+                    let _sent = opened_tx.send(Ok(()));
+                    capture_loop(camera, &capture_shared);
+                }
+                Err(why) => {
+                    let _sent = opened_tx.send(Err(why));
+                }
+            }
+        });
+
+        match opened_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(why)) => return Err(why),
+            Err(_) => {
+                return Err(ndi_error(
+                    "Capture thread panicked while opening the camera",
+                ))
+            }
+        }
+
+        Ok(NdiSender {
+            name: name.to_string(),
+            shared,
+            capture_thread: Some(capture_thread),
+        })
+    }
+
+    /// Starts publishing the camera `builder` opens as the NDI source `name`. See [`new()`](NdiSender::new).
+    /// # Errors
+    /// If the NDI runtime is not installed, the sender cannot be created, or the camera or its stream fails to open, this will error.
+    pub fn from_builder(builder: CameraBuilder, name: &str) -> Result<Self, NokhwaError> {
+        NdiSender::new(name, move || builder.open())
+    }
+
+    /// The name of the NDI source. Receivers show it after the host name, e.g. `MACHINE (name)`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How many receivers are connected right now.
+    #[must_use]
+    pub fn connection_count(&self) -> usize {
+        self.shared.sender.connections()
+    }
+
+    /// The error that stopped the camera, if it failed.
+    #[must_use]
+    pub fn error(&self) -> Option<String> {
+        match self.shared.error.lock() {
+            Ok(error) => error.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Stops publishing and closes the camera. This is also done on drop.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.capture_thread.take() {
+            let _joined = thread.join();
+        }
+    }
+}
+
+impl Drop for NdiSender {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -4,28 +4,111 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::wgpu_api::{Device as WgpuDevice, Queue as WgpuQueue, Texture as WgpuTexture};
 use crate::{backends::capture::OpenCvCaptureDevice, CaptureBackendTrait, NokhwaError};
+#[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+use crate::{texture::frame_to_texture, Resolution, TextureOptions};
 use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbaImage};
-use std::cell::RefCell;
-#[cfg(feature = "output-wgpu")]
-use wgpu::{
-    Device as WgpuDevice, Extent3d, ImageCopyTexture, ImageDataLayout, Queue as WgpuQueue,
-    Texture as WgpuTexture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
-};
+use std::{cell::RefCell, time::Duration};
+
+/// How long a [`NetworkCamera`] waits to connect, or for a frame, before it gives up.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How a [`NetworkCamera`] reconnects after its stream fails.
+///
+/// The first reconnect waits `initial_backoff`, and every failed attempt after that doubles the wait, up to `max_backoff`.
+/// The default is 5 attempts, starting at 500 milliseconds and capped at 10 seconds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReconnectPolicy {
+    max_attempts: Option<u32>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Creates a new [`ReconnectPolicy`]. If `max_attempts` is `None`, it retries forever.
+    #[must_use]
+    pub fn new(
+        max_attempts: Option<u32>,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        ReconnectPolicy {
+            max_attempts,
+            initial_backoff,
+            max_backoff: max_backoff.max(initial_backoff),
+        }
+    }
+
+    /// How many times it tries to reconnect before giving up. `None` retries forever.
+    #[must_use]
+    pub fn max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    /// How long it waits before the first reconnect.
+    #[must_use]
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// The longest it waits between two reconnects.
+    #[must_use]
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// How long it waits before reconnect `attempt`, counting from 0.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        2_u32
+            .checked_pow(attempt)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy::new(Some(5), Duration::from_millis(500), Duration::from_secs(10))
+    }
+}
+
 /// A struct that supports IP Cameras via the `OpenCV` backend.
+/// # Quirks
+/// - If capturing a frame fails, [`frame()`](NetworkCamera::frame) reconnects following its [`ReconnectPolicy`] and tries once more, so it can block for as long as the backoff takes. Set [`set_reconnect_policy(None)`](NetworkCamera::set_reconnect_policy) to error straight away instead.
+/// - Low latency mode relies on `OpenCV` returning buffered frames faster than live ones, see [`drop_queued_frames()`](OpenCvCaptureDevice::drop_queued_frames).
 pub struct NetworkCamera {
     ip: String,
+    connect_timeout: Duration,
+    reconnect_policy: Option<ReconnectPolicy>,
+    low_latency: bool,
     opencv_backend: RefCell<OpenCvCaptureDevice>,
 }
 
 impl NetworkCamera {
-    /// Creates a new [`NetworkCamera`] from an IP.
+    /// Creates a new [`NetworkCamera`] from an IP, with the [`DEFAULT_CONNECT_TIMEOUT`].
     /// # Errors
     /// If the IP is invalid or `OpenCV` fails to open the IP, this will error
     pub fn new(ip: String) -> Result<Self, NokhwaError> {
-        let opencv_camera = OpenCvCaptureDevice::new_ip_camera(ip.clone())?;
+        NetworkCamera::with_connect_timeout(ip, DEFAULT_CONNECT_TIMEOUT)
+    }
+
+    /// Creates a new [`NetworkCamera`] from an IP, which gives up connecting or waiting for a frame after `connect_timeout`.
+    /// # Errors
+    /// If the IP is invalid or `OpenCV` fails to open the IP within `connect_timeout`, this will error
+    pub fn with_connect_timeout(
+        ip: String,
+        connect_timeout: Duration,
+    ) -> Result<Self, NokhwaError> {
+        let opencv_camera =
+            OpenCvCaptureDevice::new_ip_camera_with_timeout(ip.clone(), connect_timeout)?;
         Ok(NetworkCamera {
             ip,
+            connect_timeout,
+            reconnect_policy: Some(ReconnectPolicy::default()),
+            low_latency: false,
             opencv_backend: RefCell::new(opencv_camera),
         })
     }
@@ -39,11 +122,42 @@ impl NetworkCamera {
     /// # Errors
     /// If the IP is invalid or `OpenCV` fails to open the IP, this will error
     pub fn set_ip(&mut self, ip: String) -> Result<(), NokhwaError> {
-        *self.opencv_backend.borrow_mut() = OpenCvCaptureDevice::new_ip_camera(ip.clone())?;
+        *self.opencv_backend.borrow_mut() =
+            OpenCvCaptureDevice::new_ip_camera_with_timeout(ip.clone(), self.connect_timeout)?;
         self.ip = ip;
         Ok(())
     }
 
+    /// Gets how long it waits to connect, or for a frame, before it gives up.
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    /// Sets how long it waits to connect, or for a frame, before it gives up. This takes effect the next time it connects, e.g. on [`set_ip()`](NetworkCamera::set_ip) or a reconnect.
+    pub fn set_connect_timeout(&mut self, connect_timeout: Duration) {
+        self.connect_timeout = connect_timeout;
+    }
+
+    /// Gets the [`ReconnectPolicy`]. `None` means it does not reconnect.
+    pub fn reconnect_policy(&self) -> Option<ReconnectPolicy> {
+        self.reconnect_policy
+    }
+
+    /// Sets the [`ReconnectPolicy`]. `None` turns reconnecting off, so [`frame()`](NetworkCamera::frame) errors on the first failure.
+    pub fn set_reconnect_policy(&mut self, reconnect_policy: Option<ReconnectPolicy>) {
+        self.reconnect_policy = reconnect_policy;
+    }
+
+    /// Gets whether low latency mode is on.
+    pub fn low_latency(&self) -> bool {
+        self.low_latency
+    }
+
+    /// Sets low latency mode. When on, frames that queued up since the last [`frame()`](NetworkCamera::frame) are dropped, so it always returns the newest frame instead of lagging further behind the camera.
+    pub fn set_low_latency(&mut self, low_latency: bool) {
+        self.low_latency = low_latency;
+    }
+
     /// Opens stream.
     /// # Errors
     /// If the backend fails to capture the stream this will error
@@ -51,11 +165,53 @@ impl NetworkCamera {
         self.opencv_backend.borrow_mut().open_stream()
     }
 
-    /// Gets the frame decoded as a RGB24 frame
+    /// Gets the frame decoded as a RGB24 frame. If this fails, it reconnects following the [`ReconnectPolicy`] and tries again.
     /// # Errors
-    /// If the backend fails to capture the stream, or if the decoding fails this will error
+    /// If the backend fails to capture the stream and fails to reconnect, or if the decoding fails this will error
     pub fn frame(&self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
-        self.opencv_backend.borrow_mut().frame()
+        match self.capture_frame() {
+            Ok(frame) => Ok(frame),
+            Err(why) => match self.reconnect_policy {
+                Some(policy) => {
+                    self.reconnect(policy, why)?;
+                    self.capture_frame()
+                }
+                None => Err(why),
+            },
+        }
+    }
+
+    fn capture_frame(&self) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        let mut opencv_backend = self.opencv_backend.borrow_mut();
+        if self.low_latency {
+            opencv_backend.drop_queued_frames()?;
+        }
+        opencv_backend.frame()
+    }
+
+    fn reconnect(&self, policy: ReconnectPolicy, error: NokhwaError) -> Result<(), NokhwaError> {
+        let mut last_error = error;
+        let mut attempt = 0;
+        while policy.max_attempts().map_or(true, |max| attempt < max) {
+            std::thread::sleep(policy.backoff(attempt));
+            attempt += 1;
+            let reopened = OpenCvCaptureDevice::new_ip_camera_with_timeout(
+                self.ip.clone(),
+                self.connect_timeout,
+            )
+            .and_then(|mut opencv_camera| {
+                opencv_camera.open_stream()?;
+                Ok(opencv_camera)
+            });
+            match reopened {
+                Ok(opencv_camera) => {
+                    *self.opencv_backend.borrow_mut() = opencv_camera;
+                    return Ok(());
+                }
+                Err(why) => last_error = why,
+            }
+        }
+        Err(last_error)
     }
 
     /// The minimum buffer size needed to write the current frame (RGB24). If `rgba` is true, it will instead return the minimum size of the RGBA buffer needed.
@@ -85,7 +241,7 @@ impl NetworkCamera {
         Ok(bytes)
     }
 
-    #[cfg(feature = "output-wgpu")]
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
     /// Directly copies a frame to a Wgpu texture. This will automatically convert the frame into a RGBA frame.
     /// # Errors
     /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
@@ -95,52 +251,30 @@ impl NetworkCamera {
         queue: &WgpuQueue,
         label: Option<&'a str>,
     ) -> Result<WgpuTexture, NokhwaError> {
-        use std::{convert::TryFrom, num::NonZeroU32};
+        self.frame_texture_with(device, queue, label, TextureOptions::default())
+    }
+
+    #[cfg(any(feature = "output-wgpu", feature = "output-wgpu-0-19"))]
+    /// Directly copies a frame to a Wgpu texture made with `options`, e.g. in a swapchain-compatible `Bgra8Unorm` format. See [`TextureOptions`].
+    /// # Errors
+    /// If the frame cannot be captured, the resolution is 0 on any axis, or the format of `options` is not supported, this will error.
+    pub fn frame_texture_with<'a>(
+        &mut self,
+        device: &WgpuDevice,
+        queue: &WgpuQueue,
+        label: Option<&'a str>,
+        options: TextureOptions,
+    ) -> Result<WgpuTexture, NokhwaError> {
         let frame = self.frame()?;
         let rgba_frame: RgbaImage = frame.convert();
-
-        let texture_size = Extent3d {
-            width: frame.width(),
-            height: frame.height(),
-            depth_or_array_layers: 1,
-        };
-
-        let texture = device.create_texture(&TextureDescriptor {
+        frame_to_texture(
+            device,
+            queue,
             label,
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
-        });
-
-        let width_nonzero = match NonZeroU32::try_from(4 * rgba_frame.width()) {
-            Ok(w) => Some(w),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        let height_nonzero = match NonZeroU32::try_from(rgba_frame.height()) {
-            Ok(h) => Some(h),
-            Err(why) => return Err(NokhwaError::ReadFrameError(why.to_string())),
-        };
-
-        queue.write_texture(
-            ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba_frame.to_vec(),
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: width_nonzero,
-                rows_per_image: height_nonzero,
-            },
-            texture_size,
-        );
-
-        Ok(texture)
+            Resolution::new(rgba_frame.width(), rgba_frame.height()),
+            rgba_frame.as_raw(),
+            options,
+        )
     }
 
     /// Will drop the stream.
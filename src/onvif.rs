@@ -0,0 +1,689 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#[cfg(feature = "input-ipcam")]
+use crate::network_camera::NetworkCamera;
+use crate::{CameraInfo, NokhwaError};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Formatter},
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How long a request to an ONVIF device may take before it fails.
+pub const ONVIF_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const DISCOVERY_ADDRESS: &str = "239.255.255.250:3702";
+const DEVICE_NAMESPACE: &str = "http://www.onvif.org/ver10/device/wsdl";
+const MEDIA_NAMESPACE: &str = "http://www.onvif.org/ver10/media/wsdl";
+const PTZ_NAMESPACE: &str = "http://www.onvif.org/ver20/ptz/wsdl";
+const SCHEMA_NAMESPACE: &str = "http://www.onvif.org/ver10/schema";
+
+/// A network camera found with [`discover_onvif_devices()`], talked to over ONVIF.
+///
+/// It can give the RTSP URI of its stream ([`stream_uri()`](OnvifDevice::stream_uri), or [`open()`](OnvifDevice::open) it as a [`NetworkCamera`](crate::network_camera::NetworkCamera) with the `input-ipcam` feature)
+/// and, if it has one, drive its pan/tilt/zoom head.
+///
+/// Most devices need [`set_credentials()`](OnvifDevice::set_credentials) for anything but discovery. They are sent as a WS-Security `UsernameToken` digest, never in plain text.
+/// # Quirks
+/// - Only `http://` device services are supported, as `nokhwa` has no TLS. Devices that only offer `https://` return an error.
+/// - The RTSP URI and PTZ commands are for the first media profile of the device, see [`set_profile()`](OnvifDevice::set_profile) to use another.
+/// - Devices whose clock is far off reject the credentials, as the digest is timestamped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OnvifDevice {
+    info: CameraInfo,
+    service_url: String,
+    scopes: Vec<String>,
+    credentials: Option<(String, String)>,
+    media_url: Option<String>,
+    ptz_url: Option<String>,
+    profile: Option<String>,
+}
+
+impl OnvifDevice {
+    /// Creates an [`OnvifDevice`] from the URL of its device service (e.g. `http://192.168.1.64/onvif/device_service`), for devices that cannot be discovered (e.g. on another subnet).
+    #[must_use]
+    pub fn new(service_url: String) -> Self {
+        let info = CameraInfo::new(
+            "ONVIF Camera".to_string(),
+            format!("ONVIF Device @ {}", service_url),
+            "".to_string(),
+            0,
+        );
+        OnvifDevice {
+            info,
+            service_url,
+            scopes: vec![],
+            credentials: None,
+            media_url: None,
+            ptz_url: None,
+            profile: None,
+        }
+    }
+
+    /// Get a reference to the [`CameraInfo`] of the device. The `human_name` is the name the device announces, the `bus_path` is its device service URL and the `group_id` its endpoint address.
+    #[must_use]
+    pub fn camera_info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    /// Get a reference to the URL of the device service.
+    #[must_use]
+    pub fn service_url(&self) -> &String {
+        &self.service_url
+    }
+
+    /// Get the ONVIF scopes the device announced (e.g. `onvif://www.onvif.org/hardware/DS-2CD2043G0-I`).
+    #[must_use]
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    /// Set the username and password used to authenticate with the device.
+    pub fn set_credentials(&mut self, username: String, password: String) {
+        self.credentials = Some((username, password));
+    }
+
+    /// Get the token of the media profile in use, if one has been picked yet.
+    #[must_use]
+    pub fn profile(&self) -> Option<&String> {
+        self.profile.as_ref()
+    }
+
+    /// Set the token of the media profile to use. See [`profiles()`](OnvifDevice::profiles).
+    pub fn set_profile(&mut self, profile: String) {
+        self.profile = Some(profile);
+    }
+
+    /// Gets the tokens of the media profiles of the device (usually one per stream, e.g. a main and a sub stream).
+    /// # Errors
+    /// If the device cannot be reached, rejects the credentials or gives a malformed response, this will error.
+    pub fn profiles(&mut self) -> Result<Vec<String>, NokhwaError> {
+        let media_url = self.media_url()?;
+        let response = self.request(
+            &media_url,
+            &format!("<GetProfiles xmlns=\"{}\"/>", MEDIA_NAMESPACE),
+        )?;
+        let profiles: Vec<String> = elements(&response, "Profiles")
+            .into_iter()
+            .filter_map(|(attributes, _)| attribute(attributes, "token"))
+            .map(xml_unescape)
+            .collect();
+        if profiles.is_empty() {
+            return Err(NokhwaError::GetPropertyError {
+                property: "ONVIF Profiles".to_string(),
+                error: "The device has no media profiles".to_string(),
+            });
+        }
+        Ok(profiles)
+    }
+
+    /// Gets the RTSP URI of the stream of the media profile in use. The URI does not contain the credentials.
+    /// # Errors
+    /// If the device cannot be reached, rejects the credentials or gives a malformed response, this will error.
+    pub fn stream_uri(&mut self) -> Result<String, NokhwaError> {
+        let media_url = self.media_url()?;
+        let profile = self.profile_token()?;
+        let response = self.request(
+            &media_url,
+            &format!(
+                "<GetStreamUri xmlns=\"{}\"><StreamSetup><Stream xmlns=\"{schema}\">RTP-Unicast</Stream><Transport xmlns=\"{schema}\"><Protocol>RTSP</Protocol></Transport></StreamSetup><ProfileToken>{}</ProfileToken></GetStreamUri>",
+                MEDIA_NAMESPACE,
+                xml_escape(&profile),
+                schema = SCHEMA_NAMESPACE,
+            ),
+        )?;
+        match element_text(&response, "Uri") {
+            Some(uri) => Ok(xml_unescape(uri)),
+            None => Err(NokhwaError::GetPropertyError {
+                property: "ONVIF Stream URI".to_string(),
+                error: "The response has no URI".to_string(),
+            }),
+        }
+    }
+
+    /// Opens the stream of the media profile in use as a [`NetworkCamera`], with the credentials (if any) put into the RTSP URI.
+    /// # Errors
+    /// If the stream URI cannot be read, or `OpenCV` fails to open it, this will error.
+    #[cfg(feature = "input-ipcam")]
+    pub fn open(&mut self) -> Result<NetworkCamera, NokhwaError> {
+        let uri = self.stream_uri()?;
+        let uri = match (&self.credentials, uri.find("://")) {
+            (Some((username, password)), Some(scheme_end)) => {
+                let (scheme, rest) = uri.split_at(scheme_end + 3);
+                format!(
+                    "{}{}:{}@{}",
+                    scheme,
+                    percent_encode(username),
+                    percent_encode(password),
+                    rest
+                )
+            }
+            _ => uri,
+        };
+        NetworkCamera::new(uri)
+    }
+
+    /// Whether the device has a PTZ (pan/tilt/zoom) service.
+    /// # Errors
+    /// If the device cannot be reached or rejects the credentials, this will error.
+    pub fn has_ptz(&mut self) -> Result<bool, NokhwaError> {
+        self.service_urls()?;
+        Ok(self.ptz_url.is_some())
+    }
+
+    /// Starts moving the PTZ head at the given speeds, until [`stop_ptz()`](OnvifDevice::stop_ptz) is called. `pan` and `tilt` are in `-1.0..=1.0` (left/down to right/up), `zoom` in `-1.0..=1.0` (out to in).
+    /// # Errors
+    /// If the device has no PTZ service, cannot be reached or rejects the command, this will error.
+    pub fn continuous_move(&mut self, pan: f32, tilt: f32, zoom: f32) -> Result<(), NokhwaError> {
+        let ptz_url = self.ptz_url()?;
+        let profile = self.profile_token()?;
+        self.request(
+            &ptz_url,
+            &format!(
+                "<ContinuousMove xmlns=\"{}\"><ProfileToken>{}</ProfileToken><Velocity><PanTilt xmlns=\"{schema}\" x=\"{}\" y=\"{}\"/><Zoom xmlns=\"{schema}\" x=\"{}\"/></Velocity></ContinuousMove>",
+                PTZ_NAMESPACE,
+                xml_escape(&profile),
+                pan.clamp(-1.0, 1.0),
+                tilt.clamp(-1.0, 1.0),
+                zoom.clamp(-1.0, 1.0),
+                schema = SCHEMA_NAMESPACE,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Moves the PTZ head to a position. `pan` and `tilt` are in `-1.0..=1.0` across the range of the head, `zoom` in `0.0..=1.0` (widest to narrowest).
+    /// # Errors
+    /// If the device has no PTZ service, cannot be reached or rejects the command, this will error.
+    pub fn absolute_move(&mut self, pan: f32, tilt: f32, zoom: f32) -> Result<(), NokhwaError> {
+        let ptz_url = self.ptz_url()?;
+        let profile = self.profile_token()?;
+        self.request(
+            &ptz_url,
+            &format!(
+                "<AbsoluteMove xmlns=\"{}\"><ProfileToken>{}</ProfileToken><Position><PanTilt xmlns=\"{schema}\" x=\"{}\" y=\"{}\"/><Zoom xmlns=\"{schema}\" x=\"{}\"/></Position></AbsoluteMove>",
+                PTZ_NAMESPACE,
+                xml_escape(&profile),
+                pan.clamp(-1.0, 1.0),
+                tilt.clamp(-1.0, 1.0),
+                zoom.clamp(0.0, 1.0),
+                schema = SCHEMA_NAMESPACE,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Stops any movement of the PTZ head.
+    /// # Errors
+    /// If the device has no PTZ service, cannot be reached or rejects the command, this will error.
+    pub fn stop_ptz(&mut self) -> Result<(), NokhwaError> {
+        let ptz_url = self.ptz_url()?;
+        let profile = self.profile_token()?;
+        self.request(
+            &ptz_url,
+            &format!(
+                "<Stop xmlns=\"{}\"><ProfileToken>{}</ProfileToken><PanTilt>true</PanTilt><Zoom>true</Zoom></Stop>",
+                PTZ_NAMESPACE,
+                xml_escape(&profile),
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn media_url(&mut self) -> Result<String, NokhwaError> {
+        self.service_urls()?;
+        // the device service doubles as the media service on some devices
+        Ok(self
+            .media_url
+            .clone()
+            .unwrap_or_else(|| self.service_url.clone()))
+    }
+
+    fn ptz_url(&mut self) -> Result<String, NokhwaError> {
+        self.service_urls()?;
+        match &self.ptz_url {
+            Some(url) => Ok(url.clone()),
+            None => Err(NokhwaError::GetPropertyError {
+                property: "ONVIF PTZ".to_string(),
+                error: "The device has no PTZ service".to_string(),
+            }),
+        }
+    }
+
+    fn profile_token(&mut self) -> Result<String, NokhwaError> {
+        if let Some(profile) = &self.profile {
+            return Ok(profile.clone());
+        }
+        let profile = self.profiles()?.remove(0);
+        self.profile = Some(profile.clone());
+        Ok(profile)
+    }
+
+    // reads the media and PTZ service URLs from the device service, once
+    fn service_urls(&mut self) -> Result<(), NokhwaError> {
+        if self.media_url.is_some() {
+            return Ok(());
+        }
+        let service_url = self.service_url.clone();
+        let response = self.request(
+            &service_url,
+            &format!(
+                "<GetCapabilities xmlns=\"{}\"><Category>All</Category></GetCapabilities>",
+                DEVICE_NAMESPACE
+            ),
+        )?;
+        let service = |name: &str| {
+            elements(&response, name)
+                .into_iter()
+                .find_map(|(_, body)| element_text(body, "XAddr"))
+                .map(xml_unescape)
+        };
+        self.media_url = Some(service("Media").unwrap_or_else(|| service_url.clone()));
+        self.ptz_url = service("PTZ");
+        Ok(())
+    }
+
+    // sends a SOAP request with `body`, returning the response
+    fn request(&self, url: &str, body: &str) -> Result<String, NokhwaError> {
+        let envelope = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\"><s:Header>{}</s:Header><s:Body>{}</s:Body></s:Envelope>",
+            self.security_header(),
+            body
+        );
+        let response = http_post(url, &envelope)?;
+        if let Some(reason) = element_text(&response, "Fault").map(|fault| {
+            element_text(fault, "Text")
+                .map_or_else(|| "Unknown SOAP fault".to_string(), xml_unescape)
+        }) {
+            return Err(NokhwaError::GeneralError(format!(
+                "ONVIF device {} returned an error: {}",
+                url, reason
+            )));
+        }
+        Ok(response)
+    }
+
+    // the WS-Security UsernameToken, `base64(sha1(nonce + created + password))`
+    fn security_header(&self) -> String {
+        let (username, password) = match &self.credentials {
+            Some(credentials) => credentials,
+            None => return String::new(),
+        };
+        let nonce = random_bytes();
+        let created = utc_timestamp();
+        let mut hasher = Sha1::new();
+        hasher.update(&nonce);
+        hasher.update(created.as_bytes());
+        hasher.update(password.as_bytes());
+        let digest = hasher.finalize();
+        format!(
+            "<Security s:mustUnderstand=\"1\" xmlns=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd\"><UsernameToken><Username>{}</Username><Password Type=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest\">{}</Password><Nonce EncodingType=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary\">{}</Nonce><Created xmlns=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd\">{}</Created></UsernameToken></Security>",
+            xml_escape(username),
+            base64::encode(digest),
+            base64::encode(nonce),
+            created
+        )
+    }
+}
+
+impl Debug for OnvifDevice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the password is left out, so devices can be logged
+        f.debug_struct("OnvifDevice")
+            .field("info", &self.info)
+            .field("service_url", &self.service_url)
+            .field("scopes", &self.scopes)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .field("media_url", &self.media_url)
+            .field("ptz_url", &self.ptz_url)
+            .field("profile", &self.profile)
+            .finish()
+    }
+}
+
+/// Finds the ONVIF cameras (`NetworkVideoTransmitter`s) on the local network with WS-Discovery, waiting `timeout` for them to answer.
+///
+/// The [`CameraInfo`] of every device has its index set in the order the devices answered.
+/// # Errors
+/// If the discovery probe cannot be sent (e.g. there is no network), this will error. Devices that send malformed answers are left out.
+/// # Quirks
+/// - Discovery is a multicast on the local subnet. Cameras behind a router (or on a VLAN) will not answer, create those with [`OnvifDevice::new()`].
+pub fn discover_onvif_devices(timeout: Duration) -> Result<Vec<OnvifDevice>, NokhwaError> {
+    let socket_error =
+        |why: std::io::Error| NokhwaError::GeneralError(format!("ONVIF discovery failed: {}", why));
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(socket_error)?;
+    let probe = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:a=\"http://schemas.xmlsoap.org/ws/2004/08/addressing\"><s:Header><a:Action s:mustUnderstand=\"1\">http://schemas.xmlsoap.org/ws/2005/04/discovery/Probe</a:Action><a:MessageID>urn:uuid:{}</a:MessageID><a:ReplyTo><a:Address>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:Address></a:ReplyTo><a:To s:mustUnderstand=\"1\">urn:schemas-xmlsoap-org:ws:2005:04:discovery</a:To></s:Header><s:Body><Probe xmlns=\"http://schemas.xmlsoap.org/ws/2005/04/discovery\"><d:Types xmlns:d=\"http://schemas.xmlsoap.org/ws/2005/04/discovery\" xmlns:dn=\"http://www.onvif.org/ver10/network/wsdl\">dn:NetworkVideoTransmitter</d:Types></Probe></s:Body></s:Envelope>",
+        uuid()
+    );
+    socket
+        .send_to(probe.as_bytes(), DISCOVERY_ADDRESS)
+        .map_err(socket_error)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut devices: Vec<OnvifDevice> = vec![];
+    let mut seen = HashSet::new();
+    let mut buffer = vec![0_u8; 65_535];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            break;
+        }
+        socket
+            .set_read_timeout(Some(remaining))
+            .map_err(socket_error)?;
+        let length = match socket.recv_from(&mut buffer) {
+            Ok((length, _)) => length,
+            // timed out (`WouldBlock` on unix, `TimedOut` on windows), or an ICMP error from a previous send
+            Err(_) => continue,
+        };
+        let answer = String::from_utf8_lossy(&buffer[..length]);
+        for (_, probe_match) in elements(&answer, "ProbeMatch") {
+            if let Some(mut device) = device_from_probe_match(probe_match) {
+                let key = device
+                    .info
+                    .group_id()
+                    .cloned()
+                    .unwrap_or_else(|| device.service_url.clone());
+                if seen.insert(key) {
+                    device.info.set_index(devices.len());
+                    devices.push(device);
+                }
+            }
+        }
+    }
+    Ok(devices)
+}
+
+fn device_from_probe_match(probe_match: &str) -> Option<OnvifDevice> {
+    // a device may offer several addresses (e.g. IPv4 and IPv6), the first plain HTTP one is used
+    let service_url = element_text(probe_match, "XAddrs")?
+        .split_whitespace()
+        .find(|address| address.starts_with("http://"))
+        .map(xml_unescape)?;
+    let scopes: Vec<String> = element_text(probe_match, "Scopes")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(xml_unescape)
+        .collect();
+    let scope = |name: &str| {
+        let prefix = format!("onvif://www.onvif.org/{}/", name);
+        scopes
+            .iter()
+            .find_map(|scope| scope.strip_prefix(&prefix))
+            .map(percent_decode)
+    };
+
+    let mut device = OnvifDevice::new(service_url.clone());
+    if let Some(name) = scope("name") {
+        device.info.set_human_name(name);
+    }
+    if let Some(hardware) = scope("hardware") {
+        device.info.set_misc(hardware);
+    }
+    device.info.set_bus_path(Some(service_url));
+    device.info.set_group_id(
+        elements(probe_match, "EndpointReference")
+            .into_iter()
+            .find_map(|(_, body)| element_text(body, "Address"))
+            .map(xml_unescape),
+    );
+    device.scopes = scopes;
+    Some(device)
+}
+
+// a minimal HTTP/1.0 client, so the response is never chunked and ends when the device closes the connection
+fn http_post(url: &str, body: &str) -> Result<String, NokhwaError> {
+    let http_error = |why: std::io::Error| {
+        NokhwaError::GeneralError(format!("ONVIF request to {} failed: {}", url, why))
+    };
+    let rest = match url.strip_prefix("http://") {
+        Some(rest) => rest,
+        None => {
+            return Err(NokhwaError::NotImplementedError(format!(
+                "ONVIF over anything but plain HTTP ({})",
+                url
+            )))
+        }
+    };
+    let (host, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+    let address = if host.contains(':') && !host.ends_with(']') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    let socket_address = match address.to_socket_addrs().map_err(http_error)?.next() {
+        Some(socket_address) => socket_address,
+        None => {
+            return Err(NokhwaError::GeneralError(format!(
+                "ONVIF device {} could not be resolved",
+                host
+            )))
+        }
+    };
+
+    let mut stream =
+        TcpStream::connect_timeout(&socket_address, ONVIF_REQUEST_TIMEOUT).map_err(http_error)?;
+    stream
+        .set_read_timeout(Some(ONVIF_REQUEST_TIMEOUT))
+        .map_err(http_error)?;
+    stream
+        .set_write_timeout(Some(ONVIF_REQUEST_TIMEOUT))
+        .map_err(http_error)?;
+    write!(
+        stream,
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/soap+xml; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )
+    .map_err(http_error)?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).map_err(http_error)?;
+    let response = String::from_utf8_lossy(&response).to_string();
+
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok());
+    let body = match response.find("\r\n\r\n") {
+        Some(end) => response[end + 4..].to_string(),
+        None => String::new(),
+    };
+    match status {
+        Some(200) => Ok(body),
+        // SOAP faults come with a 400 or 500, and are read by the caller
+        Some(400) | Some(500) if body.contains("Fault") => Ok(body),
+        Some(401) => Err(NokhwaError::PermissionDenied {
+            device: url.to_string(),
+            error: "The device rejected the credentials".to_string(),
+        }),
+        Some(status) => Err(NokhwaError::GeneralError(format!(
+            "ONVIF device {} responded with HTTP {}",
+            url, status
+        ))),
+        None => Err(NokhwaError::GeneralError(format!(
+            "ONVIF device {} sent a malformed response",
+            url
+        ))),
+    }
+}
+
+// every element called `name` (in any namespace), as its attributes and its contents. Elements of the same name must not nest
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let tag_end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[..tag_end];
+        let tag_name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .trim_end_matches('/');
+        let local_name = tag_name.rsplit(':').next().unwrap_or(tag_name);
+        if local_name != name || tag.starts_with('/') {
+            continue;
+        }
+        let attributes = tag[tag_name.len()..].trim_end_matches('/');
+        if tag.ends_with('/') {
+            found.push((attributes, ""));
+            rest = &rest[tag_end + 1..];
+            continue;
+        }
+        let body = &rest[tag_end + 1..];
+        let close = format!("</{}>", tag_name);
+        match body.find(&close) {
+            Some(body_end) => {
+                found.push((attributes, &body[..body_end]));
+                rest = &body[body_end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    found
+}
+
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    elements(xml, name)
+        .into_iter()
+        .next()
+        .map(|(_, body)| body.trim())
+}
+
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let start = attributes.find(&format!("{}=\"", name))? + name.len() + 2;
+    let end = attributes[start..].find('"')?;
+    Some(&attributes[start..start + end])
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(feature = "input-ipcam")]
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// not cryptographically random, which is fine for nonces and message IDs
+fn random_bytes() -> [u8; 16] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut hasher = Sha1::new();
+    hasher.update(now.as_nanos().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    let mut bytes = [0_u8; 16];
+    bytes.copy_from_slice(&hasher.finalize()[..16]);
+    bytes
+}
+
+fn uuid() -> String {
+    let bytes = random_bytes();
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+// now as `YYYY-MM-DDTHH:MM:SSZ`, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn utc_timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
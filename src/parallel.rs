@@ -0,0 +1,418 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    utils::{mjpeg_decode, yuyv422_pairs_to_rgb565, yuyv422_pairs_to_rgb888},
+    FrameFormat, NokhwaError, YuvLut,
+};
+use rayon::prelude::*;
+use std::convert::TryFrom;
+
+// how many YUYV pixel pairs each task converts. A bit more than a 1080p row, so tasks are not too small to be worth it
+const YUYV_PAIRS_PER_TASK: usize = 1024;
+
+pub(crate) fn yuyv422_to_rgb888(yuyv: &[u8], rgb: &mut [u8], lut: &YuvLut) {
+    yuyv.par_chunks(YUYV_PAIRS_PER_TASK * 4)
+        .zip(rgb.par_chunks_mut(YUYV_PAIRS_PER_TASK * 6))
+        .for_each(|(yuyv, rgb)| yuyv422_pairs_to_rgb888(yuyv, rgb, lut));
+}
+
+pub(crate) fn yuyv422_to_rgb565(yuyv: &[u8], rgb565: &mut [u16], lut: &YuvLut) {
+    yuyv.par_chunks(YUYV_PAIRS_PER_TASK * 4)
+        .zip(rgb565.par_chunks_mut(YUYV_PAIRS_PER_TASK * 2))
+        .for_each(|(yuyv, rgb565)| yuyv422_pairs_to_rgb565(yuyv, rgb565, lut));
+}
+
+// Every restart marker in a JPEG resets the entropy decoder, so the data between them can be decoded on its own.
+// When the restart interval is a whole number of MCU rows, the JPEG is split into strips of rows, each a JPEG of its own
+// (the same headers, with the height of the strip), which are decoded on separate threads and stacked.
+// Returns `None` if the JPEG cannot be split, so the caller decodes it in one go.
+pub(crate) fn mjpeg_to_rgb888(data: &[u8]) -> Option<Result<Vec<u8>, NokhwaError>> {
+    let layout = JpegLayout::parse(data)?;
+    let strips = layout.strips(rayon::current_num_threads());
+    if strips.len() < 2 {
+        return None;
+    }
+
+    let decoded = strips
+        .par_iter()
+        .map(|strip| {
+            let pixels = mjpeg_decode(&strip.jpeg)?;
+            if pixels.len() == usize::from(layout.width) * usize::from(strip.height) {
+                Ok(pixels)
+            } else {
+                Err(NokhwaError::ProcessFrameError {
+                    src: FrameFormat::MJPEG,
+                    destination: "RGB888".to_string(),
+                    error: "A strip of the JPEG decoded to the wrong size".to_string(),
+                })
+            }
+        })
+        .collect::<Result<Vec<_>, NokhwaError>>();
+
+    Some(decoded.map(|decoded| {
+        let mut rgb = crate::buffer_pool::take_buffer(
+            usize::from(layout.width) * usize::from(layout.height) * 3,
+        );
+        for pixel in decoded.iter().flatten() {
+            rgb.extend_from_slice(pixel);
+        }
+        rgb
+    }))
+}
+
+struct JpegStrip {
+    jpeg: Vec<u8>,
+    height: u16,
+}
+
+struct JpegLayout<'a> {
+    // everything up to the start of the scan
+    headers: &'a [u8],
+    // where the height is in `headers`
+    height_offset: usize,
+    width: u16,
+    height: u16,
+    // the entropy coded data between the restart markers
+    intervals: Vec<&'a [u8]>,
+    // in pixels
+    rows_per_interval: usize,
+}
+
+impl<'a> JpegLayout<'a> {
+    // Only baseline (and extended) sequential JPEGs with one interleaved scan are split, which is what cameras make.
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.get(0..2)? != [0xFF, 0xD8] {
+            return None;
+        }
+
+        let mut pos = 2;
+        let mut frame = None;
+        let mut restart_interval = 0;
+        let scan_start = loop {
+            if *data.get(pos)? != 0xFF {
+                return None;
+            }
+            let marker = *data.get(pos + 1)?;
+            match marker {
+                // fill bytes
+                0xFF => {
+                    pos += 1;
+                    continue;
+                }
+                0x01 | 0xD0..=0xD7 => {
+                    pos += 2;
+                    continue;
+                }
+                _ => {}
+            }
+            let length = usize::from(u16::from_be_bytes([
+                *data.get(pos + 2)?,
+                *data.get(pos + 3)?,
+            ]));
+            let segment = data.get(pos + 4..pos + 2 + length)?;
+            match marker {
+                0xC0 | 0xC1 => frame = Some((pos + 5, FrameHeader::parse(segment)?)),
+                // progressive, lossless and arithmetic coded JPEGs
+                0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+                0xDD => {
+                    restart_interval = u16::from_be_bytes([*segment.first()?, *segment.get(1)?])
+                }
+                0xDA => {
+                    let (_, header) = frame.as_ref()?;
+                    // a scan with only some of the components means there are more scans
+                    if *segment.first()? != header.components {
+                        return None;
+                    }
+                    break pos + 2 + length;
+                }
+                0xD9 => return None,
+                _ => {}
+            }
+            pos += 2 + length;
+        };
+        let (height_offset, header) = frame?;
+        if restart_interval == 0 || header.width == 0 || header.height == 0 {
+            return None;
+        }
+
+        let mcus_per_row = (u32::from(header.width) + header.mcu_width - 1) / header.mcu_width;
+        let mcu_rows = (u32::from(header.height) + header.mcu_height - 1) / header.mcu_height;
+        if u32::from(restart_interval) % mcus_per_row != 0 {
+            return None;
+        }
+        let rows_per_interval = u32::from(restart_interval) / mcus_per_row;
+
+        let mut intervals = Vec::new();
+        let mut interval_start = scan_start;
+        let mut pos = scan_start;
+        loop {
+            pos += data.get(pos..)?.iter().position(|byte| *byte == 0xFF)?;
+            match *data.get(pos + 1)? {
+                // a stuffed 0xFF, or fill bytes
+                0x00 | 0xFF => pos += 1,
+                0xD0..=0xD7 => {
+                    intervals.push(&data[interval_start..pos]);
+                    pos += 2;
+                    interval_start = pos;
+                }
+                0xD9 => {
+                    intervals.push(&data[interval_start..pos]);
+                    break;
+                }
+                _ => return None,
+            }
+        }
+
+        let expected_intervals = (mcu_rows + rows_per_interval - 1) / rows_per_interval;
+        if usize::try_from(expected_intervals).ok()? != intervals.len() {
+            return None;
+        }
+
+        Some(JpegLayout {
+            headers: &data[..scan_start],
+            height_offset,
+            width: header.width,
+            height: header.height,
+            intervals,
+            rows_per_interval: usize::try_from(rows_per_interval * header.mcu_height).ok()?,
+        })
+    }
+
+    // splits the JPEG into at most `count` strips of whole restart intervals
+    fn strips(&self, count: usize) -> Vec<JpegStrip> {
+        let count = count.max(1);
+        let per_strip = ((self.intervals.len() + count - 1) / count).max(1);
+        self.intervals
+            .chunks(per_strip)
+            .enumerate()
+            .map(|(idx, intervals)| {
+                let first = idx * per_strip;
+                let top = first * self.rows_per_interval;
+                let bottom = ((first + intervals.len()) * self.rows_per_interval)
+                    .min(usize::from(self.height));
+                // the strip is never taller than the frame
+                let height = u16::try_from(bottom - top).unwrap_or(self.height);
+
+                let mut jpeg = Vec::with_capacity(
+                    self.headers.len() + intervals.iter().map(|i| i.len() + 2).sum::<usize>() + 2,
+                );
+                jpeg.extend_from_slice(self.headers);
+                jpeg[self.height_offset..self.height_offset + 2]
+                    .copy_from_slice(&height.to_be_bytes());
+                for (restart, interval) in intervals.iter().enumerate() {
+                    if restart != 0 {
+                        // restart markers count up from RST0 again in every strip
+                        #[allow(clippy::cast_possible_truncation)]
+                        let marker = 0xD0 + ((restart - 1) % 8) as u8;
+                        jpeg.extend_from_slice(&[0xFF, marker]);
+                    }
+                    jpeg.extend_from_slice(interval);
+                }
+                jpeg.extend_from_slice(&[0xFF, 0xD9]);
+                JpegStrip { jpeg, height }
+            })
+            .collect()
+    }
+}
+
+struct FrameHeader {
+    width: u16,
+    height: u16,
+    components: u8,
+    mcu_width: u32,
+    mcu_height: u32,
+}
+
+impl FrameHeader {
+    // `segment` is the SOF segment after its length
+    fn parse(segment: &[u8]) -> Option<Self> {
+        let height = u16::from_be_bytes([*segment.get(1)?, *segment.get(2)?]);
+        let width = u16::from_be_bytes([*segment.get(3)?, *segment.get(4)?]);
+        let components = *segment.get(5)?;
+        if components == 0 {
+            return None;
+        }
+        let (mut max_h, mut max_v) = (1, 1);
+        for component in segment
+            .get(6..6 + usize::from(components) * 3)?
+            .chunks_exact(3)
+        {
+            max_h = max_h.max(u32::from(component[1] >> 4));
+            max_v = max_v.max(u32::from(component[1] & 0x0F));
+        }
+        // a single component scan is not interleaved, so its MCUs are single 8x8 blocks
+        if components == 1 {
+            max_h = 1;
+            max_v = 1;
+        }
+        Some(FrameHeader {
+            width,
+            height,
+            components,
+            mcu_width: 8 * max_h,
+            mcu_height: 8 * max_v,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JpegLayout;
+    use crate::utils::mjpeg_decode;
+
+    // the codes of DC categories 0 to 7 in the example luminance DC table of the JPEG spec, and their lengths
+    const DC_CODES: [(u32, u32); 8] = [
+        (0b00, 2),
+        (0b010, 3),
+        (0b011, 3),
+        (0b100, 3),
+        (0b101, 3),
+        (0b110, 3),
+        (0b1110, 4),
+        (0b11110, 5),
+    ];
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        current: u8,
+        count: u32,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, value: u32, length: u32) {
+            for bit in (0..length).rev() {
+                #[allow(clippy::cast_possible_truncation)]
+                let bit = ((value >> bit) & 1) as u8;
+                self.current = (self.current << 1) | bit;
+                self.count += 1;
+                if self.count == 8 {
+                    self.bytes.push(self.current);
+                    if self.current == 0xFF {
+                        self.bytes.push(0x00);
+                    }
+                    self.current = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        // pads the last byte with ones, like an encoder does before a marker
+        fn flush(&mut self) {
+            while self.count != 0 {
+                self.put(1, 1);
+            }
+        }
+    }
+
+    fn segment(jpeg: &mut Vec<u8>, marker: u8, data: &[u8]) {
+        #[allow(clippy::cast_possible_truncation)]
+        let length = (data.len() + 2) as u16;
+        jpeg.extend_from_slice(&[0xFF, marker]);
+        jpeg.extend_from_slice(&length.to_be_bytes());
+        jpeg.extend_from_slice(data);
+    }
+
+    // A YCbCr JPEG (`sof` is the frame marker) where every 8x8 block is flat, and the luma changes from block to block.
+    // There is a restart marker every `restart_interval` MCUs, or none if it is 0.
+    fn test_jpeg(width: u16, height: u16, restart_interval: u16, sof: u8) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        let mut quantization = vec![0x00];
+        quantization.extend_from_slice(&[8; 64]);
+        segment(&mut jpeg, 0xDB, &quantization);
+        let [height_hi, height_lo] = height.to_be_bytes();
+        let [width_hi, width_lo] = width.to_be_bytes();
+        segment(
+            &mut jpeg,
+            sof,
+            &[
+                8, height_hi, height_lo, width_hi, width_lo, 3, 1, 0x11, 0, 2, 0x11, 0, 3, 0x11, 0,
+            ],
+        );
+        let mut dc_table = vec![0x00, 0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+        dc_table.extend(0..12);
+        segment(&mut jpeg, 0xC4, &dc_table);
+        // only the end of block code, every block is flat
+        segment(
+            &mut jpeg,
+            0xC4,
+            &[0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        );
+        if restart_interval != 0 {
+            segment(&mut jpeg, 0xDD, &restart_interval.to_be_bytes());
+        }
+        segment(&mut jpeg, 0xDA, &[3, 1, 0x00, 2, 0x00, 3, 0x00, 0, 63, 0]);
+
+        let mcus_per_row = (usize::from(width) + 7) / 8;
+        let mcu_rows = (usize::from(height) + 7) / 8;
+        let mut scan = BitWriter::default();
+        let mut predictors = [0_i32; 3];
+        for mcu in 0..mcus_per_row * mcu_rows {
+            let interval = usize::from(restart_interval);
+            if interval != 0 && mcu != 0 && mcu % interval == 0 {
+                scan.flush();
+                #[allow(clippy::cast_possible_truncation)]
+                let marker = 0xD0 + ((mcu / interval - 1) % 8) as u8;
+                scan.bytes.extend_from_slice(&[0xFF, marker]);
+                predictors = [0; 3];
+            }
+            #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+            let luma = ((mcu % mcus_per_row) * 7 + (mcu / mcus_per_row) * 13) as i32 % 64 - 32;
+            for (component, predictor) in predictors.iter_mut().enumerate() {
+                let dc = if component == 0 { luma } else { 0 };
+                let diff = dc - *predictor;
+                *predictor = dc;
+                let category = 32 - diff.unsigned_abs().leading_zeros();
+                let (code, length) = DC_CODES[category as usize];
+                scan.put(code, length);
+                // negative values are stored as `diff - 1` in `category` bits
+                #[allow(clippy::cast_sign_loss)]
+                let bits = (if diff < 0 { diff - 1 } else { diff }) as u32;
+                scan.put(bits & ((1 << category) - 1), category);
+                scan.put(0, 1);
+            }
+        }
+        scan.flush();
+        jpeg.extend_from_slice(&scan.bytes);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn strips_decode_like_one_pass() {
+        // one MCU row per interval, and two MCU rows per interval with a partial last row
+        for &(width, height, restart_interval) in &[(32, 64, 4), (32, 60, 8)] {
+            let jpeg = test_jpeg(width, height, restart_interval, 0xC0);
+            let whole = mjpeg_decode(&jpeg).unwrap();
+
+            let layout = JpegLayout::parse(&jpeg).unwrap();
+            let strips = layout.strips(3);
+            assert!(strips.len() > 1);
+            assert_eq!(strips.iter().map(|strip| strip.height).sum::<u16>(), height);
+            let stacked = strips
+                .iter()
+                .flat_map(|strip| mjpeg_decode(&strip.jpeg).unwrap())
+                .collect::<Vec<[u8; 3]>>();
+            assert_eq!(stacked, whole);
+        }
+    }
+
+    #[test]
+    fn unsplittable_jpegs_are_not_parsed() {
+        // no restart interval
+        assert!(JpegLayout::parse(&test_jpeg(32, 64, 0, 0xC0)).is_none());
+        // a restart interval of one and a half MCU rows
+        assert!(JpegLayout::parse(&test_jpeg(32, 64, 6, 0xC0)).is_none());
+        // progressive
+        assert!(JpegLayout::parse(&test_jpeg(32, 64, 4, 0xC2)).is_none());
+
+        let jpeg = test_jpeg(32, 64, 4, 0xC0);
+        // cut off in the headers, and in the scan
+        assert!(JpegLayout::parse(&jpeg[..40]).is_none());
+        assert!(JpegLayout::parse(&jpeg[..jpeg.len() - 8]).is_none());
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::NokhwaError;
+use std::fmt::{Display, Formatter};
+
+/// Whether this program is allowed to use the cameras of the system, see [`camera_permission_status()`]. Useful to tell the user *why* no cameras were found.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum CameraPermission {
+    /// The program may use the cameras.
+    Granted,
+    /// The user denied camera access to this program (or to all desktop programs).
+    Denied,
+    /// Camera access is blocked by something the user cannot change, such as parental controls, an MDM profile or a system-wide policy.
+    Restricted,
+    /// The user was not asked yet. [`request_camera_permission()`] asks.
+    NotDetermined,
+    /// The platform cannot tell.
+    Unknown,
+}
+
+impl Display for CameraPermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Gets whether this program is allowed to use the cameras, without asking the user.
+/// # Quirks
+/// - macOS: This is the `AVCaptureDevice` authorization status (TCC). Note that on macOS 10.13 and older, and for programs started from a terminal, the terminal's permission is what counts.
+/// - Windows: This reads the camera privacy settings ("Let apps access your camera" and "Let desktop apps access your camera"). A system-wide block by policy is [`Restricted`](CameraPermission::Restricted). Windows versions without camera privacy settings are always [`Granted`](CameraPermission::Granted).
+/// - Linux: There is no permission system, so this checks whether the `/dev/video*` nodes can be opened. If none can because of their file permissions (the user is usually missing from the `video` group), this is [`Denied`](CameraPermission::Denied). Without any nodes, this is [`Unknown`](CameraPermission::Unknown).
+/// - WASM: The browser cannot be asked synchronously, so this is always [`Unknown`](CameraPermission::Unknown). Await [`js_camera::permission_status()`](crate::js_camera::permission_status) instead.
+/// - Other platforms are always [`Unknown`](CameraPermission::Unknown).
+#[must_use]
+pub fn camera_permission_status() -> CameraPermission {
+    platform::permission_status()
+}
+
+/// Asks the user for permission to use the cameras if they were not asked yet, and returns the new [`CameraPermission`]. If they were already asked, this returns the current status without asking again.
+/// # Quirks
+/// - macOS: This shows the system prompt and blocks until the user answers it. The program must have an `NSCameraUsageDescription` in its `Info.plist`, otherwise macOS kills it.
+/// - Windows: Desktop programs cannot show a prompt, so this only returns the [status](camera_permission_status). If it is [`Denied`](CameraPermission::Denied), point the user to `ms-settings:privacy-webcam`.
+/// - Linux: There is nothing to ask, so this only returns the [status](camera_permission_status).
+/// - WASM: This starts [`js_camera::request_permission()`](crate::js_camera::request_permission), which shows the browser prompt, and returns [`NotDetermined`](CameraPermission::NotDetermined) right away since it cannot wait for the answer. Await `js_camera::request_permission()` yourself to know it.
+/// # Errors
+/// If the platform fails to ask (e.g. there is no valid web context on WASM), this will error.
+pub fn request_camera_permission() -> Result<CameraPermission, NokhwaError> {
+    platform::request_permission()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::CameraPermission;
+    use crate::NokhwaError;
+    use std::{
+        ffi::c_void,
+        os::raw::{c_char, c_int, c_ulong},
+        sync::{Condvar, Mutex},
+    };
+
+    type Id = *mut c_void;
+    type Sel = *const c_void;
+
+    // `AVAuthorizationStatus`
+    const AV_AUTHORIZATION_NOT_DETERMINED: isize = 0;
+    const AV_AUTHORIZATION_RESTRICTED: isize = 1;
+    const AV_AUTHORIZATION_DENIED: isize = 2;
+    const AV_AUTHORIZATION_AUTHORIZED: isize = 3;
+
+    const BLOCK_IS_GLOBAL: c_int = 1 << 28;
+
+    #[link(name = "objc")]
+    extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn sel_registerName(name: *const c_char) -> Sel;
+        fn objc_msgSend();
+    }
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeVideo: Id;
+    }
+
+    #[link(name = "System", kind = "dylib")]
+    extern "C" {
+        static _NSConcreteGlobalBlock: c_void;
+    }
+
+    // the clang block ABI, just enough for a completion handler that captures nothing
+    #[repr(C)]
+    struct BlockDescriptor {
+        reserved: c_ulong,
+        size: c_ulong,
+    }
+
+    #[repr(C)]
+    struct Block {
+        isa: *const c_void,
+        flags: c_int,
+        reserved: c_int,
+        invoke: unsafe extern "C" fn(*mut Block, u8),
+        descriptor: *const BlockDescriptor,
+    }
+
+    static ANSWER: Mutex<Option<bool>> = Mutex::new(None);
+    static ANSWERED: Condvar = Condvar::new();
+
+    // called by AVFoundation on a queue of its own
+    unsafe extern "C" fn access_answered(_block: *mut Block, granted: u8) {
+        let mut answer = match ANSWER.lock() {
+            Ok(answer) => answer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        *answer = Some(granted != 0);
+        ANSWERED.notify_all();
+    }
+
+    fn capture_device_class() -> Id {
+        unsafe { objc_getClass(b"AVCaptureDevice\0".as_ptr().cast()) }
+    }
+
+    fn authorization_status() -> isize {
+        unsafe {
+            let class = capture_device_class();
+            if class.is_null() {
+                return -1;
+            }
+            let send: unsafe extern "C" fn(Id, Sel, Id) -> isize =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(
+                class,
+                sel_registerName(b"authorizationStatusForMediaType:\0".as_ptr().cast()),
+                AVMediaTypeVideo,
+            )
+        }
+    }
+
+    fn to_permission(status: isize) -> CameraPermission {
+        match status {
+            AV_AUTHORIZATION_NOT_DETERMINED => CameraPermission::NotDetermined,
+            AV_AUTHORIZATION_RESTRICTED => CameraPermission::Restricted,
+            AV_AUTHORIZATION_DENIED => CameraPermission::Denied,
+            AV_AUTHORIZATION_AUTHORIZED => CameraPermission::Granted,
+            _ => CameraPermission::Unknown,
+        }
+    }
+
+    pub(super) fn permission_status() -> CameraPermission {
+        to_permission(authorization_status())
+    }
+
+    pub(super) fn request_permission() -> Result<CameraPermission, NokhwaError> {
+        let status = permission_status();
+        if status != CameraPermission::NotDetermined {
+            return Ok(status);
+        }
+
+        match ANSWER.lock() {
+            Ok(mut answer) => *answer = None,
+            Err(poisoned) => *poisoned.into_inner() = None,
+        }
+
+        // a global block is never copied nor freed, so it is leaked to outlive the request
+        let descriptor: &'static BlockDescriptor = Box::leak(Box::new(BlockDescriptor {
+            reserved: 0,
+            size: std::mem::size_of::<Block>() as c_ulong,
+        }));
+        let block: &'static mut Block = Box::leak(Box::new(Block {
+            isa: unsafe { std::ptr::addr_of!(_NSConcreteGlobalBlock) },
+            flags: BLOCK_IS_GLOBAL,
+            reserved: 0,
+            invoke: access_answered,
+            descriptor,
+        }));
+
+        unsafe {
+            let class = capture_device_class();
+            if class.is_null() {
+                return Err(NokhwaError::GeneralError(
+                    "AVFoundation is not available".to_string(),
+                ));
+            }
+            let send: unsafe extern "C" fn(Id, Sel, Id, *mut Block) =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(
+                class,
+                sel_registerName(
+                    b"requestAccessForMediaType:completionHandler:\0"
+                        .as_ptr()
+                        .cast(),
+                ),
+                AVMediaTypeVideo,
+                block,
+            );
+        }
+
+        // the handler may run before we get here, so it is not missed
+        let mut answer = match ANSWER.lock() {
+            Ok(answer) => answer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        while answer.is_none() {
+            answer = match ANSWERED.wait(answer) {
+                Ok(answer) => answer,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        drop(answer);
+        Ok(permission_status())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::CameraPermission;
+    use crate::NokhwaError;
+    use std::{ffi::c_void, os::windows::ffi::OsStrExt};
+
+    type Hkey = isize;
+
+    #[allow(clippy::cast_possible_wrap)]
+    const HKEY_CURRENT_USER: Hkey = 0x8000_0001_u32 as i32 as isize;
+    #[allow(clippy::cast_possible_wrap)]
+    const HKEY_LOCAL_MACHINE: Hkey = 0x8000_0002_u32 as i32 as isize;
+    const RRF_RT_REG_SZ: u32 = 0x0000_0002;
+    const ERROR_SUCCESS: i32 = 0;
+
+    const WEBCAM_CONSENT: &str =
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\webcam";
+    const WEBCAM_CONSENT_DESKTOP: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\webcam\\NonPackaged";
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegGetValueW(
+            key: Hkey,
+            sub_key: *const u16,
+            value: *const u16,
+            flags: u32,
+            value_type: *mut u32,
+            data: *mut c_void,
+            data_len: *mut u32,
+        ) -> i32;
+    }
+
+    fn wide(string: &str) -> Vec<u16> {
+        std::ffi::OsStr::new(string)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    // the consent store holds "Allow" or "Deny", the key is missing before Windows 10 1803
+    #[allow(clippy::cast_possible_truncation)]
+    fn consent(key: Hkey, sub_key: &str) -> Option<bool> {
+        let sub_key = wide(sub_key);
+        let value = wide("Value");
+        let mut data = [0_u16; 16];
+        let mut data_len = (data.len() * std::mem::size_of::<u16>()) as u32;
+        let result = unsafe {
+            RegGetValueW(
+                key,
+                sub_key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                data.as_mut_ptr().cast(),
+                &mut data_len,
+            )
+        };
+        if result != ERROR_SUCCESS {
+            return None;
+        }
+        let text = String::from_utf16_lossy(&data);
+        Some(!text.trim_end_matches('\0').eq_ignore_ascii_case("Deny"))
+    }
+
+    pub(super) fn permission_status() -> CameraPermission {
+        if consent(HKEY_LOCAL_MACHINE, WEBCAM_CONSENT) == Some(false) {
+            return CameraPermission::Restricted;
+        }
+        if consent(HKEY_CURRENT_USER, WEBCAM_CONSENT) == Some(false)
+            || consent(HKEY_CURRENT_USER, WEBCAM_CONSENT_DESKTOP) == Some(false)
+        {
+            return CameraPermission::Denied;
+        }
+        CameraPermission::Granted
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(super) fn request_permission() -> Result<CameraPermission, NokhwaError> {
+        Ok(permission_status())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::CameraPermission;
+    use crate::NokhwaError;
+    use std::{fs::OpenOptions, io::ErrorKind};
+
+    pub(super) fn permission_status() -> CameraPermission {
+        let nodes = match std::fs::read_dir("/dev") {
+            Ok(nodes) => nodes,
+            Err(_) => return CameraPermission::Unknown,
+        };
+        let mut denied = false;
+        for node in nodes.filter_map(Result::ok) {
+            if !node.file_name().to_string_lossy().starts_with("video") {
+                continue;
+            }
+            // opening a node does not turn the camera on
+            match OpenOptions::new().read(true).write(true).open(node.path()) {
+                Ok(_) => return CameraPermission::Granted,
+                Err(why) if why.kind() == ErrorKind::PermissionDenied => denied = true,
+                Err(_) => {}
+            }
+        }
+        if denied {
+            CameraPermission::Denied
+        } else {
+            CameraPermission::Unknown
+        }
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(super) fn request_permission() -> Result<CameraPermission, NokhwaError> {
+        Ok(permission_status())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "input-jscam"))]
+mod platform {
+    use super::CameraPermission;
+    use crate::{js_camera, NokhwaError};
+
+    pub(super) fn permission_status() -> CameraPermission {
+        CameraPermission::Unknown
+    }
+
+    pub(super) fn request_permission() -> Result<CameraPermission, NokhwaError> {
+        // the prompt shows up even if nobody awaits the promise
+        drop(js_camera::request_permission()?);
+        Ok(CameraPermission::NotDetermined)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "macos",
+    windows,
+    target_os = "linux",
+    all(target_arch = "wasm32", feature = "input-jscam")
+)))]
+mod platform {
+    use super::CameraPermission;
+    use crate::NokhwaError;
+
+    pub(super) fn permission_status() -> CameraPermission {
+        CameraPermission::Unknown
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(super) fn request_permission() -> Result<CameraPermission, NokhwaError> {
+        Ok(CameraPermission::Unknown)
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    Camera, CameraFormat, CameraInfo, CaptureAPIBackend, CaptureBackendTrait, NokhwaError,
+};
+use std::sync::RwLock;
+
+/// Creates a capture backend for the camera at `index`, with an optional [`CameraFormat`]. See [`Camera::new()`].
+pub type PluginCreateFn =
+    fn(usize, Option<CameraFormat>) -> Result<Box<dyn CaptureBackendTrait>, NokhwaError>;
+
+/// Lists the cameras the plugin's backend can open. See [`query_devices()`](crate::query_devices).
+pub type PluginQueryFn = fn() -> Result<Vec<CameraInfo>, NokhwaError>;
+
+struct RegisteredPlugin {
+    name: String,
+    create: PluginCreateFn,
+    query: PluginQueryFn,
+}
+
+static PLUGINS: RwLock<Vec<RegisteredPlugin>> = RwLock::new(Vec::new());
+
+fn plugin_error(error: String) -> NokhwaError {
+    NokhwaError::InitializeError {
+        backend: CaptureAPIBackend::Custom,
+        error,
+    }
+}
+
+/// Registers a backend that is not part of `nokhwa` (e.g. one in a crate of its own) under `name`, so `nokhwa` can pick it like a built-in one.
+///
+/// Once registered, [`CaptureAPIBackend::Auto`] (in [`Camera::new()`] and [`query_devices()`](crate::query_devices)) tries the plugins in the order they were registered, after every built-in backend.
+/// To open a camera with one specific plugin, use [`open_plugin_camera()`] or [`CameraBuilder::plugin()`](crate::CameraBuilder::plugin).
+///
+/// ```.ignore
+/// fn create(index: usize, format: Option<CameraFormat>) -> Result<Box<dyn CaptureBackendTrait>, NokhwaError> {
+///     Ok(Box::new(MyCaptureDevice::new(index, format)?))
+/// }
+///
+/// nokhwa::register_plugin("my-backend", create, my_query_devices)?;
+/// ```
+/// # Quirks
+/// - Plugins are linked into the program at compile time. Loading backends from dynamic libraries is not supported, as Rust has no stable ABI for the trait objects and errors they pass around,
+///   and a library would get its own copy of `nokhwa`'s global state (the backend priority, quirks and buffer pool).
+/// # Errors
+/// If a plugin with the same name is already registered, this will error.
+pub fn register_plugin(
+    name: &str,
+    create: PluginCreateFn,
+    query: PluginQueryFn,
+) -> Result<(), NokhwaError> {
+    let mut plugins = match PLUGINS.write() {
+        Ok(plugins) => plugins,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if plugins.iter().any(|plugin| plugin.name == name) {
+        return Err(plugin_error(format!(
+            "A plugin named {} is already registered",
+            name
+        )));
+    }
+    plugins.push(RegisteredPlugin {
+        name: name.to_string(),
+        create,
+        query,
+    });
+    Ok(())
+}
+
+/// The names of all registered plugins, in the order they were registered.
+#[must_use]
+pub fn registered_plugins() -> Vec<String> {
+    match PLUGINS.read() {
+        Ok(plugins) => plugins.iter().map(|plugin| plugin.name.clone()).collect(),
+        Err(poisoned) => poisoned
+            .into_inner()
+            .iter()
+            .map(|plugin| plugin.name.clone())
+            .collect(),
+    }
+}
+
+fn plugin_fns(name: &str) -> Result<(PluginCreateFn, PluginQueryFn), NokhwaError> {
+    let plugins = match PLUGINS.read() {
+        Ok(plugins) => plugins,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    match plugins.iter().find(|plugin| plugin.name == name) {
+        Some(plugin) => Ok((plugin.create, plugin.query)),
+        None => Err(plugin_error(format!(
+            "No plugin named {} is registered",
+            name
+        ))),
+    }
+}
+
+// the functions of every registered plugin, in the order they were registered, for `CaptureAPIBackend::Auto`
+pub(crate) fn registered_plugin_fns() -> Vec<(PluginCreateFn, PluginQueryFn)> {
+    let plugins = match PLUGINS.read() {
+        Ok(plugins) => plugins,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    plugins
+        .iter()
+        .map(|plugin| (plugin.create, plugin.query))
+        .collect()
+}
+
+/// Lists the cameras the plugin `name` can open.
+/// # Errors
+/// If no plugin with this name is registered or its query fails, this will error.
+pub fn query_plugin_devices(name: &str) -> Result<Vec<CameraInfo>, NokhwaError> {
+    let (_, query) = plugin_fns(name)?;
+    query()
+}
+
+/// Opens the camera at `index` with the plugin `name`, as a [`Camera`]. `format` can be `None`.
+///
+/// The camera's [`backend()`](Camera::backend) is whatever the plugin reports, usually [`CaptureAPIBackend::Custom`].
+/// # Errors
+/// If no plugin with this name is registered or it fails to create the camera, this will error.
+pub fn open_plugin_camera(
+    name: &str,
+    index: usize,
+    format: Option<CameraFormat>,
+) -> Result<Camera, NokhwaError> {
+    let (create, _) = plugin_fns(name)?;
+    Ok(Camera::from_backend(create(index, format)?))
+}
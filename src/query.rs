@@ -4,12 +4,20 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use crate::{CameraInfo, CaptureAPIBackend, NokhwaError};
+use crate::{
+    backend_priority, Camera, CameraAvailability, CameraFormat, CameraInfo, CaptureAPIBackend,
+    DeviceCapabilities, NokhwaError,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    thread,
+    time::{Duration, Instant},
+};
 
 // TODO: Update as this goes
 /// Query the system for a list of available devices. Please refer to the API Backends that support `Query`) <br>
 /// Currently, these are `V4L`, `MediaFoundation`, `UVC`, and `GST`. <br>
-/// Usually the order goes Native -> UVC -> Gstreamer.
+/// With [`CaptureAPIBackend::Auto`], the backends are tried in the order of [`backend_priority()`](crate::backend_priority), which usually goes Native -> UVC -> Gstreamer, followed by any registered plugins.
 /// # Quirks
 /// - Media Foundation: The symbolic link for the device is listed in the `misc` attribute of the [`CameraInfo`].
 /// - Media Foundation: The names may contain invalid characters since they were converted from UTF16.
@@ -20,51 +28,28 @@ use crate::{CameraInfo, CaptureAPIBackend, NokhwaError};
 pub fn query_devices(api: CaptureAPIBackend) -> Result<Vec<CameraInfo>, NokhwaError> {
     match api {
         CaptureAPIBackend::Auto => {
-            // determine platform
-            match std::env::consts::OS {
-                "linux" => {
-                    if cfg!(feature = "input-v4l") {
-                        query_devices(CaptureAPIBackend::Video4Linux)
-                    } else if cfg!(feature = "input-uvc") {
-                        query_devices(CaptureAPIBackend::UniversalVideoClass)
-                    } else if cfg!(feature = "input-gstreamer") {
-                        query_devices(CaptureAPIBackend::GStreamer)
-                    } else {
-                        Err(NokhwaError::UnsupportedOperationError(
-                            CaptureAPIBackend::Auto,
-                        ))
-                    }
+            // the first backend in the priority list that can query wins
+            let mut error = NokhwaError::UnsupportedOperationError(CaptureAPIBackend::Auto);
+            for backend in backend_priority() {
+                if backend == CaptureAPIBackend::Auto {
+                    continue;
                 }
-                "windows" => {
-                    if cfg!(feature = "input-msmf") {
-                        query_devices(CaptureAPIBackend::MediaFoundation)
-                    } else if cfg!(feature = "input-uvc") {
-                        query_devices(CaptureAPIBackend::UniversalVideoClass)
-                    } else if cfg!(feature = "input-gstreamer") {
-                        query_devices(CaptureAPIBackend::GStreamer)
-                    } else {
-                        Err(NokhwaError::UnsupportedOperationError(
-                            CaptureAPIBackend::Auto,
-                        ))
-                    }
+                match query_devices(backend) {
+                    Ok(devices) => return Ok(devices),
+                    Err(NokhwaError::UnsupportedOperationError(_)) => {}
+                    Err(why) => error = why,
                 }
-                "macos" => {
-                    if cfg!(feature = "input-avfoundation") {
-                        query_devices(CaptureAPIBackend::AVFoundation)
-                    } else if cfg!(feature = "input-uvc") {
-                        query_devices(CaptureAPIBackend::UniversalVideoClass)
-                    } else if cfg!(feature = "input-gstreamer") {
-                        query_devices(CaptureAPIBackend::GStreamer)
-                    } else {
-                        Err(NokhwaError::UnsupportedOperationError(
-                            CaptureAPIBackend::Auto,
-                        ))
-                    }
+            }
+            // registered plugins come after every built-in backend, like in `Camera::new()`
+            #[cfg(feature = "plugins")]
+            for (_, query) in crate::plugin::registered_plugin_fns() {
+                match query() {
+                    Ok(devices) => return Ok(devices),
+                    Err(NokhwaError::UnsupportedOperationError(_)) => {}
+                    Err(why) => error = why,
                 }
-                _ => Err(NokhwaError::UnsupportedOperationError(
-                    CaptureAPIBackend::Auto,
-                )),
             }
+            Err(error)
         }
         CaptureAPIBackend::Video4Linux => query_v4l(),
         CaptureAPIBackend::UniversalVideoClass => query_uvc(),
@@ -74,6 +59,320 @@ pub fn query_devices(api: CaptureAPIBackend) -> Result<Vec<CameraInfo>, NokhwaEr
     }
 }
 
+/// Query the system for the names of available devices, without opening any of them. <br>
+/// The names are in the same order as [`query_devices()`]. Use this for quick device lists, where [`query_capabilities()`] would be too slow.
+/// # Errors
+/// See [`query_devices()`].
+pub fn query_names_only(api: CaptureAPIBackend) -> Result<Vec<String>, NokhwaError> {
+    // reading the camera profiles of Media Foundation devices creates their media sources
+    #[cfg(feature = "input-msmf")]
+    if api == CaptureAPIBackend::MediaFoundation {
+        return match nokhwa_bindings_windows::wmf::query_msmf() {
+            Ok(devices) => Ok(devices
+                .into_iter()
+                .map(|device| device.name_as_string())
+                .collect()),
+            Err(why) => Err(why.into()),
+        };
+    }
+    Ok(query_devices(api)?
+        .into_iter()
+        .map(|info| info.human_name().clone())
+        .collect())
+}
+
+/// How [`query_capabilities()`] opens devices to read their formats.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum QueryMode {
+    /// Open devices one after the other.
+    Serial,
+    /// Open every device at once, each on its own thread. This is much faster with many cameras or slow USB hubs, but some drivers do not like being opened concurrently.
+    Parallel,
+}
+
+/// Query the system for available devices and open each of them to read every [`CameraFormat`] and control it supports. See [`probe()`].
+/// This is a lot slower than [`query_devices()`], as every device has to be opened. See [`QueryCache`] to avoid doing this repeatedly.
+/// # Quirks
+/// - A device that fails to open or be queried is still returned, with no formats and [`error()`](DeviceCapabilities::error) set.
+/// - Backends that cannot list formats (e.g. `OpenCV`) will return only the default format the device was opened with.
+/// # Errors
+/// If the devices cannot be enumerated, this will error. See [`query_devices()`].
+pub fn query_capabilities(
+    api: CaptureAPIBackend,
+    mode: QueryMode,
+) -> Result<Vec<DeviceCapabilities>, NokhwaError> {
+    let devices = query_devices(api)?;
+    Ok(probe_devices(devices, api, mode))
+}
+
+fn probe_devices(
+    devices: Vec<CameraInfo>,
+    api: CaptureAPIBackend,
+    mode: QueryMode,
+) -> Vec<DeviceCapabilities> {
+    match mode {
+        QueryMode::Serial => devices
+            .into_iter()
+            .map(|info| probe_device(info, api))
+            .collect(),
+        QueryMode::Parallel => {
+            let handles: Vec<(CameraInfo, thread::JoinHandle<DeviceCapabilities>)> = devices
+                .into_iter()
+                .map(|info| {
+                    let thread_info = info.clone();
+                    (info, thread::spawn(move || probe_device(thread_info, api)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|(info, handle)| match handle.join() {
+                    Ok(capabilities) => capabilities,
+                    Err(_) => DeviceCapabilities::new_failed(
+                        info,
+                        NokhwaError::GeneralError("Device query thread panicked!".to_string()),
+                    ),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Opens the device at `index` and reads its [`CameraInfo`], every [`CameraFormat`](crate::CameraFormat) and every [`CameraControl`](crate::CameraControl) (with its current value) it supports, **without** starting a stream.
+/// Use this to fill a settings dialog before the camera is started.
+/// # Quirks
+/// - If the formats cannot be read, the [`DeviceCapabilities`] has no formats and its [`error()`](DeviceCapabilities::error) is set.
+/// - Backends that cannot list formats (e.g. `OpenCV`) will return only the default format the device was opened with.
+/// - Controls that fail to read are left out.
+/// - `OpenCV` can only open a device by starting to capture from it.
+/// # Errors
+/// If the device cannot be opened (e.g. it does not exist, or permission is denied), this will error.
+pub fn probe(index: usize, api: CaptureAPIBackend) -> Result<DeviceCapabilities, NokhwaError> {
+    let mut camera = Camera::new(index, None, api)?;
+    let info = camera.info();
+    Ok(probe_camera(&mut camera, info))
+}
+
+fn probe_device(info: CameraInfo, api: CaptureAPIBackend) -> DeviceCapabilities {
+    match Camera::new(*info.index(), None, api) {
+        Ok(mut camera) => probe_camera(&mut camera, info),
+        Err(why) => DeviceCapabilities::new_failed(info, why),
+    }
+}
+
+fn probe_camera(camera: &mut Camera, info: CameraInfo) -> DeviceCapabilities {
+    let mut capabilities = match camera.compatible_camera_formats() {
+        Ok(formats) => DeviceCapabilities::new(info, formats),
+        Err(NokhwaError::UnsupportedOperationError(_)) => {
+            let format = camera.camera_format();
+            DeviceCapabilities::new(info, vec![format])
+        }
+        Err(why) => return DeviceCapabilities::new_failed(info, why),
+    };
+
+    let controls = camera
+        .supported_camera_controls()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|control| camera.camera_control(control).ok())
+        .collect();
+    capabilities.set_controls(controls);
+    capabilities
+}
+
+/// Query the system for Media Foundation sensor groups. A sensor group combines several cameras (e.g. the color and infrared cameras of a Windows Hello camera) into one device, and is how the cameras of many Surface-class devices offer their camera profiles.
+///
+/// Sensor groups are numbered separately from [`query_devices()`], open them with [`MediaFoundationCaptureDevice::new_sensor_group()`](crate::backends::capture::MediaFoundationCaptureDevice::new_sensor_group).
+/// # Quirks
+/// - Only Media Foundation has sensor groups. They need Windows 10 1607 or newer.
+/// # Errors
+/// If the sensor groups cannot be enumerated, or the `input-msmf` feature is not enabled, this will error.
+pub fn query_sensor_groups() -> Result<Vec<CameraInfo>, NokhwaError> {
+    query_msmf_sensor_groups()
+}
+
+#[cfg(feature = "input-msmf")]
+fn query_msmf_sensor_groups() -> Result<Vec<CameraInfo>, NokhwaError> {
+    match nokhwa_bindings_windows::wmf::query_msmf_sensor_groups() {
+        Ok(groups) => Ok(groups
+            .into_iter()
+            .map(|mf_desc| {
+                let mut camera_info = msmf_camera_info(mf_desc);
+                camera_info.set_description("Media Foundation Sensor Group".to_string());
+                camera_info
+            })
+            .collect()),
+        Err(why) => Err(why.into()),
+    }
+}
+
+#[cfg(not(feature = "input-msmf"))]
+fn query_msmf_sensor_groups() -> Result<Vec<CameraInfo>, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(
+        CaptureAPIBackend::MediaFoundation,
+    ))
+}
+
+/// Query the system for devices like [`query_devices()`], grouped by the physical unit they are part of (See: [`CameraInfo::group_id()`]).
+/// Devices like stereo and depth cameras expose one device per sensor, which can be opened together with [`CameraGroup::open()`].
+///
+/// Groups are in the order of their first device. Devices without a group ID are each a group of their own.
+/// # Errors
+/// See [`query_devices()`].
+pub fn query_camera_groups(api: CaptureAPIBackend) -> Result<Vec<CameraGroup>, NokhwaError> {
+    let mut groups: Vec<CameraGroup> = vec![];
+    for info in query_devices(api)? {
+        let group = match info.group_id() {
+            Some(id) => groups
+                .iter_mut()
+                .find(|group| group.id.as_ref() == Some(id)),
+            None => None,
+        };
+        match group {
+            Some(group) => group.cameras.push(info),
+            None => groups.push(CameraGroup {
+                id: info.group_id().cloned(),
+                cameras: vec![info],
+            }),
+        }
+    }
+    Ok(groups)
+}
+
+/// The devices of one physical unit, such as the left, right and depth sensors of a stereo or depth camera. See [`query_camera_groups()`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct CameraGroup {
+    id: Option<String>,
+    cameras: Vec<CameraInfo>,
+}
+
+impl CameraGroup {
+    /// The [`group_id()`](CameraInfo::group_id) of the devices, or `None` for a single device without one.
+    #[must_use]
+    pub fn id(&self) -> Option<&String> {
+        self.id.as_ref()
+    }
+
+    /// The devices in the group, in the order they were queried in.
+    #[must_use]
+    pub fn cameras(&self) -> &Vec<CameraInfo> {
+        &self.cameras
+    }
+
+    /// Opens every device in the group with `format` (or their default format, if `None`), in the order of [`cameras()`](CameraGroup::cameras). The streams are not started.
+    /// # Errors
+    /// If any of the devices fails to open, this will error, and the devices that did open are closed again.
+    pub fn open(
+        &self,
+        format: Option<CameraFormat>,
+        api: CaptureAPIBackend,
+    ) -> Result<Vec<Camera>, NokhwaError> {
+        self.cameras
+            .iter()
+            .map(|info| Camera::new(*info.index(), format, api))
+            .collect()
+    }
+}
+
+// Identifies a physical device across re-enumeration, where the index may change.
+fn device_key(info: &CameraInfo) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        info.bus_path().map_or("", String::as_str),
+        info.description(),
+        info.misc(),
+        info.human_name()
+    )
+}
+
+/// A cache for [`query_capabilities()`], keyed on the device path. <br>
+/// Every [`query()`](QueryCache::query) re-enumerates the devices (which is cheap), but only opens devices that are new or whose entry is older than the time-to-live.
+/// Devices that were unplugged are dropped from the cache.
+/// # Quirks
+/// - Devices that failed to probe are not cached, and will be retried on the next query.
+#[derive(Clone, Debug)]
+pub struct QueryCache {
+    api: CaptureAPIBackend,
+    mode: QueryMode,
+    ttl: Duration,
+    entries: HashMap<String, (Instant, DeviceCapabilities)>,
+}
+
+impl QueryCache {
+    /// Create a new, empty [`QueryCache`]. Entries older than `ttl` are refreshed on the next [`query()`](QueryCache::query).
+    #[must_use]
+    pub fn new(api: CaptureAPIBackend, mode: QueryMode, ttl: Duration) -> Self {
+        QueryCache {
+            api,
+            mode,
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Queries the devices, opening only the devices that are not (freshly) cached.
+    /// # Errors
+    /// If the devices cannot be enumerated, this will error. See [`query_devices()`].
+    pub fn query(&mut self) -> Result<Vec<DeviceCapabilities>, NokhwaError> {
+        let devices = query_devices(self.api)?;
+        let now = Instant::now();
+
+        let mut stale = vec![];
+        for info in &devices {
+            let fresh = match self.entries.get(&device_key(info)) {
+                Some((probed_at, _)) => now.duration_since(*probed_at) < self.ttl,
+                None => false,
+            };
+            if !fresh {
+                stale.push(info.clone());
+            }
+        }
+
+        let mut failed = HashMap::new();
+        for capabilities in probe_devices(stale, self.api, self.mode) {
+            let key = device_key(capabilities.info());
+            if capabilities.error().is_none() {
+                self.entries.insert(key, (now, capabilities));
+            } else {
+                self.entries.remove(&key);
+                failed.insert(key, capabilities);
+            }
+        }
+
+        let mut capabilities = Vec::with_capacity(devices.len());
+        let mut seen = HashSet::with_capacity(devices.len());
+        for info in devices {
+            let key = device_key(&info);
+            if let Some(failure) = failed.remove(&key) {
+                capabilities.push(failure);
+            } else if let Some((_, cached)) = self.entries.get_mut(&key) {
+                // the index may have changed since we cached it
+                cached.set_info(info);
+                capabilities.push(cached.clone());
+            }
+            seen.insert(key);
+        }
+        self.entries.retain(|key, _| seen.contains(key));
+
+        Ok(capabilities)
+    }
+
+    /// Drops every cached entry, so the next [`query()`](QueryCache::query) opens every device again.
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Gets the time-to-live of cache entries.
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Sets the time-to-live of cache entries. This applies to entries that are already cached as well.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+}
+
 // TODO: More
 
 #[cfg(feature = "input-v4l")]
@@ -83,13 +382,22 @@ fn query_v4l() -> Result<Vec<CameraInfo>, NokhwaError> {
         let camera_info: Vec<CameraInfo> = v4l::context::enum_devices()
             .iter()
             .map(|node| {
-                CameraInfo::new(
+                let mut camera_info = CameraInfo::new(
                     node.name()
                         .unwrap_or(format!("{}", node.path().to_string_lossy())),
                     format!("Video4Linux Device @ {}", node.path().to_string_lossy()),
                     "".to_string(),
                     node.index(),
-                )
+                );
+                camera_info.fill_from_sysfs(node.index());
+                match v4l::Device::new(node.index()) {
+                    Ok(device) => {
+                        camera_info.guess_stream_kind_from_v4l(&device);
+                        camera_info.read_sensor_orientation_from_v4l(&device);
+                    }
+                    Err(_) => camera_info.guess_stream_kind(&[]),
+                }
+                camera_info
             })
             .collect();
         camera_info
@@ -150,7 +458,7 @@ fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
                         ))
                         .clone();
 
-                    camera_info_vec.push(CameraInfo::new(
+                    let mut camera_info = CameraInfo::new(
                         name.clone(),
                         usb_dev
                             .description
@@ -161,10 +469,14 @@ fn query_uvc() -> Result<Vec<CameraInfo>, NokhwaError> {
                             "{}:{} {}",
                             desc.vendor_id,
                             desc.product_id,
-                            desc.serial_number.unwrap_or_else(|| "".to_string())
+                            desc.serial_number.clone().unwrap_or_else(|| "".to_string())
                         ),
                         counter,
-                    ));
+                    );
+                    camera_info.set_vendor_id(Some(desc.vendor_id));
+                    camera_info.set_product_id(Some(desc.product_id));
+                    camera_info.set_serial(desc.serial_number);
+                    camera_info_vec.push(camera_info);
                     counter += 1;
                 }
             }
@@ -227,12 +539,29 @@ fn query_gstreamer() -> Result<Vec<CameraInfo>, NokhwaError> {
             let name = DeviceExt::display_name(gst_dev);
             let class = DeviceExt::device_class(gst_dev);
             counter += 1;
-            CameraInfo::new(
+            let mut camera_info = CameraInfo::new(
                 name.to_string(),
                 class.to_string(),
                 "".to_string(),
                 counter - 1,
-            )
+            );
+            // these are set by the device providers that know about them (e.g. v4l2, pipewire)
+            if let Some(properties) = DeviceExt::properties(gst_dev) {
+                let property = |name: &str| properties.get::<String>(name).ok();
+                camera_info.set_vendor_id(
+                    property("device.vendor.id").and_then(|vid| u16::from_str_radix(&vid, 16).ok()),
+                );
+                camera_info.set_product_id(
+                    property("device.product.id")
+                        .and_then(|pid| u16::from_str_radix(&pid, 16).ok()),
+                );
+                camera_info.set_serial(property("device.serial"));
+                camera_info
+                    .set_bus_path(property("device.bus_path").or_else(|| property("sysfs.path")));
+                camera_info.set_group_id(camera_info.bus_path().cloned());
+            }
+            camera_info.guess_stream_kind(&[]);
+            camera_info
         })
         .collect();
     device_monitor.stop();
@@ -250,21 +579,134 @@ fn query_gstreamer() -> Result<Vec<CameraInfo>, NokhwaError> {
 #[cfg(feature = "input-msmf")]
 fn query_msmf() -> Result<Vec<CameraInfo>, NokhwaError> {
     let list: Vec<CameraInfo> = match nokhwa_bindings_windows::wmf::query_msmf() {
-        Ok(l) => l
-            .into_iter()
-            .map(|mf_desc| {
-                let camera_info: CameraInfo = mf_desc.into();
-                camera_info
-            })
-            .collect(),
+        Ok(l) => l.into_iter().map(msmf_camera_info).collect(),
         Err(why) => return Err(why.into()),
     };
     Ok(list)
 }
 
+// devices whose media source cannot be created (e.g. one in use by another app) are listed without profiles
+#[cfg(feature = "input-msmf")]
+fn msmf_camera_info(
+    mf_desc: nokhwa_bindings_windows::MediaFoundationDeviceDescriptor<'static>,
+) -> CameraInfo {
+    use crate::CameraProfile;
+
+    let profiles = nokhwa_bindings_windows::wmf::device_profiles(&mf_desc.link_as_string())
+        .unwrap_or_default();
+    let mut camera_info: CameraInfo = mf_desc.into();
+    camera_info.set_profiles(profiles.into_iter().map(CameraProfile::from).collect());
+    camera_info
+}
+
 #[cfg(not(feature = "input-msmf"))]
 fn query_msmf() -> Result<Vec<CameraInfo>, NokhwaError> {
     Err(NokhwaError::UnsupportedOperationError(
         CaptureAPIBackend::MediaFoundation,
     ))
 }
+
+/// Checks whether the camera at `index` is already in use (opened exclusively by another process), without keeping it open. <br>
+/// This is meant for device pickers, so they can grey out cameras that would fail to open.
+/// # Quirks
+/// - `Video4Linux`: The device is considered busy if the kernel refuses to let us set its (current) format with `EBUSY`, which happens when another handle is streaming or has buffers allocated.
+/// - Media Foundation: A single sample is read, the device is considered busy if this fails with a locked/preempted/sharing violation error.
+/// - All other backends return [`CameraAvailability::Unknown`].
+/// # Errors
+/// If the device does not exist, cannot be opened at all (e.g. insufficient permissions), or the backend is not enabled, this will error.
+pub fn probe_availability(
+    index: usize,
+    api: CaptureAPIBackend,
+) -> Result<CameraAvailability, NokhwaError> {
+    match api {
+        CaptureAPIBackend::Auto => match std::env::consts::OS {
+            "linux" if cfg!(feature = "input-v4l") => probe_v4l(index),
+            "windows" if cfg!(feature = "input-msmf") => probe_msmf(index),
+            _ => Ok(CameraAvailability::Unknown),
+        },
+        CaptureAPIBackend::Video4Linux => probe_v4l(index),
+        CaptureAPIBackend::MediaFoundation => probe_msmf(index),
+        _ => Ok(CameraAvailability::Unknown),
+    }
+}
+
+#[cfg(feature = "input-v4l")]
+fn probe_v4l(index: usize) -> Result<CameraAvailability, NokhwaError> {
+    use v4l::{video::Capture, Device};
+
+    // EBUSY
+    const BUSY: i32 = 16;
+
+    let device = match Device::new(index) {
+        Ok(dev) => dev,
+        Err(why) => {
+            if why.raw_os_error() == Some(BUSY) {
+                return Ok(CameraAvailability::Busy);
+            }
+            return Err(NokhwaError::from_io_error(
+                &index.to_string(),
+                &why,
+                |error| {
+                    NokhwaError::OpenDeviceError(
+                        index.to_string(),
+                        format!("V4L2 Error: {}", error),
+                    )
+                },
+            ));
+        }
+    };
+
+    let format = match Capture::format(&device) {
+        Ok(fmt) => fmt,
+        Err(why) => {
+            return Err(NokhwaError::GetPropertyError {
+                property: "Format".to_string(),
+                error: why.to_string(),
+            })
+        }
+    };
+
+    match Capture::set_format(&device, &format) {
+        Ok(_) => Ok(CameraAvailability::Available),
+        Err(why) => {
+            if why.raw_os_error() == Some(BUSY) {
+                Ok(CameraAvailability::Busy)
+            } else {
+                Err(NokhwaError::SetPropertyError {
+                    property: "Format".to_string(),
+                    value: format.to_string(),
+                    error: why.to_string(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "input-v4l"))]
+fn probe_v4l(_index: usize) -> Result<CameraAvailability, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(
+        CaptureAPIBackend::Video4Linux,
+    ))
+}
+
+#[cfg(feature = "input-msmf")]
+fn probe_msmf(index: usize) -> Result<CameraAvailability, NokhwaError> {
+    use nokhwa_bindings_windows::wmf::MediaFoundationDevice;
+
+    let mut device = match MediaFoundationDevice::new(index) {
+        Ok(dev) => dev,
+        Err(why) => return Err(why.into()),
+    };
+    match device.is_locked() {
+        Ok(true) => Ok(CameraAvailability::Busy),
+        Ok(false) => Ok(CameraAvailability::Available),
+        Err(why) => Err(why.into()),
+    }
+}
+
+#[cfg(not(feature = "input-msmf"))]
+fn probe_msmf(_index: usize) -> Result<CameraAvailability, NokhwaError> {
+    Err(NokhwaError::UnsupportedOperationError(
+        CaptureAPIBackend::MediaFoundation,
+    ))
+}
@@ -0,0 +1,182 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{CameraFormat, CameraInfo, FrameFormat, KnownCameraControls, Resolution};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    sync::RwLock,
+};
+
+/// A known misbehaviour of a specific camera model, and how [`Camera`](crate::Camera) works around it.
+/// - `UnreliableFormat` - The camera advertises this format but does not deliver it (e.g. it claims 30 FPS at 1080p but gives 15).
+///   It is left out of [`Camera::compatible_camera_formats()`](crate::Camera::compatible_camera_formats), so format negotiation (e.g. [`CameraBuilder`](crate::CameraBuilder)) never picks it.
+/// - `RenegotiateAfterControl` - The camera breaks its stream after a control change. The current format is set again after every [`Camera::set_camera_control()`](crate::Camera::set_camera_control).
+/// - `BrokenControl` - The control is advertised but does not work (or breaks the camera). It is left out of [`Camera::supported_camera_controls()`](crate::Camera::supported_camera_controls) and cannot be set.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Quirk {
+    UnreliableFormat(CameraFormat),
+    RenegotiateAfterControl,
+    BrokenControl(KnownCameraControls),
+}
+
+impl Display for Quirk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Quirk::UnreliableFormat(format) => {
+                write!(f, "Unreliable format: {}", format)
+            }
+            Quirk::RenegotiateAfterControl => {
+                write!(f, "Renegotiate format after control change")
+            }
+            Quirk::BrokenControl(control) => {
+                write!(f, "Broken control: {}", control)
+            }
+        }
+    }
+}
+
+// keyed by (vendor ID, product ID)
+type QuirkTable = HashMap<(u16, u16), Vec<Quirk>>;
+
+// `None` until first use, then filled with the built-in quirks
+static QUIRKS: RwLock<Option<QuirkTable>> = RwLock::new(None);
+
+// Add entries here as they are reported, with the device name in a comment.
+fn builtin_quirks() -> QuirkTable {
+    let mut table = HashMap::new();
+    // Logitech HD Pro Webcam C920: loses its stream settings when controls change (`uvcvideo` restores its controls on init for the same reason)
+    table.insert((0x046d, 0x082d), vec![Quirk::RenegotiateAfterControl]);
+    // Microsoft LifeCam HD-3000: advertises 30 FPS for uncompressed 720p, but USB 2.0 only carries about 10
+    table.insert(
+        (0x045e, 0x0810),
+        vec![Quirk::UnreliableFormat(CameraFormat::new(
+            Resolution::new(1280, 720),
+            FrameFormat::YUYV,
+            30,
+        ))],
+    );
+    table
+}
+
+fn with_quirks<T>(f: impl FnOnce(&mut QuirkTable) -> T) -> T {
+    let mut table = match QUIRKS.write() {
+        Ok(table) => table,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    f(table.get_or_insert_with(builtin_quirks))
+}
+
+/// Gets the [`Quirk`]s known for the USB device `vendor_id:product_id`.
+#[must_use]
+pub fn quirks_for(vendor_id: u16, product_id: u16) -> Vec<Quirk> {
+    with_quirks(|table| {
+        table
+            .get(&(vendor_id, product_id))
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+/// Adds a [`Quirk`] for the USB device `vendor_id:product_id`, on top of the ones already known. Adding a quirk twice does nothing.
+///
+/// This affects cameras opened after this call, as well as ones that are already open.
+pub fn add_quirk(vendor_id: u16, product_id: u16, quirk: Quirk) {
+    with_quirks(|table| {
+        let quirks = table.entry((vendor_id, product_id)).or_default();
+        if !quirks.contains(&quirk) {
+            quirks.push(quirk);
+        }
+    });
+}
+
+/// Replaces all [`Quirk`]s for the USB device `vendor_id:product_id`, including the built-in ones. Pass an empty `Vec` to disable all workarounds for the device.
+pub fn set_quirks(vendor_id: u16, product_id: u16, quirks: Vec<Quirk>) {
+    with_quirks(|table| {
+        table.insert((vendor_id, product_id), quirks);
+    });
+}
+
+/// Throws away every [`Quirk`] added with [`add_quirk()`] or [`set_quirks()`], going back to the built-in ones.
+pub fn reset_quirks() {
+    with_quirks(|table| *table = builtin_quirks());
+}
+
+// cameras without a known USB ID (e.g. OpenCV, or a non-USB device) never have quirks
+pub(crate) fn quirks_for_info(info: &CameraInfo) -> Vec<Quirk> {
+    match (info.vendor_id(), info.product_id()) {
+        (Some(vendor_id), Some(product_id)) => quirks_for(vendor_id, product_id),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_quirk, quirks_for, reset_quirks, set_quirks, Quirk};
+    use crate::KnownCameraControls;
+    use std::sync::Mutex;
+
+    // the quirk table is global, tests that change it must not run at the same time
+    static TABLE: Mutex<()> = Mutex::new(());
+
+    fn lock_table() -> std::sync::MutexGuard<'static, ()> {
+        match TABLE.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    #[test]
+    fn add_set_and_reset() {
+        let _table = lock_table();
+        reset_quirks();
+        let builtin = quirks_for(0x046d, 0x082d);
+        assert!(builtin.contains(&Quirk::RenegotiateAfterControl));
+
+        let broken = Quirk::BrokenControl(KnownCameraControls::Focus);
+        add_quirk(0x046d, 0x082d, broken);
+        add_quirk(0x046d, 0x082d, broken);
+        let mut expected = builtin.clone();
+        expected.push(broken);
+        assert_eq!(quirks_for(0x046d, 0x082d), expected);
+
+        add_quirk(0x1234, 0x5678, broken);
+        assert_eq!(quirks_for(0x1234, 0x5678), vec![broken]);
+
+        set_quirks(0x046d, 0x082d, vec![]);
+        assert!(quirks_for(0x046d, 0x082d).is_empty());
+
+        reset_quirks();
+        assert_eq!(quirks_for(0x046d, 0x082d), builtin);
+        assert!(quirks_for(0x1234, 0x5678).is_empty());
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn unreliable_formats_are_not_compatible() {
+        use crate::{Camera, CameraFormat, FrameFormat, MockCaptureDevice, Resolution};
+
+        let _table = lock_table();
+        let reliable = CameraFormat::new(Resolution::new(640, 480), FrameFormat::YUYV, 30);
+        let unreliable = CameraFormat::new(Resolution::new(1920, 1080), FrameFormat::YUYV, 30);
+        let mut device = MockCaptureDevice::new(0, Some(reliable));
+        device.set_compatible_formats(vec![reliable, unreliable]);
+        let mut info = crate::CaptureBackendTrait::camera_info(&device);
+        info.set_vendor_id(Some(0xabcd));
+        info.set_product_id(Some(0x0001));
+        device.set_camera_info(info);
+        let mut camera = Camera::from_backend(Box::new(device));
+
+        reset_quirks();
+        assert_eq!(
+            camera.compatible_camera_formats().unwrap(),
+            vec![unreliable, reliable]
+        );
+        add_quirk(0xabcd, 0x0001, Quirk::UnreliableFormat(unreliable));
+        assert_eq!(camera.compatible_camera_formats().unwrap(), vec![reliable]);
+        reset_quirks();
+    }
+}
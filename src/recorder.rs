@@ -0,0 +1,505 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{transform, Camera, NokhwaError, Resolution, Rotation, SinkPayload};
+use gstreamer::{
+    event::Tag,
+    glib::Cast,
+    prelude::{ElementExt, GstBinExt, ObjectExt},
+    tags::ImageOrientation,
+    Bin, Buffer, ClockTime, Element, ElementFactory, MessageType, MessageView, State, TagList,
+    TagMergeMode,
+};
+use gstreamer_app::AppSrc;
+use image::{ImageBuffer, Rgb};
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How long [`Recorder::stop()`] waits for the file to be finalized.
+const FINALIZE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The codec a [`Recorder`] encodes with.
+/// - `H264` - H.264, with `x264enc` if it is installed, otherwise `openh264enc`. Plays nearly everywhere.
+/// - `VP9` - VP9, with `vp9enc`. Smaller files at the same quality, but encoding is slower.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum VideoCodec {
+    H264,
+    VP9,
+}
+
+impl Default for VideoCodec {
+    fn default() -> Self {
+        VideoCodec::H264
+    }
+}
+
+impl Display for VideoCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The file format a [`Recorder`] writes.
+/// - `MP4` - MPEG-4 Part 14. A file that is not [stopped](Recorder::stop) properly cannot be played.
+/// - `Matroska` - MKV. A file that is not stopped properly is still playable up to the last written frame.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum VideoContainer {
+    MP4,
+    Matroska,
+}
+
+impl VideoContainer {
+    fn muxer(self) -> &'static str {
+        match self {
+            VideoContainer::MP4 => "mp4mux",
+            VideoContainer::Matroska => "matroskamux",
+        }
+    }
+}
+
+impl Default for VideoContainer {
+    fn default() -> Self {
+        VideoContainer::MP4
+    }
+}
+
+impl Display for VideoContainer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+fn recorder_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("Recorder: {}", error))
+}
+
+// a running GStreamer pipeline, from `start()` to `stop()`
+struct Recording {
+    pipeline: Element,
+    source: AppSrc,
+    // `splitmuxsink`, only when segmenting
+    splitter: Option<Element>,
+    started: Instant,
+    // the last rotation sent as an `image-orientation` tag
+    orientation: Rotation,
+}
+
+/// Records frames into a video file (or a series of files), with `GStreamer`.
+///
+/// Every frame is timestamped with the time it was written relative to [`start()`](Recorder::start), so the video plays at the speed
+/// it was recorded at, even if the camera drops frames or delivers them unevenly.
+/// ```.ignore
+/// let mut recorder = Recorder::new("out.mp4", camera.resolution(), camera.frame_rate());
+/// recorder.start()?;
+/// while recording {
+///     recorder.record_frame(&mut camera)?;
+/// }
+/// recorder.stop()?;
+/// ```
+/// # Quirks
+/// - This needs the `GStreamer` base, good (`mp4mux`, `matroskamux`, `vp9enc`) and ugly (`x264enc`) or bad (`openh264enc`) plugins installed at runtime.
+///   [`segmented()`](Recorder::segmented) recording needs `GStreamer` 1.18 or newer.
+/// - Frames must match the [`Resolution`] the recorder was created with. Frames of any other size are rejected.
+/// - Frames that a [`SinkTransform`](crate::SinkTransform) turned into [`SinkPayload::Opaque`] cannot be encoded, and are rejected.
+/// - With [`RotationHandling::Metadata`](crate::RotationHandling::Metadata), [`record_frame()`](Recorder::record_frame) writes the camera's [`pending_rotation()`](Camera::pending_rotation) into MP4 files as the
+///   display matrix, and the frames (and so the [`Resolution`]) stay unrotated. Matroska has no such field, so the frames are rotated instead, like with [`RotationHandling::Pixels`](crate::RotationHandling::Pixels).
+pub struct Recorder {
+    path: PathBuf,
+    resolution: Resolution,
+    frame_rate: u32,
+    codec: VideoCodec,
+    container: VideoContainer,
+    segment_duration: Option<Duration>,
+    segmented: bool,
+    recording: Option<Recording>,
+}
+
+impl Recorder {
+    /// Creates a new [`Recorder`] that writes `resolution` sized frames at about `frame_rate` FPS into `path`, as H.264 in MP4 by default.
+    /// Nothing is written until [`start()`](Recorder::start) is called.
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(path: P, resolution: Resolution, frame_rate: u32) -> Self {
+        Recorder {
+            path: path.as_ref().to_path_buf(),
+            resolution,
+            frame_rate,
+            codec: VideoCodec::default(),
+            container: VideoContainer::default(),
+            segment_duration: None,
+            segmented: false,
+            recording: None,
+        }
+    }
+
+    /// Creates a new [`Recorder`] like [`new()`](Recorder::new), but that writes into a series of files instead of one.
+    /// `pattern` is the path with a `printf` style number in it (e.g. `clip%05d.mp4`), which counts up from 0 for every file.
+    ///
+    /// A new file is started every `segment_duration` (if not `None`), and on every [`split()`](Recorder::split).
+    #[must_use]
+    pub fn segmented<P: AsRef<Path>>(
+        pattern: P,
+        resolution: Resolution,
+        frame_rate: u32,
+        segment_duration: Option<Duration>,
+    ) -> Self {
+        let mut recorder = Recorder::new(pattern, resolution, frame_rate);
+        recorder.segmented = true;
+        recorder.segment_duration = segment_duration;
+        recorder
+    }
+
+    /// Sets the [`VideoCodec`]. This takes effect on the next [`start()`](Recorder::start).
+    #[must_use]
+    pub fn with_codec(mut self, codec: VideoCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the [`VideoContainer`]. This takes effect on the next [`start()`](Recorder::start).
+    #[must_use]
+    pub fn with_container(mut self, container: VideoContainer) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// The [`VideoCodec`] frames are encoded with.
+    #[must_use]
+    pub fn codec(&self) -> VideoCodec {
+        self.codec
+    }
+
+    /// The [`VideoContainer`] frames are written into.
+    #[must_use]
+    pub fn container(&self) -> VideoContainer {
+        self.container
+    }
+
+    /// The [`Resolution`] frames must have.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Whether the recorder has been started and not yet stopped.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// How long the current recording has been going, or `None` if the recorder is not recording.
+    #[must_use]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.recording
+            .as_ref()
+            .map(|recording| recording.started.elapsed())
+    }
+
+    fn encoder(&self) -> Result<&'static str, NokhwaError> {
+        let candidates: &[(&str, &'static str)] = match self.codec {
+            VideoCodec::H264 => &[
+                (
+                    "x264enc",
+                    "x264enc tune=zerolatency speed-preset=veryfast ! h264parse",
+                ),
+                ("openh264enc", "openh264enc ! h264parse"),
+            ],
+            VideoCodec::VP9 => &[("vp9enc", "vp9enc deadline=1")],
+        };
+        match candidates
+            .iter()
+            .find(|(factory, _)| ElementFactory::find(factory).is_some())
+        {
+            Some((_, encoder)) => Ok(*encoder),
+            None => Err(recorder_error(&format!(
+                "No {} encoder is installed (tried {})",
+                self.codec,
+                candidates
+                    .iter()
+                    .map(|(factory, _)| *factory)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    // the location is set on the element named `sink` afterwards, paths do not survive `parse_launch()`'s escaping (e.g. `\` on windows)
+    fn pipeline_description(&self) -> Result<String, NokhwaError> {
+        let sink = if self.segmented {
+            // 0 is no time limit, only manual splits
+            let max_size_time = self
+                .segment_duration
+                .map_or(0, |duration| duration.as_nanos());
+            format!(
+                "splitmuxsink name=sink max-size-time={} muxer-factory={}",
+                max_size_time,
+                self.container.muxer()
+            )
+        } else {
+            format!("{} ! filesink name=sink", self.container.muxer())
+        };
+        Ok(format!(
+            "appsrc name=source is-live=true format=time caps=\"video/x-raw,format=RGB,width={},height={},framerate={}/1\" ! videoconvert ! {} ! {}",
+            self.resolution.width(),
+            self.resolution.height(),
+            self.frame_rate,
+            self.encoder()?,
+            sink
+        ))
+    }
+
+    /// Starts recording. This creates (or overwrites) the file.
+    /// # Errors
+    /// If `GStreamer` fails to initialize, an element is missing or the file cannot be opened, this will error.
+    pub fn start(&mut self) -> Result<(), NokhwaError> {
+        if self.recording.is_some() {
+            return Err(recorder_error("Already recording"));
+        }
+        if let Err(why) = gstreamer::init() {
+            return Err(recorder_error(&format!(
+                "Failed to initialize GStreamer: {}",
+                why
+            )));
+        }
+
+        let description = self.pipeline_description()?;
+        let pipeline = match gstreamer::parse_launch(&description) {
+            Ok(pipeline) => pipeline,
+            Err(why) => {
+                return Err(recorder_error(&format!(
+                    "Failed to create pipeline {}: {}",
+                    description, why
+                )))
+            }
+        };
+        let bin = match pipeline.clone().dynamic_cast::<Bin>() {
+            Ok(bin) => bin,
+            Err(_) => return Err(recorder_error("Pipeline is not a bin")),
+        };
+        let source = match bin
+            .by_name("source")
+            .and_then(|source| source.dynamic_cast::<AppSrc>().ok())
+        {
+            Some(source) => source,
+            None => return Err(recorder_error("Failed to get appsrc")),
+        };
+        let sink = match bin.by_name("sink") {
+            Some(sink) => sink,
+            None => return Err(recorder_error("Failed to get sink")),
+        };
+        if let Err(why) = sink.set_property("location", self.path.to_string_lossy().as_ref()) {
+            return Err(recorder_error(&format!(
+                "Failed to set the location: {}",
+                why
+            )));
+        }
+        let splitter = if self.segmented { Some(sink) } else { None };
+
+        if let Err(why) = pipeline.set_state(State::Playing) {
+            let _state = pipeline.set_state(State::Null);
+            return Err(recorder_error(&format!(
+                "Failed to start pipeline: {}",
+                why
+            )));
+        }
+
+        self.recording = Some(Recording {
+            pipeline,
+            source,
+            splitter,
+            started: Instant::now(),
+            orientation: Rotation::None,
+        });
+        Ok(())
+    }
+
+    /// Writes a frame. It is timestamped with the time since [`start()`](Recorder::start).
+    /// # Errors
+    /// If the recorder is not recording, the frame is the wrong size, or the pipeline has failed (e.g. the disk is full), this will error.
+    pub fn write_frame(
+        &mut self,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<(), NokhwaError> {
+        let recording = match &self.recording {
+            Some(recording) => recording,
+            None => return Err(recorder_error("Not recording")),
+        };
+        if Resolution::new(frame.width(), frame.height()) != self.resolution {
+            return Err(recorder_error(&format!(
+                "Frame is {}x{}, the recording is {}",
+                frame.width(),
+                frame.height(),
+                self.resolution
+            )));
+        }
+        check_bus(&recording.pipeline)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let timestamp = recording.started.elapsed().as_nanos() as u64;
+        let mut buffer = Buffer::from_mut_slice(frame.as_raw().clone());
+        if let Some(buffer) = buffer.get_mut() {
+            buffer.set_pts(ClockTime::from_nseconds(timestamp));
+        }
+        if let Err(why) = recording.source.push_buffer(buffer) {
+            return Err(recorder_error(&format!("Failed to write frame: {:?}", why)));
+        }
+        Ok(())
+    }
+
+    /// Writes what a sink gets from [`Camera::frame_for_sink()`].
+    /// # Errors
+    /// If the payload is [`SinkPayload::Opaque`], or [`write_frame()`](Recorder::write_frame) fails, this will error.
+    pub fn write_payload(&mut self, payload: &SinkPayload) -> Result<(), NokhwaError> {
+        match payload.image() {
+            Some(image) => self.write_frame(image),
+            None => Err(recorder_error(
+                "Opaque frames (e.g. encrypted by a SinkTransform) cannot be encoded",
+            )),
+        }
+    }
+
+    /// Writes what a sink gets from [`Camera::frame_for_sink_with_rotation()`]. In MP4 files, `rotation` becomes the display matrix, which players apply when showing the video.
+    /// In Matroska files, the frame is rotated before it is written.
+    /// # Errors
+    /// If the rotation cannot be written, or [`write_payload()`](Recorder::write_payload) fails, this will error.
+    pub fn write_payload_with_rotation(
+        &mut self,
+        payload: &SinkPayload,
+        rotation: Rotation,
+    ) -> Result<(), NokhwaError> {
+        match self.container {
+            VideoContainer::MP4 => {
+                self.set_orientation(rotation)?;
+                self.write_payload(payload)
+            }
+            VideoContainer::Matroska => match (payload, rotation) {
+                (_, Rotation::None) | (SinkPayload::Opaque { .. }, _) => {
+                    self.write_payload(payload)
+                }
+                (SinkPayload::Image(image), _) => {
+                    self.write_frame(&transform::rotate_frame(image.clone(), rotation))
+                }
+            },
+        }
+    }
+
+    /// Captures a frame from `camera` with [`Camera::frame_for_sink_with_rotation()`] and writes it. See [`write_payload_with_rotation()`](Recorder::write_payload_with_rotation).
+    /// # Errors
+    /// If capturing fails, or [`write_payload_with_rotation()`](Recorder::write_payload_with_rotation) fails, this will error.
+    pub fn record_frame(&mut self, camera: &mut Camera) -> Result<(), NokhwaError> {
+        let (payload, rotation) = camera.frame_for_sink_with_rotation()?;
+        self.write_payload_with_rotation(&payload, rotation)
+    }
+
+    // sends an `image-orientation` tag downstream when the rotation changes, which `mp4mux` writes as the track's display matrix
+    fn set_orientation(&mut self, rotation: Rotation) -> Result<(), NokhwaError> {
+        let recording = match &mut self.recording {
+            Some(recording) => recording,
+            None => return Err(recorder_error("Not recording")),
+        };
+        if recording.orientation == rotation {
+            return Ok(());
+        }
+        let orientation = match rotation {
+            Rotation::None => "rotate-0",
+            Rotation::Rotate90 => "rotate-90",
+            Rotation::Rotate180 => "rotate-180",
+            Rotation::Rotate270 => "rotate-270",
+        };
+        let mut tags = TagList::new();
+        if let Some(tags) = tags.get_mut() {
+            tags.add::<ImageOrientation>(&orientation, TagMergeMode::Replace);
+        }
+        if !recording.source.send_event(Tag::new(tags)) {
+            return Err(recorder_error("Failed to write the rotation"));
+        }
+        recording.orientation = rotation;
+        Ok(())
+    }
+
+    /// Finishes the current file and starts the next one. The recording and its timestamps carry on.
+    /// # Errors
+    /// If the recorder was not made with [`segmented()`](Recorder::segmented), or is not recording, this will error.
+    pub fn split(&mut self) -> Result<(), NokhwaError> {
+        let splitter = match &self.recording {
+            Some(Recording {
+                splitter: Some(splitter),
+                ..
+            }) => splitter,
+            Some(_) => return Err(recorder_error("Recorder is not segmented")),
+            None => return Err(recorder_error("Not recording")),
+        };
+        match splitter.emit_by_name("split-now", &[]) {
+            Ok(_) => Ok(()),
+            Err(why) => Err(recorder_error(&format!("Failed to split: {}", why))),
+        }
+    }
+
+    /// Stops recording and finalizes the file, waiting for everything written so far to be encoded. The recorder can be started again afterwards.
+    /// # Errors
+    /// If the recorder is not recording, or the file fails to finalize in time, this will error. The recorder is stopped either way.
+    pub fn stop(&mut self) -> Result<(), NokhwaError> {
+        let recording = match self.recording.take() {
+            Some(recording) => recording,
+            None => return Err(recorder_error("Not recording")),
+        };
+        let finalized = finalize(&recording);
+        if let Err(why) = recording.pipeline.set_state(State::Null) {
+            return Err(recorder_error(&format!("Failed to stop pipeline: {}", why)));
+        }
+        finalized
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.recording.is_some() {
+            let _stopped = self.stop();
+        }
+    }
+}
+
+// sends EOS, and waits for it to come out of the muxer, which is when the file is complete (e.g. the MP4 `moov` atom is written)
+fn finalize(recording: &Recording) -> Result<(), NokhwaError> {
+    if let Err(why) = recording.source.end_of_stream() {
+        return Err(recorder_error(&format!("Failed to end stream: {:?}", why)));
+    }
+    let bus = match recording.pipeline.bus() {
+        Some(bus) => bus,
+        None => return Err(recorder_error("The pipeline has no bus!")),
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let timeout = ClockTime::from_mseconds(FINALIZE_TIMEOUT.as_millis() as u64);
+    match bus.timed_pop_filtered(timeout, &[MessageType::Eos, MessageType::Error]) {
+        Some(message) => match message.view() {
+            MessageView::Error(err) => Err(recorder_error(&format!(
+                "Failed to finalize: {}",
+                err.error()
+            ))),
+            _ => Ok(()),
+        },
+        None => Err(recorder_error(&format!(
+            "File was not finalized within {:?}",
+            FINALIZE_TIMEOUT
+        ))),
+    }
+}
+
+fn check_bus(pipeline: &Element) -> Result<(), NokhwaError> {
+    let bus = match pipeline.bus() {
+        Some(bus) => bus,
+        None => return Err(recorder_error("The pipeline has no bus!")),
+    };
+    if let Some(message) = bus.timed_pop_filtered(ClockTime::from_seconds(0), &[MessageType::Error])
+    {
+        if let MessageView::Error(err) = message.view() {
+            return Err(recorder_error(&format!("Pipeline error: {}", err.error())));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,788 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{BayerPattern, Camera, FrameFormat, NokhwaError, Resolution, SinkPayload};
+use std::{
+    convert::TryFrom,
+    ptr,
+    sync::atomic::{fence, AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// "NKHW", so consumers can tell a ring from any other shared memory
+const SHM_MAGIC: u32 = 0x4e4b_4857;
+const SHM_VERSION: u32 = 1;
+
+// ring header: magic, version, slot count, slot capacity (all `u32`), then the sequence of the latest frame (`u64`). Padded to 64 bytes
+const HEADER_SIZE: usize = 64;
+const HEADER_MAGIC: usize = 0;
+const HEADER_VERSION: usize = 4;
+const HEADER_SLOTS: usize = 8;
+const HEADER_CAPACITY: usize = 12;
+const HEADER_SEQUENCE: usize = 16;
+
+// slot header: sequence (`u64`, 0 while being written), timestamp in microseconds since the UNIX epoch (`u64`), then width, height, format and length (all `u32`)
+const SLOT_HEADER_SIZE: usize = 32;
+const SLOT_SEQUENCE: usize = 0;
+const SLOT_TIMESTAMP: usize = 8;
+const SLOT_WIDTH: usize = 16;
+const SLOT_HEIGHT: usize = 20;
+const SLOT_FORMAT: usize = 24;
+const SLOT_LENGTH: usize = 28;
+
+/// The pixel format of a frame in a [`SharedMemoryRing`], as stored in its header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SharedFrameFormat {
+    /// Decoded RGB, 3 bytes per pixel. This is what [`SharedMemoryRing::update()`] publishes, unless a [`SinkTransform`](crate::SinkTransform) makes the frames opaque.
+    Rgb8,
+    /// Decoded RGBA, 4 bytes per pixel.
+    Rgba8,
+    /// Bytes a [`SinkTransform`](crate::SinkTransform) made out of a frame (e.g. an encrypted frame), see [`SinkPayload::Opaque`]. The resolution is that of the frame they came from.
+    Opaque,
+    /// A raw frame straight from the camera.
+    Raw(FrameFormat),
+}
+
+impl SharedFrameFormat {
+    /// The code stored in the header, for consumers that are not written in Rust.
+    /// `1` is RGB, `2` is RGBA, `3` is opaque, `0x100` is MJPEG, `0x101` is YUYV, `0x102` is Y16, `0x103` is P010, `0x104` is H264, `0x105` is H265 and `0x106` is Z16.
+    /// Bayer formats are `0x200 | bits << 4 | pattern`, where the pattern is `0` for RGGB, `1` for GRBG, `2` for GBRG and `3` for BGGR (e.g. `0x281` is 8 bit GRBG).
+    #[must_use]
+    pub fn code(self) -> u32 {
+        match self {
+            SharedFrameFormat::Rgb8 => 1,
+            SharedFrameFormat::Rgba8 => 2,
+            SharedFrameFormat::Opaque => 3,
+            SharedFrameFormat::Raw(FrameFormat::MJPEG) => 0x100,
+            SharedFrameFormat::Raw(FrameFormat::YUYV) => 0x101,
+            SharedFrameFormat::Raw(FrameFormat::Y16) => 0x102,
+            SharedFrameFormat::Raw(FrameFormat::P010) => 0x103,
+            SharedFrameFormat::Raw(FrameFormat::H264) => 0x104,
+            SharedFrameFormat::Raw(FrameFormat::H265) => 0x105,
+            SharedFrameFormat::Raw(FrameFormat::Z16) => 0x106,
+            SharedFrameFormat::Raw(FrameFormat::Bayer8(pattern)) => bayer_code(8, pattern),
+            SharedFrameFormat::Raw(FrameFormat::Bayer10(pattern)) => bayer_code(10, pattern),
+            SharedFrameFormat::Raw(FrameFormat::Bayer12(pattern)) => bayer_code(12, pattern),
+        }
+    }
+
+    /// The [`SharedFrameFormat`] of a header `code`. See [`code()`](SharedFrameFormat::code).
+    #[must_use]
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(SharedFrameFormat::Rgb8),
+            2 => Some(SharedFrameFormat::Rgba8),
+            3 => Some(SharedFrameFormat::Opaque),
+            0x100 => Some(SharedFrameFormat::Raw(FrameFormat::MJPEG)),
+            0x101 => Some(SharedFrameFormat::Raw(FrameFormat::YUYV)),
+            0x102 => Some(SharedFrameFormat::Raw(FrameFormat::Y16)),
+            0x103 => Some(SharedFrameFormat::Raw(FrameFormat::P010)),
+            0x104 => Some(SharedFrameFormat::Raw(FrameFormat::H264)),
+            0x105 => Some(SharedFrameFormat::Raw(FrameFormat::H265)),
+            0x106 => Some(SharedFrameFormat::Raw(FrameFormat::Z16)),
+            0x200..=0x2ff => {
+                let pattern = match code & 0xf {
+                    0 => BayerPattern::RGGB,
+                    1 => BayerPattern::GRBG,
+                    2 => BayerPattern::GBRG,
+                    3 => BayerPattern::BGGR,
+                    _ => return None,
+                };
+                match (code >> 4) & 0xf {
+                    8 => Some(SharedFrameFormat::Raw(FrameFormat::Bayer8(pattern))),
+                    10 => Some(SharedFrameFormat::Raw(FrameFormat::Bayer10(pattern))),
+                    12 => Some(SharedFrameFormat::Raw(FrameFormat::Bayer12(pattern))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn bayer_code(bits: u32, pattern: BayerPattern) -> u32 {
+    let pattern = match pattern {
+        BayerPattern::RGGB => 0,
+        BayerPattern::GRBG => 1,
+        BayerPattern::GBRG => 2,
+        BayerPattern::BGGR => 3,
+    };
+    0x200 | (bits << 4) | pattern
+}
+
+/// What a frame read from a [`SharedMemoryRing`] is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SharedFrameInfo {
+    /// The sequence number of the frame, starting at 1.
+    pub sequence: u64,
+    /// When the frame was published, since the UNIX epoch.
+    pub timestamp: Duration,
+    /// The resolution of the frame.
+    pub resolution: Resolution,
+    /// The format of the frame.
+    pub format: SharedFrameFormat,
+    /// The length of the frame in bytes.
+    pub length: usize,
+}
+
+/// A ring of frames in named shared memory, so another process (e.g. an ML service) can read the frames a camera captures without sockets or copying them through the kernel.
+///
+/// The publishing process [`create()`](SharedMemoryRing::create)s the ring and [`write_frame()`](SharedMemoryRing::write_frame)s (or [`update()`](SharedMemoryRing::update)s) into it.
+/// Consumers [`open()`](SharedMemoryRing::open) it by name and read the latest frame with [`read_frame_into()`](SharedMemoryRing::read_frame_into).
+/// Each slot has its own header with the resolution, format and timestamp of its frame, so the resolution may change while the ring is in use.
+///
+/// The writer never waits for readers: a slow reader skips frames, and a read that was overtaken by the writer errors (try again).
+///
+/// There must only ever be one writer per ring. Writing takes `&mut self`, so a ring cannot be written from two threads at once, but nothing stops two processes:
+/// a ring from [`open()`](SharedMemoryRing::open) can write too, and writing to it while the publisher does corrupts the frames. Consumers should only read.
+///
+/// The memory layout, for consumers that are not written in Rust (all values little endian on the usual platforms, i.e. native endian):
+/// - A 64 byte header: the magic `0x4e4b4857` (`u32`), the version `1` (`u32`), the slot count (`u32`), the slot capacity in bytes (`u32`), and the sequence number of the latest frame (`u64`, 0 if none).
+/// - Then every slot: a 32 byte header with the sequence number of its frame (`u64`, 0 while it is written), the timestamp in microseconds since the UNIX epoch (`u64`), width, height, [format code](SharedFrameFormat::code) and length (all `u32`), followed by the slot capacity rounded up to 8 bytes.
+///
+/// The latest frame is in slot `(sequence - 1) % slot_count`. Read its sequence number (acquire) before and after copying the frame: if either differs from the ring's, the frame was overwritten.
+/// # Quirks
+/// - On Unix, this is POSIX shared memory (`shm_open()`), and `name` must start with a `/` (e.g. `/nokhwa-camera0`). The publisher removes the name when it is dropped, consumers that already opened the ring keep it.
+/// - On Windows, this is a named file mapping (`CreateFileMapping()`), e.g. `Local\nokhwa-camera0`. It exists as long as any process has it open.
+/// - On Linux, [`create_anonymous()`](SharedMemoryRing::create_anonymous) makes a ring in a `memfd`, which has no name and is shared by passing its [file descriptor](SharedMemoryRing::as_raw_fd) to the consumer (e.g. over a Unix socket).
+pub struct SharedMemoryRing {
+    mapping: platform::Mapping,
+    slots: u32,
+    capacity: u32,
+}
+
+// the pointers are into memory that is meant to be shared, and all shared state goes through atomics. Writing takes `&mut self`, so threads sharing a ring only read
+unsafe impl Send for SharedMemoryRing {}
+unsafe impl Sync for SharedMemoryRing {}
+
+impl SharedMemoryRing {
+    /// Creates a new ring named `name` with `slots` slots of `capacity` bytes, e.g. [`Resolution`] width * height * 3 for RGB frames. 2 or 3 slots are usually enough.
+    /// # Errors
+    /// If `slots` or `capacity` is 0, the ring would be too large, or the shared memory cannot be created (e.g. the name is in use or invalid), this will error.
+    pub fn create(name: &str, slots: u32, capacity: u32) -> Result<Self, NokhwaError> {
+        let size = ring_size(slots, capacity)?;
+        let mapping = platform::Mapping::create(name, size)?;
+        Ok(SharedMemoryRing::initialize(mapping, slots, capacity))
+    }
+
+    /// Creates a new ring like [`create()`](SharedMemoryRing::create), in a `memfd` without a name. Share it with [`as_raw_fd()`](SharedMemoryRing::as_raw_fd) and [`from_raw_fd()`](SharedMemoryRing::from_raw_fd).
+    /// # Errors
+    /// If `slots` or `capacity` is 0, the ring would be too large, or the `memfd` cannot be created, this will error.
+    #[cfg(target_os = "linux")]
+    pub fn create_anonymous(slots: u32, capacity: u32) -> Result<Self, NokhwaError> {
+        let size = ring_size(slots, capacity)?;
+        let mapping = platform::Mapping::create_anonymous(size)?;
+        Ok(SharedMemoryRing::initialize(mapping, slots, capacity))
+    }
+
+    /// Opens the ring named `name`, made by another process with [`create()`](SharedMemoryRing::create). Only read from it, the process that created it is the writer.
+    /// # Errors
+    /// If there is no shared memory named `name`, or it does not hold a ring, this will error.
+    pub fn open(name: &str) -> Result<Self, NokhwaError> {
+        SharedMemoryRing::validate(platform::Mapping::open(name)?)
+    }
+
+    /// Opens a ring from the file descriptor of one made with [`create_anonymous()`](SharedMemoryRing::create_anonymous). The ring takes ownership of `fd`.
+    /// # Errors
+    /// If `fd` cannot be mapped, or does not hold a ring, this will error.
+    /// # Safety
+    /// `fd` must be an open file descriptor that nothing else owns.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> Result<Self, NokhwaError> {
+        SharedMemoryRing::validate(platform::Mapping::from_fd(fd)?)
+    }
+
+    /// The file descriptor of the ring, to pass to a consumer. Only rings from [`create_anonymous()`](SharedMemoryRing::create_anonymous) or [`from_raw_fd()`](SharedMemoryRing::from_raw_fd) keep one, the others return `None`.
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn as_raw_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        self.mapping.fd()
+    }
+
+    fn initialize(mapping: platform::Mapping, slots: u32, capacity: u32) -> Self {
+        let ring = SharedMemoryRing {
+            mapping,
+            slots,
+            capacity,
+        };
+        // fresh shared memory is zeroed, so every slot is empty
+        ring.write_u32(HEADER_VERSION, SHM_VERSION);
+        ring.write_u32(HEADER_SLOTS, slots);
+        ring.write_u32(HEADER_CAPACITY, capacity);
+        // the magic goes last, so a consumer never sees a half made header
+        fence(Ordering::Release);
+        ring.write_u32(HEADER_MAGIC, SHM_MAGIC);
+        ring
+    }
+
+    fn validate(mapping: platform::Mapping) -> Result<Self, NokhwaError> {
+        let invalid = |error: &str| NokhwaError::StructureError {
+            structure: "SharedMemoryRing".to_string(),
+            error: error.to_string(),
+        };
+        if mapping.len() < HEADER_SIZE {
+            return Err(invalid("Too small for a ring"));
+        }
+        let mut ring = SharedMemoryRing {
+            mapping,
+            slots: 0,
+            capacity: 0,
+        };
+        if ring.read_u32(HEADER_MAGIC) != SHM_MAGIC {
+            return Err(invalid("Not a ring, or it is not initialized yet"));
+        }
+        fence(Ordering::Acquire);
+        if ring.read_u32(HEADER_VERSION) != SHM_VERSION {
+            return Err(invalid("Unsupported ring version"));
+        }
+        ring.slots = ring.read_u32(HEADER_SLOTS);
+        ring.capacity = ring.read_u32(HEADER_CAPACITY);
+        match ring_size(ring.slots, ring.capacity) {
+            Ok(size) if size <= ring.mapping.len() => Ok(ring),
+            _ => Err(invalid("The ring is larger than its shared memory")),
+        }
+    }
+
+    /// The number of slots.
+    #[must_use]
+    pub fn slots(&self) -> u32 {
+        self.slots
+    }
+
+    /// The largest frame a slot holds, in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// The sequence number of the latest frame, or 0 if none was written yet.
+    #[must_use]
+    pub fn sequence(&self) -> u64 {
+        self.atomic_u64(HEADER_SEQUENCE).load(Ordering::Acquire)
+    }
+
+    /// Writes `frame` into the next slot and returns its sequence number.
+    /// # Errors
+    /// If `frame` is larger than the [`capacity()`](SharedMemoryRing::capacity), this will error.
+    pub fn write_frame(
+        &mut self,
+        frame: &[u8],
+        resolution: Resolution,
+        format: SharedFrameFormat,
+    ) -> Result<u64, NokhwaError> {
+        let length = match u32::try_from(frame.len()) {
+            Ok(length) if length <= self.capacity => length,
+            _ => {
+                return Err(NokhwaError::ReadFrameError(format!(
+                    "The frame is {} bytes, but the ring holds {} byte frames",
+                    frame.len(),
+                    self.capacity
+                )))
+            }
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let sequence = self.sequence() + 1;
+        let slot = self.slot_offset(sequence);
+        let slot_sequence = self.atomic_u64(slot + SLOT_SEQUENCE);
+        slot_sequence.store(0, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.write_u64(slot + SLOT_TIMESTAMP, timestamp);
+        self.write_u32(slot + SLOT_WIDTH, resolution.width());
+        self.write_u32(slot + SLOT_HEIGHT, resolution.height());
+        self.write_u32(slot + SLOT_FORMAT, format.code());
+        self.write_u32(slot + SLOT_LENGTH, length);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                frame.as_ptr(),
+                self.mapping.ptr().add(slot + SLOT_HEADER_SIZE),
+                frame.len(),
+            );
+        }
+        slot_sequence.store(sequence, Ordering::Release);
+        self.atomic_u64(HEADER_SEQUENCE)
+            .store(sequence, Ordering::Release);
+        Ok(sequence)
+    }
+
+    /// Captures a frame from `camera` (see [`Camera::frame_for_sink()`]) and writes it as [`SharedFrameFormat::Rgb8`], or as [`SharedFrameFormat::Opaque`] if the camera's [`SinkTransform`](crate::SinkTransform) made it opaque.
+    /// Returns its sequence number.
+    /// # Errors
+    /// If the frame cannot be captured or transformed, or it is larger than the [`capacity()`](SharedMemoryRing::capacity), this will error.
+    pub fn update(&mut self, camera: &mut Camera) -> Result<u64, NokhwaError> {
+        let payload = camera.frame_for_sink()?;
+        let format = match payload {
+            SinkPayload::Image(_) => SharedFrameFormat::Rgb8,
+            SinkPayload::Opaque { .. } => SharedFrameFormat::Opaque,
+        };
+        self.write_frame(payload.as_bytes(), payload.resolution(), format)
+    }
+
+    /// Copies the latest frame into the start of `buffer`, returning what it is, or `None` if no frame was written yet.
+    /// # Errors
+    /// If `buffer` is too small for the frame (make it [`capacity()`](SharedMemoryRing::capacity) bytes), the frame was overwritten while it was being read, or the slot header is invalid, this will error.
+    pub fn read_frame_into(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<Option<SharedFrameInfo>, NokhwaError> {
+        let sequence = self.sequence();
+        if sequence == 0 {
+            return Ok(None);
+        }
+        let slot = self.slot_offset(sequence);
+        let slot_sequence = self.atomic_u64(slot + SLOT_SEQUENCE);
+        let overwritten =
+            || NokhwaError::ReadFrameError("The frame was overwritten while reading".to_string());
+        if slot_sequence.load(Ordering::Acquire) != sequence {
+            return Err(overwritten());
+        }
+
+        let timestamp = self.read_u64(slot + SLOT_TIMESTAMP);
+        let resolution = Resolution::new(
+            self.read_u32(slot + SLOT_WIDTH),
+            self.read_u32(slot + SLOT_HEIGHT),
+        );
+        let format = self.read_u32(slot + SLOT_FORMAT);
+        let length = self.read_u32(slot + SLOT_LENGTH).min(self.capacity) as usize;
+        if buffer.len() < length {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "The frame is {} bytes, but the buffer is {} bytes",
+                length,
+                buffer.len()
+            )));
+        }
+        unsafe {
+            ptr::copy_nonoverlapping(
+                self.mapping.ptr().add(slot + SLOT_HEADER_SIZE),
+                buffer.as_mut_ptr(),
+                length,
+            );
+        }
+        fence(Ordering::Acquire);
+        if slot_sequence.load(Ordering::Relaxed) != sequence {
+            return Err(overwritten());
+        }
+
+        let format = match SharedFrameFormat::from_code(format) {
+            Some(format) => format,
+            None => {
+                return Err(NokhwaError::ReadFrameError(format!(
+                    "Unknown frame format code {}",
+                    format
+                )))
+            }
+        };
+        Ok(Some(SharedFrameInfo {
+            sequence,
+            timestamp: Duration::from_micros(timestamp),
+            resolution,
+            format,
+            length,
+        }))
+    }
+
+    /// Reads the latest frame like [`read_frame_into()`](SharedMemoryRing::read_frame_into), into a new `Vec<u8>`.
+    /// # Errors
+    /// See [`read_frame_into()`](SharedMemoryRing::read_frame_into).
+    pub fn read_frame(&self) -> Result<Option<(SharedFrameInfo, Vec<u8>)>, NokhwaError> {
+        let mut buffer = vec![0_u8; self.capacity as usize];
+        match self.read_frame_into(&mut buffer)? {
+            Some(info) => {
+                buffer.truncate(info.length);
+                Ok(Some((info, buffer)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn slot_offset(&self, sequence: u64) -> usize {
+        #[allow(clippy::cast_possible_truncation)]
+        let slot = ((sequence - 1) % u64::from(self.slots)) as usize;
+        HEADER_SIZE + slot * slot_stride(self.capacity)
+    }
+
+    // every `u64` in the ring is 8 byte aligned, and the mapping is page aligned
+    #[allow(clippy::cast_ptr_alignment)]
+    fn atomic_u64(&self, offset: usize) -> &AtomicU64 {
+        unsafe { &*(self.mapping.ptr().add(offset) as *const AtomicU64) }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    fn read_u32(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile(self.mapping.ptr().add(offset) as *const u32) }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    fn read_u64(&self, offset: usize) -> u64 {
+        unsafe { ptr::read_volatile(self.mapping.ptr().add(offset) as *const u64) }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    fn write_u32(&self, offset: usize, value: u32) {
+        unsafe {
+            ptr::write_volatile(self.mapping.ptr().add(offset).cast::<u32>(), value);
+        }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    fn write_u64(&self, offset: usize, value: u64) {
+        unsafe {
+            ptr::write_volatile(self.mapping.ptr().add(offset).cast::<u64>(), value);
+        }
+    }
+}
+
+fn slot_stride(capacity: u32) -> usize {
+    SLOT_HEADER_SIZE + ((capacity as usize + 7) & !7)
+}
+
+fn ring_size(slots: u32, capacity: u32) -> Result<usize, NokhwaError> {
+    if slots == 0 || capacity == 0 {
+        return Err(NokhwaError::StructureError {
+            structure: "SharedMemoryRing".to_string(),
+            error: "A ring needs at least 1 slot of at least 1 byte".to_string(),
+        });
+    }
+    match slot_stride(capacity)
+        .checked_mul(slots as usize)
+        .and_then(|slots| slots.checked_add(HEADER_SIZE))
+    {
+        Some(size) => Ok(size),
+        None => Err(NokhwaError::StructureError {
+            structure: "SharedMemoryRing".to_string(),
+            error: format!("Cannot fit {} slots of {} bytes", slots, capacity),
+        }),
+    }
+}
+
+fn shm_error(operation: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!(
+        "{} failed: {}",
+        operation,
+        std::io::Error::last_os_error()
+    ))
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::shm_error;
+    use crate::NokhwaError;
+    use std::{ffi::CString, os::unix::io::RawFd, ptr};
+
+    pub struct Mapping {
+        ptr: *mut u8,
+        len: usize,
+        // the name to unlink on drop, for the process that made it
+        owned_name: Option<CString>,
+        fd: Option<RawFd>,
+    }
+
+    impl Mapping {
+        pub fn create(name: &str, len: usize) -> Result<Self, NokhwaError> {
+            let c_name = shm_name(name)?;
+            let fd = unsafe {
+                libc::shm_open(
+                    c_name.as_ptr(),
+                    libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                    0o600,
+                )
+            };
+            if fd < 0 {
+                return Err(shm_error("shm_open"));
+            }
+            match Mapping::map(fd, len, true) {
+                Ok(mut mapping) => {
+                    unsafe { libc::close(fd) };
+                    mapping.owned_name = Some(c_name);
+                    Ok(mapping)
+                }
+                Err(why) => {
+                    unsafe {
+                        libc::close(fd);
+                        libc::shm_unlink(c_name.as_ptr());
+                    }
+                    Err(why)
+                }
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        pub fn create_anonymous(len: usize) -> Result<Self, NokhwaError> {
+            let fd =
+                unsafe { libc::memfd_create(b"nokhwa-ring\0".as_ptr().cast(), libc::MFD_CLOEXEC) };
+            if fd < 0 {
+                return Err(shm_error("memfd_create"));
+            }
+            match Mapping::map(fd, len, true) {
+                Ok(mut mapping) => {
+                    mapping.fd = Some(fd);
+                    Ok(mapping)
+                }
+                Err(why) => {
+                    unsafe { libc::close(fd) };
+                    Err(why)
+                }
+            }
+        }
+
+        pub fn open(name: &str) -> Result<Self, NokhwaError> {
+            let c_name = shm_name(name)?;
+            let fd = unsafe { libc::shm_open(c_name.as_ptr(), libc::O_RDWR, 0) };
+            if fd < 0 {
+                return Err(shm_error("shm_open"));
+            }
+            let mapping = Mapping::map_existing(fd);
+            unsafe { libc::close(fd) };
+            mapping
+        }
+
+        #[cfg(target_os = "linux")]
+        pub unsafe fn from_fd(fd: RawFd) -> Result<Self, NokhwaError> {
+            match Mapping::map_existing(fd) {
+                Ok(mut mapping) => {
+                    mapping.fd = Some(fd);
+                    Ok(mapping)
+                }
+                Err(why) => {
+                    libc::close(fd);
+                    Err(why)
+                }
+            }
+        }
+
+        fn map_existing(fd: RawFd) -> Result<Self, NokhwaError> {
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+                return Err(shm_error("fstat"));
+            }
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let len = stat.st_size as usize;
+            Mapping::map(fd, len, false)
+        }
+
+        fn map(fd: RawFd, len: usize, resize: bool) -> Result<Self, NokhwaError> {
+            #[allow(clippy::cast_possible_wrap)]
+            let size = len as libc::off_t;
+            if resize && unsafe { libc::ftruncate(fd, size) } != 0 {
+                return Err(shm_error("ftruncate"));
+            }
+            let ptr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(shm_error("mmap"));
+            }
+            Ok(Mapping {
+                ptr: ptr.cast(),
+                len,
+                owned_name: None,
+                fd: None,
+            })
+        }
+
+        pub fn ptr(&self) -> *mut u8 {
+            self.ptr
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        #[cfg(target_os = "linux")]
+        pub fn fd(&self) -> Option<RawFd> {
+            self.fd
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            unsafe {
+                libc::munmap(self.ptr.cast(), self.len);
+                if let Some(fd) = self.fd {
+                    libc::close(fd);
+                }
+                if let Some(name) = &self.owned_name {
+                    libc::shm_unlink(name.as_ptr());
+                }
+            }
+        }
+    }
+
+    fn shm_name(name: &str) -> Result<CString, NokhwaError> {
+        if !name.starts_with('/') || name[1..].contains('/') {
+            return Err(NokhwaError::StructureError {
+                structure: "SharedMemoryRing".to_string(),
+                error: format!("Invalid name {}, it must be like /name", name),
+            });
+        }
+        match CString::new(name) {
+            Ok(name) => Ok(name),
+            Err(why) => Err(NokhwaError::StructureError {
+                structure: "SharedMemoryRing".to_string(),
+                error: why.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::shm_error;
+    use crate::NokhwaError;
+    use std::{ffi::c_void, ffi::OsStr, os::windows::ffi::OsStrExt, ptr};
+
+    const INVALID_HANDLE_VALUE: *mut c_void = -1_isize as *mut c_void;
+    const PAGE_READWRITE: u32 = 0x04;
+    const FILE_MAP_ALL_ACCESS: u32 = 0x000f_001f;
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct MemoryBasicInformation {
+        base_address: *mut c_void,
+        allocation_base: *mut c_void,
+        allocation_protect: u32,
+        #[cfg(target_pointer_width = "64")]
+        partition_id: u16,
+        region_size: usize,
+        state: u32,
+        protect: u32,
+        kind: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateFileMappingW(
+            file: *mut c_void,
+            attributes: *mut c_void,
+            protect: u32,
+            size_high: u32,
+            size_low: u32,
+            name: *const u16,
+        ) -> *mut c_void;
+        fn OpenFileMappingW(access: u32, inherit: i32, name: *const u16) -> *mut c_void;
+        fn MapViewOfFile(
+            mapping: *mut c_void,
+            access: u32,
+            offset_high: u32,
+            offset_low: u32,
+            len: usize,
+        ) -> *mut c_void;
+        fn UnmapViewOfFile(address: *const c_void) -> i32;
+        fn VirtualQuery(
+            address: *const c_void,
+            info: *mut MemoryBasicInformation,
+            len: usize,
+        ) -> usize;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    const ERROR_ALREADY_EXISTS: u32 = 183;
+
+    pub struct Mapping {
+        ptr: *mut u8,
+        len: usize,
+        handle: *mut c_void,
+    }
+
+    impl Mapping {
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn create(name: &str, len: usize) -> Result<Self, NokhwaError> {
+            let wide_name = wide(name);
+            let len_64 = len as u64;
+            let handle = unsafe {
+                CreateFileMappingW(
+                    INVALID_HANDLE_VALUE,
+                    ptr::null_mut(),
+                    PAGE_READWRITE,
+                    (len_64 >> 32) as u32,
+                    len_64 as u32,
+                    wide_name.as_ptr(),
+                )
+            };
+            if handle.is_null() {
+                return Err(shm_error("CreateFileMappingW"));
+            }
+            if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+                unsafe { CloseHandle(handle) };
+                return Err(NokhwaError::StructureError {
+                    structure: "SharedMemoryRing".to_string(),
+                    error: format!("{} already exists", name),
+                });
+            }
+            Mapping::map(handle, len)
+        }
+
+        pub fn open(name: &str) -> Result<Self, NokhwaError> {
+            let wide_name = wide(name);
+            let handle = unsafe { OpenFileMappingW(FILE_MAP_ALL_ACCESS, 0, wide_name.as_ptr()) };
+            if handle.is_null() {
+                return Err(shm_error("OpenFileMappingW"));
+            }
+            // 0 maps all of it, and the size is what the view turns out to be
+            let mut mapping = Mapping::map(handle, 0)?;
+            let mut info: MemoryBasicInformation = unsafe { std::mem::zeroed() };
+            if unsafe {
+                VirtualQuery(
+                    mapping.ptr.cast(),
+                    &mut info,
+                    std::mem::size_of::<MemoryBasicInformation>(),
+                )
+            } == 0
+            {
+                return Err(shm_error("VirtualQuery"));
+            }
+            mapping.len = info.region_size;
+            Ok(mapping)
+        }
+
+        fn map(handle: *mut c_void, len: usize) -> Result<Self, NokhwaError> {
+            let ptr = unsafe { MapViewOfFile(handle, FILE_MAP_ALL_ACCESS, 0, 0, len) };
+            if ptr.is_null() {
+                let why = shm_error("MapViewOfFile");
+                unsafe { CloseHandle(handle) };
+                return Err(why);
+            }
+            Ok(Mapping {
+                ptr: ptr.cast(),
+                len,
+                handle,
+            })
+        }
+
+        pub fn ptr(&self) -> *mut u8 {
+            self.ptr
+        }
+
+        pub fn len(&self) -> usize {
+            self.len
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            unsafe {
+                UnmapViewOfFile(self.ptr as *const c_void);
+                CloseHandle(self.handle);
+            }
+        }
+    }
+
+    fn wide(name: &str) -> Vec<u16> {
+        OsStr::new(name).encode_wide().chain(Some(0)).collect()
+    }
+}
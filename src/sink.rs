@@ -0,0 +1,128 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{NokhwaError, Rect, Resolution};
+use image::{ImageBuffer, Rgb};
+
+/// What a [`SinkTransform`] hands to a sink.
+/// - `Image` - A (possibly modified, e.g. redacted) RGB frame. Sinks encode it as they normally would.
+/// - `Opaque` - Bytes the sink cannot look into (e.g. an encrypted frame). Sinks that write frames (recorders, network streams) write these as is, with the [`Resolution`] of the frame they came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SinkPayload {
+    Image(ImageBuffer<Rgb<u8>, Vec<u8>>),
+    Opaque {
+        data: Vec<u8>,
+        resolution: Resolution,
+    },
+}
+
+impl SinkPayload {
+    /// The [`Resolution`] of the frame this payload came from.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            SinkPayload::Image(image) => Resolution::new(image.width(), image.height()),
+            SinkPayload::Opaque { resolution, .. } => *resolution,
+        }
+    }
+
+    /// The bytes of the payload. For an `Image`, these are the raw RGB pixels.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            SinkPayload::Image(image) => image.as_raw(),
+            SinkPayload::Opaque { data, .. } => data,
+        }
+    }
+
+    /// The [`ImageBuffer`], if this payload is still an image.
+    #[must_use]
+    pub fn image(&self) -> Option<&ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        match self {
+            SinkPayload::Image(image) => Some(image),
+            SinkPayload::Opaque { .. } => None,
+        }
+    }
+}
+
+/// A per-frame transform that runs right before frames reach a sink, e.g. to encrypt or redact them, so that a plaintext frame is never written by the sink.
+///
+/// Set it with [`Camera::set_sink_transform()`](crate::Camera::set_sink_transform). Frames from [`Camera::frame()`](crate::Camera::frame) are **not** transformed, only the ones sinks get from [`Camera::frame_for_sink()`](crate::Camera::frame_for_sink).
+///
+/// This is implemented for any `FnMut(ImageBuffer<Rgb<u8>, Vec<u8>>) -> Result<SinkPayload, NokhwaError> + Send` closure.
+pub trait SinkTransform: Send {
+    /// Transforms `frame` into what the sink should get.
+    /// # Errors
+    /// If this errors, the sink drops the frame and reports the error instead of falling back to the untransformed frame.
+    fn transform(
+        &mut self,
+        frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<SinkPayload, NokhwaError>;
+}
+
+impl<F> SinkTransform for F
+where
+    F: FnMut(ImageBuffer<Rgb<u8>, Vec<u8>>) -> Result<SinkPayload, NokhwaError> + Send,
+{
+    fn transform(
+        &mut self,
+        frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<SinkPayload, NokhwaError> {
+        self(frame)
+    }
+}
+
+/// A [`SinkTransform`] that fills regions of every frame with a solid color, e.g. to black out a screen or a face in a fixed position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redact {
+    regions: Vec<Rect>,
+    color: Rgb<u8>,
+}
+
+impl Redact {
+    /// Creates a new [`Redact`] that fills `regions` with black. The parts of a region that are outside the frame are ignored.
+    #[must_use]
+    pub fn new(regions: Vec<Rect>) -> Self {
+        Redact {
+            regions,
+            color: Rgb([0, 0, 0]),
+        }
+    }
+
+    /// Sets the color the regions are filled with.
+    #[must_use]
+    pub fn with_color(mut self, color: Rgb<u8>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The regions that are redacted.
+    #[must_use]
+    pub fn regions(&self) -> &[Rect] {
+        &self.regions
+    }
+}
+
+impl SinkTransform for Redact {
+    fn transform(
+        &mut self,
+        mut frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<SinkPayload, NokhwaError> {
+        let resolution = Resolution::new(frame.width(), frame.height());
+        for region in &self.regions {
+            let rect = match region.clamp_to(resolution) {
+                Some(rect) => rect,
+                None => continue,
+            };
+            for y in rect.y()..rect.y() + rect.height() {
+                for x in rect.x()..rect.x() + rect.width() {
+                    frame.put_pixel(x, y, self.color);
+                }
+            }
+        }
+        Ok(SinkPayload::Image(frame))
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::NokhwaError;
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    ColorType, ImageBuffer, ImageFormat, Rgb,
+};
+use std::{
+    convert::TryFrom,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The JPEG quality snapshots are saved with.
+pub const SNAPSHOT_JPEG_QUALITY: u8 = 90;
+
+const EXIF_IMAGE_WIDTH: u16 = 0x0100;
+const EXIF_IMAGE_LENGTH: u16 = 0x0101;
+const EXIF_MODEL: u16 = 0x0110;
+const EXIF_SOFTWARE: u16 = 0x0131;
+const EXIF_DATE_TIME: u16 = 0x0132;
+
+const EXIF_TYPE_ASCII: u16 = 2;
+const EXIF_TYPE_LONG: u16 = 4;
+
+// "YYYY:MM:DD HH:MM:SS", which is the only date format EXIF allows
+fn exif_date_time(timestamp: SystemTime) -> String {
+    let seconds = timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, time) = (seconds / 86400, seconds % 86400);
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    )
+}
+
+fn exif_ascii(text: &str) -> Vec<u8> {
+    let mut data = text.as_bytes().to_vec();
+    data.push(0);
+    data
+}
+
+// A big endian TIFF structure with a single IFD, as EXIF expects it (without the `Exif\0\0` header JPEG adds).
+#[allow(clippy::cast_possible_truncation)]
+fn exif_tiff(width: u32, height: u32, camera_name: &str, timestamp: SystemTime) -> Vec<u8> {
+    let mut entries = vec![
+        (
+            EXIF_IMAGE_WIDTH,
+            EXIF_TYPE_LONG,
+            width.to_be_bytes().to_vec(),
+        ),
+        (
+            EXIF_IMAGE_LENGTH,
+            EXIF_TYPE_LONG,
+            height.to_be_bytes().to_vec(),
+        ),
+    ];
+    if !camera_name.is_empty() {
+        entries.push((EXIF_MODEL, EXIF_TYPE_ASCII, exif_ascii(camera_name)));
+    }
+    entries.push((EXIF_SOFTWARE, EXIF_TYPE_ASCII, exif_ascii("nokhwa")));
+    entries.push((
+        EXIF_DATE_TIME,
+        EXIF_TYPE_ASCII,
+        exif_ascii(&exif_date_time(timestamp)),
+    ));
+
+    let mut tiff = b"MM\x00\x2A\x00\x00\x00\x08".to_vec();
+    let mut values = vec![];
+    // header, entry count, entries, next IFD offset
+    let values_offset = 8 + 2 + 12 * entries.len() + 4;
+
+    tiff.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+    for (tag, data_type, data) in entries {
+        let count = match data_type {
+            EXIF_TYPE_LONG => data.len() / 4,
+            _ => data.len(),
+        };
+        tiff.extend_from_slice(&tag.to_be_bytes());
+        tiff.extend_from_slice(&data_type.to_be_bytes());
+        tiff.extend_from_slice(&(count as u32).to_be_bytes());
+        if data.len() <= 4 {
+            let mut inline = data;
+            inline.resize(4, 0);
+            tiff.extend_from_slice(&inline);
+        } else {
+            tiff.extend_from_slice(&((values_offset + values.len()) as u32).to_be_bytes());
+            values.extend_from_slice(&data);
+            // values have to start on a word boundary
+            if values.len() % 2 == 1 {
+                values.push(0);
+            }
+        }
+    }
+    tiff.extend_from_slice(&0_u32.to_be_bytes());
+    tiff.extend_from_slice(&values);
+    tiff
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn encode_error(format: ImageFormat, why: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("Could not encode {:?} snapshot: {}", format, why))
+}
+
+/// Encodes `frame` as a JPEG (with [`SNAPSHOT_JPEG_QUALITY`]) or PNG, with EXIF metadata: the resolution, `camera_name` (as the camera model, left out if empty) and
+/// `timestamp` (in UTC, EXIF has no time zone).
+///
+/// The EXIF is written as an `APP1` segment for JPEG, and as an `eXIf` chunk for PNG.
+/// # Errors
+/// If `format` is not [`ImageFormat::Jpeg`] or [`ImageFormat::Png`], or encoding fails, this will error.
+#[allow(clippy::cast_possible_truncation)]
+pub fn encode_snapshot(
+    frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    format: ImageFormat,
+    camera_name: &str,
+    timestamp: SystemTime,
+) -> Result<Vec<u8>, NokhwaError> {
+    let (width, height) = frame.dimensions();
+    let tiff = exif_tiff(width, height, camera_name, timestamp);
+    let mut encoded = vec![];
+
+    match format {
+        ImageFormat::Jpeg => {
+            if let Err(why) = JpegEncoder::new_with_quality(&mut encoded, SNAPSHOT_JPEG_QUALITY)
+                .encode(frame.as_raw(), width, height, ColorType::Rgb8)
+            {
+                return Err(encode_error(format, &why.to_string()));
+            }
+            let segment_len = match u16::try_from(tiff.len() + 8) {
+                Ok(len) => len,
+                Err(_) => return Err(encode_error(format, "EXIF data is too large")),
+            };
+            // right after SOI and the JFIF APP0 segment
+            let insert_at = match encoded.get(4..6) {
+                Some(app0_len) => 4 + usize::from(u16::from_be_bytes([app0_len[0], app0_len[1]])),
+                None => return Err(encode_error(format, "Encoder output is too short")),
+            };
+            let mut app1 = vec![0xFF, 0xE1];
+            app1.extend_from_slice(&segment_len.to_be_bytes());
+            app1.extend_from_slice(b"Exif\0\0");
+            app1.extend_from_slice(&tiff);
+            encoded.splice(insert_at..insert_at, app1);
+        }
+        ImageFormat::Png => {
+            if let Err(why) =
+                PngEncoder::new(&mut encoded).encode(frame.as_raw(), width, height, ColorType::Rgb8)
+            {
+                return Err(encode_error(format, &why.to_string()));
+            }
+            let mut chunk_data = b"eXIf".to_vec();
+            chunk_data.extend_from_slice(&tiff);
+            let mut chunk = (tiff.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(&chunk_data);
+            chunk.extend_from_slice(&crc32(&chunk_data).to_be_bytes());
+            // the signature (8) and IHDR (25) always come first, eXIf has to be before IDAT
+            if encoded.len() < 33 {
+                return Err(encode_error(format, "Encoder output is too short"));
+            }
+            encoded.splice(33..33, chunk);
+        }
+        _ => {
+            return Err(encode_error(
+                format,
+                "Only JPEG and PNG snapshots are supported",
+            ))
+        }
+    }
+    Ok(encoded)
+}
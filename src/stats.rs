@@ -0,0 +1,182 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
+
+/// The default length of the sliding window [`CaptureStats`] are measured over.
+pub const DEFAULT_STATS_WINDOW: Duration = Duration::from_secs(2);
+
+/// Capture statistics of a [`Camera`](crate::Camera), measured over a sliding window of the most recent frames, see [`Camera::capture_stats()`](crate::Camera::capture_stats).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CaptureStats {
+    fps: f64,
+    average_interval: Option<Duration>,
+    max_interval: Option<Duration>,
+    average_decode_time: Option<Duration>,
+    window: Duration,
+    frames_in_window: usize,
+    frames_captured: u64,
+    frames_dropped: u64,
+}
+
+impl CaptureStats {
+    /// The measured frame rate over the window. This is 0 until two frames have been captured.
+    #[must_use]
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// The average time between two frames in the window.
+    #[must_use]
+    pub fn average_interval(&self) -> Option<Duration> {
+        self.average_interval
+    }
+
+    /// The longest time between two frames in the window. Much longer than [`average_interval()`](CaptureStats::average_interval) means the camera (or the consumer) stalls.
+    #[must_use]
+    pub fn max_interval(&self) -> Option<Duration> {
+        self.max_interval
+    }
+
+    /// The average time spent decoding a frame into RGB in the window. This is `None` for raw frames, and for backends that decode on a thread of their own (such as `libuvc` and `GStreamer`).
+    #[must_use]
+    pub fn average_decode_time(&self) -> Option<Duration> {
+        self.average_decode_time
+    }
+
+    /// The length of the window.
+    #[must_use]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// How many frames the window holds.
+    #[must_use]
+    pub fn frames_in_window(&self) -> usize {
+        self.frames_in_window
+    }
+
+    /// How many frames were captured since the camera was created (or the stats were reset).
+    #[must_use]
+    pub fn frames_captured(&self) -> u64 {
+        self.frames_captured
+    }
+
+    /// How many frames failed to be read or decoded since the camera was created (or the stats were reset).
+    #[must_use]
+    pub fn frames_dropped(&self) -> u64 {
+        self.frames_dropped
+    }
+}
+
+// the frames in the window, as when they arrived and how long they took to decode
+#[derive(Clone, Debug)]
+pub(crate) struct StatsTracker {
+    window: Duration,
+    frames: VecDeque<(Instant, Option<Duration>)>,
+    frames_captured: u64,
+    frames_dropped: u64,
+}
+
+impl StatsTracker {
+    pub(crate) fn new() -> Self {
+        StatsTracker {
+            window: DEFAULT_STATS_WINDOW,
+            frames: VecDeque::new(),
+            frames_captured: 0,
+            frames_dropped: 0,
+        }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub(crate) fn set_window(&mut self, window: Duration) {
+        self.window = window;
+        self.trim(Instant::now());
+    }
+
+    pub(crate) fn record_frame(&mut self, decode_time: Option<Duration>) {
+        let now = Instant::now();
+        self.frames_captured += 1;
+        self.frames.push_back((now, decode_time));
+        self.trim(now);
+    }
+
+    pub(crate) fn record_dropped_frame(&mut self) {
+        self.frames_dropped += 1;
+    }
+
+    // intervals across a reopened stream mean nothing, the counters carry on
+    pub(crate) fn restart_window(&mut self) {
+        self.frames.clear();
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.frames.clear();
+        self.frames_captured = 0;
+        self.frames_dropped = 0;
+    }
+
+    fn trim(&mut self, now: Instant) {
+        while let Some((arrived, _)) = self.frames.front() {
+            if now.duration_since(*arrived) <= self.window {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn stats(&self) -> CaptureStats {
+        let intervals = self
+            .frames
+            .iter()
+            .zip(self.frames.iter().skip(1))
+            .map(|((earlier, _), (later, _))| later.duration_since(*earlier));
+        let max_interval = intervals.max();
+
+        let (average_interval, fps) = match (self.frames.front(), self.frames.back()) {
+            (Some((first, _)), Some((last, _))) if self.frames.len() > 1 => {
+                let span = last.duration_since(*first);
+                let count = u32::try_from(self.frames.len() - 1).unwrap_or(u32::MAX);
+                let fps = if span > Duration::from_secs(0) {
+                    f64::from(count) / span.as_secs_f64()
+                } else {
+                    0_f64
+                };
+                (Some(span / count), fps)
+            }
+            _ => (None, 0_f64),
+        };
+
+        let decode_times: Vec<Duration> = self
+            .frames
+            .iter()
+            .filter_map(|(_, decode_time)| *decode_time)
+            .collect();
+        let average_decode_time = match u32::try_from(decode_times.len()) {
+            Ok(count) if count > 0 => Some(decode_times.iter().sum::<Duration>() / count),
+            _ => None,
+        };
+
+        CaptureStats {
+            fps,
+            average_interval,
+            max_interval,
+            average_decode_time,
+            window: self.window,
+            frames_in_window: self.frames.len(),
+            frames_captured: self.frames_captured,
+            frames_dropped: self.frames_dropped,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+// Internal instrumentation hooks. Without the `metrics` and `tracing` features every function here compiles down to nothing,
+// so call sites do not need to be feature-gated.
+#![cfg_attr(
+    not(all(feature = "metrics", feature = "tracing")),
+    allow(unused_variables, dead_code)
+)]
+
+use crate::{CameraFormat, CaptureAPIBackend, FormatStrategy, FrameFormat, NokhwaError};
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static LAST_DECODE_TIME: Cell<Option<Duration>> = Cell::new(None);
+}
+
+/// Counter: frames successfully captured. Labels: `backend`, `index`.
+pub(crate) const FRAMES_CAPTURED: &str = "nokhwa_frames_captured_total";
+/// Counter: frames that failed to be read or decoded and were dropped. Labels: `backend`, `index`.
+pub(crate) const FRAMES_DROPPED: &str = "nokhwa_frames_dropped_total";
+/// Gauge: instantaneous capture rate, derived from the time between the two most recent frames. Labels: `backend`, `index`.
+pub(crate) const CAPTURE_FPS: &str = "nokhwa_capture_fps";
+/// Histogram: seconds spent inside a frame call (capture + decode). Labels: `backend`, `index`.
+pub(crate) const FRAME_TIME: &str = "nokhwa_frame_seconds";
+/// Histogram: seconds spent decoding a frame into RGB. Labels: `format`.
+pub(crate) const DECODE_TIME: &str = "nokhwa_decode_seconds";
+/// Counter: times a device was re-opened. Labels: `backend`, `index`.
+pub(crate) const RECONNECTS: &str = "nokhwa_reconnects_total";
+
+/// Returns the current instant if any instrumentation is enabled, `None` otherwise.
+#[inline]
+pub(crate) fn now() -> Option<Instant> {
+    if cfg!(any(feature = "metrics", feature = "tracing")) {
+        Some(Instant::now())
+    } else {
+        None
+    }
+}
+
+/// Records a successfully captured frame. `started` is the instant the frame call began, `last_frame` is the instant
+/// the previous frame was delivered.
+#[inline]
+pub(crate) fn record_frame(
+    backend: CaptureAPIBackend,
+    index: usize,
+    started: Option<Instant>,
+    last_frame: Option<Instant>,
+) {
+    #[cfg(feature = "tracing")]
+    if let Some(started) = started {
+        tracing::trace!(%backend, index, frame_time = ?started.elapsed(), "Captured a frame");
+    }
+    #[cfg(feature = "metrics")]
+    {
+        let backend = backend.to_string();
+        let index = index.to_string();
+        metrics::increment_counter!(FRAMES_CAPTURED, "backend" => backend.clone(), "index" => index.clone());
+        if let Some(started) = started {
+            metrics::histogram!(FRAME_TIME, started.elapsed(), "backend" => backend.clone(), "index" => index.clone());
+            if let Some(last) = last_frame {
+                let interval = started.duration_since(last).as_secs_f64();
+                if interval > 0_f64 {
+                    metrics::gauge!(CAPTURE_FPS, 1_f64 / interval, "backend" => backend, "index" => index);
+                }
+            }
+        }
+    }
+}
+
+/// Records a frame that could not be read or decoded, and why.
+#[inline]
+pub(crate) fn record_dropped_frame(backend: CaptureAPIBackend, index: usize, error: &NokhwaError) {
+    #[cfg(feature = "metrics")]
+    metrics::increment_counter!(FRAMES_DROPPED, "backend" => backend.to_string(), "index" => index.to_string());
+    #[cfg(feature = "tracing")]
+    tracing::warn!(%backend, index, %error, "Dropped a frame");
+}
+
+/// Records the time spent decoding a frame of `format`, which began at `started`. Unlike the other timings, this is always taken, as it is part of the [`CaptureStats`](crate::CaptureStats)
+/// of the camera that decoded the frame (on this thread), see [`take_decode_time()`].
+#[inline]
+pub(crate) fn record_decode(format: FrameFormat, started: Instant) {
+    let elapsed = started.elapsed();
+    LAST_DECODE_TIME.with(|last| last.set(Some(elapsed)));
+    #[cfg(feature = "tracing")]
+    tracing::trace!(%format, decode_time = ?elapsed, "Decoded a frame");
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(DECODE_TIME, elapsed, "format" => format.to_string());
+}
+
+/// Takes the time the last decode on this thread took, if there was one since the last call.
+#[inline]
+pub(crate) fn take_decode_time() -> Option<Duration> {
+    LAST_DECODE_TIME.with(Cell::take)
+}
+
+/// Records a device being re-opened (e.g. after an index or backend change, or a dropped network stream).
+#[inline]
+pub(crate) fn record_reconnect(backend: CaptureAPIBackend, index: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::increment_counter!(RECONNECTS, "backend" => backend.to_string(), "index" => index.to_string());
+    #[cfg(feature = "tracing")]
+    tracing::info!(%backend, index, "Re-opened camera");
+}
+
+/// A `tracing` span that stays entered until this is dropped.
+pub(crate) struct SpanGuard {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Enters the span of a single frame call (capture, decode and transforms).
+#[inline]
+#[must_use]
+pub(crate) fn frame_span(backend: CaptureAPIBackend, index: usize) -> SpanGuard {
+    SpanGuard {
+        #[cfg(feature = "tracing")]
+        _span: tracing::trace_span!("frame", %backend, index).entered(),
+    }
+}
+
+/// Enters the span of decoding a single frame of `format`.
+#[inline]
+#[must_use]
+pub(crate) fn decode_span(format: FrameFormat) -> SpanGuard {
+    SpanGuard {
+        #[cfg(feature = "tracing")]
+        _span: tracing::trace_span!("decode", %format).entered(),
+    }
+}
+
+/// Records an attempt to open the device at `index` with `requested` (which may be [`CaptureAPIBackend::Auto`]),
+/// and the backend that ended up opening it.
+#[inline]
+pub(crate) fn record_open(
+    requested: CaptureAPIBackend,
+    index: usize,
+    format: Option<CameraFormat>,
+    result: Result<CaptureAPIBackend, &NokhwaError>,
+) {
+    #[cfg(feature = "tracing")]
+    match result {
+        Ok(backend) => tracing::info!(%backend, index, ?format, "Opened camera"),
+        Err(error) => {
+            tracing::error!(backend = %requested, index, ?format, %error, "Failed to open camera");
+        }
+    }
+}
+
+/// Records a backend that failed to open the device while falling back through the [`backend_priority()`](crate::backend_priority).
+#[inline]
+pub(crate) fn record_fallback(backend: CaptureAPIBackend, index: usize, error: &NokhwaError) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%backend, index, %error, "Backend could not open camera, trying the next one");
+}
+
+/// Records the device being dropped.
+#[inline]
+pub(crate) fn record_close(backend: CaptureAPIBackend, index: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(%backend, index, "Closing camera");
+}
+
+/// Records a stream being opened or stopped (`operation`), or failing to.
+#[inline]
+pub(crate) fn record_stream(
+    backend: CaptureAPIBackend,
+    index: usize,
+    operation: &'static str,
+    result: Result<(), &NokhwaError>,
+) {
+    #[cfg(feature = "tracing")]
+    match result {
+        Ok(()) => tracing::debug!(%backend, index, operation, "Stream changed"),
+        Err(error) => tracing::error!(%backend, index, operation, %error, "Stream failed"),
+    }
+}
+
+/// Records a [`CameraFormat`] being negotiated with the device, or the device rejecting it.
+#[inline]
+pub(crate) fn record_format(
+    backend: CaptureAPIBackend,
+    index: usize,
+    format: CameraFormat,
+    result: Result<(), &NokhwaError>,
+) {
+    #[cfg(feature = "tracing")]
+    match result {
+        Ok(()) => tracing::debug!(%backend, index, %format, "Negotiated format"),
+        Err(error) => tracing::warn!(%backend, index, %format, %error, "Camera rejected format"),
+    }
+}
+
+/// Records a `CameraBuilder` picking `picked` out of `candidates` compatible formats with `strategy`.
+#[inline]
+pub(crate) fn record_format_pick(
+    strategy: FormatStrategy,
+    candidates: usize,
+    picked: CameraFormat,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(?strategy, candidates, %picked, "Picked format");
+}
+
+/// Records any other backend call (`operation`, e.g. setting a control) failing.
+#[inline]
+pub(crate) fn record_backend_error(
+    backend: CaptureAPIBackend,
+    index: usize,
+    operation: &'static str,
+    error: &NokhwaError,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(%backend, index, operation, %error, "Backend call failed");
+}
@@ -0,0 +1,317 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{
+    wgpu_api::{
+        Device as WgpuDevice, Extent3d, Queue as WgpuQueue, Texture as WgpuTexture, TextureFormat,
+    },
+    NokhwaError, Resolution,
+};
+use std::borrow::Cow;
+
+// everything that differs between the supported `wgpu` versions lives here, the rest of the texture outputs go through it
+#[cfg(not(feature = "output-wgpu-0-19"))]
+mod version {
+    use crate::wgpu_api::{
+        Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Texture as WgpuTexture,
+        TextureDescriptor, TextureDimension, TextureFormat,
+    };
+    use std::{convert::TryFrom, num::NonZeroU32};
+
+    pub use crate::wgpu_api::TextureUsage as TextureUsages;
+
+    pub const SAMPLED: TextureUsages = TextureUsages::SAMPLED;
+
+    pub fn texture_descriptor<'a>(
+        label: Option<&'a str>,
+        size: Extent3d,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> TextureDescriptor<Option<&'a str>> {
+        TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+        }
+    }
+
+    pub fn copy_texture(texture: &WgpuTexture) -> ImageCopyTexture {
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+        }
+    }
+
+    pub fn data_layout(bytes_per_row: u32, rows_per_image: u32) -> ImageDataLayout {
+        ImageDataLayout {
+            offset: 0,
+            bytes_per_row: NonZeroU32::try_from(bytes_per_row).ok(),
+            rows_per_image: NonZeroU32::try_from(rows_per_image).ok(),
+        }
+    }
+}
+
+#[cfg(feature = "output-wgpu-0-19")]
+mod version {
+    use crate::wgpu_api::{
+        Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Texture as WgpuTexture,
+        TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    };
+
+    pub use crate::wgpu_api::TextureUsages;
+
+    pub const SAMPLED: TextureUsages = TextureUsages::TEXTURE_BINDING;
+
+    pub fn texture_descriptor<'a>(
+        label: Option<&'a str>,
+        size: Extent3d,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> TextureDescriptor<'a> {
+        TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        }
+    }
+
+    pub fn copy_texture(texture: &WgpuTexture) -> ImageCopyTexture {
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        }
+    }
+
+    pub fn data_layout(bytes_per_row: u32, rows_per_image: u32) -> ImageDataLayout {
+        ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: Some(rows_per_image),
+        }
+    }
+}
+
+use version::TextureUsages;
+
+/// How `frame_texture_with()` (e.g. [`Camera::frame_texture_with()`](crate::Camera::frame_texture_with)) and the [`TextureStreamer`](crate::TextureStreamer) make their textures.
+///
+/// The default is `Rgba8UnormSrgb` with the `SAMPLED | COPY_DST` usages (`TEXTURE_BINDING | COPY_DST` on newer `wgpu`), which is what `frame_texture()` uses.
+/// Supported formats are:
+/// - `Rgba8UnormSrgb` and `Rgba8Unorm`
+/// - `Bgra8UnormSrgb` and `Bgra8Unorm`, which match most swapchains, so the texture can be copied straight onto one
+/// - `Rgba16Float`, with the channels mapped to `0.0..=1.0`
+///
+/// The frame is converted into the format on the CPU before it is uploaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextureOptions {
+    format: TextureFormat,
+    usage: TextureUsages,
+}
+
+impl TextureOptions {
+    /// Creates the default [`TextureOptions`].
+    #[must_use]
+    pub fn new() -> Self {
+        TextureOptions::default()
+    }
+
+    /// Sets the texture format. Formats that are not supported error when the texture is made.
+    #[must_use]
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the texture usages. `COPY_DST` is always added, as the frame is copied into the texture.
+    #[must_use]
+    pub fn usage(mut self, usage: TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// The texture format.
+    #[must_use]
+    pub fn texture_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// The texture usages, including `COPY_DST`.
+    #[must_use]
+    pub fn texture_usage(&self) -> TextureUsages {
+        self.usage | TextureUsages::COPY_DST
+    }
+
+    /// Whether [`texture_format()`](TextureOptions::texture_format) is supported.
+    #[must_use]
+    pub fn is_supported(&self) -> bool {
+        bytes_per_pixel(self.format).is_some()
+    }
+
+    pub(crate) fn check(&self) -> Result<(), NokhwaError> {
+        if self.is_supported() {
+            Ok(())
+        } else {
+            Err(NokhwaError::GeneralError(format!(
+                "Unsupported texture format {:?}",
+                self.format
+            )))
+        }
+    }
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        TextureOptions {
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: version::SAMPLED | TextureUsages::COPY_DST,
+        }
+    }
+}
+
+fn bytes_per_pixel(format: TextureFormat) -> Option<u32> {
+    match format {
+        TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Rgba8Unorm
+        | TextureFormat::Bgra8UnormSrgb
+        | TextureFormat::Bgra8Unorm => Some(4),
+        TextureFormat::Rgba16Float => Some(8),
+        _ => None,
+    }
+}
+
+// converts an RGBA frame into the texel layout of `format`
+fn texture_bytes(rgba: &[u8], format: TextureFormat) -> Cow<[u8]> {
+    match format {
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => {
+            let mut bgra = rgba.to_vec();
+            for pixel in bgra.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            Cow::from(bgra)
+        }
+        TextureFormat::Rgba16Float => {
+            let mut halves = [0_u16; 256];
+            for (half, value) in halves.iter_mut().zip(0_u8..=255) {
+                *half = f16_bits(f32::from(value) / 255_f32);
+            }
+            let mut texels = Vec::with_capacity(rgba.len() * 2);
+            for channel in rgba {
+                texels.extend_from_slice(&halves[usize::from(*channel)].to_le_bytes());
+            }
+            Cow::from(texels)
+        }
+        _ => Cow::from(rgba),
+    }
+}
+
+// the bits of the closest `f16` to a non-negative `value`
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent <= 0 {
+        // subnormal, or too small for an `f16`
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        return sign | ((mantissa + (1 << (shift - 1))) >> shift) as u16;
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    // rounding may carry into the exponent, which is still correct
+    let rounded = (((exponent as u32) << 10) | (mantissa >> 13)) + ((mantissa >> 12) & 1);
+    sign | rounded as u16
+}
+
+pub(crate) fn texture_extent(resolution: Resolution) -> Extent3d {
+    Extent3d {
+        width: resolution.width(),
+        height: resolution.height(),
+        depth_or_array_layers: 1,
+    }
+}
+
+pub(crate) fn create_texture(
+    device: &WgpuDevice,
+    resolution: Resolution,
+    label: Option<&str>,
+    options: TextureOptions,
+) -> WgpuTexture {
+    device.create_texture(&version::texture_descriptor(
+        label,
+        texture_extent(resolution),
+        options.texture_format(),
+        options.texture_usage(),
+    ))
+}
+
+// converts the RGBA `frame` into the format of `options` and writes it into `texture`
+pub(crate) fn write_texture(
+    queue: &WgpuQueue,
+    texture: &WgpuTexture,
+    resolution: Resolution,
+    frame: &[u8],
+    options: TextureOptions,
+) -> Result<(), NokhwaError> {
+    let bytes_per_pixel = match bytes_per_pixel(options.texture_format()) {
+        Some(bytes) => bytes,
+        None => return options.check(),
+    };
+
+    if resolution.width() == 0 || resolution.height() == 0 {
+        return Err(NokhwaError::ReadFrameError(format!(
+            "Invalid texture resolution {}",
+            resolution
+        )));
+    }
+
+    queue.write_texture(
+        version::copy_texture(texture),
+        &texture_bytes(frame, options.texture_format()),
+        version::data_layout(bytes_per_pixel * resolution.width(), resolution.height()),
+        texture_extent(resolution),
+    );
+    Ok(())
+}
+
+// makes a texture for the RGBA `frame` and writes it in, which is what every `frame_texture_with()` does
+pub(crate) fn frame_to_texture(
+    device: &WgpuDevice,
+    queue: &WgpuQueue,
+    label: Option<&str>,
+    resolution: Resolution,
+    frame: &[u8],
+    options: TextureOptions,
+) -> Result<WgpuTexture, NokhwaError> {
+    options.check()?;
+    if resolution.width() == 0 || resolution.height() == 0 {
+        return Err(NokhwaError::ReadFrameError(format!(
+            "Invalid texture resolution {}",
+            resolution
+        )));
+    }
+    let texture = create_texture(device, resolution, label, options);
+    write_texture(queue, &texture, resolution, frame, options)?;
+    Ok(texture)
+}
@@ -0,0 +1,209 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::wgpu_api::{Device as WgpuDevice, Queue as WgpuQueue, Texture as WgpuTexture};
+use crate::{
+    texture::{create_texture, write_texture},
+    transform, Camera, NokhwaError, Resolution, TextureOptions,
+};
+use image::{buffer::ConvertBuffer, ImageBuffer, Rgb, RgbaImage};
+
+/// Streams camera frames into a ring of `N` Wgpu textures (two by default), so that a new frame is never written into the texture the GPU is currently sampling.
+///
+/// Every call to [`write_frame()`](TextureStreamer::write_frame) or [`update()`](TextureStreamer::update) writes into the *next* texture in the ring, and returns the index of the texture that is now safe to sample.
+/// The textures are `Rgba8UnormSrgb`, with the `SAMPLED | COPY_DST` usages, unless made [`with_options()`](TextureStreamer::with_options).
+///
+/// Since the textures are created up front, you can create one bind group per texture with [`textures()`](TextureStreamer::textures) and pick between them with the returned index.
+/// # Quirks
+/// - If a frame arrives with a different resolution than the textures, all textures are re-created and [`generation()`](TextureStreamer::generation) is incremented. Bind groups made from the old textures must be re-created.
+pub struct TextureStreamer {
+    textures: Vec<WgpuTexture>,
+    resolution: Resolution,
+    label: Option<String>,
+    options: TextureOptions,
+    current: Option<usize>,
+    generation: u64,
+}
+
+impl TextureStreamer {
+    /// Creates a new [`TextureStreamer`] with `count` textures of `resolution`.
+    /// # Errors
+    /// If `count` is less than 2 or the resolution is 0 on any axis, this will error.
+    pub fn new(
+        device: &WgpuDevice,
+        resolution: Resolution,
+        count: usize,
+        label: Option<&str>,
+    ) -> Result<Self, NokhwaError> {
+        TextureStreamer::with_options(device, resolution, count, label, TextureOptions::default())
+    }
+
+    /// Creates a new [`TextureStreamer`] with `count` textures of `resolution`, made with `options` (e.g. in a swapchain-compatible `Bgra8Unorm` format). See [`TextureOptions`].
+    /// # Errors
+    /// If `count` is less than 2, the resolution is 0 on any axis, or the format of `options` is not supported, this will error.
+    pub fn with_options(
+        device: &WgpuDevice,
+        resolution: Resolution,
+        count: usize,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Result<Self, NokhwaError> {
+        options.check()?;
+        if count < 2 {
+            return Err(NokhwaError::GeneralError(format!(
+                "A TextureStreamer needs at least 2 textures, got {}",
+                count
+            )));
+        }
+        if resolution.width() == 0 || resolution.height() == 0 {
+            return Err(NokhwaError::GeneralError(format!(
+                "Invalid texture resolution {}",
+                resolution
+            )));
+        }
+
+        let label = label.map(ToString::to_string);
+        let textures = create_textures(device, resolution, count, label.as_deref(), options);
+        Ok(TextureStreamer {
+            textures,
+            resolution,
+            label,
+            options,
+            current: None,
+            generation: 0,
+        })
+    }
+
+    /// Creates a new double-buffered [`TextureStreamer`] with textures of `resolution`.
+    /// # Errors
+    /// If the resolution is 0 on any axis, this will error.
+    pub fn double_buffered(
+        device: &WgpuDevice,
+        resolution: Resolution,
+        label: Option<&str>,
+    ) -> Result<Self, NokhwaError> {
+        TextureStreamer::new(device, resolution, 2, label)
+    }
+
+    /// Captures a frame from `camera`, writes it into the next texture and returns the index of the texture that is safe to sample.
+    /// The camera's [`pending_rotation()`](Camera::pending_rotation) is applied to the frame, as textures have nowhere to carry it.
+    /// # Errors
+    /// If the frame cannot be captured or the resolution is 0 on any axis, this will error.
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        device: &WgpuDevice,
+        queue: &WgpuQueue,
+    ) -> Result<usize, NokhwaError> {
+        let frame = transform::rotate_frame(camera.frame()?, camera.pending_rotation());
+        self.write_frame(device, queue, &frame)
+    }
+
+    /// Writes `frame` into the next texture and returns the index of the texture that is safe to sample. The frame will automatically be converted into a RGBA frame.
+    ///
+    /// Use this with frames from cameras that are not a [`Camera`], such as the JS or IP cameras.
+    /// # Errors
+    /// If the resolution is 0 on any axis, this will error.
+    pub fn write_frame(
+        &mut self,
+        device: &WgpuDevice,
+        queue: &WgpuQueue,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<usize, NokhwaError> {
+        let rgba_frame: RgbaImage = frame.convert();
+        let frame_resolution = Resolution::new(rgba_frame.width(), rgba_frame.height());
+
+        if frame_resolution.width() == 0 || frame_resolution.height() == 0 {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "Invalid texture resolution {}",
+                frame_resolution
+            )));
+        }
+
+        if frame_resolution != self.resolution {
+            self.textures = create_textures(
+                device,
+                frame_resolution,
+                self.textures.len(),
+                self.label.as_deref(),
+                self.options,
+            );
+            self.resolution = frame_resolution;
+            self.current = None;
+            self.generation += 1;
+        }
+
+        let next = match self.current {
+            Some(current) => (current + 1) % self.textures.len(),
+            None => 0,
+        };
+
+        write_texture(
+            queue,
+            &self.textures[next],
+            frame_resolution,
+            rgba_frame.as_raw(),
+            self.options,
+        )?;
+
+        self.current = Some(next);
+        Ok(next)
+    }
+
+    /// The texture holding the latest frame, which is safe to sample. Returns `None` if no frame has been written yet.
+    #[must_use]
+    pub fn current(&self) -> Option<&WgpuTexture> {
+        self.current.map(|idx| &self.textures[idx])
+    }
+
+    /// The index of the texture holding the latest frame. Returns `None` if no frame has been written yet.
+    #[must_use]
+    pub fn current_index(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// All textures in the ring, in index order.
+    #[must_use]
+    pub fn textures(&self) -> &[WgpuTexture] {
+        &self.textures
+    }
+
+    /// The amount of textures in the ring.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// The current resolution of the textures.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The [`TextureOptions`] the textures are made with.
+    #[must_use]
+    pub fn options(&self) -> TextureOptions {
+        self.options
+    }
+
+    /// How many times the textures have been re-created due to a resolution change.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+fn create_textures(
+    device: &WgpuDevice,
+    resolution: Resolution,
+    count: usize,
+    label: Option<&str>,
+    options: TextureOptions,
+) -> Vec<WgpuTexture> {
+    (0..count)
+        .map(|_| create_texture(device, resolution, label, options))
+        .collect()
+}
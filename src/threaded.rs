@@ -0,0 +1,1006 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraBuilder, DropPolicy, NokhwaError, Rotation};
+use flume::{Receiver, RecvTimeoutError, Sender};
+use image::{ImageBuffer, Rgb};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+// how long the capture thread sleeps after a failed frame, and the longest it waits for commands at once
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A frame captured by a [`ThreadedCamera`], with when it was captured.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimestampedFrame {
+    image: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    captured_at: SystemTime,
+    sequence: u64,
+    rotation: Rotation,
+}
+
+impl TimestampedFrame {
+    /// Create a new [`TimestampedFrame`], with no [`rotation()`](TimestampedFrame::rotation).
+    #[must_use]
+    pub fn new(
+        image: ImageBuffer<Rgb<u8>, Vec<u8>>,
+        captured_at: SystemTime,
+        sequence: u64,
+    ) -> Self {
+        TimestampedFrame {
+            image,
+            captured_at,
+            sequence,
+            rotation: Rotation::None,
+        }
+    }
+
+    /// Sets the rotation that is still to be applied to the frame.
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// The frame.
+    #[must_use]
+    pub fn image(&self) -> &ImageBuffer<Rgb<u8>, Vec<u8>> {
+        &self.image
+    }
+
+    /// Takes the frame out.
+    #[must_use]
+    pub fn into_image(self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        self.image
+    }
+
+    /// When the frame was captured (right after the backend returned it).
+    #[must_use]
+    pub fn captured_at(&self) -> SystemTime {
+        self.captured_at
+    }
+
+    /// The number of the frame, counting every frame the [`ThreadedCamera`] delivered from 1. Gaps mean nothing, a frame with a higher number is newer.
+    #[must_use]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// The rotation the consumer still has to apply to the frame. See [`Camera::pending_rotation()`].
+    #[must_use]
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+}
+
+/// What a timelapse does with the camera between shots.
+/// - `KeepWarm` - The stream stays open, and frames are read (but not decoded) between shots. Shots are instant and exposure stays settled, but the camera is busy (and its light on) all the time.
+/// - `Reopen` - The stream is closed after every shot, and opened again for the next one. This frees the camera and saves power, but every shot takes as long as opening the stream and the warm up frames.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum TimelapseDevice {
+    KeepWarm,
+    Reopen,
+}
+
+impl Default for TimelapseDevice {
+    fn default() -> Self {
+        TimelapseDevice::KeepWarm
+    }
+}
+
+/// A schedule for [`ThreadedCamera::start_timelapse()`]: one frame every `interval`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Timelapse {
+    interval: Duration,
+    device: TimelapseDevice,
+    warmup_frames: u32,
+}
+
+impl Timelapse {
+    /// Creates a new [`Timelapse`] that takes a frame every `interval`, keeping the camera warm in between.
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Timelapse {
+            interval,
+            device: TimelapseDevice::default(),
+            warmup_frames: 5,
+        }
+    }
+
+    /// Sets what happens to the camera between shots.
+    #[must_use]
+    pub fn with_device(mut self, device: TimelapseDevice) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Sets how many frames are thrown away after the stream is reopened, to let auto exposure and white balance settle. Only used with [`TimelapseDevice::Reopen`]. Defaults to 5.
+    #[must_use]
+    pub fn with_warmup_frames(mut self, warmup_frames: u32) -> Self {
+        self.warmup_frames = warmup_frames;
+        self
+    }
+
+    /// The time between shots.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// What happens to the camera between shots.
+    #[must_use]
+    pub fn device(&self) -> TimelapseDevice {
+        self.device
+    }
+
+    /// How many frames are thrown away after reopening the stream.
+    #[must_use]
+    pub fn warmup_frames(&self) -> u32 {
+        self.warmup_frames
+    }
+}
+
+/// How urgently the OS should schedule the capture thread of a [`ThreadedCamera`].
+/// - `Normal` - Like any other thread.
+/// - `High` - Ahead of normal threads. On Linux, this lowers the nice value of the thread, which needs `CAP_SYS_NICE` (or a high enough `RLIMIT_NICE`).
+/// - `Realtime` - A realtime thread (`SCHED_FIFO` on Unix, time critical on Windows), which only other realtime threads can preempt. Where that is not permitted, `High` is tried instead.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum ThreadPriority {
+    Normal,
+    High,
+    Realtime,
+}
+
+impl Default for ThreadPriority {
+    fn default() -> Self {
+        ThreadPriority::Normal
+    }
+}
+
+impl Display for ThreadPriority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Options for the capture thread of a [`ThreadedCamera`], see [`ThreadedCamera::with_options()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaptureThreadOptions {
+    priority: ThreadPriority,
+    affinity: Option<Vec<usize>>,
+    queue_depth: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Default for CaptureThreadOptions {
+    fn default() -> Self {
+        CaptureThreadOptions {
+            priority: ThreadPriority::default(),
+            affinity: None,
+            queue_depth: 1,
+            drop_policy: DropPolicy::DropOldest,
+        }
+    }
+}
+
+impl CaptureThreadOptions {
+    /// Creates new [`CaptureThreadOptions`]: normal priority, on any CPU, and a queue that only keeps the latest frame.
+    #[must_use]
+    pub fn new() -> Self {
+        CaptureThreadOptions::default()
+    }
+
+    /// Sets the [`ThreadPriority`] the capture thread asks for. If it is not permitted, a lower one is used, see [`ThreadedCamera::priority()`].
+    #[must_use]
+    pub fn with_priority(mut self, priority: ThreadPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Pins the capture thread to the CPUs (numbered from 0) in `cores`. This is supported on Linux and Windows.
+    #[must_use]
+    pub fn with_affinity(mut self, cores: Vec<usize>) -> Self {
+        self.affinity = Some(cores);
+        self
+    }
+
+    /// Sets the frame queue [`ThreadedCamera::next_frame()`] takes from: up to `depth` frames (at least 1), and what to do when it is full.
+    /// - Only the latest frame (the default, e.g. for a preview): `with_queue(1, DropPolicy::DropOldest)`
+    /// - The newest `n` frames: `with_queue(n, DropPolicy::DropOldest)`
+    /// - A FIFO of `n` frames that drops new frames when full: `with_queue(n, DropPolicy::DropNewest)`
+    /// - Every frame, holding capture up while the queue is full (e.g. for recording): `with_queue(n, DropPolicy::Block)`
+    #[must_use]
+    pub fn with_queue(mut self, depth: usize, drop_policy: DropPolicy) -> Self {
+        self.queue_depth = depth.max(1);
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// How many frames the queue holds.
+    #[must_use]
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth
+    }
+
+    /// What happens when the queue is full.
+    #[must_use]
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// The [`ThreadPriority`] the capture thread asks for.
+    #[must_use]
+    pub fn priority(&self) -> ThreadPriority {
+        self.priority
+    }
+
+    /// The CPUs the capture thread is pinned to, if any.
+    #[must_use]
+    pub fn affinity(&self) -> Option<&[usize]> {
+        self.affinity.as_deref()
+    }
+}
+
+type FrameCallback = Box<dyn FnMut(&TimestampedFrame) + Send>;
+
+enum Command {
+    Run(Box<dyn FnOnce(&mut Camera) + Send>),
+    SetCallback(Option<FrameCallback>),
+    SetMode(Mode),
+    Burst {
+        count: usize,
+        interval: Duration,
+        reply: Sender<Result<Vec<TimestampedFrame>, NokhwaError>>,
+    },
+}
+
+#[derive(Copy, Clone)]
+enum Mode {
+    Continuous,
+    Timelapse {
+        timelapse: Timelapse,
+        next_shot: Instant,
+    },
+}
+
+#[derive(Default)]
+struct Latest {
+    frame: Option<TimestampedFrame>,
+    error: Option<NokhwaError>,
+    queue: VecDeque<TimestampedFrame>,
+    dropped: u64,
+}
+
+struct Shared {
+    latest: Mutex<Latest>,
+    new_frame: Condvar,
+    queue_space: Condvar,
+    running: AtomicBool,
+    queue_depth: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Latest> {
+        match self.latest.lock() {
+            Ok(latest) => latest,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    // the frame is cloned only if it goes into the queue as well
+    fn publish(&self, frame: TimestampedFrame) {
+        let mut latest = self.lock();
+        latest.frame = Some(frame);
+        latest.error = None;
+        // wake `wait_for_frame()` before a full blocking queue holds us up
+        self.new_frame.notify_all();
+
+        let queued = match self.drop_policy {
+            DropPolicy::DropOldest => {
+                while latest.queue.len() >= self.queue_depth {
+                    latest.queue.pop_front();
+                    latest.dropped += 1;
+                }
+                true
+            }
+            DropPolicy::DropNewest => {
+                if latest.queue.len() >= self.queue_depth {
+                    latest.dropped += 1;
+                    false
+                } else {
+                    true
+                }
+            }
+            DropPolicy::Block => {
+                while latest.queue.len() >= self.queue_depth {
+                    if !self.running.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    latest = match self.queue_space.wait_timeout(latest, POLL_INTERVAL) {
+                        Ok((latest, _)) => latest,
+                        Err(poisoned) => poisoned.into_inner().0,
+                    };
+                }
+                true
+            }
+        };
+        if queued {
+            if let Some(frame) = latest.frame.clone() {
+                latest.queue.push_back(frame);
+            }
+        }
+        self.new_frame.notify_all();
+    }
+}
+
+// everything the capture thread owns
+struct Worker {
+    camera: Camera,
+    shared: Arc<Shared>,
+    commands: Receiver<Command>,
+    callback: Option<FrameCallback>,
+    mode: Mode,
+    sequence: u64,
+}
+
+impl Worker {
+    fn ensure_open(&mut self) -> Result<(), NokhwaError> {
+        if !self.camera.is_stream_open() {
+            self.camera.open_stream()?;
+        }
+        Ok(())
+    }
+
+    // captures a frame and hands it to the callback, without publishing it
+    fn take_frame(&mut self) -> Result<TimestampedFrame, NokhwaError> {
+        let image = self.camera.frame()?;
+        self.sequence += 1;
+        let frame = TimestampedFrame::new(image, SystemTime::now(), self.sequence)
+            .with_rotation(self.camera.pending_rotation());
+        if let Some(callback) = &mut self.callback {
+            callback(&frame);
+        }
+        Ok(frame)
+    }
+
+    fn capture(&mut self) -> Result<(), NokhwaError> {
+        let frame = self.take_frame()?;
+        self.shared.publish(frame);
+        Ok(())
+    }
+
+    fn report(&self, error: NokhwaError) {
+        self.shared.lock().error = Some(error);
+        self.shared.new_frame.notify_all();
+    }
+
+    // reads frames without decoding them until `until`, so the next frame is a fresh one
+    fn drain_until(&mut self, until: Instant) {
+        while Instant::now() < until && self.shared.running.load(Ordering::SeqCst) {
+            if self.camera.frame_raw().is_err() {
+                thread::sleep(POLL_INTERVAL.min(until.saturating_duration_since(Instant::now())));
+            }
+            if !self.commands.is_empty() {
+                return;
+            }
+        }
+    }
+
+    // waits without touching the camera, but wakes up for commands
+    fn idle_until(&mut self, until: Instant) -> Option<Command> {
+        let wait = until
+            .saturating_duration_since(Instant::now())
+            .min(POLL_INTERVAL);
+        self.commands.recv_timeout(wait).ok()
+    }
+
+    fn burst(
+        &mut self,
+        count: usize,
+        interval: Duration,
+    ) -> Result<Vec<TimestampedFrame>, NokhwaError> {
+        let was_open = self.camera.is_stream_open();
+        let frames = self.capture_burst(count, interval);
+        // the stream is closed again even if the burst failed
+        if !was_open {
+            let stopped = self.camera.stop_stream();
+            let frames = frames?;
+            stopped?;
+            return Ok(frames);
+        }
+        frames
+    }
+
+    fn capture_burst(
+        &mut self,
+        count: usize,
+        interval: Duration,
+    ) -> Result<Vec<TimestampedFrame>, NokhwaError> {
+        self.ensure_open()?;
+        let mut frames = Vec::with_capacity(count);
+        let mut next = Instant::now();
+        for _ in 0..count {
+            self.drain_until(next);
+            let started = Instant::now();
+            // burst frames only go back to the caller, a full `DropPolicy::Block` queue must not stall the burst it is waiting on
+            frames.push(self.take_frame()?);
+            next = started + interval;
+        }
+        Ok(frames)
+    }
+
+    fn timelapse_shot(&mut self, timelapse: Timelapse) -> Result<(), NokhwaError> {
+        if timelapse.device != TimelapseDevice::Reopen {
+            self.capture()?;
+            return Ok(());
+        }
+        let shot = self.reopened_shot(timelapse.warmup_frames);
+        // the stream is closed again even if the shot failed
+        let stopped = self.camera.stop_stream();
+        shot?;
+        stopped
+    }
+
+    fn reopened_shot(&mut self, warmup_frames: u32) -> Result<(), NokhwaError> {
+        self.ensure_open()?;
+        for _ in 0..warmup_frames {
+            let _warmup = self.camera.frame_raw()?;
+        }
+        self.capture()?;
+        Ok(())
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Run(function) => function(&mut self.camera),
+            Command::SetCallback(callback) => self.callback = callback,
+            Command::SetMode(mode) => {
+                self.mode = mode;
+                let result = match mode {
+                    Mode::Timelapse { timelapse, .. }
+                        if timelapse.device == TimelapseDevice::Reopen =>
+                    {
+                        self.camera.stop_stream()
+                    }
+                    _ => self.ensure_open(),
+                };
+                if let Err(why) = result {
+                    self.report(why);
+                }
+            }
+            Command::Burst {
+                count,
+                interval,
+                reply,
+            } => {
+                let _sent = reply.send(self.burst(count, interval));
+            }
+        }
+    }
+
+    fn run(mut self) {
+        while self.shared.running.load(Ordering::SeqCst) {
+            while let Ok(command) = self.commands.try_recv() {
+                self.handle(command);
+            }
+            match self.mode {
+                Mode::Continuous => {
+                    if let Err(why) = self.capture() {
+                        self.report(why);
+                        thread::sleep(POLL_INTERVAL);
+                    }
+                }
+                Mode::Timelapse {
+                    timelapse,
+                    next_shot,
+                } => {
+                    if Instant::now() < next_shot {
+                        match timelapse.device {
+                            TimelapseDevice::KeepWarm => self.drain_until(next_shot),
+                            TimelapseDevice::Reopen => {
+                                if let Some(command) = self.idle_until(next_shot) {
+                                    self.handle(command);
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    if let Err(why) = self.timelapse_shot(timelapse) {
+                        self.report(why);
+                    }
+                    // a late shot does not make the next ones late
+                    let mut next = next_shot + timelapse.interval;
+                    while next <= Instant::now() {
+                        next += timelapse.interval;
+                    }
+                    self.mode = Mode::Timelapse {
+                        timelapse,
+                        next_shot: next,
+                    };
+                }
+            }
+        }
+        let _stop = self.camera.stop_stream();
+    }
+}
+
+/// A [`Camera`] that captures on its own thread, so frames are always ready without blocking the caller.
+///
+/// By default it captures continuously. Get frames with [`last_frame()`](ThreadedCamera::last_frame), wait for new ones with [`wait_for_frame()`](ThreadedCamera::wait_for_frame),
+/// take them in order from a queue with [`next_frame()`](ThreadedCamera::next_frame), or have the capture thread call a [callback](ThreadedCamera::set_callback) for each one.
+/// It also does bursts ([`capture_burst()`](ThreadedCamera::capture_burst)) and [timelapses](ThreadedCamera::start_timelapse).
+/// ```.ignore
+/// let camera = ThreadedCamera::from_builder(CameraBuilder::new().index(0))?;
+/// // a frame a minute, and the camera is off in between
+/// camera.start_timelapse(Timelapse::new(Duration::from_secs(60)).with_device(TimelapseDevice::Reopen))?;
+/// camera.set_callback(|frame| save(frame))?;
+/// ```
+/// # Quirks
+/// - The [`Camera`] is opened on the capture thread and never leaves it, use [`with_camera()`](ThreadedCamera::with_camera) to change its settings.
+/// - Capture errors do not stop the thread (cameras hiccup), they are kept in [`last_error()`](ThreadedCamera::last_error) until the next successful frame.
+/// - With a [`DropPolicy::Block`] queue, the capture thread waits while the queue is full, and so do commands like [`with_camera()`](ThreadedCamera::with_camera). Keep taking frames with [`next_frame()`](ThreadedCamera::next_frame).
+/// - Dropping it stops the capture thread and waits for it however long it takes. Call [`stop()`](ThreadedCamera::stop) to release the camera at a known point, with a timeout.
+pub struct ThreadedCamera {
+    shared: Arc<Shared>,
+    commands: Sender<Command>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    // disconnects once the capture thread has let go of the camera, even if it panicked
+    finished: Receiver<()>,
+    priority: ThreadPriority,
+}
+
+fn stopped_error() -> NokhwaError {
+    NokhwaError::GeneralError("The capture thread has stopped".to_string())
+}
+
+impl ThreadedCamera {
+    /// Starts a capture thread with the camera `open_camera` opens (on that thread), and opens its stream.
+    /// # Errors
+    /// If `open_camera` or opening the stream fails, this will error.
+    pub fn new<F>(open_camera: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        ThreadedCamera::with_options(open_camera, CaptureThreadOptions::default())
+    }
+
+    /// Starts a capture thread like [`new()`](ThreadedCamera::new), with the priority and CPU affinity in `options`. They are set before the camera is opened.
+    /// # Errors
+    /// If the CPU affinity cannot be set (e.g. a CPU does not exist, or the platform does not support it), `open_camera` fails, or opening the stream fails, this will error.
+    pub fn with_options<F>(
+        open_camera: F,
+        options: CaptureThreadOptions,
+    ) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(Latest::default()),
+            new_frame: Condvar::new(),
+            queue_space: Condvar::new(),
+            running: AtomicBool::new(true),
+            queue_depth: options.queue_depth,
+            drop_policy: options.drop_policy,
+        });
+        let (command_tx, command_rx) = flume::unbounded();
+        let (opened_tx, opened_rx) = flume::bounded(1);
+        let (finished_tx, finished_rx) = flume::bounded::<()>(1);
+
+        let worker_shared = shared.clone();
+        let thread = thread::spawn(move || {
+            // dropped last, after the camera
+            let _finished = finished_tx;
+            if let Some(cores) = &options.affinity {
+                if let Err(why) = set_current_thread_affinity(cores) {
+                    let _sent = opened_tx.send(Err(why));
+                    return;
+                }
+            }
+            let priority = set_current_thread_priority(options.priority);
+
+            let camera = open_camera().and_then(|mut camera| {
+                if !camera.is_stream_open() {
+                    camera.open_stream()?;
+                }
+                Ok(camera)
+            });
+            match camera {
+                Ok(camera) => {
+                    let _sent = opened_tx.send(Ok(priority));
+                    Worker {
+                        camera,
+                        shared: worker_shared,
+                        commands: command_rx,
+                        callback: None,
+                        mode: Mode::Continuous,
+                        sequence: 0,
+                    }
+                    .run();
+                }
+                Err(why) => {
+                    let _sent = opened_tx.send(Err(why));
+                }
+            }
+        });
+
+        match opened_rx.recv() {
+            Ok(Ok(priority)) => Ok(ThreadedCamera {
+                shared,
+                commands: command_tx,
+                thread: Mutex::new(Some(thread)),
+                finished: finished_rx,
+                priority,
+            }),
+            Ok(Err(why)) => Err(why),
+            Err(_) => Err(NokhwaError::GeneralError(
+                "The capture thread panicked while opening the camera".to_string(),
+            )),
+        }
+    }
+
+    /// Starts a capture thread with the camera `builder` opens. See [`new()`](ThreadedCamera::new).
+    /// # Errors
+    /// If the camera or its stream fails to open, this will error.
+    pub fn from_builder(builder: CameraBuilder) -> Result<Self, NokhwaError> {
+        ThreadedCamera::new(move || builder.open())
+    }
+
+    /// The [`ThreadPriority`] the capture thread actually got, which is lower than the one asked for if that was not permitted.
+    #[must_use]
+    pub fn priority(&self) -> ThreadPriority {
+        self.priority
+    }
+
+    fn send(&self, command: Command) -> Result<(), NokhwaError> {
+        match self.commands.send(command) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(stopped_error()),
+        }
+    }
+
+    /// Runs `function` with the [`Camera`] on the capture thread, between two frames, and returns what it returns.
+    /// # Errors
+    /// If the capture thread has stopped, this will error.
+    pub fn with_camera<R, F>(&self, function: F) -> Result<R, NokhwaError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Camera) -> R + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        self.send(Command::Run(Box::new(move |camera| {
+            let _sent = reply_tx.send(function(camera));
+        })))?;
+        match reply_rx.recv() {
+            Ok(result) => Ok(result),
+            Err(_) => Err(stopped_error()),
+        }
+    }
+
+    /// Sets a callback that the capture thread calls with every frame, including burst and timelapse frames. It should return quickly, or it will hold up capture.
+    /// # Errors
+    /// If the capture thread has stopped, this will error.
+    pub fn set_callback<F>(&self, callback: F) -> Result<(), NokhwaError>
+    where
+        F: FnMut(&TimestampedFrame) + Send + 'static,
+    {
+        self.send(Command::SetCallback(Some(Box::new(callback))))
+    }
+
+    /// Removes the callback.
+    /// # Errors
+    /// If the capture thread has stopped, this will error.
+    pub fn clear_callback(&self) -> Result<(), NokhwaError> {
+        self.send(Command::SetCallback(None))
+    }
+
+    /// The newest frame, if any has been captured yet.
+    #[must_use]
+    pub fn last_frame(&self) -> Option<TimestampedFrame> {
+        self.shared.lock().frame.clone()
+    }
+
+    /// The error of the last failed capture, if it has not succeeded since.
+    #[must_use]
+    pub fn last_error(&self) -> Option<NokhwaError> {
+        self.shared.lock().error.clone()
+    }
+
+    /// Takes the oldest frame out of the queue (see [`CaptureThreadOptions::with_queue()`]), waiting up to `timeout` for one if it is empty.
+    /// Unlike [`last_frame()`](ThreadedCamera::last_frame), every frame is only returned once.
+    /// # Errors
+    /// If no frame arrives in time this will return [`NokhwaError::Timeout`]. If the capture thread stops, this will error.
+    pub fn next_frame(&self, timeout: Duration) -> Result<TimestampedFrame, NokhwaError> {
+        let deadline = Instant::now() + timeout;
+        let mut latest = self.shared.lock();
+        loop {
+            if let Some(frame) = latest.queue.pop_front() {
+                self.shared.queue_space.notify_all();
+                return Ok(frame);
+            }
+            if !self.shared.running.load(Ordering::SeqCst) {
+                return Err(stopped_error());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Err(NokhwaError::Timeout(timeout));
+            }
+            latest = match self.shared.new_frame.wait_timeout(latest, remaining) {
+                Ok((latest, _)) => latest,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+
+    /// How many frames are waiting in the queue.
+    #[must_use]
+    pub fn queued_frames(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// How many frames the queue has dropped because it was full. Frames the camera failed to deliver are not counted.
+    #[must_use]
+    pub fn dropped_frames(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Waits up to `timeout` for a frame newer than `sequence` (see [`TimestampedFrame::sequence()`], use 0 for any frame).
+    /// # Errors
+    /// If no such frame arrives in time, or the capture thread stops, this will error.
+    pub fn wait_for_frame(
+        &self,
+        sequence: u64,
+        timeout: Duration,
+    ) -> Result<TimestampedFrame, NokhwaError> {
+        let deadline = Instant::now() + timeout;
+        let mut latest = self.shared.lock();
+        loop {
+            if let Some(frame) = &latest.frame {
+                if frame.sequence > sequence {
+                    return Ok(frame.clone());
+                }
+            }
+            if !self.shared.running.load(Ordering::SeqCst) {
+                return Err(stopped_error());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Err(NokhwaError::Timeout(timeout));
+            }
+            latest = match self.shared.new_frame.wait_timeout(latest, remaining) {
+                Ok((latest, _)) => latest,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+
+    /// Captures `count` frames, `interval` apart (or as fast as the camera delivers them, if that is slower), and returns them in order.
+    /// Frames between the burst frames are read but not decoded. This interrupts a running timelapse, which carries on afterwards.
+    ///
+    /// Burst frames are only returned here (and passed to the [callback](ThreadedCamera::set_callback)), they do not show up in [`last_frame()`](ThreadedCamera::last_frame) or the queue.
+    /// # Errors
+    /// If any frame fails to capture or the capture thread has stopped, this will error.
+    pub fn capture_burst(
+        &self,
+        count: usize,
+        interval: Duration,
+    ) -> Result<Vec<TimestampedFrame>, NokhwaError> {
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        self.send(Command::Burst {
+            count,
+            interval,
+            reply: reply_tx,
+        })?;
+        match reply_rx.recv() {
+            Ok(result) => result,
+            Err(_) => Err(stopped_error()),
+        }
+    }
+
+    /// Switches the capture thread to a [`Timelapse`]: a frame every [`interval()`](Timelapse::interval), starting now. Frames arrive like continuously captured ones.
+    /// # Errors
+    /// If the capture thread has stopped, this will error.
+    pub fn start_timelapse(&self, timelapse: Timelapse) -> Result<(), NokhwaError> {
+        self.send(Command::SetMode(Mode::Timelapse {
+            timelapse,
+            next_shot: Instant::now(),
+        }))
+    }
+
+    /// Switches the capture thread back to capturing continuously, reopening the stream if the timelapse closed it.
+    /// # Errors
+    /// If the capture thread has stopped, this will error.
+    pub fn stop_timelapse(&self) -> Result<(), NokhwaError> {
+        self.send(Command::SetMode(Mode::Continuous))
+    }
+
+    /// Whether the capture thread is still running. It stops after [`stop()`](ThreadedCamera::stop), or if it panics.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::SeqCst) && !self.finished.is_disconnected()
+    }
+
+    /// Stops the capture thread, and waits up to `timeout` for it to close the camera and finish. Once this returns `Ok`, the device is released and can be opened again.
+    ///
+    /// This takes `&self`, so a camera that is shared (e.g. in an `Arc` a callback also holds) can be stopped without waiting for every reference to be dropped.
+    /// Everything else errors once the thread has stopped. Stopping a stopped camera does nothing.
+    /// # Errors
+    /// If the capture thread does not finish within `timeout` (e.g. the camera or a callback is stuck), this will return [`NokhwaError::Timeout`]. The thread still stops
+    /// as soon as it can, call this again to wait longer. If the capture thread panicked, this will error.
+    pub fn stop(&self, timeout: Duration) -> Result<(), NokhwaError> {
+        self.signal_stop();
+        match self.finished.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => return Err(NokhwaError::Timeout(timeout)),
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {}
+        }
+        self.join()
+    }
+
+    fn signal_stop(&self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        self.shared.new_frame.notify_all();
+        self.shared.queue_space.notify_all();
+    }
+
+    fn join(&self) -> Result<(), NokhwaError> {
+        let thread = match self.thread.lock() {
+            Ok(mut thread) => thread.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        match thread.map(JoinHandle::join) {
+            Some(Err(_)) => Err(NokhwaError::GeneralError(
+                "The capture thread panicked".to_string(),
+            )),
+            Some(Ok(())) | None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ThreadedCamera {
+    fn drop(&mut self) {
+        self.signal_stop();
+        let _joined = self.join();
+    }
+}
+
+// a nice value of -10 is what audio servers commonly use
+#[cfg(target_os = "linux")]
+const HIGH_PRIORITY_NICE: libc::c_int = -10;
+
+#[cfg(windows)]
+const THREAD_PRIORITY_HIGHEST: i32 = 2;
+#[cfg(windows)]
+const THREAD_PRIORITY_TIME_CRITICAL: i32 = 15;
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetCurrentThread() -> *mut std::ffi::c_void;
+    fn SetThreadPriority(thread: *mut std::ffi::c_void, priority: i32) -> i32;
+    fn SetThreadAffinityMask(thread: *mut std::ffi::c_void, mask: usize) -> usize;
+}
+
+fn affinity_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("Could not set capture thread affinity: {}", error))
+}
+
+// tries `priority` on the calling thread, then the lower ones, and returns the one that worked
+fn set_current_thread_priority(priority: ThreadPriority) -> ThreadPriority {
+    let mut priority = priority;
+    while priority != ThreadPriority::Normal && !try_thread_priority(priority) {
+        priority = match priority {
+            ThreadPriority::Realtime => ThreadPriority::High,
+            _ => ThreadPriority::Normal,
+        };
+    }
+    priority
+}
+
+#[cfg(unix)]
+fn try_thread_priority(priority: ThreadPriority) -> bool {
+    match priority {
+        ThreadPriority::Normal => true,
+        ThreadPriority::High => try_high_thread_priority(),
+        ThreadPriority::Realtime => unsafe {
+            let min = libc::sched_get_priority_min(libc::SCHED_FIFO);
+            let max = libc::sched_get_priority_max(libc::SCHED_FIFO);
+            let mut param: libc::sched_param = std::mem::zeroed();
+            param.sched_priority = min + (max - min) / 2;
+            libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) == 0
+        },
+    }
+}
+
+// Linux threads have their own nice value, which is the usual way to prioritize without realtime
+#[cfg(target_os = "linux")]
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn try_high_thread_priority() -> bool {
+    unsafe {
+        let thread_id = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, thread_id, HIGH_PRIORITY_NICE) == 0
+    }
+}
+
+// elsewhere, the top of the normal scheduling policy
+#[cfg(all(unix, not(target_os = "linux")))]
+fn try_high_thread_priority() -> bool {
+    unsafe {
+        let mut policy = 0;
+        let mut param: libc::sched_param = std::mem::zeroed();
+        if libc::pthread_getschedparam(libc::pthread_self(), &mut policy, &mut param) != 0 {
+            return false;
+        }
+        param.sched_priority = libc::sched_get_priority_max(policy);
+        libc::pthread_setschedparam(libc::pthread_self(), policy, &param) == 0
+    }
+}
+
+#[cfg(windows)]
+fn try_thread_priority(priority: ThreadPriority) -> bool {
+    let priority = match priority {
+        ThreadPriority::Normal => return true,
+        ThreadPriority::High => THREAD_PRIORITY_HIGHEST,
+        ThreadPriority::Realtime => THREAD_PRIORITY_TIME_CRITICAL,
+    };
+    unsafe { SetThreadPriority(GetCurrentThread(), priority) != 0 }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn try_thread_priority(priority: ThreadPriority) -> bool {
+    priority == ThreadPriority::Normal
+}
+
+#[cfg(target_os = "linux")]
+fn set_current_thread_affinity(cores: &[usize]) -> Result<(), NokhwaError> {
+    if cores.is_empty() {
+        return Err(affinity_error("No CPUs given"));
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for core in cores {
+            if *core >= libc::CPU_SETSIZE as usize {
+                return Err(affinity_error(&format!("CPU {} does not exist", core)));
+            }
+            libc::CPU_SET(*core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(affinity_error(&std::io::Error::last_os_error().to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+#[allow(clippy::cast_possible_truncation)]
+fn set_current_thread_affinity(cores: &[usize]) -> Result<(), NokhwaError> {
+    if cores.is_empty() {
+        return Err(affinity_error("No CPUs given"));
+    }
+    let mut mask = 0_usize;
+    for core in cores {
+        match 1_usize.checked_shl(*core as u32) {
+            Some(bit) => mask |= bit,
+            None => return Err(affinity_error(&format!("CPU {} does not exist", core))),
+        }
+    }
+    if unsafe { SetThreadAffinityMask(GetCurrentThread(), mask) } == 0 {
+        return Err(affinity_error(&std::io::Error::last_os_error().to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn set_current_thread_affinity(_cores: &[usize]) -> Result<(), NokhwaError> {
+    Err(affinity_error("Not supported on this platform"))
+}
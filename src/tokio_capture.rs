@@ -0,0 +1,251 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{BusFrame, Camera, NokhwaError};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        broadcast,
+        mpsc::{self, error::TrySendError},
+        oneshot,
+    },
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+
+// how long the capture loop waits after a failed frame before trying again
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+// where the capture loop puts its frames
+enum Output {
+    Broadcast(broadcast::Sender<BusFrame>),
+    Mpsc(mpsc::Sender<BusFrame>),
+}
+
+impl Output {
+    // whether the capture loop should keep going
+    fn send(&self, frame: BusFrame, dropped: &AtomicU64) -> bool {
+        match self {
+            // nobody listening right now is fine, receivers can subscribe later
+            Output::Broadcast(sender) => {
+                let _receivers = sender.send(frame);
+                true
+            }
+            Output::Mpsc(sender) => match sender.try_send(frame) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    dropped.fetch_add(1, Ordering::SeqCst);
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            },
+        }
+    }
+}
+
+struct CaptureShared {
+    error: Mutex<Option<NokhwaError>>,
+    dropped: AtomicU64,
+}
+
+impl CaptureShared {
+    fn error(&self) -> MutexGuard<'_, Option<NokhwaError>> {
+        match self.error.lock() {
+            Ok(error) => error,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// A capture loop running on `tokio`'s blocking thread pool, feeding a channel. See [`capture_to_broadcast()`] and [`capture_to_mpsc()`].
+///
+/// The loop stops when the [`CancellationToken`] it was started with is cancelled, when [`cancel()`](TokioCapture::cancel) or [`stop()`](TokioCapture::stop) is called, or when this is dropped.
+/// The camera is closed when the loop stops.
+pub struct TokioCapture {
+    task: Option<JoinHandle<()>>,
+    cancel: CancellationToken,
+    shared: Arc<CaptureShared>,
+}
+
+impl TokioCapture {
+    /// Tells the capture loop to stop, without waiting for it. Only this capture is cancelled, not the [`CancellationToken`] it was started with.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Whether the capture loop has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.as_ref().map_or(true, JoinHandle::is_finished)
+    }
+
+    /// The error of the last failed capture, if it has not succeeded since.
+    #[must_use]
+    pub fn last_error(&self) -> Option<NokhwaError> {
+        self.shared.error().clone()
+    }
+
+    /// How many frames were dropped because the `mpsc` channel was full. Frames a lagging `broadcast` receiver misses are reported to that receiver instead.
+    #[must_use]
+    pub fn dropped_frames(&self) -> u64 {
+        self.shared.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Waits for the capture loop to stop on its own (see [`TokioCapture`]) and the camera to be closed.
+    /// # Errors
+    /// If the capture loop panicked, this will error.
+    pub async fn join(mut self) -> Result<(), NokhwaError> {
+        match self.task.take() {
+            Some(task) => match task.await {
+                Ok(()) => Ok(()),
+                Err(why) => Err(NokhwaError::GeneralError(format!(
+                    "The capture task failed: {}",
+                    why
+                ))),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Stops the capture loop, and waits for the camera to be closed.
+    /// # Errors
+    /// If the capture loop panicked, this will error.
+    pub async fn stop(self) -> Result<(), NokhwaError> {
+        self.cancel();
+        self.join().await
+    }
+}
+
+impl Drop for TokioCapture {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Opens the camera `open_camera` opens in a capture loop on `tokio`'s blocking thread pool (with [`spawn_blocking()`](tokio::task::spawn_blocking)),
+/// and sends every frame to `sender`, until `cancel` is cancelled or the returned [`TokioCapture`] is stopped or dropped.
+/// Frames are [`BusFrame`]s, so cloning them for every receiver does not copy the image.
+/// ```.ignore
+/// let (sender, _) = broadcast::channel(4);
+/// let capture = capture_to_broadcast(move || CameraBuilder::new().index(0).open(), sender.clone(), shutdown.clone()).await?;
+/// // in every client handler
+/// let mut frames = sender.subscribe();
+/// while let Ok(frame) = frames.recv().await { /* ... */ }
+/// ```
+/// # Quirks
+/// - The capture loop keeps going while there are no receivers, as they can subscribe at any time. Cancel it to close the camera.
+/// - Receivers that fall behind get [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged), the capture loop never waits for them.
+/// - This holds one of `tokio`'s blocking threads for as long as the capture loop runs.
+/// # Errors
+/// If `open_camera` or opening the stream fails, this will error.
+pub async fn capture_to_broadcast<F>(
+    open_camera: F,
+    sender: broadcast::Sender<BusFrame>,
+    cancel: CancellationToken,
+) -> Result<TokioCapture, NokhwaError>
+where
+    F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+{
+    spawn_capture(open_camera, Output::Broadcast(sender), &cancel).await
+}
+
+/// Opens the camera `open_camera` opens in a capture loop on `tokio`'s blocking thread pool (with [`spawn_blocking()`](tokio::task::spawn_blocking)),
+/// and sends every frame to `sender`, until `cancel` is cancelled, the receiver is dropped, or the returned [`TokioCapture`] is stopped or dropped.
+/// ```.ignore
+/// let (sender, mut frames) = mpsc::channel(2);
+/// let capture = capture_to_mpsc(move || CameraBuilder::new().index(0).open(), sender, shutdown.child_token()).await?;
+/// while let Some(frame) = frames.recv().await { /* ... */ }
+/// ```
+/// # Quirks
+/// - When the channel is full the new frame is dropped (see [`TokioCapture::dropped_frames()`]), the capture loop never waits for the receiver.
+/// - This holds one of `tokio`'s blocking threads for as long as the capture loop runs.
+/// # Errors
+/// If `open_camera` or opening the stream fails, this will error.
+pub async fn capture_to_mpsc<F>(
+    open_camera: F,
+    sender: mpsc::Sender<BusFrame>,
+    cancel: CancellationToken,
+) -> Result<TokioCapture, NokhwaError>
+where
+    F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+{
+    spawn_capture(open_camera, Output::Mpsc(sender), &cancel).await
+}
+
+async fn spawn_capture<F>(
+    open_camera: F,
+    output: Output,
+    cancel: &CancellationToken,
+) -> Result<TokioCapture, NokhwaError>
+where
+    F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+{
+    // cancelling the capture must not cancel the caller's token
+    let cancel = cancel.child_token();
+    let shared = Arc::new(CaptureShared {
+        error: Mutex::new(None),
+        dropped: AtomicU64::new(0),
+    });
+    let (opened_tx, opened_rx) = oneshot::channel();
+
+    let task_cancel = cancel.clone();
+    let task_shared = shared.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let mut camera = match open_camera().and_then(|mut camera| {
+            if !camera.is_stream_open() {
+                camera.open_stream()?;
+            }
+            Ok(camera)
+        }) {
+            Ok(camera) => {
+                let _sent = opened_tx.send(Ok(()));
+                camera
+            }
+            Err(why) => {
+                let _sent = opened_tx.send(Err(why));
+                return;
+            }
+        };
+
+        let mut sequence = 0;
+        while !task_cancel.is_cancelled() {
+            match camera.frame() {
+                Ok(frame) => {
+                    *task_shared.error() = None;
+                    sequence += 1;
+                    let frame = BusFrame::new(Arc::new(frame), sequence, camera.pending_rotation());
+                    if !output.send(frame, &task_shared.dropped) {
+                        break;
+                    }
+                }
+                Err(why) => {
+                    *task_shared.error() = Some(why);
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        }
+        let _stopped = camera.stop_stream();
+    });
+
+    match opened_rx.await {
+        Ok(Ok(())) => Ok(TokioCapture {
+            task: Some(task),
+            cancel,
+            shared,
+        }),
+        Ok(Err(why)) => Err(why),
+        Err(_) => Err(NokhwaError::GeneralError(
+            "The capture task panicked while opening the camera".to_string(),
+        )),
+    }
+}
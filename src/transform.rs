@@ -0,0 +1,487 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{NokhwaError, Resolution};
+use image::{
+    imageops::{self, FilterType},
+    ImageBuffer, Rgb,
+};
+use std::fmt::{Display, Formatter};
+
+/// A clockwise rotation applied to frames.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum Rotation {
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    /// Creates a [`Rotation`] from clockwise degrees. Returns `None` if `degrees` is not a multiple of 90.
+    #[must_use]
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees % 360 {
+            0 => Some(Rotation::None),
+            90 => Some(Rotation::Rotate90),
+            180 => Some(Rotation::Rotate180),
+            270 => Some(Rotation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// The clockwise degrees of this [`Rotation`].
+    #[must_use]
+    pub fn degrees(self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Rotate90 => 90,
+            Rotation::Rotate180 => 180,
+            Rotation::Rotate270 => 270,
+        }
+    }
+
+    /// The [`Rotation`] that results from applying `self`, then `other`.
+    #[must_use]
+    pub fn then(self, other: Rotation) -> Rotation {
+        // both are multiples of 90 by construction
+        Rotation::from_degrees(self.degrees() + other.degrees()).unwrap_or(Rotation::None)
+    }
+
+    /// Whether this rotation swaps the width and height of a frame.
+    #[must_use]
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+
+    /// The [`Resolution`] a frame of `resolution` has after this rotation.
+    #[must_use]
+    pub fn rotated_resolution(self, resolution: Resolution) -> Resolution {
+        if self.swaps_dimensions() {
+            Resolution::new(resolution.height(), resolution.width())
+        } else {
+            resolution
+        }
+    }
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Rotation::None
+    }
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} degrees", self.degrees())
+    }
+}
+
+/// Mirrors frames along an axis.
+/// - `Horizontal` - Flips left and right, like a mirror. Most front-facing cameras want this.
+/// - `Vertical` - Flips top and bottom.
+/// - `Both` - Flips both, which is the same as a 180 degree rotation.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum Mirror {
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Default for Mirror {
+    fn default() -> Self {
+        Mirror::None
+    }
+}
+
+impl Display for Mirror {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A rectangle in frame pixel coordinates, with the origin in the top left. Used for cropping.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    /// Creates a new [`Rect`] at `x`, `y` that is `width` by `height` pixels.
+    #[must_use]
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get the left edge of the [`Rect`].
+    #[must_use]
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    /// Get the top edge of the [`Rect`].
+    #[must_use]
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    /// Get the width of the [`Rect`].
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the [`Rect`].
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the size of the [`Rect`] as a [`Resolution`].
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        Resolution::new(self.width, self.height)
+    }
+
+    /// Whether the [`Rect`] has no area.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// The part of this [`Rect`] that lies within a frame of `resolution`. Returns `None` if they do not overlap.
+    #[must_use]
+    pub fn clamp_to(&self, resolution: Resolution) -> Option<Rect> {
+        if self.x >= resolution.width() || self.y >= resolution.height() {
+            return None;
+        }
+        let clamped = Rect::new(
+            self.x,
+            self.y,
+            self.width.min(resolution.width() - self.x),
+            self.height.min(resolution.height() - self.y),
+        );
+        if clamped.is_empty() {
+            None
+        } else {
+            Some(clamped)
+        }
+    }
+}
+
+impl Display for Rect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{} at ({}, {})",
+            self.width, self.height, self.x, self.y
+        )
+    }
+}
+
+/// The filter used when resizing frames.
+/// - `Nearest` - Nearest neighbour. The fastest, but blocky.
+/// - `Bilinear` - Bilinear interpolation. Smoother, at some cost.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+}
+
+impl Default for ResizeFilter {
+    fn default() -> Self {
+        ResizeFilter::Bilinear
+    }
+}
+
+impl Display for ResizeFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Bilinear => FilterType::Triangle,
+        }
+    }
+}
+
+/// How frames are fit into a different aspect ratio when resizing, like the browser's `resizeMode` constraint.
+/// - `Stretch` - Scale each axis on its own, which distorts the image if the aspect ratio changes.
+/// - `CropAndScale` - Crop the center of the frame to the target aspect ratio, then scale. Nothing is distorted, but the edges are lost.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum ResizeMode {
+    Stretch,
+    CropAndScale,
+}
+
+impl Default for ResizeMode {
+    fn default() -> Self {
+        ResizeMode::CropAndScale
+    }
+}
+
+impl Display for ResizeMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// How a [`Rotation`] set on a [`Camera`](crate::Camera) is handled.
+/// - `Pixels` - The frame is rotated before it is returned.
+/// - `Metadata` - The frame is returned as captured, and the rotation is left to the consumer (see [`Camera::pending_rotation()`](crate::Camera::pending_rotation)).
+///   Use this for sinks that can rotate natively, such as a WebRTC track or a MP4 rotation matrix, to skip a full-frame rotate per frame.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum RotationHandling {
+    Pixels,
+    Metadata,
+}
+
+impl Default for RotationHandling {
+    fn default() -> Self {
+        RotationHandling::Pixels
+    }
+}
+
+impl Display for RotationHandling {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RotationHandling::Pixels => {
+                write!(f, "Pixels")
+            }
+            RotationHandling::Metadata => {
+                write!(f, "Metadata")
+            }
+        }
+    }
+}
+
+/// Rotates a RGB `frame` clockwise by `rotation`. [`Rotation::None`] returns the frame as is.
+#[must_use]
+pub fn rotate_frame(
+    frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    rotation: Rotation,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    match rotation {
+        Rotation::None => frame,
+        Rotation::Rotate90 => imageops::rotate90(&frame),
+        Rotation::Rotate180 => {
+            let mut frame = frame;
+            imageops::rotate180_in_place(&mut frame);
+            frame
+        }
+        Rotation::Rotate270 => imageops::rotate270(&frame),
+    }
+}
+
+/// Mirrors a RGB `frame` in place. [`Mirror::None`] returns the frame as is.
+#[must_use]
+pub fn mirror_frame(
+    frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    mirror: Mirror,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let mut frame = frame;
+    match mirror {
+        Mirror::None => {}
+        Mirror::Horizontal => imageops::flip_horizontal_in_place(&mut frame),
+        Mirror::Vertical => imageops::flip_vertical_in_place(&mut frame),
+        Mirror::Both => imageops::rotate180_in_place(&mut frame),
+    }
+    frame
+}
+
+/// Crops a RGB `frame` to `rect`. The part of `rect` outside the frame is ignored, and if `rect` does not overlap the frame at all, the frame is returned as is.
+#[must_use]
+pub fn crop_frame(
+    frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    rect: Rect,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let resolution = Resolution::new(frame.width(), frame.height());
+    match rect.clamp_to(resolution) {
+        Some(rect) if rect.resolution() != resolution => {
+            imageops::crop_imm(&frame, rect.x(), rect.y(), rect.width(), rect.height()).to_image()
+        }
+        _ => frame,
+    }
+}
+
+/// Resizes a RGB `frame` to `resolution`. If the frame already is that size, it is returned as is.
+#[must_use]
+pub fn resize_frame(
+    frame: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    resolution: Resolution,
+    filter: ResizeFilter,
+    mode: ResizeMode,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    if resolution.width() == 0
+        || resolution.height() == 0
+        || Resolution::new(frame.width(), frame.height()) == resolution
+    {
+        return frame;
+    }
+    resize_view(&frame, resolution, filter, mode)
+}
+
+// resizes without taking ownership, `resolution` must not be 0 on any axis
+fn resize_view(
+    frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    resolution: Resolution,
+    filter: ResizeFilter,
+    mode: ResizeMode,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    match mode {
+        ResizeMode::Stretch => imageops::resize(
+            frame,
+            resolution.width(),
+            resolution.height(),
+            filter.into(),
+        ),
+        ResizeMode::CropAndScale => {
+            let rect = center_crop(frame, resolution);
+            let view = imageops::crop_imm(frame, rect.x(), rect.y(), rect.width(), rect.height());
+            imageops::resize(
+                &view,
+                resolution.width(),
+                resolution.height(),
+                filter.into(),
+            )
+        }
+    }
+}
+
+// the largest rect in the center of `frame` with the aspect ratio of `resolution`
+fn center_crop(frame: &ImageBuffer<Rgb<u8>, Vec<u8>>, resolution: Resolution) -> Rect {
+    let (width, height) = (u64::from(frame.width()), u64::from(frame.height()));
+    let (target_width, target_height) = (
+        u64::from(resolution.width()),
+        u64::from(resolution.height()),
+    );
+    // compare width / height against target_width / target_height without dividing
+    let (crop_width, crop_height) = if width * target_height > target_width * height {
+        ((height * target_width / target_height).max(1), height)
+    } else {
+        (width, (width * target_height / target_width).max(1))
+    };
+    // all of these fit in a u32, since they are at most the frame size
+    #[allow(clippy::cast_possible_truncation)]
+    Rect::new(
+        ((width - crop_width) / 2) as u32,
+        ((height - crop_height) / 2) as u32,
+        crop_width as u32,
+        crop_height as u32,
+    )
+}
+
+/// Scales every frame into a few smaller variants at once, so a streaming sink can serve several quality levels to clients with different bandwidth.
+///
+/// The rungs are kept largest first. Each rung is scaled from the one above it instead of from the full frame, so the
+/// downscale passes are shared: a 1080p frame with 720p, 360p and 180p rungs is scaled once to 720p, and the smaller rungs only read the 720p frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolutionLadder {
+    rungs: Vec<Resolution>,
+    filter: ResizeFilter,
+    mode: ResizeMode,
+}
+
+impl ResolutionLadder {
+    /// Creates a new [`ResolutionLadder`] with `rungs`. Duplicate rungs are removed. This uses [`ResizeFilter::Bilinear`] and [`ResizeMode::CropAndScale`].
+    /// # Errors
+    /// If there are no rungs or any of them is 0 on any axis, this will error.
+    pub fn new(rungs: Vec<Resolution>) -> Result<Self, NokhwaError> {
+        if rungs.is_empty() {
+            return Err(NokhwaError::StructureError {
+                structure: "ResolutionLadder".to_string(),
+                error: "A ladder needs at least one rung".to_string(),
+            });
+        }
+        if let Some(empty) = rungs.iter().find(|r| r.width() == 0 || r.height() == 0) {
+            return Err(NokhwaError::StructureError {
+                structure: "ResolutionLadder".to_string(),
+                error: format!("Invalid rung {}", empty),
+            });
+        }
+        let mut rungs = rungs;
+        rungs.sort_by(|a, b| b.cmp(a));
+        rungs.dedup();
+        Ok(ResolutionLadder {
+            rungs,
+            filter: ResizeFilter::Bilinear,
+            mode: ResizeMode::CropAndScale,
+        })
+    }
+
+    /// Creates a [`ResolutionLadder`] of `count` rungs, each half the size of the one above it, starting at half of `source`.
+    /// # Errors
+    /// If `count` is 0, or `source` is too small to be halved `count` times, this will error.
+    pub fn halving(source: Resolution, count: usize) -> Result<Self, NokhwaError> {
+        let mut rungs = vec![];
+        let mut rung = source;
+        for _ in 0..count {
+            rung = Resolution::new(rung.width() / 2, rung.height() / 2);
+            rungs.push(rung);
+        }
+        ResolutionLadder::new(rungs)
+    }
+
+    /// Sets the [`ResizeFilter`] used to scale the rungs.
+    #[must_use]
+    pub fn with_filter(mut self, filter: ResizeFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the [`ResizeMode`] used when a rung has a different aspect ratio than the frame.
+    #[must_use]
+    pub fn with_mode(mut self, mode: ResizeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The rungs, largest first.
+    #[must_use]
+    pub fn rungs(&self) -> &[Resolution] {
+        &self.rungs
+    }
+
+    /// Scales `frame` to every rung, returning the variants in the same order as [`rungs()`](ResolutionLadder::rungs).
+    #[must_use]
+    pub fn scale(
+        &self,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Vec<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let mut variants: Vec<ImageBuffer<Rgb<u8>, Vec<u8>>> = Vec::with_capacity(self.rungs.len());
+        for rung in &self.rungs {
+            // scale from the previous rung if it is at least as large, it is cheaper to read
+            let source = match variants.last() {
+                Some(previous)
+                    if previous.width() >= rung.width() && previous.height() >= rung.height() =>
+                {
+                    previous
+                }
+                _ => frame,
+            };
+            let variant = if Resolution::new(source.width(), source.height()) == *rung {
+                source.clone()
+            } else {
+                resize_view(source, *rung, self.filter, self.mode)
+            };
+            variants.push(variant);
+        }
+        variants
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{DefaultDecoder, FrameDecoder, FrameFormat, NokhwaError, RawFrame};
+use image::{GrayImage, ImageBuffer, Pixel, Rgb, RgbaImage};
+use std::{
+    fmt::{Debug, Formatter},
+    time::Instant,
+};
+use turbojpeg::{Decompressor, Image, PixelFormat};
+
+/// A [`FrameDecoder`] that decodes MJPEG with `libjpeg-turbo`, using its SIMD code paths (SSE2/AVX2 on x86, NEON on ARM). This is usually 2-3x faster than the [`DefaultDecoder`] for large frames.
+///
+/// If `libjpeg-turbo` cannot be set up, or fails to decode a frame, the frame is decoded with the [`DefaultDecoder`] (`mozjpeg`) instead. Other formats always are.
+/// RGBA and luma are decoded straight into those formats, without going through RGB.
+///
+/// Use it with [`Camera::set_frame_decoder()`](crate::Camera::set_frame_decoder).
+pub struct TurboJpegDecoder {
+    decompressor: Option<Decompressor>,
+    fallbacks: u64,
+}
+
+impl TurboJpegDecoder {
+    /// Creates a new [`TurboJpegDecoder`].
+    #[must_use]
+    pub fn new() -> Self {
+        TurboJpegDecoder {
+            decompressor: Decompressor::new().ok(),
+            fallbacks: 0,
+        }
+    }
+
+    /// Whether `libjpeg-turbo` could be set up. If not, every frame is decoded with the [`DefaultDecoder`].
+    #[must_use]
+    pub fn is_accelerated(&self) -> bool {
+        self.decompressor.is_some()
+    }
+
+    /// How many MJPEG frames `libjpeg-turbo` failed to decode, and were decoded with the [`DefaultDecoder`] instead.
+    #[must_use]
+    pub fn fallback_count(&self) -> u64 {
+        self.fallbacks
+    }
+
+    // decodes `frame` into `P` pixels with `libjpeg-turbo`, or returns `None` if it cannot
+    fn decode<P: Pixel<Subpixel = u8>>(
+        &mut self,
+        frame: &RawFrame,
+        format: PixelFormat,
+    ) -> Option<ImageBuffer<P, Vec<u8>>> {
+        if frame.format() != FrameFormat::MJPEG {
+            return None;
+        }
+        let decompressor = self.decompressor.as_mut()?;
+        let _span = crate::telemetry::decode_span(FrameFormat::MJPEG);
+        let started = Instant::now();
+
+        let decoded = decompressor
+            .read_header(frame.data())
+            .and_then(|header| {
+                let channels = usize::from(P::CHANNEL_COUNT);
+                let size = header.width * header.height * channels;
+                let mut pixels = crate::buffer_pool::take_buffer(size);
+                pixels.resize(size, 0);
+                let image = Image {
+                    pixels: pixels.as_mut_slice(),
+                    width: header.width,
+                    pitch: header.width * channels,
+                    height: header.height,
+                    format,
+                };
+                decompressor.decompress(frame.data(), image)?;
+                Ok((header.width, header.height, pixels))
+            })
+            .ok()
+            .and_then(|(width, height, pixels)| {
+                #[allow(clippy::cast_possible_truncation)]
+                let (width, height) = (width as u32, height as u32);
+                ImageBuffer::from_vec(width, height, pixels)
+            });
+        match decoded {
+            Some(image) => {
+                crate::telemetry::record_decode(FrameFormat::MJPEG, started);
+                Some(image)
+            }
+            None => {
+                self.fallbacks += 1;
+                None
+            }
+        }
+    }
+}
+
+impl Default for TurboJpegDecoder {
+    fn default() -> Self {
+        TurboJpegDecoder::new()
+    }
+}
+
+impl Debug for TurboJpegDecoder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TurboJpegDecoder")
+            .field("accelerated", &self.is_accelerated())
+            .field("fallbacks", &self.fallbacks)
+            .finish()
+    }
+}
+
+impl FrameDecoder for TurboJpegDecoder {
+    fn decode_rgb(
+        &mut self,
+        frame: &RawFrame,
+    ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>, NokhwaError> {
+        match self.decode(frame, PixelFormat::RGB) {
+            Some(image) => Ok(image),
+            None => DefaultDecoder.decode_rgb(frame),
+        }
+    }
+
+    fn decode_rgba(&mut self, frame: &RawFrame) -> Result<RgbaImage, NokhwaError> {
+        match self.decode(frame, PixelFormat::RGBA) {
+            Some(image) => Ok(image),
+            None => DefaultDecoder.decode_rgba(frame),
+        }
+    }
+
+    fn decode_luma(&mut self, frame: &RawFrame) -> Result<GrayImage, NokhwaError> {
+        match self.decode(frame, PixelFormat::GRAY) {
+            Some(image) => Ok(image),
+            None => DefaultDecoder.decode_luma(frame),
+        }
+    }
+}
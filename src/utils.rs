@@ -1,17 +1,22 @@
-use crate::NokhwaError;
-use mozjpeg::Decompress;
+use crate::{NokhwaError, Rotation};
+use mozjpeg::{decompress::Format as JpegFormat, Decompress};
 use std::{
+    borrow::Cow,
     cmp::Ordering,
     convert::TryFrom,
     fmt::{Display, Formatter},
-    slice::from_raw_parts,
+    str::FromStr,
+    time::Instant,
 };
 
 #[cfg(feature = "input-msmf")]
 use nokhwa_bindings_windows::{
-    MFCameraFormat, MFControl, MFFrameFormat, MFResolution, MediaFoundationControls,
-    MediaFoundationDeviceDescriptor,
+    wmf::{device_panel, device_rotation},
+    MFCameraFormat, MFCameraProfile, MFCameraProfileKind, MFControl, MFDevicePanel, MFFrameFormat,
+    MFResolution, MediaFoundationControls, MediaFoundationDeviceDescriptor,
 };
+#[cfg(feature = "input-opencv")]
+use opencv::core::{Mat, MatTraitManual, Scalar, CV_8UC3};
 #[cfg(feature = "input-uvc")]
 use uvc::StreamFormat;
 #[cfg(feature = "input-v4l")]
@@ -19,11 +24,26 @@ use v4l::{control::Description, Format, FourCC};
 
 /// Describes a frame format (i.e. how the bytes themselves are encoded). Often called `FourCC` <br>
 /// YUYV is a mathematical color space. You can read more [here.](https://en.wikipedia.org/wiki/YCbCr) <br>
-/// MJPEG is a motion-jpeg compressed frame, it allows for high frame rates.
+/// MJPEG is a motion-jpeg compressed frame, it allows for high frame rates. <br>
+/// Y16 is 16 bit luma (gray) only, one little endian `u16` per pixel, as used by thermal and infrared cameras. <br>
+/// Z16 is 16 bit depth, one little endian `u16` per pixel holding the distance in device units (usually millimeters, `0` where the depth is unknown), as used by depth sensors. See [`StreamKind`]. <br>
+/// P010 is 10 bit YUV 4:2:0, a plane of luma followed by a plane of interleaved chroma, each sample a little endian `u16` with the value in the high 10 bits. <br>
+/// Use [`frame_u16()`](crate::Camera::frame_u16) or [`frame_rgb16()`](crate::Camera::frame_rgb16) to keep the full bit depth of these. <br>
+/// H264 and H265 are compressed video streams, which `nokhwa` does not decode. Every [`frame_raw()`](crate::Camera::frame_raw) is one access unit in Annex B format (NAL units behind `00 00 01` start codes), ready to be muxed to disk or sent over the network. See [`is_keyframe()`]. <br>
+/// Bayer formats are the raw sensor data of industrial and embedded cameras, one color per pixel in a repeating 2x2 [`BayerPattern`]. `Bayer8` has one byte per pixel,
+/// `Bayer10` and `Bayer12` have one little endian `u16` per pixel with the sample in the low bits. Use [`debayer()`] to get RGB, or [`frame_raw()`](crate::Camera::frame_raw) to get the samples themselves.
 #[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
 pub enum FrameFormat {
     MJPEG,
     YUYV,
+    Bayer8(BayerPattern),
+    Bayer10(BayerPattern),
+    Bayer12(BayerPattern),
+    Y16,
+    Z16,
+    P010,
+    H264,
+    H265,
 }
 impl Display for FrameFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -34,16 +54,162 @@ impl Display for FrameFormat {
             FrameFormat::YUYV => {
                 write!(f, "YUYV")
             }
+            FrameFormat::Bayer8(pattern) => {
+                write!(f, "{}8", pattern)
+            }
+            FrameFormat::Bayer10(pattern) => {
+                write!(f, "{}10", pattern)
+            }
+            FrameFormat::Bayer12(pattern) => {
+                write!(f, "{}12", pattern)
+            }
+            FrameFormat::Y16 => {
+                write!(f, "Y16")
+            }
+            FrameFormat::Z16 => {
+                write!(f, "Z16")
+            }
+            FrameFormat::P010 => {
+                write!(f, "P010")
+            }
+            FrameFormat::H264 => {
+                write!(f, "H264")
+            }
+            FrameFormat::H265 => {
+                write!(f, "H265")
+            }
+        }
+    }
+}
+
+impl FromStr for FrameFormat {
+    type Err = NokhwaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "MJPEG" => Ok(FrameFormat::MJPEG),
+            "YUYV" => Ok(FrameFormat::YUYV),
+            "Y16" => Ok(FrameFormat::Y16),
+            "Z16" => Ok(FrameFormat::Z16),
+            "P010" => Ok(FrameFormat::P010),
+            "H264" => Ok(FrameFormat::H264),
+            "H265" => Ok(FrameFormat::H265),
+            _ => {
+                let bayer = if s.len() > 4 && s.is_char_boundary(4) {
+                    let (pattern, bits) = s.split_at(4);
+                    match (BayerPattern::from_str(pattern), bits) {
+                        (Ok(pattern), "8") => Some(FrameFormat::Bayer8(pattern)),
+                        (Ok(pattern), "10") => Some(FrameFormat::Bayer10(pattern)),
+                        (Ok(pattern), "12") => Some(FrameFormat::Bayer12(pattern)),
+                        _ => None,
+                    }
+                } else {
+                    None
+                };
+                bayer.ok_or_else(|| NokhwaError::StructureError {
+                    structure: "FrameFormat".to_string(),
+                    error: format!("Unknown frame format {}", s),
+                })
+            }
+        }
+    }
+}
+
+impl FrameFormat {
+    /// The size in bytes of a tightly packed row of `width` pixels, or `None` for compressed formats (MJPEG, H264, H265), which have no rows.
+    /// For [`FrameFormat::P010`], rows of the luma and the chroma plane are the same size.
+    ///
+    /// Drivers may pad rows to a larger stride, see [`frame_stride()`](crate::CaptureBackendTrait::frame_stride).
+    #[must_use]
+    pub fn row_bytes(self, width: u32) -> Option<usize> {
+        let width = width as usize;
+        match self {
+            FrameFormat::MJPEG | FrameFormat::H264 | FrameFormat::H265 => None,
+            FrameFormat::Bayer8(_) => Some(width),
+            FrameFormat::YUYV
+            | FrameFormat::Bayer10(_)
+            | FrameFormat::Bayer12(_)
+            | FrameFormat::Y16
+            | FrameFormat::Z16
+            | FrameFormat::P010 => Some(width.saturating_mul(2)),
+        }
+    }
+
+    // how many rows a frame of `height` has, counting both planes of P010
+    fn rows(self, height: u32) -> usize {
+        let height = height as usize;
+        match self {
+            FrameFormat::P010 => height.saturating_add(height / 2),
+            _ => height,
+        }
+    }
+}
+
+/// The order of the colors in the 2x2 tile a Bayer sensor repeats, read left to right, top to bottom. e.g. `RGGB` is red, green on the first row and green, blue on the second.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum BayerPattern {
+    RGGB,
+    GRBG,
+    GBRG,
+    BGGR,
+}
+
+impl BayerPattern {
+    // the channel (0 = red, 1 = green, 2 = blue) of the sample at `x`, `y`
+    fn channel(self, x: usize, y: usize) -> usize {
+        let tile = match self {
+            BayerPattern::RGGB => [0, 1, 1, 2],
+            BayerPattern::GRBG => [1, 0, 2, 1],
+            BayerPattern::GBRG => [1, 2, 0, 1],
+            BayerPattern::BGGR => [2, 1, 1, 0],
+        };
+        tile[(y & 1) * 2 + (x & 1)]
+    }
+}
+
+impl Display for BayerPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let self_str = format!("{:?}", self);
+        write!(f, "{}", self_str)
+    }
+}
+
+impl FromStr for BayerPattern {
+    type Err = NokhwaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "RGGB" => Ok(BayerPattern::RGGB),
+            "GRBG" => Ok(BayerPattern::GRBG),
+            "GBRG" => Ok(BayerPattern::GBRG),
+            "BGGR" => Ok(BayerPattern::BGGR),
+            _ => Err(NokhwaError::StructureError {
+                structure: "BayerPattern".to_string(),
+                error: format!("Unknown Bayer pattern {}", s),
+            }),
         }
     }
 }
 
 #[cfg(feature = "input-uvc")]
-impl From<FrameFormat> for uvc::FrameFormat {
-    fn from(ff: FrameFormat) -> Self {
+impl TryFrom<FrameFormat> for uvc::FrameFormat {
+    type Error = NokhwaError;
+
+    fn try_from(ff: FrameFormat) -> Result<Self, Self::Error> {
         match ff {
-            FrameFormat::MJPEG => uvc::FrameFormat::MJPEG,
-            FrameFormat::YUYV => uvc::FrameFormat::YUYV,
+            FrameFormat::MJPEG => Ok(uvc::FrameFormat::MJPEG),
+            FrameFormat::YUYV => Ok(uvc::FrameFormat::YUYV),
+            FrameFormat::Bayer8(_)
+            | FrameFormat::Bayer10(_)
+            | FrameFormat::Bayer12(_)
+            | FrameFormat::Y16
+            | FrameFormat::Z16
+            | FrameFormat::P010
+            | FrameFormat::H264
+            | FrameFormat::H265 => Err(NokhwaError::UnsupportedFormat {
+                format: ff.to_string(),
+                error: "The UVC backend only supports MJPEG and YUYV".to_string(),
+            }),
         }
     }
 }
@@ -58,18 +224,95 @@ impl From<MFFrameFormat> for FrameFormat {
     }
 }
 
-impl From<FrameFormat> for MFFrameFormat {
-    fn from(ff: FrameFormat) -> Self {
+#[cfg(feature = "input-msmf")]
+impl TryFrom<FrameFormat> for MFFrameFormat {
+    type Error = NokhwaError;
+
+    fn try_from(ff: FrameFormat) -> Result<Self, Self::Error> {
         match ff {
-            FrameFormat::MJPEG => MFFrameFormat::MJPEG,
-            FrameFormat::YUYV => MFFrameFormat::YUYV,
+            FrameFormat::MJPEG => Ok(MFFrameFormat::MJPEG),
+            FrameFormat::YUYV => Ok(MFFrameFormat::YUYV),
+            FrameFormat::Bayer8(_)
+            | FrameFormat::Bayer10(_)
+            | FrameFormat::Bayer12(_)
+            | FrameFormat::Y16
+            | FrameFormat::Z16
+            | FrameFormat::P010
+            | FrameFormat::H264
+            | FrameFormat::H265 => Err(NokhwaError::UnsupportedFormat {
+                format: ff.to_string(),
+                error: "The Media Foundation backend only supports MJPEG and YUYV".to_string(),
+            }),
         }
     }
 }
 
+#[cfg(feature = "input-v4l")]
+impl From<FrameFormat> for FourCC {
+    fn from(ff: FrameFormat) -> Self {
+        FourCC::new(match ff {
+            FrameFormat::MJPEG => b"MJPG",
+            FrameFormat::YUYV => b"YUYV",
+            FrameFormat::Bayer8(BayerPattern::RGGB) => b"RGGB",
+            FrameFormat::Bayer8(BayerPattern::GRBG) => b"GRBG",
+            FrameFormat::Bayer8(BayerPattern::GBRG) => b"GBRG",
+            FrameFormat::Bayer8(BayerPattern::BGGR) => b"BA81",
+            FrameFormat::Bayer10(BayerPattern::RGGB) => b"RG10",
+            FrameFormat::Bayer10(BayerPattern::GRBG) => b"BA10",
+            FrameFormat::Bayer10(BayerPattern::GBRG) => b"GB10",
+            FrameFormat::Bayer10(BayerPattern::BGGR) => b"BG10",
+            FrameFormat::Bayer12(BayerPattern::RGGB) => b"RG12",
+            FrameFormat::Bayer12(BayerPattern::GRBG) => b"BA12",
+            FrameFormat::Bayer12(BayerPattern::GBRG) => b"GB12",
+            FrameFormat::Bayer12(BayerPattern::BGGR) => b"BG12",
+            FrameFormat::Y16 => b"Y16 ",
+            FrameFormat::Z16 => b"Z16 ",
+            FrameFormat::P010 => b"P010",
+            FrameFormat::H264 => b"H264",
+            FrameFormat::H265 => b"HEVC",
+        })
+    }
+}
+
+#[cfg(feature = "input-v4l")]
+impl TryFrom<FourCC> for FrameFormat {
+    type Error = NokhwaError;
+
+    fn try_from(fourcc: FourCC) -> Result<Self, Self::Error> {
+        let format = match &fourcc.repr {
+            b"MJPG" => FrameFormat::MJPEG,
+            b"YUYV" => FrameFormat::YUYV,
+            b"RGGB" => FrameFormat::Bayer8(BayerPattern::RGGB),
+            b"GRBG" => FrameFormat::Bayer8(BayerPattern::GRBG),
+            b"GBRG" => FrameFormat::Bayer8(BayerPattern::GBRG),
+            b"BA81" => FrameFormat::Bayer8(BayerPattern::BGGR),
+            b"RG10" => FrameFormat::Bayer10(BayerPattern::RGGB),
+            b"BA10" => FrameFormat::Bayer10(BayerPattern::GRBG),
+            b"GB10" => FrameFormat::Bayer10(BayerPattern::GBRG),
+            b"BG10" => FrameFormat::Bayer10(BayerPattern::BGGR),
+            b"RG12" => FrameFormat::Bayer12(BayerPattern::RGGB),
+            b"BA12" => FrameFormat::Bayer12(BayerPattern::GRBG),
+            b"GB12" => FrameFormat::Bayer12(BayerPattern::GBRG),
+            b"BG12" => FrameFormat::Bayer12(BayerPattern::BGGR),
+            b"Y16 " => FrameFormat::Y16,
+            b"Z16 " => FrameFormat::Z16,
+            b"P010" => FrameFormat::P010,
+            b"H264" => FrameFormat::H264,
+            b"HEVC" => FrameFormat::H265,
+            _ => {
+                return Err(NokhwaError::StructureError {
+                    structure: "FrameFormat".to_string(),
+                    error: format!("Unsupported FourCC {}", fourcc),
+                })
+            }
+        };
+        Ok(format)
+    }
+}
+
 /// Describes a Resolution.
 /// This struct consists of a Width and a Height value (x,y). <br>
-/// Note: the [`Ord`] implementation of this struct is flipped from highest to lowest.
+/// Note: the [`Ord`] implementation of this struct sorts by width, then height, from lowest to highest. Use [`cmp_by_area()`](Resolution::cmp_by_area) to sort by pixel count.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct Resolution {
     pub width_x: u32,
@@ -77,9 +320,20 @@ pub struct Resolution {
 }
 
 impl Resolution {
+    /// 640x480, VGA.
+    pub const VGA: Resolution = Resolution::new(640, 480);
+    /// 1280x720, HD or 720p.
+    pub const HD: Resolution = Resolution::new(1280, 720);
+    /// 1920x1080, Full HD or 1080p.
+    pub const FHD: Resolution = Resolution::new(1920, 1080);
+    /// 2560x1440, QHD or 1440p.
+    pub const QHD: Resolution = Resolution::new(2560, 1440);
+    /// 3840x2160, 4K UHD or 2160p.
+    pub const UHD: Resolution = Resolution::new(3840, 2160);
+
     /// Create a new resolution from 2 image size coordinates.
     #[must_use]
-    pub fn new(x: u32, y: u32) -> Self {
+    pub const fn new(x: u32, y: u32) -> Self {
         Resolution {
             width_x: x,
             height_y: y,
@@ -109,6 +363,51 @@ impl Resolution {
     pub fn y(self) -> u32 {
         self.height_y
     }
+
+    /// The width divided by the height, e.g. `1.777...` for 16:9. This is infinite (or NaN for 0x0) if the height is 0.
+    #[must_use]
+    pub fn aspect_ratio(self) -> f64 {
+        f64::from(self.width()) / f64::from(self.height())
+    }
+
+    /// The number of pixels, width times height.
+    #[must_use]
+    pub fn pixel_count(self) -> u64 {
+        u64::from(self.width()) * u64::from(self.height())
+    }
+
+    /// The number of pixels in millions, e.g. `2.0736` for 1920x1080.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn megapixels(self) -> f64 {
+        // exact for anything up to 2^53 pixels
+        self.pixel_count() as f64 / 1_000_000.0
+    }
+
+    /// Compares by [`pixel_count()`](Resolution::pixel_count), then by width. Use it with e.g. `resolutions.sort_by(Resolution::cmp_by_area)`.
+    #[must_use]
+    pub fn cmp_by_area(&self, other: &Self) -> Ordering {
+        self.pixel_count()
+            .cmp(&other.pixel_count())
+            .then(self.width().cmp(&other.width()))
+    }
+
+    /// The resolution in `candidates` closest to this one (the smallest difference in width plus difference in height), or `None` if `candidates` is empty.
+    /// Of equally close candidates, the first wins.
+    #[must_use]
+    pub fn closest_to(self, candidates: &[Resolution]) -> Option<Resolution> {
+        candidates
+            .iter()
+            .copied()
+            .min_by_key(|candidate| self.distance(*candidate))
+    }
+
+    // the difference in width plus the difference in height
+    pub(crate) fn distance(self, other: Resolution) -> u64 {
+        let width = i64::from(self.width()) - i64::from(other.width());
+        let height = i64::from(self.height()) - i64::from(other.height());
+        width.unsigned_abs() + height.unsigned_abs()
+    }
 }
 
 impl Display for Resolution {
@@ -155,7 +454,9 @@ impl From<Resolution> for MFResolution {
 
 /// This is a convenience struct that holds all information about the format of a webcam stream.
 /// It consists of a [`Resolution`], [`FrameFormat`], and a frame rate(u8).
-#[derive(Copy, Clone, Debug, Hash, PartialEq)]
+///
+/// The [`Ord`] implementation sorts by resolution (see [`Resolution`]), then frame rate, then [`FrameFormat`], all from lowest to highest.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct CameraFormat {
     resolution: Resolution,
     format: FrameFormat,
@@ -232,15 +533,32 @@ impl CameraFormat {
     }
 }
 
+impl PartialOrd for CameraFormat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CameraFormat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.resolution()
+            .cmp(&other.resolution())
+            .then(self.frame_rate().cmp(&other.frame_rate()))
+            .then(self.format().cmp(&other.format()))
+    }
+}
+
 #[cfg(feature = "input-uvc")]
-impl From<CameraFormat> for StreamFormat {
-    fn from(cf: CameraFormat) -> Self {
-        StreamFormat {
+impl TryFrom<CameraFormat> for StreamFormat {
+    type Error = NokhwaError;
+
+    fn try_from(cf: CameraFormat) -> Result<Self, Self::Error> {
+        Ok(StreamFormat {
             width: cf.width(),
             height: cf.height(),
             fps: cf.frame_rate(),
-            format: cf.format().into(),
-        }
+            format: uvc::FrameFormat::try_from(cf.format())?,
+        })
     }
 }
 
@@ -276,33 +594,252 @@ impl From<MFCameraFormat> for CameraFormat {
 }
 
 #[cfg(feature = "input-msmf")]
-impl From<CameraFormat> for MFCameraFormat {
-    fn from(cf: CameraFormat) -> Self {
-        MFCameraFormat::new(cf.resolution.into(), cf.format.into(), cf.frame_rate)
+impl TryFrom<CameraFormat> for MFCameraFormat {
+    type Error = NokhwaError;
+
+    fn try_from(cf: CameraFormat) -> Result<Self, Self::Error> {
+        Ok(MFCameraFormat::new(
+            cf.resolution.into(),
+            MFFrameFormat::try_from(cf.format)?,
+            cf.frame_rate,
+        ))
     }
 }
 
 #[cfg(feature = "input-v4l")]
 impl From<CameraFormat> for Format {
     fn from(cam_fmt: CameraFormat) -> Self {
-        let pxfmt = match cam_fmt.format() {
-            FrameFormat::MJPEG => FourCC::new(b"MJPG"),
-            FrameFormat::YUYV => FourCC::new(b"YUYV"),
+        Format::new(cam_fmt.width(), cam_fmt.height(), cam_fmt.format().into())
+    }
+}
+
+/// Which way a camera faces, relative to the device it is built into.
+/// - `Front` - Built in, facing the user (the "selfie" camera).
+/// - `Back` - Built in, facing away from the user.
+/// - `External` - Not built into the device (e.g. a USB webcam).
+/// - `Unknown` - The backend cannot tell.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum CameraPosition {
+    Front,
+    Back,
+    External,
+    Unknown,
+}
+
+impl Default for CameraPosition {
+    fn default() -> Self {
+        CameraPosition::Unknown
+    }
+}
+
+impl Display for CameraPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// What a camera (or one of the sensors of a depth camera) captures.
+/// - `Color` - A regular color (or monochrome visible light) image.
+/// - `Infrared` - An infrared image, e.g. the IR sensors of a depth camera or a face authentication camera.
+/// - `Depth` - A depth map, usually [`FrameFormat::Z16`].
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum StreamKind {
+    Color,
+    Infrared,
+    Depth,
+}
+
+impl Default for StreamKind {
+    fn default() -> Self {
+        StreamKind::Color
+    }
+}
+
+impl Display for StreamKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<FrameFormat> for StreamKind {
+    /// The [`StreamKind`] a [`FrameFormat`] implies: [`FrameFormat::Z16`] is `Depth`, everything else is `Color`.
+    fn from(format: FrameFormat) -> Self {
+        match format {
+            FrameFormat::Z16 => StreamKind::Depth,
+            _ => StreamKind::Color,
+        }
+    }
+}
+
+/// How a camera is shared with other applications.
+/// - `Exclusive` - This application has sole control of the device. Opening it fails (with [`DeviceBusy`](NokhwaError::DeviceBusy)) if another application already has it, and other applications cannot take it, or change its format or controls, while it is held.
+/// - `Shared` - This application shares the device with others. It can read frames of a stream another application has started, but may be refused when changing the format or controls.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum AccessMode {
+    Exclusive,
+    Shared,
+}
+
+impl Display for AccessMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The kind of a camera profile. Camera profiles are sets of modes a device can stream in together, such as a high frame rate mode or a photo mode, see [Camera profiles](https://docs.microsoft.com/en-us/windows-hardware/drivers/stream/camera-profiles).
+/// `Unknown` is a vendor defined profile.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub enum CameraProfileKind {
+    Legacy,
+    VideoRecording,
+    HighQualityPhoto,
+    BalancedVideoAndPhoto,
+    VideoConferencing,
+    PhotoSequence,
+    HighFrameRate,
+    VariablePhotoSequence,
+    VideoHDR8,
+    Unknown,
+}
+
+impl Display for CameraProfileKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A camera profile a device advertises. `index` tells apart profiles of the same [`CameraProfileKind`].
+/// # Quirks
+/// - Only Media Foundation has camera profiles, and only devices whose driver declares them (usually the built in cameras of Surface-class devices, and sensor groups) have any.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Debug, PartialEq, Hash, PartialOrd, Ord, Eq)]
+pub struct CameraProfile {
+    kind: CameraProfileKind,
+    index: u32,
+}
+
+impl CameraProfile {
+    /// Create a new [`CameraProfile`].
+    #[must_use]
+    pub fn new(kind: CameraProfileKind, index: u32) -> Self {
+        CameraProfile { kind, index }
+    }
+
+    /// Get the kind of the profile.
+    #[must_use]
+    pub fn kind(&self) -> CameraProfileKind {
+        self.kind
+    }
+
+    /// Get the index of the profile among the profiles of its kind.
+    #[must_use]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Display for CameraProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} #{}", self.kind, self.index)
+    }
+}
+
+#[cfg(feature = "input-msmf")]
+impl From<MFCameraProfile> for CameraProfile {
+    fn from(mf_profile: MFCameraProfile) -> Self {
+        let kind = match mf_profile.kind {
+            MFCameraProfileKind::Legacy => CameraProfileKind::Legacy,
+            MFCameraProfileKind::VideoRecording => CameraProfileKind::VideoRecording,
+            MFCameraProfileKind::HighQualityPhoto => CameraProfileKind::HighQualityPhoto,
+            MFCameraProfileKind::BalancedVideoAndPhoto => CameraProfileKind::BalancedVideoAndPhoto,
+            MFCameraProfileKind::VideoConferencing => CameraProfileKind::VideoConferencing,
+            MFCameraProfileKind::PhotoSequence => CameraProfileKind::PhotoSequence,
+            MFCameraProfileKind::HighFrameRate => CameraProfileKind::HighFrameRate,
+            MFCameraProfileKind::VariablePhotoSequence => CameraProfileKind::VariablePhotoSequence,
+            MFCameraProfileKind::VideoHDR8 => CameraProfileKind::VideoHDR8,
+            MFCameraProfileKind::Unknown => CameraProfileKind::Unknown,
         };
+        CameraProfile::new(kind, mf_profile.index)
+    }
+}
 
-        Format::new(cam_fmt.width(), cam_fmt.height(), pxfmt)
+#[cfg(feature = "input-msmf")]
+impl From<CameraProfile> for MFCameraProfile {
+    fn from(profile: CameraProfile) -> Self {
+        let kind = match profile.kind {
+            CameraProfileKind::Legacy => MFCameraProfileKind::Legacy,
+            CameraProfileKind::VideoRecording => MFCameraProfileKind::VideoRecording,
+            CameraProfileKind::HighQualityPhoto => MFCameraProfileKind::HighQualityPhoto,
+            CameraProfileKind::BalancedVideoAndPhoto => MFCameraProfileKind::BalancedVideoAndPhoto,
+            CameraProfileKind::VideoConferencing => MFCameraProfileKind::VideoConferencing,
+            CameraProfileKind::PhotoSequence => MFCameraProfileKind::PhotoSequence,
+            CameraProfileKind::HighFrameRate => MFCameraProfileKind::HighFrameRate,
+            CameraProfileKind::VariablePhotoSequence => MFCameraProfileKind::VariablePhotoSequence,
+            CameraProfileKind::VideoHDR8 => MFCameraProfileKind::VideoHDR8,
+            CameraProfileKind::Unknown => MFCameraProfileKind::Unknown,
+        };
+        MFCameraProfile {
+            kind,
+            index: profile.index,
+        }
     }
 }
 
 /// Information about a Camera e.g. its name.
 /// `description` amd `misc` may contain backend-specific information.
 /// `index` is a camera's index given to it by (usually) the OS usually in the order it is known to the system.
+///
+/// `vendor_id`, `product_id`, `serial` and `bus_path` identify the physical device, and are stable across reboots and re-plugs
+/// (`bus_path` only as long as the device stays in the same port), which makes them suitable for persisting a device choice.
+/// They are `None` when the backend has no way of knowing them.
+///
+/// `position` says whether the camera is the front (selfie) or back camera of the device, or an external one. It is [`CameraPosition::Unknown`] when the backend has no way of knowing it.
+///
+/// `group_id` is the same for every camera that is part of one physical unit, such as the left, right and depth sensors of a stereo or depth camera (See: [`query_camera_groups()`](crate::query_camera_groups)).
+/// It is `None` when the backend has no way of knowing it.
+///
+/// `stream_kind` says whether the camera captures color, infrared or depth. Depth and IR sensors are often exposed as separate cameras of one group.
+/// It is [`StreamKind::Color`] unless the backend finds otherwise.
+///
+/// `sensor_orientation` is the clockwise [`Rotation`] that turns frames upright, for sensors that are mounted sideways or upside down (as in many tablets and convertibles).
+/// It is [`Rotation::None`] unless the OS reports otherwise. [`Camera::set_auto_rotate()`](crate::Camera::set_auto_rotate) applies it to frames.
+///
+/// `profiles` are the [`CameraProfile`]s the device advertises, one of which can be selected when opening it (See: [`MediaFoundationCaptureDevice::new_with_profile()`](crate::backends::capture::MediaFoundationCaptureDevice::new_with_profile)). It is empty for devices without camera profiles.
+/// # Quirks
+/// - `Video4Linux`: The IDs and serial are read from sysfs (the same attributes udev uses), `bus_path` is the sysfs path of the USB device.
+/// - Media Foundation: The IDs are parsed from the symbolic link. `serial` is only available if the device is not a composite device (no `MI_xx`) and has a serial, otherwise `None`.
+/// - UVC: `bus_path` is not available.
+/// - `GStreamer`: Depends on the device provider, most will only give a `bus_path`.
+/// - `AVFoundation` is not yet implemented, so there is no `uniqueID` or `position` support. There is no Android backend either.
+/// - Media Foundation: `position` comes from the device's `EnclosureLocation`. Devices without one (usually USB cameras) are `External`.
+/// - `JSCamera`: `position` comes from the `facingMode` capability of the device, which some browsers only report after camera permission has been granted.
+/// - `Video4Linux`, UVC, `GStreamer` and `OpenCV`: `position` is always `Unknown`.
+/// - `Video4Linux`: `group_id` is the sysfs path of the USB device (or of the parent device, for cameras that are not USB), which every video node of the unit hangs off.
+/// - Media Foundation: `group_id` is derived from the symbolic link, with the interface (`MI_xx`) of composite devices stripped off. It is not the container ID.
+/// - `JSCamera`: `group_id` is the browser's `groupId`.
+/// - `GStreamer`: `group_id` is the `bus_path`, if the device provider gives one. UVC and `OpenCV`: `group_id` is always `None`.
+/// - `Video4Linux`: `stream_kind` is `Depth` if the device offers [`FrameFormat::Z16`]. Everywhere else it is guessed from the name, which only finds `Infrared` cameras (names with "IR" or "Infrared" in them).
+/// - UVC and `OpenCV`: `stream_kind` is always `Color`.
+/// - Media Foundation: `sensor_orientation` comes from the `RotationAngleInDegreesClockwise` of the device's `EnclosureLocation` (Windows 10 1607 and later).
+/// - `Video4Linux`: `sensor_orientation` comes from the `V4L2_CID_CAMERA_SENSOR_ROTATION` control (Linux 5.8 and later), which is mostly set from the firmware (ACPI or device tree) of built in cameras.
+/// - UVC, `GStreamer`, `OpenCV` and `JSCamera`: `sensor_orientation` is always `None`. There is no Android backend.
+/// - Media Foundation: `profiles` are read by creating the device's media source, which makes listing devices slower. Every other backend has no `profiles`.
 #[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
 pub struct CameraInfo {
     human_name: String,
     description: String,
     misc: String,
     index: usize,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial: Option<String>,
+    bus_path: Option<String>,
+    position: CameraPosition,
+    group_id: Option<String>,
+    stream_kind: StreamKind,
+    sensor_orientation: Rotation,
+    profiles: Vec<CameraProfile>,
 }
 
 impl CameraInfo {
@@ -314,6 +851,15 @@ impl CameraInfo {
             description,
             misc,
             index,
+            vendor_id: None,
+            product_id: None,
+            serial: None,
+            bus_path: None,
+            position: CameraPosition::Unknown,
+            group_id: None,
+            stream_kind: StreamKind::Color,
+            sensor_orientation: Rotation::None,
+            profiles: vec![],
         }
     }
 
@@ -360,6 +906,227 @@ impl CameraInfo {
     pub fn set_index(&mut self, index: usize) {
         self.index = index;
     }
+
+    /// Get the device's USB vendor ID, if known.
+    #[must_use]
+    pub fn vendor_id(&self) -> Option<u16> {
+        self.vendor_id
+    }
+
+    /// Set the device's USB vendor ID.
+    pub fn set_vendor_id(&mut self, vendor_id: Option<u16>) {
+        self.vendor_id = vendor_id;
+    }
+
+    /// Get the device's USB product ID, if known.
+    #[must_use]
+    pub fn product_id(&self) -> Option<u16> {
+        self.product_id
+    }
+
+    /// Set the device's USB product ID.
+    pub fn set_product_id(&mut self, product_id: Option<u16>) {
+        self.product_id = product_id;
+    }
+
+    /// Get a reference to the device's serial number, if known.
+    #[must_use]
+    pub fn serial(&self) -> Option<&String> {
+        self.serial.as_ref()
+    }
+
+    /// Set the device's serial number.
+    pub fn set_serial(&mut self, serial: Option<String>) {
+        self.serial = serial;
+    }
+
+    /// Get a reference to the device's bus path (where the device is physically attached), if known.
+    #[must_use]
+    pub fn bus_path(&self) -> Option<&String> {
+        self.bus_path.as_ref()
+    }
+
+    /// Set the device's bus path.
+    pub fn set_bus_path(&mut self, bus_path: Option<String>) {
+        self.bus_path = bus_path;
+    }
+
+    /// Get which way the device faces.
+    #[must_use]
+    pub fn position(&self) -> CameraPosition {
+        self.position
+    }
+
+    /// Set which way the device faces.
+    pub fn set_position(&mut self, position: CameraPosition) {
+        self.position = position;
+    }
+
+    /// Get a reference to the ID of the physical unit the device is part of, if known.
+    #[must_use]
+    pub fn group_id(&self) -> Option<&String> {
+        self.group_id.as_ref()
+    }
+
+    /// Set the ID of the physical unit the device is part of.
+    pub fn set_group_id(&mut self, group_id: Option<String>) {
+        self.group_id = group_id;
+    }
+
+    /// Get what the device captures.
+    #[must_use]
+    pub fn stream_kind(&self) -> StreamKind {
+        self.stream_kind
+    }
+
+    /// Set what the device captures.
+    pub fn set_stream_kind(&mut self, stream_kind: StreamKind) {
+        self.stream_kind = stream_kind;
+    }
+
+    /// Get the clockwise rotation that turns frames upright.
+    #[must_use]
+    pub fn sensor_orientation(&self) -> Rotation {
+        self.sensor_orientation
+    }
+
+    /// Set the clockwise rotation that turns frames upright.
+    pub fn set_sensor_orientation(&mut self, sensor_orientation: Rotation) {
+        self.sensor_orientation = sensor_orientation;
+    }
+
+    /// Get the camera profiles the device advertises.
+    #[must_use]
+    pub fn profiles(&self) -> &[CameraProfile] {
+        &self.profiles
+    }
+
+    /// Set the camera profiles the device advertises.
+    pub fn set_profiles(&mut self, profiles: Vec<CameraProfile>) {
+        self.profiles = profiles;
+    }
+
+    // depth cameras are the ones offering depth, infrared ones can only be told apart by name (e.g. "Integrated IR Camera")
+    pub(crate) fn guess_stream_kind(&mut self, formats: &[FrameFormat]) {
+        let name = self.human_name.to_lowercase();
+        self.stream_kind = if formats.contains(&FrameFormat::Z16) {
+            StreamKind::Depth
+        } else if name.contains("infrared")
+            || name
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| word == "ir")
+        {
+            StreamKind::Infrared
+        } else {
+            StreamKind::Color
+        };
+    }
+
+    #[cfg(feature = "input-v4l")]
+    pub(crate) fn guess_stream_kind_from_v4l(&mut self, device: &v4l::Device) {
+        use v4l::video::Capture;
+
+        let formats: Vec<FrameFormat> = Capture::enum_formats(device)
+            .map(|formats| {
+                formats
+                    .into_iter()
+                    .filter_map(|format| FrameFormat::try_from(format.fourcc).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.guess_stream_kind(&formats);
+    }
+
+    // `V4L2_CID_CAMERA_SENSOR_ROTATION` is the correction in degrees counter-clockwise
+    #[cfg(feature = "input-v4l")]
+    pub(crate) fn read_sensor_orientation_from_v4l(&mut self, device: &v4l::Device) {
+        const CAMERA_SENSOR_ROTATION: u32 = 10_094_883;
+
+        if let Ok(v4l::control::Control::Value(degrees)) = device.control(CAMERA_SENSOR_ROTATION) {
+            let clockwise =
+                u32::try_from(degrees.rem_euclid(360)).map(|degrees| (360 - degrees) % 360);
+            if let Some(rotation) = clockwise.ok().and_then(Rotation::from_degrees) {
+                self.sensor_orientation = rotation;
+            }
+        }
+    }
+
+    // Symbolic links look like `\\?\usb#vid_046d&pid_0825&mi_00#6&2d0a5b0&0&0000#{e5323777-f976-4f5b-9b55-b94699c46e44}\global`
+    #[cfg(feature = "input-msmf")]
+    pub(crate) fn fill_from_symbolic_link(&mut self, link: &str) {
+        let lowercase = link.to_lowercase();
+        let mut parts = lowercase.split('#');
+        let _prefix = parts.next();
+        let hardware_id = match parts.next() {
+            Some(id) => id,
+            None => return,
+        };
+        for id_part in hardware_id.split('&') {
+            if let Some(vid) = id_part.strip_prefix("vid_") {
+                self.vendor_id = u16::from_str_radix(vid, 16).ok();
+            } else if let Some(pid) = id_part.strip_prefix("pid_") {
+                self.product_id = u16::from_str_radix(pid, 16).ok();
+            }
+        }
+        if let Some(instance) = parts.next() {
+            // composite devices (mi_xx) and devices without a serial get an instance ID generated by windows, which contains '&'
+            if !hardware_id.contains("mi_") && !instance.contains('&') {
+                self.serial = Some(instance.to_uppercase());
+            }
+            self.bus_path = Some(format!("{}#{}", hardware_id, instance));
+            // the interfaces of a composite device are `vid_xxxx&pid_xxxx&mi_xx`, with an instance ID of the parent's followed by `&` and the interface number
+            self.group_id = if hardware_id.contains("mi_") {
+                let device_id = hardware_id
+                    .split('&')
+                    .filter(|id_part| !id_part.starts_with("mi_"))
+                    .collect::<Vec<_>>()
+                    .join("&");
+                let parent_instance = instance.rsplitn(2, '&').last().unwrap_or(instance);
+                Some(format!("{}#{}", device_id, parent_instance))
+            } else {
+                self.bus_path.clone()
+            };
+        }
+        self.position = match device_panel(link) {
+            Ok(Some(MFDevicePanel::Front)) => CameraPosition::Front,
+            Ok(Some(MFDevicePanel::Back)) => CameraPosition::Back,
+            Ok(None) => CameraPosition::External,
+            Ok(Some(_)) | Err(_) => CameraPosition::Unknown,
+        };
+        if let Ok(Some(degrees)) = device_rotation(link) {
+            self.sensor_orientation = Rotation::from_degrees(degrees).unwrap_or(Rotation::None);
+        }
+    }
+
+    // /sys/class/video4linux/videoN/device points to the USB *interface*, the parent of that is the USB device which has the
+    // idVendor, idProduct and serial attributes.
+    #[cfg(feature = "input-v4l")]
+    pub(crate) fn fill_from_sysfs(&mut self, index: usize) {
+        use std::{fs, path::PathBuf};
+
+        let interface =
+            match fs::canonicalize(format!("/sys/class/video4linux/video{}/device", index)) {
+                Ok(path) => path,
+                Err(_) => return,
+            };
+        // every video node of one unit (e.g. the color, depth and IR nodes of a depth camera) hangs off the same device
+        self.group_id = Some(interface.to_string_lossy().to_string());
+        let usb_device: PathBuf = match interface.parent() {
+            Some(parent) if parent.join("idVendor").exists() => parent.to_path_buf(),
+            _ => return,
+        };
+        let read_attr = |name: &str| -> Option<String> {
+            fs::read_to_string(usb_device.join(name))
+                .ok()
+                .map(|attr| attr.trim().to_string())
+                .filter(|attr| !attr.is_empty())
+        };
+        self.vendor_id = read_attr("idVendor").and_then(|vid| u16::from_str_radix(&vid, 16).ok());
+        self.product_id = read_attr("idProduct").and_then(|pid| u16::from_str_radix(&pid, 16).ok());
+        self.serial = read_attr("serial");
+        self.bus_path = Some(usb_device.to_string_lossy().to_string());
+        self.group_id = self.bus_path.clone();
+    }
 }
 
 impl PartialOrd for CameraInfo {
@@ -387,41 +1154,196 @@ impl Display for CameraInfo {
 #[cfg(feature = "input-msmf")]
 impl From<MediaFoundationDeviceDescriptor<'_>> for CameraInfo {
     fn from(dev_desc: MediaFoundationDeviceDescriptor<'_>) -> Self {
-        CameraInfo {
-            human_name: dev_desc.name_as_string(),
-            description: "Media Foundation Device".to_string(),
-            misc: dev_desc.link_as_string(),
-            index: dev_desc.index(),
-        }
+        let link = dev_desc.link_as_string();
+        let mut camera_info = CameraInfo::new(
+            dev_desc.name_as_string(),
+            "Media Foundation Device".to_string(),
+            link.clone(),
+            dev_desc.index(),
+        );
+        camera_info.fill_from_symbolic_link(&link);
+        camera_info.guess_stream_kind(&[]);
+        camera_info
     }
 }
 
-/// The list of known camera controls to the library. <br>
-/// These can control the picture brightness, etc. <br>
-/// Note that not all backends/devices support all these. Run [`supported_camera_controls()`](crate::CaptureBackendTrait::supported_camera_controls) to see which ones can be set.
-#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
-pub enum KnownCameraControls {
-    Brightness,
-    Contrast,
-    Hue,
-    Saturation,
-    Sharpness,
-    Gamma,
-    ColorEnable,
-    WhiteBalance,
-    BacklightComp,
-    Gain,
-    Pan,
-    Tilt,
-    Roll,
-    Zoom,
-    Exposure,
+/// A camera's [`CameraInfo`] together with every [`CameraFormat`] and [`CameraControl`] it supports. See [`query_capabilities()`](crate::query_capabilities) and [`probe()`](crate::probe).
+#[derive(Clone, Debug)]
+pub struct DeviceCapabilities {
+    info: CameraInfo,
+    formats: Vec<CameraFormat>,
+    controls: Vec<CameraControl>,
+    error: Option<NokhwaError>,
+}
+
+impl DeviceCapabilities {
+    /// Create a new [`DeviceCapabilities`].
+    #[must_use]
+    pub fn new(info: CameraInfo, formats: Vec<CameraFormat>) -> Self {
+        DeviceCapabilities {
+            info,
+            formats,
+            controls: vec![],
+            error: None,
+        }
+    }
+
+    /// Create a new [`DeviceCapabilities`] for a device that could not be probed. It will have no formats or controls.
+    #[must_use]
+    pub fn new_failed(info: CameraInfo, error: NokhwaError) -> Self {
+        DeviceCapabilities {
+            info,
+            formats: vec![],
+            controls: vec![],
+            error: Some(error),
+        }
+    }
+
+    /// Get a reference to the device info.
+    #[must_use]
+    pub fn info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    /// Set the device info.
+    pub fn set_info(&mut self, info: CameraInfo) {
+        self.info = info;
+    }
+
+    /// Get a reference to the supported formats.
+    #[must_use]
+    pub fn formats(&self) -> &Vec<CameraFormat> {
+        &self.formats
+    }
+
+    /// Get a reference to the supported controls, with their current values.
+    #[must_use]
+    pub fn controls(&self) -> &Vec<CameraControl> {
+        &self.controls
+    }
+
+    /// Set the supported controls.
+    pub fn set_controls(&mut self, controls: Vec<CameraControl>) {
+        self.controls = controls;
+    }
+
+    /// The error that occurred while probing the device's formats, if any.
+    #[must_use]
+    pub fn error(&self) -> Option<&NokhwaError> {
+        self.error.as_ref()
+    }
+}
+
+/// Whether a camera can currently be opened and streamed from. See [`probe_availability()`](crate::probe_availability).
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum CameraAvailability {
+    /// Nothing else is using the camera.
+    Available,
+    /// Another process (or another handle in this process) is using the camera exclusively.
+    Busy,
+    /// The backend cannot tell.
+    Unknown,
+}
+
+impl Display for CameraAvailability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// How [`Camera::frame_raw_with()`](crate::Camera::frame_raw_with) hands out the frame.
+/// - `Copied` - The frame is always an owned `Vec` ([`Cow::Owned`](std::borrow::Cow::Owned)), which stays valid no matter what happens to the stream. This costs a copy on backends that can borrow.
+/// - `Borrowed` - The frame is borrowed straight from the stream's buffer where the backend allows it, and is only valid until the next frame call. Backends that cannot borrow return an owned frame anyway.
+///
+/// Currently, only `Video4Linux` can borrow. Media Foundation, `GStreamer`, UVC, `OpenCV` and `JSCamera` always copy.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum FrameAccess {
+    Copied,
+    Borrowed,
+}
+
+impl Default for FrameAccess {
+    fn default() -> Self {
+        FrameAccess::Borrowed
+    }
+}
+
+impl Display for FrameAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The native handle of a backend, from [`Camera::raw_handle()`](crate::Camera::raw_handle), for operations `nokhwa` does not cover.
+/// - `Video4Linux` - The file descriptor of the device.
+/// - `MediaFoundation` - The `IMFSourceReader`, as a raw COM pointer.
+/// - `GStreamer` - The pipeline, as a raw `GstElement` pointer.
+/// - `OpenCv` - The `cv::VideoCapture`, as a raw pointer.
+///
+/// UVC has no handle, the `libuvc` bindings do not expose the `uvc_device_handle_t`. A `JSCamera` is not a [`Camera`](crate::Camera), its handle is the browser's `MediaStream`, from `JSCamera::media_stream()`.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RawHandle {
+    Video4Linux(std::os::raw::c_int),
+    MediaFoundation(*mut std::ffi::c_void),
+    GStreamer(*mut std::ffi::c_void),
+    OpenCv(*mut std::ffi::c_void),
+}
+
+/// What [`Camera::frame()`](crate::Camera::frame) does when a frame is corrupt and fails to decode, which USB cameras sending MJPEG do now and then under load.
+/// - `Error` - Return the decoding error.
+/// - `Skip` - Drop the frame and capture the next one, up to [`MAX_SKIPPED_CORRUPT_FRAMES`] times in a row before returning the error.
+/// - `RepeatLast` - Return the last frame that decoded fine again, or the error if there is none yet. This keeps a copy of every frame.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum CorruptFrameHandling {
+    Error,
+    Skip,
+    RepeatLast,
+}
+
+/// How many corrupt frames in a row [`CorruptFrameHandling::Skip`] skips before giving up.
+pub const MAX_SKIPPED_CORRUPT_FRAMES: usize = 5;
+
+impl Default for CorruptFrameHandling {
+    fn default() -> Self {
+        CorruptFrameHandling::Error
+    }
+}
+
+impl Display for CorruptFrameHandling {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The list of known camera controls to the library. <br>
+/// These can control the picture brightness, etc. <br>
+/// Note that not all backends/devices support all these. Run [`supported_camera_controls()`](crate::CaptureBackendTrait::supported_camera_controls) to see which ones can be set.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum KnownCameraControls {
+    Brightness,
+    Contrast,
+    Hue,
+    Saturation,
+    Sharpness,
+    Gamma,
+    ColorEnable,
+    WhiteBalance,
+    BacklightComp,
+    Gain,
+    Pan,
+    Tilt,
+    Roll,
+    Zoom,
+    Exposure,
     Iris,
     Focus,
+    /// The anti-flicker (banding) filter. Its values are the ones of [`PowerLineFrequency`].
+    PowerLineFrequency,
 }
 
 #[must_use]
-pub fn all_known_camera_controls() -> [KnownCameraControls; 17] {
+pub fn all_known_camera_controls() -> [KnownCameraControls; 18] {
     [
         KnownCameraControls::Brightness,
         KnownCameraControls::Contrast,
@@ -440,6 +1362,7 @@ pub fn all_known_camera_controls() -> [KnownCameraControls; 17] {
         KnownCameraControls::Exposure,
         KnownCameraControls::Iris,
         KnownCameraControls::Focus,
+        KnownCameraControls::PowerLineFrequency,
     ]
 }
 
@@ -449,6 +1372,23 @@ impl Display for KnownCameraControls {
     }
 }
 
+impl FromStr for KnownCameraControls {
+    type Err = NokhwaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_known_camera_controls()
+            .iter()
+            .find(|control| control.to_string() == s)
+        {
+            Some(control) => Ok(*control),
+            None => Err(NokhwaError::StructureError {
+                structure: "KnownCameraControls".to_string(),
+                error: format!("Unknown control {}", s),
+            }),
+        }
+    }
+}
+
 #[cfg(feature = "input-msmf")]
 impl From<MediaFoundationControls> for KnownCameraControls {
     fn from(mf_c: MediaFoundationControls) -> Self {
@@ -470,6 +1410,7 @@ impl From<MediaFoundationControls> for KnownCameraControls {
             MediaFoundationControls::Exposure => KnownCameraControls::Exposure,
             MediaFoundationControls::Iris => KnownCameraControls::Iris,
             MediaFoundationControls::Focus => KnownCameraControls::Focus,
+            MediaFoundationControls::PowerLineFrequency => KnownCameraControls::PowerLineFrequency,
         }
     }
 }
@@ -502,6 +1443,7 @@ impl TryFrom<Description> for KnownCameraControls {
             9_963_793 => KnownCameraControls::Exposure,
             10_094_866 => KnownCameraControls::Iris,
             10_094_859 => KnownCameraControls::Focus,
+            9_963_800 => KnownCameraControls::PowerLineFrequency,
             _ => {
                 return Err(NokhwaError::NotImplementedError(
                     "Control not implemented!".to_string(),
@@ -511,6 +1453,58 @@ impl TryFrom<Description> for KnownCameraControls {
     }
 }
 
+/// The values of [`KnownCameraControls::PowerLineFrequency`], which filters out the flicker (banding) of lights running on mains power. <br>
+/// Use the frequency of the local mains: 50Hz in most of Europe, Africa, Asia and Australia, 60Hz in most of the Americas.
+///
+/// V4L2, UVC and Media Foundation all use the same values, so these convert to and from the control's value.
+#[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum PowerLineFrequency {
+    Disabled,
+    Hz50,
+    Hz60,
+    /// Not supported by older cameras (UVC 1.1).
+    Auto,
+}
+
+impl Display for PowerLineFrequency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerLineFrequency::Disabled => write!(f, "Disabled"),
+            PowerLineFrequency::Hz50 => write!(f, "50Hz"),
+            PowerLineFrequency::Hz60 => write!(f, "60Hz"),
+            PowerLineFrequency::Auto => write!(f, "Auto"),
+        }
+    }
+}
+
+impl From<PowerLineFrequency> for i32 {
+    fn from(frequency: PowerLineFrequency) -> Self {
+        match frequency {
+            PowerLineFrequency::Disabled => 0,
+            PowerLineFrequency::Hz50 => 1,
+            PowerLineFrequency::Hz60 => 2,
+            PowerLineFrequency::Auto => 3,
+        }
+    }
+}
+
+impl TryFrom<i32> for PowerLineFrequency {
+    type Error = NokhwaError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PowerLineFrequency::Disabled),
+            1 => Ok(PowerLineFrequency::Hz50),
+            2 => Ok(PowerLineFrequency::Hz60),
+            3 => Ok(PowerLineFrequency::Auto),
+            _ => Err(NokhwaError::StructureError {
+                structure: "PowerLineFrequency".to_string(),
+                error: format!("Unknown value {}", value),
+            }),
+        }
+    }
+}
+
 /// This tells you weather a [`KnownCameraControls`] is automatically managed by the OS/Driver
 /// or manually managed by you, the programmer.
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
@@ -537,7 +1531,7 @@ pub struct CameraControl {
 impl CameraControl {
     /// Creates a new [`CameraControl`]
     /// # Errors
-    /// If the `value` is below `min`, above `max`, or is not divisible by `step`, this will error
+    /// If the `value` is below `min`, above `max`, or is not a whole number of `step`s from `min`, this will error. `min` and `max` themselves are valid.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         control: KnownCameraControls,
@@ -549,24 +1543,7 @@ impl CameraControl {
         flag: KnownCameraControlFlag,
         active: bool,
     ) -> Result<Self, NokhwaError> {
-        if value >= maximum {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too large".to_string(),
-            });
-        }
-        if value <= minimum {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too low".to_string(),
-            });
-        }
-        if value % step != 0 {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Not aligned with step".to_string(),
-            });
-        }
+        validate_control_value(value, minimum, maximum, step)?;
 
         Ok(CameraControl {
             control,
@@ -606,26 +1583,9 @@ impl CameraControl {
 
     /// Sets the value of this [`CameraControl`]
     /// # Errors
-    /// If the `value` is below `min`, above `max`, or is not divisible by `step`, this will error
+    /// If the `value` is below `min`, above `max`, or is not a whole number of `step`s from `min`, this will error. `min` and `max` themselves are valid.
     pub fn set_value(&mut self, value: i32) -> Result<(), NokhwaError> {
-        if value >= self.maximum_value() {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too large".to_string(),
-            });
-        }
-        if value <= self.minimum_value() {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too low".to_string(),
-            });
-        }
-        if value % self.step() != 0 {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Not aligned with step".to_string(),
-            });
-        }
+        validate_control_value(value, self.min, self.max, self.step)?;
 
         self.value = value;
         Ok(())
@@ -633,26 +1593,9 @@ impl CameraControl {
 
     /// Creates a new [`CameraControl`] but with `value`
     /// # Errors
-    /// If the `value` is below `min`, above `max`, or is not divisible by `step`, this will error
+    /// If the `value` is below `min`, above `max`, or is not a whole number of `step`s from `min`, this will error. `min` and `max` themselves are valid.
     pub fn with_value(self, value: i32) -> Result<Self, NokhwaError> {
-        if value >= self.maximum_value() {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too large".to_string(),
-            });
-        }
-        if value <= self.minimum_value() {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Value too low".to_string(),
-            });
-        }
-        if value % self.step() != 0 {
-            return Err(NokhwaError::StructureError {
-                structure: "CameraControl".to_string(),
-                error: "Not aligned with step".to_string(),
-            });
-        }
+        validate_control_value(value, self.min, self.max, self.step)?;
 
         Ok(CameraControl {
             control: self.control(),
@@ -667,7 +1610,7 @@ impl CameraControl {
     }
 
     /// Gets the step value of this [`CameraControl`]
-    /// Note that `value` must be divisible by `step`
+    /// Note that `value` must be a whole number of `step`s from the minimum, unless `step` is 0
     #[must_use]
     pub fn step(&self) -> i32 {
         self.step
@@ -693,15 +1636,54 @@ impl CameraControl {
         self.active
     }
 
-    /// Returns a list of i32s that are valid to be set.
-    #[allow(clippy::cast_sign_loss)]
+    /// Returns a list of i32s that are valid to be set. A `step` of 0 is treated as 1.
     #[must_use]
     pub fn valid_values(&self) -> Vec<i32> {
+        let step = usize::try_from(self.step().unsigned_abs()).unwrap_or(usize::MAX);
         (self.minimum_value()..=self.maximum_value())
-            .step_by(self.step() as usize)
-            .into_iter()
+            .step_by(step.max(1))
             .collect()
     }
+
+    /// Clamps `value` to between the minimum and maximum (inclusive), and rounds it to the nearest step from the minimum, never past the maximum.
+    /// The result can always be passed to [`set_value()`](CameraControl::set_value).
+    #[must_use]
+    pub fn clamp_to_valid(&self, value: i32) -> i32 {
+        let value = value.max(self.min).min(self.max);
+        let step = i64::from(self.step).abs();
+        if step <= 1 {
+            return value;
+        }
+        let (min, max) = (i64::from(self.min), i64::from(self.max));
+        let mut steps = (i64::from(value) - min + step / 2) / step;
+        // rounding up can go past a maximum that is not a whole step from the minimum
+        if min + steps * step > max {
+            steps -= 1;
+        }
+        i32::try_from(min + steps * step).unwrap_or(value)
+    }
+}
+
+// `min` and `max` are inclusive, and `value` has to be a whole number of steps from `min`. Drivers report a step of 0 for controls that take any value
+fn validate_control_value(
+    value: i32,
+    minimum: i32,
+    maximum: i32,
+    step: i32,
+) -> Result<(), NokhwaError> {
+    let error = if value > maximum {
+        "Value too large"
+    } else if value < minimum {
+        "Value too low"
+    } else if step != 0 && (i64::from(value) - i64::from(minimum)) % i64::from(step) != 0 {
+        "Not aligned with step"
+    } else {
+        return Ok(());
+    };
+    Err(NokhwaError::StructureError {
+        structure: "CameraControl".to_string(),
+        error: error.to_string(),
+    })
 }
 
 impl PartialOrd for CameraControl {
@@ -725,7 +1707,9 @@ impl Ord for CameraControl {
 /// - `MediaFoundation` - Microsoft Media Foundation, Windows only,
 /// - `OpenCV` - Uses `OpenCV` to capture. Platform agnostic.
 /// - `GStreamer` - Uses `GStreamer` RTP to capture. Platform agnostic.
+/// - `Custom` - A backend that is not part of `nokhwa` (e.g. a mock), passed to [`Camera::from_backend()`](crate::Camera::from_backend). It can not be opened by `nokhwa` itself.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum CaptureAPIBackend {
     Auto,
     AVFoundation,
@@ -734,6 +1718,7 @@ pub enum CaptureAPIBackend {
     MediaFoundation,
     OpenCv,
     GStreamer,
+    Custom,
 }
 
 impl Display for CaptureAPIBackend {
@@ -744,43 +1729,81 @@ impl Display for CaptureAPIBackend {
 }
 
 /// Converts a MJPEG stream of [u8] into a Vec<u8> of RGB888. (R,G,B,R,G,B,...)
+///
+/// Grayscale and CMYK JPEGs are converted to RGB too. CMYK is assumed to be stored inverted, as Adobe software (and so most CMYK JPEGs) does.
 /// # Errors
-/// If `mozjpeg` fails to read scanlines or setup the decompressor, this will error.
-/// # Safety
-/// This function uses `unsafe`. The caller must ensure that:
-/// - The input data is of the right size, does not exceed bounds, and/or the final size matches with the initial size.
+/// If `mozjpeg` fails to read scanlines or setup the decompressor, or the frame is corrupt, this will error.
 pub fn mjpeg_to_rgb888(data: &[u8]) -> Result<Vec<u8>, NokhwaError> {
-    let mut mozjpeg_decomp = match Decompress::new_mem(data) {
-        Ok(decomp) => match decomp.rgb() {
-            Ok(decompresser) => decompresser,
-            Err(why) => {
-                return Err(NokhwaError::ProcessFrameError {
-                    src: FrameFormat::MJPEG,
-                    destination: "RGB888".to_string(),
-                    error: why.to_string(),
-                })
-            }
-        },
-        Err(why) => {
-            return Err(NokhwaError::ProcessFrameError {
-                src: FrameFormat::MJPEG,
-                destination: "RGB888".to_string(),
-                error: why.to_string(),
-            })
-        }
-    };
-    let decompressed = match mozjpeg_decomp.read_scanlines::<[u8; 3]>() {
-        Some(pixels) => pixels,
-        None => {
-            return Err(NokhwaError::ProcessFrameError {
-                src: FrameFormat::MJPEG,
-                destination: "RGB888".to_string(),
-                error: "Failed to get read readlines into RGB888 pixels!".to_string(),
-            })
-        }
+    let _span = crate::telemetry::decode_span(FrameFormat::MJPEG);
+    let started = Instant::now();
+    // JPEGs with restart markers are decoded in strips, one per thread
+    #[cfg(feature = "rayon")]
+    if let Some(rgb) = crate::parallel::mjpeg_to_rgb888(data) {
+        let rgb = rgb?;
+        crate::telemetry::record_decode(FrameFormat::MJPEG, started);
+        return Ok(rgb);
+    }
+    let decompressed = mjpeg_decode(data)?;
+
+    // `mozjpeg` always allocates the scanlines itself, but the copy we hand out comes from the pool
+    let mut rgb = crate::buffer_pool::take_buffer(decompressed.len() * 3);
+    for pixel in &decompressed {
+        rgb.extend_from_slice(pixel);
+    }
+    crate::telemetry::record_decode(FrameFormat::MJPEG, started);
+    Ok(rgb)
+}
+
+// decodes a whole JPEG into RGB888 pixels.
+// `mozjpeg` panics on fatal libjpeg errors, which corrupt frames (that USB cameras send now and then under load) cause, so those are caught here.
+pub(crate) fn mjpeg_decode(data: &[u8]) -> Result<Vec<[u8; 3]>, NokhwaError> {
+    match std::panic::catch_unwind(|| mjpeg_decode_unwinding(data)) {
+        Ok(decoded) => decoded,
+        Err(_) => Err(mjpeg_error(
+            "libjpeg failed to decode the frame, it is corrupt",
+        )),
+    }
+}
+
+fn mjpeg_decode_unwinding(data: &[u8]) -> Result<Vec<[u8; 3]>, NokhwaError> {
+    let decompress = Decompress::new_mem(data).map_err(mjpeg_error)?;
+    let (width, height) = decompress.size();
+    let pixels = match decompress.image().map_err(mjpeg_error)? {
+        JpegFormat::RGB(mut started) => started.read_scanlines::<[u8; 3]>(),
+        JpegFormat::Gray(mut started) => started
+            .read_scanlines::<u8>()
+            .map(|luma| luma.into_iter().map(|luma| [luma, luma, luma]).collect()),
+        JpegFormat::CMYK(mut started) => started
+            .read_scanlines::<[u8; 4]>()
+            .map(|cmyk| cmyk.into_iter().map(inverted_cmyk_to_rgb).collect()),
     };
+    match pixels {
+        Some(pixels) if pixels.len() == width * height => Ok(pixels),
+        Some(pixels) => Err(mjpeg_error(format!(
+            "Decoded {} pixels, but the JPEG is {}x{}",
+            pixels.len(),
+            width,
+            height
+        ))),
+        None => Err(mjpeg_error(
+            "Failed to get read readlines into RGB888 pixels!",
+        )),
+    }
+}
+
+// Adobe CMYK is inverted, 0 is full ink and 255 none, so multiplying with the black gives the RGB straight away
+fn inverted_cmyk_to_rgb([c, m, y, k]: [u8; 4]) -> [u8; 3] {
+    #[allow(clippy::cast_possible_truncation)]
+    let ink = |value: u8| (u16::from(value) * u16::from(k) / 255) as u8;
+    [ink(c), ink(m), ink(y)]
+}
 
-    Ok(unsafe { from_raw_parts(decompressed.as_ptr().cast(), decompressed.len() * 3) }.to_vec())
+fn mjpeg_error<E: Display>(why: E) -> NokhwaError {
+    NokhwaError::ProcessFrameError {
+        src: FrameFormat::MJPEG,
+        destination: "RGB888".to_string(),
+        error: why.to_string(),
+    }
 }
 
 // For those maintaining this, I recommend you read: https://docs.microsoft.com/en-us/windows/win32/medfound/recommended-8-bit-yuv-formats-for-video-rendering#yuy2
@@ -791,116 +1814,1088 @@ pub fn mjpeg_to_rgb888(data: &[u8]) -> Result<Vec<u8>, NokhwaError> {
 // then it is converted to 6 bytes (2 pixels) of RGB888
 /// Converts a YUYV 4:2:2 datastream to a RGB888 Stream. [For further reading](https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB)
 /// # Errors
-/// This may error when the data stream size is not divisible by 4, a i32 -> u8 conversion fails, or it fails to read from a certain index.
+/// This will error when the data stream size is not divisible by 4.
 pub fn yuyv422_to_rgb888(data: &[u8]) -> Result<Vec<u8>, NokhwaError> {
-    let mut rgb_vec: Vec<u8> = vec![];
+    yuyv422_to_rgb888_with(data, ColorSpace::default())
+}
+
+/// Converts a YUYV 4:2:2 datastream to a RGB888 Stream, like [`yuyv422_to_rgb888()`], but in `color_space` instead of the BT.601 limited range default.
+/// # Errors
+/// This will error when the data stream size is not divisible by 4.
+pub fn yuyv422_to_rgb888_with(
+    data: &[u8],
+    color_space: ColorSpace,
+) -> Result<Vec<u8>, NokhwaError> {
+    let _span = crate::telemetry::decode_span(FrameFormat::YUYV);
+    let started = Instant::now();
+    let mut rgb_vec: Vec<u8> = crate::buffer_pool::take_buffer(data.len() / 2 * 3);
     if data.len() % 4 == 0 {
-        for px_idx in (0..data.len()).step_by(4) {
-            let y1 = match data.get(px_idx) {
-                Some(px) => match i32::try_from(*px) {
-                    Ok(i) => i,
-                    Err(why) => {
-                        return Err(NokhwaError::ProcessFrameError { src: FrameFormat::YUYV, destination: "RGB888".to_string(), error: format!("Failed to convert byte at {} to a i32 because {}, This shouldn't happen!", px_idx, why.to_string()) });
-                    }
-                },
-                None => {
-                    return Err(NokhwaError::ProcessFrameError {
-                        src: FrameFormat::YUYV,
-                        destination: "RGB888".to_string(),
-                        error: format!(
-                            "Failed to get bytes at {}, this is probably a bug, please report!",
-                            px_idx
-                        ),
-                    });
-                }
-            };
+        let lut = YuvLut::new(color_space);
+        rgb_vec.resize(data.len() / 2 * 3, 0);
+        #[cfg(feature = "rayon")]
+        crate::parallel::yuyv422_to_rgb888(data, &mut rgb_vec, &lut);
+        #[cfg(not(feature = "rayon"))]
+        yuyv422_pairs_to_rgb888(data, &mut rgb_vec, &lut);
+        crate::telemetry::record_decode(FrameFormat::YUYV, started);
+        Ok(rgb_vec)
+    } else {
+        Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: "Assertion failure, the YUV stream isn't 4:2:2! (wrong number of bytes)"
+                .to_string(),
+        })
+    }
+}
 
-            let u = match data.get(px_idx + 1) {
-                Some(px) => match i32::try_from(*px) {
-                    Ok(i) => i,
-                    Err(why) => {
-                        return Err(NokhwaError::ProcessFrameError { src: FrameFormat::YUYV, destination: "RGB888".to_string(), error: format!("Failed to convert byte at {} to a i32 because {}, This shouldn't happen!", px_idx+1, why.to_string()) });
-                    }
-                },
-                None => {
-                    return Err(NokhwaError::ProcessFrameError {
-                        src: FrameFormat::YUYV,
-                        destination: "RGB888".to_string(),
-                        error: format!(
-                            "Failed to get bytes at {}, this is probably a bug, please report!",
-                            px_idx + 1
-                        ),
-                    });
-                }
-            };
+/// Converts a YUYV 4:2:2 frame of `resolution` to RGB888 in `color_space`, where each row starts `stride` bytes after the one before it (e.g. V4L2's `bytesperline`). The padding is skipped over, without copying the frame.
+/// # Errors
+/// This will error if the width is 0 or odd, `stride` is smaller than a row, or `data` is too small.
+pub fn yuyv422_to_rgb888_strided(
+    data: &[u8],
+    resolution: Resolution,
+    stride: usize,
+    color_space: ColorSpace,
+) -> Result<Vec<u8>, NokhwaError> {
+    let row_bytes = FrameFormat::YUYV
+        .row_bytes(resolution.width())
+        .unwrap_or_default();
+    let rows = FrameFormat::YUYV.rows(resolution.height());
+    if stride == row_bytes {
+        let frame = row_bytes
+            .checked_mul(rows)
+            .and_then(|size| data.get(..size));
+        return yuyv422_to_rgb888_with(frame.unwrap_or(data), color_space);
+    }
+    if resolution.width() == 0 || resolution.width() % 2 != 0 || stride < row_bytes {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: format!(
+                "Stride {} does not fit rows of width {}",
+                stride,
+                resolution.width()
+            ),
+        });
+    }
+    let needed = strided_frame_bytes(rows, stride, row_bytes);
+    if needed.map_or(true, |needed| data.len() < needed) {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: frame_size_error(data.len(), needed),
+        });
+    }
+
+    let _span = crate::telemetry::decode_span(FrameFormat::YUYV);
+    let started = Instant::now();
+    let lut = YuvLut::new(color_space);
+    let mut rgb_vec = crate::buffer_pool::take_buffer(rows * row_bytes / 2 * 3);
+    rgb_vec.resize(rows * row_bytes / 2 * 3, 0);
+    for (row, rgb_row) in data
+        .chunks(stride)
+        .zip(rgb_vec.chunks_exact_mut(row_bytes / 2 * 3))
+    {
+        yuyv422_pairs_to_rgb888(&row[..row_bytes], rgb_row, &lut);
+    }
+    crate::telemetry::record_decode(FrameFormat::YUYV, started);
+    Ok(rgb_vec)
+}
 
-            let y2 = match data.get(px_idx + 2) {
-                Some(px) => match i32::try_from(*px) {
-                    Ok(i) => i,
-                    Err(why) => {
-                        return Err(NokhwaError::ProcessFrameError { src: FrameFormat::YUYV, destination: "RGB888".to_string(), error: format!("Failed to convert byte at {} to a i32 because {}, This shouldn't happen!", px_idx+2, why.to_string()) });
-                    }
-                },
-                None => {
-                    return Err(NokhwaError::ProcessFrameError {
-                        src: FrameFormat::YUYV,
-                        destination: "RGB888".to_string(),
-                        error: format!(
-                            "Failed to get bytes at {}, this is probably a bug, please report!",
-                            px_idx + 2
-                        ),
-                    });
-                }
-            };
+/// Converts a single row (or any run of whole pixel pairs) of YUYV 4:2:2 into `out` as RGB888, without allocating.
+/// `out` must be exactly `yuyv_row.len() / 2 * 3` bytes long.
+/// # Errors
+/// This will error if `yuyv_row` is not divisible by 4 or `out` is the wrong size.
+pub fn yuyv422_row_to_rgb888(yuyv_row: &[u8], out: &mut [u8]) -> Result<(), NokhwaError> {
+    yuyv422_row_to_rgb888_with(yuyv_row, out, ColorSpace::default())
+}
 
-            let v = match data.get(px_idx + 3) {
-                Some(px) => match i32::try_from(*px) {
-                    Ok(i) => i,
-                    Err(why) => {
-                        return Err(NokhwaError::ProcessFrameError { src: FrameFormat::YUYV, destination: "RGB888".to_string(), error: format!("Failed to convert byte at {} to a i32 because {}, This shouldn't happen!", px_idx+3, why.to_string()) });
+/// Converts a row of YUYV 4:2:2 into `out` as RGB888, like [`yuyv422_row_to_rgb888()`], but in `color_space`.
+/// # Errors
+/// This will error if `yuyv_row` is not divisible by 4 or `out` is the wrong size.
+pub fn yuyv422_row_to_rgb888_with(
+    yuyv_row: &[u8],
+    out: &mut [u8],
+    color_space: ColorSpace,
+) -> Result<(), NokhwaError> {
+    yuyv422_row_to_rgb888_lut(yuyv_row, out, &YuvLut::new(color_space))
+}
+
+// so row by row conversion only makes the tables once
+fn yuyv422_row_to_rgb888_lut(
+    yuyv_row: &[u8],
+    out: &mut [u8],
+    lut: &YuvLut,
+) -> Result<(), NokhwaError> {
+    if yuyv_row.len() % 4 != 0 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: "Assertion failure, the YUV stream isn't 4:2:2! (wrong number of bytes)"
+                .to_string(),
+        });
+    }
+    if out.len() != yuyv_row.len() / 2 * 3 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: format!(
+                "Output buffer is {} bytes, expected {}",
+                out.len(),
+                yuyv_row.len() / 2 * 3
+            ),
+        });
+    }
+
+    yuyv422_pairs_to_rgb888(yuyv_row, out, lut);
+    Ok(())
+}
+
+// converts whole pixel pairs of `yuyv` into `rgb`, which has to be big enough to hold them
+pub(crate) fn yuyv422_pairs_to_rgb888(yuyv: &[u8], rgb: &mut [u8], lut: &YuvLut) {
+    for (yuyv, rgb) in yuyv.chunks_exact(4).zip(rgb.chunks_exact_mut(6)) {
+        rgb[0..3].copy_from_slice(&lut.yuv_to_rgb888(yuyv[0], yuyv[1], yuyv[3]));
+        rgb[3..6].copy_from_slice(&lut.yuv_to_rgb888(yuyv[2], yuyv[1], yuyv[3]));
+    }
+}
+
+// converts whole pixel pairs of `yuyv` into `rgb565`, which has to be big enough to hold them
+pub(crate) fn yuyv422_pairs_to_rgb565(yuyv: &[u8], rgb565: &mut [u16], lut: &YuvLut) {
+    for (yuyv, rgb565) in yuyv.chunks_exact(4).zip(rgb565.chunks_exact_mut(2)) {
+        let [r1, g1, b1] = lut.yuv_to_rgb888(yuyv[0], yuyv[1], yuyv[3]);
+        let [r2, g2, b2] = lut.yuv_to_rgb888(yuyv[2], yuyv[1], yuyv[3]);
+        rgb565[0] = rgb888_to_rgb565(r1, g1, b1);
+        rgb565[1] = rgb888_to_rgb565(r2, g2, b2);
+    }
+}
+
+/// An iterator that converts a YUYV 4:2:2 frame to RGB888 one row at a time, top to bottom. Each item is one row of `width * 3` bytes. <br>
+/// Only a single row is held in memory at a time, which allows bounded memory usage for very large frames, and processing a row while the next is converted.
+/// Use [`next_into()`](Yuyv422Rows::next_into) to reuse a buffer instead of allocating a new one per row.
+///
+/// This is created by [`yuyv422_rows()`] and [`yuyv422_rows_with()`].
+#[derive(Clone, Debug)]
+pub struct Yuyv422Rows<'a> {
+    rows: std::slice::ChunksExact<'a, u8>,
+    lut: YuvLut,
+}
+
+impl Yuyv422Rows<'_> {
+    /// Converts the next row into `out`, which must be `width * 3` bytes long. Returns `None` if there are no rows left.
+    /// # Errors
+    /// This will error if `out` is the wrong size.
+    pub fn next_into(&mut self, out: &mut [u8]) -> Option<Result<(), NokhwaError>> {
+        self.rows
+            .next()
+            .map(|row| yuyv422_row_to_rgb888_lut(row, out, &self.lut))
+    }
+}
+
+impl Iterator for Yuyv422Rows<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next().map(|row| {
+            let mut rgb_row = vec![0_u8; row.len() / 2 * 3];
+            // the row size was checked by `yuyv422_rows()`, so this cannot fail
+            let _ = yuyv422_row_to_rgb888_lut(row, &mut rgb_row, &self.lut);
+            rgb_row
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Yuyv422Rows<'_> {}
+
+/// Creates a [`Yuyv422Rows`] iterator, which converts the YUYV 4:2:2 frame `data` of `width` pixels per row to RGB888 row by row.
+/// # Errors
+/// This will error if `width` is 0 or odd, or `data` is not made up of whole rows.
+pub fn yuyv422_rows(data: &[u8], width: u32) -> Result<Yuyv422Rows<'_>, NokhwaError> {
+    yuyv422_rows_with(data, width, ColorSpace::default())
+}
+
+/// Creates a [`Yuyv422Rows`] iterator like [`yuyv422_rows()`], that converts in `color_space`.
+/// # Errors
+/// This will error if `width` is 0 or odd, or `data` is not made up of whole rows.
+pub fn yuyv422_rows_with(
+    data: &[u8],
+    width: u32,
+    color_space: ColorSpace,
+) -> Result<Yuyv422Rows<'_>, NokhwaError> {
+    let row_size = width as usize * 2;
+    if width == 0 || width % 2 != 0 || data.len() % row_size != 0 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB888".to_string(),
+            error: format!(
+                "{} bytes is not a whole number of rows of width {}",
+                data.len(),
+                width
+            ),
+        });
+    }
+    Ok(Yuyv422Rows {
+        rows: data.chunks_exact(row_size),
+        lut: YuvLut::new(color_space),
+    })
+}
+
+/// Demosaics a Bayer frame (see [`FrameFormat`]) of `resolution` into RGB888, using bilinear interpolation: the two colors a pixel lacks are the average of its neighbours of that color.
+/// 10 and 12 bit samples are scaled down to 8 bits.
+/// # Errors
+/// If `format` is not a Bayer format, the resolution is smaller than 2x2 or `data` is too small for it, this will error.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_wrap)]
+#[allow(clippy::cast_sign_loss)]
+pub fn debayer(
+    data: &[u8],
+    resolution: Resolution,
+    format: FrameFormat,
+) -> Result<Vec<u8>, NokhwaError> {
+    let (pattern, bits) = match format {
+        FrameFormat::Bayer8(pattern) => (pattern, 8_u32),
+        FrameFormat::Bayer10(pattern) => (pattern, 10),
+        FrameFormat::Bayer12(pattern) => (pattern, 12),
+        FrameFormat::MJPEG
+        | FrameFormat::YUYV
+        | FrameFormat::Y16
+        | FrameFormat::Z16
+        | FrameFormat::P010
+        | FrameFormat::H264
+        | FrameFormat::H265 => {
+            return Err(NokhwaError::ProcessFrameError {
+                src: format,
+                destination: "RGB888".to_string(),
+                error: "Not a Bayer format".to_string(),
+            })
+        }
+    };
+    let bytes_per_sample = if bits == 8 { 1 } else { 2 };
+    let width = resolution.width() as usize;
+    let height = resolution.height() as usize;
+    if width < 2 || height < 2 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: format,
+            destination: "RGB888".to_string(),
+            error: format!("Cannot demosaic a {} frame", resolution),
+        });
+    }
+    let needed = frame_bytes(resolution, bytes_per_sample);
+    // the output is 3 bytes per pixel, which has to fit as well
+    if frame_bytes(resolution, 3).is_none() || needed.map_or(true, |needed| data.len() < needed) {
+        return Err(NokhwaError::ProcessFrameError {
+            src: format,
+            destination: "RGB888".to_string(),
+            error: frame_size_error(data.len(), needed),
+        });
+    }
+
+    let _span = crate::telemetry::decode_span(format);
+    let started = Instant::now();
+    let sample = |x: usize, y: usize| -> u16 {
+        let index = y * width + x;
+        if bytes_per_sample == 1 {
+            u16::from(data[index])
+        } else {
+            (u16::from_le_bytes([data[index * 2], data[index * 2 + 1]]) >> (bits - 8)).min(255)
+        }
+    };
+    // neighbours past the edge are mirrored, which keeps them the same color as the one they stand in for
+    let mirror = |position: usize, offset: isize, length: usize| -> usize {
+        match position as isize + offset {
+            -1 => 1,
+            p if p as usize == length => length - 2,
+            p => p as usize,
+        }
+    };
+
+    let mut rgb = crate::buffer_pool::take_buffer(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let own = pattern.channel(x, y);
+            let mut sums = [0_u16; 3];
+            let mut counts = [0_u16; 3];
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let nx = mirror(x, dx, width);
+                    let ny = mirror(y, dy, height);
+                    let channel = pattern.channel(nx, ny);
+                    if channel != own {
+                        sums[channel] += sample(nx, ny);
+                        counts[channel] += 1;
                     }
-                },
-                None => {
-                    return Err(NokhwaError::ProcessFrameError {
-                        src: FrameFormat::YUYV,
-                        destination: "RGB888".to_string(),
-                        error: format!(
-                            "Failed to get bytes at {}, this is probably a bug, please report!",
-                            px_idx + 3
-                        ),
-                    });
                 }
-            };
+            }
+            sums[own] = sample(x, y);
+            counts[own] = 1;
+            for (sum, count) in sums.iter().zip(counts.iter()) {
+                rgb.push((sum / count) as u8);
+            }
+        }
+    }
+    crate::telemetry::record_decode(format, started);
+    Ok(rgb)
+}
+
+/// Converts a Y16 frame (one little endian `u16` per pixel) into 16 bit luma samples.
+/// # Errors
+/// This will error if the data stream size is odd.
+pub fn y16_to_luma16(data: &[u8]) -> Result<Vec<u16>, NokhwaError> {
+    le_u16_samples(data, FrameFormat::Y16, "Luma16")
+}
 
-            let pixel1 = yuyv444_to_rgb888(y1, u, v);
-            let pixel2 = yuyv444_to_rgb888(y2, u, v);
-            rgb_vec.append(&mut pixel1.to_vec());
-            rgb_vec.append(&mut pixel2.to_vec());
+/// Converts a Z16 frame (one little endian `u16` per pixel) into depth samples, in the units of the device (usually millimeters). `0` means the depth is unknown.
+/// # Errors
+/// This will error if the data stream size is odd.
+pub fn z16_to_depth(data: &[u8]) -> Result<Vec<u16>, NokhwaError> {
+    le_u16_samples(data, FrameFormat::Z16, "Depth16")
+}
+
+/// Converts a Z16 frame into gray RGB888 for display. Every frame is scaled so its farthest point is white, pixels with unknown depth are black.
+/// # Errors
+/// This will error if the data stream size is odd.
+pub fn z16_to_rgb888(data: &[u8]) -> Result<Vec<u8>, NokhwaError> {
+    let depth = le_u16_samples(data, FrameFormat::Z16, "RGB888")?;
+    let _span = crate::telemetry::decode_span(FrameFormat::Z16);
+    let started = Instant::now();
+    let farthest = u32::from(depth.iter().copied().max().unwrap_or(0).max(1));
+    let mut rgb = crate::buffer_pool::take_buffer(depth.len() * 3);
+    for sample in depth {
+        #[allow(clippy::cast_possible_truncation)]
+        let gray = (u32::from(sample) * 255 / farthest) as u8;
+        rgb.extend_from_slice(&[gray; 3]);
+    }
+    crate::telemetry::record_decode(FrameFormat::Z16, started);
+    Ok(rgb)
+}
+
+// reads `data` as little endian `u16` samples, as used by Y16 and Z16
+fn le_u16_samples(
+    data: &[u8],
+    src: FrameFormat,
+    destination: &str,
+) -> Result<Vec<u16>, NokhwaError> {
+    if data.len() % 2 != 0 {
+        return Err(NokhwaError::ProcessFrameError {
+            src,
+            destination: destination.to_string(),
+            error: format!(
+                "Assertion failure, the {} stream has an odd number of bytes",
+                src
+            ),
+        });
+    }
+    Ok(data
+        .chunks_exact(2)
+        .map(|sample| u16::from_le_bytes([sample[0], sample[1]]))
+        .collect())
+}
+
+/// Converts a Y16 frame into gray RGB888, keeping the most significant 8 bits of every sample.
+/// # Errors
+/// This will error if the data stream size is odd.
+pub fn y16_to_rgb888(data: &[u8]) -> Result<Vec<u8>, NokhwaError> {
+    if data.len() % 2 != 0 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::Y16,
+            destination: "RGB888".to_string(),
+            error: "Assertion failure, the Y16 stream has an odd number of bytes".to_string(),
+        });
+    }
+    let _span = crate::telemetry::decode_span(FrameFormat::Y16);
+    let started = Instant::now();
+    let mut rgb = crate::buffer_pool::take_buffer(data.len() / 2 * 3);
+    for sample in data.chunks_exact(2) {
+        // little endian, so the second byte is the most significant one
+        rgb.extend_from_slice(&[sample[1]; 3]);
+    }
+    crate::telemetry::record_decode(FrameFormat::Y16, started);
+    Ok(rgb)
+}
+
+// checks that `data` is a whole P010 frame of `resolution`, then calls `pixel` with the 16 bit Y, U and V of every pixel, row by row
+fn p010_pixels(
+    data: &[u8],
+    resolution: Resolution,
+    destination: &str,
+    mut pixel: impl FnMut(i32, i32, i32),
+) -> Result<(), NokhwaError> {
+    let width = resolution.width() as usize;
+    let height = resolution.height() as usize;
+    if width % 2 != 0 || height % 2 != 0 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::P010,
+            destination: destination.to_string(),
+            error: format!("P010 needs an even resolution, got {}", resolution),
+        });
+    }
+    let luma_size = frame_bytes(resolution, 2);
+    let needed = luma_size.and_then(|luma_size| luma_size.checked_add(luma_size / 2));
+    let luma_size = match (luma_size, needed) {
+        (Some(luma_size), Some(needed)) if data.len() >= needed => luma_size,
+        _ => {
+            return Err(NokhwaError::ProcessFrameError {
+                src: FrameFormat::P010,
+                destination: destination.to_string(),
+                error: frame_size_error(data.len(), needed),
+            })
         }
-        Ok(rgb_vec)
-    } else {
-        Err(NokhwaError::ProcessFrameError {
+    };
+
+    let sample = |index: usize| i32::from(u16::from_le_bytes([data[index], data[index + 1]]));
+    for y in 0..height {
+        // one U and V pair for every 2x2 block of pixels
+        let chroma_row = luma_size + (y / 2) * width * 2;
+        for x in 0..width {
+            let chroma = chroma_row + (x / 2) * 4;
+            pixel(
+                sample((y * width + x) * 2),
+                sample(chroma),
+                sample(chroma + 2),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Gets the 16 bit luma plane of a P010 frame of `resolution`, dropping the chroma.
+/// # Errors
+/// This will error if the resolution is not even or `data` is too small for it.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+pub fn p010_to_luma16(data: &[u8], resolution: Resolution) -> Result<Vec<u16>, NokhwaError> {
+    let mut luma = Vec::with_capacity(frame_bytes(resolution, 1).unwrap_or_default());
+    p010_pixels(data, resolution, "Luma16", |y, _, _| luma.push(y as u16))?;
+    Ok(luma)
+}
+
+/// Converts a P010 frame of `resolution` into RGB888.
+/// # Errors
+/// This will error if the resolution is not even or `data` is too small for it.
+pub fn p010_to_rgb888(data: &[u8], resolution: Resolution) -> Result<Vec<u8>, NokhwaError> {
+    p010_to_rgb888_with(data, resolution, ColorSpace::default())
+}
+
+/// Converts a P010 frame of `resolution` into RGB888, in `color_space`. P010 is usually BT.709.
+/// # Errors
+/// This will error if the resolution is not even or `data` is too small for it.
+pub fn p010_to_rgb888_with(
+    data: &[u8],
+    resolution: Resolution,
+    color_space: ColorSpace,
+) -> Result<Vec<u8>, NokhwaError> {
+    let _span = crate::telemetry::decode_span(FrameFormat::P010);
+    let started = Instant::now();
+    let mut rgb = crate::buffer_pool::take_buffer(frame_bytes(resolution, 3).unwrap_or_default());
+    p010_pixels(data, resolution, "RGB888", |y, u, v| {
+        rgb.extend_from_slice(&yuyv444_to_rgb888_with(y >> 8, u >> 8, v >> 8, color_space));
+    })?;
+    crate::telemetry::record_decode(FrameFormat::P010, started);
+    Ok(rgb)
+}
+
+/// Converts a P010 frame of `resolution` into 16 bit RGB (R,G,B,R,G,B,...), keeping the full bit depth.
+/// # Errors
+/// This will error if the resolution is not even or `data` is too small for it.
+pub fn p010_to_rgb48(data: &[u8], resolution: Resolution) -> Result<Vec<u16>, NokhwaError> {
+    p010_to_rgb48_with(data, resolution, ColorSpace::default())
+}
+
+/// Converts a P010 frame of `resolution` into 16 bit RGB, in `color_space`.
+/// # Errors
+/// This will error if the resolution is not even or `data` is too small for it.
+pub fn p010_to_rgb48_with(
+    data: &[u8],
+    resolution: Resolution,
+    color_space: ColorSpace,
+) -> Result<Vec<u16>, NokhwaError> {
+    let _span = crate::telemetry::decode_span(FrameFormat::P010);
+    let started = Instant::now();
+    let mut rgb = Vec::with_capacity(frame_bytes(resolution, 3).unwrap_or_default());
+    p010_pixels(data, resolution, "RGB48", |y, u, v| {
+        rgb.extend_from_slice(&yuv444_to_rgb48_with(y, u, v, color_space));
+    })?;
+    crate::telemetry::record_decode(FrameFormat::P010, started);
+    Ok(rgb)
+}
+
+/// Whether an H264 or H265 access unit (in Annex B format) can be decoded on its own, i.e. it has an IDR (H264) or IRAP (H265) picture.
+/// Recordings and streams should start at one. Always `false` for other formats.
+#[must_use]
+pub fn is_keyframe(data: &[u8], format: FrameFormat) -> bool {
+    // the NAL unit header follows every `00 00 01` start code (four byte start codes end in the same three bytes)
+    data.windows(4)
+        .filter(|window| window[..3] == [0, 0, 1])
+        .any(|window| match format {
+            FrameFormat::H264 => window[3] & 0x1f == 5,
+            FrameFormat::H265 => (16..=21).contains(&((window[3] >> 1) & 0x3f)),
+            _ => false,
+        })
+}
+
+// the size of a tightly packed frame, or `None` if it does not fit into a `usize` (which 32 bit targets run into well before `u32::MAX` squared)
+fn frame_bytes(resolution: Resolution, bytes_per_pixel: usize) -> Option<usize> {
+    usize::try_from(resolution.width())
+        .ok()?
+        .checked_mul(usize::try_from(resolution.height()).ok()?)?
+        .checked_mul(bytes_per_pixel)
+}
+
+// the size of a frame of `rows` rows `stride` bytes apart, where the last row does not need to be padded
+fn strided_frame_bytes(rows: usize, stride: usize, row_bytes: usize) -> Option<usize> {
+    rows.saturating_sub(1)
+        .checked_mul(stride)?
+        .checked_add(row_bytes)
+}
+
+fn frame_size_error(size: usize, needed: Option<usize>) -> String {
+    match needed {
+        Some(needed) => format!("Frame is {} bytes, expected {}", size, needed),
+        None => format!("Frame is {} bytes, but the frame size overflows", size),
+    }
+}
+
+// the error every decoder gives for compressed video streams
+pub(crate) fn passthrough_error(format: FrameFormat, destination: &str) -> NokhwaError {
+    NokhwaError::ProcessFrameError {
+        src: format,
+        destination: destination.to_string(),
+        error: "Compressed video streams are not decoded, use frame_raw() instead".to_string(),
+    }
+}
+
+/// Removes the padding from the rows of an uncompressed `data` frame of `format` and `resolution`, where each row starts `stride` bytes after the one before it.
+/// Drivers pad rows for alignment (e.g. V4L2's `bytesperline`), and every converter in `nokhwa` expects tightly packed rows, so pass frames with a [`frame_stride()`](crate::CaptureBackendTrait::frame_stride) through this first.
+///
+/// If the rows are not padded (or `format` is compressed), `data` is borrowed as is. The last row does not need to be padded.
+/// # Errors
+/// If `stride` is smaller than a row, or `data` is too small, this will error.
+pub fn pack_frame_rows(
+    data: &[u8],
+    format: FrameFormat,
+    resolution: Resolution,
+    stride: usize,
+) -> Result<Cow<[u8]>, NokhwaError> {
+    let row_bytes = match format.row_bytes(resolution.width()) {
+        Some(row_bytes) if row_bytes != stride => row_bytes,
+        _ => return Ok(Cow::Borrowed(data)),
+    };
+    let rows = format.rows(resolution.height());
+    if stride < row_bytes {
+        return Err(NokhwaError::ProcessFrameError {
+            src: format,
+            destination: "packed rows".to_string(),
+            error: format!(
+                "Stride {} is smaller than a row of {} bytes",
+                stride, row_bytes
+            ),
+        });
+    }
+    let needed = strided_frame_bytes(rows, stride, row_bytes);
+    if rows == 0 || needed.map_or(true, |needed| data.len() < needed) {
+        return Err(NokhwaError::ProcessFrameError {
+            src: format,
+            destination: "packed rows".to_string(),
+            error: frame_size_error(data.len(), needed),
+        });
+    }
+
+    let mut packed = crate::buffer_pool::take_buffer(rows * row_bytes);
+    for row in data.chunks(stride).take(rows) {
+        packed.extend_from_slice(&row[..row_bytes]);
+    }
+    Ok(Cow::Owned(packed))
+}
+
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into YUYV 4:2:2, in BT.601 limited range ([`ColorSpace::Bt601Limited`]), which is what most programs reading YUYV expect.
+/// Every two pixels share the average of their chroma.
+/// # Errors
+/// This will error if `width` is 0 or odd, or `rgb` is not made up of whole rows.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn rgb888_to_yuyv422(rgb: &[u8], width: u32) -> Result<Vec<u8>, NokhwaError> {
+    let width = width as usize;
+    if width == 0
+        || width % 2 != 0
+        || width
+            .checked_mul(3)
+            .map_or(true, |row| rgb.len() % row != 0)
+    {
+        return Err(NokhwaError::GeneralError(format!(
+            "Could not convert RGB888 to YUYV: {} bytes is not a whole number of rows of (even) width {}",
+            rgb.len(),
+            width
+        )));
+    }
+
+    let luma = |px: &[u8]| {
+        let (r, g, b) = (i32::from(px[0]), i32::from(px[1]), i32::from(px[2]));
+        ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16
+    };
+    let mut yuyv = crate::buffer_pool::take_buffer(rgb.len() / 3 * 2);
+    for pair in rgb.chunks_exact(6) {
+        let (r, g, b) = (
+            i32::from(pair[0]) + i32::from(pair[3]),
+            i32::from(pair[1]) + i32::from(pair[4]),
+            i32::from(pair[2]) + i32::from(pair[5]),
+        );
+        // the sums are of two pixels, hence the extra bit of shift
+        let u = ((-38 * r - 74 * g + 112 * b + 256) >> 9) + 128;
+        let v = ((112 * r - 94 * g - 18 * b + 256) >> 9) + 128;
+        yuyv.extend_from_slice(&[
+            luma(&pair[0..3]) as u8,
+            u.clamp(0, 255) as u8,
+            luma(&pair[3..6]) as u8,
+            v.clamp(0, 255) as u8,
+        ]);
+    }
+    Ok(yuyv)
+}
+
+/// Packs a RGB888 pixel into a RGB565 pixel (5 bits red, 6 bits green, 5 bits blue, red in the most significant bits).
+#[must_use]
+#[inline]
+pub fn rgb888_to_rgb565(r: u8, g: u8, b: u8) -> u16 {
+    (u16::from(r >> 3) << 11) | (u16::from(g >> 2) << 5) | u16::from(b >> 3)
+}
+
+/// Converts a YUYV 4:2:2 datastream directly to RGB565, without an intermediate RGB888 buffer. See [`yuyv422_to_rgb565_into()`].
+/// # Errors
+/// This will error if the data stream size is not divisible by 4.
+pub fn yuyv422_to_rgb565(data: &[u8]) -> Result<Vec<u16>, NokhwaError> {
+    let mut rgb565 = vec![0_u16; data.len() / 2];
+    yuyv422_to_rgb565_into(data, &mut rgb565)?;
+    Ok(rgb565)
+}
+
+/// Converts a YUYV 4:2:2 datastream directly into `out` as RGB565, without allocating. `out` must be `data.len() / 2` pixels long. <br>
+/// The pixels are in native endianness, most SPI displays want big endian (use [`u16::to_be()`]).
+/// # Errors
+/// This will error if the data stream size is not divisible by 4 or `out` is the wrong size.
+pub fn yuyv422_to_rgb565_into(data: &[u8], out: &mut [u16]) -> Result<(), NokhwaError> {
+    yuyv422_to_rgb565_into_with(data, out, ColorSpace::default())
+}
+
+/// Converts a YUYV 4:2:2 datastream directly into `out` as RGB565, like [`yuyv422_to_rgb565_into()`], but in `color_space`.
+/// # Errors
+/// This will error if the data stream size is not divisible by 4 or `out` is the wrong size.
+pub fn yuyv422_to_rgb565_into_with(
+    data: &[u8],
+    out: &mut [u16],
+    color_space: ColorSpace,
+) -> Result<(), NokhwaError> {
+    if data.len() % 4 != 0 {
+        return Err(NokhwaError::ProcessFrameError {
             src: FrameFormat::YUYV,
-            destination: "RGB888".to_string(),
+            destination: "RGB565".to_string(),
             error: "Assertion failure, the YUV stream isn't 4:2:2! (wrong number of bytes)"
                 .to_string(),
-        })
+        });
+    }
+    if out.len() != data.len() / 2 {
+        return Err(NokhwaError::ProcessFrameError {
+            src: FrameFormat::YUYV,
+            destination: "RGB565".to_string(),
+            error: format!(
+                "Output buffer is {} pixels, expected {}",
+                out.len(),
+                data.len() / 2
+            ),
+        });
+    }
+
+    let _span = crate::telemetry::decode_span(FrameFormat::YUYV);
+    let started = Instant::now();
+    let lut = YuvLut::new(color_space);
+    #[cfg(feature = "rayon")]
+    crate::parallel::yuyv422_to_rgb565(data, out, &lut);
+    #[cfg(not(feature = "rayon"))]
+    yuyv422_pairs_to_rgb565(data, out, &lut);
+    crate::telemetry::record_decode(FrameFormat::YUYV, started);
+    Ok(())
+}
+
+/// Converts a MJPEG stream of [u8] into RGB565. The JPEG has to be decoded to RGB888 first, so prefer YUYV where memory is tight.
+/// # Errors
+/// If `mozjpeg` fails to read scanlines or setup the decompressor, or the frame is corrupt, this will error.
+pub fn mjpeg_to_rgb565(data: &[u8]) -> Result<Vec<u16>, NokhwaError> {
+    Ok(mjpeg_to_rgb888(data)?
+        .chunks_exact(3)
+        .map(|px| rgb888_to_rgb565(px[0], px[1], px[2]))
+        .collect())
+}
+
+/// Packs a RGB888 pixel into a RGB555 pixel (the top bit is unused and always 0, then 5 bits each of red, green and blue).
+#[must_use]
+#[inline]
+pub fn rgb888_to_rgb555(r: u8, g: u8, b: u8) -> u16 {
+    (u16::from(r >> 3) << 10) | (u16::from(g >> 3) << 5) | u16::from(b >> 3)
+}
+
+/// The dithering to apply when reducing RGB888 to a 16-bit packed format. Dithering trades banding in smooth gradients for a fine, regular noise.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Dithering {
+    /// Truncate every channel.
+    None,
+    /// Ordered dithering with a 4x4 Bayer matrix. This is cheap, and stable from frame to frame (no flickering).
+    Ordered,
+}
+
+impl Default for Dithering {
+    fn default() -> Self {
+        Dithering::None
+    }
+}
+
+impl Display for Dithering {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Adds the dither `threshold` (0-15) to `channel`, spread over one quantization step of a `bits` wide channel. The result still has to be truncated.
+#[inline]
+fn dither_channel(channel: u8, bits: u8, threshold: u8) -> u8 {
+    let step = 1_u16 << (8 - bits);
+    let offset = u16::from(threshold) * step / 16;
+    #[allow(clippy::cast_possible_truncation)]
+    let dithered = (u16::from(channel) + offset).min(255) as u8;
+    dithered
+}
+
+fn rgb888_to_packed_into(
+    rgb: &[u8],
+    width: u32,
+    out: &mut [u16],
+    dithering: Dithering,
+    bits: [u8; 3],
+    destination: &str,
+    pack: fn(u8, u8, u8) -> u16,
+) -> Result<(), NokhwaError> {
+    let width = width as usize;
+    if width == 0
+        || width
+            .checked_mul(3)
+            .map_or(true, |row| rgb.len() % row != 0)
+    {
+        return Err(NokhwaError::GeneralError(format!(
+            "Could not convert RGB888 to {}: {} bytes is not a whole number of rows of width {}",
+            destination,
+            rgb.len(),
+            width
+        )));
+    }
+    if out.len() != rgb.len() / 3 {
+        return Err(NokhwaError::GeneralError(format!(
+            "Could not convert RGB888 to {}: Output buffer is {} pixels, expected {}",
+            destination,
+            out.len(),
+            rgb.len() / 3
+        )));
+    }
+
+    for (idx, (px, packed)) in rgb.chunks_exact(3).zip(out.iter_mut()).enumerate() {
+        *packed = match dithering {
+            Dithering::None => pack(px[0], px[1], px[2]),
+            Dithering::Ordered => {
+                let threshold = BAYER_4X4[(idx / width) % 4][(idx % width) % 4];
+                pack(
+                    dither_channel(px[0], bits[0], threshold),
+                    dither_channel(px[1], bits[1], threshold),
+                    dither_channel(px[2], bits[2], threshold),
+                )
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into RGB565 pixels (in native endianness). See [`rgb888_to_rgb565()`].
+/// # Errors
+/// This will error if `width` is 0 or `rgb` is not made up of whole rows.
+pub fn rgb888_buffer_to_rgb565(
+    rgb: &[u8],
+    width: u32,
+    dithering: Dithering,
+) -> Result<Vec<u16>, NokhwaError> {
+    let mut out = vec![0_u16; rgb.len() / 3];
+    rgb888_buffer_to_rgb565_into(rgb, width, &mut out, dithering)?;
+    Ok(out)
+}
+
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into `out` as RGB565 pixels (in native endianness), without allocating. `out` must be `rgb.len() / 3` pixels long.
+/// # Errors
+/// This will error if `width` is 0, `rgb` is not made up of whole rows, or `out` is the wrong size.
+pub fn rgb888_buffer_to_rgb565_into(
+    rgb: &[u8],
+    width: u32,
+    out: &mut [u16],
+    dithering: Dithering,
+) -> Result<(), NokhwaError> {
+    rgb888_to_packed_into(
+        rgb,
+        width,
+        out,
+        dithering,
+        [5, 6, 5],
+        "RGB565",
+        rgb888_to_rgb565,
+    )
+}
+
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into RGB555 pixels (in native endianness). See [`rgb888_to_rgb555()`].
+/// # Errors
+/// This will error if `width` is 0 or `rgb` is not made up of whole rows.
+pub fn rgb888_buffer_to_rgb555(
+    rgb: &[u8],
+    width: u32,
+    dithering: Dithering,
+) -> Result<Vec<u16>, NokhwaError> {
+    let mut out = vec![0_u16; rgb.len() / 3];
+    rgb888_buffer_to_rgb555_into(rgb, width, &mut out, dithering)?;
+    Ok(out)
+}
+
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into `out` as RGB555 pixels (in native endianness), without allocating. `out` must be `rgb.len() / 3` pixels long.
+/// # Errors
+/// This will error if `width` is 0, `rgb` is not made up of whole rows, or `out` is the wrong size.
+pub fn rgb888_buffer_to_rgb555_into(
+    rgb: &[u8],
+    width: u32,
+    out: &mut [u16],
+    dithering: Dithering,
+) -> Result<(), NokhwaError> {
+    rgb888_to_packed_into(
+        rgb,
+        width,
+        out,
+        dithering,
+        [5, 5, 5],
+        "RGB555",
+        rgb888_to_rgb555,
+    )
+}
+
+#[cfg(feature = "input-opencv")]
+/// Converts a RGB888 buffer (R,G,B,R,G,B,...) of `width` pixels per row into a `CV_8UC3` `OpenCV` [`Mat`], in `OpenCV`'s usual BGR order.
+/// # Errors
+/// This will error if `width` is 0, `rgb` is not made up of whole rows, or `OpenCV` fails to allocate the [`Mat`].
+pub fn rgb888_buffer_to_bgr_mat(rgb: &[u8], width: u32) -> Result<Mat, NokhwaError> {
+    let width = width as usize;
+    if width == 0 || rgb.len() % (width * 3) != 0 {
+        return Err(NokhwaError::GeneralError(format!(
+            "Could not convert RGB888 to Mat: {} bytes is not made up of rows of {} pixels",
+            rgb.len(),
+            width
+        )));
+    }
+    let (rows, cols) = match (i32::try_from(rgb.len() / (width * 3)), i32::try_from(width)) {
+        (Ok(rows), Ok(cols)) => (rows, cols),
+        _ => {
+            return Err(NokhwaError::GeneralError(
+                "Could not convert RGB888 to Mat: Frame is too large".to_string(),
+            ))
+        }
+    };
+
+    let mut mat = match Mat::new_rows_cols_with_default(rows, cols, CV_8UC3, Scalar::all(0.0)) {
+        Ok(mat) => mat,
+        Err(why) => {
+            return Err(NokhwaError::GeneralError(format!(
+                "Could not create Mat: {}",
+                why
+            )))
+        }
+    };
+    let bgr = match mat.data_bytes_mut() {
+        Ok(bytes) => bytes,
+        Err(why) => {
+            return Err(NokhwaError::GeneralError(format!(
+                "Could not write to Mat: {}",
+                why
+            )))
+        }
+    };
+    for (dst, src) in bgr.chunks_exact_mut(3).zip(rgb.chunks_exact(3)) {
+        dst[0] = src[2];
+        dst[1] = src[1];
+        dst[2] = src[0];
+    }
+    Ok(mat)
+}
+
+/// The `YCbCr` encoding of YUV frames: the matrix (BT.601 for SD, BT.709 for HD) and the range of the values.
+/// Limited (or "TV") range has luma in `16..=235` and chroma in `16..=240`, full (or "PC", "JPEG") range uses all of `0..=255`.
+///
+/// Most webcams are BT.601 limited range, which is the default. Decoding with the wrong one gives slightly off colors and washed out or crushed blacks.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ColorSpace {
+    Bt601Limited,
+    Bt601Full,
+    Bt709Limited,
+    Bt709Full,
+}
+
+impl ColorSpace {
+    // the luma offset and scale, then the V -> R, U -> G, V -> G and U -> B terms, all scaled by 256
+    fn coefficients(self) -> [i32; 6] {
+        match self {
+            ColorSpace::Bt601Limited => [16, 298, 409, 100, 208, 516],
+            ColorSpace::Bt601Full => [0, 256, 359, 88, 183, 454],
+            ColorSpace::Bt709Limited => [16, 298, 459, 55, 136, 541],
+            ColorSpace::Bt709Full => [0, 256, 403, 48, 120, 475],
+        }
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Bt601Limited
+    }
+}
+
+impl Display for ColorSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Precomputed lookup tables for converting 8 bit `YCbCr` in one [`ColorSpace`] to RGB888.
+///
+/// This gives the same results as [`yuyv444_to_rgb888_with()`], but replaces the multiplications and clamping with table lookups, which is roughly twice as fast.
+/// The YUYV converters (e.g. [`yuyv422_to_rgb888()`]) use it internally. Making one is cheap, but reuse it if you convert pixels yourself.
+#[derive(Clone)]
+pub struct YuvLut {
+    color_space: ColorSpace,
+    y: [i32; 256],
+    rv: [i32; 256],
+    gu: [i32; 256],
+    gv: [i32; 256],
+    bu: [i32; 256],
+    clamp: [u8; 1024],
+}
+
+// `clamp` is indexed by the channel value (which is always within -384..640) plus this
+const LUT_CLAMP_OFFSET: i32 = 384;
+
+impl YuvLut {
+    /// Creates the lookup tables for `color_space`.
+    #[must_use]
+    pub fn new(color_space: ColorSpace) -> Self {
+        let [y_offset, y_scale, rv, gu, gv, bu] = color_space.coefficients();
+        let mut lut = YuvLut {
+            color_space,
+            y: [0; 256],
+            rv: [0; 256],
+            gu: [0; 256],
+            gv: [0; 256],
+            bu: [0; 256],
+            clamp: [0; 1024],
+        };
+        for (idx, value) in (0..256).enumerate() {
+            // the rounding term is folded into the luma table
+            lut.y[idx] = (value - y_offset) * y_scale + 128;
+            lut.rv[idx] = rv * (value - 128);
+            lut.gu[idx] = gu * (value - 128);
+            lut.gv[idx] = gv * (value - 128);
+            lut.bu[idx] = bu * (value - 128);
+        }
+        for (idx, clamped) in lut.clamp.iter_mut().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::cast_possible_wrap)]
+            #[allow(clippy::cast_sign_loss)]
+            let value = (idx as i32 - LUT_CLAMP_OFFSET).clamp(0, 255) as u8;
+            *clamped = value;
+        }
+        lut
+    }
+
+    /// The [`ColorSpace`] the tables were made for.
+    #[must_use]
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Convert `YCbCr` 4:4:4 to a RGB888.
+    #[allow(clippy::cast_sign_loss)]
+    #[must_use]
+    #[inline]
+    pub fn yuv_to_rgb888(&self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let c = self.y[usize::from(y)];
+        let (u, v) = (usize::from(u), usize::from(v));
+        // the channel values before clamping stay within -300..560 for every colour space, so the indices are in bounds
+        let r = ((c + self.rv[v]) >> 8) + LUT_CLAMP_OFFSET;
+        let g = ((c - self.gu[u] - self.gv[v]) >> 8) + LUT_CLAMP_OFFSET;
+        let b = ((c + self.bu[u]) >> 8) + LUT_CLAMP_OFFSET;
+        [
+            self.clamp[r as usize],
+            self.clamp[g as usize],
+            self.clamp[b as usize],
+        ]
+    }
+}
+
+impl std::fmt::Debug for YuvLut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("YuvLut")
+            .field("color_space", &self.color_space)
+            .finish()
     }
 }
 
 // equation from https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB
-/// Convert `YCbCr` 4:4:4 to a RGB888. [For further reading](https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB)
+/// Convert `YCbCr` 4:4:4 to a RGB888, in BT.601 limited range. [For further reading](https://en.wikipedia.org/wiki/YUV#Converting_between_Y%E2%80%B2UV_and_RGB)
+#[must_use]
+#[inline]
+pub fn yuyv444_to_rgb888(y: i32, u: i32, v: i32) -> [u8; 3] {
+    yuyv444_to_rgb888_with(y, u, v, ColorSpace::Bt601Limited)
+}
+
+/// Convert `YCbCr` 4:4:4 in `color_space` to a RGB888. Samples outside of 0-255 are clamped.
 #[allow(clippy::many_single_char_names)]
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_sign_loss)]
 #[must_use]
-pub fn yuyv444_to_rgb888(y: i32, u: i32, v: i32) -> [u8; 3] {
-    let c298 = (y - 16) * 298;
+#[inline]
+pub fn yuyv444_to_rgb888_with(y: i32, u: i32, v: i32, color_space: ColorSpace) -> [u8; 3] {
+    let [y_offset, y_scale, rv, gu, gv, bu] = color_space.coefficients();
+    // samples outside of a byte would overflow the math below
+    let (y, u, v) = (y.clamp(0, 255), u.clamp(0, 255), v.clamp(0, 255));
+    let c = (y - y_offset) * y_scale;
     let d = u - 128;
     let e = v - 128;
-    let r = ((c298 + 409 * e + 128) >> 8).clamp(0, 255) as u8;
-    let g = ((c298 - 100 * d - 208 * e + 128) >> 8).clamp(0, 255) as u8;
-    let b = ((c298 + 516 * d + 128) >> 8).clamp(0, 255) as u8;
+    let r = ((c + rv * e + 128) >> 8).clamp(0, 255) as u8;
+    let g = ((c - gu * d - gv * e + 128) >> 8).clamp(0, 255) as u8;
+    let b = ((c + bu * d + 128) >> 8).clamp(0, 255) as u8;
+    [r, g, b]
+}
+
+/// Convert 16 bit `YCbCr` 4:4:4 to 16 bit RGB, with the same equation as [`yuyv444_to_rgb888()`] scaled up to 16 bits.
+#[must_use]
+#[inline]
+pub fn yuv444_to_rgb48(y: i32, u: i32, v: i32) -> [u16; 3] {
+    yuv444_to_rgb48_with(y, u, v, ColorSpace::Bt601Limited)
+}
+
+/// Convert 16 bit `YCbCr` 4:4:4 in `color_space` to 16 bit RGB. Samples outside of 0-65535 are clamped.
+#[allow(clippy::many_single_char_names)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+#[must_use]
+#[inline]
+pub fn yuv444_to_rgb48_with(y: i32, u: i32, v: i32, color_space: ColorSpace) -> [u16; 3] {
+    let [y_offset, y_scale, rv, gu, gv, bu] = color_space.coefficients();
+    let (y, u, v) = (y.clamp(0, 65535), u.clamp(0, 65535), v.clamp(0, 65535));
+    let c = (y - (y_offset << 8)) * y_scale;
+    let d = u - (128 << 8);
+    let e = v - (128 << 8);
+    let r = ((c + rv * e + 128) >> 8).clamp(0, 65535) as u16;
+    let g = ((c - gu * d - gv * e + 128) >> 8).clamp(0, 65535) as u16;
+    let b = ((c + bu * d + 128) >> 8).clamp(0, 65535) as u16;
     [r, g, b]
 }
 
@@ -929,3 +2924,41 @@ impl Display for CameraIndexType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{yuyv444_to_rgb888_with, ColorSpace, YuvLut};
+
+    #[test]
+    fn yuv_lut_matches_arithmetic() {
+        for &color_space in &[
+            ColorSpace::Bt601Limited,
+            ColorSpace::Bt601Full,
+            ColorSpace::Bt709Limited,
+            ColorSpace::Bt709Full,
+        ] {
+            let lut = YuvLut::new(color_space);
+            // every luma, and every third chroma value (0 and 255 included)
+            for y in 0..=255_u8 {
+                for u in (0..=255_u8).step_by(3) {
+                    for v in (0..=255_u8).step_by(3) {
+                        assert_eq!(
+                            lut.yuv_to_rgb888(y, u, v),
+                            yuyv444_to_rgb888_with(
+                                i32::from(y),
+                                i32::from(u),
+                                i32::from(v),
+                                color_space
+                            ),
+                            "{:?} at Y {} U {} V {}",
+                            color_space,
+                            y,
+                            u,
+                            v
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
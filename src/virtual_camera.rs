@@ -0,0 +1,411 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, NokhwaError, Resolution, SinkPayload};
+use image::{ImageBuffer, Rgb};
+
+fn virtual_camera_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("Virtual camera: {}", error))
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::virtual_camera_error;
+    use crate::{rgb888_to_yuyv422, NokhwaError, Resolution};
+    use std::{
+        fs::{self, File, OpenOptions},
+        io::Write,
+        mem::size_of,
+        os::unix::io::AsRawFd,
+    };
+
+    const V4L2_BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
+    const V4L2_FIELD_NONE: u32 = 1;
+    const V4L2_COLORSPACE_SRGB: u32 = 8;
+
+    // `struct v4l2_pix_format`
+    #[repr(C)]
+    #[derive(Default)]
+    struct V4l2PixFormat {
+        width: u32,
+        height: u32,
+        pixelformat: u32,
+        field: u32,
+        bytesperline: u32,
+        sizeimage: u32,
+        colorspace: u32,
+        private: u32,
+        flags: u32,
+        ycbcr_enc: u32,
+        quantization: u32,
+        xfer_func: u32,
+    }
+
+    // `struct v4l2_format`, with only the `pix` member of its union. The union holds pointers, so it is pointer aligned
+    #[repr(C)]
+    struct V4l2Format {
+        buffer_type: u32,
+        _align: [usize; 0],
+        pix: V4l2PixFormat,
+        _rest_of_union: [u8; 200 - size_of::<V4l2PixFormat>()],
+    }
+
+    // `_IOWR('V', 5, struct v4l2_format)`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+    const VIDIOC_S_FMT: u32 =
+        (3 << 30) | ((size_of::<V4l2Format>() as u32) << 16) | ((b'V' as u32) << 8) | 5;
+
+    // a v4l2loopback device, written to as YUYV
+    pub(super) struct Sink {
+        device: String,
+        file: File,
+        resolution: Resolution,
+    }
+
+    impl Sink {
+        pub(super) fn open(
+            device: Option<&str>,
+            resolution: Resolution,
+            _frame_rate: u32,
+        ) -> Result<Self, NokhwaError> {
+            let device = match device {
+                Some(device) => device.to_string(),
+                None => find_loopback_device()?,
+            };
+            let file = match OpenOptions::new().write(true).open(&device) {
+                Ok(file) => file,
+                Err(why) => {
+                    return Err(NokhwaError::from_io_error(&device, &why, |error| {
+                        NokhwaError::OpenDeviceError(device.clone(), error)
+                    }))
+                }
+            };
+
+            let (width, height) = (resolution.width(), resolution.height());
+            let bytes_per_line = width.checked_mul(2);
+            let size_image = bytes_per_line.and_then(|line| line.checked_mul(height));
+            let (bytes_per_line, size_image) = match (bytes_per_line, size_image) {
+                (Some(line), Some(size)) if width % 2 == 0 && width > 0 && height > 0 => {
+                    (line, size)
+                }
+                _ => {
+                    return Err(virtual_camera_error(&format!(
+                        "{} is not a valid YUYV resolution (the width must be even)",
+                        resolution
+                    )))
+                }
+            };
+            let mut format = V4l2Format {
+                buffer_type: V4L2_BUF_TYPE_VIDEO_OUTPUT,
+                _align: [],
+                pix: V4l2PixFormat {
+                    width,
+                    height,
+                    pixelformat: u32::from_le_bytes(*b"YUYV"),
+                    field: V4L2_FIELD_NONE,
+                    bytesperline: bytes_per_line,
+                    sizeimage: size_image,
+                    colorspace: V4L2_COLORSPACE_SRGB,
+                    ..V4l2PixFormat::default()
+                },
+                _rest_of_union: [0; 200 - size_of::<V4l2PixFormat>()],
+            };
+            // SAFETY: `format` is a `struct v4l2_format`, which is what `VIDIOC_S_FMT` reads and writes
+            // the request is a `c_ulong` on glibc and a `c_int` on musl
+            #[allow(clippy::cast_lossless, clippy::cast_possible_wrap)]
+            let result = unsafe {
+                libc::ioctl(
+                    file.as_raw_fd(),
+                    VIDIOC_S_FMT as _,
+                    &mut format as *mut V4l2Format,
+                )
+            };
+            if result < 0 {
+                return Err(virtual_camera_error(&format!(
+                    "{} refused the format {} YUYV: {}",
+                    device,
+                    resolution,
+                    std::io::Error::last_os_error()
+                )));
+            }
+            Ok(Sink {
+                device,
+                file,
+                resolution,
+            })
+        }
+
+        pub(super) fn device(&self) -> &str {
+            &self.device
+        }
+
+        pub(super) fn write(&mut self, rgb: &[u8]) -> Result<(), NokhwaError> {
+            let yuyv = rgb888_to_yuyv422(rgb, self.resolution.width())?;
+            match self.file.write_all(&yuyv) {
+                Ok(()) => Ok(()),
+                Err(why) => Err(virtual_camera_error(&format!(
+                    "Failed to write to {}: {}",
+                    self.device, why
+                ))),
+            }
+        }
+    }
+
+    // v4l2loopback devices are virtual, they have no bus device behind them
+    fn find_loopback_device() -> Result<String, NokhwaError> {
+        let mut nodes: Vec<(usize, String)> = fs::read_dir("/sys/class/video4linux")
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                fs::canonicalize(entry.path())
+                    .map(|path| path.to_string_lossy().contains("/virtual/"))
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let index = name.strip_prefix("video")?.parse().ok()?;
+                Some((index, format!("/dev/{}", name)))
+            })
+            .collect();
+        nodes.sort();
+        match nodes.into_iter().next() {
+            Some((_, device)) => Ok(device),
+            None => Err(virtual_camera_error(
+                "No v4l2loopback device found, is the v4l2loopback module loaded?",
+            )),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::virtual_camera_error;
+    use crate::{NokhwaError, Resolution};
+    use libloading::Library;
+    use std::{
+        convert::TryFrom,
+        os::raw::{c_float, c_int, c_void},
+    };
+
+    type CreateCameraFn = unsafe extern "C" fn(c_int, c_int, c_float) -> *mut c_void;
+    type DeleteCameraFn = unsafe extern "C" fn(*mut c_void);
+    type SendFrameFn = unsafe extern "C" fn(*mut c_void, *const c_void);
+
+    // a softcam (DirectShow filter) camera, written to as BGR
+    pub(super) struct Sink {
+        device: String,
+        camera: *mut c_void,
+        delete_camera: DeleteCameraFn,
+        send_frame: SendFrameFn,
+        // keeps the functions above mapped, must be dropped last
+        _library: Library,
+    }
+
+    impl Sink {
+        pub(super) fn open(
+            device: Option<&str>,
+            resolution: Resolution,
+            frame_rate: u32,
+        ) -> Result<Self, NokhwaError> {
+            let device = device.unwrap_or("softcam.dll").to_string();
+            // SAFETY: softcam has no initialization code beyond setting up its own state
+            let library = match unsafe { Library::new(&device) } {
+                Ok(library) => library,
+                Err(why) => {
+                    return Err(virtual_camera_error(&format!(
+                        "Failed to load {}, is softcam installed? {}",
+                        device, why
+                    )))
+                }
+            };
+            let (width, height) = match (
+                c_int::try_from(resolution.width()),
+                c_int::try_from(resolution.height()),
+            ) {
+                (Ok(width), Ok(height)) => (width, height),
+                _ => {
+                    return Err(virtual_camera_error(&format!(
+                        "{} is too large",
+                        resolution
+                    )))
+                }
+            };
+            // SAFETY: the types match the declarations in `softcam.h`
+            unsafe {
+                let symbol_error = |why: libloading::Error| {
+                    virtual_camera_error(&format!("Invalid softcam library: {}", why))
+                };
+                let create_camera = *library
+                    .get::<CreateCameraFn>(b"scCreateCamera\0")
+                    .map_err(symbol_error)?;
+                let delete_camera = *library
+                    .get::<DeleteCameraFn>(b"scDeleteCamera\0")
+                    .map_err(symbol_error)?;
+                let send_frame = *library
+                    .get::<SendFrameFn>(b"scSendFrame\0")
+                    .map_err(symbol_error)?;
+                #[allow(clippy::cast_precision_loss)]
+                let camera = create_camera(width, height, frame_rate as c_float);
+                // softcam only allows one camera per process
+                if camera.is_null() {
+                    return Err(virtual_camera_error(
+                        "softcam failed to create the camera, is another one open?",
+                    ));
+                }
+                Ok(Sink {
+                    device,
+                    camera,
+                    delete_camera,
+                    send_frame,
+                    _library: library,
+                })
+            }
+        }
+
+        pub(super) fn device(&self) -> &str {
+            &self.device
+        }
+
+        pub(super) fn write(&mut self, rgb: &[u8]) -> Result<(), NokhwaError> {
+            let mut bgr = rgb.to_vec();
+            for pixel in bgr.chunks_exact_mut(3) {
+                pixel.swap(0, 2);
+            }
+            // SAFETY: `bgr` is a whole frame of the resolution the camera was created with, and softcam copies it
+            unsafe { (self.send_frame)(self.camera, bgr.as_ptr().cast()) };
+            Ok(())
+        }
+    }
+
+    impl Drop for Sink {
+        fn drop(&mut self) {
+            // SAFETY: the camera is valid, and not used after this
+            unsafe { (self.delete_camera)(self.camera) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use crate::{NokhwaError, Resolution};
+
+    pub(super) struct Sink;
+
+    impl Sink {
+        pub(super) fn open(
+            _device: Option<&str>,
+            _resolution: Resolution,
+            _frame_rate: u32,
+        ) -> Result<Self, NokhwaError> {
+            Err(NokhwaError::NotImplementedError(
+                "Virtual cameras on this platform".to_string(),
+            ))
+        }
+
+        pub(super) fn device(&self) -> &str {
+            ""
+        }
+
+        pub(super) fn write(&mut self, _rgb: &[u8]) -> Result<(), NokhwaError> {
+            Ok(())
+        }
+    }
+}
+
+/// Writes frames out to a virtual camera, which other programs (Zoom, Teams, OBS, browsers) see as a webcam. This makes "capture, filter in Rust, appear as a webcam" pipelines possible.
+/// ```.ignore
+/// let mut virtual_camera = VirtualCamera::new(None, camera.resolution(), camera.frame_rate())?;
+/// loop {
+///     let frame = my_filter(camera.frame()?);
+///     virtual_camera.write_frame(&frame)?;
+/// }
+/// ```
+/// # Quirks
+/// - Linux: Writes to a [`v4l2loopback`](https://github.com/umlaeute/v4l2loopback) device as YUYV. `device` is its node (e.g. `/dev/video10`), or `None` for the first one.
+///   Load the module with `exclusive_caps=1` for Chrome and WebRTC apps to list it.
+/// - Windows: Writes to a [softcam](https://github.com/tshino/softcam) DirectShow filter, which must be installed (registered) separately. `device` is the path of `softcam.dll`, or `None` to let Windows find it.
+///   softcam only allows one virtual camera per process. Media Foundation only apps (e.g. the Windows Camera app) do not see DirectShow filters.
+/// - There is no virtual camera support on other platforms, creating one returns [`NokhwaError::NotImplementedError`].
+/// - Frames must match the [`Resolution`] the virtual camera was created with, and the width must be even. Frames of any other size are rejected.
+pub struct VirtualCamera {
+    sink: platform::Sink,
+    resolution: Resolution,
+    frame_rate: u32,
+}
+
+impl VirtualCamera {
+    /// Opens the virtual camera `device` (see the quirks, `None` picks the default) for `resolution` sized frames at about `frame_rate` FPS.
+    /// # Errors
+    /// If there is no virtual camera device, it cannot be opened, or it refuses the resolution, this will error.
+    pub fn new(
+        device: Option<&str>,
+        resolution: Resolution,
+        frame_rate: u32,
+    ) -> Result<Self, NokhwaError> {
+        Ok(VirtualCamera {
+            sink: platform::Sink::open(device, resolution, frame_rate)?,
+            resolution,
+            frame_rate,
+        })
+    }
+
+    /// The device that is written to: the `v4l2loopback` node on Linux, the softcam library on Windows.
+    #[must_use]
+    pub fn device(&self) -> &str {
+        self.sink.device()
+    }
+
+    /// The [`Resolution`] frames must have.
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// The frame rate the virtual camera announces.
+    #[must_use]
+    pub fn frame_rate(&self) -> u32 {
+        self.frame_rate
+    }
+
+    /// Writes a frame, which programs watching the virtual camera see next.
+    /// # Errors
+    /// If the frame is the wrong size, or the device fails to take it, this will error.
+    pub fn write_frame(
+        &mut self,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<(), NokhwaError> {
+        if Resolution::new(frame.width(), frame.height()) != self.resolution {
+            return Err(virtual_camera_error(&format!(
+                "Frame is {}x{}, the virtual camera is {}",
+                frame.width(),
+                frame.height(),
+                self.resolution
+            )));
+        }
+        self.sink.write(frame.as_raw())
+    }
+
+    /// Writes what a sink gets from [`Camera::frame_for_sink()`].
+    /// # Errors
+    /// If the payload is [`SinkPayload::Opaque`], or [`write_frame()`](VirtualCamera::write_frame) fails, this will error.
+    pub fn write_payload(&mut self, payload: &SinkPayload) -> Result<(), NokhwaError> {
+        match payload.image() {
+            Some(image) => self.write_frame(image),
+            None => Err(virtual_camera_error(
+                "Opaque frames (e.g. encrypted by a SinkTransform) cannot be shown",
+            )),
+        }
+    }
+
+    /// Captures a frame from `camera` with [`Camera::frame_for_sink()`] and writes it.
+    /// # Errors
+    /// If capturing fails, or [`write_payload()`](VirtualCamera::write_payload) fails, this will error.
+    pub fn forward_frame(&mut self, camera: &mut Camera) -> Result<(), NokhwaError> {
+        let payload = camera.frame_for_sink()?;
+        self.write_payload(&payload)
+    }
+}
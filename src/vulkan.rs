@@ -0,0 +1,275 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{NokhwaError, Resolution};
+use ash::{vk, Device};
+use image::{ImageBuffer, Rgb};
+
+/// Uploads frames into Vulkan images for engines that use Vulkan directly through `ash`, rather than `wgpu`.
+///
+/// You own every Vulkan object: the uploader only borrows a host-visible staging buffer (and its memory) from you.
+/// [`write_frame()`](VulkanFrameUploader::write_frame) converts a frame into the staging buffer, and [`record_copy()`](VulkanFrameUploader::record_copy) records the copy from it into your `vk::Image` into your command buffer, which you then submit.
+///
+/// Supported formats are `R8G8B8A8_UNORM`, `R8G8B8A8_SRGB`, `B8G8R8A8_UNORM` and `B8G8R8A8_SRGB`.
+/// # Quirks
+/// - Do not write a new frame while a copy from the staging buffer is still executing on the GPU. Use one uploader (and staging buffer) per frame in flight, or wait on a fence.
+#[derive(Clone)]
+pub struct VulkanFrameUploader {
+    device: Device,
+    staging_buffer: vk::Buffer,
+    staging_memory: vk::DeviceMemory,
+    staging_size: vk::DeviceSize,
+    format: vk::Format,
+}
+
+impl VulkanFrameUploader {
+    /// Creates a new [`VulkanFrameUploader`] writing into `staging_buffer`, which is bound to the start of `staging_memory` and is `staging_size` bytes large.
+    /// The staging buffer needs the `TRANSFER_SRC` usage. Use [`staging_size()`](VulkanFrameUploader::staging_size) to know how large to make it.
+    /// # Errors
+    /// If `format` is not supported, this will error.
+    /// # Safety
+    /// `staging_buffer` and `staging_memory` must be valid handles from `device`, the memory must be host-visible and not mapped, and they must outlive the uploader.
+    pub unsafe fn new(
+        device: Device,
+        staging_buffer: vk::Buffer,
+        staging_memory: vk::DeviceMemory,
+        staging_size: vk::DeviceSize,
+        format: vk::Format,
+    ) -> Result<Self, NokhwaError> {
+        if !is_supported_format(format) {
+            return Err(NokhwaError::GeneralError(format!(
+                "Unsupported Vulkan format {:?}",
+                format
+            )));
+        }
+        Ok(VulkanFrameUploader {
+            device,
+            staging_buffer,
+            staging_memory,
+            staging_size,
+            format,
+        })
+    }
+
+    /// How large a staging buffer has to be to hold a frame of `resolution` in a supported format.
+    #[must_use]
+    pub fn staging_size(resolution: Resolution) -> vk::DeviceSize {
+        vk::DeviceSize::from(resolution.width()) * vk::DeviceSize::from(resolution.height()) * 4
+    }
+
+    /// The format frames are converted into.
+    #[must_use]
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    /// Converts `frame` into the [`format()`](VulkanFrameUploader::format) and writes it into the staging buffer. Returns the resolution written, for [`record_copy()`](VulkanFrameUploader::record_copy).
+    /// # Errors
+    /// If the frame does not fit into the staging buffer, or mapping the staging memory fails, this will error.
+    pub fn write_frame(
+        &self,
+        frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    ) -> Result<Resolution, NokhwaError> {
+        let resolution = Resolution::new(frame.width(), frame.height());
+        let size = VulkanFrameUploader::staging_size(resolution);
+        if size > self.staging_size {
+            return Err(NokhwaError::ReadFrameError(format!(
+                "A {} frame needs {} bytes, but the staging buffer is {} bytes",
+                resolution, size, self.staging_size
+            )));
+        }
+
+        let bgra = matches!(
+            self.format,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB
+        );
+        // SAFETY: the memory is host-visible and unmapped (see `new()`), and at least `size` bytes large
+        unsafe {
+            let mapped = match self.device.map_memory(
+                self.staging_memory,
+                0,
+                size,
+                vk::MemoryMapFlags::empty(),
+            ) {
+                Ok(mapped) => mapped,
+                Err(why) => {
+                    return Err(NokhwaError::GeneralError(format!(
+                        "vkMapMemory failed: {}",
+                        why
+                    )))
+                }
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            let staging = std::slice::from_raw_parts_mut(mapped.cast::<u8>(), size as usize);
+            for (texel, pixel) in staging.chunks_exact_mut(4).zip(frame.pixels()) {
+                let [r, g, b] = pixel.0;
+                texel.copy_from_slice(&if bgra { [b, g, r, 255] } else { [r, g, b, 255] });
+            }
+
+            // a no-op on coherent memory, and needed on everything else
+            let flushed =
+                self.device
+                    .flush_mapped_memory_ranges(&[vk::MappedMemoryRange::builder()
+                        .memory(self.staging_memory)
+                        .offset(0)
+                        .size(vk::WHOLE_SIZE)
+                        .build()]);
+            self.device.unmap_memory(self.staging_memory);
+            if let Err(why) = flushed {
+                return Err(NokhwaError::GeneralError(format!(
+                    "vkFlushMappedMemoryRanges failed: {}",
+                    why
+                )));
+            }
+        }
+        Ok(resolution)
+    }
+
+    /// Records copying the frame in the staging buffer into `image` into `command_buffer`, along with the layout transitions: from `old_layout` into `TRANSFER_DST_OPTIMAL` for the copy, then into `new_layout` (e.g. `SHADER_READ_ONLY_OPTIMAL`).
+    /// # Safety
+    /// `command_buffer` must be recording, and `image` must be a 2D color image of the [`format()`](VulkanFrameUploader::format) and at least `resolution`, with the `TRANSFER_DST` usage and currently in `old_layout`.
+    /// The copy must finish executing before the next [`write_frame()`](VulkanFrameUploader::write_frame).
+    pub unsafe fn record_copy(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        resolution: Resolution,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+    ) {
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .level_count(1)
+            .layer_count(1)
+            .build();
+
+        let to_transfer = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::empty())
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .old_layout(old_layout)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .build();
+        self.device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[to_transfer],
+        );
+
+        let region = vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .image_subresource(
+                vk::ImageSubresourceLayers::builder()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_extent(vk::Extent3D {
+                width: resolution.width(),
+                height: resolution.height(),
+                depth: 1,
+            })
+            .build();
+        self.device.cmd_copy_buffer_to_image(
+            command_buffer,
+            self.staging_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+        );
+
+        let to_new = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(new_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .build();
+        self.device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[to_new],
+        );
+    }
+}
+
+fn is_supported_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8G8B8A8_UNORM
+            | vk::Format::R8G8B8A8_SRGB
+            | vk::Format::B8G8R8A8_UNORM
+            | vk::Format::B8G8R8A8_SRGB
+    )
+}
+
+/// Imports a DMA-BUF (e.g. one exported by a V4L2 driver or a hardware decoder) as `vk::DeviceMemory` of `size` bytes, so it can be bound to an image without copying it. Needs the `VK_KHR_external_memory_fd` and `VK_EXT_external_memory_dma_buf` device extensions.
+///
+/// If `memory_type_index` is `None`, the first memory type the DMA-BUF can be imported as is used.
+///
+/// Binding the memory to an image (which has to be made with `vk::ExternalMemoryImageCreateInfo`, and usually a DRM format modifier) is up to you.
+/// # Errors
+/// If the DMA-BUF cannot be imported, this will error. `fd` is not closed then.
+/// # Safety
+/// `external_memory_fd` must be made from `device`, and `fd` must be a DMA-BUF of at least `size` bytes. On success, Vulkan owns `fd`, so do not close it.
+#[cfg(target_os = "linux")]
+pub unsafe fn import_dma_buf(
+    device: &Device,
+    external_memory_fd: &ash::extensions::khr::ExternalMemoryFd,
+    fd: std::os::unix::io::RawFd,
+    size: vk::DeviceSize,
+    memory_type_index: Option<u32>,
+) -> Result<vk::DeviceMemory, NokhwaError> {
+    let handle_type = vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT;
+    let memory_type_index = match memory_type_index {
+        Some(index) => index,
+        None => match external_memory_fd.get_memory_fd_properties(handle_type, fd) {
+            Ok(properties) if properties.memory_type_bits != 0 => {
+                properties.memory_type_bits.trailing_zeros()
+            }
+            Ok(_) => {
+                return Err(NokhwaError::GeneralError(
+                    "The DMA-BUF cannot be imported as any memory type".to_string(),
+                ))
+            }
+            Err(why) => {
+                return Err(NokhwaError::GeneralError(format!(
+                    "vkGetMemoryFdPropertiesKHR failed: {}",
+                    why
+                )))
+            }
+        },
+    };
+
+    let mut import_info = vk::ImportMemoryFdInfoKHR::builder()
+        .handle_type(handle_type)
+        .fd(fd);
+    let allocate_info = vk::MemoryAllocateInfo::builder()
+        .allocation_size(size)
+        .memory_type_index(memory_type_index)
+        .push_next(&mut import_info);
+    match device.allocate_memory(&allocate_info, None) {
+        Ok(memory) => Ok(memory),
+        Err(why) => Err(NokhwaError::GeneralError(format!(
+            "Importing the DMA-BUF failed: {}",
+            why
+        ))),
+    }
+}
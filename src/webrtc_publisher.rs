@@ -0,0 +1,484 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{Camera, CameraBuilder, FrameFormat, NokhwaError, Resolution};
+use bytes::Bytes;
+use gstreamer::{
+    glib::Cast,
+    prelude::{ElementExt, GstBinExt},
+    Bin, Buffer, ClockTime, Element, ElementFactory, State,
+};
+use gstreamer_app::{AppSink, AppSrc};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
+use webrtc::{
+    api::{
+        interceptor_registry::register_default_interceptors,
+        media_engine::{MediaEngine, MIME_TYPE_H264},
+        APIBuilder, API,
+    },
+    ice_transport::ice_server::RTCIceServer,
+    interceptor::registry::Registry,
+    media::Sample,
+    peer_connection::{
+        configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
+        sdp::session_description::RTCSessionDescription, RTCPeerConnection,
+    },
+    rtp_transceiver::rtp_codec::RTCRtpCodecCapability,
+    track::track_local::{track_local_static_sample::TrackLocalStaticSample, TrackLocal},
+};
+
+// how often the capture loop checks for peers and shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+fn webrtc_error(error: &str) -> NokhwaError {
+    NokhwaError::GeneralError(format!("WebRTC: {}", error))
+}
+
+struct Shared {
+    peers: Mutex<Vec<Arc<RTCPeerConnection>>>,
+    running: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl Shared {
+    // drops the peers that have gone away, returning how many are left
+    fn prune_peers(&self) -> usize {
+        let mut peers = match self.peers.lock() {
+            Ok(peers) => peers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        peers.retain(|peer| {
+            !matches!(
+                peer.connection_state(),
+                RTCPeerConnectionState::Failed | RTCPeerConnectionState::Closed
+            )
+        });
+        peers.len()
+    }
+
+    fn fail(&self, why: String) {
+        match self.error.lock() {
+            Ok(mut error) => *error = Some(why),
+            Err(poisoned) => *poisoned.into_inner() = Some(why),
+        }
+    }
+}
+
+// encodes RGB frames into H.264 access units with GStreamer, for cameras that do not give H.264 themselves
+struct H264Encoder {
+    pipeline: Element,
+    source: AppSrc,
+    sink: AppSink,
+    started: Instant,
+}
+
+impl H264Encoder {
+    fn new(resolution: Resolution, frame_rate: u32) -> Result<Self, NokhwaError> {
+        if let Err(why) = gstreamer::init() {
+            return Err(webrtc_error(&format!(
+                "Failed to initialize GStreamer: {}",
+                why
+            )));
+        }
+        // browsers can all decode constrained baseline, and a keyframe every 2 seconds lets new peers start quickly
+        let candidates = [
+            (
+                "x264enc",
+                format!(
+                    "x264enc tune=zerolatency speed-preset=ultrafast key-int-max={}",
+                    frame_rate.max(1) * 2
+                ),
+            ),
+            ("openh264enc", "openh264enc ! h264parse".to_string()),
+        ];
+        let encoder = match candidates
+            .iter()
+            .find(|(factory, _)| ElementFactory::find(factory).is_some())
+        {
+            Some((_, encoder)) => encoder,
+            None => {
+                return Err(webrtc_error(
+                    "No H.264 encoder is installed (tried x264enc, openh264enc)",
+                ))
+            }
+        };
+        let description = format!(
+            "appsrc name=source is-live=true format=time caps=\"video/x-raw,format=RGB,width={},height={},framerate={}/1\" ! videoconvert ! {} ! video/x-h264,profile=constrained-baseline,stream-format=byte-stream,alignment=au ! appsink name=sink sync=false",
+            resolution.width(),
+            resolution.height(),
+            frame_rate,
+            encoder
+        );
+        let pipeline = match gstreamer::parse_launch(&description) {
+            Ok(pipeline) => pipeline,
+            Err(why) => {
+                return Err(webrtc_error(&format!(
+                    "Failed to create pipeline {}: {}",
+                    description, why
+                )))
+            }
+        };
+        let bin = match pipeline.clone().dynamic_cast::<Bin>() {
+            Ok(bin) => bin,
+            Err(_) => return Err(webrtc_error("Pipeline is not a bin")),
+        };
+        let source = match bin
+            .by_name("source")
+            .and_then(|source| source.dynamic_cast::<AppSrc>().ok())
+        {
+            Some(source) => source,
+            None => return Err(webrtc_error("Failed to get appsrc")),
+        };
+        let sink = match bin
+            .by_name("sink")
+            .and_then(|sink| sink.dynamic_cast::<AppSink>().ok())
+        {
+            Some(sink) => sink,
+            None => return Err(webrtc_error("Failed to get appsink")),
+        };
+        if let Err(why) = pipeline.set_state(State::Playing) {
+            let _state = pipeline.set_state(State::Null);
+            return Err(webrtc_error(&format!("Failed to start pipeline: {}", why)));
+        }
+        Ok(H264Encoder {
+            pipeline,
+            source,
+            sink,
+            started: Instant::now(),
+        })
+    }
+
+    // pushes one RGB frame, returning the access units that are ready (the encoder may hold on to a frame or two)
+    fn encode(&self, rgb: Vec<u8>) -> Result<Vec<Bytes>, NokhwaError> {
+        #[allow(clippy::cast_possible_truncation)]
+        let timestamp = self.started.elapsed().as_nanos() as u64;
+        let mut buffer = Buffer::from_mut_slice(rgb);
+        if let Some(buffer) = buffer.get_mut() {
+            buffer.set_pts(ClockTime::from_nseconds(timestamp));
+        }
+        if let Err(why) = self.source.push_buffer(buffer) {
+            return Err(webrtc_error(&format!("Failed to encode frame: {:?}", why)));
+        }
+        let mut access_units = vec![];
+        while let Some(sample) = self.sink.try_pull_sample(ClockTime::from_seconds(0)) {
+            if let Some(map) = sample
+                .buffer()
+                .and_then(|buffer| buffer.map_readable().ok())
+            {
+                access_units.push(Bytes::copy_from_slice(map.as_slice()));
+            }
+        }
+        Ok(access_units)
+    }
+}
+
+impl Drop for H264Encoder {
+    fn drop(&mut self) {
+        let _state = self.pipeline.set_state(State::Null);
+    }
+}
+
+// the access units of the next frame, straight from the camera if it gives H.264 and there is no sink transform to run
+fn next_access_units(
+    camera: &mut Camera,
+    encoder: &mut Option<H264Encoder>,
+) -> Result<Vec<Bytes>, NokhwaError> {
+    if camera.camera_format().format() == FrameFormat::H264 && !camera.has_sink_transform() {
+        return Ok(vec![Bytes::copy_from_slice(&camera.frame_raw()?)]);
+    }
+    let payload = camera.frame_for_sink()?;
+    let image = match payload.image() {
+        Some(image) => image,
+        None => {
+            return Err(webrtc_error(
+                "Opaque frames (e.g. encrypted by a SinkTransform) cannot be encoded",
+            ))
+        }
+    };
+    let resolution = Resolution::new(image.width(), image.height());
+    if encoder.is_none() {
+        *encoder = Some(H264Encoder::new(resolution, camera.frame_rate())?);
+    }
+    match encoder {
+        Some(encoder) => encoder.encode(image.as_raw().clone()),
+        None => Ok(vec![]),
+    }
+}
+
+fn capture_loop(
+    mut camera: Camera,
+    shared: &Shared,
+    runtime: &Runtime,
+    track: &TrackLocalStaticSample,
+) {
+    let frame_duration = Duration::from_secs(1) / camera.frame_rate().max(1);
+    let mut encoder = None;
+    while shared.running.load(Ordering::SeqCst) {
+        // no one is watching, don't capture (and encode) frames for nothing
+        if shared.prune_peers() == 0 {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        match next_access_units(&mut camera, &mut encoder) {
+            Ok(access_units) => {
+                for data in access_units {
+                    let sample = Sample {
+                        data,
+                        duration: frame_duration,
+                        ..Sample::default()
+                    };
+                    // peers that are still connecting drop samples, which is not an error
+                    let _written = runtime.block_on(track.write_sample(&sample));
+                }
+            }
+            // a late frame is not fatal, try again
+            Err(NokhwaError::Timeout(_)) => {}
+            Err(why) => {
+                shared.fail(why.to_string());
+                break;
+            }
+        }
+    }
+    let _stop = camera.stop_stream();
+}
+
+/// Publishes a camera as a WebRTC video track (H.264), so browsers can watch it live without a media server in between.
+///
+/// Signaling is left to the application: get an SDP offer from the browser (e.g. over a WebSocket, or a WHIP/WHEP style HTTP `POST`), give it to [`accept_offer()`](WebRtcPublisher::accept_offer),
+/// and send the answer back. The answer contains all ICE candidates, so there is nothing to trickle.
+///
+/// Any number of peers can watch at once, they all get the same frames. Frames are only captured while at least one peer is connected.
+/// ```.ignore
+/// let publisher = WebRtcPublisher::from_builder(CameraBuilder::new().index(0))?;
+/// let answer = publisher.accept_offer(&offer_from_browser)?;
+/// // send `answer` to the browser
+/// ```
+/// # Quirks
+/// - Cameras that give [`FrameFormat::H264`] are sent as is, and new peers only see video from the camera's next keyframe.
+///   Everything else goes through [`Camera::frame_for_sink()`] and is encoded with `GStreamer` (`x264enc` or `openh264enc` must be installed), with a keyframe every 2 seconds.
+/// - With a [`SinkTransform`](crate::SinkTransform) set, H.264 cameras are decoded and encoded again like any other, so the transform sees every frame. This needs a [`FrameDecoder`](crate::FrameDecoder) that decodes H.264, otherwise publishing fails.
+/// - There are no ICE servers by default, which only works on the local network. Use [`set_ice_servers()`](WebRtcPublisher::set_ice_servers) to add STUN/TURN servers.
+/// - `webrtc-rs` runs on a `tokio` runtime that the publisher creates and owns, so this does not need (and should not be created from within) an async context.
+pub struct WebRtcPublisher {
+    api: API,
+    runtime: Arc<Runtime>,
+    track: Arc<TrackLocalStaticSample>,
+    ice_servers: Vec<String>,
+    shared: Arc<Shared>,
+    capture_thread: Option<JoinHandle<()>>,
+}
+
+impl WebRtcPublisher {
+    /// Starts publishing the camera opened by `open_camera`, which runs on the capture thread. The stream is opened after `open_camera` returns, if it is not open already.
+    /// # Errors
+    /// If the WebRTC stack fails to set up, or `open_camera` or opening the stream fails, this will error.
+    pub fn new<F>(open_camera: F) -> Result<Self, NokhwaError>
+    where
+        F: FnOnce() -> Result<Camera, NokhwaError> + Send + 'static,
+    {
+        let runtime = match Runtime::new() {
+            Ok(runtime) => Arc::new(runtime),
+            Err(why) => {
+                return Err(webrtc_error(&format!(
+                    "Failed to start tokio runtime: {}",
+                    why
+                )))
+            }
+        };
+        let mut media_engine = MediaEngine::default();
+        if let Err(why) = media_engine.register_default_codecs() {
+            return Err(webrtc_error(&format!("Failed to register codecs: {}", why)));
+        }
+        let registry = match register_default_interceptors(Registry::new(), &mut media_engine) {
+            Ok(registry) => registry,
+            Err(why) => {
+                return Err(webrtc_error(&format!(
+                    "Failed to register interceptors: {}",
+                    why
+                )))
+            }
+        };
+        let api = APIBuilder::new()
+            .with_media_engine(media_engine)
+            .with_interceptor_registry(registry)
+            .build();
+        let track = Arc::new(TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_H264.to_string(),
+                ..RTCRtpCodecCapability::default()
+            },
+            "video".to_string(),
+            "nokhwa".to_string(),
+        ));
+
+        let shared = Arc::new(Shared {
+            peers: Mutex::new(vec![]),
+            running: AtomicBool::new(true),
+            error: Mutex::new(None),
+        });
+
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let capture_shared = shared.clone();
+        let capture_runtime = runtime.clone();
+        let capture_track = track.clone();
+        let capture_thread = thread::spawn(move || {
+            let camera = open_camera().and_then(|mut camera| {
+                if !camera.is_stream_open() {
+                    camera.open_stream()?;
+                }
+                Ok(camera)
+            });
+            match camera {
+                Ok(camera) => {
+                    let _sent = opened_tx.send(Ok(()));
+                    capture_loop(camera, &capture_shared, &capture_runtime, &capture_track);
+                }
+                Err(why) => {
+                    let _sent = opened_tx.send(Err(why));
+                }
+            }
+        });
+
+        match opened_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(why)) => return Err(why),
+            Err(_) => {
+                return Err(webrtc_error(
+                    "Capture thread panicked while opening the camera",
+                ))
+            }
+        }
+
+        Ok(WebRtcPublisher {
+            api,
+            runtime,
+            track,
+            ice_servers: vec![],
+            shared,
+            capture_thread: Some(capture_thread),
+        })
+    }
+
+    /// Starts publishing the camera `builder` opens. See [`new()`](WebRtcPublisher::new).
+    /// # Errors
+    /// If the WebRTC stack fails to set up, or the camera or its stream fails to open, this will error.
+    pub fn from_builder(builder: CameraBuilder) -> Result<Self, NokhwaError> {
+        WebRtcPublisher::new(move || builder.open())
+    }
+
+    /// The STUN/TURN server URLs (e.g. `stun:stun.l.google.com:19302`) peers are connected through.
+    #[must_use]
+    pub fn ice_servers(&self) -> &[String] {
+        &self.ice_servers
+    }
+
+    /// Sets the STUN/TURN server URLs. Only peers accepted after this use them.
+    pub fn set_ice_servers(&mut self, ice_servers: Vec<String>) {
+        self.ice_servers = ice_servers;
+    }
+
+    /// Accepts a peer: takes the SDP offer of a browser (`RTCPeerConnection.createOffer()`, with a `recvonly` video transceiver) and returns the SDP answer to send back to it.
+    /// This waits for ICE gathering to finish, so the answer has every candidate.
+    /// # Errors
+    /// If the offer is malformed, has no H.264 video, or the connection cannot be set up, this will error.
+    pub fn accept_offer(&self, offer: &str) -> Result<String, NokhwaError> {
+        let configuration = RTCConfiguration {
+            ice_servers: vec![RTCIceServer {
+                urls: self.ice_servers.clone(),
+                ..RTCIceServer::default()
+            }],
+            ..RTCConfiguration::default()
+        };
+        let offer = match RTCSessionDescription::offer(offer.to_string()) {
+            Ok(offer) => offer,
+            Err(why) => return Err(webrtc_error(&format!("Invalid offer: {}", why))),
+        };
+        let track = self.track.clone();
+        let runtime = self.runtime.clone();
+        let peer = self
+            .runtime
+            .block_on(async {
+                let peer = Arc::new(self.api.new_peer_connection(configuration).await?);
+                let sender = peer
+                    .add_track(track as Arc<dyn TrackLocal + Send + Sync>)
+                    .await?;
+                // RTCP has to be read for the interceptors (e.g. NACK) to work
+                runtime.spawn(async move {
+                    let mut rtcp = vec![0_u8; 1500];
+                    while sender.read(&mut rtcp).await.is_ok() {}
+                });
+                peer.set_remote_description(offer).await?;
+                let answer = peer.create_answer(None).await?;
+                let mut gathered = peer.gathering_complete_promise().await;
+                peer.set_local_description(answer).await?;
+                let _gathered = gathered.recv().await;
+                Ok::<_, webrtc::Error>(peer)
+            })
+            .map_err(|why| webrtc_error(&format!("Failed to accept peer: {}", why)))?;
+        let answer = match self.runtime.block_on(peer.local_description()) {
+            Some(answer) => answer.sdp,
+            None => return Err(webrtc_error("The peer has no local description")),
+        };
+        match self.shared.peers.lock() {
+            Ok(mut peers) => peers.push(peer),
+            Err(poisoned) => poisoned.into_inner().push(peer),
+        }
+        Ok(answer)
+    }
+
+    /// How many peers are connected (or connecting) right now.
+    #[must_use]
+    pub fn peer_count(&self) -> usize {
+        self.shared.prune_peers()
+    }
+
+    /// Disconnects every peer. The publisher keeps running, and can accept new ones.
+    pub fn disconnect_peers(&self) {
+        let peers = match self.shared.peers.lock() {
+            Ok(mut peers) => std::mem::take(&mut *peers),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        };
+        for peer in peers {
+            let _closed = self.runtime.block_on(peer.close());
+        }
+    }
+
+    /// The error that stopped the camera, if it failed.
+    #[must_use]
+    pub fn error(&self) -> Option<String> {
+        match self.shared.error.lock() {
+            Ok(error) => error.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Stops publishing, disconnects all peers and closes the camera. This is also done on drop.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.capture_thread.take() {
+            let _joined = thread.join();
+        }
+        self.disconnect_peers();
+    }
+}
+
+impl Drop for WebRtcPublisher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}